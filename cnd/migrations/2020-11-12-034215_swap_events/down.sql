@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE herc20_refund_events;
+DROP TABLE herc20_redeem_events;
+DROP TABLE herc20_fund_events;
+DROP TABLE herc20_deploy_events;
+DROP TABLE hbit_refund_events;
+DROP TABLE hbit_redeem_events;
+DROP TABLE hbit_fund_events;
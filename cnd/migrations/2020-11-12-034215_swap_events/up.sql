@@ -0,0 +1,71 @@
+-- Your SQL goes here
+
+-- Every swap emits at most one event of each kind, hence `swap_id` is unique.
+-- `block_height` is the height of the block that included the transaction of
+-- the event, NULL if it is not known.
+CREATE TABLE hbit_fund_events
+(
+    id INTEGER     NOT NULL PRIMARY KEY,
+    swap_id UNIQUE NOT NULL,
+    txid           NOT NULL,
+    vout           NOT NULL,
+    block_height,
+    FOREIGN KEY (swap_id) REFERENCES swaps (id)
+);
+
+CREATE TABLE hbit_redeem_events
+(
+    id INTEGER     NOT NULL PRIMARY KEY,
+    swap_id UNIQUE NOT NULL,
+    txid           NOT NULL,
+    secret         NOT NULL,
+    block_height,
+    FOREIGN KEY (swap_id) REFERENCES swaps (id)
+);
+
+CREATE TABLE hbit_refund_events
+(
+    id INTEGER     NOT NULL PRIMARY KEY,
+    swap_id UNIQUE NOT NULL,
+    txid           NOT NULL,
+    block_height,
+    FOREIGN KEY (swap_id) REFERENCES swaps (id)
+);
+
+CREATE TABLE herc20_deploy_events
+(
+    id INTEGER     NOT NULL PRIMARY KEY,
+    swap_id UNIQUE NOT NULL,
+    tx_hash        NOT NULL,
+    contract       NOT NULL,
+    block_height,
+    FOREIGN KEY (swap_id) REFERENCES swaps (id)
+);
+
+CREATE TABLE herc20_fund_events
+(
+    id INTEGER     NOT NULL PRIMARY KEY,
+    swap_id UNIQUE NOT NULL,
+    tx_hash        NOT NULL,
+    block_height,
+    FOREIGN KEY (swap_id) REFERENCES swaps (id)
+);
+
+CREATE TABLE herc20_redeem_events
+(
+    id INTEGER     NOT NULL PRIMARY KEY,
+    swap_id UNIQUE NOT NULL,
+    tx_hash        NOT NULL,
+    secret         NOT NULL,
+    block_height,
+    FOREIGN KEY (swap_id) REFERENCES swaps (id)
+);
+
+CREATE TABLE herc20_refund_events
+(
+    id INTEGER     NOT NULL PRIMARY KEY,
+    swap_id UNIQUE NOT NULL,
+    tx_hash        NOT NULL,
+    block_height,
+    FOREIGN KEY (swap_id) REFERENCES swaps (id)
+);
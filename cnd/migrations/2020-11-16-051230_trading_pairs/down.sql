@@ -0,0 +1,16 @@
+-- This file should undo anything in `up.sql`
+
+-- SQLite cannot drop columns, hence we rebuild the table without them.
+CREATE TABLE orders_without_trading_pairs
+(
+    id INTEGER      NOT NULL PRIMARY KEY,
+    order_id UNIQUE NOT NULL,
+    position        NOT NULL,
+    created_at      NOT NULL
+);
+
+INSERT INTO orders_without_trading_pairs (id, order_id, position, created_at)
+SELECT id, order_id, position, created_at FROM orders;
+
+DROP TABLE orders;
+ALTER TABLE orders_without_trading_pairs RENAME TO orders;
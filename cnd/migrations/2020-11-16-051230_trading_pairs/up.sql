@@ -0,0 +1,5 @@
+-- Your SQL goes here
+
+-- Orders used to be BTC/DAI orders only.
+ALTER TABLE orders ADD COLUMN trading_pair NOT NULL DEFAULT 'BTC/DAI';
+ALTER TABLE orders ADD COLUMN quote_decimals NOT NULL DEFAULT '18';
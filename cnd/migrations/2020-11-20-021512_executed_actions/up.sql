@@ -0,0 +1,12 @@
+-- Your SQL goes here
+
+-- The built-in wallet executes every action of a swap at most once.
+CREATE TABLE executed_actions
+(
+    id INTEGER     NOT NULL PRIMARY KEY,
+    swap_id        NOT NULL,
+    action         NOT NULL,
+    transaction_id NOT NULL,
+    UNIQUE (swap_id, action),
+    FOREIGN KEY (swap_id) REFERENCES swaps (id)
+);
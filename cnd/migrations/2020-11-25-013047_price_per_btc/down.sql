@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+
+-- Prices that are not a whole number of the smallest unit per satoshi are
+-- rounded down.
+UPDATE btc_dai_orders SET price = CASE
+    WHEN length(price) > 8 THEN substr(price, 1, length(price) - 8)
+    ELSE '0'
+END;
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+
+-- Prices are stored in the smallest unit of the token per bitcoin instead of
+-- per satoshi. Prices are decimal strings, appending eight zeros multiplies
+-- them by the number of satoshis in a bitcoin.
+UPDATE btc_dai_orders SET price = price || '00000000' WHERE price != '0';
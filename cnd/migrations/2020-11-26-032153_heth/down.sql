@@ -0,0 +1,20 @@
+-- This file should undo anything in `up.sql`
+
+DROP VIEW swap_contexts;
+CREATE VIEW swap_contexts AS
+SELECT local_swap_id as id,
+       role,
+       COALESCE(
+               (SELECT 'herc20' from herc20s where herc20s.swap_id = swaps.id and herc20s.side = 'Alpha'),
+               (SELECT 'hbit' from hbits where hbits.swap_id = swaps.id and hbits.side = 'Alpha')
+           ) as alpha,
+       COALESCE(
+               (SELECT 'herc20' from herc20s where herc20s.swap_id = swaps.id and herc20s.side = 'Beta'),
+               (SELECT 'hbit' from hbits where hbits.swap_id = swaps.id and hbits.side = 'Beta')
+           ) as beta
+FROM swaps;
+
+DROP TABLE heth_refund_events;
+DROP TABLE heth_redeem_events;
+DROP TABLE heth_fund_events;
+DROP TABLE heths;
@@ -0,0 +1,62 @@
+-- Your SQL goes here
+
+CREATE TABLE heths
+(
+    id                          INTEGER NOT NULL PRIMARY KEY,
+    swap_id                     INTEGER NOT NULL,
+    amount                      NOT NULL,
+    chain_id                    NOT NULL,
+    expiry                      NOT NULL,
+    redeem_identity             NOT NULL,
+    refund_identity             NOT NULL,
+    side                        NOT NULL,
+    FOREIGN KEY(swap_id)        REFERENCES swaps(id)
+);
+
+-- Every swap emits at most one event of each kind, hence `swap_id` is unique.
+-- `block_height` is the height of the block that included the transaction of
+-- the event, NULL if it is not known.
+CREATE TABLE heth_fund_events
+(
+    id INTEGER     NOT NULL PRIMARY KEY,
+    swap_id UNIQUE NOT NULL,
+    tx_hash        NOT NULL,
+    contract       NOT NULL,
+    block_height,
+    FOREIGN KEY (swap_id) REFERENCES swaps (id)
+);
+
+CREATE TABLE heth_redeem_events
+(
+    id INTEGER     NOT NULL PRIMARY KEY,
+    swap_id UNIQUE NOT NULL,
+    tx_hash        NOT NULL,
+    secret         NOT NULL,
+    block_height,
+    FOREIGN KEY (swap_id) REFERENCES swaps (id)
+);
+
+CREATE TABLE heth_refund_events
+(
+    id INTEGER     NOT NULL PRIMARY KEY,
+    swap_id UNIQUE NOT NULL,
+    tx_hash        NOT NULL,
+    block_height,
+    FOREIGN KEY (swap_id) REFERENCES swaps (id)
+);
+
+DROP VIEW swap_contexts;
+CREATE VIEW swap_contexts AS
+SELECT local_swap_id as id,
+       role,
+       COALESCE(
+               (SELECT 'herc20' from herc20s where herc20s.swap_id = swaps.id and herc20s.side = 'Alpha'),
+               (SELECT 'heth' from heths where heths.swap_id = swaps.id and heths.side = 'Alpha'),
+               (SELECT 'hbit' from hbits where hbits.swap_id = swaps.id and hbits.side = 'Alpha')
+           ) as alpha,
+       COALESCE(
+               (SELECT 'herc20' from herc20s where herc20s.swap_id = swaps.id and herc20s.side = 'Beta'),
+               (SELECT 'heth' from heths where heths.swap_id = swaps.id and heths.side = 'Beta'),
+               (SELECT 'hbit' from hbits where hbits.swap_id = swaps.id and hbits.side = 'Beta')
+           ) as beta
+FROM swaps;
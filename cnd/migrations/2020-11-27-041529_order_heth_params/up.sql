@@ -0,0 +1,12 @@
+-- Your SQL goes here
+
+CREATE TABLE order_heth_params
+(
+    id INTEGER        NOT NULL PRIMARY KEY,
+    order_id UNIQUE   NOT NULL,
+    chain_id          NOT NULL,
+    side              NOT NULL,
+    our_htlc_identity NOT NULL,
+    expiry_offset     NOT NULL,
+    FOREIGN KEY (order_id) REFERENCES orders (id)
+);
@@ -4,7 +4,7 @@ mod validation;
 
 use crate::{ethereum, ethereum::ChainId, fs};
 use anyhow::{Context, Result};
-use comit::ledger;
+use comit::{ledger, TradingPair};
 use conquer_once::Lazy;
 use libp2p::Multiaddr;
 use reqwest::Url;
@@ -13,7 +13,7 @@ use std::{fmt::Debug, path::PathBuf, str::FromStr};
 
 pub use self::{
     file::File,
-    settings::{AllowedOrigins, Bitcoin, BitcoinFees, Settings},
+    settings::{AllowedOrigins, Bitcoin, BitcoinConnector, BitcoinFees, Settings},
     validation::validate_connection_to_network,
 };
 
@@ -63,20 +63,44 @@ static CYPHERBLOCK_TESTNET_URL: Lazy<Url> = Lazy::new(|| {
 #[serde(deny_unknown_fields)]
 pub struct Data {
     pub dir: PathBuf,
+    /// Whether to keep the blocks the swaps look at on disk, so they do not
+    /// have to be fetched again after a restart.
+    #[serde(default)]
+    pub persistent_block_cache: bool,
 }
 
 impl Data {
     pub fn default() -> Result<Self> {
         Ok(Self {
             dir: fs::data_dir().context("unable to determine default data path")?,
+            persistent_block_cache: false,
         })
     }
 }
 
+/// The built-in wallet which signs and broadcasts swap actions on behalf of the
+/// user.
+///
+/// The wallet is derived from the seed of cnd and uses the configured bitcoind
+/// and geth nodes. It is disabled by default, in which case actions have to be
+/// executed by an external wallet.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Wallet {
+    pub enabled: bool,
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Bitcoind {
     pub node_url: Url,
+    /// Additional nodes we fail over to if the primary one is unhealthy or
+    /// lags behind.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_node_urls: Vec<Url>,
+    /// The `-zmqpubhashblock` endpoint of the primary node, used to get
+    /// notified about new blocks instead of long-polling for them.
+    pub zmq_url: Option<Url>,
 }
 
 impl Bitcoind {
@@ -87,13 +111,33 @@ impl Bitcoind {
             ledger::Bitcoin::Regtest => BITCOIND_RPC_REGTEST.clone(),
         };
 
-        Bitcoind { node_url }
+        Bitcoind {
+            node_url,
+            fallback_node_urls: vec![],
+            zmq_url: None,
+        }
     }
 }
 
+/// An Esplora server to watch swaps through instead of bitcoind.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Esplora {
+    pub url: Url,
+}
+
+/// An Electrum server to watch swaps through instead of bitcoind, e.g.
+/// `ssl://electrum.blockstream.info:50002`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Electrum {
+    pub url: Url,
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Ethereum {
     pub chain_id: ChainId,
+    pub watch_mempool: bool,
     pub geth: Geth,
     pub tokens: Tokens,
 }
@@ -102,6 +146,7 @@ impl Ethereum {
     fn new(chain_id: ChainId) -> Result<Ethereum> {
         Ok(Self {
             chain_id,
+            watch_mempool: false,
             geth: Geth::new(),
             tokens: Tokens::new(chain_id)?,
         })
@@ -122,6 +167,7 @@ impl Ethereum {
 
         let chain_id = ethereum.chain_id;
         let geth = ethereum.geth.unwrap_or_else(Geth::new);
+        let watch_mempool = ethereum.watch_mempool.unwrap_or(false);
         let tokens = ethereum.tokens.map_or_else(
             || Tokens::new(chain_id),
             |file| Tokens::from_file(file, chain_id),
@@ -129,6 +175,7 @@ impl Ethereum {
 
         Ok(Ethereum {
             chain_id,
+            watch_mempool,
             geth,
             tokens,
         })
@@ -139,6 +186,7 @@ impl From<Ethereum> for file::Ethereum {
     fn from(ethereum: Ethereum) -> Self {
         file::Ethereum {
             chain_id: ethereum.chain_id,
+            watch_mempool: Some(ethereum.watch_mempool),
             geth: Some(ethereum.geth),
             tokens: Some(ethereum.tokens.into()),
         }
@@ -149,6 +197,7 @@ impl From<Tokens> for file::Tokens {
     fn from(tokens: Tokens) -> Self {
         file::Tokens {
             dai: Some(tokens.dai),
+            erc20: tokens.erc20,
         }
     }
 }
@@ -157,12 +206,21 @@ impl From<Tokens> for file::Tokens {
 #[serde(deny_unknown_fields)]
 pub struct Geth {
     pub node_url: Url,
+    /// Additional nodes we fail over to if the primary one is unhealthy or
+    /// lags behind.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_node_urls: Vec<Url>,
+    /// Websocket endpoint of the node, used to get notified about new blocks
+    /// instead of polling for them.
+    pub websocket_url: Option<Url>,
 }
 
 impl Geth {
     fn new() -> Self {
         Self {
             node_url: WEB3_URL.clone(),
+            fallback_node_urls: vec![],
+            websocket_url: None,
         }
     }
 }
@@ -170,22 +228,71 @@ impl Geth {
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct Tokens {
     pub dai: ethereum::Address,
+    /// ERC20 tokens other than DAI that can be traded against bitcoin.
+    pub erc20: Vec<Token>,
 }
 
 impl Tokens {
     fn new(chain_id: ChainId) -> Result<Self> {
         let dai = dai_address_from_chain_id(chain_id)?;
 
-        Ok(Self { dai })
+        Ok(Self { dai, erc20: vec![] })
     }
 
     fn from_file(file: file::Tokens, id: ChainId) -> Result<Self> {
         let dai = file.dai.map_or_else(|| dai_address_from_chain_id(id), Ok)?;
 
-        Ok(Self { dai })
+        let mut trading_pairs = vec![TradingPair::btc_dai()];
+        for token in file.erc20.iter() {
+            let trading_pair = TradingPair::btc_against(&token.symbol)?;
+
+            if trading_pair.is_ether() {
+                anyhow::bail!(
+                    "{} denotes ether and cannot be configured as a token",
+                    token.symbol
+                );
+            }
+            if trading_pairs.contains(&trading_pair) {
+                anyhow::bail!("token {} is configured more than once", token.symbol);
+            }
+            trading_pairs.push(trading_pair);
+        }
+
+        Ok(Self {
+            dai,
+            erc20: file.erc20,
+        })
+    }
+
+    /// All configured tokens, starting with DAI.
+    pub fn all(&self) -> Vec<Token> {
+        let dai = Token {
+            symbol: String::from("DAI"),
+            address: self.dai,
+            decimals: 18,
+        };
+
+        std::iter::once(dai)
+            .chain(self.erc20.iter().cloned())
+            .collect()
+    }
+
+    /// The token the given trading pair is quoted in, if it is configured.
+    pub fn find(&self, trading_pair: &TradingPair) -> Option<Token> {
+        self.all()
+            .into_iter()
+            .find(|token| token.symbol.eq_ignore_ascii_case(trading_pair.quote()))
     }
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Token {
+    pub symbol: String,
+    pub address: ethereum::Address,
+    pub decimals: u8,
+}
+
 fn dai_address_from_chain_id(id: ChainId) -> Result<ethereum::Address> {
     Ok(match id {
         ChainId::MAINNET => *DAI_MAINNET,
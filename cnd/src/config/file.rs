@@ -1,5 +1,5 @@
 use crate::{
-    config::{settings, Bitcoind, Data, Geth, Settings},
+    config::{settings, Bitcoind, Data, Electrum, Esplora, Geth, Settings, Token, Wallet},
     ethereum,
     ethereum::ChainId,
 };
@@ -24,6 +24,7 @@ pub struct File {
     pub logging: Option<Logging>,
     pub bitcoin: Option<Bitcoin>,
     pub ethereum: Option<Ethereum>,
+    pub wallet: Option<Wallet>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -31,13 +32,19 @@ pub struct File {
 pub struct Network {
     pub listen: Vec<Multiaddr>,
     pub peer_addresses: Option<Vec<Multiaddr>>,
+    pub rendezvous_points: Option<Vec<Multiaddr>>,
+    pub rendezvous_server: Option<bool>,
+    pub mdns: Option<bool>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Bitcoin {
     pub network: ledger::Bitcoin,
+    pub watch_mempool: Option<bool>,
     pub bitcoind: Option<Bitcoind>,
+    pub esplora: Option<Esplora>,
+    pub electrum: Option<Electrum>,
     pub fees: Option<BitcoinFees>,
 }
 
@@ -75,6 +82,7 @@ pub struct CypherBlock {
 #[serde(deny_unknown_fields)]
 pub struct Ethereum {
     pub chain_id: ChainId,
+    pub watch_mempool: Option<bool>,
     pub geth: Option<Geth>,
     pub tokens: Option<Tokens>,
 }
@@ -83,6 +91,8 @@ pub struct Ethereum {
 #[serde(deny_unknown_fields)]
 pub struct Tokens {
     pub dai: Option<ethereum::Address>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub erc20: Vec<Token>,
 }
 
 impl File {
@@ -94,6 +104,7 @@ impl File {
             logging: Option::None,
             bitcoin: Option::None,
             ethereum: Option::None,
+            wallet: Option::None,
         }
     }
 
@@ -192,12 +203,16 @@ impl From<Settings> for File {
             logging: settings::Logging { level },
             bitcoin,
             ethereum,
+            wallet,
         } = settings;
 
         File {
             network: Some(Network {
                 listen: network.listen,
                 peer_addresses: Some(network.peer_addresses),
+                rendezvous_points: Some(network.rendezvous_points),
+                rendezvous_server: Some(network.rendezvous_server),
+                mdns: Some(network.mdns),
             }),
             http_api: Some(HttpApi {
                 socket,
@@ -215,15 +230,25 @@ impl From<Settings> for File {
             }),
             bitcoin: Some(bitcoin.into()),
             ethereum: Some(ethereum.into()),
+            wallet: Some(wallet),
         }
     }
 }
 
 impl From<settings::Bitcoin> for Bitcoin {
     fn from(settings: settings::Bitcoin) -> Self {
+        let (esplora, electrum) = match settings.connector {
+            settings::BitcoinConnector::Bitcoind => (None, None),
+            settings::BitcoinConnector::Esplora(url) => (Some(Esplora { url }), None),
+            settings::BitcoinConnector::Electrum(url) => (None, Some(Electrum { url })),
+        };
+
         Self {
             network: settings.network,
+            watch_mempool: Some(settings.watch_mempool),
             bitcoind: Some(settings.bitcoind),
+            esplora,
+            electrum,
             fees: Some(settings.fees.into()),
         }
     }
@@ -284,6 +309,9 @@ mod tests {
             Network {
                 listen: vec!["/ip4/0.0.0.0/tcp/9939".parse().unwrap()],
                 peer_addresses: Some(vec!["/ip4/1.1.1.1/tcp/9939".parse().unwrap()]),
+                rendezvous_points: None,
+                rendezvous_server: None,
+                mdns: None,
             },
             Network {
                 listen: (vec![
@@ -294,6 +322,9 @@ mod tests {
                     "/ip4/1.1.1.1/tcp/9939".parse().unwrap(),
                     "/ip4/2.2.2.2/tcp/3456".parse().unwrap(),
                 ]),
+                rendezvous_points: None,
+                rendezvous_server: None,
+                mdns: None,
             },
         ];
 
@@ -367,6 +398,7 @@ network = "regtest"
 
 [bitcoin.bitcoind]
 node_url = "http://localhost:18443/"
+zmq_url = "tcp://localhost:28332"
 
 [bitcoin.fees]
 strategy = "static"
@@ -379,14 +411,21 @@ chain_id = 1337
 
 [ethereum.geth]
 node_url = "http://localhost:8545/"
+websocket_url = "ws://localhost:8546/"
 
 [ethereum.tokens]
 dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
+
+[wallet]
+enabled = true
 "#;
         let file = File {
             network: Some(Network {
                 listen: vec!["/ip4/0.0.0.0/tcp/9939".parse().unwrap()],
                 peer_addresses: None,
+                rendezvous_points: None,
+                rendezvous_server: None,
+                mdns: None,
             }),
             http_api: Some(HttpApi {
                 socket: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8000),
@@ -396,15 +435,21 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
             }),
             data: Some(Data {
                 dir: PathBuf::from("/tmp/comit/"),
+                persistent_block_cache: false,
             }),
             logging: Some(Logging {
                 level: Some(Level::Debug),
             }),
             bitcoin: Some(Bitcoin {
                 network: ledger::Bitcoin::Regtest,
+                watch_mempool: None,
                 bitcoind: Some(Bitcoind {
                     node_url: "http://localhost:18443".parse().unwrap(),
+                    fallback_node_urls: vec![],
+                    zmq_url: Some("tcp://localhost:28332".parse().unwrap()),
                 }),
+                esplora: None,
+                electrum: None,
                 fees: Some(BitcoinFees {
                     strategy: BitcoinFeesStrategy::Static,
                     r#static: Some(Static {
@@ -415,8 +460,11 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
             }),
             ethereum: Some(Ethereum {
                 chain_id: ChainId::GETH_DEV,
+                watch_mempool: None,
                 geth: Some(Geth {
                     node_url: "http://localhost:8545".parse().unwrap(),
+                    fallback_node_urls: vec![],
+                    websocket_url: Some("ws://localhost:8546".parse().unwrap()),
                 }),
                 tokens: Some(Tokens {
                     dai: Some(
@@ -424,8 +472,10 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
                             .parse()
                             .unwrap(),
                     ),
+                    erc20: vec![],
                 }),
             }),
+            wallet: Some(Wallet { enabled: true }),
         };
 
         let config = toml::from_str::<File>(contents);
@@ -476,14 +526,33 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
             [bitcoind]
             node_url = "http://example.com:18443"
             "#,
+            r#"
+            network = "mainnet"
+            [esplora]
+            url = "https://blockstream.info/api"
+            "#,
+            r#"
+            network = "mainnet"
+            [electrum]
+            url = "ssl://electrum.blockstream.info:50002"
+            "#,
+            r#"
+            network = "mainnet"
+            watch_mempool = true
+            "#,
         ];
 
         let expected = vec![
             Bitcoin {
                 network: ledger::Bitcoin::Mainnet,
+                watch_mempool: None,
                 bitcoind: Some(Bitcoind {
                     node_url: Url::parse("http://example.com:8332").unwrap(),
+                    fallback_node_urls: vec![],
+                    zmq_url: None,
                 }),
+                esplora: None,
+                electrum: None,
                 fees: Some(BitcoinFees {
                     strategy: BitcoinFeesStrategy::Static,
                     r#static: Some(Static {
@@ -494,9 +563,14 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
             },
             Bitcoin {
                 network: ledger::Bitcoin::Testnet,
+                watch_mempool: None,
                 bitcoind: Some(Bitcoind {
                     node_url: Url::parse("http://example.com:18332").unwrap(),
+                    fallback_node_urls: vec![],
+                    zmq_url: None,
                 }),
+                esplora: None,
+                electrum: None,
                 fees: Some(BitcoinFees {
                     strategy: BitcoinFeesStrategy::CypherBlock,
                     r#static: None,
@@ -507,9 +581,42 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
             },
             Bitcoin {
                 network: ledger::Bitcoin::Regtest,
+                watch_mempool: None,
                 bitcoind: Some(Bitcoind {
                     node_url: Url::parse("http://example.com:18443").unwrap(),
+                    fallback_node_urls: vec![],
+                    zmq_url: None,
                 }),
+                esplora: None,
+                electrum: None,
+                fees: None,
+            },
+            Bitcoin {
+                network: ledger::Bitcoin::Mainnet,
+                watch_mempool: None,
+                bitcoind: None,
+                esplora: Some(Esplora {
+                    url: Url::parse("https://blockstream.info/api").unwrap(),
+                }),
+                electrum: None,
+                fees: None,
+            },
+            Bitcoin {
+                network: ledger::Bitcoin::Mainnet,
+                watch_mempool: None,
+                bitcoind: None,
+                esplora: None,
+                electrum: Some(Electrum {
+                    url: Url::parse("ssl://electrum.blockstream.info:50002").unwrap(),
+                }),
+                fees: None,
+            },
+            Bitcoin {
+                network: ledger::Bitcoin::Mainnet,
+                watch_mempool: Some(true),
+                bitcoind: None,
+                esplora: None,
+                electrum: None,
                 fees: None,
             },
         ];
@@ -552,8 +659,11 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
         let expected = vec![
             Ethereum {
                 chain_id: ChainId::KOVAN,
+                watch_mempool: None,
                 geth: Some(Geth {
                     node_url: Url::parse("http://example.com:8545").unwrap(),
+                    fallback_node_urls: vec![],
+                    websocket_url: None,
                 }),
                 tokens: Some(Tokens {
                     dai: Some(
@@ -561,12 +671,16 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
                             .parse()
                             .unwrap(),
                     ),
+                    erc20: vec![],
                 }),
             },
             Ethereum {
                 chain_id: ChainId::ROPSTEN,
+                watch_mempool: None,
                 geth: Some(Geth {
                     node_url: Url::parse("http://example.com:8545").unwrap(),
+                    fallback_node_urls: vec![],
+                    websocket_url: None,
                 }),
                 tokens: Some(Tokens {
                     dai: Some(
@@ -574,12 +688,16 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
                             .parse()
                             .unwrap(),
                     ),
+                    erc20: vec![],
                 }),
             },
             Ethereum {
                 chain_id: ChainId::MAINNET,
+                watch_mempool: None,
                 geth: Some(Geth {
                     node_url: Url::parse("http://example.com:8545").unwrap(),
+                    fallback_node_urls: vec![],
+                    websocket_url: None,
                 }),
                 tokens: Some(Tokens {
                     dai: Some(
@@ -587,6 +705,7 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
                             .parse()
                             .unwrap(),
                     ),
+                    erc20: vec![],
                 }),
             },
         ];
@@ -599,4 +718,41 @@ dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn additional_erc20_tokens_deserialize_correctly() {
+        let file_contents = r#"
+            chain_id = 1
+            [tokens]
+            dai = "0x6b175474e89094c44da98b954eedeac495271d0f"
+            [[tokens.erc20]]
+            symbol = "USDC"
+            address = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+            decimals = 6
+            "#;
+
+        let expected = Ethereum {
+            chain_id: ChainId::MAINNET,
+            watch_mempool: None,
+            geth: None,
+            tokens: Some(Tokens {
+                dai: Some(
+                    "0x6b175474e89094c44da98b954eedeac495271d0f"
+                        .parse()
+                        .unwrap(),
+                ),
+                erc20: vec![Token {
+                    symbol: "USDC".to_owned(),
+                    address: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+                        .parse()
+                        .unwrap(),
+                    decimals: 6,
+                }],
+            }),
+        };
+
+        let actual = toml::from_str::<Ethereum>(file_contents).unwrap();
+
+        assert_eq!(actual, expected);
+    }
 }
@@ -1,6 +1,6 @@
 use crate::config::{
-    file, Bitcoind, Data, Ethereum, File, COMIT_SOCKET, CYPHERBLOCK_MAINNET_URL,
-    CYPHERBLOCK_TESTNET_URL, FEERATE_SAT_PER_VBYTE,
+    file, Bitcoind, Data, Electrum, Esplora, Ethereum, File, Wallet, COMIT_SOCKET,
+    CYPHERBLOCK_MAINNET_URL, CYPHERBLOCK_TESTNET_URL, FEERATE_SAT_PER_VBYTE,
 };
 use anyhow::Result;
 use comit::ledger;
@@ -22,12 +22,16 @@ pub struct Settings {
     pub logging: Logging,
     pub bitcoin: Bitcoin,
     pub ethereum: Ethereum,
+    pub wallet: Wallet,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Network {
     pub listen: Vec<Multiaddr>,
     pub peer_addresses: Vec<Multiaddr>,
+    pub rendezvous_points: Vec<Multiaddr>,
+    pub rendezvous_server: bool,
+    pub mdns: bool,
 }
 
 impl Default for Network {
@@ -35,6 +39,9 @@ impl Default for Network {
         Self {
             listen: vec![COMIT_SOCKET.clone()],
             peer_addresses: vec![],
+            rendezvous_points: vec![],
+            rendezvous_server: false,
+            mdns: false,
         }
     }
 }
@@ -43,10 +50,16 @@ impl From<file::Network> for Network {
     fn from(network: file::Network) -> Self {
         let listen = network.listen;
         let peer_addresses = network.peer_addresses.unwrap_or_default();
+        let rendezvous_points = network.rendezvous_points.unwrap_or_default();
+        let rendezvous_server = network.rendezvous_server.unwrap_or(false);
+        let mdns = network.mdns.unwrap_or(false);
 
         Self {
             listen,
             peer_addresses,
+            rendezvous_points,
+            rendezvous_server,
+            mdns,
         }
     }
 }
@@ -128,7 +141,9 @@ impl From<file::Logging> for Logging {
 #[derive(Clone, Debug, PartialEq)]
 pub struct Bitcoin {
     pub network: ledger::Bitcoin,
+    pub watch_mempool: bool,
     pub bitcoind: Bitcoind,
+    pub connector: BitcoinConnector,
     pub fees: BitcoinFees,
 }
 
@@ -136,7 +151,9 @@ impl Bitcoin {
     fn default_from_network(network: ledger::Bitcoin) -> Self {
         Self {
             network,
+            watch_mempool: false,
             bitcoind: Bitcoind::new(network),
+            connector: BitcoinConnector::Bitcoind,
             fees: BitcoinFees::default_from_network(network),
         }
     }
@@ -159,6 +176,20 @@ impl Bitcoin {
 
         let network = bitcoin.network;
         let bitcoind = bitcoin.bitcoind.unwrap_or_else(|| Bitcoind::new(network));
+        let connector = match (bitcoin.esplora, bitcoin.electrum) {
+            (Some(_), Some(_)) => anyhow::bail!(
+                "bitcoin.esplora and bitcoin.electrum must not be present at the same time."
+            ),
+            (Some(Esplora { url }), None) => BitcoinConnector::Esplora(url),
+            (None, Some(Electrum { url })) => BitcoinConnector::Electrum(url),
+            (None, None) => BitcoinConnector::Bitcoind,
+        };
+        let watch_mempool = bitcoin.watch_mempool.unwrap_or(false);
+        if watch_mempool {
+            if let BitcoinConnector::Electrum(_) = connector {
+                anyhow::bail!("bitcoin.watch_mempool is not supported with bitcoin.electrum.");
+            }
+        }
         let fees = bitcoin.fees.map_or_else(
             || Ok(BitcoinFees::default_from_network(network)),
             |file| BitcoinFees::from_file(file, network),
@@ -166,12 +197,25 @@ impl Bitcoin {
 
         Ok(Bitcoin {
             network,
+            watch_mempool,
             bitcoind,
+            connector,
             fees,
         })
     }
 }
 
+/// The backend used to follow the Bitcoin blockchain.
+///
+/// bitcoind is still needed for the built-in wallet, the other backends allow
+/// to watch swaps without running a full node.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BitcoinConnector {
+    Bitcoind,
+    Esplora(url::Url),
+    Electrum(url::Url),
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum BitcoinFees {
     StaticSatPerVbyte(bitcoin::Amount),
@@ -227,8 +271,27 @@ impl Settings {
             logging,
             bitcoin,
             ethereum,
+            wallet,
         } = config_file;
 
+        // The built-in wallet keeps its bitcoin in bitcoind, we must not fall
+        // back to a bitcoind on localhost if the user configured another
+        // backend to follow the chain.
+        let wallet = wallet.unwrap_or_default();
+        if let Some(file::Bitcoin {
+            bitcoind: None,
+            esplora,
+            electrum,
+            ..
+        }) = &bitcoin
+        {
+            if wallet.enabled && (esplora.is_some() || electrum.is_some()) {
+                anyhow::bail!(
+                    "wallet.enabled requires bitcoin.bitcoind if bitcoin.esplora or bitcoin.electrum is present."
+                );
+            }
+        }
+
         Ok(Self {
             network: network.map_or_else(Network::default, Network::from),
             http_api: http_api.map_or_else(HttpApi::default, HttpApi::from),
@@ -247,6 +310,7 @@ impl Settings {
                 || Ethereum::new(comit_network.unwrap_or_default().into()),
                 |file| Ethereum::from_file(file, comit_network),
             )?,
+            wallet,
         })
     }
 }
@@ -334,9 +398,49 @@ mod tests {
             .is_equal_to(Network {
                 listen: vec!["/ip4/0.0.0.0/tcp/9939".parse().unwrap()],
                 peer_addresses: vec![],
+                rendezvous_points: vec![],
+                rendezvous_server: false,
+                mdns: false,
             })
     }
 
+    #[test]
+    fn wallet_defaults_to_disabled() {
+        let config_file = File {
+            wallet: None,
+            ..File::default()
+        };
+
+        let settings = Settings::from_config_file_and_defaults(config_file, None);
+
+        assert_that(&settings)
+            .is_ok()
+            .map(|settings| &settings.wallet)
+            .is_equal_to(Wallet { enabled: false })
+    }
+
+    #[test]
+    fn given_wallet_enabled_and_esplora_without_bitcoind_then_error() {
+        let config_file = File {
+            bitcoin: Some(file::Bitcoin {
+                network: ledger::Bitcoin::Mainnet,
+                watch_mempool: None,
+                bitcoind: None,
+                esplora: Some(Esplora {
+                    url: "https://blockstream.info/api".parse().unwrap(),
+                }),
+                electrum: None,
+                fees: None,
+            }),
+            wallet: Some(Wallet { enabled: true }),
+            ..File::default()
+        };
+
+        let settings = Settings::from_config_file_and_defaults(config_file, None);
+
+        assert_that(&settings).is_err();
+    }
+
     #[test]
     fn bitcoin_defaults() {
         let config_file = File { ..File::default() };
@@ -348,9 +452,13 @@ mod tests {
             .map(|settings| &settings.bitcoin)
             .is_equal_to(Bitcoin {
                 network: ledger::Bitcoin::Mainnet,
+                watch_mempool: false,
                 bitcoind: Bitcoind {
                     node_url: "http://localhost:8332".parse().unwrap(),
+                    fallback_node_urls: vec![],
+                    zmq_url: None,
                 },
+                connector: BitcoinConnector::Bitcoind,
                 fees: BitcoinFees::CypherBlock(CYPHERBLOCK_MAINNET_URL.clone()),
             })
     }
@@ -381,7 +489,10 @@ mod tests {
             let config_file = File {
                 bitcoin: Some(file::Bitcoin {
                     network,
+                    watch_mempool: None,
                     bitcoind: None,
+                    esplora: None,
+                    electrum: None,
                     fees: None,
                 }),
                 ..File::default()
@@ -394,9 +505,13 @@ mod tests {
                 .map(|settings| &settings.bitcoin)
                 .is_equal_to(Bitcoin {
                     network,
+                    watch_mempool: false,
                     bitcoind: Bitcoind {
                         node_url: url.parse().unwrap(),
+                        fallback_node_urls: vec![],
+                        zmq_url: None,
                     },
+                    connector: BitcoinConnector::Bitcoind,
                     fees,
                 })
         }
@@ -413,10 +528,16 @@ mod tests {
             .map(|settings| &settings.ethereum)
             .is_equal_to(Ethereum {
                 chain_id: ChainId::MAINNET,
+                watch_mempool: false,
                 geth: Geth {
                     node_url: "http://localhost:8545".parse().unwrap(),
+                    fallback_node_urls: vec![],
+                    websocket_url: None,
+                },
+                tokens: Tokens {
+                    dai: *DAI_MAINNET,
+                    erc20: vec![],
                 },
-                tokens: Tokens { dai: *DAI_MAINNET },
             })
     }
 
@@ -425,7 +546,10 @@ mod tests {
         let comit_network = comit::Network::Main;
         let config_file = file::Bitcoin {
             network: ledger::Bitcoin::Testnet,
+            watch_mempool: None,
             bitcoind: None,
+            esplora: None,
+            electrum: None,
             fees: None,
         };
 
@@ -438,7 +562,10 @@ mod tests {
     fn given_no_network_on_cli_then_use_config() {
         let config_file = file::Bitcoin {
             network: ledger::Bitcoin::Testnet,
+            watch_mempool: None,
             bitcoind: None,
+            esplora: None,
+            electrum: None,
             fees: None,
         };
 
@@ -455,7 +582,10 @@ mod tests {
         let comit_network = comit::Network::Main;
         let config_file = file::Bitcoin {
             network: ledger::Bitcoin::Mainnet,
+            watch_mempool: None,
             bitcoind: None,
+            esplora: None,
+            electrum: None,
             fees: None,
         };
 
@@ -464,6 +594,67 @@ mod tests {
         assert_that(&result).is_ok();
     }
 
+    #[test]
+    fn given_esplora_config_then_use_esplora_connector() {
+        let config_file = file::Bitcoin {
+            network: ledger::Bitcoin::Mainnet,
+            watch_mempool: None,
+            bitcoind: None,
+            esplora: Some(Esplora {
+                url: "https://blockstream.info/api".parse().unwrap(),
+            }),
+            electrum: None,
+            fees: None,
+        };
+
+        let result = Bitcoin::from_file(config_file, None);
+
+        assert_that(&result)
+            .is_ok()
+            .map(|b| &b.connector)
+            .is_equal_to(BitcoinConnector::Esplora(
+                "https://blockstream.info/api".parse().unwrap(),
+            ));
+    }
+
+    #[test]
+    fn given_esplora_and_electrum_config_present_then_error() {
+        let config_file = file::Bitcoin {
+            network: ledger::Bitcoin::Mainnet,
+            watch_mempool: None,
+            bitcoind: None,
+            esplora: Some(Esplora {
+                url: "https://blockstream.info/api".parse().unwrap(),
+            }),
+            electrum: Some(Electrum {
+                url: "ssl://electrum.blockstream.info:50002".parse().unwrap(),
+            }),
+            fees: None,
+        };
+
+        let result = Bitcoin::from_file(config_file, None);
+
+        assert_that(&result).is_err();
+    }
+
+    #[test]
+    fn given_electrum_config_and_watch_mempool_then_error() {
+        let config_file = file::Bitcoin {
+            network: ledger::Bitcoin::Mainnet,
+            watch_mempool: Some(true),
+            bitcoind: None,
+            esplora: None,
+            electrum: Some(Electrum {
+                url: "ssl://electrum.blockstream.info:50002".parse().unwrap(),
+            }),
+            fees: None,
+        };
+
+        let result = Bitcoin::from_file(config_file, None);
+
+        assert_that(&result).is_err();
+    }
+
     #[test]
     fn given_bitcoin_fees_static_strategy_and_cypherblock_config_present_then_error() {
         let config_file = file::BitcoinFees {
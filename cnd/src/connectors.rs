@@ -1,36 +1,72 @@
 use crate::{
     btsieve,
     btsieve::{
-        bitcoin::BitcoindConnector,
-        ethereum::{GetLogs, TransactionByHash, Web3Connector},
-        ConnectedNetwork, LatestBlock,
+        bitcoin::{AnyConnector, Mempool, TransactionsByScript},
+        ethereum::{BlockByNumber, GetLogs, PendingTransactions, TransactionByHash, Web3Connector},
+        ChainFollower, ConnectedNetwork, LatestBlock, MultiConnector,
     },
     ethereum,
 };
 use comit::{
-    btsieve::{ethereum::ReceiptByHash, BlockByHash},
-    ledger,
+    btsieve::{ethereum::ReceiptByHash, BlockByHash, BlockNotifications},
+    ledger, Never,
 };
 use std::sync::Arc;
 
+type BitcoinCache = btsieve::bitcoin::Cache<MultiConnector<AnyConnector, ledger::Bitcoin>>;
+type EthereumCache = btsieve::ethereum::Cache<MultiConnector<Web3Connector, ethereum::ChainId>>;
+
+type BitcoinConnector = ChainFollower<BitcoinCache, bitcoin::Block, bitcoin::BlockHash>;
+type EthereumConnector = ChainFollower<EthereumCache, ethereum::Block, ethereum::Hash>;
+
 /// A facade for accessing various blockchain connectors.
+///
+/// All watchers share one chain follower per ledger, see
+/// [`Connectors::follow_chains`].
 #[derive(Debug, Clone)]
 pub struct Connectors {
-    bitcoin: Arc<btsieve::bitcoin::Cache<BitcoindConnector>>,
-    ethereum: Arc<btsieve::ethereum::Cache<Web3Connector>>,
+    bitcoin: Arc<BitcoinConnector>,
+    ethereum: Arc<EthereumConnector>,
+    watch_bitcoin_mempool: bool,
+    watch_ethereum_mempool: bool,
 }
 
 impl Connectors {
-    pub fn new(
-        bitcoin: btsieve::bitcoin::Cache<BitcoindConnector>,
-        ethereum: btsieve::ethereum::Cache<Web3Connector>,
-    ) -> Self {
+    pub fn new(bitcoin: BitcoinCache, ethereum: EthereumCache) -> Self {
         Self {
-            bitcoin: Arc::new(bitcoin),
-            ethereum: Arc::new(ethereum),
+            bitcoin: Arc::new(ChainFollower::new(bitcoin)),
+            ethereum: Arc::new(ChainFollower::new(ethereum)),
+            watch_bitcoin_mempool: false,
+            watch_ethereum_mempool: false,
         }
     }
 
+    /// Configures whether swaps look for the secret in transactions that are
+    /// not mined yet.
+    pub fn with_mempool_watching(self, bitcoin: bool, ethereum: bool) -> Self {
+        Self {
+            watch_bitcoin_mempool: bitcoin,
+            watch_ethereum_mempool: ethereum,
+            ..self
+        }
+    }
+
+    pub fn watch_bitcoin_mempool(&self) -> bool {
+        self.watch_bitcoin_mempool
+    }
+
+    pub fn watch_ethereum_mempool(&self) -> bool {
+        self.watch_ethereum_mempool
+    }
+
+    /// Follows the Bitcoin and the Ethereum chain, meant to be spawned as a
+    /// task.
+    pub async fn follow_chains(self) -> Never {
+        let (never, _) = futures::future::join(self.bitcoin.follow(), self.ethereum.follow()).await;
+
+        never
+    }
+
     /// Provides access to a reference of the Bitcoin connector.
     ///
     /// Most importantly, we don't directly expose the concrete type of the
@@ -40,7 +76,10 @@ impl Connectors {
     ) -> Arc<
         impl LatestBlock<Block = bitcoin::Block>
             + BlockByHash<Block = bitcoin::Block, BlockHash = bitcoin::BlockHash>
-            + ConnectedNetwork<Network = ledger::Bitcoin>,
+            + BlockNotifications
+            + ConnectedNetwork<Network = ledger::Bitcoin>
+            + Mempool
+            + TransactionsByScript,
     > {
         self.bitcoin.clone()
     }
@@ -54,10 +93,13 @@ impl Connectors {
     ) -> Arc<
         impl LatestBlock<Block = ethereum::Block>
             + BlockByHash<Block = ethereum::Block, BlockHash = ethereum::Hash>
+            + BlockNotifications
             + ReceiptByHash
             + TransactionByHash
             + ConnectedNetwork<Network = ethereum::ChainId>
-            + GetLogs,
+            + BlockByNumber
+            + GetLogs
+            + PendingTransactions,
     > {
         self.ethereum.clone()
     }
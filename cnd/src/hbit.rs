@@ -1,42 +1,65 @@
 pub use comit::{hbit::*, identity};
 
 use crate::{
-    btsieve::{BlockByHash, ConnectedNetwork, LatestBlock},
+    btsieve::{
+        bitcoin::{median_time_past, Mempool, TransactionsByScript},
+        BlockByHash, BlockNotifications, ConnectedNetwork, LatestBlock,
+    },
     ledger,
     storage::Storage,
     LocalSwapId,
 };
 use anyhow::Result;
 use backoff::{backoff::Constant, future::FutureOperation};
-use comit::swap::hbit::{IncorrectlyFunded, WatchForFunded, WatchForRedeemed};
-use futures::TryFutureExt;
+use comit::{
+    expiries::CurrentTime,
+    swap::hbit::{
+        IncorrectlyFunded, WatchForFunded, WatchForRedeemed, WatchForRedeemedInMempool,
+        WatchForRefunded,
+    },
+    Secret, Timestamp,
+};
+use futures::{future, TryFutureExt};
 use std::{sync::Arc, time::Duration};
 use time::OffsetDateTime;
 
-#[derive(Clone, Debug, Default)]
-pub struct Events {
-    pub fund: Option<comit::swap::hbit::Funded>,
-    pub redeem: Option<comit::hbit::Redeemed>,
-}
-
 pub struct Facade<C> {
     pub connector: Arc<C>,
+    pub watch_mempool: bool,
     pub swap_id: LocalSwapId,
     pub storage: Storage,
 }
 
+// A derived implementation would require `C: Clone`.
+impl<C> Clone for Facade<C> {
+    fn clone(&self) -> Self {
+        Self {
+            connector: self.connector.clone(),
+            watch_mempool: self.watch_mempool,
+            swap_id: self.swap_id,
+            storage: self.storage.clone(),
+        }
+    }
+}
+
 #[async_trait::async_trait]
 impl<C> WatchForFunded for Facade<C>
 where
     C: LatestBlock<Block = bitcoin::Block>
         + BlockByHash<Block = bitcoin::Block, BlockHash = bitcoin::BlockHash>
-        + ConnectedNetwork<Network = ledger::Bitcoin>,
+        + BlockNotifications
+        + ConnectedNetwork<Network = ledger::Bitcoin>
+        + TransactionsByScript,
 {
     async fn watch_for_funded(
         &self,
         params: &Params,
         start_of_swap: OffsetDateTime,
     ) -> Result<Funded, IncorrectlyFunded> {
+        if let Some(funded) = self.storage.load_event(self.swap_id).await {
+            return Ok(funded);
+        }
+
         let operation = || {
             comit::hbit::watch_for_funded(self.connector.as_ref(), &params.shared, start_of_swap)
                 .map_err(backoff::Error::Transient)
@@ -49,13 +72,7 @@ where
             .await
             .expect("transient error is never returned")?;
 
-        self.storage
-            .hbit_events
-            .lock()
-            .await
-            .entry(self.swap_id)
-            .or_default()
-            .fund = Some(funded);
+        self.storage.save_event(funded, self.swap_id).await;
 
         Ok(funded)
     }
@@ -66,7 +83,9 @@ impl<C> WatchForRedeemed for Facade<C>
 where
     C: LatestBlock<Block = bitcoin::Block>
         + BlockByHash<Block = bitcoin::Block, BlockHash = bitcoin::BlockHash>
-        + ConnectedNetwork<Network = ledger::Bitcoin>,
+        + BlockNotifications
+        + ConnectedNetwork<Network = ledger::Bitcoin>
+        + TransactionsByScript,
 {
     async fn watch_for_redeemed(
         &self,
@@ -74,6 +93,10 @@ where
         fund_event: Funded,
         start_of_swap: OffsetDateTime,
     ) -> Redeemed {
+        if let Some(redeemed) = self.storage.load_event(self.swap_id).await {
+            return redeemed;
+        }
+
         let operation = || {
             watch_for_redeemed(
                 self.connector.as_ref(),
@@ -91,14 +114,104 @@ where
             .await
             .expect("transient error is never returned");
 
-        self.storage
-            .hbit_events
-            .lock()
-            .await
-            .entry(self.swap_id)
-            .or_default()
-            .redeem = Some(redeemed);
+        self.storage.save_event(redeemed, self.swap_id).await;
 
         redeemed
     }
 }
+
+#[async_trait::async_trait]
+impl<C> WatchForRedeemedInMempool for Facade<C>
+where
+    C: Mempool + TransactionsByScript + ConnectedNetwork<Network = ledger::Bitcoin>,
+{
+    async fn watch_for_redeemed_in_mempool(&self, params: &Params, fund_event: Funded) -> Secret {
+        // The secret is learned once the redeem transaction is mined instead.
+        if !self.watch_mempool {
+            return future::pending().await;
+        }
+
+        let operation = || {
+            watch_for_redeemed_in_mempool(
+                self.connector.as_ref(),
+                &params.shared,
+                fund_event.location,
+            )
+            .map_err(backoff::Error::Transient)
+        };
+
+        operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!(
+                    "failed to watch mempool for hbit redeem, retrying ...: {:#}",
+                    e
+                )
+            })
+            .await
+            .expect("transient error is never returned")
+    }
+}
+
+#[async_trait::async_trait]
+impl<C> WatchForRefunded for Facade<C>
+where
+    C: LatestBlock<Block = bitcoin::Block>
+        + BlockByHash<Block = bitcoin::Block, BlockHash = bitcoin::BlockHash>
+        + BlockNotifications
+        + ConnectedNetwork<Network = ledger::Bitcoin>
+        + TransactionsByScript,
+{
+    async fn watch_for_refunded(
+        &self,
+        params: &Params,
+        fund_event: Funded,
+        start_of_swap: OffsetDateTime,
+    ) -> Refunded {
+        if let Some(refunded) = self.storage.load_event(self.swap_id).await {
+            return refunded;
+        }
+
+        let operation = || {
+            watch_for_refunded(
+                self.connector.as_ref(),
+                &params.shared,
+                fund_event.location,
+                start_of_swap,
+            )
+            .map_err(backoff::Error::Transient)
+        };
+
+        let refunded = operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!("failed to watch for hbit refund, retrying ...: {:#}", e)
+            })
+            .await
+            .expect("transient error is never returned");
+
+        self.storage.save_event(refunded, self.swap_id).await;
+
+        refunded
+    }
+}
+
+#[async_trait::async_trait]
+impl<C> CurrentTime for Facade<C>
+where
+    C: LatestBlock<Block = bitcoin::Block>
+        + BlockByHash<Block = bitcoin::Block, BlockHash = bitcoin::BlockHash>,
+{
+    async fn current_time(&self) -> Timestamp {
+        let operation =
+            || median_time_past(self.connector.as_ref()).map_err(backoff::Error::Transient);
+
+        operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!(
+                    "failed to get median time past of bitcoin chain, retrying ...: {:#}",
+                    e
+                )
+            })
+            .await
+            .expect("transient error is never returned")
+    }
+}
@@ -2,8 +2,11 @@ pub use comit::herc20::*;
 
 use crate::{
     btsieve::{
-        ethereum::{GetLogs, ReceiptByHash, TransactionByHash},
-        BlockByHash, ConnectedNetwork, LatestBlock,
+        ethereum::{
+            latest_block_timestamp, BlockByNumber, GetLogs, PendingTransactions, ReceiptByHash,
+            TransactionByHash,
+        },
+        BlockByHash, BlockNotifications, ConnectedNetwork, LatestBlock,
     },
     ethereum::{Block, ChainId, Hash},
     storage::Storage,
@@ -11,29 +14,43 @@ use crate::{
 };
 use anyhow::Result;
 use backoff::{backoff::Constant, future::FutureOperation};
-use comit::swap::herc20::{IncorrectlyFunded, WatchForDeployed, WatchForFunded, WatchForRedeemed};
-use futures::TryFutureExt;
+use comit::{
+    expiries::CurrentTime,
+    swap::herc20::{
+        IncorrectlyFunded, WatchForDeployed, WatchForFunded, WatchForRedeemed,
+        WatchForRedeemedInMempool, WatchForRefunded,
+    },
+    Secret, Timestamp,
+};
+use futures::{future, TryFutureExt};
 use std::{sync::Arc, time::Duration};
 use time::OffsetDateTime;
 
-#[derive(Clone, Debug, Default)]
-pub struct Events {
-    pub deploy: Option<comit::herc20::Deployed>,
-    pub fund: Option<comit::swap::herc20::Funded>,
-    pub redeem: Option<comit::herc20::Redeemed>,
-}
-
 pub struct Facade<C> {
     pub connector: Arc<C>,
+    pub watch_mempool: bool,
     pub swap_id: LocalSwapId,
     pub storage: Storage,
 }
 
+// A derived implementation would require `C: Clone`.
+impl<C> Clone for Facade<C> {
+    fn clone(&self) -> Self {
+        Self {
+            connector: self.connector.clone(),
+            watch_mempool: self.watch_mempool,
+            swap_id: self.swap_id,
+            storage: self.storage.clone(),
+        }
+    }
+}
+
 #[async_trait::async_trait]
 impl<C> WatchForDeployed for Facade<C>
 where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = Hash>
+        + BlockNotifications
         + ReceiptByHash
         + ConnectedNetwork<Network = ChainId>,
 {
@@ -42,6 +59,10 @@ where
         params: Params,
         utc_start_of_swap: OffsetDateTime,
     ) -> Deployed {
+        if let Some(deployed) = self.storage.load_event(self.swap_id).await {
+            return deployed;
+        }
+
         let operation = || {
             watch_for_deployed(self.connector.as_ref(), params.clone(), utc_start_of_swap)
                 .map_err(backoff::Error::Transient)
@@ -57,13 +78,7 @@ where
             .await
             .expect("transient error is never returned");
 
-        self.storage
-            .herc20_events
-            .lock()
-            .await
-            .entry(self.swap_id)
-            .or_default()
-            .deploy = Some(deployed);
+        self.storage.save_event(deployed, self.swap_id).await;
 
         deployed
     }
@@ -74,9 +89,11 @@ impl<C> WatchForFunded for Facade<C>
 where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = Hash>
+        + BlockNotifications
         + ReceiptByHash
         + TransactionByHash
         + ConnectedNetwork<Network = ChainId>
+        + BlockByNumber
         + GetLogs,
 {
     async fn watch_for_funded(
@@ -85,6 +102,10 @@ where
         deploy_event: Deployed,
         utc_start_of_swap: OffsetDateTime,
     ) -> Result<comit::swap::herc20::Funded, IncorrectlyFunded> {
+        if let Some(funded) = self.storage.load_event(self.swap_id).await {
+            return Ok(funded);
+        }
+
         let operation = || {
             watch_for_funded(
                 self.connector.as_ref(),
@@ -102,13 +123,7 @@ where
             .await
             .expect("transient error is never returned")?;
 
-        self.storage
-            .herc20_events
-            .lock()
-            .await
-            .entry(self.swap_id)
-            .or_default()
-            .fund = Some(funded);
+        self.storage.save_event(funded, self.swap_id).await;
 
         Ok(funded)
     }
@@ -119,9 +134,11 @@ impl<C> WatchForRedeemed for Facade<C>
 where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = Hash>
+        + BlockNotifications
         + ReceiptByHash
         + TransactionByHash
         + ConnectedNetwork<Network = ChainId>
+        + BlockByNumber
         + GetLogs,
 {
     async fn watch_for_redeemed(
@@ -130,6 +147,10 @@ where
         deploy_event: Deployed,
         utc_start_of_swap: OffsetDateTime,
     ) -> Redeemed {
+        if let Some(redeemed) = self.storage.load_event(self.swap_id).await {
+            return redeemed;
+        }
+
         let operation = || {
             watch_for_redeemed(self.connector.as_ref(), utc_start_of_swap, deploy_event)
                 .map_err(backoff::Error::Transient)
@@ -142,14 +163,102 @@ where
             .await
             .expect("transient error is never returned");
 
-        self.storage
-            .herc20_events
-            .lock()
-            .await
-            .entry(self.swap_id)
-            .or_default()
-            .redeem = Some(redeemed);
+        self.storage.save_event(redeemed, self.swap_id).await;
 
         redeemed
     }
 }
+
+#[async_trait::async_trait]
+impl<C> WatchForRedeemedInMempool for Facade<C>
+where
+    C: PendingTransactions,
+{
+    async fn watch_for_redeemed_in_mempool(
+        &self,
+        params: Params,
+        deploy_event: Deployed,
+    ) -> Secret {
+        // The secret is learned once the redeem transaction is mined instead.
+        if !self.watch_mempool {
+            return future::pending().await;
+        }
+
+        let operation = || {
+            watch_for_redeemed_in_mempool(self.connector.as_ref(), &params, deploy_event)
+                .map_err(backoff::Error::Transient)
+        };
+
+        operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!(
+                    "failed to watch pending transactions for herc20 redeem, retrying ...: {:#}",
+                    e
+                )
+            })
+            .await
+            .expect("transient error is never returned")
+    }
+}
+
+#[async_trait::async_trait]
+impl<C> WatchForRefunded for Facade<C>
+where
+    C: LatestBlock<Block = Block>
+        + BlockByHash<Block = Block, BlockHash = Hash>
+        + BlockNotifications
+        + ReceiptByHash
+        + TransactionByHash
+        + ConnectedNetwork<Network = ChainId>
+        + BlockByNumber
+        + GetLogs,
+{
+    async fn watch_for_refunded(
+        &self,
+        _: Params,
+        deploy_event: Deployed,
+        utc_start_of_swap: OffsetDateTime,
+    ) -> Refunded {
+        if let Some(refunded) = self.storage.load_event(self.swap_id).await {
+            return refunded;
+        }
+
+        let operation = || {
+            watch_for_refunded(self.connector.as_ref(), utc_start_of_swap, deploy_event)
+                .map_err(backoff::Error::Transient)
+        };
+
+        let refunded = operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!("failed to watch for herc20 refund, retrying ...: {:#}", e)
+            })
+            .await
+            .expect("transient error is never returned");
+
+        self.storage.save_event(refunded, self.swap_id).await;
+
+        refunded
+    }
+}
+
+#[async_trait::async_trait]
+impl<C> CurrentTime for Facade<C>
+where
+    C: LatestBlock<Block = Block>,
+{
+    async fn current_time(&self) -> Timestamp {
+        let operation = || {
+            latest_block_timestamp(self.connector.as_ref()).map_err(backoff::Error::Transient)
+        };
+
+        operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!(
+                    "failed to get timestamp of latest ethereum block, retrying ...: {:#}",
+                    e
+                )
+            })
+            .await
+            .expect("transient error is never returned")
+    }
+}
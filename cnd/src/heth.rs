@@ -0,0 +1,212 @@
+pub use comit::heth::*;
+
+use crate::{
+    btsieve::{
+        ethereum::{
+            latest_block_timestamp, BlockByNumber, GetLogs, PendingTransactions, ReceiptByHash,
+            TransactionByHash,
+        },
+        BlockByHash, BlockNotifications, ConnectedNetwork, LatestBlock,
+    },
+    ethereum::{Block, ChainId, Hash},
+    storage::Storage,
+    LocalSwapId,
+};
+use anyhow::Result;
+use backoff::{backoff::Constant, future::FutureOperation};
+use comit::{
+    expiries::CurrentTime,
+    swap::heth::{
+        IncorrectlyFunded, WatchForFunded, WatchForRedeemed, WatchForRedeemedInMempool,
+        WatchForRefunded,
+    },
+    Secret, Timestamp,
+};
+use futures::{future, TryFutureExt};
+use std::{sync::Arc, time::Duration};
+use time::OffsetDateTime;
+
+pub struct Facade<C> {
+    pub connector: Arc<C>,
+    pub watch_mempool: bool,
+    pub swap_id: LocalSwapId,
+    pub storage: Storage,
+}
+
+// A derived implementation would require `C: Clone`.
+impl<C> Clone for Facade<C> {
+    fn clone(&self) -> Self {
+        Self {
+            connector: self.connector.clone(),
+            watch_mempool: self.watch_mempool,
+            swap_id: self.swap_id,
+            storage: self.storage.clone(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<C> WatchForFunded for Facade<C>
+where
+    C: LatestBlock<Block = Block>
+        + BlockByHash<Block = Block, BlockHash = Hash>
+        + BlockNotifications
+        + ReceiptByHash
+        + ConnectedNetwork<Network = ChainId>,
+{
+    async fn watch_for_funded(
+        &self,
+        params: Params,
+        utc_start_of_swap: OffsetDateTime,
+    ) -> Result<Funded, IncorrectlyFunded> {
+        if let Some(funded) = self.storage.load_event(self.swap_id).await {
+            return Ok(funded);
+        }
+
+        let operation = || {
+            watch_for_funded(self.connector.as_ref(), params.clone(), utc_start_of_swap)
+                .map_err(backoff::Error::Transient)
+        };
+
+        let funded = operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!("failed to watch for heth funding, retrying ...: {:#}", e)
+            })
+            .await
+            .expect("transient error is never returned")?;
+
+        self.storage.save_event(funded, self.swap_id).await;
+
+        Ok(funded)
+    }
+}
+
+#[async_trait::async_trait]
+impl<C> WatchForRedeemed for Facade<C>
+where
+    C: LatestBlock<Block = Block>
+        + BlockByHash<Block = Block, BlockHash = Hash>
+        + BlockNotifications
+        + ReceiptByHash
+        + TransactionByHash
+        + ConnectedNetwork<Network = ChainId>
+        + BlockByNumber
+        + GetLogs,
+{
+    async fn watch_for_redeemed(
+        &self,
+        _: Params,
+        fund_event: Funded,
+        utc_start_of_swap: OffsetDateTime,
+    ) -> Redeemed {
+        if let Some(redeemed) = self.storage.load_event(self.swap_id).await {
+            return redeemed;
+        }
+
+        let operation = || {
+            watch_for_redeemed(self.connector.as_ref(), utc_start_of_swap, fund_event)
+                .map_err(backoff::Error::Transient)
+        };
+
+        let redeemed = operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!("failed to watch for heth redeem, retrying ...: {:#}", e)
+            })
+            .await
+            .expect("transient error is never returned");
+
+        self.storage.save_event(redeemed, self.swap_id).await;
+
+        redeemed
+    }
+}
+
+#[async_trait::async_trait]
+impl<C> WatchForRedeemedInMempool for Facade<C>
+where
+    C: PendingTransactions,
+{
+    async fn watch_for_redeemed_in_mempool(&self, params: Params, fund_event: Funded) -> Secret {
+        // The secret is learned once the redeem transaction is mined instead.
+        if !self.watch_mempool {
+            return future::pending().await;
+        }
+
+        let operation = || {
+            watch_for_redeemed_in_mempool(self.connector.as_ref(), &params, fund_event)
+                .map_err(backoff::Error::Transient)
+        };
+
+        operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!(
+                    "failed to watch pending transactions for heth redeem, retrying ...: {:#}",
+                    e
+                )
+            })
+            .await
+            .expect("transient error is never returned")
+    }
+}
+
+#[async_trait::async_trait]
+impl<C> WatchForRefunded for Facade<C>
+where
+    C: LatestBlock<Block = Block>
+        + BlockByHash<Block = Block, BlockHash = Hash>
+        + BlockNotifications
+        + ReceiptByHash
+        + TransactionByHash
+        + ConnectedNetwork<Network = ChainId>
+        + BlockByNumber
+        + GetLogs,
+{
+    async fn watch_for_refunded(
+        &self,
+        _: Params,
+        fund_event: Funded,
+        utc_start_of_swap: OffsetDateTime,
+    ) -> Refunded {
+        if let Some(refunded) = self.storage.load_event(self.swap_id).await {
+            return refunded;
+        }
+
+        let operation = || {
+            watch_for_refunded(self.connector.as_ref(), utc_start_of_swap, fund_event)
+                .map_err(backoff::Error::Transient)
+        };
+
+        let refunded = operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!("failed to watch for heth refund, retrying ...: {:#}", e)
+            })
+            .await
+            .expect("transient error is never returned");
+
+        self.storage.save_event(refunded, self.swap_id).await;
+
+        refunded
+    }
+}
+
+#[async_trait::async_trait]
+impl<C> CurrentTime for Facade<C>
+where
+    C: LatestBlock<Block = Block>,
+{
+    async fn current_time(&self) -> Timestamp {
+        let operation = || {
+            latest_block_timestamp(self.connector.as_ref()).map_err(backoff::Error::Transient)
+        };
+
+        operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!(
+                    "failed to get timestamp of latest ethereum block, retrying ...: {:#}",
+                    e
+                )
+            })
+            .await
+            .expect("transient error is never returned")
+    }
+}
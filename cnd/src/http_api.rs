@@ -1,5 +1,6 @@
 mod action;
 mod dial_addr;
+mod events;
 mod info;
 mod markets;
 mod orders;
@@ -17,11 +18,11 @@ pub const PATH: &str = "swaps";
 use crate::{
     asset,
     asset::Erc20Quantity,
-    ethereum,
+    ethereum, hbit, herc20, heth,
     storage::{BtcDaiOrder, Order},
 };
 use anyhow::Result;
-use comit::{swap::Action, OrderId, Position, Price, Quantity};
+use comit::{swap::Action, OrderId, Position, Price, Quantity, TradingPair};
 use serde::Serialize;
 use warp::http::Method;
 
@@ -43,7 +44,11 @@ impl From<(Order, BtcDaiOrder)> for OrderProperties {
         Self {
             id: order.order_id,
             position: order.position,
-            price: Amount::from(btc_dai_order.price),
+            price: Amount::price(
+                btc_dai_order.price,
+                &order.trading_pair,
+                order.quote_decimals,
+            ),
             quantity: Amount::from(btc_dai_order.quantity),
             state: State {
                 open: btc_dai_order.open.to_inner(),
@@ -56,17 +61,12 @@ impl From<(Order, BtcDaiOrder)> for OrderProperties {
     }
 }
 
+/// An amount of a currency, given in the smallest unit of the currency.
 #[derive(Clone, Debug, Serialize, PartialEq)]
-#[serde(tag = "currency")]
-pub enum Amount {
-    #[serde(rename = "BTC")]
-    Bitcoin {
-        #[serde(with = "asset::bitcoin::sats_as_string")]
-        value: asset::Bitcoin,
-        decimals: u8,
-    },
-    #[serde(rename = "DAI")]
-    Dai { value: Erc20Quantity, decimals: u8 },
+pub struct Amount {
+    currency: String,
+    value: String,
+    decimals: u8,
 }
 
 impl From<Quantity<asset::Bitcoin>> for Amount {
@@ -75,23 +75,42 @@ impl From<Quantity<asset::Bitcoin>> for Amount {
     }
 }
 
-impl From<Price<asset::Bitcoin, Erc20Quantity>> for Amount {
-    fn from(price: Price<asset::Bitcoin, Erc20Quantity>) -> Self {
-        Amount::dai(price.wei_per_btc())
-    }
-}
+/// The number of decimals of ether, which has no token configuration.
+const ETHER_DECIMALS: u8 = 18;
 
 impl Amount {
     fn btc(value: asset::Bitcoin) -> Self {
-        Amount::Bitcoin { value, decimals: 8 }
+        Amount {
+            currency: String::from("BTC"),
+            value: value.as_sat().to_string(),
+            decimals: 8,
+        }
     }
 
-    fn dai(value: Erc20Quantity) -> Self {
-        Amount::Dai {
-            value,
-            decimals: 18,
+    fn ether(value: asset::Ether) -> Self {
+        Amount {
+            currency: String::from("ETH"),
+            value: value.to_wei_dec(),
+            decimals: ETHER_DECIMALS,
         }
     }
+
+    fn erc20(symbol: &str, value: Erc20Quantity, decimals: u8) -> Self {
+        Amount {
+            currency: symbol.to_owned(),
+            value: value.to_wei_dec(),
+            decimals,
+        }
+    }
+
+    /// The price of one bitcoin in the quote currency of the trading pair.
+    fn price(
+        price: Price<asset::Bitcoin, Erc20Quantity>,
+        trading_pair: &TradingPair,
+        decimals: u8,
+    ) -> Self {
+        Amount::erc20(trading_pair.quote(), price.wei_per_btc(), decimals)
+    }
 }
 
 #[derive(Serialize)]
@@ -145,6 +164,7 @@ fn cancel_action(order: &OrderProperties) -> Option<siren::Action> {
 pub enum Protocol {
     Hbit { asset: Amount },
     Herc20 { asset: Amount },
+    Heth { asset: Amount },
 }
 
 impl Protocol {
@@ -154,9 +174,15 @@ impl Protocol {
         }
     }
 
-    pub fn herc20_dai(dai: Erc20Quantity) -> Self {
+    pub fn herc20(symbol: &str, quantity: Erc20Quantity, decimals: u8) -> Self {
         Protocol::Herc20 {
-            asset: Amount::dai(dai),
+            asset: Amount::erc20(symbol, quantity, decimals),
+        }
+    }
+
+    pub fn heth(ether: asset::Ether) -> Self {
+        Protocol::Heth {
+            asset: Amount::ether(ether),
         }
     }
 }
@@ -167,6 +193,7 @@ pub enum ActionName {
     Deploy,
     Fund,
     Redeem,
+    Refund,
 }
 
 impl From<Action> for ActionName {
@@ -175,8 +202,13 @@ impl From<Action> for ActionName {
             Action::Herc20Deploy(_) => ActionName::Deploy,
             Action::Herc20Fund(..) => ActionName::Fund,
             Action::Herc20Redeem(..) => ActionName::Redeem,
+            Action::Herc20Refund(_) => ActionName::Refund,
+            Action::HethFund(_) => ActionName::Fund,
+            Action::HethRedeem(..) => ActionName::Redeem,
+            Action::HethRefund(_) => ActionName::Refund,
             Action::HbitFund(_) => ActionName::Fund,
             Action::HbitRedeem(..) => ActionName::Redeem,
+            Action::HbitRefund(_) => ActionName::Refund,
         }
     }
 }
@@ -186,15 +218,112 @@ impl From<Action> for ActionName {
 pub enum SwapEvent {
     HbitFunded { tx: bitcoin::Txid },
     HbitRedeemed { tx: bitcoin::Txid },
+    HbitRefunded { tx: bitcoin::Txid },
     Herc20Deployed { tx: ethereum::Hash },
     Herc20Funded { tx: ethereum::Hash },
     Herc20Redeemed { tx: ethereum::Hash },
+    Herc20Refunded { tx: ethereum::Hash },
+    HethFunded { tx: ethereum::Hash },
+    HethRedeemed { tx: ethereum::Hash },
+    HethRefunded { tx: ethereum::Hash },
+}
+
+impl From<hbit::Funded> for SwapEvent {
+    fn from(event: hbit::Funded) -> Self {
+        SwapEvent::HbitFunded {
+            tx: event.location.txid,
+        }
+    }
+}
+
+impl From<hbit::Redeemed> for SwapEvent {
+    fn from(event: hbit::Redeemed) -> Self {
+        SwapEvent::HbitRedeemed {
+            tx: event.transaction,
+        }
+    }
+}
+
+impl From<hbit::Refunded> for SwapEvent {
+    fn from(event: hbit::Refunded) -> Self {
+        SwapEvent::HbitRefunded {
+            tx: event.transaction,
+        }
+    }
+}
+
+impl From<herc20::Deployed> for SwapEvent {
+    fn from(event: herc20::Deployed) -> Self {
+        SwapEvent::Herc20Deployed {
+            tx: event.transaction,
+        }
+    }
+}
+
+impl From<herc20::Funded> for SwapEvent {
+    fn from(event: herc20::Funded) -> Self {
+        SwapEvent::Herc20Funded {
+            tx: event.transaction,
+        }
+    }
+}
+
+impl From<herc20::Redeemed> for SwapEvent {
+    fn from(event: herc20::Redeemed) -> Self {
+        SwapEvent::Herc20Redeemed {
+            tx: event.transaction,
+        }
+    }
+}
+
+impl From<herc20::Refunded> for SwapEvent {
+    fn from(event: herc20::Refunded) -> Self {
+        SwapEvent::Herc20Refunded {
+            tx: event.transaction,
+        }
+    }
+}
+
+impl From<heth::Funded> for SwapEvent {
+    fn from(event: heth::Funded) -> Self {
+        SwapEvent::HethFunded {
+            tx: event.transaction,
+        }
+    }
+}
+
+impl From<heth::Redeemed> for SwapEvent {
+    fn from(event: heth::Redeemed) -> Self {
+        SwapEvent::HethRedeemed {
+            tx: event.transaction,
+        }
+    }
+}
+
+impl From<heth::Refunded> for SwapEvent {
+    fn from(event: heth::Refunded) -> Self {
+        SwapEvent::HethRefunded {
+            tx: event.transaction,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, thiserror::Error)]
 #[error("action not found")]
 pub struct ActionNotFound;
 
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("built-in wallet is not enabled")]
+pub struct WalletNotEnabled;
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("action {0} was already executed")]
+pub struct ActionAlreadyExecuted(pub ActionName);
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("no token is configured for trading pair {0}")]
+pub struct UnsupportedTradingPair(pub TradingPair);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -209,7 +338,11 @@ mod tests {
         let properties = OrderProperties {
             id: OrderId::from(Uuid::from_u128(0)),
             position: Position::Sell,
-            price: Amount::dai(Erc20Quantity::from_wei_dec_str("9100000000000000000000").unwrap()),
+            price: Amount::erc20(
+                "DAI",
+                Erc20Quantity::from_wei_dec_str("9100000000000000000000").unwrap(),
+                18,
+            ),
             quantity: Amount::btc(Bitcoin::from_sat(10000000)),
             state: State {
                 open: Bitcoin::from_sat(3000000),
@@ -262,8 +395,11 @@ mod tests {
 
     #[test]
     fn dai_amount_serializes_properly() {
-        let amount =
-            Amount::dai(Erc20Quantity::from_wei_dec_str("9000000000000000000000").unwrap());
+        let amount = Amount::erc20(
+            "DAI",
+            Erc20Quantity::from_wei_dec_str("9000000000000000000000").unwrap(),
+            18,
+        );
 
         let string = serde_json::to_string(&amount).unwrap();
 
@@ -273,6 +409,20 @@ mod tests {
         )
     }
 
+    #[test]
+    fn price_is_given_in_the_quote_currency_of_the_trading_pair() {
+        let usdc = TradingPair::btc_against("USDC").unwrap();
+        // 1 BTC : 15_000 USDC = 100_000_000 SAT : 15_000_000_000 (6 decimals)
+        let price = Price::from_wei_per_sat(Erc20Quantity::from_wei(150u64));
+
+        let string = serde_json::to_string(&Amount::price(price, &usdc, 6)).unwrap();
+
+        assert_eq!(
+            string,
+            r#"{"currency":"USDC","value":"15000000000","decimals":6}"#
+        )
+    }
+
     #[test]
     fn hbit_protocol_serializes_correctly() {
         let protocol = Protocol::hbit(asset::Bitcoin::from_sat(10_000));
@@ -294,7 +444,8 @@ mod tests {
 
     #[test]
     fn herc20_protocol_serializes_correctly() {
-        let protocol = Protocol::herc20_dai(Erc20Quantity::from_wei(1_000_000_000_000_000u64));
+        let protocol =
+            Protocol::herc20("DAI", Erc20Quantity::from_wei(1_000_000_000_000_000u64), 18);
 
         let result = serde_json::to_string_pretty(&protocol).unwrap();
 
@@ -307,6 +458,25 @@ mod tests {
     "value": "1000000000000000",
     "decimals": 18
   }
+}"#
+        )
+    }
+
+    #[test]
+    fn heth_protocol_serializes_correctly() {
+        let protocol = Protocol::heth(asset::Ether::from_wei(1_000_000_000_000_000u64));
+
+        let result = serde_json::to_string_pretty(&protocol).unwrap();
+
+        assert_eq!(
+            result,
+            r#"{
+  "protocol": "heth",
+  "asset": {
+    "currency": "ETH",
+    "value": "1000000000000000",
+    "decimals": 18
+  }
 }"#
         )
     }
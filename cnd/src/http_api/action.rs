@@ -104,8 +104,12 @@ impl ActionResponseBody {
             Action::Herc20Deploy(inner) => inner.into(),
             Action::Herc20Fund(inner) => inner.into(),
             Action::Herc20Redeem(inner, _) => inner.into(),
+            Action::Herc20Refund(inner) => inner.into(),
+            Action::HethFund(inner) => inner.into(),
+            Action::HethRedeem(inner, _) => inner.into(),
+            Action::HethRefund(inner) => inner.into(),
             Action::HbitFund(inner) => inner.into(),
-            Action::HbitRedeem(inner, _) => {
+            Action::HbitRedeem(inner, _) | Action::HbitRefund(inner) => {
                 let network = inner.network;
                 let rate = bitcoin_fees.get_per_vbyte_rate().await?;
 
@@ -0,0 +1,174 @@
+//! Pushes updates about orders and swaps to clients as server-sent events.
+//!
+//! Each update is sent as an event with one of the following names:
+//!
+//! - `order`: The state of an order changed, the data is the same as the
+//!   properties returned from `/orders/:order_id`.
+//! - `match`: One of our orders was matched against an order of another peer.
+//! - `swap_event`: An event of a swap's HTLC was observed on the ledger.
+//! - `next_action`: The next action of a swap changed.
+
+use crate::{
+    http_api::{serde_peer_id, ActionName, Amount, OrderProperties, SwapEvent},
+    storage::{BtcDaiOrder, Order, Storage},
+    updates::Update,
+    LocalSwapId,
+};
+use anyhow::Result;
+use comit::{orderpool::Match, OrderId, Position};
+use futures::{stream, Stream};
+use libp2p::PeerId;
+use serde::Serialize;
+use std::convert::Infallible;
+use tokio::sync::broadcast::RecvError;
+use warp::{sse::ServerSentEvent, Filter, Rejection, Reply};
+
+/// The warp filter for subscribing to the event stream.
+pub fn route(storage: Storage) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::get().and(warp::path!("events")).map(move || {
+        let events = events(storage.clone());
+
+        warp::sse::reply(warp::sse::keep_alive().stream(events))
+    })
+}
+
+fn events(storage: Storage) -> impl Stream<Item = Result<impl ServerSentEvent, Infallible>> {
+    let updates = storage.updates.subscribe();
+
+    stream::unfold((updates, storage), |(mut updates, storage)| async move {
+        loop {
+            match updates.recv().await {
+                Ok(update) => match event(update, &storage).await {
+                    Ok((name, data)) => {
+                        let event = (warp::sse::event(name), warp::sse::json(data));
+
+                        return Some((Ok(event), (updates, storage)));
+                    }
+                    Err(e) => tracing::warn!("failed to create event from update: {:#}", e),
+                },
+                Err(RecvError::Lagged(skipped)) => tracing::warn!(
+                    "event stream fell behind, {} updates were not delivered",
+                    skipped
+                ),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
+async fn event(update: Update, storage: &Storage) -> Result<(&'static str, serde_json::Value)> {
+    let event = match update {
+        Update::Order(order_id) => {
+            let (order, btc_dai_order) = load_order(storage, order_id).await?;
+            let properties = OrderProperties::from((order, btc_dai_order));
+
+            ("order", serde_json::to_value(properties)?)
+        }
+        Update::Match(new_match) => {
+            let (order, _) = load_order(storage, new_match.ours).await?;
+            let properties = MatchProperties::new(new_match, &order);
+
+            ("match", serde_json::to_value(properties)?)
+        }
+        Update::SwapEvent(id, event) => (
+            "swap_event",
+            serde_json::to_value(SwapEventProperties { id, event })?,
+        ),
+        Update::NextAction(id, action) => (
+            "next_action",
+            serde_json::to_value(NextActionProperties { id, action })?,
+        ),
+    };
+
+    Ok(event)
+}
+
+async fn load_order(storage: &Storage, order_id: OrderId) -> Result<(Order, BtcDaiOrder)> {
+    storage
+        .db
+        .do_in_transaction(|conn| {
+            let order = Order::by_order_id(conn, order_id)?;
+            let btc_dai_order = BtcDaiOrder::by_order(conn, &order)?;
+
+            Ok((order, btc_dai_order))
+        })
+        .await
+}
+
+#[derive(Debug, Serialize)]
+struct MatchProperties {
+    #[serde(with = "serde_peer_id")]
+    peer: PeerId,
+    ours: OrderId,
+    theirs: OrderId,
+    position: Position,
+    price: Amount,
+    quantity: Amount,
+}
+
+impl MatchProperties {
+    fn new(new_match: Match, our_order: &Order) -> Self {
+        Self {
+            peer: new_match.peer,
+            ours: new_match.ours,
+            theirs: new_match.theirs,
+            position: new_match.our_position,
+            price: Amount::price(
+                new_match.price,
+                &our_order.trading_pair,
+                our_order.quote_decimals,
+            ),
+            quantity: Amount::from(new_match.quantity),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct SwapEventProperties {
+    id: LocalSwapId,
+    event: SwapEvent,
+}
+
+#[derive(Debug, Serialize)]
+struct NextActionProperties {
+    id: LocalSwapId,
+    action: Option<ActionName>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum;
+
+    #[test]
+    fn swap_event_serializes_correctly() {
+        let properties = SwapEventProperties {
+            id: "111152ca-ecf2-4cc6-b35c-b4351ac28a34".parse().unwrap(),
+            event: SwapEvent::Herc20Funded {
+                tx: ethereum::Hash::from([0u8; 32]),
+            },
+        };
+
+        let json = serde_json::to_string(&properties).unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"id":"111152ca-ecf2-4cc6-b35c-b4351ac28a34","event":{"name":"herc20_funded","tx":"0x0000000000000000000000000000000000000000000000000000000000000000"}}"#
+        )
+    }
+
+    #[test]
+    fn next_action_serializes_correctly() {
+        let properties = NextActionProperties {
+            id: "111152ca-ecf2-4cc6-b35c-b4351ac28a34".parse().unwrap(),
+            action: Some(ActionName::Fund),
+        };
+
+        let json = serde_json::to_string(&properties).unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"id":"111152ca-ecf2-4cc6-b35c-b4351ac28a34","action":"fund"}"#
+        )
+    }
+}
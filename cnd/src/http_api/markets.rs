@@ -1,3 +1,3 @@
-mod get_btc_dai;
+mod get;
 
-pub use get_btc_dai::route as get_btc_dai;
+pub use get::route as get;
@@ -1,23 +1,26 @@
 use crate::{
-    http_api::{problem, serde_peer_id, Amount},
+    config::Settings,
+    http_api::{problem, serde_peer_id, Amount, UnsupportedTradingPair, ETHER_DECIMALS},
     network::Swarm,
 };
 use anyhow::{Context, Result};
-use comit::{expiries, order::SwapProtocol, BtcDaiOrder, OrderId, Position};
+use comit::{expiries, order::SwapProtocol, BtcErc20Order, OrderId, Position, TradingPair};
 use futures::TryFutureExt;
 use libp2p::PeerId;
 use serde::Serialize;
 use warp::{reply, Filter, Rejection, Reply};
 
-/// The warp filter for getting the BTC/DAI market view.
+/// The warp filter for getting the market view of a trading pair, e.g.
+/// `/markets/BTC-DAI`.
 pub fn route(
     swarm: Swarm,
+    settings: Settings,
     network: comit::Network,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::get()
-        .and(warp::path!("markets" / "BTC-DAI"))
-        .and_then(move || {
-            handler(swarm.clone(), network)
+        .and(warp::path!("markets" / TradingPair))
+        .and_then(move |trading_pair| {
+            handler(trading_pair, swarm.clone(), settings.clone(), network)
                 .map_err(problem::from_anyhow)
                 .map_err(warp::reject::custom)
         })
@@ -25,12 +28,28 @@ pub fn route(
 
 /// Retrieves viable orders: orders that have expiries that match the safe
 /// expiries determined by the expiries module.
-async fn handler(swarm: Swarm, network: comit::Network) -> Result<impl Reply> {
+async fn handler(
+    trading_pair: TradingPair,
+    swarm: Swarm,
+    settings: Settings,
+    network: comit::Network,
+) -> Result<impl Reply> {
+    let decimals = if trading_pair.is_ether() {
+        ETHER_DECIMALS
+    } else {
+        settings
+            .ethereum
+            .tokens
+            .find(&trading_pair)
+            .ok_or_else(|| UnsupportedTradingPair(trading_pair.clone()))?
+            .decimals
+    };
+
     let mut orders = siren::Entity::default();
     let local_peer_id = swarm.local_peer_id();
 
     let viable_orders = swarm
-        .btc_dai_market()
+        .market(&trading_pair)
         .await
         .into_iter()
         .filter(|(_, order)| has_viable_expiries(order, network));
@@ -40,7 +59,7 @@ async fn handler(swarm: Swarm, network: comit::Network) -> Result<impl Reply> {
             .with_properties(MarketItem {
                 id: order.id,
                 quantity: Amount::from(order.quantity),
-                price: Amount::from(order.price),
+                price: Amount::price(order.price, &trading_pair, decimals),
                 ours: maker == local_peer_id,
                 maker,
                 position: order.position,
@@ -53,7 +72,7 @@ async fn handler(swarm: Swarm, network: comit::Network) -> Result<impl Reply> {
     Ok(reply::json(&orders))
 }
 
-pub fn has_viable_expiries(order: &BtcDaiOrder, network: comit::Network) -> bool {
+pub fn has_viable_expiries(order: &BtcErc20Order, network: comit::Network) -> bool {
     match order.swap_protocol {
         SwapProtocol::HbitHerc20 {
             hbit_expiry_offset,
@@ -69,6 +88,18 @@ pub fn has_viable_expiries(order: &BtcDaiOrder, network: comit::Network) -> bool
             (herc20_expiry_offset, hbit_expiry_offset)
                 == expiries::expiry_offsets_herc20_hbit(network)
         }
+        SwapProtocol::HbitHeth {
+            hbit_expiry_offset,
+            heth_expiry_offset,
+        } => {
+            (hbit_expiry_offset, heth_expiry_offset) == expiries::expiry_offsets_hbit_heth(network)
+        }
+        SwapProtocol::HethHbit {
+            heth_expiry_offset,
+            hbit_expiry_offset,
+        } => {
+            (heth_expiry_offset, hbit_expiry_offset) == expiries::expiry_offsets_heth_hbit(network)
+        }
     }
 }
 
@@ -85,8 +116,8 @@ struct MarketItem {
 
 #[cfg(test)]
 mod tests {
-    use crate::http_api::markets::get_btc_dai::has_viable_expiries;
-    use comit::{asset, order::SwapProtocol, BtcDaiOrder, Position, Price, Quantity, Role};
+    use crate::http_api::markets::get::has_viable_expiries;
+    use comit::{asset, order::SwapProtocol, BtcErc20Order, Position, Price, Quantity, Role};
     use spectral::{assert_that, prelude::MappingIterAssertions};
     use time::Duration;
 
@@ -101,22 +132,22 @@ mod tests {
         let filtered_orders = unfiltered_orders
             .into_iter()
             .filter(|order| has_viable_expiries(order, comit::Network::Dev))
-            .collect::<Vec<BtcDaiOrder>>();
+            .collect::<Vec<BtcErc20Order>>();
 
         assert_eq!(filtered_orders.len(), 1);
         assert_that(&filtered_orders)
             .matching_contains(|order| order_with_viable_expiries.id == order.id);
     }
 
-    fn order_with_viable_expiries() -> BtcDaiOrder {
-        BtcDaiOrder::sell(
+    fn order_with_viable_expiries() -> BtcErc20Order {
+        BtcErc20Order::sell(
             Quantity::new(asset::Bitcoin::ZERO),
             Price::from_wei_per_sat(asset::Erc20Quantity::zero()),
             SwapProtocol::new(Role::Alice, Position::Sell, comit::Network::Dev),
         )
     }
 
-    fn order_with_unviable_expiries() -> BtcDaiOrder {
+    fn order_with_unviable_expiries() -> BtcErc20Order {
         let unsafe_hbit_expiry_offset = Duration::zero();
         let unsafe_herc20_expiry_offset = Duration::zero();
 
@@ -129,11 +160,11 @@ mod tests {
         assert_ne!(
             order_with_viable_expiries()
                 .swap_protocol
-                .herc20_expiry_offset(),
+                .ethereum_expiry_offset(),
             unsafe_herc20_expiry_offset
         );
 
-        BtcDaiOrder::sell(
+        BtcErc20Order::sell(
             Quantity::new(asset::Bitcoin::ZERO),
             Price::from_wei_per_sat(asset::Erc20Quantity::zero()),
             SwapProtocol::HbitHerc20 {
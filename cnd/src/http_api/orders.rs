@@ -1,9 +1,9 @@
 mod cancel;
 mod get_single;
 mod list_open;
-mod make_btc_dai;
+mod make;
 
 pub use cancel::route as cancel;
 pub use get_single::route as get_single;
 pub use list_open::route as list_open;
-pub use make_btc_dai::route as make_btc_dai;
+pub use make::route as make;
@@ -2,6 +2,7 @@ use crate::{
     http_api::problem,
     network::Swarm,
     storage::{commands, Storage},
+    updates::Update,
 };
 use anyhow::Result;
 use comit::OrderId;
@@ -27,6 +28,7 @@ async fn handler(order_id: OrderId, storage: Storage, swarm: Swarm) -> Result<im
 
     db.do_in_transaction(|conn| commands::update_btc_dai_order_to_cancelled(conn, order_id))
         .await?;
+    storage.updates.publish(Update::Order(order_id));
     swarm.cancel_order(order_id).await;
 
     Ok(warp::reply())
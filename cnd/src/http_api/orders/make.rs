@@ -0,0 +1,204 @@
+//! This file contains most of the logic for making new orders through the HTTP
+//! API.
+//!
+//! Compared to other routes, emphasis has been placed on making this file
+//! self-contained by exposing a single filter and keeping every thing else
+//! private.
+
+use crate::{
+    asset::{
+        Erc20Quantity, {self},
+    },
+    config::{Settings, Token},
+    ethereum,
+    http_api::{problem, UnsupportedTradingPair, ETHER_DECIMALS},
+    network::Swarm,
+    storage::{
+        InsertableBtcDaiOrder, InsertableOrder, InsertableOrderHbitParams,
+        InsertableOrderHerc20Params, InsertableOrderHethParams, Storage,
+    },
+    updates::Update,
+    Role,
+};
+use anyhow::Result;
+use comit::{order::SwapProtocol, BtcErc20Order, Position, Price, Quantity, Side, TradingPair};
+use diesel::SqliteConnection;
+use futures::TryFutureExt;
+use serde::Deserialize;
+use warp::{http::StatusCode, Filter, Rejection, Reply};
+
+/// The warp filter for making a new order of a trading pair, e.g.
+/// `/orders/BTC-DAI`.
+pub fn route(
+    storage: Storage,
+    swarm: Swarm,
+    settings: Settings,
+    network: comit::Network,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::post()
+        .and(warp::path!("orders" / TradingPair))
+        .and(warp::body::json())
+        .and_then(move |trading_pair, body| {
+            handler(
+                trading_pair,
+                body,
+                storage.clone(),
+                swarm.clone(),
+                settings.clone(),
+                network,
+            )
+            .map_err(problem::from_anyhow)
+            .map_err(warp::reject::custom)
+        })
+}
+
+async fn handler(
+    trading_pair: TradingPair,
+    body: Body,
+    storage: Storage,
+    swarm: Swarm,
+    settings: Settings,
+    network: comit::Network,
+) -> Result<impl Reply> {
+    let db = &storage.db;
+
+    // Ether is not a token, it is locked with heth instead of herc20.
+    let token = if trading_pair.is_ether() {
+        None
+    } else {
+        let token = settings
+            .ethereum
+            .tokens
+            .find(&trading_pair)
+            .ok_or_else(|| UnsupportedTradingPair(trading_pair.clone()))?;
+
+        Some(token)
+    };
+
+    let order = BtcErc20Order::new(
+        body.position,
+        Quantity::new(body.quantity),
+        Price::from_wei_per_btc(body.price),
+        SwapProtocol::for_trading_pair(&trading_pair, body.swap.role, body.position, network),
+    )
+    .with_trading_pair(trading_pair);
+    let order_id = order.id;
+
+    db.do_in_transaction(save_order(order.clone(), token, body.swap, settings))
+        .await?;
+    storage.updates.publish(Update::Order(order_id));
+    swarm.publish_order(order).await;
+
+    Ok(warp::reply::with_header(
+        StatusCode::CREATED,
+        "Location",
+        format!("/orders/{}", order_id),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct Body {
+    position: Position,
+    #[serde(with = "asset::bitcoin::sats_as_string")]
+    quantity: asset::Bitcoin,
+    /// In the smallest unit of the quote currency per bitcoin.
+    price: Erc20Quantity,
+    swap: SwapParams,
+}
+
+#[derive(Debug, Deserialize)]
+struct SwapParams {
+    #[serde(default = "default_role")]
+    role: Role,
+    bitcoin_address: bitcoin::Address,
+    ethereum_address: ethereum::Address,
+}
+
+fn default_role() -> Role {
+    Role::Alice
+}
+
+fn save_order(
+    order: BtcErc20Order,
+    token: Option<Token>,
+    swap: SwapParams,
+    settings: Settings,
+) -> impl FnOnce(&SqliteConnection) -> Result<()> {
+    let insertable_order = InsertableOrder::new(
+        order.id,
+        order.position,
+        order.created_at,
+        order.trading_pair.clone(),
+        token
+            .as_ref()
+            .map_or(ETHER_DECIMALS, |token| token.decimals),
+    );
+
+    let insertable_btc_dai_order = {
+        let quantity = order.quantity.to_inner();
+        let price = order.price.wei_per_btc();
+
+        move |order_fk| InsertableBtcDaiOrder::new(order_fk, quantity, price)
+    };
+
+    let insertable_hbit = {
+        let network = settings.bitcoin.network;
+        let swap_protocol = order.swap_protocol;
+        let our_final_address = swap.bitcoin_address;
+
+        move |order_fk| {
+            InsertableOrderHbitParams::new(
+                order_fk,
+                network,
+                our_final_address,
+                swap_protocol.hbit_expiry_offset().whole_seconds(),
+                match swap_protocol {
+                    SwapProtocol::HbitHerc20 { .. } | SwapProtocol::HbitHeth { .. } => Side::Alpha,
+                    SwapProtocol::Herc20Hbit { .. } | SwapProtocol::HethHbit { .. } => Side::Beta,
+                },
+            )
+        }
+    };
+
+    let chain_id = settings.ethereum.chain_id;
+    let our_htlc_identity = swap.ethereum_address;
+    let ethereum_expiry_offset = order.swap_protocol.ethereum_expiry_offset().whole_seconds();
+    let ethereum_side = match order.swap_protocol {
+        SwapProtocol::Herc20Hbit { .. } | SwapProtocol::HethHbit { .. } => Side::Alpha,
+        SwapProtocol::HbitHerc20 { .. } | SwapProtocol::HbitHeth { .. } => Side::Beta,
+    };
+
+    let insertable_herc20_params = move |order_fk, token_contract| {
+        InsertableOrderHerc20Params::new(
+            order_fk,
+            chain_id,
+            our_htlc_identity,
+            token_contract,
+            ethereum_expiry_offset,
+            ethereum_side,
+        )
+    };
+
+    let insertable_heth_params = move |order_fk| {
+        InsertableOrderHethParams::new(
+            order_fk,
+            chain_id,
+            our_htlc_identity,
+            ethereum_expiry_offset,
+            ethereum_side,
+        )
+    };
+
+    move |conn| {
+        let order_fk = insertable_order.insert(conn)?;
+
+        insertable_btc_dai_order(order_fk).insert(conn)?;
+        insertable_hbit(order_fk).insert(conn)?;
+        match token {
+            Some(token) => insertable_herc20_params(order_fk, token.address).insert(conn)?,
+            None => insertable_heth_params(order_fk).insert(conn)?,
+        }
+
+        Ok(())
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{
-    http_api::ActionNotFound,
+    http_api::{ActionAlreadyExecuted, ActionNotFound, UnsupportedTradingPair, WalletNotEnabled},
     storage::{commands::NotOpen, NoOrderExists, NoSwapExists},
 };
 use http_api_problem::HttpApiProblem;
@@ -29,6 +29,15 @@ pub fn from_anyhow(e: anyhow::Error) -> HttpApiProblem {
         e if e.is::<ActionNotFound>() => {
             HttpApiProblem::new("Action not found.").set_status(StatusCode::NOT_FOUND)
         }
+        e if e.is::<ActionAlreadyExecuted>() => {
+            HttpApiProblem::new("Action was already executed.").set_status(StatusCode::CONFLICT)
+        }
+        e if e.is::<WalletNotEnabled>() => HttpApiProblem::new("Wallet not enabled.")
+            .set_status(StatusCode::BAD_REQUEST)
+            .set_detail("Enable the built-in wallet in the configuration file of cnd."),
+        e if e.is::<UnsupportedTradingPair>() => {
+            HttpApiProblem::new("Trading pair not supported.").set_status(StatusCode::NOT_FOUND)
+        }
         e => {
             tracing::error!("unhandled error: {:#}", e);
 
@@ -2,9 +2,10 @@ use crate::{
     bitcoin_fees::BitcoinFees,
     config::{AllowedOrigins, Settings},
     http_api,
-    http_api::{dial_addr, info, markets, orders, peers, swaps, tokens},
+    http_api::{dial_addr, events, info, markets, orders, peers, swaps, tokens},
     network::Swarm,
     storage::Storage,
+    wallet::Wallet,
     LocalSwapId,
 };
 use warp::{self, filters::BoxedFilter, Filter, Reply};
@@ -18,6 +19,7 @@ pub fn create(
     storage: Storage,
     settings: &Settings,
     bitcoin_fees: BitcoinFees,
+    wallet: Option<Wallet>,
     network: comit::Network,
 ) -> BoxedFilter<(impl Reply,)> {
     let swaps = warp::path(http_api::PATH);
@@ -30,6 +32,7 @@ pub fn create(
         move || storage.clone()
     });
     let bitcoin_fees = warp::any().map(move || bitcoin_fees.clone());
+    let wallet = warp::any().map(move || wallet.clone());
     let preflight_cors_route = warp::options().map(warp::reply);
 
     let cors = warp::cors()
@@ -76,16 +79,21 @@ pub fn create(
     let action = warp::get()
         .and(swaps)
         .and(warp::path::param())
-        .and(
-            warp::path("fund")
-                .or(warp::path("deploy"))
-                .or(warp::path("redeem"))
-                .or(warp::path("refund")),
-        )
+        .and(warp::path::param())
+        .and(warp::path::end())
+        .and(storage_filter.clone())
+        .and(bitcoin_fees.clone())
+        .and_then(swaps::action);
+
+    let execute_action = warp::post()
+        .and(swaps)
+        .and(warp::path::param())
+        .and(warp::path::param())
         .and(warp::path::end())
         .and(storage_filter)
         .and(bitcoin_fees)
-        .and_then(swaps::action);
+        .and(wallet)
+        .and_then(swaps::execute_action);
 
     let post_dial_addr = warp::post()
         .and(warp::path!("dial"))
@@ -101,7 +109,8 @@ pub fn create(
         .or(get_swap)
         .or(get_swaps)
         .or(action)
-        .or(orders::make_btc_dai(
+        .or(execute_action)
+        .or(orders::make(
             storage.clone(),
             swarm.clone(),
             settings.clone(),
@@ -109,9 +118,10 @@ pub fn create(
         ))
         .or(orders::get_single(storage.clone()))
         .or(orders::list_open(storage.clone()))
-        .or(orders::cancel(storage, swarm.clone()))
+        .or(orders::cancel(storage.clone(), swarm.clone()))
+        .or(events::route(storage))
         .or(tokens::list(settings.clone()))
-        .or(markets::get_btc_dai(swarm, network))
+        .or(markets::get(swarm, settings.clone(), network))
         .or(post_dial_addr)
         .recover(http_api::unpack_problem)
         .with(warp::trace(|info| {
@@ -1,4 +1,4 @@
-//! The REST API exposes the "/swaps" endpoint for three purposes:
+//! The REST API exposes the "/swaps" endpoint for four purposes:
 //!
 //! 1. To create a swap: POST requests can create a swap on the swap
 //!    protocol endpoint e.g., /swaps/hbit/herc20
@@ -8,18 +8,26 @@
 //!
 //! 3. To fetch swap actions: GET requests can fetch an appropriate swap
 //!    action on the action endpoint e.g., "/swaps/:swap_id/fund"
+//!
+//! 4. To execute swap actions: POST requests on the action endpoint sign and
+//!    broadcast the action with the built-in wallet, if it is enabled
 
 use crate::{
     bitcoin_fees::BitcoinFees,
     http_api::{
-        action::ActionResponseBody, problem, route_factory, ActionName, ActionNotFound, Protocol,
-        SwapEvent,
+        action::ActionResponseBody, problem, route_factory, ActionAlreadyExecuted, ActionName,
+        ActionNotFound, Protocol, SwapEvent, WalletNotEnabled,
+    },
+    storage::{
+        queries::{get_active_swap_contexts, get_swap_fk},
+        ExecutedAction, InsertableExecutedAction, Load, Storage,
     },
-    storage::{queries::get_active_swap_contexts, Load, Storage},
+    wallet::{TransactionId, Wallet},
     LocalSwapId, Role,
 };
 use comit::swap::Action;
 use serde::Serialize;
+use std::str::FromStr;
 use warp::{http, Rejection, Reply};
 
 #[allow(clippy::needless_pass_by_value)]
@@ -107,11 +115,26 @@ impl std::fmt::Display for ActionName {
             ActionName::Deploy => "deploy",
             ActionName::Fund => "fund",
             ActionName::Redeem => "redeem",
+            ActionName::Refund => "refund",
         };
         write!(f, "{}", str)
     }
 }
 
+impl FromStr for ActionName {
+    type Err = ActionNotFound;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "deploy" => Ok(ActionName::Deploy),
+            "fund" => Ok(ActionName::Fund),
+            "redeem" => Ok(ActionName::Redeem),
+            "refund" => Ok(ActionName::Refund),
+            _ => Err(ActionNotFound),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct SwapResource {
     pub role: Role,
@@ -121,13 +144,13 @@ pub struct SwapResource {
 }
 
 #[allow(clippy::needless_pass_by_value)]
-pub async fn action<DUMMY>(
+pub async fn action(
     id: LocalSwapId,
-    _: DUMMY,
+    action_name: ActionName,
     storage: Storage,
     bitcoin_fees: BitcoinFees,
 ) -> Result<impl Reply, Rejection> {
-    handle_action(id, storage, bitcoin_fees)
+    handle_action(id, action_name, storage, bitcoin_fees)
         .await
         .map(|body| warp::reply::json(&body))
         .map_err(problem::from_anyhow)
@@ -137,16 +160,123 @@ pub async fn action<DUMMY>(
 #[allow(clippy::unit_arg, clippy::let_unit_value, clippy::cognitive_complexity)]
 async fn handle_action(
     id: LocalSwapId,
+    action_name: ActionName,
     storage: Storage,
     bitcoin_fees: BitcoinFees,
 ) -> anyhow::Result<ActionResponseBody> {
+    let action = next_action(&storage, id, action_name).await?;
+
+    ActionResponseBody::from_action(action, bitcoin_fees).await
+}
+
+/// Returns the next action of the swap if it is the requested one.
+async fn next_action(
+    storage: &Storage,
+    id: LocalSwapId,
+    action_name: ActionName,
+) -> anyhow::Result<Action> {
     let action = storage
         .next_action
         .lock()
         .await
         .get(&id)
         .cloned()
+        .filter(|action| ActionName::from(action.clone()) == action_name)
         .ok_or(ActionNotFound)?;
 
-    ActionResponseBody::from_action(action, bitcoin_fees).await
+    Ok(action)
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExecutedActionBody {
+    action: ActionName,
+    transaction: TransactionId,
+}
+
+#[allow(clippy::needless_pass_by_value)]
+pub async fn execute_action(
+    id: LocalSwapId,
+    action_name: ActionName,
+    storage: Storage,
+    bitcoin_fees: BitcoinFees,
+    wallet: Option<Wallet>,
+) -> Result<impl Reply, Rejection> {
+    handle_execute_action(id, action_name, storage, bitcoin_fees, wallet)
+        .await
+        .map(|body| warp::reply::json(&body))
+        .map_err(problem::from_anyhow)
+        .map_err(warp::reject::custom)
+}
+
+async fn handle_execute_action(
+    id: LocalSwapId,
+    action_name: ActionName,
+    storage: Storage,
+    bitcoin_fees: BitcoinFees,
+    wallet: Option<Wallet>,
+) -> anyhow::Result<ExecutedActionBody> {
+    let wallet = wallet.ok_or(WalletNotEnabled)?;
+
+    // Holding the lock for the whole request makes sure that we don't execute
+    // an action twice if the same request is sent concurrently.
+    let _guard = wallet.lock().await;
+
+    let executed_action = storage
+        .db
+        .do_in_transaction(|conn| {
+            ExecutedAction::by_swap_id_and_action(conn, id, &action_name.to_string())
+        })
+        .await?;
+    if executed_action.is_some() {
+        anyhow::bail!(ActionAlreadyExecuted(action_name))
+    }
+
+    let action = next_action(&storage, id, action_name).await?;
+
+    let transaction = wallet.execute(action, &bitcoin_fees).await?;
+    tracing::info!(
+        "executed {} action of swap {}: {}",
+        action_name,
+        id,
+        transaction
+    );
+
+    storage
+        .db
+        .do_in_transaction(|conn| {
+            let swap_fk = get_swap_fk(conn, id)?;
+
+            InsertableExecutedAction::new(swap_fk, action_name.to_string(), transaction.to_string())
+                .insert(conn)
+        })
+        .await?;
+
+    Ok(ExecutedActionBody {
+        action: action_name,
+        transaction,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn action_name_roundtrips_through_path_segment() {
+        for name in &[
+            ActionName::Deploy,
+            ActionName::Fund,
+            ActionName::Redeem,
+            ActionName::Refund,
+        ] {
+            let segment = name.to_string();
+
+            assert_eq!(segment.parse::<ActionName>().unwrap(), *name);
+        }
+    }
+
+    #[test]
+    fn unknown_path_segment_is_not_an_action() {
+        assert!("accept".parse::<ActionName>().is_err());
+    }
 }
@@ -34,6 +34,7 @@ mod file_lock;
 mod fs;
 mod hbit;
 mod herc20;
+mod heth;
 mod http_api;
 mod local_swap_id;
 mod republish;
@@ -41,6 +42,8 @@ mod respawn;
 mod spawn;
 mod storage;
 mod trace;
+mod updates;
+mod wallet;
 
 mod htlc_location {
     pub use comit::htlc_location::*;
@@ -66,7 +69,11 @@ mod btsieve {
 
 use self::{
     bitcoin_fees::BitcoinFees,
-    btsieve::{bitcoin::BitcoindConnector, ethereum::Web3Connector},
+    btsieve::{
+        bitcoin::{AnyConnector, BitcoindConnector, ElectrumConnector, EsploraConnector},
+        ethereum::Web3Connector,
+        BlockStore, MultiConnector,
+    },
     config::{validate_connection_to_network, Settings},
     connectors::Connectors,
     file_lock::TryLockExclusive,
@@ -75,7 +82,8 @@ use self::{
     republish::republish_open_orders,
     respawn::respawn,
     spawn::*,
-    storage::{RootSeed, Sqlite, Storage},
+    storage::{queries::get_start_of_oldest_active_swap, RootSeed, Sqlite, Storage},
+    wallet::Wallet,
 };
 use crate::{
     cli::{Command, CreateTransaction},
@@ -145,44 +153,108 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
+    let block_store = if settings.data.persistent_block_cache {
+        Some(BlockStore::open(&settings.data.dir.join("block_cache"))?)
+    } else {
+        None
+    };
+
     let bitcoin_connector = {
         let config::Bitcoin {
             bitcoind,
             network,
+            connector,
+            watch_mempool: _,
             fees: _,
         } = &settings.bitcoin;
-        let connector = BitcoindConnector::new(bitcoind.node_url.clone())?;
+        let connector = match connector {
+            config::BitcoinConnector::Bitcoind => {
+                let mut connector = BitcoindConnector::new(bitcoind.node_url.clone())?;
+                if let Some(zmq_url) = &bitcoind.zmq_url {
+                    connector = connector.with_hash_block_subscription(zmq_url.clone());
+                }
+
+                AnyConnector::Bitcoind(connector)
+            }
+            config::BitcoinConnector::Esplora(url) => {
+                AnyConnector::Esplora(EsploraConnector::new(url.clone()))
+            }
+            config::BitcoinConnector::Electrum(url) => {
+                AnyConnector::Electrum(ElectrumConnector::new(url.clone())?)
+            }
+        };
 
         match validate_connection_to_network(&connector, *network).await {
             Ok(inner) => inner?,
             Err(e) => tracing::warn!("Could not validate Bitcoin node config: {}", e),
         }
 
+        let fallbacks = bitcoind
+            .fallback_node_urls
+            .iter()
+            .map(|url| BitcoindConnector::new(url.clone()).map(AnyConnector::Bitcoind))
+            .collect::<Result<Vec<_>>>()?;
+        let connector = MultiConnector::new(*network, connector).with_fallbacks(fallbacks);
+
         const BITCOIN_BLOCK_CACHE_CAPACITY: usize = 144;
 
-        btsieve::bitcoin::Cache::new(connector, BITCOIN_BLOCK_CACHE_CAPACITY)
+        let cache = btsieve::bitcoin::Cache::new(connector, BITCOIN_BLOCK_CACHE_CAPACITY);
+        match &block_store {
+            Some(block_store) => cache.with_block_store(block_store)?,
+            None => cache,
+        }
     };
 
     let ethereum_connector = {
         let config::Ethereum { geth, chain_id, .. } = &settings.ethereum;
-        let connector = Web3Connector::new(geth.node_url.clone());
+        let mut connector = Web3Connector::new(geth.node_url.clone());
+        if let Some(websocket_url) = &geth.websocket_url {
+            connector = connector.with_new_heads_subscription(websocket_url.clone());
+        }
 
         match validate_connection_to_network(&connector, *chain_id).await {
             Ok(inner) => inner?,
             Err(e) => tracing::warn!("Could not validate Ethereum node config: {}", e),
         }
 
+        let fallbacks = geth
+            .fallback_node_urls
+            .iter()
+            .cloned()
+            .map(Web3Connector::new);
+        let connector = MultiConnector::new(*chain_id, connector).with_fallbacks(fallbacks);
+
         const ETHEREUM_BLOCK_CACHE_CAPACITY: usize = 720;
         const ETHEREUM_RECEIPT_CACHE_CAPACITY: usize = 720;
 
-        btsieve::ethereum::Cache::new(
+        let cache = btsieve::ethereum::Cache::new(
             connector,
             ETHEREUM_BLOCK_CACHE_CAPACITY,
             ETHEREUM_RECEIPT_CACHE_CAPACITY,
-        )
+        );
+        match &block_store {
+            Some(block_store) => cache.with_block_store(block_store)?,
+            None => cache,
+        }
     };
 
-    let connectors = Connectors::new(bitcoin_connector, ethereum_connector);
+    let connectors = Connectors::new(bitcoin_connector, ethereum_connector).with_mempool_watching(
+        settings.bitcoin.watch_mempool,
+        settings.ethereum.watch_mempool,
+    );
+    tokio::spawn(connectors.clone().follow_chains());
+    if let Some(block_store) = block_store {
+        let storage = storage.clone();
+        tokio::spawn(block_store.prune_periodically(move || {
+            let storage = storage.clone();
+            async move {
+                storage
+                    .db
+                    .do_in_transaction(get_start_of_oldest_active_swap)
+                    .await
+            }
+        }));
+    }
 
     let swarm = Swarm::new(
         &settings,
@@ -193,6 +265,16 @@ async fn main() -> Result<()> {
     )
     .await?;
 
+    let wallet = if settings.wallet.enabled {
+        let wallet = Wallet::new(seed, &settings)
+            .await
+            .context("failed to initialize built-in wallet")?;
+
+        Some(wallet)
+    } else {
+        None
+    };
+
     let http_api_listener = bind_http_api_socket(&settings).await?;
     match respawn(storage.clone(), connectors.clone(), Handle::current()).await {
         Ok(()) => {}
@@ -206,6 +288,7 @@ async fn main() -> Result<()> {
     tokio::spawn(make_http_api_worker(
         settings,
         bitcoin_fees,
+        wallet,
         options.network.unwrap_or_default(),
         swarm.clone(),
         storage,
@@ -245,12 +328,13 @@ async fn bind_http_api_socket(settings: &Settings) -> Result<tokio::net::TcpList
 async fn make_http_api_worker(
     settings: Settings,
     bitcoin_fees: BitcoinFees,
+    wallet: Option<Wallet>,
     network: comit::Network,
     swarm: Swarm,
     storage: Storage,
     incoming_requests: tokio::net::TcpListener,
 ) {
-    let routes = http_api::create_routes(swarm, storage, &settings, bitcoin_fees, network);
+    let routes = http_api::create_routes(swarm, storage, &settings, bitcoin_fees, wallet, network);
 
     match incoming_requests.local_addr() {
         Ok(socket) => {
@@ -1,9 +1,7 @@
 mod comit_node;
 mod peer_tracker;
 mod swarm;
-mod transport;
 
 // Export comit network types while maintaining the module abstraction.
 pub use ::comit::{asset, ledger, network::*};
 pub use swarm::{Swarm, SwarmWorker};
-pub use transport::ComitTransport;
@@ -7,9 +7,15 @@ use crate::{
         commands, InsertableOrderSwap, InsertableSecretHash, Order, OrderHbitParams, Storage,
         SwapContext,
     },
+    updates::Update,
 };
 use comit::{
-    network::{orderbook, orderbook::Orderbook, setup_swap, setup_swap::SetupSwap},
+    network::{
+        orderbook,
+        orderbook::{DiscoveryConfig, Orderbook},
+        setup_swap,
+        setup_swap::SetupSwap,
+    },
     orderpool, LockProtocol, Never, OrderId, Quantity, Role, Side,
 };
 use futures::{channel::mpsc, SinkExt, TryFutureExt};
@@ -43,16 +49,17 @@ impl ComitNode {
         peer_id: PeerId,
         key: Keypair,
         matches_sender: mpsc::Sender<orderpool::Match>,
-    ) -> Self {
-        Self {
+        discovery: DiscoveryConfig,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
             setup_swap: Default::default(),
-            orderbook: Orderbook::new(peer_id, key),
+            orderbook: Orderbook::new(peer_id, key).with_discovery(discovery)?,
             peer_tracker: PeerTracker::default(),
             task_executor,
             storage,
             connectors,
             matches_sender,
-        }
+        })
     }
 }
 
@@ -61,6 +68,7 @@ impl ComitNode {
 pub struct SetupSwapContext {
     pub swap: LocalSwapId,
     pub order: OrderId,
+    pub their_order: OrderId,
     pub match_reference_point: OffsetDateTime,
 }
 
@@ -82,6 +90,10 @@ impl libp2p::swarm::NetworkBehaviourEventProcess<orderbook::BehaviourOutEvent> f
                     new_match.theirs,
                     new_match.peer
                 );
+                self.storage
+                    .updates
+                    .publish(Update::Match(new_match.clone()));
+
                 let mut sender = self.matches_sender.clone();
 
                 self.task_executor.spawn(async move {
@@ -101,14 +113,18 @@ impl libp2p::swarm::NetworkBehaviourEventProcess<setup_swap::BehaviourOutEvent<S
         match event {
             setup_swap::BehaviourOutEvent::ExecutableSwap(exec_swap) => {
                 use crate::storage::{InsertableHbit, InsertableHerc20, InsertableSwap};
-                use comit::network::setup_swap::SwapProtocol::*;
 
                 let SetupSwapContext {
                     swap: swap_id,
                     order: order_id,
+                    their_order,
                     match_reference_point: start_of_swap,
                 } = exec_swap.context;
-                let protocol = exec_swap.swap_protocol;
+                let hbit_side = hbit_side(exec_swap.swap_protocol);
+                let herc20_side = match hbit_side {
+                    Side::Alpha => Side::Beta,
+                    Side::Beta => Side::Alpha,
+                };
                 let role = exec_swap.our_role;
                 let secret_hash = exec_swap.herc20.secret_hash;
 
@@ -123,16 +139,13 @@ impl libp2p::swarm::NetworkBehaviourEventProcess<setup_swap::BehaviourOutEvent<S
                         hbit_params.network,
                         hbit_params.expiry.into(),
                         our_final_address,
-                        match (role, protocol) {
-                            (Role::Alice, HbitHerc20) => hbit_params.redeem_identity,
-                            (Role::Bob, HbitHerc20) => hbit_params.refund_identity,
-                            (Role::Alice, Herc20Hbit) => hbit_params.refund_identity,
-                            (Role::Bob, Herc20Hbit) => hbit_params.redeem_identity,
-                        },
-                        match protocol {
-                            HbitHerc20 => Side::Alpha,
-                            Herc20Hbit => Side::Beta,
+                        match (role, hbit_side) {
+                            (Role::Alice, Side::Alpha) => hbit_params.redeem_identity,
+                            (Role::Bob, Side::Alpha) => hbit_params.refund_identity,
+                            (Role::Alice, Side::Beta) => hbit_params.refund_identity,
+                            (Role::Bob, Side::Beta) => hbit_params.redeem_identity,
                         },
+                        hbit_side,
                     )
                 };
 
@@ -145,10 +158,7 @@ impl libp2p::swarm::NetworkBehaviourEventProcess<setup_swap::BehaviourOutEvent<S
                         herc20_params.expiry.into(),
                         herc20_params.redeem_identity,
                         herc20_params.refund_identity,
-                        match protocol {
-                            Herc20Hbit => Side::Alpha,
-                            HbitHerc20 => Side::Beta,
-                        },
+                        herc20_side,
                     )
                 };
                 let insertable_secret_hash =
@@ -168,7 +178,127 @@ impl libp2p::swarm::NetworkBehaviourEventProcess<setup_swap::BehaviourOutEvent<S
                                 insertable_secret_hash(swap_pk).insert(conn)?;
                                 insertable_herc20(swap_pk).insert(conn)?;
 
-                                commands::update_btc_dai_order_to_settling(conn, order_id)?;
+                                commands::update_btc_dai_order_to_settling(
+                                    conn,
+                                    order_id,
+                                    hbit_params.asset,
+                                )?;
+
+                                let order = Order::by_order_id(conn, order_id)?;
+                                InsertableOrderSwap::new(swap_pk, order.id).insert(conn)?;
+                                let order_hbit_params = OrderHbitParams::by_order(conn, &order)?;
+                                insertable_hbit(swap_pk, order_hbit_params.our_final_address)
+                                    .insert(conn)?;
+
+                                Ok(())
+                            })
+                            .await?;
+                        storage.updates.publish(Update::Order(order_id));
+
+                        spawn::spawn(connectors, storage, handle, SwapContext {
+                            id: swap_id,
+                            role,
+                            alpha: match hbit_side {
+                                Side::Alpha => LockProtocol::Hbit,
+                                Side::Beta => LockProtocol::Herc20,
+                            },
+                            beta: match hbit_side {
+                                Side::Alpha => LockProtocol::Herc20,
+                                Side::Beta => LockProtocol::Hbit,
+                            },
+                        })
+                        .await?;
+
+                        Ok(())
+                    }
+                };
+                self.task_executor
+                    .spawn(save_data_and_start_swap.map_err(|e: anyhow::Error| {
+                        tracing::error!("{}", e);
+                    }));
+                if let Err(e) = self.orderbook.orderpool_mut().notify_swap_setup_successful(
+                    order_id,
+                    their_order,
+                    Quantity::new(hbit_params.asset),
+                ) {
+                    tracing::error!(
+                        "failed to notify orderpool about successful swap setup: {:#}",
+                        e
+                    );
+                }
+            }
+            setup_swap::BehaviourOutEvent::ExecutableHethSwap(exec_swap) => {
+                use crate::storage::{InsertableHbit, InsertableHeth, InsertableSwap};
+
+                let SetupSwapContext {
+                    swap: swap_id,
+                    order: order_id,
+                    their_order,
+                    match_reference_point: start_of_swap,
+                } = exec_swap.context;
+                let hbit_side = hbit_side(exec_swap.swap_protocol);
+                let heth_side = match hbit_side {
+                    Side::Alpha => Side::Beta,
+                    Side::Beta => Side::Alpha,
+                };
+                let role = exec_swap.our_role;
+                let secret_hash = exec_swap.heth.secret_hash;
+
+                let insertable_swap =
+                    InsertableSwap::new(swap_id, exec_swap.peer_id, role, start_of_swap);
+
+                let hbit_params = exec_swap.hbit;
+                let insertable_hbit = move |swap_fk, our_final_address| {
+                    InsertableHbit::new(
+                        swap_fk,
+                        hbit_params.asset,
+                        hbit_params.network,
+                        hbit_params.expiry.into(),
+                        our_final_address,
+                        match (role, hbit_side) {
+                            (Role::Alice, Side::Alpha) => hbit_params.redeem_identity,
+                            (Role::Bob, Side::Alpha) => hbit_params.refund_identity,
+                            (Role::Alice, Side::Beta) => hbit_params.refund_identity,
+                            (Role::Bob, Side::Beta) => hbit_params.redeem_identity,
+                        },
+                        hbit_side,
+                    )
+                };
+
+                let heth_params = exec_swap.heth;
+                let insertable_heth = move |swap_fk| {
+                    InsertableHeth::new(
+                        swap_fk,
+                        heth_params.asset,
+                        heth_params.chain_id,
+                        heth_params.expiry.into(),
+                        heth_params.redeem_identity,
+                        heth_params.refund_identity,
+                        heth_side,
+                    )
+                };
+                let insertable_secret_hash =
+                    move |swap_fk| InsertableSecretHash::new(swap_fk, secret_hash);
+
+                let save_data_and_start_swap = {
+                    let connectors = self.connectors.clone();
+                    let storage = self.storage.clone();
+                    let handle = self.task_executor.clone();
+
+                    async move {
+                        storage
+                            .db
+                            .do_in_transaction(|conn| {
+                                let swap_pk = insertable_swap.insert(conn)?;
+
+                                insertable_secret_hash(swap_pk).insert(conn)?;
+                                insertable_heth(swap_pk).insert(conn)?;
+
+                                commands::update_btc_dai_order_to_settling(
+                                    conn,
+                                    order_id,
+                                    hbit_params.asset,
+                                )?;
 
                                 let order = Order::by_order_id(conn, order_id)?;
                                 InsertableOrderSwap::new(swap_pk, order.id).insert(conn)?;
@@ -179,16 +309,18 @@ impl libp2p::swarm::NetworkBehaviourEventProcess<setup_swap::BehaviourOutEvent<S
                                 Ok(())
                             })
                             .await?;
+                        storage.updates.publish(Update::Order(order_id));
+
                         spawn::spawn(connectors, storage, handle, SwapContext {
                             id: swap_id,
                             role,
-                            alpha: match protocol {
-                                Herc20Hbit => LockProtocol::Herc20,
-                                HbitHerc20 => LockProtocol::Hbit,
+                            alpha: match hbit_side {
+                                Side::Alpha => LockProtocol::Hbit,
+                                Side::Beta => LockProtocol::Heth,
                             },
-                            beta: match protocol {
-                                Herc20Hbit => LockProtocol::Hbit,
-                                HbitHerc20 => LockProtocol::Herc20,
+                            beta: match hbit_side {
+                                Side::Alpha => LockProtocol::Heth,
+                                Side::Beta => LockProtocol::Hbit,
                             },
                         })
                         .await?;
@@ -200,11 +332,11 @@ impl libp2p::swarm::NetworkBehaviourEventProcess<setup_swap::BehaviourOutEvent<S
                     .spawn(save_data_and_start_swap.map_err(|e: anyhow::Error| {
                         tracing::error!("{}", e);
                     }));
-                if let Err(e) = self
-                    .orderbook
-                    .orderpool_mut()
-                    .notify_swap_setup_successful(order_id, Quantity::new(hbit_params.asset))
-                {
+                if let Err(e) = self.orderbook.orderpool_mut().notify_swap_setup_successful(
+                    order_id,
+                    their_order,
+                    Quantity::new(hbit_params.asset),
+                ) {
                     tracing::error!(
                         "failed to notify orderpool about successful swap setup: {:#}",
                         e
@@ -215,6 +347,21 @@ impl libp2p::swarm::NetworkBehaviourEventProcess<setup_swap::BehaviourOutEvent<S
                 "Already have role dependent parameters from this peer: {}",
                 peer
             ),
+            setup_swap::BehaviourOutEvent::SetupFailed { context, .. } => self
+                .orderbook
+                .orderpool_mut()
+                .notify_swap_setup_failed(context.order, context.their_order),
         }
     }
 }
+
+/// The side of the hbit protocol in a swap, the other protocol is on the
+/// opposite side.
+fn hbit_side(swap_protocol: setup_swap::SwapProtocol) -> Side {
+    use comit::network::setup_swap::SwapProtocol::*;
+
+    match swap_protocol {
+        HbitHerc20 | HbitHeth => Side::Alpha,
+        Herc20Hbit | HethHbit => Side::Beta,
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{
-    asset,
+    asset::{self, ethereum::FromWei},
     config::Settings,
     connectors::Connectors,
     local_swap_id::LocalSwapId,
@@ -13,9 +13,12 @@ use crate::{
 };
 use anyhow::{Context as _, Result};
 use comit::{
-    network::setup_swap::{CommonParams, RoleDependentParams},
+    network::{
+        orderbook::DiscoveryConfig,
+        setup_swap::{CommonParams, EthereumAsset, RoleDependentParams},
+    },
     order::SwapProtocol,
-    orderpool, BtcDaiOrder, OrderId, Role, SecretHash, Side,
+    orderpool, BtcErc20Order, OrderId, Role, SecretHash, Side, TradingPair,
 };
 use futures::{channel::mpsc, stream::StreamExt};
 use libp2p::{
@@ -54,18 +57,30 @@ impl Swarm {
         let local_peer_id = PeerId::from(local_key_pair.public());
         tracing::info!("Starting with peer_id: {}", local_peer_id);
 
-        let transport = transport::build(local_key_pair.clone(), settings.network.listen.clone())?;
+        let transport = transport::build(local_key_pair.clone(), &settings.network.listen, None)?;
 
         let (sender, receiver) = mpsc::channel(1);
 
-        let behaviour = ComitNode::new(
+        let discovery = DiscoveryConfig {
+            rendezvous_points: settings.network.rendezvous_points.clone(),
+            rendezvous_server: settings.network.rendezvous_server,
+            mdns: settings.network.mdns,
+        };
+
+        let mut behaviour = ComitNode::new(
             task_executor.clone(),
             storage.clone(),
             connectors,
             local_peer_id.clone(),
             local_key_pair,
             sender,
-        );
+            discovery,
+        )?;
+
+        for token in settings.ethereum.tokens.all() {
+            let trading_pair = TradingPair::btc_against(&token.symbol)?;
+            behaviour.orderbook.find_makers(trading_pair);
+        }
 
         let mut swarm = SwarmBuilder::new(transport, behaviour, local_peer_id.clone())
             .executor(Box::new(TokioExecutor {
@@ -95,17 +110,18 @@ impl Swarm {
         })
     }
 
-    pub async fn publish_order(&self, order: BtcDaiOrder) {
+    pub async fn publish_order(&self, order: BtcErc20Order) {
         self.inner.lock().await.orderbook.publish(order);
     }
 
-    pub async fn btc_dai_market(&self) -> Vec<(PeerId, BtcDaiOrder)> {
+    pub async fn market(&self, trading_pair: &TradingPair) -> Vec<(PeerId, BtcErc20Order)> {
         self.inner
             .lock()
             .await
             .orderbook
             .orderpool()
             .all()
+            .filter(|(_, order)| &order.trading_pair == trading_pair)
             .map(|(maker, order)| (maker.clone(), order.clone()))
             .collect()
     }
@@ -216,29 +232,39 @@ async fn new_match_worker(
 ) {
     while let Some(new_match) = receiver.next().await {
         let order_id = new_match.ours;
+        let their_order = new_match.theirs;
         let peer = new_match.peer.clone();
         let match_reference_point = new_match.match_reference_point;
 
-        let (swap_id, common, role, protocol) =
-            match handle_new_match(&seed, &storage, new_match).await {
-                Ok(result) => result,
-                Err(e) => {
-                    tracing::warn!("failed to handle new match: {:?}", e);
-                    continue;
-                }
-            };
-
+        let result = handle_new_match(&seed, &storage, new_match).await;
         let mut guard = swarm.lock().await;
 
+        let (swap_id, common, role, protocol) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                tracing::warn!("failed to handle new match: {:?}", e);
+                guard
+                    .orderbook
+                    .orderpool_mut()
+                    .notify_swap_setup_failed(order_id, their_order);
+                continue;
+            }
+        };
+
         if let Err(e) = guard
             .setup_swap
             .send(&peer, role, common, protocol, SetupSwapContext {
                 swap: swap_id,
                 order: order_id,
+                their_order,
                 match_reference_point,
             })
         {
             tracing::warn!("failed to setup swap for order {}: {:#}", order_id, e);
+            guard
+                .orderbook
+                .orderpool_mut()
+                .notify_swap_setup_failed(order_id, their_order);
         }
     }
 }
@@ -258,45 +284,68 @@ async fn handle_new_match(
     let protocol = new_match.swap_protocol;
     let order_id = new_match.ours;
 
-    let (order_hbit, order_herc20) = storage
+    let (order_hbit, (chain_id, our_htlc_address, token_contract)) = storage
         .db
         .do_in_transaction(|conn| {
             use crate::storage::*;
 
             let order = Order::by_order_id(conn, order_id)?;
             let hbit_params = OrderHbitParams::by_order(conn, &order)?;
-            let herc20_params = OrderHerc20Params::by_order(conn, &order)?;
+            let ethereum_params = if protocol.is_heth() {
+                let heth_params = OrderHethParams::by_order(conn, &order)?;
+
+                (heth_params.chain_id, heth_params.our_htlc_address, None)
+            } else {
+                let herc20_params = OrderHerc20Params::by_order(conn, &order)?;
+
+                (
+                    herc20_params.chain_id,
+                    herc20_params.our_htlc_address,
+                    Some(herc20_params.token_contract),
+                )
+            };
 
-            Ok((hbit_params, herc20_params))
+            Ok((hbit_params, ethereum_params))
         })
         .await?;
 
     let our_role = protocol.role(new_match.our_position);
     let ethereum_absolute_expiry =
-        new_match.match_reference_point + protocol.herc20_expiry_offset();
+        new_match.match_reference_point + protocol.ethereum_expiry_offset();
     let bitcoin_absolute_expiry = new_match.match_reference_point + protocol.hbit_expiry_offset();
-    let erc20_quantity = new_match.quote();
     let hbit_quantity = new_match.quantity;
+    // The quote of BTC/ETH is in wei, which `Ether` covers entirely.
+    let ethereum_asset = match token_contract {
+        Some(token_contract) => EthereumAsset::Erc20(asset::Erc20 {
+            token_contract,
+            quantity: new_match.quote(),
+        }),
+        None => EthereumAsset::Ether(asset::Ether::from_wei(new_match.quote().to_u256())),
+    };
+
+    let (alice_order_id, bob_order_id) = match our_role {
+        Role::Alice => (new_match.ours, new_match.theirs),
+        Role::Bob => (new_match.theirs, new_match.ours),
+    };
 
     // TODO: Fix these!
     #[allow(clippy::cast_sign_loss)]
     #[allow(clippy::cast_possible_truncation)]
     let common_params = CommonParams {
-        erc20: asset::Erc20 {
-            token_contract: order_herc20.token_contract,
-            quantity: erc20_quantity,
-        },
+        alice_order_id,
+        bob_order_id,
+        ethereum: ethereum_asset,
         bitcoin: hbit_quantity.to_inner(),
         ethereum_absolute_expiry: ethereum_absolute_expiry.timestamp() as u32,
         bitcoin_absolute_expiry: bitcoin_absolute_expiry.timestamp() as u32,
-        ethereum_chain_id: u32::from(order_herc20.chain_id).into(),
+        ethereum_chain_id: u32::from(chain_id).into(),
         bitcoin_network: order_hbit.network,
     };
     let role_params = match our_role {
         Role::Alice => {
             let swap_seed = seed.derive_swap_seed(swap_id);
             RoleDependentParams::Alice(AliceParams {
-                ethereum_identity: order_herc20.our_htlc_address,
+                ethereum_identity: our_htlc_address,
                 bitcoin_identity: storage.derive_transient_identity(
                     swap_id,
                     our_role,
@@ -306,7 +355,7 @@ async fn handle_new_match(
             })
         }
         Role::Bob => RoleDependentParams::Bob(BobParams {
-            ethereum_identity: order_herc20.our_htlc_address,
+            ethereum_identity: our_htlc_address,
             bitcoin_identity: storage.derive_transient_identity(
                 swap_id,
                 our_role,
@@ -317,6 +366,8 @@ async fn handle_new_match(
     let setup_swap_protocol = match protocol {
         SwapProtocol::HbitHerc20 { .. } => setup_swap::SwapProtocol::HbitHerc20,
         SwapProtocol::Herc20Hbit { .. } => setup_swap::SwapProtocol::Herc20Hbit,
+        SwapProtocol::HbitHeth { .. } => setup_swap::SwapProtocol::HbitHeth,
+        SwapProtocol::HethHbit { .. } => setup_swap::SwapProtocol::HethHbit,
     };
 
     Ok((swap_id, common_params, role_params, setup_swap_protocol))
@@ -324,7 +375,107 @@ async fn handle_new_match(
 
 fn hbit_side(new_match: &orderpool::Match) -> Side {
     match new_match.swap_protocol {
-        SwapProtocol::HbitHerc20 { .. } => Side::Alpha,
-        SwapProtocol::Herc20Hbit { .. } => Side::Beta,
+        SwapProtocol::HbitHerc20 { .. } | SwapProtocol::HbitHeth { .. } => Side::Alpha,
+        SwapProtocol::Herc20Hbit { .. } | SwapProtocol::HethHbit { .. } => Side::Beta,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{
+        InsertableBtcDaiOrder, InsertableOrder, InsertableOrderHbitParams,
+        InsertableOrderHethParams,
+    };
+    use comit::{ethereum::ChainId, ledger, Position, Price, Quantity};
+    use std::str::FromStr;
+
+    #[tokio::test]
+    async fn btc_eth_match_sets_up_hbit_heth_swap_of_ether() {
+        let storage = Storage::test();
+        let seed = RootSeed::new_random(&mut rand::thread_rng()).unwrap();
+        let ethereum_address = crate::ethereum::Address::random();
+        let bitcoin_address =
+            ::bitcoin::Address::from_str("bcrt1qk60fmayw8xrtqd4ru2ut8kgv08wyqpdzqkj55h").unwrap();
+        let trading_pair = TradingPair::btc_eth();
+        // 0.5 BTC at 20 ETH per BTC.
+        let order = BtcErc20Order::sell(
+            Quantity::new(asset::Bitcoin::from_sat(50_000_000)),
+            Price::from_wei_per_btc(
+                asset::Erc20Quantity::from_wei_dec_str("20000000000000000000").unwrap(),
+            ),
+            SwapProtocol::for_trading_pair(
+                &trading_pair,
+                Role::Alice,
+                Position::Sell,
+                comit::Network::Dev,
+            ),
+        )
+        .with_trading_pair(trading_pair);
+
+        let insertable_order = InsertableOrder::new(
+            order.id,
+            order.position,
+            order.created_at,
+            order.trading_pair.clone(),
+            18,
+        );
+        let quantity = order.quantity.to_inner();
+        let price = order.price.wei_per_btc();
+        let hbit_expiry_offset = order.swap_protocol.hbit_expiry_offset().whole_seconds();
+        let heth_expiry_offset = order.swap_protocol.ethereum_expiry_offset().whole_seconds();
+        storage
+            .db
+            .do_in_transaction(move |conn| {
+                let order_fk = insertable_order.insert(conn)?;
+                InsertableBtcDaiOrder::new(order_fk, quantity, price).insert(conn)?;
+                InsertableOrderHbitParams::new(
+                    order_fk,
+                    ledger::Bitcoin::Regtest,
+                    bitcoin_address,
+                    hbit_expiry_offset,
+                    Side::Alpha,
+                )
+                .insert(conn)?;
+                InsertableOrderHethParams::new(
+                    order_fk,
+                    ChainId::GETH_DEV,
+                    ethereum_address,
+                    heth_expiry_offset,
+                    Side::Beta,
+                )
+                .insert(conn)?;
+
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        let new_match = orderpool::Match {
+            peer: PeerId::random(),
+            price: order.price.clone(),
+            quantity: order.quantity,
+            ours: order.id,
+            theirs: OrderId::random(),
+            our_position: order.position,
+            swap_protocol: order.swap_protocol,
+            match_reference_point: order.created_at,
+        };
+
+        let (_, common, role_params, protocol) =
+            handle_new_match(&seed, &storage, new_match).await.unwrap();
+
+        assert_eq!(protocol, setup_swap::SwapProtocol::HbitHeth);
+        assert_eq!(
+            common.ethereum,
+            EthereumAsset::Ether(asset::Ether::from_wei_dec_str("10000000000000000000").unwrap())
+        );
+        assert_eq!(common.ethereum_chain_id, ChainId::GETH_DEV);
+        match role_params {
+            RoleDependentParams::Alice(params) => {
+                assert_eq!(params.ethereum_identity, ethereum_address)
+            }
+            RoleDependentParams::Bob(_) => panic!("the seller of bitcoin is Alice in hbit-heth"),
+        }
     }
 }
@@ -8,6 +8,7 @@ use crate::{
     connectors::Connectors,
     spawn::spawn,
     storage::{commands, queries::get_active_swap_contexts, Storage},
+    updates::Update,
 };
 use tokio::runtime::Handle;
 
@@ -29,12 +30,16 @@ pub async fn respawn(
             continue;
         };
 
-        if let Err(e) = storage
+        match storage
             .db
             .do_in_transaction(|conn| commands::update_order_of_swap_to_settling(conn, id))
             .await
         {
-            tracing::warn!(swap_id = %id, "failed to update order state for swap {:#}", e);
+            Ok(Some(order_id)) => storage.updates.publish(Update::Order(order_id)),
+            Ok(None) => {}
+            Err(e) => {
+                tracing::warn!(swap_id = %id, "failed to update order state for swap {:#}", e)
+            }
         }
     }
 
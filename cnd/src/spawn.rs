@@ -1,12 +1,18 @@
 use crate::{
     connectors::Connectors,
-    herc20,
+    herc20, heth,
+    http_api::ActionName,
     local_swap_id::LocalSwapId,
     storage::{commands, Load, SwapContext},
+    updates::Update,
     Role, Side, Storage,
 };
 use anyhow::Result;
-use comit::swap::{hbit, Action};
+use comit::{
+    expiries::{CurrentTime, Expiries},
+    swap::{alice_verdict, bob_verdict, hbit, Action, Verdict},
+    Network, OrderId,
+};
 use diesel::SqliteConnection;
 use futures::prelude::*;
 use time::OffsetDateTime;
@@ -50,32 +56,33 @@ pub async fn spawn(
 }
 
 async fn handle_swap_result(swap_result: Result<()>, storage: Storage, swap_id: LocalSwapId) {
-    let db_update: Box<dyn Fn(&SqliteConnection) -> Result<()> + Send> = match swap_result {
+    let db_update: Box<dyn Fn(&SqliteConnection) -> Result<OrderId> + Send> = match swap_result {
         Ok(()) => {
             tracing::info!(swap = %swap_id, "swap completed");
 
             Box::new(move |conn| {
-                commands::update_order_of_swap_to_closed(conn, swap_id)?;
+                let order_id = commands::update_order_of_swap_to_closed(conn, swap_id)?;
                 commands::mark_swap_as_completed(conn, swap_id, OffsetDateTime::now_utc())?;
 
-                Ok(())
+                Ok(order_id)
             })
         }
         Err(e) => {
             tracing::error!(swap = %swap_id, "failed to complete swap: {:#}", e);
 
             Box::new(move |conn| {
-                commands::update_order_of_swap_to_failed(conn, swap_id)?;
+                let order_id = commands::update_order_of_swap_to_failed(conn, swap_id)?;
                 // we don't mark a swap as completed in case of failure so that a
                 // restart of the node will respawn the swap
 
-                Ok(())
+                Ok(order_id)
             })
         }
     };
 
-    if let Err(e) = storage.db.do_in_transaction(db_update).await {
-        tracing::warn!("failed to update db state: {:#}", e)
+    match storage.db.do_in_transaction(db_update).await {
+        Ok(order_id) => storage.updates.publish(Update::Order(order_id)),
+        Err(e) => tracing::warn!("failed to update db state: {:#}", e),
     }
 }
 
@@ -88,14 +95,24 @@ impl Swap<hbit::Params, herc20::Params> {
     ) -> Result<()> {
         let hbit_facade = crate::hbit::Facade {
             connector: connectors.bitcoin(),
+            watch_mempool: connectors.watch_bitcoin_mempool(),
             swap_id: id,
             storage: storage.clone(),
         };
         let herc20_facade = crate::herc20::Facade {
             connector: connectors.ethereum(),
+            watch_mempool: connectors.watch_ethereum_mempool(),
             swap_id: id,
             storage: storage.clone(),
         };
+        let expiries = Expiries::new_hbit_herc20_with_expiries(
+            Network::from(self.alpha.shared.network),
+            self.start_of_swap.into(),
+            self.alpha.shared.expiry,
+            self.beta.expiry,
+            hbit_facade.clone(),
+            herc20_facade.clone(),
+        );
 
         match self.role {
             Role::Alice => {
@@ -103,11 +120,14 @@ impl Swap<hbit::Params, herc20::Params> {
                     comit::swap::hbit_herc20_alice(
                         hbit_facade,
                         herc20_facade,
+                        crate::SECP.clone(),
                         self.alpha,
                         self.beta,
                         storage.seed.derive_swap_seed(id).derive_secret(),
                         self.start_of_swap,
                     ),
+                    expiries,
+                    self.role,
                     storage,
                     id,
                 )
@@ -123,6 +143,8 @@ impl Swap<hbit::Params, herc20::Params> {
                         self.beta,
                         self.start_of_swap,
                     ),
+                    expiries,
+                    self.role,
                     storage,
                     id,
                 )
@@ -141,14 +163,24 @@ impl Swap<herc20::Params, hbit::Params> {
     ) -> Result<()> {
         let hbit_facade = crate::hbit::Facade {
             connector: connectors.bitcoin(),
+            watch_mempool: connectors.watch_bitcoin_mempool(),
             swap_id: id,
             storage: storage.clone(),
         };
         let herc20_facade = crate::herc20::Facade {
             connector: connectors.ethereum(),
+            watch_mempool: connectors.watch_ethereum_mempool(),
             swap_id: id,
             storage: storage.clone(),
         };
+        let expiries = Expiries::new_herc20_hbit_with_expiries(
+            Network::from(self.beta.shared.network),
+            self.start_of_swap.into(),
+            self.alpha.expiry,
+            self.beta.shared.expiry,
+            herc20_facade.clone(),
+            hbit_facade.clone(),
+        );
 
         match self.role {
             Role::Alice => {
@@ -162,6 +194,8 @@ impl Swap<herc20::Params, hbit::Params> {
                         storage.seed.derive_swap_seed(id).derive_secret(),
                         self.start_of_swap,
                     ),
+                    expiries,
+                    self.role,
                     storage,
                     id,
                 )
@@ -172,10 +206,81 @@ impl Swap<herc20::Params, hbit::Params> {
                     comit::swap::herc20_hbit_bob(
                         herc20_facade,
                         hbit_facade,
+                        crate::SECP.clone(),
+                        self.alpha,
+                        self.beta,
+                        self.start_of_swap,
+                    ),
+                    expiries,
+                    self.role,
+                    storage,
+                    id,
+                )
+                .await
+            }
+        }
+    }
+}
+
+impl Swap<hbit::Params, heth::Params> {
+    async fn execute(
+        self,
+        id: LocalSwapId,
+        connectors: Connectors,
+        storage: Storage,
+    ) -> Result<()> {
+        let hbit_facade = crate::hbit::Facade {
+            connector: connectors.bitcoin(),
+            watch_mempool: connectors.watch_bitcoin_mempool(),
+            swap_id: id,
+            storage: storage.clone(),
+        };
+        let heth_facade = crate::heth::Facade {
+            connector: connectors.ethereum(),
+            watch_mempool: connectors.watch_ethereum_mempool(),
+            swap_id: id,
+            storage: storage.clone(),
+        };
+        let expiries = Expiries::new_hbit_heth_with_expiries(
+            Network::from(self.alpha.shared.network),
+            self.start_of_swap.into(),
+            self.alpha.shared.expiry,
+            self.beta.expiry,
+            hbit_facade.clone(),
+            heth_facade.clone(),
+        );
+
+        match self.role {
+            Role::Alice => {
+                drive(
+                    comit::swap::hbit_heth_alice(
+                        hbit_facade,
+                        heth_facade,
+                        crate::SECP.clone(),
                         self.alpha,
                         self.beta,
+                        storage.seed.derive_swap_seed(id).derive_secret(),
                         self.start_of_swap,
                     ),
+                    expiries,
+                    self.role,
+                    storage,
+                    id,
+                )
+                .await
+            }
+            Role::Bob => {
+                drive(
+                    comit::swap::hbit_heth_bob(
+                        hbit_facade,
+                        heth_facade,
+                        crate::SECP.clone(),
+                        self.alpha,
+                        self.beta,
+                        self.start_of_swap,
+                    ),
+                    expiries,
+                    self.role,
                     storage,
                     id,
                 )
@@ -185,17 +290,167 @@ impl Swap<herc20::Params, hbit::Params> {
     }
 }
 
-async fn drive<E>(
+impl Swap<heth::Params, hbit::Params> {
+    async fn execute(
+        self,
+        id: LocalSwapId,
+        connectors: Connectors,
+        storage: Storage,
+    ) -> Result<()> {
+        let hbit_facade = crate::hbit::Facade {
+            connector: connectors.bitcoin(),
+            watch_mempool: connectors.watch_bitcoin_mempool(),
+            swap_id: id,
+            storage: storage.clone(),
+        };
+        let heth_facade = crate::heth::Facade {
+            connector: connectors.ethereum(),
+            watch_mempool: connectors.watch_ethereum_mempool(),
+            swap_id: id,
+            storage: storage.clone(),
+        };
+        let expiries = Expiries::new_heth_hbit_with_expiries(
+            Network::from(self.beta.shared.network),
+            self.start_of_swap.into(),
+            self.alpha.expiry,
+            self.beta.shared.expiry,
+            heth_facade.clone(),
+            hbit_facade.clone(),
+        );
+
+        match self.role {
+            Role::Alice => {
+                drive(
+                    comit::swap::heth_hbit_alice(
+                        heth_facade,
+                        hbit_facade,
+                        crate::SECP.clone(),
+                        self.alpha,
+                        self.beta,
+                        storage.seed.derive_swap_seed(id).derive_secret(),
+                        self.start_of_swap,
+                    ),
+                    expiries,
+                    self.role,
+                    storage,
+                    id,
+                )
+                .await
+            }
+            Role::Bob => {
+                drive(
+                    comit::swap::heth_hbit_bob(
+                        heth_facade,
+                        hbit_facade,
+                        crate::SECP.clone(),
+                        self.alpha,
+                        self.beta,
+                        self.start_of_swap,
+                    ),
+                    expiries,
+                    self.role,
+                    storage,
+                    id,
+                )
+                .await
+            }
+        }
+    }
+}
+
+async fn drive<A, B, E>(
     mut swap: impl Stream<Item = Result<Action, E>> + Unpin,
+    expiries: Expiries<A, B>,
+    role: Role,
     storage: Storage,
     swap_id: LocalSwapId,
 ) -> Result<()>
 where
+    A: CurrentTime,
+    B: CurrentTime,
     E: std::error::Error + Send + Sync + 'static,
 {
     while let Some(action) = swap.try_next().await? {
-        storage.next_action.lock().await.insert(swap_id, action);
+        // A respawned swap yields the actions executed before the restart again.
+        // Neither these nor the action we offered before are to be executed.
+        if is_recorded(&storage, swap_id, &action).await {
+            if storage.next_action.lock().await.remove(&swap_id).is_some() {
+                storage.updates.publish(Update::NextAction(swap_id, None));
+            }
+            continue;
+        }
+
+        let verdict = match role {
+            Role::Alice => alice_verdict(&expiries, &action).await,
+            Role::Bob => bob_verdict(&expiries, &action).await,
+        };
+
+        match verdict {
+            Verdict::Execute => {
+                let name = ActionName::from(action.clone());
+
+                storage.next_action.lock().await.insert(swap_id, action);
+                storage
+                    .updates
+                    .publish(Update::NextAction(swap_id, Some(name)));
+            }
+            Verdict::WaitToRefund => {
+                tracing::warn!(
+                    swap = %swap_id,
+                    "not enough time left to safely complete the swap, waiting to refund"
+                );
+
+                storage.next_action.lock().await.remove(&swap_id);
+                storage.updates.publish(Update::NextAction(swap_id, None));
+            }
+            Verdict::Abort => {
+                storage.next_action.lock().await.remove(&swap_id);
+                storage.updates.publish(Update::NextAction(swap_id, None));
+
+                anyhow::bail!("not enough time left to safely complete the swap, aborting")
+            }
+        }
     }
 
     Ok(())
 }
+
+/// Whether the event an action results in has already been recorded.
+async fn is_recorded(storage: &Storage, swap_id: LocalSwapId, action: &Action) -> bool {
+    match action {
+        Action::Herc20Deploy(_) => storage
+            .load_event::<herc20::Deployed>(swap_id)
+            .await
+            .is_some(),
+        Action::Herc20Fund(_) => storage
+            .load_event::<herc20::Funded>(swap_id)
+            .await
+            .is_some(),
+        Action::Herc20Redeem(..) => storage
+            .load_event::<herc20::Redeemed>(swap_id)
+            .await
+            .is_some(),
+        Action::Herc20Refund(_) => storage
+            .load_event::<herc20::Refunded>(swap_id)
+            .await
+            .is_some(),
+        Action::HethFund(_) => storage.load_event::<heth::Funded>(swap_id).await.is_some(),
+        Action::HethRedeem(..) => storage
+            .load_event::<heth::Redeemed>(swap_id)
+            .await
+            .is_some(),
+        Action::HethRefund(_) => storage
+            .load_event::<heth::Refunded>(swap_id)
+            .await
+            .is_some(),
+        Action::HbitFund(_) => storage.load_event::<hbit::Funded>(swap_id).await.is_some(),
+        Action::HbitRedeem(..) => storage
+            .load_event::<hbit::Redeemed>(swap_id)
+            .await
+            .is_some(),
+        Action::HbitRefund(_) => storage
+            .load_event::<hbit::Refunded>(swap_id)
+            .await
+            .is_some(),
+    }
+}
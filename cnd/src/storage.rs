@@ -1,9 +1,14 @@
 mod db;
 mod http_api;
 mod seed;
+mod swap_events;
 
 use crate::{
-    asset, hbit, herc20, identity, spawn, storage::db::queries::get_swap_context_by_id,
+    asset, hbit, herc20, heth,
+    http_api::SwapEvent,
+    identity, spawn,
+    storage::db::queries::get_swap_context_by_id,
+    updates::{Update, Updates},
     LocalSwapId, Role, Side,
 };
 use async_trait::async_trait;
@@ -20,6 +25,12 @@ pub trait Load<T>: Send + Sync + 'static {
     async fn load(&self, swap_id: LocalSwapId) -> anyhow::Result<T>;
 }
 
+/// Save data for a particular swap to the storage layer.
+#[async_trait]
+pub trait Save<T>: Send + Sync + 'static {
+    async fn save(&self, data: T, swap_id: LocalSwapId) -> anyhow::Result<()>;
+}
+
 /// A facade for the storage layer.
 #[derive(Debug, Clone)]
 pub struct Storage {
@@ -27,8 +38,7 @@ pub struct Storage {
     pub seed: RootSeed,
 
     pub next_action: Arc<Mutex<HashMap<LocalSwapId, Action>>>,
-    pub hbit_events: Arc<Mutex<HashMap<LocalSwapId, hbit::Events>>>,
-    pub herc20_events: Arc<Mutex<HashMap<LocalSwapId, herc20::Events>>>,
+    pub updates: Updates,
 }
 
 impl Storage {
@@ -37,8 +47,7 @@ impl Storage {
             db,
             seed,
             next_action: Arc::new(Default::default()),
-            hbit_events: Arc::new(Default::default()),
-            herc20_events: Arc::new(Default::default()),
+            updates: Updates::default(),
         }
     }
 
@@ -63,6 +72,43 @@ impl Storage {
     }
 }
 
+impl Storage {
+    /// Load an event of a swap if it has been recorded already.
+    ///
+    /// A failure to load is not fatal, the caller falls back to watching the
+    /// ledger.
+    pub async fn load_event<E>(&self, swap_id: LocalSwapId) -> Option<E>
+    where
+        Self: Load<Option<E>>,
+    {
+        match self.load(swap_id).await {
+            Ok(event) => event,
+            Err(e) => {
+                tracing::warn!("failed to load swap event from the database: {:#}", e);
+                None
+            }
+        }
+    }
+
+    /// Record an event of a swap so it does not need to be watched for again.
+    ///
+    /// Subscribers to the updates are notified about the event regardless of
+    /// whether saving it succeeded.
+    pub async fn save_event<E>(&self, event: E, swap_id: LocalSwapId)
+    where
+        Self: Save<E>,
+        E: Copy,
+        SwapEvent: From<E>,
+    {
+        if let Err(e) = self.save(event, swap_id).await {
+            tracing::warn!("failed to save swap event to the database: {:#}", e);
+        }
+
+        self.updates
+            .publish(Update::SwapEvent(swap_id, SwapEvent::from(event)));
+    }
+}
+
 #[cfg(test)]
 #[allow(dead_code)]
 impl Storage {
@@ -137,6 +183,27 @@ impl IntoParams for herc20::Params {
     }
 }
 
+impl IntoParams for heth::Params {
+    type ProtocolTable = Heth;
+
+    fn into_params(
+        heth: Self::ProtocolTable,
+        _: LocalSwapId,
+        _: RootSeed,
+        _: Role,
+        secret_hash: comit::SecretHash,
+    ) -> anyhow::Result<heth::Params> {
+        Ok(heth::Params {
+            asset: heth.amount,
+            redeem_identity: heth.redeem_identity,
+            refund_identity: heth.refund_identity,
+            expiry: heth.expiry,
+            secret_hash,
+            chain_id: heth.chain_id,
+        })
+    }
+}
+
 impl IntoParams for comit::swap::hbit::Params {
     type ProtocolTable = Hbit;
 
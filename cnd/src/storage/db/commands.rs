@@ -16,24 +16,29 @@ use comit::{OrderId, Quantity};
 use diesel::prelude::*;
 use time::OffsetDateTime;
 
-/// Move the amount that is settling from open to settling.
+/// Move the quantity of a newly set up swap from open to settling.
 ///
-/// Whilst we don't have partial order matching, this simply means updating
-/// `settling` to the quantity of the order and all other state fields to `0`.
-///
-/// Once we implement partial order matching, this will need to get more
-/// sophisticated.
-pub fn update_btc_dai_order_to_settling(conn: &SqliteConnection, order_id: OrderId) -> Result<()> {
+/// An order may be filled partially by several swaps, hence only the quantity
+/// of the swap is moved, the rest of the order stays open.
+pub fn update_btc_dai_order_to_settling(
+    conn: &SqliteConnection,
+    order_id: OrderId,
+    quantity: asset::Bitcoin,
+) -> Result<()> {
     let order = Order::by_order_id(conn, order_id)?;
     let btc_dai_order = BtcDaiOrder::by_order(conn, &order)?;
 
+    let open = btc_dai_order
+        .open
+        .to_inner()
+        .checked_sub(quantity)
+        .with_context(|| format!("order {} has less than {} open", order_id, quantity))?;
+    let settling = btc_dai_order.settling.to_inner() + quantity;
+
     let affected_rows = diesel::update(&btc_dai_order)
         .set((
-            btc_dai_orders::settling.eq(Text::<Satoshis>(btc_dai_order.quantity.to_inner().into())),
-            btc_dai_orders::open.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::closed.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::failed.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::cancelled.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
+            btc_dai_orders::open.eq(Text::<Satoshis>(open.into())),
+            btc_dai_orders::settling.eq(Text::<Satoshis>(settling.into())),
         ))
         .execute(conn)?;
 
@@ -44,6 +49,10 @@ pub fn update_btc_dai_order_to_settling(conn: &SqliteConnection, order_id: Order
     Ok(())
 }
 
+/// Cancel whatever is still open of an order.
+///
+/// Quantities which are already settling are not affected, the corresponding
+/// swaps will run to completion.
 pub fn update_btc_dai_order_to_cancelled(conn: &SqliteConnection, order_id: OrderId) -> Result<()> {
     let order = Order::by_order_id(conn, order_id)?;
     let btc_dai_order = BtcDaiOrder::by_order(conn, &order)?;
@@ -52,14 +61,12 @@ pub fn update_btc_dai_order_to_cancelled(conn: &SqliteConnection, order_id: Orde
         anyhow::bail!(NotOpen(order_id))
     }
 
+    let cancelled = btc_dai_order.cancelled.to_inner() + btc_dai_order.open.to_inner();
+
     let affected_rows = diesel::update(&btc_dai_order)
         .set((
-            btc_dai_orders::cancelled
-                .eq(Text::<Satoshis>(btc_dai_order.quantity.to_inner().into())),
-            btc_dai_orders::settling.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
+            btc_dai_orders::cancelled.eq(Text::<Satoshis>(cancelled.into())),
             btc_dai_orders::open.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::closed.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::failed.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
         ))
         .execute(conn)?;
 
@@ -70,26 +77,24 @@ pub fn update_btc_dai_order_to_cancelled(conn: &SqliteConnection, order_id: Orde
     Ok(())
 }
 
-pub fn update_order_of_swap_to_closed(conn: &SqliteConnection, swap_id: LocalSwapId) -> Result<()> {
-    let (order, btc_dai_order) = orders::table
-        .inner_join(order_swaps::table.inner_join(swaps::table))
-        .inner_join(btc_dai_orders::table)
-        .filter(swaps::local_swap_id.eq(Text(swap_id)))
-        .select((orders::all_columns, btc_dai_orders::all_columns))
-        .first::<(Order, BtcDaiOrder)>(conn)
-        .with_context(|| NoOrderForSwap(swap_id))?;
+/// Move the quantity of a completed swap from settling to closed.
+pub fn update_order_of_swap_to_closed(
+    conn: &SqliteConnection,
+    swap_id: LocalSwapId,
+) -> Result<OrderId> {
+    let (order, btc_dai_order, quantity) = order_of_swap(conn, swap_id)?;
 
-    if btc_dai_order.settling == Quantity::new(asset::Bitcoin::ZERO) {
-        anyhow::bail!(NotSettling(order.order_id))
-    }
+    let settling = btc_dai_order
+        .settling
+        .to_inner()
+        .checked_sub(quantity)
+        .ok_or(NotSettling(order.order_id))?;
+    let closed = btc_dai_order.closed.to_inner() + quantity;
 
     let affected_rows = diesel::update(&btc_dai_order)
         .set((
-            btc_dai_orders::closed.eq(Text::<Satoshis>(btc_dai_order.quantity.to_inner().into())),
-            btc_dai_orders::settling.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::open.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::cancelled.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::failed.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
+            btc_dai_orders::settling.eq(Text::<Satoshis>(settling.into())),
+            btc_dai_orders::closed.eq(Text::<Satoshis>(closed.into())),
         ))
         .execute(conn)?;
 
@@ -97,29 +102,27 @@ pub fn update_order_of_swap_to_closed(conn: &SqliteConnection, swap_id: LocalSwa
         anyhow::bail!("failed to mark order {} as closed", order.order_id)
     }
 
-    Ok(())
+    Ok(order.order_id)
 }
 
-pub fn update_order_of_swap_to_failed(conn: &SqliteConnection, swap_id: LocalSwapId) -> Result<()> {
-    let (order, btc_dai_order) = orders::table
-        .inner_join(order_swaps::table.inner_join(swaps::table))
-        .inner_join(btc_dai_orders::table)
-        .filter(swaps::local_swap_id.eq(Text(swap_id)))
-        .select((orders::all_columns, btc_dai_orders::all_columns))
-        .first::<(Order, BtcDaiOrder)>(conn)
-        .with_context(|| NoOrderForSwap(swap_id))?;
+/// Move the quantity of a failed swap from settling to failed.
+pub fn update_order_of_swap_to_failed(
+    conn: &SqliteConnection,
+    swap_id: LocalSwapId,
+) -> Result<OrderId> {
+    let (order, btc_dai_order, quantity) = order_of_swap(conn, swap_id)?;
 
-    if btc_dai_order.settling == Quantity::new(asset::Bitcoin::ZERO) {
-        anyhow::bail!(NotSettling(order.order_id))
-    }
+    let settling = btc_dai_order
+        .settling
+        .to_inner()
+        .checked_sub(quantity)
+        .ok_or(NotSettling(order.order_id))?;
+    let failed = btc_dai_order.failed.to_inner() + quantity;
 
     let affected_rows = diesel::update(&btc_dai_order)
         .set((
-            btc_dai_orders::failed.eq(Text::<Satoshis>(btc_dai_order.quantity.to_inner().into())),
-            btc_dai_orders::settling.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::open.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::cancelled.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::closed.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
+            btc_dai_orders::settling.eq(Text::<Satoshis>(settling.into())),
+            btc_dai_orders::failed.eq(Text::<Satoshis>(failed.into())),
         ))
         .execute(conn)?;
 
@@ -127,28 +130,30 @@ pub fn update_order_of_swap_to_failed(conn: &SqliteConnection, swap_id: LocalSwa
         anyhow::bail!("failed to mark order {} as failed", order.order_id)
     }
 
-    Ok(())
+    Ok(order.order_id)
 }
 
+/// Move the quantity of a respawned swap back to settling.
+///
+/// Only swaps that previously failed need to be moved, the quantity of swaps
+/// that were interrupted by a shutdown is still accounted for as settling.
+/// Returns the id of the order if its state changed.
 pub fn update_order_of_swap_to_settling(
     conn: &SqliteConnection,
     swap_id: LocalSwapId,
-) -> Result<()> {
-    let (order, btc_dai_order) = orders::table
-        .inner_join(order_swaps::table.inner_join(swaps::table))
-        .inner_join(btc_dai_orders::table)
-        .filter(swaps::local_swap_id.eq(Text(swap_id)))
-        .select((orders::all_columns, btc_dai_orders::all_columns))
-        .first::<(Order, BtcDaiOrder)>(conn)
-        .with_context(|| NoOrderForSwap(swap_id))?;
+) -> Result<Option<OrderId>> {
+    let (order, btc_dai_order, quantity) = order_of_swap(conn, swap_id)?;
+
+    let failed = match btc_dai_order.failed.to_inner().checked_sub(quantity) {
+        Some(failed) => failed,
+        None => return Ok(None),
+    };
+    let settling = btc_dai_order.settling.to_inner() + quantity;
 
     let affected_rows = diesel::update(&btc_dai_order)
         .set((
-            btc_dai_orders::settling.eq(Text::<Satoshis>(btc_dai_order.quantity.to_inner().into())),
-            btc_dai_orders::open.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::closed.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::failed.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
-            btc_dai_orders::cancelled.eq(Text::<Satoshis>(asset::Bitcoin::ZERO.into())),
+            btc_dai_orders::failed.eq(Text::<Satoshis>(failed.into())),
+            btc_dai_orders::settling.eq(Text::<Satoshis>(settling.into())),
         ))
         .execute(conn)?;
 
@@ -156,7 +161,31 @@ pub fn update_order_of_swap_to_settling(
         anyhow::bail!("failed to mark order {} as settling", order.order_id)
     }
 
-    Ok(())
+    Ok(Some(order.order_id))
+}
+
+/// Load the order a swap was set up from, together with the quantity the swap
+/// fills.
+fn order_of_swap(
+    conn: &SqliteConnection,
+    swap_id: LocalSwapId,
+) -> Result<(Order, BtcDaiOrder, asset::Bitcoin)> {
+    let (order, btc_dai_order) = orders::table
+        .inner_join(order_swaps::table.inner_join(swaps::table))
+        .inner_join(btc_dai_orders::table)
+        .filter(swaps::local_swap_id.eq(Text(swap_id)))
+        .select((orders::all_columns, btc_dai_orders::all_columns))
+        .first::<(Order, BtcDaiOrder)>(conn)
+        .with_context(|| NoOrderForSwap(swap_id))?;
+
+    let quantity = swaps::table
+        .inner_join(hbits::table)
+        .filter(swaps::local_swap_id.eq(Text(swap_id)))
+        .select(hbits::amount)
+        .first::<Text<Satoshis>>(conn)
+        .with_context(|| format!("no hbit parameters found for swap {}", swap_id))?;
+
+    Ok((order, btc_dai_order, quantity.into()))
 }
 
 pub fn mark_swap_as_completed(
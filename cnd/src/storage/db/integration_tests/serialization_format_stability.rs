@@ -5,10 +5,10 @@
 
 use crate::{
     identity,
-    storage::db::wrapper_types::{Erc20Amount, Ether, Satoshis},
+    storage::db::wrapper_types::{Erc20Amount, Ether, HexSecret, Satoshis},
     LockProtocol,
 };
-use comit::{ledger, Position, SecretHash};
+use comit::{ethereum, ledger, Position, SecretHash};
 use std::{fmt, str::FromStr};
 
 #[test]
@@ -52,6 +52,25 @@ fn secrethash() {
     );
 }
 
+#[test]
+fn secret() {
+    roundtrip_test::<HexSecret>("68d627971643a6f97f27c58957826fcba853ec2077fd10ec6b93d8e61deb4c66");
+}
+
+#[test]
+fn bitcoin_txid() {
+    roundtrip_test::<bitcoin::Txid>(
+        "a98ab3e0e9ab4ab5e77b6a40f0e4c4d6e0e9e88a50e8bbab27c6b7d2e4d8a2d1",
+    );
+}
+
+#[test]
+fn ethereum_transaction_hash() {
+    roundtrip_test::<ethereum::Hash>(
+        "0x3ae3b6ffb04204f52dee42000e8b971c0f7c2b4aa8dd4455e41a30ee4b31e8a9",
+    );
+}
+
 #[test]
 fn protocol() {
     roundtrip_test::<LockProtocol>("hbit");
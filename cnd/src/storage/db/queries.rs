@@ -7,13 +7,13 @@ use crate::{
     asset,
     storage::{
         db::{schema::*, wrapper_types::Satoshis},
-        BtcDaiOrder, NoSwapExists, Order, OrderHbitParams, OrderHerc20Params, ParamsTuple,
-        SwapContext, Text,
+        BtcDaiOrder, HethParamsTuple, NoSwapExists, Order, OrderHbitParams, OrderHerc20Params,
+        OrderHethParams, ParamsTuple, SwapContext, Text,
     },
     LocalSwapId,
 };
 use anyhow::{Context, Result};
-use comit::order::SwapProtocol;
+use comit::{order::SwapProtocol, TradingPair};
 use diesel::{prelude::*, SqliteConnection};
 use std::convert::TryFrom;
 use time::OffsetDateTime;
@@ -28,6 +28,16 @@ pub fn get_swap_context_by_id(conn: &SqliteConnection, id: LocalSwapId) -> Resul
     Ok(context)
 }
 
+/// Get the foreign key of a swap, required to insert rows belonging to it.
+pub fn get_swap_fk(conn: &SqliteConnection, id: LocalSwapId) -> Result<i32> {
+    let swap_fk = swap_id_fk!(id)
+        .first::<i32>(conn)
+        .optional()?
+        .ok_or(NoSwapExists(id))?;
+
+    Ok(swap_fk)
+}
+
 pub fn get_active_swap_contexts(conn: &SqliteConnection) -> Result<Vec<SwapContext>> {
     let query = swaps::table
         .inner_join(swap_contexts::table.on(swap_contexts::id.eq(swaps::local_swap_id)))
@@ -40,6 +50,33 @@ pub fn get_active_swap_contexts(conn: &SqliteConnection) -> Result<Vec<SwapConte
     Ok(contexts)
 }
 
+/// Get the start of the oldest swap that is not completed yet.
+pub fn get_start_of_oldest_active_swap(conn: &SqliteConnection) -> Result<Option<OffsetDateTime>> {
+    let start_of_swap = swaps::table
+        .left_join(completed_swaps::table)
+        .filter(completed_swaps::completed_on.is_null())
+        .select(diesel::dsl::min(swaps::start_of_swap))
+        .first::<Option<i64>>(conn)?;
+
+    Ok(start_of_swap.map(OffsetDateTime::from_unix_timestamp))
+}
+
+/// Get the trading pair of the order a swap was set up from, together with the
+/// number of decimals of its quote currency.
+pub fn get_trading_pair_of_swap(
+    conn: &SqliteConnection,
+    swap_id: LocalSwapId,
+) -> Result<Option<(TradingPair, u8)>> {
+    let trading_pair = orders::table
+        .inner_join(order_swaps::table.inner_join(swaps::table))
+        .filter(swaps::local_swap_id.eq(Text(swap_id)))
+        .select((orders::trading_pair, orders::quote_decimals))
+        .first::<(Text<TradingPair>, Text<u8>)>(conn)
+        .optional()?;
+
+    Ok(trading_pair.map(|(trading_pair, decimals)| (trading_pair.0, decimals.0)))
+}
+
 pub fn all_open_btc_dai_orders(conn: &SqliteConnection) -> Result<Vec<(Order, BtcDaiOrder)>> {
     let orders = orders::table
         .inner_join(btc_dai_orders::table)
@@ -50,37 +87,59 @@ pub fn all_open_btc_dai_orders(conn: &SqliteConnection) -> Result<Vec<(Order, Bt
     Ok(orders)
 }
 
-pub fn get_orders_to_republish(conn: &SqliteConnection) -> Result<Vec<comit::BtcDaiOrder>> {
-    let orders = orders::table
+pub fn get_orders_to_republish(conn: &SqliteConnection) -> Result<Vec<comit::BtcErc20Order>> {
+    let herc20_orders = orders::table
         .inner_join(btc_dai_orders::table)
         .inner_join(order_hbit_params::table)
         .inner_join(order_herc20_params::table)
         .filter(btc_dai_orders::open.ne(Text::<Satoshis>(asset::Bitcoin::ZERO.into())))
-        .load::<(Order, BtcDaiOrder, OrderHbitParams, OrderHerc20Params)>(conn)?;
-
-    let orders = orders
+        .load::<(Order, BtcDaiOrder, OrderHbitParams, OrderHerc20Params)>(conn)?
         .into_iter()
-        .map::<Result<comit::BtcDaiOrder>, _>(
+        .map(
             |(order, btc_dai_order, order_hbit_params, order_herc20_params)| {
                 let swap_protocol =
-                    SwapProtocol::try_from(ParamsTuple(order_herc20_params, order_hbit_params))
-                        .with_context(|| {
-                            format!(
-                                "failed to construct swap protocol from params for order {}",
-                                order.order_id
-                            )
-                        })?;
-
-                Ok(comit::BtcDaiOrder {
-                    id: order.order_id,
-                    position: order.position,
-                    swap_protocol,
-                    created_at: OffsetDateTime::from_unix_timestamp(order.created_at),
-                    quantity: btc_dai_order.quantity,
-                    price: btc_dai_order.price,
-                })
+                    SwapProtocol::try_from(ParamsTuple(order_herc20_params, order_hbit_params));
+
+                (order, btc_dai_order, swap_protocol)
+            },
+        );
+    let heth_orders = orders::table
+        .inner_join(btc_dai_orders::table)
+        .inner_join(order_hbit_params::table)
+        .inner_join(order_heth_params::table)
+        .filter(btc_dai_orders::open.ne(Text::<Satoshis>(asset::Bitcoin::ZERO.into())))
+        .load::<(Order, BtcDaiOrder, OrderHbitParams, OrderHethParams)>(conn)?
+        .into_iter()
+        .map(
+            |(order, btc_dai_order, order_hbit_params, order_heth_params)| {
+                let swap_protocol =
+                    SwapProtocol::try_from(HethParamsTuple(order_heth_params, order_hbit_params));
+
+                (order, btc_dai_order, swap_protocol)
             },
-        )
+        );
+
+    let orders = herc20_orders
+        .chain(heth_orders)
+        .map::<Result<comit::BtcErc20Order>, _>(|(order, btc_dai_order, swap_protocol)| {
+            let swap_protocol = swap_protocol.with_context(|| {
+                format!(
+                    "failed to construct swap protocol from params for order {}",
+                    order.order_id
+                )
+            })?;
+
+            Ok(comit::BtcErc20Order {
+                id: order.order_id,
+                trading_pair: order.trading_pair,
+                position: order.position,
+                swap_protocol,
+                created_at: OffsetDateTime::from_unix_timestamp(order.created_at),
+                // Only what is still open, the rest has been filled already.
+                quantity: btc_dai_order.open,
+                price: btc_dai_order.price,
+            })
+        })
         .collect::<Result<Vec<_>, _>>()?;
 
     Ok(orders)
@@ -151,6 +210,31 @@ mod tests {
         }
     }
 
+    proptest! {
+        #[test]
+        fn get_start_of_oldest_active_swap_ignores_completed_swap(
+            active_swap in db::proptest::tables::insertable_swap(),
+            completed_swap in db::proptest::tables::insertable_swap(),
+            insertable_completed_swap in db::proptest::tables::insertable_completed_swap(SECOND_SWAP_FK),
+        ) {
+            let db = Sqlite::test();
+            let mut runtime = Runtime::new().unwrap();
+            let expected = OffsetDateTime::from_unix_timestamp(active_swap.start_of_swap);
+
+            let start_of_oldest_swap = runtime.block_on(async {
+                db.do_in_transaction(|conn| {
+                    active_swap.insert(conn)?;
+                    completed_swap.insert(conn)?;
+                    insertable_completed_swap.insert(conn)?;
+
+                    get_start_of_oldest_active_swap(conn)
+                }).await.unwrap()
+            });
+
+            assert_eq!(start_of_oldest_swap, Some(expected))
+        }
+    }
+
     proptest! {
         #[test]
         fn get_swap_context_by_id_returns_correct_swap(
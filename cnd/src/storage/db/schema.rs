@@ -33,6 +33,19 @@ table! {
     }
 }
 
+table! {
+    heths {
+        id -> Integer,
+        swap_id -> Integer,
+        amount -> Text,
+        chain_id -> BigInt,
+        expiry -> BigInt,
+        redeem_identity -> Text,
+        refund_identity -> Text,
+        side -> Text,
+    }
+}
+
 table! {
     hbits {
         id -> Integer,
@@ -61,6 +74,8 @@ table! {
         order_id -> Text,
         position -> Text,
         created_at -> BigInt,
+        trading_pair -> Text,
+        quote_decimals -> Text,
     }
 }
 
@@ -101,6 +116,17 @@ table! {
     }
 }
 
+table! {
+    order_heth_params {
+        id -> Integer,
+        order_id -> Integer,
+        chain_id -> BigInt,
+        side -> Text,
+        our_htlc_identity -> Text,
+        expiry_offset -> BigInt,
+    }
+}
+
 table! {
     order_swaps {
         id -> Integer,
@@ -117,15 +143,125 @@ table! {
     }
 }
 
+table! {
+    hbit_fund_events {
+        id -> Integer,
+        swap_id -> Integer,
+        txid -> Text,
+        vout -> BigInt,
+        block_height -> Nullable<BigInt>,
+    }
+}
+
+table! {
+    hbit_redeem_events {
+        id -> Integer,
+        swap_id -> Integer,
+        txid -> Text,
+        secret -> Text,
+        block_height -> Nullable<BigInt>,
+    }
+}
+
+table! {
+    hbit_refund_events {
+        id -> Integer,
+        swap_id -> Integer,
+        txid -> Text,
+        block_height -> Nullable<BigInt>,
+    }
+}
+
+table! {
+    herc20_deploy_events {
+        id -> Integer,
+        swap_id -> Integer,
+        tx_hash -> Text,
+        contract -> Text,
+        block_height -> Nullable<BigInt>,
+    }
+}
+
+table! {
+    herc20_fund_events {
+        id -> Integer,
+        swap_id -> Integer,
+        tx_hash -> Text,
+        block_height -> Nullable<BigInt>,
+    }
+}
+
+table! {
+    herc20_redeem_events {
+        id -> Integer,
+        swap_id -> Integer,
+        tx_hash -> Text,
+        secret -> Text,
+        block_height -> Nullable<BigInt>,
+    }
+}
+
+table! {
+    herc20_refund_events {
+        id -> Integer,
+        swap_id -> Integer,
+        tx_hash -> Text,
+        block_height -> Nullable<BigInt>,
+    }
+}
+
+table! {
+    heth_fund_events {
+        id -> Integer,
+        swap_id -> Integer,
+        tx_hash -> Text,
+        contract -> Text,
+        block_height -> Nullable<BigInt>,
+    }
+}
+
+table! {
+    heth_redeem_events {
+        id -> Integer,
+        swap_id -> Integer,
+        tx_hash -> Text,
+        secret -> Text,
+        block_height -> Nullable<BigInt>,
+    }
+}
+
+table! {
+    heth_refund_events {
+        id -> Integer,
+        swap_id -> Integer,
+        tx_hash -> Text,
+        block_height -> Nullable<BigInt>,
+    }
+}
+
+table! {
+    executed_actions {
+        id -> Integer,
+        swap_id -> Integer,
+        action -> Text,
+        transaction_id -> Text,
+    }
+}
+
 allow_tables_to_appear_in_same_query!(swaps, herc20s);
 allow_tables_to_appear_in_same_query!(swaps, hbits);
 allow_tables_to_appear_in_same_query!(hbits, herc20s);
+allow_tables_to_appear_in_same_query!(swaps, heths);
+allow_tables_to_appear_in_same_query!(hbits, heths);
 allow_tables_to_appear_in_same_query!(orders, btc_dai_orders);
 allow_tables_to_appear_in_same_query!(orders, order_hbit_params);
 allow_tables_to_appear_in_same_query!(orders, order_herc20_params);
 allow_tables_to_appear_in_same_query!(btc_dai_orders, order_hbit_params);
 allow_tables_to_appear_in_same_query!(btc_dai_orders, order_herc20_params);
 allow_tables_to_appear_in_same_query!(order_hbit_params, order_herc20_params);
+allow_tables_to_appear_in_same_query!(orders, order_heth_params);
+allow_tables_to_appear_in_same_query!(btc_dai_orders, order_heth_params);
+allow_tables_to_appear_in_same_query!(order_hbit_params, order_heth_params);
 allow_tables_to_appear_in_same_query!(orders, order_swaps);
 allow_tables_to_appear_in_same_query!(swaps, order_swaps);
 allow_tables_to_appear_in_same_query!(orders, swaps);
@@ -134,10 +270,33 @@ allow_tables_to_appear_in_same_query!(btc_dai_orders, order_swaps);
 allow_tables_to_appear_in_same_query!(swap_contexts, swaps);
 allow_tables_to_appear_in_same_query!(completed_swaps, swaps);
 allow_tables_to_appear_in_same_query!(completed_swaps, swap_contexts);
+allow_tables_to_appear_in_same_query!(hbit_fund_events, swaps);
+allow_tables_to_appear_in_same_query!(hbit_redeem_events, swaps);
+allow_tables_to_appear_in_same_query!(hbit_refund_events, swaps);
+allow_tables_to_appear_in_same_query!(herc20_deploy_events, swaps);
+allow_tables_to_appear_in_same_query!(herc20_fund_events, swaps);
+allow_tables_to_appear_in_same_query!(herc20_redeem_events, swaps);
+allow_tables_to_appear_in_same_query!(herc20_refund_events, swaps);
+allow_tables_to_appear_in_same_query!(heth_fund_events, swaps);
+allow_tables_to_appear_in_same_query!(heth_redeem_events, swaps);
+allow_tables_to_appear_in_same_query!(heth_refund_events, swaps);
+allow_tables_to_appear_in_same_query!(executed_actions, swaps);
 joinable!(btc_dai_orders -> orders (order_id));
 joinable!(order_hbit_params -> orders (order_id));
 joinable!(order_herc20_params -> orders (order_id));
+joinable!(order_heth_params -> orders (order_id));
 joinable!(order_swaps -> orders (order_id));
 joinable!(order_swaps -> swaps (swap_id));
 joinable!(completed_swaps -> swaps (swap_id));
 joinable!(hbits -> swaps (swap_id));
+joinable!(hbit_fund_events -> swaps (swap_id));
+joinable!(hbit_redeem_events -> swaps (swap_id));
+joinable!(hbit_refund_events -> swaps (swap_id));
+joinable!(herc20_deploy_events -> swaps (swap_id));
+joinable!(herc20_fund_events -> swaps (swap_id));
+joinable!(herc20_redeem_events -> swaps (swap_id));
+joinable!(herc20_refund_events -> swaps (swap_id));
+joinable!(heth_fund_events -> swaps (swap_id));
+joinable!(heth_redeem_events -> swaps (swap_id));
+joinable!(heth_refund_events -> swaps (swap_id));
+joinable!(executed_actions -> swaps (swap_id));
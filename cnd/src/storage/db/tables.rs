@@ -18,12 +18,44 @@ macro_rules! swap_id_fk {
     };
 }
 
+/// Implements a lookup of the event a swap emitted on a particular ledger.
+///
+/// Every swap emits at most one event of each kind, hence there is at most one
+/// row per swap in each of the event tables.
+macro_rules! impl_by_swap_id {
+    ($event:ident, $table:ident) => {
+        impl $event {
+            pub fn by_swap_id(
+                conn: &SqliteConnection,
+                swap_id: crate::LocalSwapId,
+            ) -> anyhow::Result<Option<Self>> {
+                use crate::storage::db::schema::swaps;
+
+                let event = $table::table
+                    .inner_join(swaps::table)
+                    .filter(swaps::local_swap_id.eq(Text(swap_id)))
+                    .select($table::all_columns)
+                    .first::<$event>(conn)
+                    .optional()?;
+
+                Ok(event)
+            }
+        }
+    };
+}
+
 mod btc_dai_orders;
 mod completed_swaps;
+mod executed_actions;
+mod hbit_events;
 mod hbits;
+mod herc20_events;
 mod herc20s;
+mod heth_events;
+mod heths;
 mod order_hbit_params;
 mod order_herc20_params;
+mod order_heth_params;
 mod order_swaps;
 mod orders;
 mod secret_hashes;
@@ -33,10 +65,26 @@ mod swaps;
 pub use btc_dai_orders::{BtcDaiOrder, InsertableBtcDaiOrder};
 use comit::order::SwapProtocol;
 pub use completed_swaps::{CompletedSwap, InsertableCompletedSwap};
+pub use executed_actions::{ExecutedAction, InsertableExecutedAction};
+pub use hbit_events::{
+    HbitFundEvent, HbitRedeemEvent, HbitRefundEvent, InsertableHbitFundEvent,
+    InsertableHbitRedeemEvent, InsertableHbitRefundEvent,
+};
 pub use hbits::{Hbit, InsertableHbit};
+pub use herc20_events::{
+    Herc20DeployEvent, Herc20FundEvent, Herc20RedeemEvent, Herc20RefundEvent,
+    InsertableHerc20DeployEvent, InsertableHerc20FundEvent, InsertableHerc20RedeemEvent,
+    InsertableHerc20RefundEvent,
+};
 pub use herc20s::{Herc20, InsertableHerc20};
+pub use heth_events::{
+    HethFundEvent, HethRedeemEvent, HethRefundEvent, InsertableHethFundEvent,
+    InsertableHethRedeemEvent, InsertableHethRefundEvent,
+};
+pub use heths::{Heth, InsertableHeth};
 pub use order_hbit_params::{InsertableOrderHbitParams, OrderHbitParams};
 pub use order_herc20_params::{InsertableOrderHerc20Params, OrderHerc20Params};
+pub use order_heth_params::{InsertableOrderHethParams, OrderHethParams};
 pub use order_swaps::{InsertableOrderSwap, OrderSwap};
 pub use orders::{InsertableOrder, NoOrderExists, Order};
 pub use secret_hashes::{InsertableSecretHash, SecretHash};
@@ -52,6 +100,10 @@ use time::Duration;
 #[derive(Debug)]
 pub struct ParamsTuple(pub OrderHerc20Params, pub OrderHbitParams);
 
+/// The params of an order of BTC/ETH, see [`ParamsTuple`].
+#[derive(Debug)]
+pub struct HethParamsTuple(pub OrderHethParams, pub OrderHbitParams);
+
 impl TryFrom<ParamsTuple> for SwapProtocol {
     type Error = SameSide;
 
@@ -107,6 +159,61 @@ impl TryFrom<ParamsTuple> for SwapProtocol {
     }
 }
 
+impl TryFrom<HethParamsTuple> for SwapProtocol {
+    type Error = SameSide;
+
+    fn try_from(value: HethParamsTuple) -> Result<Self, Self::Error> {
+        match value {
+            HethParamsTuple(
+                OrderHethParams {
+                    side: Side::Beta,
+                    expiry_offset: heth_expiry_offset,
+                    ..
+                },
+                OrderHbitParams {
+                    side: Side::Alpha,
+                    expiry_offset: hbit_expiry_offset,
+                    ..
+                },
+            ) => Ok(SwapProtocol::HbitHeth {
+                hbit_expiry_offset: AlphaOffset::from(Duration::seconds(hbit_expiry_offset)),
+                heth_expiry_offset: BetaOffset::from(Duration::seconds(heth_expiry_offset)),
+            }),
+            HethParamsTuple(
+                OrderHethParams {
+                    side: Side::Alpha,
+                    expiry_offset: heth_expiry_offset,
+                    ..
+                },
+                OrderHbitParams {
+                    side: Side::Beta,
+                    expiry_offset: hbit_expiry_offset,
+                    ..
+                },
+            ) => Ok(SwapProtocol::HethHbit {
+                heth_expiry_offset: AlphaOffset::from(Duration::seconds(heth_expiry_offset)),
+                hbit_expiry_offset: BetaOffset::from(Duration::seconds(hbit_expiry_offset)),
+            }),
+            HethParamsTuple(
+                OrderHethParams {
+                    side: Side::Alpha, ..
+                },
+                OrderHbitParams {
+                    side: Side::Alpha, ..
+                },
+            ) => Err(SameSide(Side::Alpha)),
+            HethParamsTuple(
+                OrderHethParams {
+                    side: Side::Beta, ..
+                },
+                OrderHbitParams {
+                    side: Side::Beta, ..
+                },
+            ) => Err(SameSide(Side::Beta)),
+        }
+    }
+}
+
 #[derive(thiserror::Error, Debug, Clone, Copy)]
 #[error("both params are side {0}")]
 pub struct SameSide(pub Side);
@@ -182,3 +289,5 @@ macro_rules! impl_load_tables {
 
 impl_load_tables!(Herc20, Hbit);
 impl_load_tables!(Hbit, Herc20);
+impl_load_tables!(Heth, Hbit);
+impl_load_tables!(Hbit, Heth);
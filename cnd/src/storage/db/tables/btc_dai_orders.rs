@@ -1,7 +1,7 @@
 use crate::storage::{
     db::{
         schema::*,
-        wrapper_types::{Erc20Amount, Satoshis, WeiPerSat},
+        wrapper_types::{Erc20Amount, Satoshis, WeiPerBtc},
     },
     Order, Text,
 };
@@ -21,7 +21,7 @@ pub struct BtcDaiOrder {
     pub order_id: i32,
     #[diesel(deserialize_as = "Text<Satoshis>")]
     pub quantity: Quantity<bitcoin::Bitcoin>,
-    #[diesel(deserialize_as = "Text<WeiPerSat>")]
+    #[diesel(deserialize_as = "Text<WeiPerBtc>")]
     pub price: Price<bitcoin::Bitcoin, Erc20Quantity>,
     #[diesel(deserialize_as = "Text<Satoshis>")]
     pub open: Quantity<bitcoin::Bitcoin>,
@@ -0,0 +1,64 @@
+use crate::storage::{
+    db::{schema::executed_actions, tables::Swap},
+    Text,
+};
+use anyhow::Result;
+use diesel::{prelude::*, sqlite::SqliteConnection};
+
+/// A transaction the built-in wallet broadcast to execute an action of a
+/// swap.
+#[derive(Associations, Clone, Debug, Identifiable, Queryable, PartialEq)]
+#[belongs_to(Swap)]
+#[table_name = "executed_actions"]
+pub struct ExecutedAction {
+    id: i32,
+    swap_id: i32,
+    pub action: String,
+    pub transaction_id: String,
+}
+
+impl ExecutedAction {
+    pub fn by_swap_id_and_action(
+        conn: &SqliteConnection,
+        swap_id: crate::LocalSwapId,
+        action: &str,
+    ) -> Result<Option<Self>> {
+        use crate::storage::db::schema::swaps;
+
+        let executed_action = executed_actions::table
+            .inner_join(swaps::table)
+            .filter(swaps::local_swap_id.eq(Text(swap_id)))
+            .filter(executed_actions::action.eq(action))
+            .select(executed_actions::all_columns)
+            .first::<ExecutedAction>(conn)
+            .optional()?;
+
+        Ok(executed_action)
+    }
+}
+
+#[derive(Insertable, Clone, Debug)]
+#[table_name = "executed_actions"]
+pub struct InsertableExecutedAction {
+    swap_id: i32,
+    action: String,
+    transaction_id: String,
+}
+
+impl InsertableExecutedAction {
+    pub fn new(swap_fk: i32, action: String, transaction_id: String) -> Self {
+        Self {
+            swap_id: swap_fk,
+            action,
+            transaction_id,
+        }
+    }
+
+    pub fn insert(self, conn: &SqliteConnection) -> Result<()> {
+        diesel::insert_into(executed_actions::table)
+            .values(self)
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
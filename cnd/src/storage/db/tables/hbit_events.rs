@@ -0,0 +1,167 @@
+use crate::storage::{
+    db::{
+        schema::*,
+        tables::Swap,
+        wrapper_types::{HexSecret, U32},
+    },
+    Text,
+};
+use anyhow::Result;
+use comit::{hbit, Secret};
+use diesel::{prelude::*, sqlite::SqliteConnection};
+
+#[derive(Associations, Clone, Copy, Debug, Identifiable, Queryable, PartialEq)]
+#[belongs_to(Swap)]
+#[table_name = "hbit_fund_events"]
+pub struct HbitFundEvent {
+    id: i32,
+    swap_id: i32,
+    #[diesel(deserialize_as = "Text<::bitcoin::Txid>")]
+    pub txid: ::bitcoin::Txid,
+    #[diesel(deserialize_as = "U32")]
+    pub vout: u32,
+    block_height: Option<U32>,
+}
+
+impl_by_swap_id!(HbitFundEvent, hbit_fund_events);
+
+impl From<HbitFundEvent> for hbit::Funded {
+    fn from(event: HbitFundEvent) -> Self {
+        hbit::Funded {
+            location: ::bitcoin::OutPoint {
+                txid: event.txid,
+                vout: event.vout,
+            },
+            block_height: event.block_height.map(|height| height.0),
+        }
+    }
+}
+
+#[derive(Insertable, Clone, Copy, Debug)]
+#[table_name = "hbit_fund_events"]
+pub struct InsertableHbitFundEvent {
+    swap_id: i32,
+    txid: Text<::bitcoin::Txid>,
+    vout: U32,
+    block_height: Option<U32>,
+}
+
+impl InsertableHbitFundEvent {
+    pub fn new(swap_fk: i32, event: hbit::Funded) -> Self {
+        Self {
+            swap_id: swap_fk,
+            txid: Text(event.location.txid),
+            vout: U32(event.location.vout),
+            block_height: event.block_height.map(U32),
+        }
+    }
+
+    pub fn insert(self, conn: &SqliteConnection) -> Result<()> {
+        diesel::insert_or_ignore_into(hbit_fund_events::table)
+            .values(self)
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Associations, Clone, Copy, Debug, Identifiable, Queryable, PartialEq)]
+#[belongs_to(Swap)]
+#[table_name = "hbit_redeem_events"]
+pub struct HbitRedeemEvent {
+    id: i32,
+    swap_id: i32,
+    #[diesel(deserialize_as = "Text<::bitcoin::Txid>")]
+    pub txid: ::bitcoin::Txid,
+    #[diesel(deserialize_as = "Text<HexSecret>")]
+    pub secret: Secret,
+    block_height: Option<U32>,
+}
+
+impl_by_swap_id!(HbitRedeemEvent, hbit_redeem_events);
+
+impl From<HbitRedeemEvent> for hbit::Redeemed {
+    fn from(event: HbitRedeemEvent) -> Self {
+        hbit::Redeemed {
+            transaction: event.txid,
+            secret: event.secret,
+            block_height: event.block_height.map(|height| height.0),
+        }
+    }
+}
+
+#[derive(Insertable, Clone, Copy, Debug)]
+#[table_name = "hbit_redeem_events"]
+pub struct InsertableHbitRedeemEvent {
+    swap_id: i32,
+    txid: Text<::bitcoin::Txid>,
+    secret: Text<HexSecret>,
+    block_height: Option<U32>,
+}
+
+impl InsertableHbitRedeemEvent {
+    pub fn new(swap_fk: i32, event: hbit::Redeemed) -> Self {
+        Self {
+            swap_id: swap_fk,
+            txid: Text(event.transaction),
+            secret: Text(event.secret.into()),
+            block_height: event.block_height.map(U32),
+        }
+    }
+
+    pub fn insert(self, conn: &SqliteConnection) -> Result<()> {
+        diesel::insert_or_ignore_into(hbit_redeem_events::table)
+            .values(self)
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Associations, Clone, Copy, Debug, Identifiable, Queryable, PartialEq)]
+#[belongs_to(Swap)]
+#[table_name = "hbit_refund_events"]
+pub struct HbitRefundEvent {
+    id: i32,
+    swap_id: i32,
+    #[diesel(deserialize_as = "Text<::bitcoin::Txid>")]
+    pub txid: ::bitcoin::Txid,
+    block_height: Option<U32>,
+}
+
+impl_by_swap_id!(HbitRefundEvent, hbit_refund_events);
+
+impl From<HbitRefundEvent> for hbit::Refunded {
+    fn from(event: HbitRefundEvent) -> Self {
+        hbit::Refunded {
+            transaction: event.txid,
+            block_height: event.block_height.map(|height| height.0),
+        }
+    }
+}
+
+#[derive(Insertable, Clone, Copy, Debug)]
+#[table_name = "hbit_refund_events"]
+pub struct InsertableHbitRefundEvent {
+    swap_id: i32,
+    txid: Text<::bitcoin::Txid>,
+    block_height: Option<U32>,
+}
+
+impl InsertableHbitRefundEvent {
+    pub fn new(swap_fk: i32, event: hbit::Refunded) -> Self {
+        Self {
+            swap_id: swap_fk,
+            txid: Text(event.transaction),
+            block_height: event.block_height.map(U32),
+        }
+    }
+
+    pub fn insert(self, conn: &SqliteConnection) -> Result<()> {
+        diesel::insert_or_ignore_into(hbit_refund_events::table)
+            .values(self)
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
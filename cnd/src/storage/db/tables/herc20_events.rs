@@ -0,0 +1,216 @@
+use crate::{
+    ethereum,
+    storage::{
+        db::{
+            schema::*,
+            tables::Swap,
+            wrapper_types::{HexSecret, U32},
+        },
+        Text,
+    },
+};
+use anyhow::Result;
+use comit::{herc20, Secret};
+use diesel::{prelude::*, sqlite::SqliteConnection};
+
+#[derive(Associations, Clone, Copy, Debug, Identifiable, Queryable, PartialEq)]
+#[belongs_to(Swap)]
+#[table_name = "herc20_deploy_events"]
+pub struct Herc20DeployEvent {
+    id: i32,
+    swap_id: i32,
+    #[diesel(deserialize_as = "Text<ethereum::Hash>")]
+    pub tx_hash: ethereum::Hash,
+    #[diesel(deserialize_as = "Text<ethereum::Address>")]
+    pub contract: ethereum::Address,
+    block_height: Option<U32>,
+}
+
+impl_by_swap_id!(Herc20DeployEvent, herc20_deploy_events);
+
+impl From<Herc20DeployEvent> for herc20::Deployed {
+    fn from(event: Herc20DeployEvent) -> Self {
+        herc20::Deployed {
+            transaction: event.tx_hash,
+            location: event.contract,
+            block_height: event.block_height.map(|height| height.0),
+        }
+    }
+}
+
+#[derive(Insertable, Clone, Copy, Debug)]
+#[table_name = "herc20_deploy_events"]
+pub struct InsertableHerc20DeployEvent {
+    swap_id: i32,
+    tx_hash: Text<ethereum::Hash>,
+    contract: Text<ethereum::Address>,
+    block_height: Option<U32>,
+}
+
+impl InsertableHerc20DeployEvent {
+    pub fn new(swap_fk: i32, event: herc20::Deployed) -> Self {
+        Self {
+            swap_id: swap_fk,
+            tx_hash: Text(event.transaction),
+            contract: Text(event.location),
+            block_height: event.block_height.map(U32),
+        }
+    }
+
+    pub fn insert(self, conn: &SqliteConnection) -> Result<()> {
+        diesel::insert_or_ignore_into(herc20_deploy_events::table)
+            .values(self)
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Associations, Clone, Copy, Debug, Identifiable, Queryable, PartialEq)]
+#[belongs_to(Swap)]
+#[table_name = "herc20_fund_events"]
+pub struct Herc20FundEvent {
+    id: i32,
+    swap_id: i32,
+    #[diesel(deserialize_as = "Text<ethereum::Hash>")]
+    pub tx_hash: ethereum::Hash,
+    block_height: Option<U32>,
+}
+
+impl_by_swap_id!(Herc20FundEvent, herc20_fund_events);
+
+impl From<Herc20FundEvent> for herc20::Funded {
+    fn from(event: Herc20FundEvent) -> Self {
+        herc20::Funded {
+            transaction: event.tx_hash,
+            block_height: event.block_height.map(|height| height.0),
+        }
+    }
+}
+
+#[derive(Insertable, Clone, Copy, Debug)]
+#[table_name = "herc20_fund_events"]
+pub struct InsertableHerc20FundEvent {
+    swap_id: i32,
+    tx_hash: Text<ethereum::Hash>,
+    block_height: Option<U32>,
+}
+
+impl InsertableHerc20FundEvent {
+    pub fn new(swap_fk: i32, event: herc20::Funded) -> Self {
+        Self {
+            swap_id: swap_fk,
+            tx_hash: Text(event.transaction),
+            block_height: event.block_height.map(U32),
+        }
+    }
+
+    pub fn insert(self, conn: &SqliteConnection) -> Result<()> {
+        diesel::insert_or_ignore_into(herc20_fund_events::table)
+            .values(self)
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Associations, Clone, Copy, Debug, Identifiable, Queryable, PartialEq)]
+#[belongs_to(Swap)]
+#[table_name = "herc20_redeem_events"]
+pub struct Herc20RedeemEvent {
+    id: i32,
+    swap_id: i32,
+    #[diesel(deserialize_as = "Text<ethereum::Hash>")]
+    pub tx_hash: ethereum::Hash,
+    #[diesel(deserialize_as = "Text<HexSecret>")]
+    pub secret: Secret,
+    block_height: Option<U32>,
+}
+
+impl_by_swap_id!(Herc20RedeemEvent, herc20_redeem_events);
+
+impl From<Herc20RedeemEvent> for herc20::Redeemed {
+    fn from(event: Herc20RedeemEvent) -> Self {
+        herc20::Redeemed {
+            transaction: event.tx_hash,
+            secret: event.secret,
+            block_height: event.block_height.map(|height| height.0),
+        }
+    }
+}
+
+#[derive(Insertable, Clone, Copy, Debug)]
+#[table_name = "herc20_redeem_events"]
+pub struct InsertableHerc20RedeemEvent {
+    swap_id: i32,
+    tx_hash: Text<ethereum::Hash>,
+    secret: Text<HexSecret>,
+    block_height: Option<U32>,
+}
+
+impl InsertableHerc20RedeemEvent {
+    pub fn new(swap_fk: i32, event: herc20::Redeemed) -> Self {
+        Self {
+            swap_id: swap_fk,
+            tx_hash: Text(event.transaction),
+            secret: Text(event.secret.into()),
+            block_height: event.block_height.map(U32),
+        }
+    }
+
+    pub fn insert(self, conn: &SqliteConnection) -> Result<()> {
+        diesel::insert_or_ignore_into(herc20_redeem_events::table)
+            .values(self)
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Associations, Clone, Copy, Debug, Identifiable, Queryable, PartialEq)]
+#[belongs_to(Swap)]
+#[table_name = "herc20_refund_events"]
+pub struct Herc20RefundEvent {
+    id: i32,
+    swap_id: i32,
+    #[diesel(deserialize_as = "Text<ethereum::Hash>")]
+    pub tx_hash: ethereum::Hash,
+    block_height: Option<U32>,
+}
+
+impl_by_swap_id!(Herc20RefundEvent, herc20_refund_events);
+
+impl From<Herc20RefundEvent> for herc20::Refunded {
+    fn from(event: Herc20RefundEvent) -> Self {
+        herc20::Refunded {
+            transaction: event.tx_hash,
+            block_height: event.block_height.map(|height| height.0),
+        }
+    }
+}
+
+#[derive(Insertable, Clone, Copy, Debug)]
+#[table_name = "herc20_refund_events"]
+pub struct InsertableHerc20RefundEvent {
+    swap_id: i32,
+    tx_hash: Text<ethereum::Hash>,
+    block_height: Option<U32>,
+}
+
+impl InsertableHerc20RefundEvent {
+    pub fn new(swap_fk: i32, event: herc20::Refunded) -> Self {
+        Self {
+            swap_id: swap_fk,
+            tx_hash: Text(event.transaction),
+            block_height: event.block_height.map(U32),
+        }
+    }
+
+    pub fn insert(self, conn: &SqliteConnection) -> Result<()> {
+        diesel::insert_or_ignore_into(herc20_refund_events::table)
+            .values(self)
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{
+    ethereum,
+    storage::{
+        db::{
+            schema::*,
+            tables::Swap,
+            wrapper_types::{HexSecret, U32},
+        },
+        Text,
+    },
+};
+use anyhow::Result;
+use comit::{heth, Secret};
+use diesel::{prelude::*, sqlite::SqliteConnection};
+
+#[derive(Associations, Clone, Copy, Debug, Identifiable, Queryable, PartialEq)]
+#[belongs_to(Swap)]
+#[table_name = "heth_fund_events"]
+pub struct HethFundEvent {
+    id: i32,
+    swap_id: i32,
+    #[diesel(deserialize_as = "Text<ethereum::Hash>")]
+    pub tx_hash: ethereum::Hash,
+    #[diesel(deserialize_as = "Text<ethereum::Address>")]
+    pub contract: ethereum::Address,
+    block_height: Option<U32>,
+}
+
+impl_by_swap_id!(HethFundEvent, heth_fund_events);
+
+impl From<HethFundEvent> for heth::Funded {
+    fn from(event: HethFundEvent) -> Self {
+        heth::Funded {
+            transaction: event.tx_hash,
+            location: event.contract,
+            block_height: event.block_height.map(|height| height.0),
+        }
+    }
+}
+
+#[derive(Insertable, Clone, Copy, Debug)]
+#[table_name = "heth_fund_events"]
+pub struct InsertableHethFundEvent {
+    swap_id: i32,
+    tx_hash: Text<ethereum::Hash>,
+    contract: Text<ethereum::Address>,
+    block_height: Option<U32>,
+}
+
+impl InsertableHethFundEvent {
+    pub fn new(swap_fk: i32, event: heth::Funded) -> Self {
+        Self {
+            swap_id: swap_fk,
+            tx_hash: Text(event.transaction),
+            contract: Text(event.location),
+            block_height: event.block_height.map(U32),
+        }
+    }
+
+    pub fn insert(self, conn: &SqliteConnection) -> Result<()> {
+        diesel::insert_or_ignore_into(heth_fund_events::table)
+            .values(self)
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Associations, Clone, Copy, Debug, Identifiable, Queryable, PartialEq)]
+#[belongs_to(Swap)]
+#[table_name = "heth_redeem_events"]
+pub struct HethRedeemEvent {
+    id: i32,
+    swap_id: i32,
+    #[diesel(deserialize_as = "Text<ethereum::Hash>")]
+    pub tx_hash: ethereum::Hash,
+    #[diesel(deserialize_as = "Text<HexSecret>")]
+    pub secret: Secret,
+    block_height: Option<U32>,
+}
+
+impl_by_swap_id!(HethRedeemEvent, heth_redeem_events);
+
+impl From<HethRedeemEvent> for heth::Redeemed {
+    fn from(event: HethRedeemEvent) -> Self {
+        heth::Redeemed {
+            transaction: event.tx_hash,
+            secret: event.secret,
+            block_height: event.block_height.map(|height| height.0),
+        }
+    }
+}
+
+#[derive(Insertable, Clone, Copy, Debug)]
+#[table_name = "heth_redeem_events"]
+pub struct InsertableHethRedeemEvent {
+    swap_id: i32,
+    tx_hash: Text<ethereum::Hash>,
+    secret: Text<HexSecret>,
+    block_height: Option<U32>,
+}
+
+impl InsertableHethRedeemEvent {
+    pub fn new(swap_fk: i32, event: heth::Redeemed) -> Self {
+        Self {
+            swap_id: swap_fk,
+            tx_hash: Text(event.transaction),
+            secret: Text(event.secret.into()),
+            block_height: event.block_height.map(U32),
+        }
+    }
+
+    pub fn insert(self, conn: &SqliteConnection) -> Result<()> {
+        diesel::insert_or_ignore_into(heth_redeem_events::table)
+            .values(self)
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Associations, Clone, Copy, Debug, Identifiable, Queryable, PartialEq)]
+#[belongs_to(Swap)]
+#[table_name = "heth_refund_events"]
+pub struct HethRefundEvent {
+    id: i32,
+    swap_id: i32,
+    #[diesel(deserialize_as = "Text<ethereum::Hash>")]
+    pub tx_hash: ethereum::Hash,
+    block_height: Option<U32>,
+}
+
+impl_by_swap_id!(HethRefundEvent, heth_refund_events);
+
+impl From<HethRefundEvent> for heth::Refunded {
+    fn from(event: HethRefundEvent) -> Self {
+        heth::Refunded {
+            transaction: event.tx_hash,
+            block_height: event.block_height.map(|height| height.0),
+        }
+    }
+}
+
+#[derive(Insertable, Clone, Copy, Debug)]
+#[table_name = "heth_refund_events"]
+pub struct InsertableHethRefundEvent {
+    swap_id: i32,
+    tx_hash: Text<ethereum::Hash>,
+    block_height: Option<U32>,
+}
+
+impl InsertableHethRefundEvent {
+    pub fn new(swap_fk: i32, event: heth::Refunded) -> Self {
+        Self {
+            swap_id: swap_fk,
+            tx_hash: Text(event.transaction),
+            block_height: event.block_height.map(U32),
+        }
+    }
+
+    pub fn insert(self, conn: &SqliteConnection) -> Result<()> {
+        diesel::insert_or_ignore_into(heth_refund_events::table)
+            .values(self)
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
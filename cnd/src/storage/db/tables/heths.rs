@@ -0,0 +1,75 @@
+use crate::storage::{
+    db::{
+        schema::heths,
+        tables::Swap,
+        wrapper_types::{Ether, U32},
+    },
+    Text,
+};
+use anyhow::Result;
+use comit::{asset, ethereum, ethereum::ChainId, Side, Timestamp};
+use diesel::{prelude::*, SqliteConnection};
+
+#[derive(Associations, Clone, Debug, Identifiable, Queryable, PartialEq)]
+#[belongs_to(Swap)]
+#[table_name = "heths"]
+pub struct Heth {
+    id: i32,
+    swap_id: i32,
+    #[diesel(deserialize_as = "Text<Ether>")]
+    pub amount: asset::Ether,
+    #[diesel(deserialize_as = "U32")]
+    pub chain_id: ChainId,
+    #[diesel(deserialize_as = "U32")]
+    pub expiry: Timestamp,
+    #[diesel(deserialize_as = "Text<ethereum::Address>")]
+    pub redeem_identity: ethereum::Address,
+    #[diesel(deserialize_as = "Text<ethereum::Address>")]
+    pub refund_identity: ethereum::Address,
+    #[diesel(deserialize_as = "Text<Side>")]
+    pub side: Side,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[table_name = "heths"]
+pub struct InsertableHeth {
+    pub swap_id: i32,
+    pub amount: Text<Ether>,
+    pub chain_id: U32,
+    pub expiry: U32,
+    pub redeem_identity: Text<ethereum::Address>,
+    pub refund_identity: Text<ethereum::Address>,
+    pub side: Text<Side>,
+}
+
+impl InsertableHeth {
+    pub fn insert(self, conn: &SqliteConnection) -> Result<()> {
+        diesel::insert_into(heths::table)
+            .values(self)
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
+
+impl InsertableHeth {
+    pub fn new(
+        swap_fk: i32,
+        asset: asset::Ether,
+        chain_id: ChainId,
+        expiry: u32,
+        redeem_identity: ethereum::Address,
+        refund_identity: ethereum::Address,
+        side: Side,
+    ) -> Self {
+        Self {
+            swap_id: swap_fk,
+            amount: Text(asset.into()),
+            chain_id: u32::from(chain_id).into(),
+            expiry: U32::from(expiry),
+            redeem_identity: Text(redeem_identity),
+            refund_identity: Text(refund_identity),
+            side: Text(side),
+        }
+    }
+}
@@ -1,10 +1,10 @@
 use crate::storage::{db::schema::orders, Text};
 use anyhow::{Context, Result};
-use comit::{OrderId, Position};
+use comit::{OrderId, Position, TradingPair};
 use diesel::{prelude::*, SqliteConnection};
 use time::OffsetDateTime;
 
-#[derive(Associations, Clone, Copy, Debug, Identifiable, Queryable, PartialEq)]
+#[derive(Associations, Clone, Debug, Identifiable, Queryable, PartialEq)]
 #[table_name = "orders"]
 pub struct Order {
     pub id: i32,
@@ -13,6 +13,11 @@ pub struct Order {
     #[diesel(deserialize_as = "Text<Position>")]
     pub position: Position,
     pub created_at: i64,
+    #[diesel(deserialize_as = "Text<TradingPair>")]
+    pub trading_pair: TradingPair,
+    /// The number of decimals of the token the trading pair is quoted in.
+    #[diesel(deserialize_as = "Text<u8>")]
+    pub quote_decimals: u8,
 }
 
 impl Order {
@@ -30,20 +35,30 @@ impl Order {
 #[error("no order exists in the database for id {0}")]
 pub struct NoOrderExists(pub OrderId);
 
-#[derive(Insertable, Clone, Copy, Debug)]
+#[derive(Insertable, Clone, Debug)]
 #[table_name = "orders"]
 pub struct InsertableOrder {
     pub order_id: Text<OrderId>,
     pub position: Text<Position>,
     pub created_at: i64,
+    pub trading_pair: Text<TradingPair>,
+    pub quote_decimals: Text<u8>,
 }
 
 impl InsertableOrder {
-    pub fn new(order_id: OrderId, position: Position, created_at: OffsetDateTime) -> Self {
+    pub fn new(
+        order_id: OrderId,
+        position: Position,
+        created_at: OffsetDateTime,
+        trading_pair: TradingPair,
+        quote_decimals: u8,
+    ) -> Self {
         Self {
             order_id: Text(order_id),
             position: Text(position),
             created_at: created_at.timestamp(),
+            trading_pair: Text(trading_pair),
+            quote_decimals: Text(quote_decimals),
         }
     }
 
@@ -6,7 +6,7 @@ mod timestamp;
 mod u32;
 
 pub use self::{text::Text, timestamp::Timestamp, u32::U32};
-use comit::{asset::Erc20Quantity, Price, Quantity};
+use comit::{asset::Erc20Quantity, Price, Quantity, Secret};
 
 /// A wrapper type for representing satoshis
 ///
@@ -47,6 +47,36 @@ impl From<Text<Satoshis>> for Quantity<asset::Bitcoin> {
     }
 }
 
+/// A wrapper type for storing a secret as hex.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HexSecret(Secret);
+
+impl FromStr for HexSecret {
+    type Err = <Secret as FromStr>::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Secret::from_str(s).map(Self)
+    }
+}
+
+impl fmt::Display for HexSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.0.as_raw_secret()))
+    }
+}
+
+impl From<Secret> for HexSecret {
+    fn from(secret: Secret) -> Self {
+        HexSecret(secret)
+    }
+}
+
+impl From<Text<HexSecret>> for Secret {
+    fn from(text: Text<HexSecret>) -> Self {
+        (text.0).0
+    }
+}
+
 /// These types wrap Ethereum assets to provide `FromStr` and `Display`
 /// implementations that use decimal numbers.
 #[derive(Debug, Clone, PartialEq)]
@@ -108,9 +138,9 @@ impl fmt::Display for Erc20Amount {
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub struct WeiPerSat(asset::Erc20Quantity);
+pub struct WeiPerBtc(asset::Erc20Quantity);
 
-impl FromStr for WeiPerSat {
+impl FromStr for WeiPerBtc {
     type Err = crate::asset::ethereum::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -118,14 +148,14 @@ impl FromStr for WeiPerSat {
     }
 }
 
-impl fmt::Display for WeiPerSat {
+impl fmt::Display for WeiPerBtc {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.0.to_wei_dec())
     }
 }
 
-impl From<Text<WeiPerSat>> for Price<asset::Bitcoin, Erc20Quantity> {
-    fn from(rate: Text<WeiPerSat>) -> Self {
-        Price::from_wei_per_sat((rate.0).0)
+impl From<Text<WeiPerBtc>> for Price<asset::Bitcoin, Erc20Quantity> {
+    fn from(rate: Text<WeiPerBtc>) -> Self {
+        Price::from_wei_per_btc((rate.0).0)
     }
 }
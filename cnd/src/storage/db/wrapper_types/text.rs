@@ -79,6 +79,10 @@ impl_from_text!(Position);
 impl_from_text!(LockProtocol);
 impl_from_text!(comit::bitcoin::PublicKey);
 impl_from_text!(comit::SecretHash);
+impl_from_text!(ethereum::Hash);
+impl_from_text!(::bitcoin::Txid);
+impl_from_text!(comit::TradingPair);
+impl_from_text!(u8);
 
 impl From<Text<Erc20Amount>> for asset::Erc20Quantity {
     fn from(text: Text<Erc20Amount>) -> Self {
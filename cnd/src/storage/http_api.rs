@@ -1,17 +1,29 @@
 //! Implement traits to Load/Save types defined in the http_api module.
 use crate::{
+    hbit, herc20, heth,
     http_api::{Protocol, SwapEvent, SwapResource},
-    storage::{Hbit, Herc20, Load, LoadTables, SwapContext, Tables},
+    storage::{
+        queries, Hbit, HbitFundEvent, HbitRedeemEvent, HbitRefundEvent, Herc20, Herc20DeployEvent,
+        Herc20FundEvent, Herc20RedeemEvent, Herc20RefundEvent, Heth, HethFundEvent,
+        HethRedeemEvent, HethRefundEvent, Load, LoadTables, SwapContext, Tables,
+    },
     LocalSwapId, Storage,
 };
 use anyhow::Result;
 use async_trait::async_trait;
-use comit::LockProtocol;
+use comit::{LockProtocol, TradingPair};
 
 #[async_trait]
 impl Load<SwapResource> for Storage {
     async fn load(&self, swap_id: LocalSwapId) -> Result<SwapResource> {
         let context: SwapContext = self.load(swap_id).await?;
+        let (trading_pair, decimals) = self
+            .db
+            .do_in_transaction(|conn| queries::get_trading_pair_of_swap(conn, swap_id))
+            .await?
+            // Swaps which were not set up from an order are BTC/DAI swaps.
+            .unwrap_or_else(|| (TradingPair::btc_dai(), 18));
+        let symbol = trading_pair.quote();
 
         let (alpha, beta) = match context {
             SwapContext {
@@ -23,7 +35,7 @@ impl Load<SwapResource> for Storage {
 
                 (
                     Protocol::hbit(tab.alpha.into()),
-                    Protocol::herc20_dai(tab.beta.amount),
+                    Protocol::herc20(symbol, tab.beta.amount, decimals),
                 )
             }
             SwapContext {
@@ -34,37 +46,38 @@ impl Load<SwapResource> for Storage {
                 let tab: Tables<Herc20, Hbit> = self.db.load_tables(swap_id).await?;
 
                 (
-                    Protocol::herc20_dai(tab.alpha.amount),
+                    Protocol::herc20(symbol, tab.alpha.amount, decimals),
                     Protocol::hbit(tab.beta.into()),
                 )
             }
-            _ => anyhow::bail!("unsupported combination of locking protocols"),
-        };
-
-        let mut swap_events = Vec::with_capacity(5);
-
-        if let Some(events) = self.hbit_events.lock().await.get(&swap_id) {
-            if let Some(tx) = events.fund.map(|e| e.location.txid) {
-                swap_events.push(SwapEvent::HbitFunded { tx })
-            }
+            SwapContext {
+                alpha: LockProtocol::Hbit,
+                beta: LockProtocol::Heth,
+                ..
+            } => {
+                let tab: Tables<Hbit, Heth> = self.db.load_tables(swap_id).await?;
 
-            if let Some(tx) = events.redeem.map(|e| e.transaction) {
-                swap_events.push(SwapEvent::HbitRedeemed { tx })
-            }
-        }
-        if let Some(events) = self.herc20_events.lock().await.get(&swap_id) {
-            if let Some(tx) = events.deploy.as_ref().map(|e| e.transaction) {
-                swap_events.push(SwapEvent::Herc20Deployed { tx })
+                (
+                    Protocol::hbit(tab.alpha.into()),
+                    Protocol::heth(tab.beta.amount),
+                )
             }
+            SwapContext {
+                alpha: LockProtocol::Heth,
+                beta: LockProtocol::Hbit,
+                ..
+            } => {
+                let tab: Tables<Heth, Hbit> = self.db.load_tables(swap_id).await?;
 
-            if let Some(tx) = events.fund.as_ref().map(|e| e.transaction) {
-                swap_events.push(SwapEvent::Herc20Funded { tx })
+                (
+                    Protocol::heth(tab.alpha.amount),
+                    Protocol::hbit(tab.beta.into()),
+                )
             }
+            _ => anyhow::bail!("unsupported combination of locking protocols"),
+        };
 
-            if let Some(tx) = events.redeem.as_ref().map(|e| e.transaction) {
-                swap_events.push(SwapEvent::Herc20Redeemed { tx })
-            }
-        }
+        let swap_events: Vec<SwapEvent> = self.load(swap_id).await?;
 
         Ok(SwapResource {
             role: context.role,
@@ -74,3 +87,57 @@ impl Load<SwapResource> for Storage {
         })
     }
 }
+
+#[async_trait]
+impl Load<Vec<SwapEvent>> for Storage {
+    async fn load(&self, swap_id: LocalSwapId) -> Result<Vec<SwapEvent>> {
+        self.db
+            .do_in_transaction(|conn| {
+                let mut events = Vec::with_capacity(5);
+
+                events.extend(
+                    HbitFundEvent::by_swap_id(conn, swap_id)?
+                        .map(|event| SwapEvent::from(hbit::Funded::from(event))),
+                );
+                events.extend(
+                    HbitRedeemEvent::by_swap_id(conn, swap_id)?
+                        .map(|event| SwapEvent::from(hbit::Redeemed::from(event))),
+                );
+                events.extend(
+                    HbitRefundEvent::by_swap_id(conn, swap_id)?
+                        .map(|event| SwapEvent::from(hbit::Refunded::from(event))),
+                );
+                events.extend(
+                    Herc20DeployEvent::by_swap_id(conn, swap_id)?
+                        .map(|event| SwapEvent::from(herc20::Deployed::from(event))),
+                );
+                events.extend(
+                    Herc20FundEvent::by_swap_id(conn, swap_id)?
+                        .map(|event| SwapEvent::from(herc20::Funded::from(event))),
+                );
+                events.extend(
+                    Herc20RedeemEvent::by_swap_id(conn, swap_id)?
+                        .map(|event| SwapEvent::from(herc20::Redeemed::from(event))),
+                );
+                events.extend(
+                    Herc20RefundEvent::by_swap_id(conn, swap_id)?
+                        .map(|event| SwapEvent::from(herc20::Refunded::from(event))),
+                );
+                events.extend(
+                    HethFundEvent::by_swap_id(conn, swap_id)?
+                        .map(|event| SwapEvent::from(heth::Funded::from(event))),
+                );
+                events.extend(
+                    HethRedeemEvent::by_swap_id(conn, swap_id)?
+                        .map(|event| SwapEvent::from(heth::Redeemed::from(event))),
+                );
+                events.extend(
+                    HethRefundEvent::by_swap_id(conn, swap_id)?
+                        .map(|event| SwapEvent::from(heth::Refunded::from(event))),
+                );
+
+                Ok(events)
+            })
+            .await
+    }
+}
@@ -0,0 +1,125 @@
+//! Implement traits to Load/Save the events emitted by the HTLCs of a swap.
+use crate::{
+    hbit, herc20, heth,
+    storage::{
+        db::queries::get_swap_fk, HbitFundEvent, HbitRedeemEvent, HbitRefundEvent,
+        Herc20DeployEvent, Herc20FundEvent, Herc20RedeemEvent, Herc20RefundEvent, HethFundEvent,
+        HethRedeemEvent, HethRefundEvent, InsertableHbitFundEvent, InsertableHbitRedeemEvent,
+        InsertableHbitRefundEvent, InsertableHerc20DeployEvent, InsertableHerc20FundEvent,
+        InsertableHerc20RedeemEvent, InsertableHerc20RefundEvent, InsertableHethFundEvent,
+        InsertableHethRedeemEvent, InsertableHethRefundEvent, Load, Save,
+    },
+    LocalSwapId, Storage,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+
+macro_rules! impl_load_save_event {
+    ($event:ty, $table:ident, $insertable:ident) => {
+        #[async_trait]
+        impl Load<Option<$event>> for Storage {
+            async fn load(&self, swap_id: LocalSwapId) -> Result<Option<$event>> {
+                let event = self
+                    .db
+                    .do_in_transaction(|conn| $table::by_swap_id(conn, swap_id))
+                    .await?;
+
+                Ok(event.map(Into::into))
+            }
+        }
+
+        #[async_trait]
+        impl Save<$event> for Storage {
+            async fn save(&self, event: $event, swap_id: LocalSwapId) -> Result<()> {
+                self.db
+                    .do_in_transaction(|conn| {
+                        let swap_fk = get_swap_fk(conn, swap_id)?;
+                        $insertable::new(swap_fk, event).insert(conn)
+                    })
+                    .await
+            }
+        }
+    };
+}
+
+impl_load_save_event!(hbit::Funded, HbitFundEvent, InsertableHbitFundEvent);
+impl_load_save_event!(hbit::Redeemed, HbitRedeemEvent, InsertableHbitRedeemEvent);
+impl_load_save_event!(hbit::Refunded, HbitRefundEvent, InsertableHbitRefundEvent);
+impl_load_save_event!(
+    herc20::Deployed,
+    Herc20DeployEvent,
+    InsertableHerc20DeployEvent
+);
+impl_load_save_event!(herc20::Funded, Herc20FundEvent, InsertableHerc20FundEvent);
+impl_load_save_event!(
+    herc20::Redeemed,
+    Herc20RedeemEvent,
+    InsertableHerc20RedeemEvent
+);
+impl_load_save_event!(
+    herc20::Refunded,
+    Herc20RefundEvent,
+    InsertableHerc20RefundEvent
+);
+impl_load_save_event!(heth::Funded, HethFundEvent, InsertableHethFundEvent);
+impl_load_save_event!(heth::Redeemed, HethRedeemEvent, InsertableHethRedeemEvent);
+impl_load_save_event!(heth::Refunded, HethRefundEvent, InsertableHethRefundEvent);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::InsertableSwap;
+    use comit::{Role, Secret};
+    use libp2p::PeerId;
+    use std::str::FromStr;
+    use time::OffsetDateTime;
+
+    #[tokio::test]
+    async fn recorded_event_is_loaded() {
+        let storage = Storage::test();
+        let swap_id = LocalSwapId::default();
+        let swap = InsertableSwap::new(
+            swap_id,
+            PeerId::random(),
+            Role::Alice,
+            OffsetDateTime::now_utc(),
+        );
+        storage
+            .db
+            .do_in_transaction(|conn| swap.insert(conn))
+            .await
+            .unwrap();
+
+        let not_yet_recorded: Option<hbit::Redeemed> = storage.load(swap_id).await.unwrap();
+        assert!(not_yet_recorded.is_none());
+
+        let redeemed = hbit::Redeemed {
+            transaction: ::bitcoin::Txid::from_str(
+                "a98ab3e0e9ab4ab5e77b6a40f0e4c4d6e0e9e88a50e8bbab27c6b7d2e4d8a2d1",
+            )
+            .unwrap(),
+            secret: Secret::from_str(
+                "68d627971643a6f97f27c58957826fcba853ec2077fd10ec6b93d8e61deb4c66",
+            )
+            .unwrap(),
+            block_height: Some(654_321),
+        };
+        storage.save(redeemed, swap_id).await.unwrap();
+
+        let loaded: Option<hbit::Redeemed> = storage.load(swap_id).await.unwrap();
+        assert_eq!(loaded, Some(redeemed));
+    }
+
+    #[tokio::test]
+    async fn saving_event_of_unknown_swap_fails() {
+        let storage = Storage::test();
+        let refunded = herc20::Refunded {
+            transaction: Default::default(),
+            block_height: None,
+        };
+
+        let result = storage.save(refunded, LocalSwapId::default()).await;
+
+        assert!(result.is_err());
+    }
+}
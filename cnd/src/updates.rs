@@ -0,0 +1,76 @@
+//! Notifications about changes to orders and swaps.
+//!
+//! Other components publish an [`Update`] whenever they change the state of an
+//! order or swap. The HTTP API forwards them to its subscribers so that clients
+//! do not need to poll.
+
+use crate::{
+    http_api::{ActionName, SwapEvent},
+    LocalSwapId,
+};
+use comit::{orderpool::Match, OrderId};
+use tokio::sync::broadcast;
+
+/// Updates not consumed by a subscriber within this many subsequent updates
+/// are dropped for that subscriber.
+const CAPACITY: usize = 256;
+
+#[derive(Debug, Clone)]
+pub enum Update {
+    /// The state of an order changed, i.e. it was created or (parts of) it
+    /// moved to settling, closed, failed or cancelled.
+    Order(OrderId),
+    /// One of our orders was matched against an order of another peer.
+    Match(Match),
+    /// A swap event was recorded.
+    SwapEvent(LocalSwapId, SwapEvent),
+    /// The next action of a swap changed, `None` if there is currently
+    /// nothing to do.
+    NextAction(LocalSwapId, Option<ActionName>),
+}
+
+/// A broadcast channel for [`Update`]s.
+#[derive(Debug, Clone)]
+pub struct Updates(broadcast::Sender<Update>);
+
+impl Updates {
+    /// Notify all current subscribers about an update.
+    ///
+    /// Not having any subscribers is not an error, the update is dropped.
+    pub fn publish(&self, update: Update) {
+        let _ = self.0.send(update);
+    }
+
+    /// Subscribe to all updates published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Update> {
+        self.0.subscribe()
+    }
+}
+
+impl Default for Updates {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+
+        Self(sender)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn subscriber_receives_updates_published_after_subscribing() {
+        let updates = Updates::default();
+        let order_id = OrderId::random();
+
+        updates.publish(Update::Order(order_id));
+        let mut receiver = updates.subscribe();
+        updates.publish(Update::Order(order_id));
+
+        let update = receiver.recv().await.unwrap();
+
+        assert!(matches!(update, Update::Order(id) if id == order_id));
+        assert!(receiver.try_recv().is_err());
+    }
+}
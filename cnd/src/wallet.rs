@@ -0,0 +1,125 @@
+//! An optional hot wallet that executes the actions of a swap inside cnd.
+//!
+//! By default, cnd only tells the user which transaction to sign and broadcast
+//! next. With the wallet enabled, cnd does this itself with keys derived from
+//! its seed. Bitcoin is held in a bitcoind wallet, ether and ERC20 tokens by a
+//! single account whose transactions are signed by cnd and broadcast through
+//! geth.
+
+mod bitcoin;
+mod ethereum;
+
+use crate::{bitcoin_fees::BitcoinFees, config::Settings, storage::RootSeed};
+use anyhow::Result;
+use comit::swap::Action;
+use serde::Serialize;
+use std::{fmt, sync::Arc};
+use tokio::sync::Mutex;
+
+#[derive(Clone, Debug)]
+pub struct Wallet {
+    bitcoin: Arc<bitcoin::Wallet>,
+    ethereum: Arc<ethereum::Wallet>,
+    /// Serializes the execution of actions so that we do not hand out the
+    /// same nonce or coins to two transactions.
+    lock: Arc<Mutex<()>>,
+}
+
+impl Wallet {
+    /// The bitcoin are always held by the configured bitcoind, even if the
+    /// chain is followed through Esplora or Electrum. Config validation
+    /// ensures that bitcoind is configured explicitly in that case.
+    pub async fn new(seed: RootSeed, settings: &Settings) -> Result<Self> {
+        let bitcoin = bitcoin::Wallet::new(
+            seed,
+            settings.bitcoin.bitcoind.node_url.clone(),
+            settings.bitcoin.network,
+        )
+        .await?;
+        let ethereum = ethereum::Wallet::new(
+            seed,
+            settings.ethereum.geth.node_url.clone(),
+            settings.ethereum.chain_id,
+        )
+        .await?;
+
+        tracing::info!(
+            "built-in wallet enabled, bitcoind wallet: {}, ethereum account: {}",
+            bitcoin.name(),
+            ethereum.account()
+        );
+
+        Ok(Self {
+            bitcoin: Arc::new(bitcoin),
+            ethereum: Arc::new(ethereum),
+            lock: Arc::new(Mutex::new(())),
+        })
+    }
+
+    /// Acquire exclusive access to the wallet for the duration of the returned
+    /// guard.
+    pub async fn lock(&self) -> tokio::sync::MutexGuard<'_, ()> {
+        self.lock.lock().await
+    }
+
+    /// Sign and broadcast the transaction that executes the given action.
+    ///
+    /// Callers are expected to hold the guard returned from [`Wallet::lock`].
+    pub async fn execute(
+        &self,
+        action: Action,
+        bitcoin_fees: &BitcoinFees,
+    ) -> Result<TransactionId> {
+        let transaction_id = match action {
+            Action::HbitFund(action) => {
+                let rate = bitcoin_fees.get_per_vbyte_rate().await?;
+                let txid = self
+                    .bitcoin
+                    .send_to_address(action.to, action.amount, rate)
+                    .await?;
+
+                TransactionId::Bitcoin(txid)
+            }
+            Action::HbitRedeem(action, _) | Action::HbitRefund(action) => {
+                let rate = bitcoin_fees.get_per_vbyte_rate().await?;
+                let transaction = action.sign(&crate::SECP, rate)?;
+                let txid = self.bitcoin.send_raw_transaction(transaction).await?;
+
+                TransactionId::Bitcoin(txid)
+            }
+            Action::Herc20Deploy(action) | Action::HethFund(action) => {
+                let hash = self.ethereum.deploy_contract(action).await?;
+
+                TransactionId::Ethereum(hash)
+            }
+            Action::Herc20Fund(action)
+            | Action::Herc20Redeem(action, _)
+            | Action::Herc20Refund(action)
+            | Action::HethRedeem(action, _)
+            | Action::HethRefund(action) => {
+                let hash = self.ethereum.call_contract(action).await?;
+
+                TransactionId::Ethereum(hash)
+            }
+        };
+
+        Ok(transaction_id)
+    }
+}
+
+/// The id of a transaction broadcast by the wallet.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum TransactionId {
+    Bitcoin(::bitcoin::Txid),
+    Ethereum(crate::ethereum::Hash),
+}
+
+impl fmt::Display for TransactionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionId::Bitcoin(txid) => write!(f, "{}", txid),
+            TransactionId::Ethereum(hash) => write!(f, "{}", hash),
+        }
+    }
+}
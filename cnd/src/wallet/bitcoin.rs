@@ -0,0 +1,195 @@
+use crate::{btsieve::jsonrpc, storage::RootSeed};
+use ::bitcoin::{
+    consensus::encode::serialize_hex, secp256k1::SecretKey, Address, Amount, PrivateKey,
+    Transaction, Txid,
+};
+use anyhow::{Context, Result};
+use comit::ledger;
+use serde::Deserialize;
+use url::Url;
+
+/// Domain separator for deriving the HD seed of the bitcoind wallet from the
+/// seed of cnd.
+const WALLET_SEED_TAG: &[u8] = b"BITCOIN_WALLET";
+
+/// A wallet managed by bitcoind whose HD seed is derived from the seed of cnd.
+///
+/// The bitcoind node needs to accept JSON-RPC requests, i.e. the RPC
+/// credentials have to be part of the configured node URL.
+#[derive(Debug)]
+pub struct Wallet {
+    /// The wallet is named `cnd_x` with `x` being the first 4 bytes of the
+    /// hash of the wallet seed.
+    name: String,
+    client: jsonrpc::Client,
+    network: ledger::Bitcoin,
+}
+
+impl Wallet {
+    pub async fn new(seed: RootSeed, url: Url, network: ledger::Bitcoin) -> Result<Self> {
+        let wallet_seed = seed.sha256_with_seed(&[WALLET_SEED_TAG]);
+        let wallet = Self {
+            name: format!("cnd_{}", hex::encode(&wallet_seed[..4])),
+            client: jsonrpc::Client::new(url),
+            network,
+        };
+
+        wallet.assert_network().await?;
+        wallet.init(wallet_seed).await?;
+
+        Ok(wallet)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Create the wallet on first use, later on it is only loaded.
+    async fn init(&self, wallet_seed: [u8; 32]) -> Result<()> {
+        if self.wallet_info().await.is_ok() {
+            return Ok(());
+        }
+
+        if let Ok(wallet) = self.load_wallet().await {
+            tracing::debug!("loaded bitcoind wallet {}", wallet.name);
+            return Ok(());
+        }
+
+        let wallet = self
+            .client
+            .send::<_, WalletName>(jsonrpc::Request::new("createwallet", vec![
+                jsonrpc::serialize(&self.name)?,
+                jsonrpc::serialize(false)?, // disable_private_keys
+                jsonrpc::serialize(true)?,  // blank
+            ]))
+            .await
+            .context("failed to create bitcoind wallet")?;
+        tracing::info!("created bitcoind wallet {}", wallet.name);
+
+        let key = SecretKey::from_slice(&wallet_seed).context("wallet seed is not a valid key")?;
+        let wif = PrivateKey {
+            compressed: true,
+            network: self.network.into(),
+            key,
+        }
+        .to_wif();
+
+        self.client
+            .send_with_path::<_, Option<()>>(
+                &self.path(),
+                jsonrpc::Request::new("sethdseed", vec![
+                    jsonrpc::serialize(true)?, // newkeypool
+                    jsonrpc::serialize(wif)?,
+                ]),
+            )
+            .await
+            .context("failed to set HD seed of bitcoind wallet")?;
+
+        // bitcoind's responses contain a `result` of `null` alongside an
+        // `error`, hence we double check that the seed was set.
+        if self.wallet_info().await?.hdseedid.is_none() {
+            anyhow::bail!("failed to set HD seed of bitcoind wallet {}", self.name)
+        }
+
+        Ok(())
+    }
+
+    /// Send the amount to the address, bitcoind selects the inputs and adds a
+    /// change output.
+    pub async fn send_to_address(
+        &self,
+        address: Address,
+        amount: Amount,
+        sat_per_vbyte: Amount,
+    ) -> Result<Txid> {
+        self.assert_network().await?;
+
+        let txid = self
+            .client
+            .send_with_path(
+                &self.path(),
+                jsonrpc::Request::new("sendtoaddress", vec![
+                    jsonrpc::serialize(address)?,
+                    jsonrpc::serialize(amount.as_btc())?,
+                    jsonrpc::serialize(None::<String>)?, // comment
+                    jsonrpc::serialize(None::<String>)?, // comment_to
+                    jsonrpc::serialize(false)?,          // subtractfeefromamount
+                    jsonrpc::serialize(None::<bool>)?,   // replaceable
+                    jsonrpc::serialize(None::<u32>)?,    // conf_target
+                    jsonrpc::serialize("unset")?,        // estimate_mode
+                    jsonrpc::serialize(None::<bool>)?,   // avoid_reuse
+                    jsonrpc::serialize(sat_per_vbyte.as_sat())?, // fee_rate
+                ]),
+            )
+            .await
+            .context("failed to send to address")?;
+
+        Ok(txid)
+    }
+
+    pub async fn send_raw_transaction(&self, transaction: Transaction) -> Result<Txid> {
+        self.assert_network().await?;
+
+        let txid = self
+            .client
+            .send(jsonrpc::Request::new("sendrawtransaction", vec![
+                serialize_hex(&transaction),
+            ]))
+            .await
+            .context("failed to send raw transaction")?;
+
+        Ok(txid)
+    }
+
+    async fn wallet_info(&self) -> Result<WalletInfo> {
+        self.client
+            .send_with_path(
+                &self.path(),
+                jsonrpc::Request::new("getwalletinfo", Vec::<()>::new()),
+            )
+            .await
+    }
+
+    async fn load_wallet(&self) -> Result<WalletName> {
+        self.client
+            .send(jsonrpc::Request::new("loadwallet", vec![&self.name]))
+            .await
+    }
+
+    async fn assert_network(&self) -> Result<()> {
+        let info = self
+            .client
+            .send::<_, BlockchainInfo>(jsonrpc::Request::new("getblockchaininfo", Vec::<()>::new()))
+            .await?;
+
+        if info.chain != self.network {
+            anyhow::bail!(
+                "wrong network: expected {}, got {}",
+                self.network,
+                info.chain
+            );
+        }
+
+        Ok(())
+    }
+
+    fn path(&self) -> String {
+        format!("/wallet/{}", self.name)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct WalletInfo {
+    hdseedid: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WalletName {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockchainInfo {
+    #[serde(with = "ledger::bitcoin::bitcoind_jsonrpc_network")]
+    chain: ledger::Bitcoin,
+}
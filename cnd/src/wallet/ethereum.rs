@@ -0,0 +1,195 @@
+use crate::{
+    asset,
+    btsieve::jsonrpc,
+    ethereum::{Address, ChainId, Hash},
+    storage::RootSeed,
+};
+use anyhow::{Context, Result};
+use clarity::Uint256;
+use comit::swap::actions::{CallContract, DeployContract};
+use url::Url;
+
+/// Domain separator for deriving the private key of the wallet from the seed of
+/// cnd.
+const WALLET_SEED_TAG: &[u8] = b"ETHEREUM_WALLET";
+
+/// A wallet holding a single private key derived from the seed of cnd.
+///
+/// Transactions are signed locally and broadcast through geth.
+#[derive(Debug)]
+pub struct Wallet {
+    private_key: clarity::PrivateKey,
+    client: jsonrpc::Client,
+}
+
+impl Wallet {
+    pub async fn new(seed: RootSeed, url: Url, chain_id: ChainId) -> Result<Self> {
+        let private_key =
+            clarity::PrivateKey::from_slice(&seed.sha256_with_seed(&[WALLET_SEED_TAG]))
+                .map_err(|e| anyhow::anyhow!("{}", e))
+                .context("failed to create private key from wallet seed")?;
+
+        let wallet = Self {
+            private_key,
+            client: jsonrpc::Client::new(url),
+        };
+
+        wallet.assert_chain(chain_id).await?;
+
+        Ok(wallet)
+    }
+
+    pub fn account(&self) -> Address {
+        let pk = self.private_key.to_public_key().expect("cannot fail");
+
+        let mut bytes = [0u8; 20];
+        bytes.copy_from_slice(pk.as_bytes());
+
+        Address::from(bytes)
+    }
+
+    pub async fn deploy_contract(
+        &self,
+        DeployContract {
+            data,
+            amount,
+            gas_limit,
+            chain_id,
+        }: DeployContract,
+    ) -> Result<Hash> {
+        self.sign_and_send(
+            clarity::Address::default(),
+            amount,
+            data,
+            gas_limit,
+            chain_id,
+        )
+        .await
+    }
+
+    pub async fn call_contract(
+        &self,
+        CallContract {
+            to,
+            data,
+            gas_limit,
+            chain_id,
+        }: CallContract,
+    ) -> Result<Hash> {
+        let to = clarity::Address::from_slice(to.as_bytes())
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .context("failed to convert contract address")?;
+
+        self.sign_and_send(
+            to,
+            asset::Ether::zero(),
+            data.unwrap_or_default(),
+            gas_limit,
+            chain_id,
+        )
+        .await
+    }
+
+    async fn sign_and_send(
+        &self,
+        to: clarity::Address,
+        value: asset::Ether,
+        data: Vec<u8>,
+        gas_limit: u64,
+        chain_id: ChainId,
+    ) -> Result<Hash> {
+        self.assert_chain(chain_id).await?;
+
+        let nonce = self.transaction_count().await?;
+        let gas_price = self.gas_price().await?;
+
+        let transaction = clarity::Transaction {
+            nonce,
+            gas_price,
+            gas_limit: gas_limit.into(),
+            to,
+            value: Uint256::from_bytes_le(&value.to_bytes()),
+            data,
+            signature: None,
+        }
+        .sign(&self.private_key, Some(u32::from(chain_id) as u64));
+
+        let transaction_hex = format!(
+            "0x{}",
+            hex::encode(
+                transaction
+                    .to_bytes()
+                    .context("failed to serialize signed transaction to bytes")?
+            )
+        );
+
+        let hash = self
+            .client
+            .send(jsonrpc::Request::new("eth_sendRawTransaction", vec![
+                transaction_hex,
+            ]))
+            .await
+            .context("failed to send raw transaction")?;
+
+        Ok(hash)
+    }
+
+    async fn transaction_count(&self) -> Result<Uint256> {
+        let count = self
+            .client
+            .send::<_, String>(jsonrpc::Request::new("eth_getTransactionCount", vec![
+                jsonrpc::serialize(self.account())?,
+                jsonrpc::serialize("pending")?,
+            ]))
+            .await?;
+
+        parse_quantity(&count)
+    }
+
+    async fn gas_price(&self) -> Result<Uint256> {
+        let gas_price = self
+            .client
+            .send::<_, String>(jsonrpc::Request::new("eth_gasPrice", Vec::<()>::new()))
+            .await?;
+
+        parse_quantity(&gas_price)
+    }
+
+    async fn assert_chain(&self, expected: ChainId) -> Result<()> {
+        let version = self
+            .client
+            .send::<_, String>(jsonrpc::Request::new("net_version", Vec::<()>::new()))
+            .await
+            .context("failed to fetch net version")?;
+        let actual = ChainId::from(version.parse::<u32>()?);
+
+        if expected != actual {
+            anyhow::bail!("wrong chain id: expected {}, got {}", expected, actual);
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a hex encoded quantity as returned by the Ethereum JSON-RPC API.
+fn parse_quantity(quantity: &str) -> Result<Uint256> {
+    let digits = quantity.trim_start_matches("0x");
+    let quantity = Uint256::from_str_radix(digits, 16)
+        .with_context(|| format!("failed to parse {} as quantity", quantity))?;
+
+    Ok(quantity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_encoded_quantities() {
+        assert_eq!(parse_quantity("0x0").unwrap(), Uint256::from(0u64));
+        assert_eq!(
+            parse_quantity("0x4a817c800").unwrap(),
+            Uint256::from(20_000_000_000u64)
+        );
+    }
+}
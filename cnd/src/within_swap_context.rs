@@ -2,7 +2,7 @@
 macro_rules! within_swap_context {
     ($swap_context:expr, $fn:expr) => {{
         use crate::{storage::SwapContext, LockProtocol, Role};
-        use comit::{herc20, swap::hbit};
+        use comit::{herc20, heth, swap::hbit};
 
         let swap_context: SwapContext = $swap_context;
 
@@ -59,6 +59,58 @@ macro_rules! within_swap_context {
 
                 $fn
             }
+            SwapContext {
+                alpha: LockProtocol::Heth,
+                beta: LockProtocol::Hbit,
+                role: Role::Alice,
+                ..
+            } => {
+                #[allow(dead_code)]
+                type AlphaParams = heth::Params;
+                #[allow(dead_code)]
+                type BetaParams = hbit::Params;
+
+                $fn
+            }
+            SwapContext {
+                alpha: LockProtocol::Heth,
+                beta: LockProtocol::Hbit,
+                role: Role::Bob,
+                ..
+            } => {
+                #[allow(dead_code)]
+                type AlphaParams = heth::Params;
+                #[allow(dead_code)]
+                type BetaParams = hbit::Params;
+
+                $fn
+            }
+            SwapContext {
+                alpha: LockProtocol::Hbit,
+                beta: LockProtocol::Heth,
+                role: Role::Alice,
+                ..
+            } => {
+                #[allow(dead_code)]
+                type AlphaParams = hbit::Params;
+                #[allow(dead_code)]
+                type BetaParams = heth::Params;
+
+                $fn
+            }
+            SwapContext {
+                alpha: LockProtocol::Hbit,
+                beta: LockProtocol::Heth,
+                role: Role::Bob,
+                ..
+            } => {
+                #[allow(dead_code)]
+                type AlphaParams = hbit::Params;
+                #[allow(dead_code)]
+                type BetaParams = heth::Params;
+
+                $fn
+            }
             _ => unimplemented!("protocol combination not supported: {:?}", swap_context),
         }
     }};
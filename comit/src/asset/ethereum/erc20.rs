@@ -2,7 +2,7 @@ use crate::{
     asset,
     asset::ethereum::{Error, FromWei, TryFromWei},
     ethereum::{Address, U256},
-    order::{Price, Quantity},
+    order::{Price, Quantity, SATS_PER_BTC},
 };
 use num::{pow::Pow, BigUint, Num, Zero};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
@@ -53,11 +53,12 @@ impl Erc20Quantity {
 impl Mul<Price<asset::Bitcoin, Erc20Quantity>> for Quantity<asset::Bitcoin> {
     type Output = Erc20Quantity;
 
+    /// Rounds down to the smallest unit of the token.
     fn mul(self, rhs: Price<asset::Bitcoin, Erc20Quantity>) -> Self::Output {
-        let wei_per_sat = rhs.wei_per_sat().0;
+        let wei_per_btc = rhs.wei_per_btc().0;
         let sat = self.sats();
 
-        let value = Erc20Quantity(wei_per_sat * sat);
+        let value = Erc20Quantity(wei_per_btc * sat / SATS_PER_BTC);
 
         debug_assert!(value <= Erc20Quantity::max_value());
 
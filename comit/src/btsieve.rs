@@ -1,19 +1,42 @@
 pub mod bitcoin;
+mod block_store;
+mod chain_follower;
+mod confirmations;
 pub mod ethereum;
-mod jsonrpc;
+pub mod jsonrpc;
+mod mempool;
+mod multi_connector;
+
+pub use self::{
+    block_store::BlockStore,
+    chain_follower::ChainFollower,
+    confirmations::{restart_on_reorg, wait_for_confirmations, Reorg},
+    multi_connector::MultiConnector,
+};
 
 use crate::Never;
 use anyhow::Result;
 use async_trait::async_trait;
+use futures::FutureExt;
 use genawaiter::sync::{Co, Gen};
 use std::{collections::HashSet, future::Future, hash::Hash, time::Duration};
 use time::OffsetDateTime;
+use tokio::sync::broadcast::{self, RecvError};
 
 #[async_trait]
 pub trait LatestBlock: Send + Sync + 'static {
     type Block;
 
     async fn latest_block(&self) -> Result<Self::Block>;
+
+    /// Subscribes to the blocks added to the chain, oldest first.
+    ///
+    /// Only a connector that follows the chain, i.e. the [`ChainFollower`],
+    /// pushes blocks. All others return `None`, consumers have to fetch the
+    /// latest block themselves instead.
+    fn new_blocks(&self) -> Option<broadcast::Receiver<Self::Block>> {
+        None
+    }
 }
 
 #[async_trait]
@@ -31,6 +54,53 @@ pub trait ConnectedNetwork: Send + Sync + 'static {
     async fn connected_network(&self) -> Result<Self::Network>;
 }
 
+/// Provides the height of the latest block the node knows about.
+///
+/// This is cheaper than fetching the latest block and allows us to compare
+/// the tips of several nodes.
+#[async_trait]
+pub trait TipHeight: Send + Sync + 'static {
+    async fn tip_height(&self) -> Result<u64>;
+}
+
+/// Notifies about new blocks.
+///
+/// This allows us to wait for new blocks instead of repeatedly fetching the
+/// latest block. Implementations may resolve without a new block being
+/// available, e.g. after a timeout, hence consumers always have to check the
+/// latest block themselves.
+#[async_trait]
+pub trait BlockNotifications: Send + Sync + 'static {
+    async fn wait_for_new_block(&self) -> Result<()>;
+}
+
+/// Block notifications for nodes that cannot push new blocks to us.
+///
+/// Assumes that there might be a new block after every interval.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Polling {
+    interval: Duration,
+}
+
+impl Polling {
+    pub fn new(interval: Duration) -> Self {
+        Self { interval }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+}
+
+#[async_trait]
+impl BlockNotifications for Polling {
+    async fn wait_for_new_block(&self) -> Result<()> {
+        tokio::time::delay_for(self.interval).await;
+
+        Ok(())
+    }
+}
+
 /// Checks if a given block predates a certain timestamp.
 pub trait Predates {
     fn predates(&self, timestamp: OffsetDateTime) -> bool;
@@ -55,21 +125,25 @@ pub trait PreviousBlockHash {
 /// To do this reliably, we start with the current latest block and walk the
 /// blockchain backwards until we pass the given timestamp.
 ///
-/// To make sure we don't miss any blocks as we keep fetching the latest block,
-/// we continuously check if we've seen a block's parent before. If we don't we
-/// walk back the ancestor chain again until we've seen a parent or we are past
-/// the given timestamp again.
+/// If the connector pushes new blocks, see [`LatestBlock::new_blocks`], we
+/// take them from there and only walk back if we missed some. Otherwise, we
+/// only fetch the latest block again once the connector notifies us about a
+/// new block. To make sure we don't miss any blocks as we keep fetching the
+/// latest block, we continuously check if we've seen a block's parent before.
+/// If we don't we walk back the ancestor chain again until we've seen a parent
+/// or we are past the given timestamp again.
 pub fn fetch_blocks_since<'a, C, B, H>(
     connector: &'a C,
     start_of_swap: OffsetDateTime,
-    poll_interval: Duration,
 ) -> Gen<B, (), impl Future<Output = Result<Never>> + 'a>
 where
-    C: LatestBlock<Block = B> + BlockByHash<Block = B, BlockHash = H>,
+    C: LatestBlock<Block = B> + BlockByHash<Block = B, BlockHash = H> + BlockNotifications,
     B: Predates + BlockHash<BlockHash = H> + PreviousBlockHash<BlockHash = H> + Clone + 'a,
     H: Eq + Hash + Copy,
 {
     Gen::new(|co| async move {
+        // Subscribe first, the blocks added whilst we walk back are pushed
+        let mut new_blocks = connector.new_blocks();
         let block = connector.latest_block().await?;
 
         // Look back in time until we get a block that predates start_of_swap.
@@ -78,28 +152,48 @@ where
             block,
             |_| true, // initially, yield all blocks because we haven't seen any of them
             connector,
-            poll_interval,
             &co,
         )
         .await?;
 
         // Look forward in time, but keep going back for missed blocks
         loop {
-            let block = connector.latest_block().await?;
-
-            let missed_blocks = walk_back_until(
-                seen_block_or_predates_start_of_swap(&seen_blocks, start_of_swap),
-                block,
-                |b| !seen_blocks.contains(b), // only yield if we haven't seen the block before
-                connector,
-                poll_interval,
-                &co,
-            )
-            .await?;
+            let block = match new_blocks.as_mut() {
+                Some(receiver) => match receiver.recv().await {
+                    Ok(block) => block,
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::debug!("missed {} pushed blocks, walking back", missed);
+                        connector.latest_block().await?
+                    }
+                    Err(RecvError::Closed) => {
+                        new_blocks = None;
+                        connector.latest_block().await?
+                    }
+                },
+                None => connector.latest_block().await?,
+            };
 
-            seen_blocks.extend(missed_blocks);
+            let block_hash = block.block_hash();
+            if seen_blocks.contains(&block.previous_block_hash()) {
+                if seen_blocks.insert(block_hash) {
+                    co.yield_(block).await;
+                }
+            } else if !seen_blocks.contains(&block_hash) {
+                let missed_blocks = walk_back_until(
+                    seen_block_or_predates_start_of_swap(&seen_blocks, start_of_swap),
+                    block,
+                    |b| !seen_blocks.contains(b), // only yield if we haven't seen the block before
+                    connector,
+                    &co,
+                )
+                .await?;
+
+                seen_blocks.extend(missed_blocks);
+            }
 
-            tokio::time::delay_for(poll_interval).await;
+            if new_blocks.is_none() {
+                connector.wait_for_new_block().await?;
+            }
         }
     })
 }
@@ -114,11 +208,10 @@ async fn walk_back_until<C, P, Y, B, H>(
     starting_block: B,
     should_yield: Y,
     connector: &C,
-    poll_interval: Duration,
     co: &Co<B>,
 ) -> Result<HashSet<H>>
 where
-    C: BlockByHash<Block = B, BlockHash = H> + LatestBlock<Block = B>,
+    C: BlockByHash<Block = B, BlockHash = H> + LatestBlock<Block = B> + BlockNotifications,
     P: Fn(&B) -> bool,
     Y: Fn(&H) -> bool,
     B: BlockHash<BlockHash = H> + PreviousBlockHash<BlockHash = H>,
@@ -129,7 +222,9 @@ where
     let mut current_blockhash = starting_block.block_hash();
     let mut current_block = starting_block;
 
-    let mut delay_until_fetch_latest_block_again = tokio::time::delay_for(poll_interval);
+    // Walking back can take a while, hence we keep an eye on new blocks whilst
+    // doing so.
+    let mut new_block = connector.wait_for_new_block();
 
     loop {
         seen_blocks.insert(current_blockhash);
@@ -147,7 +242,9 @@ where
             return Ok(seen_blocks);
         }
 
-        if delay_until_fetch_latest_block_again.is_elapsed() {
+        if let Some(result) = (&mut new_block).now_or_never() {
+            result?;
+
             let latest_block = connector.latest_block().await?;
             let latest_block_hash = latest_block.block_hash();
 
@@ -156,7 +253,7 @@ where
                 co.yield_(latest_block).await;
             }
 
-            delay_until_fetch_latest_block_again = tokio::time::delay_for(poll_interval)
+            new_block = connector.wait_for_new_block();
         }
 
         current_block = connector.block_by_hash(current_blockhash).await?
@@ -216,7 +313,7 @@ mod tests {
         let connector =
             FakeConnector::new(blocks, 50, INFURA_LATENCY, ETHEREUM_MAINNET_MINING_SPEED);
 
-        let gen = fetch_blocks_since(&connector, start_of_swap, ZERO_POLL_INTERVAL);
+        let gen = fetch_blocks_since(&connector, start_of_swap);
         let yielded_blocks = fallible_generator_to_try_stream(gen)
             .map_ok(|b| b.number)
             .try_take_while(|n| future::ready(Ok(*n != 52)))
@@ -239,7 +336,7 @@ mod tests {
         let start_of_swap = blocks[20].timestamp;
         let connector = FakeConnector::new(blocks, 50, Duration::from_secs(0), mining_speed);
 
-        let gen = fetch_blocks_since(&connector, start_of_swap, ZERO_POLL_INTERVAL);
+        let gen = fetch_blocks_since(&connector, start_of_swap);
         let yielded_blocks = fallible_generator_to_try_stream(gen)
             .map_ok(|b| b.number)
             .try_take_while(|n| future::ready(Ok(*n != 65)))
@@ -337,6 +434,13 @@ mod tests {
         }
     }
 
+    #[async_trait]
+    impl BlockNotifications for FakeConnector {
+        async fn wait_for_new_block(&self) -> Result<()> {
+            Polling::new(ZERO_POLL_INTERVAL).wait_for_new_block().await
+        }
+    }
+
     #[async_trait]
     impl LatestBlock for FakeConnector {
         type Block = FakeBlock;
@@ -1,26 +1,112 @@
+mod any_connector;
 mod bitcoind_connector;
 mod cache;
+mod electrum_connector;
+mod esplora_connector;
+mod zmq;
 
 pub use self::{
+    any_connector::AnyConnector,
     bitcoind_connector::{BitcoindConnector, ChainInfo},
     cache::Cache,
+    electrum_connector::ElectrumConnector,
+    esplora_connector::EsploraConnector,
 };
 use crate::{
     btsieve::{
-        fetch_blocks_since, BlockByHash, BlockHash, ConnectedNetwork, LatestBlock, Predates,
-        PreviousBlockHash,
+        fetch_blocks_since, wait_for_confirmations, BlockByHash, BlockHash, BlockNotifications,
+        ConnectedNetwork, LatestBlock, Predates, PreviousBlockHash,
     },
-    identity, ledger,
+    identity, ledger, Timestamp,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use bitcoin::{
+    self,
+    blockdata::{
+        constants::genesis_block,
+        script::{read_scriptint, Instruction},
+    },
+    hashes::{sha256, Hash as _},
+    OutPoint, Script, Txid,
 };
-use anyhow::Result;
-use bitcoin::{self, OutPoint};
 use genawaiter::GeneratorState;
-use std::time::Duration;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    time::Duration,
+};
 use time::OffsetDateTime;
 
 type Hash = bitcoin::BlockHash;
 type Block = bitcoin::Block;
 
+/// A transaction that pays to or spends from a script.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScriptTransaction {
+    pub txid: Txid,
+    /// Height of the block that included the transaction, `None` if the
+    /// transaction is still in the mempool.
+    pub height: Option<u32>,
+}
+
+/// Looks up the transactions of a script.
+///
+/// Only servers that index transactions by script, such as Esplora and
+/// Electrum, can answer this without scanning the blockchain. Other connectors
+/// return `None`, the watchers scan the blockchain for them instead.
+#[async_trait]
+pub trait TransactionsByScript: Send + Sync + 'static {
+    async fn transactions_by_script(
+        &self,
+        script: &Script,
+    ) -> Result<Option<Vec<ScriptTransaction>>>;
+    async fn transaction_by_txid(&self, txid: Txid) -> Result<bitcoin::Transaction>;
+}
+
+/// Gives access to the transactions that are waiting to be mined.
+///
+/// The mempool of a node is only an approximation of what will be mined next:
+/// Transactions in it may be replaced or dropped, hence a transaction found in
+/// the mempool must not be treated as final.
+#[async_trait]
+pub trait Mempool: Send + Sync + 'static {
+    async fn mempool_txids(&self) -> Result<Vec<Txid>>;
+    async fn mempool_transaction(&self, txid: Txid) -> Result<bitcoin::Transaction>;
+}
+
+/// Computes the hash under which Electrum and Esplora index the transactions
+/// of a script: The SHA256 hash of the script in reversed byte order.
+pub fn script_hash(script: &Script) -> String {
+    let mut hash = sha256::Hash::hash(script.as_bytes()).into_inner();
+    hash.reverse();
+
+    hex::encode(hash)
+}
+
+/// Identifies the network by the hash of its genesis block.
+fn network_from_genesis_hash(genesis_hash: Hash) -> Result<ledger::Bitcoin> {
+    use ledger::Bitcoin::*;
+
+    [Mainnet, Testnet, Regtest]
+        .iter()
+        .copied()
+        .find(|network| genesis_block((*network).into()).block_hash() == genesis_hash)
+        .ok_or_else(|| anyhow::anyhow!("unknown genesis block {}", genesis_hash))
+}
+
+/// How often we poll for new blocks if we cannot be notified about them.
+fn poll_interval(network: ledger::Bitcoin) -> Duration {
+    use ledger::Bitcoin::*;
+
+    let seconds = match network {
+        Mainnet | Testnet => 30,
+        Regtest => 1,
+    };
+
+    Duration::from_secs(seconds)
+}
+
 impl BlockHash for Block {
     type BlockHash = Hash;
 
@@ -37,29 +123,148 @@ impl PreviousBlockHash for Block {
     }
 }
 
-#[tracing::instrument(level = "debug", skip(blockchain_connector, start_of_swap, identity), fields(%outpoint))]
+/// Reads the height of a block from its coinbase transaction.
+///
+/// Since BIP34 the first item of the coinbase script is the height of the
+/// block. Blocks that predate BIP34 do not necessarily follow this.
+pub fn block_height(block: &Block) -> Option<u32> {
+    let coinbase = block.txdata.first()?.input.first()?;
+
+    match coinbase.script_sig.instructions().next()?.ok()? {
+        Instruction::PushBytes(bytes) if bytes.len() <= 4 => {
+            let height = read_scriptint(bytes).ok()?;
+
+            u32::try_from(height).ok()
+        }
+        // Heights up to 16 are encoded as OP_1 to OP_16.
+        Instruction::Op(op) => match op.into_u8() {
+            code @ 0x51..=0x60 => Some(u32::from(code - 0x50)),
+            _ => None,
+        },
+        Instruction::PushBytes(_) => None,
+    }
+}
+
+/// Watches for a transaction that spends the outpoint with the given identity.
+///
+/// `address` is the address the outpoint pays to. Connectors that index
+/// transactions by script find the spending transaction in its history.
+#[tracing::instrument(level = "debug", skip(blockchain_connector, start_of_swap, address, identity), fields(%outpoint))]
 pub async fn watch_for_spent_outpoint<C>(
     blockchain_connector: &C,
     start_of_swap: OffsetDateTime,
     outpoint: OutPoint,
+    address: bitcoin::Address,
     identity: identity::Bitcoin,
-) -> Result<(bitcoin::Transaction, bitcoin::TxIn)>
+    confirmations: u8,
+) -> Result<(bitcoin::Transaction, bitcoin::TxIn, Option<u32>)>
 where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = Hash>
-        + ConnectedNetwork<Network = ledger::Bitcoin>,
+        + BlockNotifications
+        + ConnectedNetwork<Network = ledger::Bitcoin>
+        + TransactionsByScript,
 {
-    let (transaction, txin) = watch(blockchain_connector, start_of_swap, |transaction| {
-        transaction
-            .input
-            .iter()
-            .filter(|txin| txin.previous_output == outpoint)
-            .find(|txin| txin.witness.contains(&identity.to_bytes()))
-            .cloned()
-    })
+    let (transaction, txin, block_height) = watch(
+        blockchain_connector,
+        start_of_swap,
+        &address.script_pubkey(),
+        confirmations,
+        |transaction| spending_input(transaction, outpoint, &identity.to_bytes()),
+    )
     .await?;
 
-    Ok((transaction, txin))
+    Ok((transaction, txin, block_height))
+}
+
+/// Watches the mempool for a transaction that spends the outpoint with the
+/// given identity.
+///
+/// The transaction is returned as soon as it is seen, i.e. before it is mined.
+/// Transactions that are already in a block are not found, watching the
+/// blockchain through [`watch_for_spent_outpoint`] is still needed for those.
+///
+/// `address` is the address the outpoint pays to. Connectors that index
+/// transactions by script are only asked for the mempool transactions of its
+/// script, the whole mempool is only scanned through bitcoind.
+#[tracing::instrument(level = "debug", skip(connector, address, identity), fields(%outpoint))]
+pub async fn watch_for_spent_outpoint_in_mempool<C>(
+    connector: &C,
+    outpoint: OutPoint,
+    address: bitcoin::Address,
+    identity: identity::Bitcoin,
+) -> Result<(bitcoin::Transaction, bitcoin::TxIn)>
+where
+    C: Mempool + TransactionsByScript + ConnectedNetwork<Network = ledger::Bitcoin>,
+{
+    let poll_interval = poll_interval(connector.connected_network().await?);
+    let script = address.script_pubkey();
+    let identity = identity.to_bytes();
+    let mut seen = HashSet::new();
+
+    loop {
+        let (mempool, by_script) = match connector.transactions_by_script(&script).await? {
+            Some(transactions) => {
+                let txids = transactions
+                    .into_iter()
+                    .filter(|transaction| transaction.height.is_none())
+                    .map(|transaction| transaction.txid)
+                    .collect::<HashSet<_>>();
+
+                (txids, true)
+            }
+            None => {
+                let txids = connector
+                    .mempool_txids()
+                    .await?
+                    .into_iter()
+                    .collect::<HashSet<_>>();
+
+                (txids, false)
+            }
+        };
+
+        for txid in mempool.difference(&seen) {
+            // The transaction may have been mined or dropped since we asked for the
+            // mempool.
+            let transaction = if by_script {
+                connector.transaction_by_txid(*txid).await
+            } else {
+                connector.mempool_transaction(*txid).await
+            };
+            let transaction = match transaction {
+                Ok(transaction) => transaction,
+                Err(e) => {
+                    tracing::debug!("failed to fetch mempool transaction {}: {:#}", txid, e);
+                    continue;
+                }
+            };
+
+            if let Some(txin) = spending_input(&transaction, outpoint, &identity) {
+                tracing::info!("transaction {} matched in mempool", txid);
+                return Ok((transaction, txin));
+            }
+        }
+
+        // Forgetting transactions that left the mempool keeps this from growing
+        // without bound.
+        seen = mempool;
+
+        tokio::time::delay_for(poll_interval).await;
+    }
+}
+
+fn spending_input(
+    transaction: &bitcoin::Transaction,
+    outpoint: OutPoint,
+    identity: &[u8],
+) -> Option<bitcoin::TxIn> {
+    transaction
+        .input
+        .iter()
+        .filter(|txin| txin.previous_output == outpoint)
+        .find(|txin| txin.witness.iter().any(|item| item.as_slice() == identity))
+        .cloned()
 }
 
 #[tracing::instrument(level = "debug", skip(blockchain_connector, start_of_swap))]
@@ -67,51 +272,185 @@ pub async fn watch_for_created_outpoint<C>(
     blockchain_connector: &C,
     start_of_swap: OffsetDateTime,
     address: bitcoin::Address,
-) -> Result<(bitcoin::Transaction, bitcoin::OutPoint)>
+    confirmations: u8,
+) -> Result<(bitcoin::Transaction, bitcoin::OutPoint, Option<u32>)>
 where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = Hash>
-        + ConnectedNetwork<Network = ledger::Bitcoin>,
+        + BlockNotifications
+        + ConnectedNetwork<Network = ledger::Bitcoin>
+        + TransactionsByScript,
 {
-    let (transaction, out_point) = watch(blockchain_connector, start_of_swap, |transaction| {
-        let txid = transaction.txid();
-        transaction
-            .output
-            .iter()
-            .enumerate()
-            .map(|(index, txout)| {
-                // Casting a usize to u32 can lead to truncation on 64bit platforms
-                // However, bitcoin limits the number of inputs to u32 anyway, so this
-                // is not a problem for us.
-                #[allow(clippy::cast_possible_truncation)]
-                (index as u32, txout)
-            })
-            .find(|(_, txout)| txout.script_pubkey == address.script_pubkey())
-            .map(|(vout, _txout)| OutPoint { txid, vout })
-    })
+    let script = address.script_pubkey();
+    let (transaction, out_point, block_height) = watch(
+        blockchain_connector,
+        start_of_swap,
+        &script,
+        confirmations,
+        |transaction| {
+            let txid = transaction.txid();
+            transaction
+                .output
+                .iter()
+                .enumerate()
+                .map(|(index, txout)| {
+                    // Casting a usize to u32 can lead to truncation on 64bit platforms
+                    // However, bitcoin limits the number of inputs to u32 anyway, so this
+                    // is not a problem for us.
+                    #[allow(clippy::cast_possible_truncation)]
+                    (index as u32, txout)
+                })
+                .find(|(_, txout)| txout.script_pubkey == script)
+                .map(|(vout, _txout)| OutPoint { txid, vout })
+        },
+    )
     .await?;
 
-    Ok((transaction, out_point))
+    Ok((transaction, out_point, block_height))
+}
+
+/// The number of blocks whose timestamps make up the median time past.
+const MEDIAN_TIME_SPAN: usize = 11;
+
+/// Computes the median time past of the current chain tip.
+///
+/// Absolute timelocks in Bitcoin are not evaluated against the timestamp of
+/// the latest block but against the median of the timestamps of the last 11
+/// blocks (BIP113). This is the time that matters for deciding whether an
+/// `hbit` HTLC can be refunded.
+pub async fn median_time_past<C>(connector: &C) -> Result<Timestamp>
+where
+    C: LatestBlock<Block = Block> + BlockByHash<Block = Block, BlockHash = Hash>,
+{
+    let mut block = connector.latest_block().await?;
+    let mut timestamps = vec![block.header.time];
+
+    while timestamps.len() < MEDIAN_TIME_SPAN {
+        let previous_block_hash = block.header.prev_blockhash;
+
+        // The genesis block does not have a parent.
+        if previous_block_hash == Hash::default() {
+            break;
+        }
+
+        block = connector.block_by_hash(previous_block_hash).await?;
+        timestamps.push(block.header.time);
+    }
+
+    timestamps.sort_unstable();
+
+    Ok(Timestamp::from(timestamps[timestamps.len() / 2]))
 }
 
 async fn watch<C, S, M>(
+    connector: &C,
+    start_of_swap: OffsetDateTime,
+    script: &Script,
+    confirmations: u8,
+    sieve: S,
+) -> Result<(bitcoin::Transaction, M, Option<u32>)>
+where
+    C: LatestBlock<Block = Block>
+        + BlockByHash<Block = Block, BlockHash = Hash>
+        + BlockNotifications
+        + ConnectedNetwork<Network = ledger::Bitcoin>
+        + TransactionsByScript,
+    S: Fn(&bitcoin::Transaction) -> Option<M>,
+{
+    if let Some(found) = watch_script(connector, script, confirmations, &sieve).await? {
+        return Ok(found);
+    }
+
+    let (block_hash, block_height, transaction, result) =
+        find(connector, start_of_swap, sieve).await?;
+
+    wait_for_confirmations(connector, block_hash, confirmations).await?;
+
+    Ok((transaction, result, block_height))
+}
+
+/// Watches the history of a script for a transaction accepted by the sieve
+/// until it has the given number of confirmations.
+///
+/// This saves us from scanning every block since the start of the swap but
+/// only works with connectors that index transactions by script. For all
+/// others, `None` is returned.
+async fn watch_script<C, S, M>(
+    connector: &C,
+    script: &Script,
+    confirmations: u8,
+    sieve: S,
+) -> Result<Option<(bitcoin::Transaction, M, Option<u32>)>>
+where
+    C: LatestBlock<Block = Block> + BlockNotifications + TransactionsByScript,
+    S: Fn(&bitcoin::Transaction) -> Option<M>,
+{
+    let mut transactions = HashMap::new();
+
+    loop {
+        let history = match connector.transactions_by_script(script).await? {
+            Some(history) => history,
+            None => return Ok(None),
+        };
+
+        // Transactions in the mempool are not final, we only look at mined
+        // ones. The history always reflects the current chain, hence a reorg
+        // is picked up on the next iteration.
+        let mut mined = history
+            .into_iter()
+            .filter_map(|transaction| Some((transaction.height?, transaction.txid)))
+            .collect::<Vec<_>>();
+        mined.sort_unstable();
+
+        for (height, txid) in mined {
+            if !transactions.contains_key(&txid) {
+                let transaction = connector.transaction_by_txid(txid).await?;
+                transactions.insert(txid, transaction);
+            }
+            let transaction = &transactions[&txid];
+
+            let result = match sieve(transaction) {
+                Some(result) => result,
+                None => continue,
+            };
+
+            let latest_block = connector.latest_block().await?;
+            let tip = block_height(&latest_block)
+                .context("failed to read the height of the latest block")?;
+
+            if tip.saturating_sub(height) + 1 >= u32::from(confirmations) {
+                tracing::info!("transaction {} matched at height {}", txid, height);
+                return Ok(Some((transaction.clone(), result, Some(height))));
+            }
+
+            break;
+        }
+
+        connector.wait_for_new_block().await?;
+    }
+}
+
+async fn find<C, S, M>(
     connector: &C,
     start_of_swap: OffsetDateTime,
     sieve: S,
-) -> Result<(bitcoin::Transaction, M)>
+) -> Result<(Hash, Option<u32>, bitcoin::Transaction, M)>
 where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = Hash>
-        + ConnectedNetwork<Network = ledger::Bitcoin>,
+        + BlockNotifications
+        + ConnectedNetwork<Network = ledger::Bitcoin>
+        + TransactionsByScript,
     S: Fn(&bitcoin::Transaction) -> Option<M>,
 {
-    let poll_interval = poll_interval(connector).await?;
-    let mut block_generator = fetch_blocks_since(connector, start_of_swap, poll_interval);
+    let mut block_generator = fetch_blocks_since(connector, start_of_swap);
 
     loop {
         match block_generator.async_resume().await {
             GeneratorState::Yielded(block) => {
-                let block_span = tracing::error_span!("block", hash = %block.block_hash(), tx_count = %block.txdata.len());
+                let block_hash = block.block_hash();
+                let block_height = block_height(&block);
+                let block_span = tracing::error_span!("block", hash = %block_hash, tx_count = %block.txdata.len());
                 let _enter_block_span = block_span.enter();
 
                 for transaction in block.txdata.into_iter() {
@@ -120,7 +459,7 @@ where
 
                     if let Some(result) = sieve(&transaction) {
                         tracing::info!("transaction matched");
-                        return Ok((transaction, result));
+                        return Ok((block_hash, block_height, transaction, result));
                     }
                 }
 
@@ -144,17 +483,187 @@ impl Predates for Block {
     }
 }
 
-async fn poll_interval<C>(connector: &C) -> Result<Duration>
-where
-    C: ConnectedNetwork<Network = ledger::Bitcoin>,
-{
-    use ledger::Bitcoin::*;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::{blockdata::script::Builder, TxIn};
+    use std::str::FromStr;
 
-    let network = connector.connected_network().await?;
-    let seconds = match network {
-        Mainnet | Testnet => 30,
-        Regtest => 1,
-    };
+    fn block_with_coinbase(script_sig: Script) -> Block {
+        let mut block = genesis_block(bitcoin::Network::Regtest);
+        block.txdata[0].input = vec![TxIn {
+            script_sig,
+            ..block.txdata[0].input[0].clone()
+        }];
+
+        block
+    }
+
+    fn block_at_height(height: u32) -> Block {
+        let script_sig = Builder::new()
+            .push_int(i64::from(height))
+            .push_int(0)
+            .into_script();
+
+        block_with_coinbase(script_sig)
+    }
+
+    fn transaction(lock_time: u32) -> bitcoin::Transaction {
+        bitcoin::Transaction {
+            lock_time,
+            ..genesis_block(bitcoin::Network::Regtest).txdata[0].clone()
+        }
+    }
 
-    Ok(Duration::from_secs(seconds))
+    /// A connector that indexes transactions by script, every script has the
+    /// same history.
+    struct ScriptIndex {
+        tip: u32,
+        history: Vec<(bitcoin::Transaction, Option<u32>)>,
+    }
+
+    #[async_trait]
+    impl LatestBlock for ScriptIndex {
+        type Block = Block;
+
+        async fn latest_block(&self) -> Result<Block> {
+            Ok(block_at_height(self.tip))
+        }
+    }
+
+    #[async_trait]
+    impl BlockNotifications for ScriptIndex {
+        async fn wait_for_new_block(&self) -> Result<()> {
+            anyhow::bail!("the tip never changes")
+        }
+    }
+
+    #[async_trait]
+    impl TransactionsByScript for ScriptIndex {
+        async fn transactions_by_script(
+            &self,
+            _script: &Script,
+        ) -> Result<Option<Vec<ScriptTransaction>>> {
+            let history = self
+                .history
+                .iter()
+                .map(|(transaction, height)| ScriptTransaction {
+                    txid: transaction.txid(),
+                    height: *height,
+                })
+                .collect();
+
+            Ok(Some(history))
+        }
+
+        async fn transaction_by_txid(&self, txid: Txid) -> Result<bitcoin::Transaction> {
+            self.history
+                .iter()
+                .map(|(transaction, _)| transaction.clone())
+                .find(|transaction| transaction.txid() == txid)
+                .context("unknown transaction")
+        }
+    }
+
+    #[async_trait]
+    impl Mempool for ScriptIndex {
+        async fn mempool_txids(&self) -> Result<Vec<Txid>> {
+            anyhow::bail!("the whole mempool must not be scanned")
+        }
+
+        async fn mempool_transaction(&self, _txid: Txid) -> Result<bitcoin::Transaction> {
+            anyhow::bail!("the whole mempool must not be scanned")
+        }
+    }
+
+    #[async_trait]
+    impl ConnectedNetwork for ScriptIndex {
+        type Network = ledger::Bitcoin;
+
+        async fn connected_network(&self) -> Result<ledger::Bitcoin> {
+            Ok(ledger::Bitcoin::Regtest)
+        }
+    }
+
+    #[tokio::test]
+    async fn finds_confirmed_transaction_in_script_history() {
+        let wanted = transaction(3);
+        let connector = ScriptIndex {
+            tip: 10,
+            history: vec![
+                (transaction(1), Some(5)),
+                (transaction(2), None),
+                (wanted.clone(), Some(8)),
+            ],
+        };
+
+        let (transaction, _, height) = watch_script(&connector, &Script::new(), 3, |transaction| {
+            if transaction.lock_time == wanted.lock_time {
+                Some(())
+            } else {
+                None
+            }
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(transaction, wanted);
+        assert_eq!(height, Some(8));
+    }
+
+    #[tokio::test]
+    async fn waits_for_confirmations_of_transaction_in_script_history() {
+        let connector = ScriptIndex {
+            tip: 10,
+            history: vec![(transaction(1), Some(9))],
+        };
+
+        let result = watch_script(&connector, &Script::new(), 3, |_| Some(())).await;
+
+        assert!(result.is_err(), "should wait for a new block");
+    }
+
+    #[tokio::test]
+    async fn finds_spending_transaction_in_mempool_of_script() {
+        let identity = identity::Bitcoin::from_str(
+            "02c2a8efce029526d364c2cf39d89e3cdda05e5df7b2cbfc098b4e3d02b70b5275",
+        )
+        .unwrap();
+        let outpoint = OutPoint {
+            txid: transaction(1).txid(),
+            vout: 0,
+        };
+        let mut spending = transaction(2);
+        spending.input[0].previous_output = outpoint;
+        spending.input[0].witness = vec![identity.to_bytes()];
+        let connector = ScriptIndex {
+            tip: 10,
+            history: vec![(transaction(1), Some(5)), (spending.clone(), None)],
+        };
+        let address = bitcoin::Address::p2wsh(&Script::new(), bitcoin::Network::Regtest);
+
+        let (transaction, txin) =
+            watch_for_spent_outpoint_in_mempool(&connector, outpoint, address, identity)
+                .await
+                .unwrap();
+
+        assert_eq!(transaction, spending);
+        assert_eq!(txin.previous_output, outpoint);
+    }
+
+    #[test]
+    fn block_height_is_read_from_the_coinbase() {
+        for height in &[1u32, 16, 17, 128, 500, 32_767, 32_768, 654_321] {
+            let script_sig = Builder::new()
+                .push_int(i64::from(*height))
+                .push_int(0)
+                .into_script();
+
+            assert_eq!(
+                block_height(&block_with_coinbase(script_sig)),
+                Some(*height)
+            );
+        }
+    }
 }
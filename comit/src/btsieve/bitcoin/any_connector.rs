@@ -0,0 +1,131 @@
+use crate::{
+    btsieve::{
+        bitcoin::{
+            BitcoindConnector, ElectrumConnector, EsploraConnector, Mempool, ScriptTransaction,
+            TransactionsByScript,
+        },
+        BlockByHash, BlockNotifications, ConnectedNetwork, LatestBlock, TipHeight,
+    },
+    ledger,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use bitcoin::{Block, BlockHash, Script, Transaction, Txid};
+
+/// One of the supported backends for following the Bitcoin blockchain,
+/// selected at runtime through the configuration.
+#[derive(Debug)]
+pub enum AnyConnector {
+    Bitcoind(BitcoindConnector),
+    Esplora(EsploraConnector),
+    Electrum(ElectrumConnector),
+}
+
+#[async_trait]
+impl LatestBlock for AnyConnector {
+    type Block = Block;
+
+    async fn latest_block(&self) -> Result<Self::Block> {
+        match self {
+            AnyConnector::Bitcoind(connector) => connector.latest_block().await,
+            AnyConnector::Esplora(connector) => connector.latest_block().await,
+            AnyConnector::Electrum(connector) => connector.latest_block().await,
+        }
+    }
+}
+
+#[async_trait]
+impl BlockByHash for AnyConnector {
+    type Block = Block;
+    type BlockHash = BlockHash;
+
+    async fn block_by_hash(&self, block_hash: Self::BlockHash) -> Result<Self::Block> {
+        match self {
+            AnyConnector::Bitcoind(connector) => connector.block_by_hash(block_hash).await,
+            AnyConnector::Esplora(connector) => connector.block_by_hash(block_hash).await,
+            AnyConnector::Electrum(connector) => connector.block_by_hash(block_hash).await,
+        }
+    }
+}
+
+#[async_trait]
+impl BlockNotifications for AnyConnector {
+    async fn wait_for_new_block(&self) -> Result<()> {
+        match self {
+            AnyConnector::Bitcoind(connector) => connector.wait_for_new_block().await,
+            AnyConnector::Esplora(connector) => connector.wait_for_new_block().await,
+            AnyConnector::Electrum(connector) => connector.wait_for_new_block().await,
+        }
+    }
+}
+
+#[async_trait]
+impl ConnectedNetwork for AnyConnector {
+    type Network = ledger::Bitcoin;
+
+    async fn connected_network(&self) -> Result<ledger::Bitcoin> {
+        match self {
+            AnyConnector::Bitcoind(connector) => connector.connected_network().await,
+            AnyConnector::Esplora(connector) => connector.connected_network().await,
+            AnyConnector::Electrum(connector) => connector.connected_network().await,
+        }
+    }
+}
+
+#[async_trait]
+impl TipHeight for AnyConnector {
+    async fn tip_height(&self) -> Result<u64> {
+        match self {
+            AnyConnector::Bitcoind(connector) => connector.tip_height().await,
+            AnyConnector::Esplora(connector) => connector.tip_height().await,
+            AnyConnector::Electrum(connector) => connector.tip_height().await,
+        }
+    }
+}
+
+/// Esplora and Electrum servers are only asked for the mempool transactions of
+/// a script, see [`TransactionsByScript`]. Scanning the whole mempool of a
+/// public server would get us rate-limited, hence it is only done through
+/// bitcoind.
+#[async_trait]
+impl Mempool for AnyConnector {
+    async fn mempool_txids(&self) -> Result<Vec<Txid>> {
+        match self {
+            AnyConnector::Bitcoind(connector) => connector.mempool_txids().await,
+            AnyConnector::Esplora(_) | AnyConnector::Electrum(_) => {
+                anyhow::bail!("watching the whole mempool is only supported with bitcoind")
+            }
+        }
+    }
+
+    async fn mempool_transaction(&self, txid: Txid) -> Result<Transaction> {
+        match self {
+            AnyConnector::Bitcoind(connector) => connector.mempool_transaction(txid).await,
+            AnyConnector::Esplora(_) | AnyConnector::Electrum(_) => {
+                anyhow::bail!("watching the whole mempool is only supported with bitcoind")
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl TransactionsByScript for AnyConnector {
+    async fn transactions_by_script(
+        &self,
+        script: &Script,
+    ) -> Result<Option<Vec<ScriptTransaction>>> {
+        match self {
+            AnyConnector::Bitcoind(connector) => connector.transactions_by_script(script).await,
+            AnyConnector::Esplora(connector) => connector.transactions_by_script(script).await,
+            AnyConnector::Electrum(connector) => connector.transactions_by_script(script).await,
+        }
+    }
+
+    async fn transaction_by_txid(&self, txid: Txid) -> Result<Transaction> {
+        match self {
+            AnyConnector::Bitcoind(connector) => connector.transaction_by_txid(txid).await,
+            AnyConnector::Esplora(connector) => connector.transaction_by_txid(txid).await,
+            AnyConnector::Electrum(connector) => connector.transaction_by_txid(txid).await,
+        }
+    }
+}
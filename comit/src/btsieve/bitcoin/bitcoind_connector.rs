@@ -1,25 +1,50 @@
 use crate::{
-    btsieve::{BlockByHash, ConnectedNetwork, LatestBlock},
+    btsieve::{
+        bitcoin::{
+            poll_interval, zmq::HashBlocks, Mempool, ScriptTransaction, TransactionsByScript,
+        },
+        jsonrpc, BlockByHash, BlockNotifications, ConnectedNetwork, LatestBlock, Polling,
+        TipHeight,
+    },
     ledger,
 };
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use bitcoin::{consensus::deserialize, BlockHash};
+use bitcoin::{consensus::deserialize, BlockHash, Script, Txid};
 use reqwest::{Client, Url};
 use serde::Deserialize;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// How long we poll for new blocks after waiting for them through the RPC
+/// interface failed, before we try the RPC interface again.
+const LONG_POLLING_RETRY_INTERVAL: Duration = Duration::from_secs(10 * 60);
 
 #[derive(Copy, Clone, Debug, Deserialize)]
 pub struct ChainInfo {
     bestblockhash: BlockHash,
+    blocks: u64,
     #[serde(with = "ledger::bitcoin::bitcoind_jsonrpc_network")]
     pub chain: ledger::Bitcoin,
 }
 
+#[derive(Copy, Clone, Debug, Deserialize)]
+struct NewBlock {
+    hash: BlockHash,
+    height: u32,
+}
+
 #[derive(Debug)]
 pub struct BitcoindConnector {
     chaininfo_url: Url,
     raw_block_by_hash_url: Url,
     client: Client,
+    rpc_client: jsonrpc::Client,
+    hash_blocks: Option<HashBlocks>,
+    polling: Mutex<Option<Polling>>,
+    long_polling_failed_at: Mutex<Option<Instant>>,
 }
 
 impl BitcoindConnector {
@@ -28,9 +53,22 @@ impl BitcoindConnector {
             chaininfo_url: base_url.join("rest/chaininfo.json")?,
             raw_block_by_hash_url: base_url.join("rest/block/")?,
             client: Client::new(),
+            rpc_client: jsonrpc::Client::new(base_url),
+            hash_blocks: None,
+            polling: Mutex::new(None),
+            long_polling_failed_at: Mutex::new(None),
         })
     }
 
+    /// Subscribe to new blocks through the `-zmqpubhashblock` endpoint of the
+    /// node instead of long-polling for them.
+    pub fn with_hash_block_subscription(self, zmq_url: Url) -> Self {
+        Self {
+            hash_blocks: Some(HashBlocks::new(zmq_url)),
+            ..self
+        }
+    }
+
     fn raw_block_by_hash_url(&self, block_hash: &BlockHash) -> Url {
         self.raw_block_by_hash_url
             .join(&format!("{}.hex", block_hash))
@@ -51,6 +89,57 @@ impl BitcoindConnector {
 
         Ok(chain_info)
     }
+
+    /// Long-polls bitcoind for a new block using the `waitfornewblock` RPC.
+    ///
+    /// bitcoind returns the current tip if no new block arrived within the
+    /// given timeout.
+    async fn wait_for_new_block_rpc(&self, timeout: Duration) -> Result<()> {
+        // Our timeouts are way shorter than u64::MAX milliseconds.
+        #[allow(clippy::cast_possible_truncation)]
+        let timeout = timeout.as_millis() as u64;
+
+        let block = self
+            .rpc_client
+            .send::<_, NewBlock>(jsonrpc::Request::new("waitfornewblock", vec![timeout]))
+            .await?;
+
+        tracing::trace!(
+            "bitcoind tip is block {} at height {}",
+            block.hash,
+            block.height
+        );
+
+        Ok(())
+    }
+
+    fn is_long_polling_suspended(&self) -> bool {
+        self.long_polling_failed_at
+            .lock()
+            .expect("mutex is not poisoned")
+            .map_or(false, |failed_at| {
+                failed_at.elapsed() < LONG_POLLING_RETRY_INTERVAL
+            })
+    }
+
+    fn set_long_polling_failed_at(&self, failed_at: Option<Instant>) {
+        *self
+            .long_polling_failed_at
+            .lock()
+            .expect("mutex is not poisoned") = failed_at;
+    }
+
+    async fn polling(&self) -> Result<Polling> {
+        if let Some(polling) = *self.polling.lock().expect("mutex is not poisoned") {
+            return Ok(polling);
+        }
+
+        let network = self.connected_network().await?;
+        let polling = Polling::new(poll_interval(network));
+        *self.polling.lock().expect("mutex is not poisoned") = Some(polling);
+
+        Ok(polling)
+    }
 }
 
 #[async_trait]
@@ -88,6 +177,56 @@ impl BlockByHash for BitcoindConnector {
     }
 }
 
+/// Waits for new blocks using the `hashblock` ZMQ notifications of bitcoind if
+/// configured, and its `waitfornewblock` RPC otherwise or if the subscription
+/// fails.
+///
+/// Waiting for a new block is capped at the poll interval of the network to
+/// not miss blocks that were mined right before we started waiting.
+///
+/// While waiting through the RPC interface, the request occupies one of
+/// bitcoind's RPC worker threads (`-rpcthreads`, 4 by default). This is
+/// affordable because only the chain follower waits for new blocks, hence
+/// there is a single waiting request per node.
+///
+/// If the RPC interface is not available, e.g. because no credentials are
+/// configured, we fall back to polling and try the RPC interface again after
+/// `LONG_POLLING_RETRY_INTERVAL`.
+#[async_trait]
+impl BlockNotifications for BitcoindConnector {
+    async fn wait_for_new_block(&self) -> Result<()> {
+        let polling = self.polling().await?;
+
+        if let Some(hash_blocks) = &self.hash_blocks {
+            match tokio::time::timeout(polling.interval(), hash_blocks.wait_for_new_block()).await {
+                Ok(Ok(())) | Err(_) => return Ok(()),
+                Ok(Err(e)) => tracing::warn!(
+                    "hashblock subscription failed, falling back to long polling: {:#}",
+                    e
+                ),
+            }
+        }
+
+        if !self.is_long_polling_suspended() {
+            match self.wait_for_new_block_rpc(polling.interval()).await {
+                Ok(()) => {
+                    self.set_long_polling_failed_at(None);
+                    return Ok(());
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "waiting for new blocks with bitcoind failed, falling back to polling: {:#}",
+                        e
+                    );
+                    self.set_long_polling_failed_at(Some(Instant::now()));
+                }
+            }
+        }
+
+        polling.wait_for_new_block().await
+    }
+}
+
 #[async_trait]
 impl ConnectedNetwork for BitcoindConnector {
     type Network = ledger::Bitcoin;
@@ -99,6 +238,61 @@ impl ConnectedNetwork for BitcoindConnector {
     }
 }
 
+#[async_trait]
+impl TipHeight for BitcoindConnector {
+    async fn tip_height(&self) -> Result<u64> {
+        let height = self.chain_info().await?.blocks;
+
+        Ok(height)
+    }
+}
+
+/// Inspects the mempool through the RPC interface, REST only exposes it in
+/// bulk.
+#[async_trait]
+impl Mempool for BitcoindConnector {
+    async fn mempool_txids(&self) -> Result<Vec<Txid>> {
+        let txids = self
+            .rpc_client
+            .send::<Vec<()>, Vec<Txid>>(jsonrpc::Request::new("getrawmempool", vec![]))
+            .await?;
+
+        Ok(txids)
+    }
+
+    async fn mempool_transaction(&self, txid: Txid) -> Result<bitcoin::Transaction> {
+        let hex = self
+            .rpc_client
+            .send::<_, String>(jsonrpc::Request::new("getrawtransaction", vec![
+                jsonrpc::serialize(txid)?,
+                jsonrpc::serialize(false)?,
+            ]))
+            .await?;
+        let bytes = hex::decode(hex.trim()).context("failed to decode hex")?;
+        let transaction =
+            deserialize(&bytes).context("failed to deserialize bytes as transaction")?;
+
+        Ok(transaction)
+    }
+}
+
+/// Bitcoind does not index transactions by script, hence watchers have to scan
+/// the blockchain. Looking up a transaction by its id only works for the
+/// mempool unless the `txindex` of bitcoind is enabled.
+#[async_trait]
+impl TransactionsByScript for BitcoindConnector {
+    async fn transactions_by_script(
+        &self,
+        _script: &Script,
+    ) -> Result<Option<Vec<ScriptTransaction>>> {
+        Ok(None)
+    }
+
+    async fn transaction_by_txid(&self, txid: Txid) -> Result<bitcoin::Transaction> {
+        self.mempool_transaction(txid).await
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 #[error("GET request to {0} failed")]
 pub struct GetRequestFailed(Url);
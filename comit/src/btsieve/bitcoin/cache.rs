@@ -1,10 +1,18 @@
 use crate::{
-    btsieve::{BlockByHash, ConnectedNetwork, LatestBlock},
+    btsieve::{
+        bitcoin::{Mempool, ScriptTransaction, TransactionsByScript},
+        block_store::{BlockStore, Tree},
+        mempool::MempoolSnapshot,
+        BlockByHash, BlockNotifications, ConnectedNetwork, LatestBlock,
+    },
     ledger,
 };
 use anyhow::Result;
 use async_trait::async_trait;
-use bitcoin::{Block, BlockHash as Hash, BlockHash};
+use bitcoin::{
+    consensus::{deserialize, serialize},
+    Block, BlockHash as Hash, BlockHash, Script, Transaction, Txid,
+};
 use derivative::Derivative;
 use lru::LruCache;
 use std::sync::Arc;
@@ -18,6 +26,10 @@ pub struct Cache<C> {
     pub block_cache: Arc<Mutex<LruCache<BlockHash, Block>>>,
     #[derivative(Debug = "ignore")]
     pub connected_network_cache: Arc<Mutex<Option<ledger::Bitcoin>>>,
+    #[derivative(Debug = "ignore")]
+    block_store: Option<Tree>,
+    #[derivative(Debug = "ignore")]
+    mempool: Arc<Mutex<MempoolSnapshot<Txid, Transaction>>>,
 }
 
 impl<C> Cache<C> {
@@ -29,6 +41,34 @@ impl<C> Cache<C> {
             connector,
             block_cache,
             connected_network_cache,
+            block_store: None,
+            mempool: Arc::new(Mutex::new(MempoolSnapshot::default())),
+        }
+    }
+
+    /// Keeps the blocks in the given store as well, so they survive a restart.
+    pub fn with_block_store(self, store: &BlockStore) -> Result<Self> {
+        Ok(Cache {
+            block_store: Some(store.tree("bitcoin_blocks")?),
+            ..self
+        })
+    }
+
+    fn load_block(&self, block_hash: &BlockHash) -> Option<Block> {
+        self.block_store.as_ref()?.load(&block_hash[..], |bytes| {
+            let block = deserialize(bytes)?;
+
+            Ok(block)
+        })
+    }
+
+    fn save_block(&self, block: &Block) {
+        if let Some(store) = &self.block_store {
+            store.save(
+                &block.block_hash()[..],
+                u64::from(block.header.time),
+                &serialize(block),
+            );
         }
     }
 }
@@ -46,6 +86,7 @@ where
         let block_hash = block.block_hash();
         let mut guard = self.block_cache.lock().await;
         if !guard.contains(&block_hash) {
+            self.save_block(&block);
             guard.put(block_hash, block.clone());
         }
 
@@ -66,7 +107,15 @@ where
             return Ok(block.clone());
         }
 
-        let block = self.connector.block_by_hash(block_hash).await?;
+        let block = match self.load_block(&block_hash) {
+            Some(block) => block,
+            None => {
+                let block = self.connector.block_by_hash(block_hash).await?;
+                self.save_block(&block);
+
+                block
+            }
+        };
 
         // We dropped the lock so at this stage the block may have been inserted by
         // another thread, no worries, inserting the same block twice does not hurt.
@@ -77,6 +126,16 @@ where
     }
 }
 
+#[async_trait]
+impl<C> BlockNotifications for Cache<C>
+where
+    C: BlockNotifications,
+{
+    async fn wait_for_new_block(&self) -> Result<()> {
+        self.connector.wait_for_new_block().await
+    }
+}
+
 #[async_trait]
 impl<C> ConnectedNetwork for Cache<C>
 where
@@ -95,3 +154,47 @@ where
         Ok(network)
     }
 }
+
+#[async_trait]
+impl<C> Mempool for Cache<C>
+where
+    C: Mempool,
+{
+    async fn mempool_txids(&self) -> Result<Vec<Txid>> {
+        let mut mempool = self.mempool.lock().await;
+
+        if mempool.is_stale() {
+            let txids = self.connector.mempool_txids().await?;
+            mempool
+                .update(txids, |txid| self.connector.mempool_transaction(txid))
+                .await;
+        }
+
+        Ok(mempool.ids())
+    }
+
+    async fn mempool_transaction(&self, txid: Txid) -> Result<Transaction> {
+        if let Some(transaction) = self.mempool.lock().await.transaction(&txid) {
+            return Ok(transaction);
+        }
+
+        self.connector.mempool_transaction(txid).await
+    }
+}
+
+#[async_trait]
+impl<C> TransactionsByScript for Cache<C>
+where
+    C: TransactionsByScript,
+{
+    async fn transactions_by_script(
+        &self,
+        script: &Script,
+    ) -> Result<Option<Vec<ScriptTransaction>>> {
+        self.connector.transactions_by_script(script).await
+    }
+
+    async fn transaction_by_txid(&self, txid: Txid) -> Result<Transaction> {
+        self.connector.transaction_by_txid(txid).await
+    }
+}
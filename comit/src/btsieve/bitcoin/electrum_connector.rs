@@ -0,0 +1,534 @@
+use crate::{
+    btsieve::{
+        bitcoin::{
+            network_from_genesis_hash, poll_interval, script_hash, ScriptTransaction,
+            TransactionsByScript,
+        },
+        jsonrpc, BlockByHash, BlockNotifications, ConnectedNetwork, LatestBlock, Polling,
+        TipHeight,
+    },
+    ledger,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use bitcoin::{consensus::deserialize, Block, BlockHash, BlockHeader, Script, Transaction, Txid};
+use derivative::Derivative;
+use lru::LruCache;
+use reqwest::Url;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use std::{collections::HashMap, convert::TryFrom, sync::Mutex};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+/// The version of the Electrum protocol we speak.
+const PROTOCOL_VERSION: &str = "1.4";
+
+/// How many requests we pipeline when fetching the transactions of a block.
+const BATCH_SIZE: u32 = 100;
+
+/// How many block heights we remember, see [`ElectrumConnector`].
+const HEIGHTS_CAPACITY: usize = 10_000;
+
+/// A connector to an Electrum server, e.g. electrs or ElectrumX.
+///
+/// The URL is expected in the form `tcp://host:port` or `ssl://host:port`.
+///
+/// Electrum servers do not serve whole blocks. We assemble a block from its
+/// header and the transactions at each of its positions and check the result
+/// against the merkle root of the header. This takes many requests per block,
+/// hence the connector should be used behind a cache. The block at the tip is
+/// kept by the connector itself, so polling for new blocks only costs us a
+/// request for the tip as long as there is none.
+///
+/// Blocks can only be looked up by height. We remember the height of every
+/// block we fetched as well as the height of its parent, which is enough to
+/// walk the blockchain backwards from its tip.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct ElectrumConnector {
+    url: Url,
+    #[derivative(Debug = "ignore")]
+    connection: tokio::sync::Mutex<Option<Connection>>,
+    #[derivative(Debug = "ignore")]
+    heights: Mutex<LruCache<BlockHash, u32>>,
+    #[derivative(Debug = "ignore")]
+    last_block: Mutex<Option<Block>>,
+    network: Mutex<Option<ledger::Bitcoin>>,
+}
+
+impl ElectrumConnector {
+    pub fn new(url: Url) -> Result<Self> {
+        match url.scheme() {
+            "tcp" | "ssl" => {}
+            scheme => anyhow::bail!(
+                "unsupported scheme {} for Electrum server, expected tcp or ssl",
+                scheme
+            ),
+        }
+
+        Ok(Self {
+            url,
+            connection: tokio::sync::Mutex::new(None),
+            heights: Mutex::new(LruCache::new(HEIGHTS_CAPACITY)),
+            last_block: Mutex::new(None),
+            network: Mutex::new(None),
+        })
+    }
+
+    async fn request<T>(&self, method: &'static str, params: Value) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let response = self
+            .send_all(vec![(method, params)])
+            .await?
+            .pop()
+            .expect("one response per request")
+            .with_context(|| format!("Electrum request {} failed", method))?;
+
+        let response = serde_json::from_value(response)
+            .with_context(|| format!("failed to deserialize response to {}", method))?;
+
+        Ok(response)
+    }
+
+    async fn send_all(
+        &self,
+        requests: Vec<(&'static str, Value)>,
+    ) -> Result<Vec<Result<Value, jsonrpc::JsonRpcError>>> {
+        let mut connection = self.connection.lock().await;
+
+        if connection.is_none() {
+            *connection = Some(Connection::connect(&self.url).await?);
+        }
+
+        let result = connection
+            .as_mut()
+            .expect("connection was established above")
+            .send_all(requests)
+            .await;
+
+        if result.is_err() {
+            // Reconnect on the next request.
+            *connection = None;
+        }
+
+        result
+    }
+
+    async fn header(&self, height: u32) -> Result<BlockHeader> {
+        let header = self
+            .request::<String>("blockchain.block.header", json!([height]))
+            .await?;
+
+        decode(&header)
+    }
+
+    async fn block_at(&self, height: u32) -> Result<Block> {
+        let header = self.header(height).await?;
+
+        self.block(height, header).await
+    }
+
+    async fn block(&self, height: u32, header: BlockHeader) -> Result<Block> {
+        if let Some(block) = &*self.last_block.lock().expect("mutex is not poisoned") {
+            if block.header == header {
+                return Ok(block.clone());
+            }
+        }
+
+        let mut txids = Vec::new();
+        let mut position = 0;
+        'positions: loop {
+            let requests = (position..position + BATCH_SIZE)
+                .map(|position| {
+                    (
+                        "blockchain.transaction.id_from_pos",
+                        json!([height, position]),
+                    )
+                })
+                .collect();
+
+            for response in self.send_all(requests).await? {
+                match response {
+                    Ok(txid) => txids.push(serde_json::from_value::<Txid>(txid)?),
+                    // Positions after the last transaction of the block are
+                    // rejected. Should this have been a different error, the
+                    // merkle root will not match.
+                    Err(_) => break 'positions,
+                }
+            }
+            position += BATCH_SIZE;
+        }
+
+        let mut txdata = Vec::with_capacity(txids.len());
+        for chunk in txids.chunks(BATCH_SIZE as usize) {
+            let requests = chunk
+                .iter()
+                .map(|txid| ("blockchain.transaction.get", json!([txid])))
+                .collect();
+
+            for response in self.send_all(requests).await? {
+                let transaction = serde_json::from_value::<String>(response?)?;
+                txdata.push(decode::<Transaction>(&transaction)?);
+            }
+        }
+
+        let block = Block { header, txdata };
+        if !block.check_merkle_root() {
+            anyhow::bail!(
+                "transactions of block {} do not match its merkle root",
+                block.block_hash()
+            );
+        }
+
+        self.remember_height(&block.header, height);
+        *self.last_block.lock().expect("mutex is not poisoned") = Some(block.clone());
+
+        Ok(block)
+    }
+
+    fn remember_height(&self, header: &BlockHeader, height: u32) {
+        let mut heights = self.heights.lock().expect("mutex is not poisoned");
+
+        heights.put(header.block_hash(), height);
+        if let Some(parent_height) = height.checked_sub(1) {
+            heights.put(header.prev_blockhash, parent_height);
+        }
+    }
+
+    async fn polling(&self) -> Result<Polling> {
+        let network = self.connected_network().await?;
+
+        Ok(Polling::new(poll_interval(network)))
+    }
+}
+
+#[async_trait]
+impl LatestBlock for ElectrumConnector {
+    type Block = Block;
+
+    async fn latest_block(&self) -> Result<Self::Block> {
+        let tip = self
+            .request::<Tip>("blockchain.headers.subscribe", json!([]))
+            .await?;
+        let header = decode(&tip.hex)?;
+
+        self.block(tip.height, header).await
+    }
+}
+
+#[async_trait]
+impl BlockByHash for ElectrumConnector {
+    type Block = Block;
+    type BlockHash = BlockHash;
+
+    async fn block_by_hash(&self, block_hash: Self::BlockHash) -> Result<Self::Block> {
+        let height = self
+            .heights
+            .lock()
+            .expect("mutex is not poisoned")
+            .get(&block_hash)
+            .copied()
+            .with_context(|| format!("height of block {} is unknown", block_hash))?;
+
+        let block = self.block_at(height).await?;
+        if block.block_hash() != block_hash {
+            anyhow::bail!(
+                "block {} is no longer part of the chain at height {}",
+                block_hash,
+                height
+            );
+        }
+
+        Ok(block)
+    }
+}
+
+/// Electrum servers push new headers to subscribers. We share one connection
+/// for all requests though, hence we poll instead.
+#[async_trait]
+impl BlockNotifications for ElectrumConnector {
+    async fn wait_for_new_block(&self) -> Result<()> {
+        self.polling().await?.wait_for_new_block().await
+    }
+}
+
+#[async_trait]
+impl ConnectedNetwork for ElectrumConnector {
+    type Network = ledger::Bitcoin;
+
+    async fn connected_network(&self) -> Result<ledger::Bitcoin> {
+        if let Some(network) = *self.network.lock().expect("mutex is not poisoned") {
+            return Ok(network);
+        }
+
+        let genesis = self.header(0).await?;
+        let network = network_from_genesis_hash(genesis.block_hash())?;
+        *self.network.lock().expect("mutex is not poisoned") = Some(network);
+
+        Ok(network)
+    }
+}
+
+#[async_trait]
+impl TipHeight for ElectrumConnector {
+    async fn tip_height(&self) -> Result<u64> {
+        let tip = self
+            .request::<Tip>("blockchain.headers.subscribe", json!([]))
+            .await?;
+
+        Ok(u64::from(tip.height))
+    }
+}
+
+#[async_trait]
+impl TransactionsByScript for ElectrumConnector {
+    async fn transactions_by_script(
+        &self,
+        script: &Script,
+    ) -> Result<Option<Vec<ScriptTransaction>>> {
+        let history = self
+            .request::<Vec<HistoryEntry>>(
+                "blockchain.scripthash.get_history",
+                json!([script_hash(script)]),
+            )
+            .await?;
+
+        let transactions = history
+            .into_iter()
+            .map(|entry| ScriptTransaction {
+                txid: entry.tx_hash,
+                // Heights of 0 and -1 denote transactions in the mempool.
+                height: u32::try_from(entry.height)
+                    .ok()
+                    .filter(|height| *height > 0),
+            })
+            .collect();
+
+        Ok(Some(transactions))
+    }
+
+    async fn transaction_by_txid(&self, txid: Txid) -> Result<Transaction> {
+        let transaction = self
+            .request::<String>("blockchain.transaction.get", json!([txid]))
+            .await?;
+
+        decode(&transaction)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Tip {
+    height: u32,
+    hex: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+struct HistoryEntry {
+    tx_hash: Txid,
+    height: i64,
+}
+
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T> Stream for T where T: AsyncRead + AsyncWrite + Unpin + Send {}
+
+/// A connection to an Electrum server.
+///
+/// Electrum speaks JSON-RPC over TCP, one message per line. Requests can be
+/// pipelined, the server answers them in any order.
+struct Connection {
+    stream: BufReader<Box<dyn Stream>>,
+    next_id: u64,
+}
+
+impl Connection {
+    async fn connect(url: &Url) -> Result<Self> {
+        let host = url.host_str().context("Electrum URL has no host")?;
+        let port = url.port().context("Electrum URL has no port")?;
+
+        let tcp = TcpStream::connect((host, port))
+            .await
+            .with_context(|| format!("failed to connect to {}", url))?;
+        let stream: Box<dyn Stream> = match url.scheme() {
+            "ssl" => {
+                let connector = tokio_tls::TlsConnector::from(native_tls::TlsConnector::new()?);
+                Box::new(connector.connect(host, tcp).await?)
+            }
+            _ => Box::new(tcp),
+        };
+
+        let mut connection = Self {
+            stream: BufReader::new(stream),
+            next_id: 0,
+        };
+
+        // Servers may refuse other requests until the version was negotiated.
+        let version = connection
+            .send_all(vec![("server.version", json!(["comit", PROTOCOL_VERSION]))])
+            .await?
+            .pop()
+            .expect("one response per request")?;
+        tracing::debug!("connected to Electrum server {}: {}", url, version);
+
+        Ok(connection)
+    }
+
+    async fn send_all(
+        &mut self,
+        requests: Vec<(&'static str, Value)>,
+    ) -> Result<Vec<Result<Value, jsonrpc::JsonRpcError>>> {
+        let mut ids = Vec::with_capacity(requests.len());
+        let mut lines = String::new();
+        for (method, params) in requests {
+            let id = self.next_id;
+            self.next_id += 1;
+
+            let request = jsonrpc::Request::new(method, params).with_id(id);
+            lines.push_str(&serde_json::to_string(&request)?);
+            lines.push('\n');
+            ids.push(id.to_string());
+        }
+        self.stream.write_all(lines.as_bytes()).await?;
+
+        let mut responses = HashMap::with_capacity(ids.len());
+        while responses.len() < ids.len() {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line).await? == 0 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "Electrum server closed the connection",
+                )
+                .into());
+            }
+
+            let message = serde_json::from_str::<Value>(&line)
+                .context("failed to deserialize message from Electrum server")?;
+            let id = match message.get("id") {
+                Some(Value::String(id)) => id.clone(),
+                Some(Value::Number(id)) => id.to_string(),
+                // Notifications about subscriptions do not have an id.
+                _ => continue,
+            };
+            let response = serde_json::from_value::<jsonrpc::Response<Value>>(message)
+                .context("failed to deserialize JSON-RPC response")?;
+
+            responses.insert(id, response.payload.into_result());
+        }
+
+        let responses = ids
+            .iter()
+            .map(|id| {
+                responses
+                    .remove(id)
+                    .with_context(|| format!("no response for request {}", id))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(responses)
+    }
+}
+
+fn decode<T>(hex: &str) -> Result<T>
+where
+    T: bitcoin::consensus::Decodable,
+{
+    let bytes = hex::decode(hex).context("failed to decode hex")?;
+    let decoded = deserialize(&bytes).context("failed to deserialize bytes")?;
+
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::{blockdata::constants::genesis_block, consensus::serialize};
+    use tokio::net::TcpListener;
+
+    /// Answers requests like an Electrum server whose chain only consists of
+    /// the given genesis block.
+    async fn serve(genesis: Block) -> Url {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("tcp://{}", listener.local_addr().unwrap())
+            .parse()
+            .unwrap();
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+
+            loop {
+                let mut line = String::new();
+                if stream.read_line(&mut line).await.unwrap() == 0 {
+                    return;
+                }
+                let request = serde_json::from_str::<Value>(&line).unwrap();
+                let params = &request["params"];
+
+                let payload = match request["method"].as_str().unwrap() {
+                    "server.version" => json!({ "result": ["mock", PROTOCOL_VERSION] }),
+                    "blockchain.headers.subscribe" => json!({
+                        "result": { "height": 0, "hex": hex::encode(serialize(&genesis.header)) }
+                    }),
+                    "blockchain.block.header" => {
+                        json!({ "result": hex::encode(serialize(&genesis.header)) })
+                    }
+                    "blockchain.transaction.id_from_pos" => match params[1].as_u64().unwrap() {
+                        0 => json!({ "result": genesis.txdata[0].txid() }),
+                        _ => json!({ "error": { "code": 1, "message": "no tx at position" } }),
+                    },
+                    "blockchain.transaction.get" => {
+                        json!({ "result": hex::encode(serialize(&genesis.txdata[0])) })
+                    }
+                    method => panic!("unexpected request {}", method),
+                };
+
+                let mut response = payload;
+                response["id"] = request["id"].clone();
+                response["jsonrpc"] = json!("2.0");
+                let response = format!("{}\n", response);
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        url
+    }
+
+    #[tokio::test]
+    async fn assembles_blocks_from_headers_and_transactions() {
+        let genesis = genesis_block(bitcoin::Network::Regtest);
+        let connector = ElectrumConnector::new(serve(genesis.clone()).await).unwrap();
+
+        let latest_block = connector.latest_block().await.unwrap();
+        let block_by_hash = connector.block_by_hash(genesis.block_hash()).await.unwrap();
+        let network = connector.connected_network().await.unwrap();
+
+        assert_eq!(latest_block, genesis);
+        assert_eq!(block_by_hash, genesis);
+        assert_eq!(network, ledger::Bitcoin::Regtest);
+    }
+
+    #[test]
+    fn rejects_unsupported_schemes() {
+        let result = ElectrumConnector::new("http://localhost:50001".parse().unwrap());
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn computes_script_hash_like_electrum() {
+        // Example from the documentation of the Electrum protocol.
+        let script = Script::from(
+            hex::decode("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac").unwrap(),
+        );
+
+        assert_eq!(
+            script_hash(&script),
+            "8b01df4e368ea28f8dc0423bcf7a4923e3a12d307c875e47a0cfbf90b5c39161"
+        );
+    }
+}
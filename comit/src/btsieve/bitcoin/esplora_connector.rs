@@ -0,0 +1,324 @@
+use crate::{
+    btsieve::{
+        bitcoin::{
+            network_from_genesis_hash, poll_interval, script_hash, ScriptTransaction,
+            TransactionsByScript,
+        },
+        BlockByHash, BlockNotifications, ConnectedNetwork, LatestBlock, Polling, TipHeight,
+    },
+    ledger,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use bitcoin::{consensus::deserialize, BlockHash, Script, Txid};
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use std::sync::Mutex;
+
+/// A connector to the HTTP API of an Esplora server, e.g. blockstream.info.
+///
+/// This allows users to follow the Bitcoin blockchain without running a
+/// full node. Esplora cannot notify us about new blocks, hence we poll.
+///
+/// API documentation: https://github.com/Blockstream/esplora/blob/master/API.md
+#[derive(Debug)]
+pub struct EsploraConnector {
+    base_url: Url,
+    client: Client,
+    network: Mutex<Option<ledger::Bitcoin>>,
+}
+
+impl EsploraConnector {
+    pub fn new(mut base_url: Url) -> Self {
+        // Joining paths onto a URL without a trailing slash replaces the last
+        // segment, e.g. the `/api` of `https://blockstream.info/api`.
+        if !base_url.path().ends_with('/') {
+            let path = format!("{}/", base_url.path());
+            base_url.set_path(&path);
+        }
+
+        Self {
+            base_url,
+            client: Client::new(),
+            network: Mutex::new(None),
+        }
+    }
+
+    fn url(&self, path: &str) -> Url {
+        self.base_url.join(path).expect("building url should work")
+    }
+
+    async fn get(&self, path: &str) -> Result<reqwest::Response> {
+        let url = self.url(path);
+        let response = self
+            .client
+            .get(url.clone())
+            .send()
+            .await
+            .with_context(|| format!("GET request to {} failed", url))?
+            .error_for_status()?;
+
+        Ok(response)
+    }
+
+    async fn get_block_hash(&self, path: &str) -> Result<BlockHash> {
+        let block_hash = self
+            .get(path)
+            .await?
+            .text()
+            .await?
+            .trim()
+            .parse()
+            .with_context(|| format!("failed to parse block hash returned by {}", path))?;
+
+        Ok(block_hash)
+    }
+
+    async fn polling(&self) -> Result<Polling> {
+        let network = self.connected_network().await?;
+
+        Ok(Polling::new(poll_interval(network)))
+    }
+}
+
+#[async_trait]
+impl LatestBlock for EsploraConnector {
+    type Block = bitcoin::Block;
+
+    async fn latest_block(&self) -> Result<Self::Block> {
+        let block_hash = self.get_block_hash("blocks/tip/hash").await?;
+        let block = self.block_by_hash(block_hash).await?;
+
+        Ok(block)
+    }
+}
+
+#[async_trait]
+impl BlockByHash for EsploraConnector {
+    type Block = bitcoin::Block;
+    type BlockHash = bitcoin::BlockHash;
+
+    async fn block_by_hash(&self, block_hash: Self::BlockHash) -> Result<Self::Block> {
+        let bytes = self
+            .get(&format!("block/{}/raw", block_hash))
+            .await?
+            .bytes()
+            .await?;
+        let block = deserialize(&bytes).context("failed to deserialize bytes as block")?;
+
+        Ok(block)
+    }
+}
+
+#[async_trait]
+impl BlockNotifications for EsploraConnector {
+    async fn wait_for_new_block(&self) -> Result<()> {
+        self.polling().await?.wait_for_new_block().await
+    }
+}
+
+#[async_trait]
+impl ConnectedNetwork for EsploraConnector {
+    type Network = ledger::Bitcoin;
+
+    async fn connected_network(&self) -> Result<ledger::Bitcoin> {
+        if let Some(network) = *self.network.lock().expect("mutex is not poisoned") {
+            return Ok(network);
+        }
+
+        let genesis_hash = self.get_block_hash("block-height/0").await?;
+        let network = network_from_genesis_hash(genesis_hash)?;
+        *self.network.lock().expect("mutex is not poisoned") = Some(network);
+
+        Ok(network)
+    }
+}
+
+#[async_trait]
+impl TipHeight for EsploraConnector {
+    async fn tip_height(&self) -> Result<u64> {
+        let height = self
+            .get("blocks/tip/height")
+            .await?
+            .text()
+            .await?
+            .trim()
+            .parse()
+            .context("failed to parse tip height")?;
+
+        Ok(height)
+    }
+}
+
+/// Esplora returns the mempool and the 25 most recent confirmed transactions
+/// of a script. That is plenty for the scripts of our HTLCs.
+#[async_trait]
+impl TransactionsByScript for EsploraConnector {
+    async fn transactions_by_script(
+        &self,
+        script: &Script,
+    ) -> Result<Option<Vec<ScriptTransaction>>> {
+        let transactions = self
+            .get(&format!("scripthash/{}/txs", script_hash(script)))
+            .await?
+            .json::<Vec<EsploraTransaction>>()
+            .await
+            .context("failed to deserialize JSON response as transactions")?
+            .into_iter()
+            .map(ScriptTransaction::from)
+            .collect();
+
+        Ok(Some(transactions))
+    }
+
+    async fn transaction_by_txid(&self, txid: Txid) -> Result<bitcoin::Transaction> {
+        let bytes = self.get(&format!("tx/{}/raw", txid)).await?.bytes().await?;
+        let transaction =
+            deserialize(&bytes).context("failed to deserialize bytes as transaction")?;
+
+        Ok(transaction)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+struct EsploraTransaction {
+    txid: Txid,
+    status: Status,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+struct Status {
+    block_height: Option<u32>,
+}
+
+impl From<EsploraTransaction> for ScriptTransaction {
+    fn from(transaction: EsploraTransaction) -> Self {
+        Self {
+            txid: transaction.txid,
+            height: transaction.status.block_height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::{blockdata::constants::genesis_block, consensus::serialize};
+    use std::collections::HashMap;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    /// Serves the given bodies by path, just enough HTTP for our client.
+    async fn serve(bodies: HashMap<String, Vec<u8>>) -> Url {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/api", listener.local_addr().unwrap())
+            .parse()
+            .unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+
+                let mut request = vec![0u8; 4096];
+                let n = stream.read(&mut request).await.unwrap();
+                let request = String::from_utf8_lossy(&request[..n]);
+                let path = request.split_whitespace().nth(1).unwrap();
+
+                let (status, body) = match bodies.get(path) {
+                    Some(body) => ("200 OK", body.clone()),
+                    None => ("404 Not Found", Vec::new()),
+                };
+                let head = format!(
+                    "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                );
+                stream.write_all(head.as_bytes()).await.unwrap();
+                stream.write_all(&body).await.unwrap();
+            }
+        });
+
+        url
+    }
+
+    #[tokio::test]
+    async fn fetches_latest_block_and_network() {
+        let genesis = genesis_block(bitcoin::Network::Regtest);
+        let genesis_hash = genesis.block_hash().to_string();
+        let bodies = vec![
+            (
+                "/api/blocks/tip/hash".to_owned(),
+                genesis_hash.clone().into_bytes(),
+            ),
+            (
+                "/api/block-height/0".to_owned(),
+                genesis_hash.clone().into_bytes(),
+            ),
+            (
+                format!("/api/block/{}/raw", genesis_hash),
+                serialize(&genesis),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let connector = EsploraConnector::new(serve(bodies).await);
+
+        let latest_block = connector.latest_block().await.unwrap();
+        let network = connector.connected_network().await.unwrap();
+
+        assert_eq!(latest_block, genesis);
+        assert_eq!(network, ledger::Bitcoin::Regtest);
+    }
+
+    #[tokio::test]
+    async fn fetches_transactions_by_script() {
+        let script = Script::from(vec![0x51]);
+        let txs = r#"[
+  {
+    "txid": "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b",
+    "status": {
+      "confirmed": true,
+      "block_height": 0,
+      "block_hash": "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206"
+    }
+  },
+  {
+    "txid": "2a593b84b1943521be01f97a59fc7feba30e7e8527fb2ba20b0158ca09016d02",
+    "status": {
+      "confirmed": false
+    }
+  }
+]"#;
+        let bodies = vec![(
+            format!("/api/scripthash/{}/txs", script_hash(&script)),
+            txs.as_bytes().to_vec(),
+        )]
+        .into_iter()
+        .collect();
+        let connector = EsploraConnector::new(serve(bodies).await);
+
+        let transactions = connector
+            .transactions_by_script(&script)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].height, Some(0));
+        assert_eq!(transactions[1].height, None);
+    }
+
+    #[test]
+    fn base_url_keeps_its_path() {
+        let connector = EsploraConnector::new("https://blockstream.info/api".parse().unwrap());
+
+        assert_eq!(
+            connector.url("blocks/tip/hash"),
+            "https://blockstream.info/api/blocks/tip/hash"
+                .parse()
+                .unwrap()
+        );
+    }
+}
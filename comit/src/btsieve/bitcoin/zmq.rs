@@ -0,0 +1,291 @@
+use anyhow::{Context, Result};
+use derivative::Derivative;
+use futures::future::{self, Either};
+use reqwest::Url;
+use std::convert::TryFrom;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::{broadcast, Mutex},
+};
+
+/// The topic under which bitcoind publishes the hash of every new block.
+const TOPIC: &[u8] = b"hashblock";
+
+/// Notifications only consist of a topic, a block hash and a sequence number.
+/// We refuse larger frames instead of allocating whatever the peer announces.
+const MAX_FRAME_SIZE: u64 = 1024;
+
+/// Flags of a ZMTP frame.
+const MORE: u8 = 0x01;
+const LONG: u8 = 0x02;
+const COMMAND: u8 = 0x04;
+
+/// A `hashblock` subscription via the ZMQ endpoint of bitcoind
+/// (`-zmqpubhashblock`).
+///
+/// We speak just enough ZMTP 3.0 to subscribe to a publisher that does not
+/// use any security mechanism, which is how bitcoind publishes, to not
+/// depend on libzmq.
+///
+/// Like `NewHeads`, the subscription is shared between all callers: Whoever
+/// gets hold of the connection reads the next notification and notifies
+/// everybody else through a broadcast channel. The connection is
+/// (re-)established lazily.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct HashBlocks {
+    url: Url,
+    #[derivative(Debug = "ignore")]
+    subscription: Mutex<Option<TcpStream>>,
+    #[derivative(Debug = "ignore")]
+    new_block: broadcast::Sender<()>,
+}
+
+impl HashBlocks {
+    pub fn new(url: Url) -> Self {
+        let (new_block, _) = broadcast::channel(1);
+
+        Self {
+            url,
+            subscription: Mutex::new(None),
+            new_block,
+        }
+    }
+
+    /// Resolves once bitcoind announces a new block.
+    pub async fn wait_for_new_block(&self) -> Result<()> {
+        let mut new_block = self.new_block.subscribe();
+
+        match future::select(
+            Box::pin(new_block.recv()),
+            Box::pin(self.subscription.lock()),
+        )
+        .await
+        {
+            // Lagging behind still means that there is a new block.
+            Either::Left((..)) => Ok(()),
+            Either::Right((mut subscription, _)) => {
+                if let Err(e) = self.next_block(&mut subscription).await {
+                    // Reconnect on the next call.
+                    *subscription = None;
+                    return Err(e);
+                }
+
+                // Nobody might be waiting, that is fine.
+                let _ = self.new_block.send(());
+
+                Ok(())
+            }
+        }
+    }
+
+    async fn next_block(&self, subscription: &mut Option<TcpStream>) -> Result<()> {
+        if subscription.is_none() {
+            *subscription = Some(self.subscribe().await?);
+        }
+
+        loop {
+            let flags = subscription
+                .as_mut()
+                .expect("subscription was established above")
+                .read_u8()
+                .await?;
+
+            // Waiting for a message can be cancelled, e.g. by a timeout. Once
+            // it arrives, we own the connection until the message is read, a
+            // half-read message would leave us out of step with the frames.
+            let mut stream = subscription.take().expect("subscription is set");
+            let message = read_message(&mut stream, flags).await?;
+            *subscription = Some(stream);
+
+            match message.as_slice() {
+                [topic, hash, ..] if topic.as_slice() == TOPIC => {
+                    tracing::trace!("new block {}", hex::encode(hash));
+
+                    return Ok(());
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    async fn subscribe(&self) -> Result<TcpStream> {
+        let host = self.url.host_str().context("ZMQ URL has no host")?;
+        let port = self.url.port().context("ZMQ URL has no port")?;
+
+        let mut stream = TcpStream::connect((host, port))
+            .await
+            .with_context(|| format!("failed to connect to {}", self.url))?;
+
+        stream.write_all(&greeting()).await?;
+        let mut peer_greeting = [0; 64];
+        stream.read_exact(&mut peer_greeting).await?;
+        anyhow::ensure!(
+            peer_greeting[0] == 0xff && peer_greeting[9] == 0x7f && peer_greeting[10] >= 3,
+            "{} does not speak ZMTP 3",
+            self.url
+        );
+        anyhow::ensure!(
+            peer_greeting[12..32] == greeting()[12..32],
+            "{} requires a security mechanism",
+            self.url
+        );
+
+        write_frame(&mut stream, COMMAND, &ready(b"SUB")).await?;
+        let (flags, command) = read_frame(&mut stream).await?;
+        anyhow::ensure!(
+            flags & COMMAND != 0 && command.starts_with(b"\x05READY"),
+            "ZMQ handshake with {} failed",
+            self.url
+        );
+
+        // ZMTP 3.0 subscribes with a message that starts with 1.
+        let mut subscribe = vec![1];
+        subscribe.extend_from_slice(TOPIC);
+        write_frame(&mut stream, 0, &subscribe).await?;
+
+        tracing::debug!("subscribed to new blocks at {}", self.url);
+
+        Ok(stream)
+    }
+}
+
+/// The greeting of ZMTP 3.0 announcing the NULL security mechanism.
+fn greeting() -> [u8; 64] {
+    let mut greeting = [0; 64];
+    greeting[0] = 0xff;
+    greeting[9] = 0x7f;
+    greeting[10] = 3;
+    greeting[12..16].copy_from_slice(b"NULL");
+
+    greeting
+}
+
+/// The READY command that completes the handshake of the NULL security
+/// mechanism.
+fn ready(socket_type: &[u8]) -> Vec<u8> {
+    let mut command = vec![5];
+    command.extend_from_slice(b"READY");
+    command.push(11);
+    command.extend_from_slice(b"Socket-Type");
+    let size = u32::try_from(socket_type.len()).expect("socket types are short");
+    command.extend_from_slice(&size.to_be_bytes());
+    command.extend_from_slice(socket_type);
+
+    command
+}
+
+async fn write_frame(stream: &mut TcpStream, flags: u8, body: &[u8]) -> Result<()> {
+    let size = u8::try_from(body.len()).expect("we only send short frames");
+
+    let mut frame = vec![flags, size];
+    frame.extend_from_slice(body);
+    stream.write_all(&frame).await?;
+
+    Ok(())
+}
+
+async fn read_frame(stream: &mut TcpStream) -> Result<(u8, Vec<u8>)> {
+    let flags = stream.read_u8().await?;
+    let body = read_frame_body(stream, flags).await?;
+
+    Ok((flags, body))
+}
+
+async fn read_frame_body(stream: &mut TcpStream, flags: u8) -> Result<Vec<u8>> {
+    let size = if flags & LONG != 0 {
+        stream.read_u64().await?
+    } else {
+        u64::from(stream.read_u8().await?)
+    };
+    anyhow::ensure!(
+        size <= MAX_FRAME_SIZE,
+        "ZMQ frame of {} bytes is too large",
+        size
+    );
+
+    let mut body = vec![0; usize::try_from(size)?];
+    stream.read_exact(&mut body).await?;
+
+    Ok(body)
+}
+
+/// Reads the frames of a message whose first frame has the given flags,
+/// skipping commands.
+async fn read_message(stream: &mut TcpStream, mut flags: u8) -> Result<Vec<Vec<u8>>> {
+    let mut frames = Vec::new();
+
+    loop {
+        let body = read_frame_body(stream, flags).await?;
+        if flags & COMMAND == 0 {
+            frames.push(body);
+            if flags & MORE == 0 {
+                return Ok(frames);
+            }
+        }
+
+        flags = stream.read_u8().await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Accepts a single subscriber like bitcoind and publishes the given
+    /// notifications to it.
+    async fn publish(notifications: Vec<(&'static [u8], [u8; 32])>) -> Url {
+        let mut listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("tcp://{}", listener.local_addr().unwrap())
+            .parse()
+            .unwrap();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+
+            let mut peer_greeting = [0; 64];
+            stream.read_exact(&mut peer_greeting).await.unwrap();
+            stream.write_all(&greeting()).await.unwrap();
+
+            let (flags, command) = read_frame(&mut stream).await.unwrap();
+            assert_eq!((flags, command), (COMMAND, ready(b"SUB")));
+            write_frame(&mut stream, COMMAND, &ready(b"PUB"))
+                .await
+                .unwrap();
+
+            let (_, subscribe) = read_frame(&mut stream).await.unwrap();
+            assert_eq!(subscribe, b"\x01hashblock");
+
+            for (sequence, (topic, hash)) in notifications.into_iter().enumerate() {
+                write_frame(&mut stream, MORE, topic).await.unwrap();
+                write_frame(&mut stream, MORE, &hash).await.unwrap();
+                let sequence = u32::try_from(sequence).unwrap();
+                write_frame(&mut stream, 0, &sequence.to_le_bytes())
+                    .await
+                    .unwrap();
+            }
+        });
+
+        url
+    }
+
+    #[tokio::test]
+    async fn resolves_once_a_block_is_published() {
+        let url = publish(vec![(&b"rawtx"[..], [1; 32]), (TOPIC, [2; 32])]).await;
+        let hash_blocks = HashBlocks::new(url);
+
+        hash_blocks.wait_for_new_block().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn fails_if_publisher_closes_the_connection() {
+        let url = publish(vec![(&b"rawtx"[..], [1; 32])]).await;
+        let hash_blocks = HashBlocks::new(url);
+
+        let result = hash_blocks.wait_for_new_block().await;
+
+        assert!(result.is_err());
+    }
+}
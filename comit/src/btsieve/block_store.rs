@@ -0,0 +1,220 @@
+use crate::Never;
+use anyhow::{Context, Result};
+use std::{convert::TryInto, future::Future, path::Path, time::Duration};
+use time::OffsetDateTime;
+
+/// How often the daemons prune the block store.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Block timestamps may be up to two hours off, hence we keep the blocks of
+/// this period before the start of the oldest swap as well.
+const PRUNE_MARGIN: Duration = Duration::from_secs(2 * 60 * 60);
+
+/// Every entry is prefixed with a unix timestamp, big-endian encoded.
+const TIMESTAMP_LEN: usize = 8;
+
+/// Stores blocks and receipts on disk so they survive a restart.
+///
+/// Entries are keyed by hash and hence never become stale, a chain
+/// reorganisation merely means that we do not ask for some of them anymore.
+/// Blocks are stored as received from the node, including the link to their
+/// parent, which allows walking back the chain without asking the node.
+///
+/// Every entry carries the timestamp of its block, this allows us to prune
+/// everything that no active swap needs anymore.
+#[derive(Clone, Debug)]
+pub struct BlockStore {
+    db: sled::Db,
+}
+
+impl BlockStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        let db = sled::open(path)
+            .with_context(|| format!("failed to open block store at {}", path.display()))?;
+
+        Ok(Self { db })
+    }
+
+    #[cfg(test)]
+    pub fn temporary() -> Self {
+        let db = sled::Config::new()
+            .temporary(true)
+            .open()
+            .expect("to open temporary sled db");
+
+        Self { db }
+    }
+
+    pub(crate) fn tree(&self, name: &str) -> Result<Tree> {
+        let tree = self
+            .db
+            .open_tree(name)
+            .with_context(|| format!("failed to open {} in block store", name))?;
+
+        Ok(Tree(tree))
+    }
+
+    /// Removes the entries of all blocks that were mined long enough before
+    /// the start of the oldest active swap.
+    ///
+    /// Returns the number of removed entries.
+    pub fn prune(&self, start_of_oldest_swap: OffsetDateTime) -> Result<usize> {
+        let before = unix_timestamp(start_of_oldest_swap - PRUNE_MARGIN);
+        let mut pruned = 0;
+
+        for name in self.db.tree_names() {
+            let tree = self.db.open_tree(name)?;
+
+            for entry in tree.iter() {
+                let (key, value) = entry?;
+
+                // Corrupt entries are of no use either.
+                let keep = decode(&value).map_or(false, |(timestamp, _)| timestamp >= before);
+                if !keep {
+                    let _ = tree.remove(key)?;
+                    pruned += 1;
+                }
+            }
+        }
+
+        tracing::debug!("pruned {} entries from the block store", pruned);
+
+        Ok(pruned)
+    }
+
+    /// Prunes the store every hour, keeping the blocks the active swaps may
+    /// still look at, meant to be spawned as a task.
+    ///
+    /// `start_of_oldest_swap` looks up the start of the oldest active swap,
+    /// `None` if there is no active swap.
+    pub async fn prune_periodically<F, Fut>(self, start_of_oldest_swap: F) -> Never
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<Option<OffsetDateTime>>>,
+    {
+        loop {
+            let pruned = start_of_oldest_swap()
+                .await
+                .and_then(|start_of_oldest_swap| {
+                    self.prune(start_of_oldest_swap.unwrap_or_else(OffsetDateTime::now_utc))
+                });
+
+            if let Err(e) = pruned {
+                tracing::warn!("failed to prune block store: {:#}", e);
+            }
+
+            tokio::time::delay_for(PRUNE_INTERVAL).await;
+        }
+    }
+}
+
+/// One kind of entries in the [`BlockStore`], e.g. Bitcoin blocks.
+///
+/// Failing to access the store only costs us another request to the node,
+/// hence errors are logged instead of returned.
+#[derive(Clone, Debug)]
+pub(crate) struct Tree(sled::Tree);
+
+impl Tree {
+    pub fn load<T, D>(&self, key: &[u8], deserialize: D) -> Option<T>
+    where
+        D: FnOnce(&[u8]) -> Result<T>,
+    {
+        let result = self
+            .0
+            .get(key)
+            .map_err(anyhow::Error::from)
+            .and_then(|value| {
+                value
+                    .map(|value| decode(&value).and_then(|(_, data)| deserialize(data)))
+                    .transpose()
+            });
+
+        match result {
+            Ok(value) => value,
+            Err(e) => {
+                tracing::warn!(
+                    "failed to load {} from block store: {:#}",
+                    hex::encode(key),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    pub fn save(&self, key: &[u8], timestamp: u64, data: &[u8]) {
+        let mut value = Vec::with_capacity(TIMESTAMP_LEN + data.len());
+        value.extend_from_slice(&timestamp.to_be_bytes());
+        value.extend_from_slice(data);
+
+        if let Err(e) = self.0.insert(key, value) {
+            tracing::warn!(
+                "failed to save {} to block store: {:#}",
+                hex::encode(key),
+                e
+            );
+        }
+    }
+}
+
+/// The current time, for entries that do not belong to a block we know.
+pub(crate) fn now() -> u64 {
+    unix_timestamp(OffsetDateTime::now_utc())
+}
+
+fn unix_timestamp(time: OffsetDateTime) -> u64 {
+    // Unix timestamps before 1970 are not a concern.
+    #[allow(clippy::cast_sign_loss)]
+    let timestamp = time.timestamp().max(0) as u64;
+
+    timestamp
+}
+
+fn decode(value: &[u8]) -> Result<(u64, &[u8])> {
+    anyhow::ensure!(value.len() >= TIMESTAMP_LEN, "entry without timestamp");
+    let (timestamp, data) = value.split_at(TIMESTAMP_LEN);
+    let timestamp = timestamp.try_into().expect("slice has timestamp length");
+
+    Ok((u64::from_be_bytes(timestamp), data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deserialize(data: &[u8]) -> Result<Vec<u8>> {
+        Ok(data.to_vec())
+    }
+
+    #[test]
+    fn loads_saved_entry() {
+        let store = BlockStore::temporary();
+        let tree = store.tree("blocks").unwrap();
+
+        tree.save(b"hash", 1_600_000_000, b"block");
+
+        assert_eq!(tree.load(b"hash", deserialize), Some(b"block".to_vec()));
+        assert_eq!(tree.load(b"other", deserialize), None);
+    }
+
+    #[test]
+    fn prunes_entries_before_start_of_oldest_swap() {
+        let store = BlockStore::temporary();
+        let blocks = store.tree("blocks").unwrap();
+        let receipts = store.tree("receipts").unwrap();
+        let start_of_swap = OffsetDateTime::from_unix_timestamp(1_600_000_000);
+        let margin = PRUNE_MARGIN.as_secs();
+
+        blocks.save(b"old", 1_600_000_000 - margin - 1, b"block");
+        blocks.save(b"within margin", 1_600_000_000 - margin, b"block");
+        receipts.save(b"new", 1_600_000_001, b"receipt");
+
+        let pruned = store.prune(start_of_swap).unwrap();
+
+        assert_eq!(pruned, 1);
+        assert_eq!(blocks.load(b"old", deserialize), None);
+        assert!(blocks.load(b"within margin", deserialize).is_some());
+        assert!(receipts.load(b"new", deserialize).is_some());
+    }
+}
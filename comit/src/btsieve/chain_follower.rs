@@ -0,0 +1,555 @@
+use crate::{
+    btsieve::{
+        bitcoin::{Mempool, ScriptTransaction, TransactionsByScript},
+        ethereum::{
+            BlockByNumber, Event, GetLogs, PendingTransactions, ReceiptByHash, TransactionByHash,
+        },
+        BlockByHash, BlockHash, BlockNotifications, ConnectedNetwork, LatestBlock,
+        PreviousBlockHash,
+    },
+    ethereum::{Block, Hash, Log, Transaction, TransactionReceipt},
+    Never,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use derivative::Derivative;
+use std::{collections::VecDeque, ops::RangeInclusive, sync::Mutex, time::Duration};
+use tokio::sync::broadcast;
+
+/// The number of blocks of the canonical chain we keep track of.
+///
+/// Reorgs deeper than this are treated like switching to a new chain.
+const MAX_TRACKED_BLOCKS: usize = 144;
+
+/// How many new blocks a subscriber may fall behind before it misses some.
+const NEW_BLOCKS_CAPACITY: usize = MAX_TRACKED_BLOCKS;
+
+/// How long to wait before following the chain again after a failure.
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Upper bound for waiting on a new block from the follower.
+///
+/// A watcher may fetch the latest block right before the follower announces a
+/// new one and start waiting right after. Waking up regularly makes sure such a
+/// block is picked up in time. This is cheap because the latest block is
+/// served from memory.
+const MAX_WAIT: Duration = Duration::from_secs(10);
+
+/// Follows the chain of a ledger on behalf of any number of watchers.
+///
+/// Instead of every watcher fetching the latest block and waiting for new
+/// blocks itself, a single task runs [`ChainFollower::follow`]. Watchers use
+/// the follower as their connector: The latest block is served from memory,
+/// new blocks are pushed to all of them at once, see
+/// [`LatestBlock::new_blocks`], and all other requests are passed on to the
+/// wrapped connector, usually a cache. Blocks that the follower missed in
+/// between two updates are fetched by the follower and pushed as well, hence
+/// watchers only walk back the chain themselves to look for the blocks mined
+/// before they started.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct ChainFollower<C, B, H> {
+    connector: C,
+    #[derivative(Debug = "ignore")]
+    chain: Mutex<Chain<B, H>>,
+    #[derivative(Debug = "ignore")]
+    new_blocks: broadcast::Sender<B>,
+    /// Wakes up the watchers waiting for a new block, cheaper than cloning
+    /// the block for every one of them.
+    #[derivative(Debug = "ignore")]
+    tip_updates: broadcast::Sender<()>,
+}
+
+struct Chain<B, H> {
+    tip: Option<B>,
+    /// Hashes of the canonical chain, newest first.
+    hashes: VecDeque<H>,
+}
+
+impl<C, B, H> ChainFollower<C, B, H>
+where
+    C: LatestBlock<Block = B> + BlockByHash<Block = B, BlockHash = H> + BlockNotifications,
+    B: BlockHash<BlockHash = H> + PreviousBlockHash<BlockHash = H> + Clone,
+    H: Eq + Copy,
+{
+    pub fn new(connector: C) -> Self {
+        let (new_blocks, _) = broadcast::channel(NEW_BLOCKS_CAPACITY);
+        let (tip_updates, _) = broadcast::channel(1);
+
+        Self {
+            connector,
+            chain: Mutex::new(Chain {
+                tip: None,
+                hashes: VecDeque::new(),
+            }),
+            new_blocks,
+            tip_updates,
+        }
+    }
+
+    /// Follows the chain until the end of time.
+    ///
+    /// This is meant to be spawned as a task. Until it is, the follower
+    /// behaves like the wrapped connector.
+    pub async fn follow(&self) -> Never {
+        loop {
+            let result = async {
+                self.update().await?;
+                self.connector.wait_for_new_block().await
+            }
+            .await;
+
+            if let Err(e) = result {
+                tracing::warn!(
+                    "failed to follow the chain, retrying in {} seconds: {:#}",
+                    RETRY_INTERVAL.as_secs(),
+                    e
+                );
+                tokio::time::delay_for(RETRY_INTERVAL).await;
+            }
+        }
+    }
+
+    async fn update(&self) -> Result<()> {
+        let latest_block = self.connector.latest_block().await?;
+        let latest_hash = latest_block.block_hash();
+
+        if self.lock_chain().hashes.front() == Some(&latest_hash) {
+            return Ok(());
+        }
+
+        // Walk back until we reach a block we know to fetch all blocks we
+        // missed. There is nothing to walk back to when we just started.
+        let mut parent_hash = latest_block.previous_block_hash();
+        let mut new_blocks = vec![latest_block];
+        while new_blocks.len() < MAX_TRACKED_BLOCKS && self.is_unknown(&parent_hash) {
+            let parent = self.connector.block_by_hash(parent_hash).await?;
+
+            parent_hash = parent.previous_block_hash();
+            new_blocks.push(parent);
+        }
+
+        let mut chain = self.lock_chain();
+        match chain.hashes.iter().position(|hash| *hash == parent_hash) {
+            Some(0) => {}
+            Some(orphaned) => {
+                tracing::info!("chain reorganisation, {} blocks were orphaned", orphaned);
+                chain.hashes.drain(..orphaned);
+            }
+            None => chain.hashes.clear(),
+        }
+        for block in new_blocks.iter().rev() {
+            chain.hashes.push_front(block.block_hash());
+        }
+        chain.hashes.truncate(MAX_TRACKED_BLOCKS);
+        chain.tip = new_blocks.first().cloned();
+        drop(chain);
+
+        // Nobody might be subscribed, that is fine.
+        for block in new_blocks.into_iter().rev() {
+            let _ = self.new_blocks.send(block);
+        }
+        let _ = self.tip_updates.send(());
+
+        Ok(())
+    }
+
+    fn is_unknown(&self, hash: &H) -> bool {
+        let chain = self.lock_chain();
+
+        !chain.hashes.is_empty() && !chain.hashes.contains(hash)
+    }
+}
+
+impl<C, B, H> ChainFollower<C, B, H> {
+    fn lock_chain(&self) -> std::sync::MutexGuard<'_, Chain<B, H>> {
+        self.chain.lock().expect("mutex is not poisoned")
+    }
+
+    /// Subscribes to the blocks added to the chain, oldest first.
+    ///
+    /// After a reorg, the blocks of the new chain are pushed from the fork
+    /// onwards. Subscribers that fall behind by more than
+    /// [`NEW_BLOCKS_CAPACITY`] blocks miss the oldest ones.
+    pub fn subscribe(&self) -> broadcast::Receiver<B> {
+        self.new_blocks.subscribe()
+    }
+
+    fn is_following(&self) -> bool {
+        self.lock_chain().tip.is_some()
+    }
+}
+
+#[async_trait]
+impl<C, B, H> LatestBlock for ChainFollower<C, B, H>
+where
+    C: LatestBlock<Block = B>,
+    B: Clone + Send + Sync + 'static,
+    H: Send + Sync + 'static,
+{
+    type Block = B;
+
+    async fn latest_block(&self) -> Result<Self::Block> {
+        let tip = self.lock_chain().tip.clone();
+
+        match tip {
+            Some(tip) => Ok(tip),
+            None => self.connector.latest_block().await,
+        }
+    }
+
+    /// Nothing is pushed until the follower follows the chain, until then
+    /// watchers fetch the latest block themselves.
+    fn new_blocks(&self) -> Option<broadcast::Receiver<Self::Block>> {
+        if self.is_following() {
+            Some(self.subscribe())
+        } else {
+            None
+        }
+    }
+}
+
+#[async_trait]
+impl<C, B, H> BlockNotifications for ChainFollower<C, B, H>
+where
+    C: BlockNotifications,
+    B: Clone + Send + Sync + 'static,
+    H: Send + Sync + 'static,
+{
+    async fn wait_for_new_block(&self) -> Result<()> {
+        if !self.is_following() {
+            return self.connector.wait_for_new_block().await;
+        }
+
+        let mut tip_updates = self.tip_updates.subscribe();
+        let _ = tokio::time::timeout(MAX_WAIT, tip_updates.recv()).await;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<C, B, H> BlockByHash for ChainFollower<C, B, H>
+where
+    C: BlockByHash<Block = B, BlockHash = H>,
+    B: Send + Sync + 'static,
+    H: Send + Sync + 'static,
+{
+    type Block = B;
+    type BlockHash = H;
+
+    async fn block_by_hash(&self, block_hash: Self::BlockHash) -> Result<Self::Block> {
+        self.connector.block_by_hash(block_hash).await
+    }
+}
+
+#[async_trait]
+impl<C, B, H> ConnectedNetwork for ChainFollower<C, B, H>
+where
+    C: ConnectedNetwork,
+    B: Send + Sync + 'static,
+    H: Send + Sync + 'static,
+{
+    type Network = C::Network;
+
+    async fn connected_network(&self) -> Result<Self::Network> {
+        self.connector.connected_network().await
+    }
+}
+
+#[async_trait]
+impl<C, B, H> ReceiptByHash for ChainFollower<C, B, H>
+where
+    C: ReceiptByHash,
+    B: Send + Sync + 'static,
+    H: Send + Sync + 'static,
+{
+    async fn receipt_by_hash(&self, transaction_hash: Hash) -> Result<TransactionReceipt> {
+        self.connector.receipt_by_hash(transaction_hash).await
+    }
+}
+
+#[async_trait]
+impl<C, B, H> TransactionByHash for ChainFollower<C, B, H>
+where
+    C: TransactionByHash,
+    B: Send + Sync + 'static,
+    H: Send + Sync + 'static,
+{
+    async fn transaction_by_hash(&self, transaction_hash: Hash) -> Result<Transaction> {
+        self.connector.transaction_by_hash(transaction_hash).await
+    }
+}
+
+#[async_trait]
+impl<C, B, H> BlockByNumber for ChainFollower<C, B, H>
+where
+    C: BlockByNumber,
+    B: Send + Sync + 'static,
+    H: Send + Sync + 'static,
+{
+    async fn block_by_number(&self, number: u64) -> Result<Block> {
+        self.connector.block_by_number(number).await
+    }
+}
+
+#[async_trait]
+impl<C, B, H> GetLogs for ChainFollower<C, B, H>
+where
+    C: GetLogs,
+    B: Send + Sync + 'static,
+    H: Send + Sync + 'static,
+{
+    async fn get_logs(&self, event: Event, blocks: RangeInclusive<u64>) -> Result<Vec<Log>> {
+        self.connector.get_logs(event, blocks).await
+    }
+}
+
+#[async_trait]
+impl<C, B, H> PendingTransactions for ChainFollower<C, B, H>
+where
+    C: PendingTransactions,
+    B: Send + Sync + 'static,
+    H: Send + Sync + 'static,
+{
+    async fn pending_transaction_hashes(&self) -> Result<Vec<Hash>> {
+        self.connector.pending_transaction_hashes().await
+    }
+
+    async fn pending_transaction(&self, transaction_hash: Hash) -> Result<Transaction> {
+        self.connector.pending_transaction(transaction_hash).await
+    }
+}
+
+#[async_trait]
+impl<C, B, H> Mempool for ChainFollower<C, B, H>
+where
+    C: Mempool,
+    B: Send + Sync + 'static,
+    H: Send + Sync + 'static,
+{
+    async fn mempool_txids(&self) -> Result<Vec<bitcoin::Txid>> {
+        self.connector.mempool_txids().await
+    }
+
+    async fn mempool_transaction(&self, txid: bitcoin::Txid) -> Result<bitcoin::Transaction> {
+        self.connector.mempool_transaction(txid).await
+    }
+}
+
+#[async_trait]
+impl<C, B, H> TransactionsByScript for ChainFollower<C, B, H>
+where
+    C: TransactionsByScript,
+    B: Send + Sync + 'static,
+    H: Send + Sync + 'static,
+{
+    async fn transactions_by_script(
+        &self,
+        script: &bitcoin::Script,
+    ) -> Result<Option<Vec<ScriptTransaction>>> {
+        self.connector.transactions_by_script(script).await
+    }
+
+    async fn transaction_by_txid(&self, txid: bitcoin::Txid) -> Result<bitcoin::Transaction> {
+        self.connector.transaction_by_txid(txid).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btsieve::{fetch_blocks_since, Polling, Predates};
+    use genawaiter::{sync::Gen, GeneratorState};
+    use std::future::Future;
+    use time::OffsetDateTime;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct FakeBlock {
+        hash: u32,
+        parent: u32,
+    }
+
+    impl BlockHash for FakeBlock {
+        type BlockHash = u32;
+
+        fn block_hash(&self) -> u32 {
+            self.hash
+        }
+    }
+
+    impl PreviousBlockHash for FakeBlock {
+        type BlockHash = u32;
+
+        fn previous_block_hash(&self) -> u32 {
+            self.parent
+        }
+    }
+
+    /// Only the genesis block predates the start of the swap.
+    impl Predates for FakeBlock {
+        fn predates(&self, _: OffsetDateTime) -> bool {
+            self.hash == 0
+        }
+    }
+
+    /// A chain where the hash of every block is its height, unless the block
+    /// was mined on a fork.
+    #[derive(Debug)]
+    struct FakeConnector {
+        blocks: Mutex<Vec<FakeBlock>>,
+    }
+
+    impl FakeConnector {
+        fn new(height: u32) -> Self {
+            let blocks = (0..=height)
+                .map(|hash| FakeBlock {
+                    hash,
+                    parent: hash.saturating_sub(1),
+                })
+                .collect();
+
+            Self {
+                blocks: Mutex::new(blocks),
+            }
+        }
+
+        fn mine(&self, hash: u32) {
+            let mut blocks = self.blocks.lock().unwrap();
+            let parent = blocks.last().unwrap().hash;
+
+            blocks.push(FakeBlock { hash, parent });
+        }
+
+        fn orphan(&self, count: usize) {
+            let mut blocks = self.blocks.lock().unwrap();
+            let height = blocks.len() - count;
+
+            blocks.truncate(height);
+        }
+    }
+
+    #[async_trait]
+    impl LatestBlock for FakeConnector {
+        type Block = FakeBlock;
+
+        async fn latest_block(&self) -> Result<FakeBlock> {
+            Ok(*self.blocks.lock().unwrap().last().unwrap())
+        }
+    }
+
+    #[async_trait]
+    impl BlockByHash for FakeConnector {
+        type Block = FakeBlock;
+        type BlockHash = u32;
+
+        async fn block_by_hash(&self, block_hash: u32) -> Result<FakeBlock> {
+            self.blocks
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|block| block.hash == block_hash)
+                .copied()
+                .ok_or_else(|| anyhow::anyhow!("unknown block {}", block_hash))
+        }
+    }
+
+    #[async_trait]
+    impl BlockNotifications for FakeConnector {
+        async fn wait_for_new_block(&self) -> Result<()> {
+            Polling::new(Duration::from_secs(0))
+                .wait_for_new_block()
+                .await
+        }
+    }
+
+    fn hashes(follower: &ChainFollower<FakeConnector, FakeBlock, u32>) -> Vec<u32> {
+        follower.lock_chain().hashes.iter().copied().collect()
+    }
+
+    async fn next_hash<F>(blocks: &mut Gen<FakeBlock, (), F>) -> u32
+    where
+        F: Future<Output = Result<Never>>,
+    {
+        match blocks.async_resume().await {
+            GeneratorState::Yielded(block) => block.hash,
+            GeneratorState::Complete(Ok(never)) => match never {},
+            GeneratorState::Complete(Err(e)) => panic!("failed to fetch blocks: {:#}", e),
+        }
+    }
+
+    #[tokio::test]
+    async fn serves_latest_block_from_memory_once_following() {
+        let follower = ChainFollower::new(FakeConnector::new(10));
+
+        follower.update().await.unwrap();
+        follower.connector.mine(11);
+
+        let latest_block = follower.latest_block().await.unwrap();
+
+        assert_eq!(latest_block.hash, 10);
+    }
+
+    #[tokio::test]
+    async fn tracks_missed_blocks() {
+        let follower = ChainFollower::new(FakeConnector::new(10));
+
+        follower.update().await.unwrap();
+        follower.connector.mine(11);
+        follower.connector.mine(12);
+        follower.update().await.unwrap();
+
+        assert_eq!(hashes(&follower), vec![12, 11, 10]);
+    }
+
+    #[tokio::test]
+    async fn pushes_missed_blocks_to_subscribers_oldest_first() {
+        let follower = ChainFollower::new(FakeConnector::new(10));
+
+        follower.update().await.unwrap();
+        let mut new_blocks = follower.subscribe();
+        follower.connector.mine(11);
+        follower.connector.mine(12);
+        follower.update().await.unwrap();
+
+        assert_eq!(new_blocks.recv().await.unwrap().hash, 11);
+        assert_eq!(new_blocks.recv().await.unwrap().hash, 12);
+    }
+
+    #[tokio::test]
+    async fn feeds_watchers_with_the_pushed_blocks() {
+        let follower = ChainFollower::new(FakeConnector::new(2));
+
+        follower.update().await.unwrap();
+        let mut blocks = fetch_blocks_since(&follower, OffsetDateTime::now_utc());
+        let mut yielded = Vec::new();
+        for _ in 0..3 {
+            yielded.push(next_hash(&mut blocks).await);
+        }
+        follower.connector.mine(3);
+        follower.connector.mine(4);
+        follower.update().await.unwrap();
+        for _ in 0..2 {
+            yielded.push(next_hash(&mut blocks).await);
+        }
+
+        // Walking back yields the newest block first, pushed blocks are
+        // yielded oldest first.
+        assert_eq!(yielded, vec![2, 1, 0, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn drops_orphaned_blocks_on_reorg() {
+        let follower = ChainFollower::new(FakeConnector::new(10));
+
+        follower.update().await.unwrap();
+        follower.connector.mine(11);
+        follower.update().await.unwrap();
+        follower.connector.orphan(1);
+        follower.connector.mine(111);
+        follower.connector.mine(112);
+        follower.update().await.unwrap();
+
+        assert_eq!(hashes(&follower), vec![112, 111, 10]);
+    }
+}
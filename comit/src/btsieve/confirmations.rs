@@ -0,0 +1,359 @@
+use crate::btsieve::{BlockByHash, BlockHash, BlockNotifications, LatestBlock, PreviousBlockHash};
+use anyhow::Result;
+use std::future::Future;
+use thiserror::Error;
+
+/// The block that included a transaction we were waiting for was orphaned.
+///
+/// The transaction may be included in a different block of the new chain or
+/// may have disappeared altogether, hence whatever was derived from it has to
+/// be rolled back and the transaction has to be looked for again.
+#[derive(Clone, Copy, Debug, Error)]
+#[error("block including the transaction was orphaned by a chain reorganisation")]
+pub struct Reorg;
+
+/// Runs the watcher until it finds a transaction that is not orphaned.
+///
+/// Every time the watcher fails with [`Reorg`], the transaction is looked for
+/// again from the start of the swap.
+pub async fn restart_on_reorg<F, Fut, T>(mut watch: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    loop {
+        match watch().await {
+            Err(e) if e.downcast_ref::<Reorg>().is_some() => {
+                tracing::warn!("{:#}, watching for the transaction again", e);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Waits until the block with the given hash has the required number of
+/// confirmations.
+///
+/// The block itself counts as the first confirmation, i.e. a transaction that
+/// was just mined has one confirmation. Fails with [`Reorg`] if the block is
+/// orphaned whilst we are waiting.
+///
+/// Reorgs are detected by remembering the chain on top of the block as well as
+/// as many of its ancestors as we require confirmations. A chain
+/// reorganisation that is deeper than that violates our assumption of finality
+/// and cannot be detected.
+///
+/// We walk back at most as many blocks from the tip as we remember instead of
+/// all the way to genesis. The first time we look, the block was just found in
+/// the chain, not reaching it means that it is buried deeper than that. Later
+/// on, it means that the block was orphaned by a chain that forked below its
+/// ancestors, or that more blocks were mined in the meantime than we can tell
+/// apart from such a chain, hence we report a [`Reorg`] and the transaction is
+/// looked for again.
+pub async fn wait_for_confirmations<C, B, H>(
+    connector: &C,
+    block_hash: H,
+    confirmations: u8,
+) -> Result<()>
+where
+    C: LatestBlock<Block = B> + BlockByHash<Block = B, BlockHash = H> + BlockNotifications,
+    B: BlockHash<BlockHash = H> + PreviousBlockHash<BlockHash = H>,
+    H: Eq + Copy,
+{
+    let required = usize::from(confirmations);
+
+    if required <= 1 {
+        return Ok(());
+    }
+
+    // The block and the blocks on top of it, oldest first.
+    let mut confirming = vec![block_hash];
+
+    let mut ancestors = Vec::with_capacity(required);
+    let mut block = connector.block_by_hash(block_hash).await?;
+    while ancestors.len() < required {
+        let parent_hash = block.previous_block_hash();
+
+        ancestors.push(parent_hash);
+        if ancestors.len() < required {
+            block = connector.block_by_hash(parent_hash).await?;
+        }
+    }
+
+    let mut first_look = true;
+
+    loop {
+        let latest_block = connector.latest_block().await?;
+        let max_walk = confirming.len() + ancestors.len();
+
+        let mut new_hashes = Vec::new();
+        let mut current = latest_block;
+        loop {
+            let hash = current.block_hash();
+
+            if let Some(index) = confirming.iter().position(|known| *known == hash) {
+                let orphaned = confirming.len() - index - 1;
+                if orphaned > 0 {
+                    tracing::info!(
+                        "chain reorganisation on top of the block, {} blocks were orphaned",
+                        orphaned
+                    );
+                }
+
+                confirming.truncate(index + 1);
+                confirming.extend(new_hashes.into_iter().rev());
+                break;
+            }
+
+            if ancestors.contains(&hash) {
+                return Err(Reorg.into());
+            }
+
+            if new_hashes.len() >= max_walk {
+                if first_look {
+                    return Ok(());
+                }
+
+                tracing::warn!(
+                    "walked back {} blocks from the tip without reaching the block or its ancestors",
+                    max_walk
+                );
+                return Err(Reorg.into());
+            }
+
+            // We keep walking back even if there are enough blocks on top of
+            // the block, these blocks may belong to a chain that orphaned it.
+            new_hashes.push(hash);
+
+            current = connector
+                .block_by_hash(current.previous_block_hash())
+                .await?;
+        }
+
+        tracing::debug!(
+            "transaction has {} out of {} confirmations",
+            confirming.len(),
+            required
+        );
+
+        if confirming.len() >= required {
+            return Ok(());
+        }
+
+        first_look = false;
+        connector.wait_for_new_block().await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btsieve::Polling;
+    use async_trait::async_trait;
+    use std::{sync::Mutex, time::Duration};
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct FakeBlock {
+        hash: u32,
+        parent: u32,
+    }
+
+    impl BlockHash for FakeBlock {
+        type BlockHash = u32;
+
+        fn block_hash(&self) -> u32 {
+            self.hash
+        }
+    }
+
+    impl PreviousBlockHash for FakeBlock {
+        type BlockHash = u32;
+
+        fn previous_block_hash(&self) -> u32 {
+            self.parent
+        }
+    }
+
+    /// A chain where the hash of every block is its height, unless the block
+    /// was mined on a fork. Every time we wait for a new block, the next block
+    /// of the script is mined.
+    #[derive(Debug)]
+    struct FakeConnector {
+        blocks: Mutex<Vec<FakeBlock>>,
+        tip: Mutex<usize>,
+        script: Mutex<Vec<Mine>>,
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    enum Mine {
+        OnTip(u32),
+        OnTopOf(u32, u32),
+    }
+
+    impl FakeConnector {
+        fn new(height: u32, script: Vec<Mine>) -> Self {
+            let blocks = (0..=height)
+                .map(|hash| FakeBlock {
+                    hash,
+                    parent: hash.saturating_sub(1),
+                })
+                .collect::<Vec<_>>();
+            let tip = blocks.len() - 1;
+
+            Self {
+                blocks: Mutex::new(blocks),
+                tip: Mutex::new(tip),
+                script: Mutex::new(script.into_iter().rev().collect()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LatestBlock for FakeConnector {
+        type Block = FakeBlock;
+
+        async fn latest_block(&self) -> Result<FakeBlock> {
+            let tip = *self.tip.lock().unwrap();
+
+            Ok(self.blocks.lock().unwrap()[tip])
+        }
+    }
+
+    #[async_trait]
+    impl BlockByHash for FakeConnector {
+        type Block = FakeBlock;
+        type BlockHash = u32;
+
+        async fn block_by_hash(&self, block_hash: u32) -> Result<FakeBlock> {
+            self.blocks
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|block| block.hash == block_hash)
+                .copied()
+                .ok_or_else(|| anyhow::anyhow!("unknown block {}", block_hash))
+        }
+    }
+
+    #[async_trait]
+    impl BlockNotifications for FakeConnector {
+        async fn wait_for_new_block(&self) -> Result<()> {
+            let next = self
+                .script
+                .lock()
+                .unwrap()
+                .pop()
+                .expect("test to finish before running out of blocks");
+
+            {
+                let mut blocks = self.blocks.lock().unwrap();
+                let mut tip = self.tip.lock().unwrap();
+                let block = match next {
+                    Mine::OnTip(hash) => FakeBlock {
+                        hash,
+                        parent: blocks[*tip].hash,
+                    },
+                    Mine::OnTopOf(hash, parent) => FakeBlock { hash, parent },
+                };
+                blocks.push(block);
+                *tip = blocks.len() - 1;
+            }
+
+            Polling::new(Duration::from_secs(0))
+                .wait_for_new_block()
+                .await
+        }
+    }
+
+    #[tokio::test]
+    async fn waits_until_block_is_buried_deep_enough() {
+        let connector = FakeConnector::new(10, vec![Mine::OnTip(11), Mine::OnTip(12)]);
+
+        wait_for_confirmations(&connector, 10, 3).await.unwrap();
+
+        assert_eq!(connector.latest_block().await.unwrap().hash, 12);
+    }
+
+    #[tokio::test]
+    async fn returns_immediately_for_old_blocks() {
+        let connector = FakeConnector::new(10, vec![]);
+
+        wait_for_confirmations(&connector, 5, 3).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn survives_reorg_on_top_of_the_block() {
+        let connector = FakeConnector::new(10, vec![
+            Mine::OnTip(11),
+            Mine::OnTopOf(111, 10),
+            Mine::OnTip(112),
+        ]);
+
+        wait_for_confirmations(&connector, 10, 3).await.unwrap();
+
+        assert_eq!(connector.latest_block().await.unwrap().hash, 112);
+    }
+
+    #[tokio::test]
+    async fn reports_reorg_if_block_is_orphaned() {
+        let connector = FakeConnector::new(10, vec![Mine::OnTopOf(110, 9)]);
+
+        let result = wait_for_confirmations(&connector, 10, 3).await;
+
+        assert!(result.unwrap_err().downcast_ref::<Reorg>().is_some());
+    }
+
+    #[tokio::test]
+    async fn reports_reorg_if_block_was_orphaned_by_a_longer_chain() {
+        let connector = FakeConnector::new(10, vec![
+            Mine::OnTopOf(110, 9),
+            Mine::OnTip(111),
+            Mine::OnTip(112),
+        ]);
+        for _ in 0..3 {
+            connector.wait_for_new_block().await.unwrap();
+        }
+
+        let result = wait_for_confirmations(&connector, 10, 3).await;
+
+        assert!(result.unwrap_err().downcast_ref::<Reorg>().is_some());
+    }
+
+    #[tokio::test]
+    async fn reports_reorg_instead_of_walking_back_to_genesis() {
+        let connector = FakeConnector::new(10, vec![Mine::OnTip(11), Mine::OnTopOf(105, 5)]);
+
+        let result = wait_for_confirmations(&connector, 10, 3).await;
+
+        assert!(result.unwrap_err().downcast_ref::<Reorg>().is_some());
+    }
+
+    #[tokio::test]
+    async fn restarts_the_watcher_after_a_reorg() {
+        let mut attempts = 0;
+
+        let result = restart_on_reorg(|| {
+            attempts += 1;
+            let attempt = attempts;
+            async move {
+                if attempt < 3 {
+                    Err(Reorg.into())
+                } else {
+                    Ok(attempt)
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn reports_reorg_if_chain_forked_below_the_block() {
+        let connector = FakeConnector::new(10, vec![Mine::OnTopOf(108, 7)]);
+
+        let result = wait_for_confirmations(&connector, 10, 3).await;
+
+        assert!(result.unwrap_err().downcast_ref::<Reorg>().is_some());
+    }
+}
@@ -1,21 +1,26 @@
 mod cache;
+mod new_heads;
 mod watch_for_contract_creation;
 mod watch_for_event;
+mod watch_for_pending_transaction;
 mod web3_connector;
 
 pub use self::{
     cache::Cache,
+    new_heads::NewHeads,
     watch_for_contract_creation::{matching_transaction_and_receipt, watch_for_contract_creation},
     watch_for_event::watch_for_event,
+    watch_for_pending_transaction::watch_for_pending_transaction,
     web3_connector::Web3Connector,
 };
 use crate::{
-    btsieve::{BlockHash, ConnectedNetwork, Predates, PreviousBlockHash},
+    btsieve::{BlockHash, ConnectedNetwork, LatestBlock, Predates, PreviousBlockHash},
     ethereum::{Address, Block, ChainId, Hash, Log, Transaction, TransactionReceipt, U256},
+    expiries, Timestamp,
 };
 use anyhow::Result;
 use async_trait::async_trait;
-use std::time::Duration;
+use std::ops::RangeInclusive;
 use time::OffsetDateTime;
 
 #[async_trait]
@@ -28,9 +33,26 @@ pub trait TransactionByHash: Send + Sync + 'static {
     async fn transaction_by_hash(&self, transaction_hash: Hash) -> Result<Transaction>;
 }
 
+/// Looks up the block with the given number in the chain of the node.
+#[async_trait]
+pub trait BlockByNumber: Send + Sync + 'static {
+    async fn block_by_number(&self, number: u64) -> Result<Block>;
+}
+
+/// Looks up the logs matching the event in the given range of blocks.
 #[async_trait]
 pub trait GetLogs: Send + Sync + 'static {
-    async fn get_logs(&self, event: Event) -> Result<Vec<Log>>;
+    async fn get_logs(&self, event: Event, blocks: RangeInclusive<u64>) -> Result<Vec<Log>>;
+}
+
+/// Gives access to the transactions that are waiting to be mined.
+///
+/// Pending transactions may be replaced or dropped, hence they must not be
+/// treated as final.
+#[async_trait]
+pub trait PendingTransactions: Send + Sync + 'static {
+    async fn pending_transaction_hashes(&self) -> Result<Vec<Hash>>;
+    async fn pending_transaction(&self, transaction_hash: Hash) -> Result<Transaction>;
 }
 
 impl BlockHash for Block {
@@ -57,23 +79,37 @@ impl Predates for Block {
     }
 }
 
-/// Event works similar to web3 filters:
-/// https://web3js.readthedocs.io/en/1.0/web3-eth-subscribe.html?highlight=filter#subscribe-logs
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
-pub struct Event {
-    pub address: Address,
-    pub topics: Vec<Option<Hash>>,
+/// Returns the timestamp of the latest block.
+///
+/// The `herc20` HTLC compares its expiry against `block.timestamp`, hence this
+/// is the time that matters for deciding whether it can be refunded.
+pub async fn latest_block_timestamp<C>(connector: &C) -> Result<Timestamp>
+where
+    C: LatestBlock<Block = Block>,
+{
+    let block = connector.latest_block().await?;
+
+    Ok(Timestamp::from(block.timestamp))
 }
 
-async fn poll_interval<C>(connector: &C) -> Result<Duration>
+/// Returns the number of confirmations after which we consider a transaction
+/// on the connected chain final.
+///
+/// This is the number of confirmations that `finality_alpha` and
+/// `finality_beta` of the expiries are based on.
+pub async fn required_confirmations<C>(connector: &C) -> Result<u8>
 where
     C: ConnectedNetwork<Network = ChainId>,
 {
-    let network = connector.connected_network().await?;
-    let seconds = match network {
-        ChainId::GETH_DEV => 1,
-        _ => 10,
-    };
+    let chain_id = connector.connected_network().await?;
 
-    Ok(Duration::from_secs(seconds))
+    Ok(expiries::ethereum_confirmations(chain_id.into()))
+}
+
+/// Event works similar to web3 filters:
+/// https://web3js.readthedocs.io/en/1.0/web3-eth-subscribe.html?highlight=filter#subscribe-logs
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Event {
+    pub address: Address,
+    pub topics: Vec<Option<Hash>>,
 }
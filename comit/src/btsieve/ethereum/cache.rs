@@ -1,7 +1,12 @@
 use crate::{
     btsieve::{
-        ethereum::{self, Event, GetLogs, Hash, ReceiptByHash, TransactionByHash},
-        BlockByHash, ConnectedNetwork, LatestBlock,
+        block_store::{self, BlockStore, Tree},
+        ethereum::{
+            self, BlockByNumber, Event, GetLogs, Hash, PendingTransactions, ReceiptByHash,
+            TransactionByHash,
+        },
+        mempool::MempoolSnapshot,
+        BlockByHash, BlockNotifications, ConnectedNetwork, LatestBlock,
     },
     ethereum::{ChainId, Log, Transaction, TransactionReceipt},
 };
@@ -10,7 +15,7 @@ use async_trait::async_trait;
 use derivative::Derivative;
 use lru::LruCache;
 pub use primitive_types::U256;
-use std::sync::Arc;
+use std::{ops::RangeInclusive, sync::Arc};
 use tokio::sync::Mutex;
 
 // This makes it a bit obscure that we have an option, the compile will point it
@@ -27,6 +32,10 @@ pub struct Cache<C> {
     pub receipt_cache: Arc<Mutex<LruCache<Hash, TransactionReceipt>>>,
     #[derivative(Debug = "ignore")]
     pub connected_network_cache: Arc<Mutex<Option<ChainId>>>,
+    #[derivative(Debug = "ignore")]
+    block_store: Option<(Tree, Tree)>,
+    #[derivative(Debug = "ignore")]
+    pending: Arc<Mutex<MempoolSnapshot<Hash, Transaction>>>,
 }
 
 impl<C> Cache<C> {
@@ -44,6 +53,58 @@ impl<C> Cache<C> {
             block_cache,
             receipt_cache,
             connected_network_cache,
+            block_store: None,
+            pending: Arc::new(Mutex::new(MempoolSnapshot::default())),
+        }
+    }
+
+    /// Keeps the blocks and receipts in the given store as well, so they
+    /// survive a restart.
+    pub fn with_block_store(self, store: &BlockStore) -> Result<Self> {
+        let blocks = store.tree("ethereum_blocks")?;
+        let receipts = store.tree("ethereum_receipts")?;
+
+        Ok(Cache {
+            block_store: Some((blocks, receipts)),
+            ..self
+        })
+    }
+
+    fn load_block(&self, block_hash: &Hash) -> Option<Block> {
+        let (blocks, _) = self.block_store.as_ref()?;
+
+        blocks.load(block_hash.as_bytes(), |bytes| {
+            let block = serde_json::from_slice(bytes)?;
+
+            Ok(block)
+        })
+    }
+
+    fn save_block(&self, block: &Block) {
+        if let Some((blocks, _)) = &self.block_store {
+            let bytes = serde_json::to_vec(block).expect("block can be serialized");
+
+            blocks.save(block.hash.as_bytes(), block.timestamp.low_u64(), &bytes);
+        }
+    }
+
+    fn load_receipt(&self, transaction_hash: &Hash) -> Option<TransactionReceipt> {
+        let (_, receipts) = self.block_store.as_ref()?;
+
+        receipts.load(transaction_hash.as_bytes(), |bytes| {
+            let receipt = serde_json::from_slice(bytes)?;
+
+            Ok(receipt)
+        })
+    }
+
+    fn save_receipt(&self, transaction_hash: &Hash, receipt: &TransactionReceipt) {
+        if let Some((_, receipts)) = &self.block_store {
+            let bytes = serde_json::to_vec(receipt).expect("receipt can be serialized");
+
+            // Receipts are only fetched on behalf of swaps that are active
+            // right now, hence they are needed as long as these swaps are.
+            receipts.save(transaction_hash.as_bytes(), block_store::now(), &bytes);
         }
     }
 }
@@ -60,6 +121,7 @@ where
 
         let mut guard = self.block_cache.lock().await;
         if !guard.contains(&block.hash) {
+            self.save_block(&block);
             guard.put(block.hash, block.clone());
         }
 
@@ -80,7 +142,15 @@ where
             return Ok(block.clone());
         }
 
-        let block = self.connector.block_by_hash(block_hash).await?;
+        let block = match self.load_block(&block_hash) {
+            Some(block) => block,
+            None => {
+                let block = self.connector.block_by_hash(block_hash).await?;
+                self.save_block(&block);
+
+                block
+            }
+        };
 
         // We dropped the lock so at this stage the block may have been inserted by
         // another thread, no worries, inserting the same block twice does not hurt.
@@ -101,7 +171,15 @@ where
             return Ok(receipt.clone());
         }
 
-        let receipt = self.connector.receipt_by_hash(transaction_hash).await?;
+        let receipt = match self.load_receipt(&transaction_hash) {
+            Some(receipt) => receipt,
+            None => {
+                let receipt = self.connector.receipt_by_hash(transaction_hash).await?;
+                self.save_receipt(&transaction_hash, &receipt);
+
+                receipt
+            }
+        };
 
         // We dropped the lock so at this stage the receipt may have been inserted by
         // another thread, no worries, inserting the same receipt twice does not hurt.
@@ -112,6 +190,16 @@ where
     }
 }
 
+#[async_trait]
+impl<C> BlockNotifications for Cache<C>
+where
+    C: BlockNotifications,
+{
+    async fn wait_for_new_block(&self) -> Result<()> {
+        self.connector.wait_for_new_block().await
+    }
+}
+
 #[async_trait]
 impl<C> ConnectedNetwork for Cache<C>
 where
@@ -131,13 +219,29 @@ where
     }
 }
 
+/// Blocks are not cached by number, the block with a given number changes with
+/// every chain reorganisation.
+#[async_trait]
+impl<C> BlockByNumber for Cache<C>
+where
+    C: BlockByNumber,
+{
+    async fn block_by_number(&self, number: u64) -> anyhow::Result<Block> {
+        self.connector.block_by_number(number).await
+    }
+}
+
 #[async_trait]
 impl<C> GetLogs for Cache<C>
 where
     C: GetLogs,
 {
-    async fn get_logs(&self, event: Event) -> anyhow::Result<Vec<Log>> {
-        self.connector.get_logs(event).await
+    async fn get_logs(
+        &self,
+        event: Event,
+        blocks: RangeInclusive<u64>,
+    ) -> anyhow::Result<Vec<Log>> {
+        self.connector.get_logs(event, blocks).await
     }
 }
 
@@ -150,3 +254,30 @@ where
         self.connector.transaction_by_hash(transaction_hash).await
     }
 }
+
+#[async_trait]
+impl<C> PendingTransactions for Cache<C>
+where
+    C: PendingTransactions,
+{
+    async fn pending_transaction_hashes(&self) -> anyhow::Result<Vec<Hash>> {
+        let mut pending = self.pending.lock().await;
+
+        if pending.is_stale() {
+            let hashes = self.connector.pending_transaction_hashes().await?;
+            pending
+                .update(hashes, |hash| self.connector.pending_transaction(hash))
+                .await;
+        }
+
+        Ok(pending.ids())
+    }
+
+    async fn pending_transaction(&self, transaction_hash: Hash) -> anyhow::Result<Transaction> {
+        if let Some(transaction) = self.pending.lock().await.transaction(&transaction_hash) {
+            return Ok(transaction);
+        }
+
+        self.connector.pending_transaction(transaction_hash).await
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{
+    btsieve::jsonrpc,
+    ethereum::{Hash, U256},
+};
+use anyhow::{Context, Result};
+use derivative::Derivative;
+use futures::{
+    future::{self, Either},
+    SinkExt, Stream, StreamExt,
+};
+use reqwest::Url;
+use serde::Deserialize;
+use std::pin::Pin;
+use tokio::sync::{broadcast, Mutex};
+use tokio_tungstenite::tungstenite::{self, Message};
+
+type Subscription = Pin<Box<dyn Stream<Item = Result<Message, tungstenite::Error>> + Send>>;
+
+/// A `newHeads` subscription via the websocket endpoint of an Ethereum node.
+///
+/// The subscription is shared between all callers: Whoever gets hold of the
+/// websocket reads the next head and notifies everybody else through a
+/// broadcast channel. The websocket is (re-)connected lazily.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct NewHeads {
+    url: Url,
+    #[derivative(Debug = "ignore")]
+    subscription: Mutex<Option<Subscription>>,
+    #[derivative(Debug = "ignore")]
+    new_head: broadcast::Sender<()>,
+}
+
+impl NewHeads {
+    pub fn new(url: Url) -> Self {
+        let (new_head, _) = broadcast::channel(1);
+
+        Self {
+            url,
+            subscription: Mutex::new(None),
+            new_head,
+        }
+    }
+
+    /// Resolves once the node announces a new head.
+    pub async fn wait_for_new_head(&self) -> Result<()> {
+        let mut new_head = self.new_head.subscribe();
+
+        match future::select(
+            Box::pin(new_head.recv()),
+            Box::pin(self.subscription.lock()),
+        )
+        .await
+        {
+            // Lagging behind still means that there is a new head.
+            Either::Left((..)) => Ok(()),
+            Either::Right((mut subscription, _)) => {
+                if let Err(e) = self.next_head(&mut subscription).await {
+                    // Reconnect on the next call.
+                    *subscription = None;
+                    return Err(e);
+                }
+
+                // Nobody might be waiting, that is fine.
+                let _ = self.new_head.send(());
+
+                Ok(())
+            }
+        }
+    }
+
+    async fn next_head(&self, subscription: &mut Option<Subscription>) -> Result<()> {
+        if subscription.is_none() {
+            *subscription = Some(self.subscribe().await?);
+        }
+        let stream = subscription
+            .as_mut()
+            .expect("subscription was established above");
+
+        loop {
+            let message = stream
+                .next()
+                .await
+                .context("websocket connection was closed")??;
+
+            match message {
+                Message::Text(text) => {
+                    let notification = serde_json::from_str::<Notification>(&text)
+                        .context("failed to deserialize newHeads notification")?;
+                    let head = notification.params.result;
+
+                    tracing::trace!("new head {} at height {}", head.hash, head.number);
+
+                    return Ok(());
+                }
+                Message::Close(_) => anyhow::bail!("websocket connection was closed"),
+                // Pings are answered by tungstenite.
+                _ => continue,
+            }
+        }
+    }
+
+    async fn subscribe(&self) -> Result<Subscription> {
+        let (mut stream, _) = tokio_tungstenite::connect_async(self.url.as_str())
+            .await
+            .with_context(|| format!("failed to connect to {}", self.url))?;
+
+        let request = jsonrpc::Request::new("eth_subscribe", vec!["newHeads"]);
+        stream
+            .send(Message::Text(serde_json::to_string(&request)?))
+            .await?;
+
+        let response = stream
+            .next()
+            .await
+            .context("websocket connection was closed")??
+            .into_text()?;
+        let subscription_id = serde_json::from_str::<jsonrpc::Response<String>>(&response)
+            .context("failed to deserialize JSON response as JSON-RPC response")?
+            .payload
+            .into_result()?;
+
+        tracing::debug!("subscribed to new heads with id {}", subscription_id);
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Notification {
+    params: NotificationParams,
+}
+
+#[derive(Debug, Deserialize)]
+struct NotificationParams {
+    result: Head,
+}
+
+#[derive(Debug, Deserialize)]
+struct Head {
+    hash: Hash,
+    number: U256,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserializes_new_heads_notification() {
+        let notification = r#"{
+  "jsonrpc": "2.0",
+  "method": "eth_subscription",
+  "params": {
+    "subscription": "0x9ce59a13059e417087c02d3236a0b1cc",
+    "result": {
+      "difficulty": "0x15d9223a23aa",
+      "extraData": "0xd983010305844765746887676f312e342e328777696e646f7773",
+      "gasLimit": "0x47e7c4",
+      "gasUsed": "0x38658",
+      "hash": "0x7c4c1a4f2a4b7b8a2bbd1b5a2ab3fa0d1d3c0e3d6cf6d8e1d5a51d3b3e6bbd8d",
+      "logsBloom": "0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+      "miner": "0xf8b483dba2c3b7176a3da549ad41a48bb3121069",
+      "nonce": "0x084149998194cc5f",
+      "number": "0x1348c9",
+      "parentHash": "0x7736fab79e05dc611604d22470dadad26f56fe494421b5b333de816ce1f25701",
+      "receiptRoot": "0x2fab35823ad00c7bb388595cb46652fe7886e00660a01e867824d3dceb1c8d36",
+      "sha3Uncles": "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347",
+      "stateRoot": "0xb3346685172db67de536d8765c43c31009d0eb3bd9c501c9be3229203f15f378",
+      "timestamp": "0x56ffeff8",
+      "transactionsRoot": "0x0167ffa60e3ebc0b080cdb95f7c0087dd6c0e61413140e39d94d3468d7c9689f"
+    }
+  }
+}"#;
+
+        let notification = serde_json::from_str::<Notification>(notification).unwrap();
+
+        assert_eq!(notification.params.result.number, U256::from(0x0013_48c9));
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     btsieve::{
-        ethereum::{poll_interval, ReceiptByHash},
-        fetch_blocks_since, BlockByHash, ConnectedNetwork, LatestBlock,
+        ethereum::ReceiptByHash, fetch_blocks_since, wait_for_confirmations, BlockByHash,
+        BlockNotifications, ConnectedNetwork, LatestBlock,
     },
     ethereum::{Address, Block, ChainId, Hash, Transaction, TransactionReceipt},
 };
@@ -14,15 +14,17 @@ pub async fn watch_for_contract_creation<C>(
     connector: &C,
     start_of_swap: OffsetDateTime,
     expected_bytecode: &[u8],
-) -> Result<(Transaction, Address)>
+    confirmations: u8,
+) -> Result<(Transaction, Address, Option<u32>)>
 where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = Hash>
         + ReceiptByHash
+        + BlockNotifications
         + ConnectedNetwork<Network = ChainId>,
 {
     let (transaction, receipt) =
-        matching_transaction_and_receipt(connector, start_of_swap, |transaction| {
+        matching_transaction_and_receipt(connector, start_of_swap, confirmations, |transaction| {
             // transaction.to address is None if, and only if, the transaction
             // creates a contract.
 
@@ -55,7 +57,7 @@ where
             .await?;
 
     match receipt.contract_address {
-        Some(location) => Ok((transaction, location)),
+        Some(location) => Ok((transaction, location, receipt.block_height())),
         None => Err(anyhow::anyhow!("contract address missing from receipt")),
     }
 }
@@ -63,22 +65,27 @@ where
 pub async fn matching_transaction_and_receipt<C, F>(
     connector: &C,
     start_of_swap: OffsetDateTime,
+    confirmations: u8,
     matcher: F,
 ) -> Result<(Transaction, TransactionReceipt)>
 where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = Hash>
         + ReceiptByHash
+        + BlockNotifications
         + ConnectedNetwork<Network = ChainId>,
     F: Fn(&Transaction) -> bool + Clone,
 {
-    let poll_interval = poll_interval(connector).await?;
-    let mut block_generator = fetch_blocks_since(connector, start_of_swap, poll_interval);
+    let mut block_generator = fetch_blocks_since(connector, start_of_swap);
 
     loop {
         match block_generator.async_resume().await {
             GeneratorState::Yielded(block) => {
+                let block_hash = block.hash;
+
                 if let Some(result) = process_block(block, connector, matcher.clone()).await? {
+                    wait_for_confirmations(connector, block_hash, confirmations).await?;
+
                     return Ok(result);
                 }
             }
@@ -1,57 +1,384 @@
 use crate::{
     btsieve::{
-        ethereum::{poll_interval, Event, GetLogs, ReceiptByHash, TransactionByHash},
-        BlockByHash, ConnectedNetwork, LatestBlock,
+        ethereum::{BlockByNumber, Event, GetLogs, ReceiptByHash, TransactionByHash},
+        wait_for_confirmations, BlockByHash, BlockNotifications, ConnectedNetwork, LatestBlock,
     },
-    ethereum::{Block, ChainId, Hash, Log, Transaction},
+    ethereum::{Block, ChainId, Hash, Input, Log, Transaction, H2048, U256},
 };
-use anyhow::Result;
+use anyhow::{Context, Result};
+use std::{cmp, iter};
 use time::OffsetDateTime;
 
+/// The maximum number of blocks we query logs for at once.
+///
+/// Nodes limit the size of `eth_getLogs` responses, Infura for example to
+/// 10000 logs. Our events are very specific, hence ranges can be large.
+const MAX_BLOCK_RANGE: u64 = 10_000;
+
+/// The number of blocks before the last scanned one we look at again when
+/// following the chain, to find logs that moved to a different block because
+/// of a chain reorganisation.
+const REORG_DEPTH: u64 = 12;
+
+/// Up to this many new blocks we check the logs bloom of every block instead
+/// of asking the node for logs.
+const MAX_BLOOM_CHECKS: u64 = 32;
+
+/// Watches for a log matching the event that was emitted after the start of
+/// the swap.
+///
+/// The blocks since the start of the swap are searched with ranged
+/// `eth_getLogs` requests. Afterwards, we follow the chain and only ask the
+/// node for logs if the logs bloom of a new block may contain the event.
 pub async fn watch_for_event<C>(
     connector: &C,
-    _start_of_swap: OffsetDateTime,
+    start_of_swap: OffsetDateTime,
     expected_event: Event,
+    confirmations: u8,
 ) -> Result<(Transaction, Log)>
 where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = Hash>
         + ReceiptByHash
         + TransactionByHash
+        + BlockNotifications
         + ConnectedNetwork<Network = ChainId>
+        + BlockByNumber
         + GetLogs,
 {
-    let poll_interval = poll_interval(connector).await?;
+    let latest_block = connector.latest_block().await?;
+    let latest = block_number(&latest_block)?;
+    let start = first_block_since(connector, &latest_block, start_of_swap).await?;
+
+    let mut found = find_in_range(connector, &expected_event, start, latest).await?;
+    let mut scanned = latest_block.hash;
+    let mut last_scanned = latest;
 
     loop {
-        let logs = connector.get_logs(expected_event.clone()).await?;
+        if let Some((block_hash, log)) = found {
+            wait_for_confirmations(connector, block_hash, confirmations).await?;
 
-        if let Some(log) = find_log_for_event(&expected_event, logs) {
             let tx = connector.transaction_by_hash(log.transaction_hash).await?;
 
             return Ok((tx, log));
         }
 
-        tokio::time::delay_for(poll_interval).await;
+        // Logs only change with new blocks.
+        connector.wait_for_new_block().await?;
+
+        let latest_block = connector.latest_block().await?;
+        if latest_block.hash == scanned {
+            continue;
+        }
+        scanned = latest_block.hash;
+
+        let latest = block_number(&latest_block)?;
+        let from = rescan_from(start, last_scanned, latest);
+        found = if latest.saturating_sub(from) < MAX_BLOOM_CHECKS {
+            find_in_recent_blocks(connector, &expected_event, latest_block, from).await?
+        } else {
+            find_in_range(connector, &expected_event, from, latest).await?
+        };
+        last_scanned = latest;
+    }
+}
+
+/// Searches the blocks `from..=to` for the event, in chunks of at most
+/// `MAX_BLOCK_RANGE` blocks.
+async fn find_in_range<C>(
+    connector: &C,
+    event: &Event,
+    from: u64,
+    to: u64,
+) -> Result<Option<(Hash, Log)>>
+where
+    C: GetLogs,
+{
+    for (chunk_from, chunk_to) in chunks(from, to, MAX_BLOCK_RANGE) {
+        tracing::trace!("looking for event in blocks {} to {}", chunk_from, chunk_to);
+
+        let logs = connector
+            .get_logs(event.clone(), chunk_from..=chunk_to)
+            .await?;
+
+        if let Some(found) = find_log_for_event(event, logs) {
+            return Ok(Some(found));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Walks back from the latest block to the block with number `from` and asks
+/// for the logs of blocks whose logs bloom may contain the event.
+///
+/// The blocks were just fetched by the chain follower, hence walking back is
+/// served from the cache.
+async fn find_in_recent_blocks<C>(
+    connector: &C,
+    event: &Event,
+    latest_block: Block,
+    from: u64,
+) -> Result<Option<(Hash, Log)>>
+where
+    C: BlockByHash<Block = Block, BlockHash = Hash> + GetLogs,
+{
+    let mut block = latest_block;
+
+    loop {
+        let number = block_number(&block)?;
+
+        if bloom_may_contain(&block.logs_bloom, event) {
+            let logs = connector.get_logs(event.clone(), number..=number).await?;
+
+            if let Some(found) = find_log_for_event(event, logs) {
+                return Ok(Some(found));
+            }
+        }
+
+        if number <= from {
+            return Ok(None);
+        }
+
+        block = connector.block_by_hash(block.parent_hash).await?;
     }
 }
 
-fn find_log_for_event(event: &Event, logs: Vec<Log>) -> Option<Log> {
+fn find_log_for_event(event: &Event, logs: Vec<Log>) -> Option<(Hash, Log)> {
     match event {
         Event { topics, .. } if topics.is_empty() => None,
-        Event { address, topics } => logs.into_iter().find(|log| {
+        Event { address, topics } => logs.into_iter().find_map(|log| {
             if address != &log.address {
-                return false;
+                return None;
             }
 
             if log.topics.len() != topics.len() {
-                return false;
+                return None;
             }
 
-            log.topics.iter().enumerate().all(|(index, tx_topic)| {
+            let topics_match = log.topics.iter().enumerate().all(|(index, tx_topic)| {
                 let topic = &topics[index];
                 topic.as_ref().map_or(true, |topic| tx_topic == topic)
-            })
+            });
+            if !topics_match {
+                return None;
+            }
+
+            // Logs of pending transactions do not have a block yet.
+            log.block_hash.map(|block_hash| (block_hash, log))
         }),
     }
 }
+
+/// A bloom filter never yields false negatives: If it does not contain the
+/// address and all topics of the event, none of the logs of the block match.
+fn bloom_may_contain(bloom: &H2048, event: &Event) -> bool {
+    bloom.contains_input(Input::Raw(event.address.as_bytes()))
+        && event
+            .topics
+            .iter()
+            .flatten()
+            .all(|topic| bloom.contains_input(Input::Raw(topic.as_bytes())))
+}
+
+/// Returns the number of the first block that was mined after the start of
+/// the swap.
+///
+/// Block timestamps increase with every block, hence we can binary search for
+/// it between the lower bound given by the elapsed seconds and the latest
+/// block. This takes a few dozen requests at most, far fewer than scanning the
+/// logs of all blocks since the lower bound.
+async fn first_block_since<C>(
+    connector: &C,
+    latest_block: &Block,
+    start_of_swap: OffsetDateTime,
+) -> Result<u64>
+where
+    C: BlockByNumber,
+{
+    let start_of_swap = unix_timestamp(start_of_swap);
+    let mut low = lowest_block_since(
+        block_number(latest_block)?,
+        latest_block.timestamp,
+        start_of_swap,
+    );
+    let mut high = block_number(latest_block)?;
+
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let block = connector.block_by_number(middle).await?;
+
+        if block.timestamp >= start_of_swap {
+            high = middle;
+        } else {
+            low = middle + 1;
+        }
+    }
+
+    Ok(low)
+}
+
+/// Returns a lower bound for the number of the first block that was mined
+/// after the start of the swap.
+///
+/// Every block has a later timestamp than its parent, hence there cannot have
+/// been more blocks since the start of the swap than seconds have passed.
+fn lowest_block_since(latest: u64, latest_timestamp: U256, start_of_swap: U256) -> u64 {
+    let elapsed = latest_timestamp.saturating_sub(start_of_swap);
+
+    if elapsed >= U256::from(latest) {
+        0
+    } else {
+        latest - elapsed.low_u64()
+    }
+}
+
+/// Returns the first block to scan after a new block was mined.
+///
+/// We may have missed any number of blocks since we last scanned, e.g. while
+/// the node was unreachable. Starting from the last scanned block makes sure
+/// none of them are skipped, the latest might even be lower than the last
+/// scanned one after a reorg.
+fn rescan_from(start: u64, last_scanned: u64, latest: u64) -> u64 {
+    let last_scanned = cmp::min(last_scanned, latest);
+
+    cmp::max(start, last_scanned.saturating_sub(REORG_DEPTH))
+}
+
+fn chunks(from: u64, to: u64, size: u64) -> impl Iterator<Item = (u64, u64)> {
+    iter::successors(Some(from), move |chunk_from| chunk_from.checked_add(size))
+        .take_while(move |chunk_from| *chunk_from <= to)
+        .map(move |chunk_from| {
+            (
+                chunk_from,
+                cmp::min(chunk_from.saturating_add(size - 1), to),
+            )
+        })
+}
+
+fn unix_timestamp(time: OffsetDateTime) -> U256 {
+    // Unix timestamps before 1970 are not a concern.
+    #[allow(clippy::cast_sign_loss)]
+    U256::from(time.timestamp().max(0) as u64)
+}
+
+fn block_number(block: &Block) -> Result<u64> {
+    let number = block
+        .number
+        .with_context(|| format!("block {} does not have a number", block.hash))?;
+
+    Ok(number.low_u64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::Address;
+    use std::str::FromStr;
+
+    fn event() -> Event {
+        Event {
+            address: Address::from_str("0xe46FB33e4DB653De84cB0E0E8b810A6c4cD39d59").unwrap(),
+            topics: vec![
+                Some(
+                    Hash::from_str(
+                        "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+                    )
+                    .unwrap(),
+                ),
+                None,
+            ],
+        }
+    }
+
+    #[test]
+    fn bloom_contains_address_and_topics_of_event() {
+        let event = event();
+        let mut bloom = H2048::default();
+        bloom.accrue(Input::Raw(event.address.as_bytes()));
+        bloom.accrue(Input::Raw(event.topics[0].unwrap().as_bytes()));
+
+        assert!(bloom_may_contain(&bloom, &event));
+    }
+
+    #[test]
+    fn bloom_without_address_does_not_contain_event() {
+        let event = event();
+        let mut bloom = H2048::default();
+        bloom.accrue(Input::Raw(event.topics[0].unwrap().as_bytes()));
+
+        assert!(!bloom_may_contain(&bloom, &event));
+    }
+
+    #[test]
+    fn splits_range_into_chunks() {
+        let chunks = chunks(5, 27, 10).collect::<Vec<_>>();
+
+        assert_eq!(chunks, vec![(5, 14), (15, 24), (25, 27)]);
+    }
+
+    #[test]
+    fn single_block_is_one_chunk() {
+        let chunks = chunks(7, 7, 10).collect::<Vec<_>>();
+
+        assert_eq!(chunks, vec![(7, 7)]);
+    }
+
+    #[test]
+    fn rescans_all_blocks_since_last_scanned() {
+        assert_eq!(rescan_from(0, 1_000, 1_001), 988);
+        assert_eq!(rescan_from(0, 1_000, 1_500), 988);
+        assert_eq!(rescan_from(995, 1_000, 1_001), 995);
+        assert_eq!(rescan_from(0, 1_000, 998), 986);
+    }
+
+    #[test]
+    fn lowest_block_since_is_bounded_by_elapsed_seconds() {
+        let start_of_swap = U256::from(1_000_000);
+
+        assert_eq!(
+            lowest_block_since(5_000, U256::from(1_000_600), start_of_swap),
+            4_400
+        );
+        assert_eq!(
+            lowest_block_since(500, U256::from(1_000_600), start_of_swap),
+            0
+        );
+        assert_eq!(
+            lowest_block_since(5_000, U256::from(999_000), start_of_swap),
+            5_000
+        );
+    }
+
+    /// A chain with a block every 15 seconds since the unix epoch.
+    struct FakeConnector;
+
+    #[async_trait::async_trait]
+    impl BlockByNumber for FakeConnector {
+        async fn block_by_number(&self, number: u64) -> Result<Block> {
+            Ok(Block {
+                number: Some(U256::from(number)),
+                timestamp: U256::from(number * 15),
+                ..Block::default()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn finds_first_block_since_start_of_swap() {
+        let latest_block = FakeConnector.block_by_number(5_000).await.unwrap();
+
+        let first = |start_of_swap| {
+            first_block_since(
+                &FakeConnector,
+                &latest_block,
+                OffsetDateTime::from_unix_timestamp(start_of_swap),
+            )
+        };
+
+        assert_eq!(first(60_000).await.unwrap(), 4_000);
+        assert_eq!(first(60_001).await.unwrap(), 4_001);
+        assert_eq!(first(0).await.unwrap(), 0);
+        assert_eq!(first(100_000).await.unwrap(), 5_000);
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{btsieve::ethereum::PendingTransactions, ethereum::Transaction};
+use anyhow::Result;
+use std::{collections::HashSet, time::Duration};
+
+/// How often we look at the pending transactions again.
+///
+/// Pending transactions are usually mined within a few blocks, hence we have
+/// to look more often than we poll for new blocks.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Watches the pending transactions of the node for a transaction accepted by
+/// the sieve.
+///
+/// Transactions that are already mined are not found, watching the blockchain
+/// is still needed for those.
+pub async fn watch_for_pending_transaction<C, S, M>(connector: &C, sieve: S) -> Result<M>
+where
+    C: PendingTransactions,
+    S: Fn(&Transaction) -> Option<M>,
+{
+    let mut seen = HashSet::new();
+
+    loop {
+        let pending = connector
+            .pending_transaction_hashes()
+            .await?
+            .into_iter()
+            .collect::<HashSet<_>>();
+
+        for hash in pending.difference(&seen) {
+            // The transaction may have been mined or dropped since we asked for the
+            // pending transactions.
+            let transaction = match connector.pending_transaction(*hash).await {
+                Ok(transaction) => transaction,
+                Err(e) => {
+                    tracing::debug!("failed to fetch pending transaction {}: {:#}", hash, e);
+                    continue;
+                }
+            };
+
+            if let Some(result) = sieve(&transaction) {
+                tracing::info!("pending transaction {} matched", hash);
+                return Ok(result);
+            }
+        }
+
+        // Forgetting transactions that were mined or dropped keeps this from
+        // growing without bound.
+        seen = pending;
+
+        tokio::time::delay_for(POLL_INTERVAL).await;
+    }
+}
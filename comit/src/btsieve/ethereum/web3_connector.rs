@@ -1,22 +1,41 @@
 use crate::{
     btsieve::{
-        ethereum::{Event, GetLogs, ReceiptByHash, TransactionByHash},
-        jsonrpc, BlockByHash, ConnectedNetwork, LatestBlock,
+        ethereum::{
+            BlockByNumber, Event, GetLogs, NewHeads, PendingTransactions, ReceiptByHash,
+            TransactionByHash,
+        },
+        jsonrpc, BlockByHash, BlockNotifications, ConnectedNetwork, LatestBlock, Polling,
+        TipHeight,
     },
-    ethereum::{ChainId, Hash, Log, Transaction, TransactionReceipt},
+    ethereum::{Block, ChainId, Hash, Log, Transaction, TransactionReceipt, U256},
 };
 use anyhow::Result;
 use async_trait::async_trait;
+use serde::Deserialize;
+use std::{ops::RangeInclusive, sync::Mutex, time::Duration};
 
 #[derive(Debug)]
 pub struct Web3Connector {
     client: jsonrpc::Client,
+    new_heads: Option<NewHeads>,
+    polling: Mutex<Option<Polling>>,
 }
 
 impl Web3Connector {
     pub fn new(node_url: reqwest::Url) -> Self {
         Self {
             client: jsonrpc::Client::new(node_url),
+            new_heads: None,
+            polling: Mutex::new(None),
+        }
+    }
+
+    /// Subscribe to new blocks through the websocket endpoint of the node
+    /// instead of polling for them.
+    pub fn with_new_heads_subscription(self, websocket_url: reqwest::Url) -> Self {
+        Self {
+            new_heads: Some(NewHeads::new(websocket_url)),
+            ..self
         }
     }
 
@@ -28,6 +47,18 @@ impl Web3Connector {
 
         Ok(ChainId::from(version.parse::<u32>()?))
     }
+
+    async fn polling(&self) -> Result<Polling> {
+        if let Some(polling) = *self.polling.lock().expect("mutex is not poisoned") {
+            return Ok(polling);
+        }
+
+        let chain_id = self.net_version().await?;
+        let polling = Polling::new(poll_interval(chain_id));
+        *self.polling.lock().expect("mutex is not poisoned") = Some(polling);
+
+        Ok(polling)
+    }
 }
 
 #[async_trait]
@@ -93,6 +124,30 @@ impl TransactionByHash for Web3Connector {
     }
 }
 
+/// Waits for new blocks using the `newHeads` subscription if configured.
+///
+/// Waiting for a new head is capped at the poll interval of the network to
+/// not miss blocks that were mined right before we started waiting. Should the
+/// subscription fail, we fall back to polling.
+#[async_trait]
+impl BlockNotifications for Web3Connector {
+    async fn wait_for_new_block(&self) -> Result<()> {
+        let polling = self.polling().await?;
+
+        if let Some(new_heads) = &self.new_heads {
+            match tokio::time::timeout(polling.interval(), new_heads.wait_for_new_head()).await {
+                Ok(Ok(())) | Err(_) => return Ok(()),
+                Ok(Err(e)) => tracing::warn!(
+                    "newHeads subscription failed, falling back to polling: {:#}",
+                    e
+                ),
+            }
+        }
+
+        polling.wait_for_new_block().await
+    }
+}
+
 #[async_trait]
 impl ConnectedNetwork for Web3Connector {
     type Network = ChainId;
@@ -105,19 +160,41 @@ impl ConnectedNetwork for Web3Connector {
 }
 
 #[async_trait]
-impl GetLogs for Web3Connector {
-    async fn get_logs(&self, event: Event) -> Result<Vec<Log>> {
-        // Ideally, we would be computing this based on start-of-swap.
-        // However, some manual testing on mainnet shows that even specifying 0 is
-        // reasonably performant (~ 1sec for Infura). Hence, we just specify 0
-        // here to be on the safe side.
-        let from_block = "0x0";
+impl TipHeight for Web3Connector {
+    async fn tip_height(&self) -> Result<u64> {
+        let height = self
+            .client
+            .send::<Vec<()>, U256>(jsonrpc::Request::new("eth_blockNumber", vec![]))
+            .await?;
+
+        Ok(height.low_u64())
+    }
+}
+
+#[async_trait]
+impl BlockByNumber for Web3Connector {
+    async fn block_by_number(&self, number: u64) -> Result<Block> {
+        let block = self
+            .client
+            .send(jsonrpc::Request::new("eth_getBlockByNumber", vec![
+                jsonrpc::serialize(format!("{:#x}", number))?,
+                jsonrpc::serialize(true)?,
+            ]))
+            .await?;
 
+        Ok(block)
+    }
+}
+
+#[async_trait]
+impl GetLogs for Web3Connector {
+    async fn get_logs(&self, event: Event, blocks: RangeInclusive<u64>) -> Result<Vec<Log>> {
         let logs = self
             .client
             .send(jsonrpc::Request::new("eth_getLogs", vec![
                 serde_json::json!({
-                    "fromBlock": from_block,
+                    "fromBlock": format!("{:#x}", blocks.start()),
+                    "toBlock": format!("{:#x}", blocks.end()),
                     "address": event.address,
                     "topics": event.topics
                 }),
@@ -127,3 +204,39 @@ impl GetLogs for Web3Connector {
         Ok(logs)
     }
 }
+
+/// The pending block is assembled from the transactions the node would mine
+/// next. It has neither a hash nor a parent, hence we only look at the hashes
+/// of its transactions.
+#[derive(Debug, Deserialize)]
+struct PendingBlock {
+    transactions: Vec<Hash>,
+}
+
+#[async_trait]
+impl PendingTransactions for Web3Connector {
+    async fn pending_transaction_hashes(&self) -> Result<Vec<Hash>> {
+        let block: PendingBlock = self
+            .client
+            .send(jsonrpc::Request::new("eth_getBlockByNumber", vec![
+                jsonrpc::serialize("pending")?,
+                jsonrpc::serialize(false)?,
+            ]))
+            .await?;
+
+        Ok(block.transactions)
+    }
+
+    async fn pending_transaction(&self, transaction_hash: Hash) -> Result<Transaction> {
+        self.transaction_by_hash(transaction_hash).await
+    }
+}
+
+fn poll_interval(chain_id: ChainId) -> Duration {
+    let seconds = match chain_id {
+        ChainId::GETH_DEV => 1,
+        _ => 10,
+    };
+
+    Duration::from_secs(seconds)
+}
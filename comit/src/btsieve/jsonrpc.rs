@@ -21,13 +21,25 @@ impl Client {
         Req: Debug + Serialize,
         Res: DeserializeOwned,
     {
+        self.send_with_path("", request).await
+    }
+
+    /// Send the request to an endpoint relative to the base URL, e.g. the
+    /// `/wallet/<name>` endpoints of bitcoind.
+    pub async fn send_with_path<Req, Res>(&self, path: &str, request: Request<Req>) -> Result<Res>
+    where
+        Req: Debug + Serialize,
+        Res: DeserializeOwned,
+    {
+        let url = self.url.join(path)?;
+
         let response = self
             .inner
-            .post(self.url.clone())
+            .post(url.clone())
             .json(&request)
             .send()
             .await
-            .with_context(|| format!("failed to send POST request to {}", self.url))?
+            .with_context(|| format!("failed to send POST request to {}", url))?
             .json::<Response<Res>>()
             .await
             .context("failed to deserialize JSON response as JSON-RPC response")?
@@ -61,6 +73,15 @@ impl<T> Request<T> {
             params,
         }
     }
+
+    /// Requests that are sent over the same connection without waiting for
+    /// the response need distinct ids to match them up with their response.
+    pub fn with_id(self, id: u64) -> Self {
+        Self {
+            id: id.to_string(),
+            ..self
+        }
+    }
 }
 
 #[derive(serde::Deserialize, Debug, PartialEq)]
@@ -77,7 +98,7 @@ pub enum ResponsePayload<R> {
 }
 
 impl<R> ResponsePayload<R> {
-    fn into_result(self) -> Result<R, JsonRpcError> {
+    pub fn into_result(self) -> Result<R, JsonRpcError> {
         match self {
             ResponsePayload::Result(result) => Ok(result),
             ResponsePayload::Error(e) => Err(e),
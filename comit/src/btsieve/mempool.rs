@@ -0,0 +1,125 @@
+use anyhow::Result;
+use std::{
+    collections::HashMap,
+    future::Future,
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+/// How old the snapshot may be before it is updated again.
+///
+/// Every watcher polls the mempool on its own schedule. Within this period,
+/// they are all served the same snapshot instead of asking the node again.
+const MAX_AGE: Duration = Duration::from_secs(1);
+
+/// The transactions waiting to be mined as of the last poll, shared by all
+/// watchers of a connector.
+///
+/// Updating the snapshot only fetches the transactions that were not part of
+/// it before, transactions that left the mempool are forgotten.
+#[derive(Debug)]
+pub struct MempoolSnapshot<I, T> {
+    transactions: HashMap<I, T>,
+    polled_at: Option<Instant>,
+}
+
+impl<I, T> Default for MempoolSnapshot<I, T> {
+    fn default() -> Self {
+        Self {
+            transactions: HashMap::new(),
+            polled_at: None,
+        }
+    }
+}
+
+impl<I, T> MempoolSnapshot<I, T>
+where
+    I: Eq + Hash + Copy + std::fmt::Display,
+    T: Clone,
+{
+    pub fn is_stale(&self) -> bool {
+        self.polled_at
+            .map_or(true, |polled_at| polled_at.elapsed() >= MAX_AGE)
+    }
+
+    pub fn ids(&self) -> Vec<I> {
+        self.transactions.keys().copied().collect()
+    }
+
+    pub fn transaction(&self, id: &I) -> Option<T> {
+        self.transactions.get(id).cloned()
+    }
+
+    /// Replaces the snapshot with the given ids, fetching the transactions we
+    /// do not know yet.
+    ///
+    /// A transaction may be mined or dropped in between asking for the ids and
+    /// fetching it, such transactions are left out.
+    pub async fn update<F, Fut>(&mut self, ids: Vec<I>, fetch: F)
+    where
+        F: Fn(I) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut transactions = HashMap::with_capacity(ids.len());
+
+        for id in ids {
+            let transaction = match self.transactions.remove(&id) {
+                Some(transaction) => transaction,
+                None => match fetch(id).await {
+                    Ok(transaction) => transaction,
+                    Err(e) => {
+                        tracing::debug!("failed to fetch pending transaction {}: {:#}", id, e);
+                        continue;
+                    }
+                },
+            };
+
+            transactions.insert(id, transaction);
+        }
+
+        self.transactions = transactions;
+        self.polled_at = Some(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn only_fetches_new_transactions() {
+        let fetched = AtomicUsize::new(0);
+        let fetch = |id: u32| {
+            fetched.fetch_add(1, Ordering::SeqCst);
+            async move { Ok(id * 10) }
+        };
+        let mut snapshot = MempoolSnapshot::default();
+
+        snapshot.update(vec![1, 2], &fetch).await;
+        snapshot.update(vec![2, 3], &fetch).await;
+
+        let mut ids = snapshot.ids();
+        ids.sort();
+        assert_eq!(ids, vec![2, 3]);
+        assert_eq!(snapshot.transaction(&3), Some(30));
+        assert_eq!(fetched.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn leaves_out_transactions_that_cannot_be_fetched() {
+        let mut snapshot = MempoolSnapshot::default();
+
+        snapshot
+            .update(vec![1, 2], |id: u32| async move {
+                if id == 1 {
+                    anyhow::bail!("transaction was mined")
+                }
+                Ok(id)
+            })
+            .await;
+
+        assert_eq!(snapshot.ids(), vec![2]);
+        assert!(!snapshot.is_stale());
+    }
+}
@@ -0,0 +1,684 @@
+use crate::{
+    btsieve::{
+        bitcoin::{Mempool, ScriptTransaction, TransactionsByScript},
+        ethereum::{
+            BlockByNumber, Event, GetLogs, PendingTransactions, ReceiptByHash, TransactionByHash,
+        },
+        BlockByHash, BlockNotifications, ConnectedNetwork, LatestBlock, TipHeight,
+    },
+    ethereum::{Block, Hash, Log, Transaction, TransactionReceipt},
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use bitcoin::{Script, Txid};
+use futures::future::{join_all, Future};
+use std::{
+    fmt::Debug,
+    ops::RangeInclusive,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+
+/// How often we compare the tips of all endpoints.
+///
+/// This is also the longest we wait for a block notification of a single
+/// endpoint, a node that stopped syncing would otherwise keep us waiting
+/// forever.
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long we wait for an endpoint to answer a single probe of a health
+/// check before we consider it unhealthy.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Request<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// Spreads the requests of btsieve over several endpoints of the same network.
+///
+/// All requests go to the active endpoint. Every once in a while, we check the
+/// health of all endpoints in the background and make the one with the highest
+/// tip the active one. If a request fails because the active endpoint cannot
+/// be reached or does not answer in time, we fail over to the next best
+/// endpoint right away and try again. Errors the endpoint answered with, like
+/// an unknown transaction, are returned as they are.
+///
+/// Endpoints that are connected to a different network than the configured
+/// one are refused for good, their blocks are of no use to us.
+#[derive(Debug)]
+pub struct MultiConnector<C, N> {
+    network: N,
+    endpoints: Vec<Arc<Endpoint<C>>>,
+    active: Arc<AtomicUsize>,
+    health_check: Arc<HealthCheck>,
+    /// Makes sure only one request selects an endpoint in the foreground.
+    selecting: Mutex<()>,
+}
+
+#[derive(Debug, Default)]
+struct HealthCheck {
+    /// When the endpoints were last checked, `None` until the first check.
+    last: std::sync::Mutex<Option<Instant>>,
+    /// Whether a health check is running in the background.
+    running: AtomicBool,
+}
+
+#[derive(Debug)]
+struct Endpoint<C> {
+    connector: C,
+    refused: AtomicBool,
+}
+
+impl<C, N> MultiConnector<C, N> {
+    /// Creates a connector for the given network, the primary endpoint is
+    /// preferred if several endpoints share the highest tip.
+    pub fn new(network: N, primary: C) -> Self {
+        Self {
+            network,
+            endpoints: vec![Arc::new(Endpoint::new(primary))],
+            active: Arc::new(AtomicUsize::new(0)),
+            health_check: Arc::new(HealthCheck::default()),
+            selecting: Mutex::new(()),
+        }
+    }
+
+    pub fn with_fallbacks(mut self, fallbacks: impl IntoIterator<Item = C>) -> Self {
+        self.endpoints.extend(
+            fallbacks
+                .into_iter()
+                .map(|fallback| Arc::new(Endpoint::new(fallback))),
+        );
+
+        self
+    }
+}
+
+impl HealthCheck {
+    fn last(&self) -> Option<Instant> {
+        *self.last.lock().expect("mutex is not poisoned")
+    }
+
+    fn record(&self) {
+        let _ = self
+            .last
+            .lock()
+            .expect("mutex is not poisoned")
+            .replace(Instant::now());
+    }
+}
+
+impl<C> Endpoint<C> {
+    fn new(connector: C) -> Self {
+        Self {
+            connector,
+            refused: AtomicBool::new(false),
+        }
+    }
+
+    fn is_refused(&self) -> bool {
+        self.refused.load(Ordering::SeqCst)
+    }
+
+    /// Returns the tip of this endpoint if it is healthy and connected to the
+    /// given network.
+    async fn tip_height<N>(&self, index: usize, network: N) -> Option<u64>
+    where
+        C: ConnectedNetwork<Network = N> + TipHeight,
+        N: PartialEq + Debug,
+    {
+        if self.is_refused() {
+            return None;
+        }
+
+        match tokio::time::timeout(PROBE_TIMEOUT, self.connector.connected_network()).await {
+            Ok(Ok(connected)) if connected == network => {}
+            Ok(Ok(connected)) => {
+                tracing::error!(
+                    "refusing endpoint {}, it is connected to {:?} instead of {:?}",
+                    index,
+                    connected,
+                    network
+                );
+                self.refused.store(true, Ordering::SeqCst);
+                return None;
+            }
+            Ok(Err(e)) => {
+                tracing::warn!("endpoint {} is unhealthy: {:#}", index, e);
+                return None;
+            }
+            Err(_) => {
+                tracing::warn!("endpoint {} is unhealthy: probe timed out", index);
+                return None;
+            }
+        }
+
+        match tokio::time::timeout(PROBE_TIMEOUT, self.connector.tip_height()).await {
+            Ok(Ok(height)) => Some(height),
+            Ok(Err(e)) => {
+                tracing::warn!("endpoint {} is unhealthy: {:#}", index, e);
+                None
+            }
+            Err(_) => {
+                tracing::warn!("endpoint {} is unhealthy: probe timed out", index);
+                None
+            }
+        }
+    }
+}
+
+impl<C, N> MultiConnector<C, N>
+where
+    C: ConnectedNetwork<Network = N> + TipHeight,
+    N: PartialEq + Debug + Copy + Send + Sync + 'static,
+{
+    fn active(&self) -> Result<(usize, &C)> {
+        let index = self.active.load(Ordering::SeqCst);
+        let endpoint = &self.endpoints[index];
+
+        if endpoint.is_refused() {
+            anyhow::bail!("none of the endpoints is connected to {:?}", self.network)
+        }
+
+        Ok((index, &endpoint.connector))
+    }
+
+    /// Checks the health of the endpoints if the last check is due.
+    ///
+    /// Until the first check, we do not know which endpoint to use, hence it
+    /// is done right away. Later checks run in the background so requests do
+    /// not have to wait for slow endpoints.
+    async fn check_health_if_due(&self) {
+        match self.health_check.last() {
+            None => {
+                let _selecting = self.selecting.lock().await;
+
+                // Another request might have checked while we were waiting.
+                if self.health_check.last().is_none() {
+                    select_endpoint(self.network, &self.endpoints, &self.active, None).await;
+                    self.health_check.record();
+                }
+            }
+            Some(last) if last.elapsed() >= HEALTH_CHECK_INTERVAL => self.spawn_health_check(),
+            Some(_) => {}
+        }
+    }
+
+    fn spawn_health_check(&self) {
+        if self.health_check.running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let network = self.network;
+        let endpoints = self.endpoints.clone();
+        let active = self.active.clone();
+        let health_check = self.health_check.clone();
+
+        tokio::spawn(async move {
+            select_endpoint(network, &endpoints, &active, None).await;
+            health_check.record();
+            health_check.running.store(false, Ordering::SeqCst);
+        });
+    }
+
+    /// Makes another endpoint the active one after the given one failed.
+    ///
+    /// Returns whether there is another endpoint to try.
+    async fn fail_over(&self, failed: usize) -> bool {
+        let _selecting = self.selecting.lock().await;
+
+        // Another request might have failed over while we were waiting.
+        if self.active.load(Ordering::SeqCst) != failed {
+            return true;
+        }
+
+        select_endpoint(self.network, &self.endpoints, &self.active, Some(failed)).await;
+        self.health_check.record();
+
+        self.active.load(Ordering::SeqCst) != failed
+    }
+
+    async fn request<'a, T, F>(&'a self, request: F) -> Result<T>
+    where
+        F: Fn(&'a C) -> Request<'a, T> + Send + Sync,
+        T: Send,
+    {
+        self.check_health_if_due().await;
+
+        let (index, connector) = self.active()?;
+        let error = match request(connector).await {
+            Ok(response) => return Ok(response),
+            Err(e) => e,
+        };
+
+        if !is_transport_error(&error) || !self.fail_over(index).await {
+            return Err(error);
+        }
+
+        let (next, connector) = self.active()?;
+        tracing::warn!(
+            "request to endpoint {} failed, retrying with endpoint {}: {:#}",
+            index,
+            next,
+            error
+        );
+
+        request(connector).await
+    }
+}
+
+/// Whether the request failed on its way to or from the endpoint rather than
+/// being answered with an error.
+fn is_transport_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            // Errors without a status did not get an answer, e.g. timeouts.
+            return match e.status() {
+                Some(status) => {
+                    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                }
+                None => true,
+            };
+        }
+
+        cause.is::<std::io::Error>() || cause.is::<native_tls::Error>()
+    })
+}
+
+/// Makes the healthy endpoint with the highest tip the active one.
+async fn select_endpoint<C, N>(
+    network: N,
+    endpoints: &[Arc<Endpoint<C>>],
+    active: &AtomicUsize,
+    exclude: Option<usize>,
+) where
+    C: ConnectedNetwork<Network = N> + TipHeight,
+    N: PartialEq + Debug + Copy,
+{
+    let tips = join_all(
+        endpoints
+            .iter()
+            .enumerate()
+            .map(|(index, endpoint)| async move {
+                if exclude == Some(index) {
+                    return None;
+                }
+
+                endpoint.tip_height(index, network).await
+            }),
+    )
+    .await;
+
+    // `max_by_key` returns the last of several equal elements, reversing
+    // makes us prefer the endpoints that were configured first.
+    let best = tips
+        .into_iter()
+        .enumerate()
+        .rev()
+        .filter_map(|(index, tip)| Some((index, tip?)))
+        .max_by_key(|(_, tip)| *tip);
+
+    match best {
+        Some((index, tip)) => {
+            let previous = active.swap(index, Ordering::SeqCst);
+            if previous != index {
+                tracing::info!(
+                    "switching from endpoint {} to endpoint {} at height {}",
+                    previous,
+                    index,
+                    tip
+                );
+            }
+        }
+        None => tracing::warn!("none of the {} endpoints is healthy", endpoints.len()),
+    }
+}
+
+#[async_trait]
+impl<C, N> ConnectedNetwork for MultiConnector<C, N>
+where
+    C: ConnectedNetwork<Network = N> + TipHeight,
+    N: PartialEq + Debug + Copy + Send + Sync + 'static,
+{
+    type Network = N;
+
+    async fn connected_network(&self) -> Result<Self::Network> {
+        self.check_health_if_due().await;
+        let _ = self.active()?;
+
+        Ok(self.network)
+    }
+}
+
+#[async_trait]
+impl<C, N> TipHeight for MultiConnector<C, N>
+where
+    C: ConnectedNetwork<Network = N> + TipHeight,
+    N: PartialEq + Debug + Copy + Send + Sync + 'static,
+{
+    async fn tip_height(&self) -> Result<u64> {
+        self.request(|connector| connector.tip_height()).await
+    }
+}
+
+#[async_trait]
+impl<C, N> LatestBlock for MultiConnector<C, N>
+where
+    C: LatestBlock + ConnectedNetwork<Network = N> + TipHeight,
+    C::Block: Send,
+    N: PartialEq + Debug + Copy + Send + Sync + 'static,
+{
+    type Block = C::Block;
+
+    async fn latest_block(&self) -> Result<Self::Block> {
+        self.request(|connector| connector.latest_block()).await
+    }
+}
+
+#[async_trait]
+impl<C, N> BlockByHash for MultiConnector<C, N>
+where
+    C: BlockByHash + ConnectedNetwork<Network = N> + TipHeight,
+    C::Block: Send,
+    C::BlockHash: Copy + Send + Sync,
+    N: PartialEq + Debug + Copy + Send + Sync + 'static,
+{
+    type Block = C::Block;
+    type BlockHash = C::BlockHash;
+
+    async fn block_by_hash(&self, block_hash: Self::BlockHash) -> Result<Self::Block> {
+        self.request(move |connector| connector.block_by_hash(block_hash))
+            .await
+    }
+}
+
+#[async_trait]
+impl<C, N> BlockNotifications for MultiConnector<C, N>
+where
+    C: BlockNotifications + ConnectedNetwork<Network = N> + TipHeight,
+    N: PartialEq + Debug + Copy + Send + Sync + 'static,
+{
+    async fn wait_for_new_block(&self) -> Result<()> {
+        self.check_health_if_due().await;
+
+        let (index, connector) = self.active()?;
+        let new_block = tokio::time::timeout(HEALTH_CHECK_INTERVAL, connector.wait_for_new_block());
+        let error = match new_block.await {
+            // Consumers check the latest block after a timeout as well.
+            Ok(Ok(())) | Err(_) => return Ok(()),
+            Ok(Err(e)) => e,
+        };
+
+        if !self.fail_over(index).await {
+            return Err(error);
+        }
+
+        tracing::warn!(
+            "block notifications of endpoint {} failed: {:#}",
+            index,
+            error
+        );
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<C, N> Mempool for MultiConnector<C, N>
+where
+    C: Mempool + ConnectedNetwork<Network = N> + TipHeight,
+    N: PartialEq + Debug + Copy + Send + Sync + 'static,
+{
+    async fn mempool_txids(&self) -> Result<Vec<Txid>> {
+        self.request(|connector| connector.mempool_txids()).await
+    }
+
+    async fn mempool_transaction(&self, txid: Txid) -> Result<bitcoin::Transaction> {
+        self.request(move |connector| connector.mempool_transaction(txid))
+            .await
+    }
+}
+
+#[async_trait]
+impl<C, N> TransactionsByScript for MultiConnector<C, N>
+where
+    C: TransactionsByScript + ConnectedNetwork<Network = N> + TipHeight,
+    N: PartialEq + Debug + Copy + Send + Sync + 'static,
+{
+    async fn transactions_by_script(
+        &self,
+        script: &Script,
+    ) -> Result<Option<Vec<ScriptTransaction>>> {
+        self.request(move |connector| connector.transactions_by_script(script))
+            .await
+    }
+
+    async fn transaction_by_txid(&self, txid: Txid) -> Result<bitcoin::Transaction> {
+        self.request(move |connector| connector.transaction_by_txid(txid))
+            .await
+    }
+}
+
+#[async_trait]
+impl<C, N> ReceiptByHash for MultiConnector<C, N>
+where
+    C: ReceiptByHash + ConnectedNetwork<Network = N> + TipHeight,
+    N: PartialEq + Debug + Copy + Send + Sync + 'static,
+{
+    async fn receipt_by_hash(&self, transaction_hash: Hash) -> Result<TransactionReceipt> {
+        self.request(move |connector| connector.receipt_by_hash(transaction_hash))
+            .await
+    }
+}
+
+#[async_trait]
+impl<C, N> TransactionByHash for MultiConnector<C, N>
+where
+    C: TransactionByHash + ConnectedNetwork<Network = N> + TipHeight,
+    N: PartialEq + Debug + Copy + Send + Sync + 'static,
+{
+    async fn transaction_by_hash(&self, transaction_hash: Hash) -> Result<Transaction> {
+        self.request(move |connector| connector.transaction_by_hash(transaction_hash))
+            .await
+    }
+}
+
+#[async_trait]
+impl<C, N> BlockByNumber for MultiConnector<C, N>
+where
+    C: BlockByNumber + ConnectedNetwork<Network = N> + TipHeight,
+    N: PartialEq + Debug + Copy + Send + Sync + 'static,
+{
+    async fn block_by_number(&self, number: u64) -> Result<Block> {
+        self.request(move |connector| connector.block_by_number(number))
+            .await
+    }
+}
+
+#[async_trait]
+impl<C, N> GetLogs for MultiConnector<C, N>
+where
+    C: GetLogs + ConnectedNetwork<Network = N> + TipHeight,
+    N: PartialEq + Debug + Copy + Send + Sync + 'static,
+{
+    async fn get_logs(&self, event: Event, blocks: RangeInclusive<u64>) -> Result<Vec<Log>> {
+        self.request(|connector| connector.get_logs(event.clone(), blocks.clone()))
+            .await
+    }
+}
+
+#[async_trait]
+impl<C, N> PendingTransactions for MultiConnector<C, N>
+where
+    C: PendingTransactions + ConnectedNetwork<Network = N> + TipHeight,
+    N: PartialEq + Debug + Copy + Send + Sync + 'static,
+{
+    async fn pending_transaction_hashes(&self) -> Result<Vec<Hash>> {
+        self.request(|connector| connector.pending_transaction_hashes())
+            .await
+    }
+
+    async fn pending_transaction(&self, transaction_hash: Hash) -> Result<Transaction> {
+        self.request(move |connector| connector.pending_transaction(transaction_hash))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger;
+
+    #[derive(Debug)]
+    struct FakeConnector {
+        network: ledger::Bitcoin,
+        tip: u64,
+        broken: bool,
+    }
+
+    impl FakeConnector {
+        fn new(network: ledger::Bitcoin, tip: u64) -> Self {
+            Self {
+                network,
+                tip,
+                broken: false,
+            }
+        }
+
+        fn broken(self) -> Self {
+            Self {
+                broken: true,
+                ..self
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ConnectedNetwork for FakeConnector {
+        type Network = ledger::Bitcoin;
+
+        async fn connected_network(&self) -> Result<Self::Network> {
+            Ok(self.network)
+        }
+    }
+
+    #[async_trait]
+    impl TipHeight for FakeConnector {
+        async fn tip_height(&self) -> Result<u64> {
+            Ok(self.tip)
+        }
+    }
+
+    #[async_trait]
+    impl LatestBlock for FakeConnector {
+        type Block = u64;
+
+        async fn latest_block(&self) -> Result<Self::Block> {
+            if self.broken {
+                return Err(std::io::Error::from(std::io::ErrorKind::ConnectionRefused).into());
+            }
+
+            Ok(self.tip)
+        }
+    }
+
+    #[async_trait]
+    impl BlockByHash for FakeConnector {
+        type Block = u64;
+        type BlockHash = u64;
+
+        async fn block_by_hash(&self, block_hash: Self::BlockHash) -> Result<Self::Block> {
+            anyhow::ensure!(block_hash <= self.tip, "unknown block {}", block_hash);
+
+            Ok(block_hash)
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_over_to_fallback_if_request_fails() {
+        let connector = MultiConnector::new(
+            ledger::Bitcoin::Regtest,
+            FakeConnector::new(ledger::Bitcoin::Regtest, 10).broken(),
+        )
+        .with_fallbacks(vec![FakeConnector::new(ledger::Bitcoin::Regtest, 9)]);
+
+        let block = connector.latest_block().await.unwrap();
+
+        assert_eq!(block, 9);
+    }
+
+    #[tokio::test]
+    async fn does_not_fail_over_if_endpoint_answers_with_error() {
+        let connector = MultiConnector::new(
+            ledger::Bitcoin::Regtest,
+            FakeConnector::new(ledger::Bitcoin::Regtest, 10),
+        )
+        .with_fallbacks(vec![FakeConnector::new(ledger::Bitcoin::Regtest, 11)]);
+        connector.health_check.record();
+
+        let result = connector.block_by_hash(11).await;
+
+        assert!(result.is_err());
+        assert_eq!(connector.active.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn returns_error_if_all_endpoints_fail() {
+        let connector = MultiConnector::new(
+            ledger::Bitcoin::Regtest,
+            FakeConnector::new(ledger::Bitcoin::Regtest, 10).broken(),
+        )
+        .with_fallbacks(vec![
+            FakeConnector::new(ledger::Bitcoin::Regtest, 9).broken()
+        ]);
+
+        let result = connector.latest_block().await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn prefers_endpoint_with_highest_tip() {
+        let connector = MultiConnector::new(
+            ledger::Bitcoin::Regtest,
+            FakeConnector::new(ledger::Bitcoin::Regtest, 10),
+        )
+        .with_fallbacks(vec![
+            FakeConnector::new(ledger::Bitcoin::Regtest, 12),
+            FakeConnector::new(ledger::Bitcoin::Regtest, 12),
+        ]);
+
+        let block = connector.latest_block().await.unwrap();
+
+        assert_eq!(block, 12);
+        assert_eq!(connector.active.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn refuses_endpoint_connected_to_other_network() {
+        let connector = MultiConnector::new(
+            ledger::Bitcoin::Regtest,
+            FakeConnector::new(ledger::Bitcoin::Testnet, 1_000),
+        )
+        .with_fallbacks(vec![FakeConnector::new(ledger::Bitcoin::Regtest, 10)]);
+
+        let block = connector.latest_block().await.unwrap();
+
+        assert_eq!(block, 10);
+        assert!(connector.endpoints[0].is_refused());
+    }
+
+    #[tokio::test]
+    async fn fails_if_no_endpoint_is_connected_to_network() {
+        let connector = MultiConnector::new(
+            ledger::Bitcoin::Regtest,
+            FakeConnector::new(ledger::Bitcoin::Testnet, 10),
+        );
+
+        let result = connector.connected_network().await;
+
+        assert!(result.is_err());
+    }
+}
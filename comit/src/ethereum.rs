@@ -3,7 +3,7 @@ use anyhow::Result;
 pub use ethbloom::{Bloom as H2048, Input};
 use hex::FromHexError;
 pub use primitive_types::U256;
-use serde::{Deserialize, Deserializer, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{
     fmt,
     fmt::{Display, Formatter},
@@ -127,7 +127,7 @@ impl FromStr for Hash {
 }
 
 /// "Receipt" of an executed transaction: details of its execution.
-#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TransactionReceipt {
     /// Contract address created, or `None` if not a deployment.
     #[serde(rename = "contractAddress")]
@@ -135,13 +135,28 @@ pub struct TransactionReceipt {
     /// Logs generated within this transaction.
     pub logs: Vec<Log>,
     /// Status: Whether or not the transaction executed successfully
-    #[serde(rename = "status", deserialize_with = "deserialize_status")]
+    #[serde(
+        rename = "status",
+        serialize_with = "serialize_status",
+        deserialize_with = "deserialize_status"
+    )]
     pub successful: bool,
     /// The block number this transaction was included in.
     #[serde(rename = "blockNumber")]
     pub block_number: Option<U256>,
 }
 
+impl TransactionReceipt {
+    pub fn block_height(&self) -> Option<u32> {
+        self.block_number.as_ref().map(block_height)
+    }
+}
+
+/// Ethereum will not see more than `u32::MAX` blocks any time soon.
+fn block_height(number: &U256) -> u32 {
+    number.low_u32()
+}
+
 fn deserialize_status<'de, D>(deserializer: D) -> Result<bool, <D as Deserializer<'de>>::Error>
 where
     D: Deserializer<'de>,
@@ -151,8 +166,15 @@ where
     Ok(&hex_string == "0x1")
 }
 
+fn serialize_status<S>(successful: &bool, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(if *successful { "0x1" } else { "0x0" })
+}
+
 /// Description of a Transaction, pending or in the chain.
-#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Transaction {
     /// Hash
     pub hash: Hash,
@@ -165,7 +187,7 @@ pub struct Transaction {
 }
 
 /// A log produced by a transaction.
-#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Log {
     /// H160
     pub address: Address,
@@ -175,18 +197,34 @@ pub struct Log {
     pub data: crate::ethereum::UnformattedData,
     #[serde(rename = "transactionHash")]
     pub transaction_hash: Hash,
+    /// Hash of the block that included the transaction, `None` if the
+    /// transaction is pending.
+    #[serde(rename = "blockHash")]
+    pub block_hash: Option<Hash>,
+    /// Number of the block that included the transaction, `None` if the
+    /// transaction is pending.
+    #[serde(rename = "blockNumber", default)]
+    pub block_number: Option<U256>,
+}
+
+impl Log {
+    pub fn block_height(&self) -> Option<u32> {
+        self.block_number.as_ref().map(block_height)
+    }
 }
 
 /// The block returned from RPC calls.
 ///
 /// This type contains only the fields we are actually using.
-#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Block {
     /// Hash of the block
     pub hash: Hash,
     /// Hash of the parent
     #[serde(rename = "parentHash")]
     pub parent_hash: Hash,
+    /// Number of the block, `None` if the block is pending
+    pub number: Option<U256>,
     /// Logs bloom
     #[serde(rename = "logsBloom")]
     pub logs_bloom: H2048,
@@ -378,6 +416,21 @@ mod tests {
         assert_eq!(receipt.successful, false);
     }
 
+    #[test]
+    fn receipt_roundtrips_through_json() {
+        let receipt = TransactionReceipt {
+            contract_address: Some(Address::random()),
+            logs: vec![],
+            successful: true,
+            block_number: Some(U256::from(42)),
+        };
+
+        let json = serde_json::to_string(&receipt).unwrap();
+        let deserialized = serde_json::from_str::<TransactionReceipt>(&json).unwrap();
+
+        assert_eq!(deserialized, receipt);
+    }
+
     proptest! {
         #[test]
         fn address_from_hex_doesnt_panic(string in any::<String>()) {
@@ -36,6 +36,20 @@ pub fn expiry_offsets_hbit_herc20(network: Network) -> (AlphaOffset, BetaOffset)
     expiry_offsets(&config)
 }
 
+/// Calculate a pair of expiries suitable for use with the heth-hbit COMIT
+/// protocol.
+pub fn expiry_offsets_heth_hbit(network: Network) -> (AlphaOffset, BetaOffset) {
+    let config = Config::heth_hbit(network);
+    expiry_offsets(&config)
+}
+
+/// Calculate a pair of expiries suitable for use with the hbit-heth COMIT
+/// protocol.
+pub fn expiry_offsets_hbit_heth(network: Network) -> (AlphaOffset, BetaOffset) {
+    let config = Config::hbit_heth(network);
+    expiry_offsets(&config)
+}
+
 fn expiry_offsets(config: &Config) -> (AlphaOffset, BetaOffset) {
     let alice_needs = happy_path_swap_period_for_alice(config);
     let bob_needs = happy_path_swap_period_for_bob(config);
@@ -131,6 +145,132 @@ where
         Expiries::new(config, start_at, alpha_connector, beta_connector)
     }
 
+    pub fn new_heth_hbit(
+        network: Network,
+        start_at: Timestamp,
+        alpha_connector: A,
+        beta_connector: B,
+    ) -> Self {
+        let config = Config::heth_hbit(network);
+        Expiries::new(config, start_at, alpha_connector, beta_connector)
+    }
+
+    pub fn new_hbit_heth(
+        network: Network,
+        start_at: Timestamp,
+        alpha_connector: A,
+        beta_connector: B,
+    ) -> Self {
+        let config = Config::hbit_heth(network);
+        Expiries::new(config, start_at, alpha_connector, beta_connector)
+    }
+
+    /// Like `new_herc20_hbit` but uses the expiries that were agreed upon for
+    /// the swap instead of deriving them from the standard offsets.
+    pub fn new_herc20_hbit_with_expiries(
+        network: Network,
+        start_at: Timestamp,
+        alpha_expiry: Timestamp,
+        beta_expiry: Timestamp,
+        alpha_connector: A,
+        beta_connector: B,
+    ) -> Self {
+        let config = Config::herc20_hbit(network);
+        Expiries::with_expiries(
+            config,
+            start_at,
+            alpha_expiry.into(),
+            beta_expiry.into(),
+            alpha_connector,
+            beta_connector,
+        )
+    }
+
+    /// Like `new_hbit_herc20` but uses the expiries that were agreed upon for
+    /// the swap instead of deriving them from the standard offsets.
+    pub fn new_hbit_herc20_with_expiries(
+        network: Network,
+        start_at: Timestamp,
+        alpha_expiry: Timestamp,
+        beta_expiry: Timestamp,
+        alpha_connector: A,
+        beta_connector: B,
+    ) -> Self {
+        let config = Config::hbit_herc20(network);
+        Expiries::with_expiries(
+            config,
+            start_at,
+            alpha_expiry.into(),
+            beta_expiry.into(),
+            alpha_connector,
+            beta_connector,
+        )
+    }
+
+    /// Like `new_heth_hbit` but uses the expiries that were agreed upon for
+    /// the swap instead of deriving them from the standard offsets.
+    pub fn new_heth_hbit_with_expiries(
+        network: Network,
+        start_at: Timestamp,
+        alpha_expiry: Timestamp,
+        beta_expiry: Timestamp,
+        alpha_connector: A,
+        beta_connector: B,
+    ) -> Self {
+        let config = Config::heth_hbit(network);
+        Expiries::with_expiries(
+            config,
+            start_at,
+            alpha_expiry.into(),
+            beta_expiry.into(),
+            alpha_connector,
+            beta_connector,
+        )
+    }
+
+    /// Like `new_hbit_heth` but uses the expiries that were agreed upon for
+    /// the swap instead of deriving them from the standard offsets.
+    pub fn new_hbit_heth_with_expiries(
+        network: Network,
+        start_at: Timestamp,
+        alpha_expiry: Timestamp,
+        beta_expiry: Timestamp,
+        alpha_connector: A,
+        beta_connector: B,
+    ) -> Self {
+        let config = Config::hbit_heth(network);
+        Expiries::with_expiries(
+            config,
+            start_at,
+            alpha_expiry.into(),
+            beta_expiry.into(),
+            alpha_connector,
+            beta_connector,
+        )
+    }
+
+    fn with_expiries(
+        config: Config,
+        start_at: Timestamp,
+        alpha_expiry: AlphaExpiry,
+        beta_expiry: BetaExpiry,
+        alpha_connector: A,
+        beta_connector: B,
+    ) -> Self {
+        let alpha_offset = timestamp::duration_between(start_at, alpha_expiry.0).into();
+        let beta_offset = timestamp::duration_between(start_at, beta_expiry.0).into();
+        Expiries {
+            config,
+            alpha_connector,
+            beta_connector,
+            alpha_offset,
+            beta_offset,
+            start_at,
+            alpha_expiry,
+            beta_expiry,
+        }
+    }
+
     fn new(config: Config, start_at: Timestamp, alpha_connector: A, beta_connector: B) -> Self {
         let (alpha_offset, beta_offset) = expiry_offsets(&config);
         let (alpha_expiry, beta_expiry) = to_timestamps(start_at, alpha_offset, beta_offset);
@@ -238,17 +378,11 @@ where
         &self,
         current_state: AliceState,
     ) -> (AliceAction, AliceState) {
-        match self.protocol() {
-            Protocol::Herc20Hbit => current_state.next_herc20_hbit(),
-            Protocol::HbitHerc20 => current_state.next_hbit_herc20(),
-        }
+        current_state.next(self.protocol())
     }
 
     fn next_action_and_state_for_bob(&self, current_state: BobState) -> (BobAction, BobState) {
-        match self.protocol() {
-            Protocol::Herc20Hbit => current_state.next_herc20_hbit(),
-            Protocol::HbitHerc20 => current_state.next_hbit_herc20(),
-        }
+        current_state.next(self.protocol())
     }
 
     fn protocol(&self) -> Protocol {
@@ -328,10 +462,7 @@ fn period_for_alice_to_complete(config: &Config, current_state: AliceState) -> D
         }
 
         let transition_period = state.transition_period(config);
-        let (_action, next_state) = match config.protocol() {
-            Protocol::Herc20Hbit => state.next_herc20_hbit(),
-            Protocol::HbitHerc20 => state.next_hbit_herc20(),
-        };
+        let (_action, next_state) = state.next(config.protocol());
 
         period_to_complete(config, next_state, acc + transition_period)
     }
@@ -349,10 +480,7 @@ fn period_for_bob_to_complete(config: &Config, current_state: BobState) -> Durat
         }
 
         let transition_period = state.transition_period(config);
-        let (_action, next_state) = match config.protocol() {
-            Protocol::Herc20Hbit => state.next_herc20_hbit(),
-            Protocol::HbitHerc20 => state.next_hbit_herc20(),
-        };
+        let (_action, next_state) = state.next(config.protocol());
 
         period_to_complete(config, next_state, acc + transition_period)
     }
@@ -449,10 +577,9 @@ pub enum AliceState {
     /// ERC20 only. The deploy transaction has been broadcast to the network.
     DeployAlphaTransactionBroadcast,
     /// ERC20 only. Implies deploy ERC20 HTLC has been mined.
-    // We only wait for a single confirmation because that is what our current btsieve
-    // implementation does. This is safe since the next action is Alice to fund and we
-    // wait for the fund transaction to reach finality which implies the deploy
-    // transaction has also reached finality.
+    // We only wait for a single confirmation. This is safe since the next action is
+    // Alice to fund and we wait for the fund transaction to reach finality which
+    // implies the deploy transaction has also reached finality.
     AlphaDeployed,
     /// The fund alpha transaction has been broadcast to the network.
     FundAlphaTransactionBroadcast,
@@ -491,6 +618,18 @@ impl AliceState {
         AliceState::None
     }
 
+    /// Gets the next action required to transition to the next state for a
+    /// swap of the given protocol.
+    fn next(&self, protocol: Protocol) -> (AliceAction, AliceState) {
+        match protocol {
+            Protocol::Herc20Hbit => self.next_herc20_hbit(),
+            // Neither hbit nor heth require Alice to deploy the HTLC before funding it.
+            Protocol::HbitHerc20 | Protocol::HbitHeth | Protocol::HethHbit => {
+                self.next_hbit_herc20()
+            }
+        }
+    }
+
     /// Gets the next action required to transition to the next state for a
     /// herc20-hbit swap.
     fn next_herc20_hbit(&self) -> (AliceAction, AliceState) {
@@ -532,10 +671,7 @@ impl AliceState {
     fn transition_period(&self, c: &Config) -> Duration {
         use self::AliceAction::*;
 
-        let (next_action, _next_state) = match c.protocol() {
-            Protocol::Herc20Hbit => self.next_herc20_hbit(),
-            Protocol::HbitHerc20 => self.next_hbit_herc20(),
-        };
+        let (next_action, _next_state) = self.next(c.protocol());
 
         match next_action {
             Start => {
@@ -660,6 +796,18 @@ impl BobState {
         BobState::Started
     }
 
+    /// Gets the next action required to transition to the next state for a
+    /// swap of the given protocol.
+    fn next(&self, protocol: Protocol) -> (BobAction, BobState) {
+        match protocol {
+            Protocol::HbitHerc20 => self.next_hbit_herc20(),
+            // Neither hbit nor heth require Bob to deploy the HTLC before funding it.
+            Protocol::Herc20Hbit | Protocol::HbitHeth | Protocol::HethHbit => {
+                self.next_herc20_hbit()
+            }
+        }
+    }
+
     /// Gets the next action required to transition to the next state for a
     /// herc20-hbit swap.
     fn next_herc20_hbit(&self) -> (BobAction, BobState) {
@@ -707,10 +855,7 @@ impl BobState {
     fn transition_period(&self, c: &Config) -> Duration {
         use self::BobAction::*;
 
-        let (next_action, _next_state) = match c.protocol() {
-            Protocol::Herc20Hbit => self.next_herc20_hbit(),
-            Protocol::HbitHerc20 => self.next_hbit_herc20(),
-        };
+        let (next_action, _next_state) = self.next(c.protocol());
 
         match next_action {
             // Once Alice starts we need at least this much time, note that c.start() is not
@@ -894,6 +1039,12 @@ mod tests {
 
         let (a, b) = expiry_offsets_hbit_herc20(Network::Main);
         print("hbit-herc20", a, b);
+
+        let (a, b) = expiry_offsets_heth_hbit(Network::Main);
+        print("heth-hbit", a, b);
+
+        let (a, b) = expiry_offsets_hbit_heth(Network::Main);
+        print("hbit-heth", a, b);
     }
 
     #[test]
@@ -904,6 +1055,28 @@ mod tests {
         assert_eq!(b, 35.seconds().into());
     }
 
+    #[tokio::test]
+    async fn agreed_upon_expiries_are_used() {
+        let start_at = Timestamp::now();
+        let (ac, bc) = mock_connectors();
+
+        // Way shorter than the standard offsets for mainnet.
+        let alpha_expiry = start_at.plus(60);
+        let beta_expiry = start_at.plus(30);
+
+        let exp = Expiries::new_hbit_herc20_with_expiries(
+            Network::Main,
+            start_at,
+            alpha_expiry,
+            beta_expiry,
+            ac,
+            bc,
+        );
+        let got_action = exp.next_action_for_alice(AliceState::initial()).await;
+
+        assert_that!(got_action).is_equal_to(AliceAction::Abort);
+    }
+
     #[tokio::test]
     async fn alice_can_complete_an_hbit_herc20_swap() {
         let start_at = Timestamp::now();
@@ -988,6 +1161,48 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn alice_can_complete_an_heth_hbit_swap() {
+        let start_at = Timestamp::now();
+        let (ac, bc) = mock_connectors();
+
+        let exp = Expiries::new_heth_hbit(Network::Main, start_at, ac.clone(), bc.clone());
+        let mut cur = AliceState::initial();
+
+        let inc = 1.minutes();
+
+        while cur != AliceState::Done {
+            inc_connectors(inc, ac.clone(), bc.clone()).await;
+            let (want_action, state) = cur.next(Protocol::HethHbit);
+            let got_action = exp.next_action_for_alice(cur).await;
+
+            assert_that!(got_action).is_equal_to(want_action);
+
+            cur = state;
+        }
+    }
+
+    #[tokio::test]
+    async fn bob_can_complete_an_hbit_heth_swap() {
+        let start_at = Timestamp::now();
+        let (ac, bc) = mock_connectors();
+
+        let exp = Expiries::new_hbit_heth(Network::Main, start_at, ac.clone(), bc.clone());
+        let mut cur = BobState::initial();
+
+        let inc = 1.minutes();
+
+        while cur != BobState::Done {
+            inc_connectors(inc, ac.clone(), bc.clone()).await;
+            let (want_action, state) = cur.next(Protocol::HbitHeth);
+            let got_action = exp.next_action_for_bob(cur).await;
+
+            assert_that!(got_action).is_equal_to(want_action);
+
+            cur = state;
+        }
+    }
+
     #[tokio::test]
     async fn bob_can_complete_an_herc20_hbit_swap_with_slow_alice_start() {
         let start_at = Timestamp::now();
@@ -98,8 +98,29 @@ pub struct Config {
 impl Config {
     /// Construct a config object suitable for a herc20-hbit swap.
     pub fn herc20_hbit(network: Network) -> Self {
+        Config::ethereum_bitcoin(Protocol::Herc20Hbit, network)
+    }
+
+    /// Construct a config object suitable for a hbit-herc20 swap.
+    pub fn hbit_herc20(network: Network) -> Self {
+        Config::bitcoin_ethereum(Protocol::HbitHerc20, network)
+    }
+
+    /// Construct a config object suitable for a heth-hbit swap.
+    pub fn heth_hbit(network: Network) -> Self {
+        Config::ethereum_bitcoin(Protocol::HethHbit, network)
+    }
+
+    /// Construct a config object suitable for a hbit-heth swap.
+    pub fn hbit_heth(network: Network) -> Self {
+        Config::bitcoin_ethereum(Protocol::HbitHeth, network)
+    }
+
+    /// Config for a protocol with Ethereum as the alpha and Bitcoin as the beta
+    /// ledger.
+    fn ethereum_bitcoin(protocol: Protocol, network: Network) -> Self {
         Config {
-            protocol: Protocol::Herc20Hbit,
+            protocol,
             alpha_required_confirmations: ethereum_confirmations(network),
             beta_required_confirmations: bitcoin_confirmations(network),
             alpha_average_block_time: ethereum_blocktime(network),
@@ -115,10 +136,11 @@ impl Config {
         }
     }
 
-    /// Construct a config object suitable for a hbit-herc20 swap.
-    pub fn hbit_herc20(network: Network) -> Self {
+    /// Config for a protocol with Bitcoin as the alpha and Ethereum as the beta
+    /// ledger.
+    fn bitcoin_ethereum(protocol: Protocol, network: Network) -> Self {
         Config {
-            protocol: Protocol::HbitHerc20,
+            protocol,
             alpha_required_confirmations: bitcoin_confirmations(network),
             beta_required_confirmations: ethereum_confirmations(network),
             alpha_average_block_time: bitcoin_blocktime(network),
@@ -335,6 +357,8 @@ impl fmt::Display for Config {
 pub enum Protocol {
     Herc20Hbit,
     HbitHerc20,
+    HethHbit,
+    HbitHeth,
 }
 
 fn bitcoin_blocktime(network: Network) -> Duration {
@@ -344,7 +368,7 @@ fn bitcoin_blocktime(network: Network) -> Duration {
     }
 }
 
-fn bitcoin_confirmations(network: Network) -> u8 {
+pub fn bitcoin_confirmations(network: Network) -> u8 {
     match network {
         Network::Main | Network::Test => main::BITCOIN_CONFIRMATIONS,
         Network::Dev => dev::BITCOIN_CONFIRMATIONS,
@@ -365,7 +389,7 @@ fn ethereum_blocktime(network: Network) -> Duration {
     }
 }
 
-fn ethereum_confirmations(network: Network) -> u8 {
+pub fn ethereum_confirmations(network: Network) -> u8 {
     match network {
         Network::Main | Network::Test => main::ETHEREUM_CONFIRMATIONS,
         Network::Dev => dev::ETHEREUM_CONFIRMATIONS,
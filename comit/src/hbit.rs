@@ -3,10 +3,13 @@
 use crate::{
     asset,
     btsieve::{
-        bitcoin::{watch_for_created_outpoint, watch_for_spent_outpoint},
-        BlockByHash, ConnectedNetwork, LatestBlock,
+        bitcoin::{
+            watch_for_created_outpoint, watch_for_spent_outpoint,
+            watch_for_spent_outpoint_in_mempool, Mempool, TransactionsByScript,
+        },
+        restart_on_reorg, BlockByHash, BlockNotifications, ConnectedNetwork, LatestBlock,
     },
-    htlc_location, identity, ledger,
+    expiries, htlc_location, identity, ledger,
     swap::actions::{SendToAddress, SpendOutput},
     timestamp::Timestamp,
     Secret, SecretHash,
@@ -33,17 +36,23 @@ pub struct IncorrectlyFunded {
 #[derive(Debug, Clone, Copy)]
 pub struct Funded {
     pub location: htlc_location::Bitcoin,
+    /// Height of the block that included the transaction, if known.
+    pub block_height: Option<u32>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Redeemed {
     pub transaction: bitcoin::Txid,
     pub secret: Secret,
+    /// Height of the block that included the transaction, if known.
+    pub block_height: Option<u32>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Refunded {
     pub transaction: bitcoin::Txid,
+    /// Height of the block that included the transaction, if known.
+    pub block_height: Option<u32>,
 }
 
 #[async_trait::async_trait]
@@ -65,6 +74,25 @@ pub trait WatchForRedeemed {
     ) -> Redeemed;
 }
 
+/// Watches for a redeem transaction that has not been mined yet.
+///
+/// This reveals the secret before it can be learned through
+/// [`WatchForRedeemed`], the redeem transaction may still be replaced though.
+#[async_trait::async_trait]
+pub trait WatchForRedeemedInMempool {
+    async fn watch_for_redeemed_in_mempool(&self, params: &Params, fund_event: Funded) -> Secret;
+}
+
+#[async_trait::async_trait]
+pub trait WatchForRefunded {
+    async fn watch_for_refunded(
+        &self,
+        params: &Params,
+        fund_event: Funded,
+        start_of_swap: OffsetDateTime,
+    ) -> Refunded;
+}
+
 pub async fn watch_for_funded<C>(
     connector: &C,
     params: &SharedParams,
@@ -73,19 +101,30 @@ pub async fn watch_for_funded<C>(
 where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = BlockHash>
-        + ConnectedNetwork<Network = ledger::Bitcoin>,
+        + BlockNotifications
+        + ConnectedNetwork<Network = ledger::Bitcoin>
+        + TransactionsByScript,
 {
     let expected_asset = params.asset;
 
-    let (transaction, location) =
-        watch_for_created_outpoint(connector, start_of_swap, params.compute_address())
-            .instrument(tracing::info_span!("", action = "fund"))
-            .await?;
+    let (transaction, location, block_height) = restart_on_reorg(|| {
+        watch_for_created_outpoint(
+            connector,
+            start_of_swap,
+            params.compute_address(),
+            params.required_confirmations(),
+        )
+        .instrument(tracing::info_span!("", action = "fund"))
+    })
+    .await?;
 
     let asset = asset::Bitcoin::from_sat(transaction.output[location.vout as usize].value);
 
     match expected_asset.cmp(&asset) {
-        Ordering::Equal => Ok(Ok(Funded { location })),
+        Ordering::Equal => Ok(Ok(Funded {
+            location,
+            block_height,
+        })),
         _ => Ok(Err(IncorrectlyFunded {
             expected: expected_asset,
             got: asset,
@@ -102,12 +141,22 @@ pub async fn watch_for_redeemed<C>(
 where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = BlockHash>
-        + ConnectedNetwork<Network = ledger::Bitcoin>,
+        + BlockNotifications
+        + ConnectedNetwork<Network = ledger::Bitcoin>
+        + TransactionsByScript,
 {
-    let (transaction, _) =
-        watch_for_spent_outpoint(connector, start_of_swap, location, params.redeem_identity)
-            .instrument(tracing::info_span!("", action = "redeem"))
-            .await?;
+    // The secret is revealed once the redeem transaction is mined, a reorg
+    // cannot take it back. Hence we do not wait for further confirmations.
+    let (transaction, _, block_height) = watch_for_spent_outpoint(
+        connector,
+        start_of_swap,
+        location,
+        params.compute_address(),
+        params.redeem_identity,
+        1,
+    )
+    .instrument(tracing::info_span!("", action = "redeem"))
+    .await?;
 
     let secret = extract_secret(&transaction, &params.secret_hash)
         .expect("Redeem transaction must contain secret");
@@ -115,9 +164,33 @@ where
     Ok(Redeemed {
         transaction: transaction.txid(),
         secret,
+        block_height,
     })
 }
 
+pub async fn watch_for_redeemed_in_mempool<C>(
+    connector: &C,
+    params: &SharedParams,
+    location: htlc_location::Bitcoin,
+) -> Result<Secret>
+where
+    C: Mempool + TransactionsByScript + ConnectedNetwork<Network = ledger::Bitcoin>,
+{
+    let (transaction, _) = watch_for_spent_outpoint_in_mempool(
+        connector,
+        location,
+        params.compute_address(),
+        params.redeem_identity,
+    )
+    .instrument(tracing::info_span!("", action = "redeem"))
+    .await?;
+
+    let secret = extract_secret(&transaction, &params.secret_hash)
+        .expect("Redeem transaction must contain secret");
+
+    Ok(secret)
+}
+
 pub async fn watch_for_refunded<C>(
     connector: &C,
     params: &SharedParams,
@@ -127,15 +200,26 @@ pub async fn watch_for_refunded<C>(
 where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = BlockHash>
-        + ConnectedNetwork<Network = ledger::Bitcoin>,
+        + BlockNotifications
+        + ConnectedNetwork<Network = ledger::Bitcoin>
+        + TransactionsByScript,
 {
-    let (transaction, _) =
-        watch_for_spent_outpoint(connector, start_of_swap, location, params.refund_identity)
-            .instrument(tracing::info_span!("", action = "refund"))
-            .await?;
+    let (transaction, _, block_height) = restart_on_reorg(|| {
+        watch_for_spent_outpoint(
+            connector,
+            start_of_swap,
+            location,
+            params.compute_address(),
+            params.refund_identity,
+            params.required_confirmations(),
+        )
+        .instrument(tracing::info_span!("", action = "refund"))
+    })
+    .await?;
 
     Ok(Refunded {
         transaction: transaction.txid(),
+        block_height,
     })
 }
 
@@ -250,6 +334,12 @@ impl SharedParams {
     pub fn compute_address(&self) -> Address {
         Htlc::from(*self).compute_address(self.network.into())
     }
+
+    /// The number of confirmations after which we consider a transaction of
+    /// this HTLC final.
+    pub fn required_confirmations(&self) -> u8 {
+        expiries::bitcoin_confirmations(self.network.into())
+    }
 }
 
 fn extract_secret(transaction: &Transaction, secret_hash: &SecretHash) -> Option<Secret> {
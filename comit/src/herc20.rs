@@ -5,9 +5,11 @@ use crate::{
     asset::{ethereum::FromWei, Erc20, Erc20Quantity},
     btsieve::{
         ethereum::{
-            watch_for_contract_creation, watch_for_event, GetLogs, ReceiptByHash, TransactionByHash,
+            required_confirmations, watch_for_contract_creation, watch_for_event,
+            watch_for_pending_transaction, BlockByNumber, GetLogs, PendingTransactions,
+            ReceiptByHash, TransactionByHash,
         },
-        BlockByHash, ConnectedNetwork, LatestBlock,
+        restart_on_reorg, BlockByHash, BlockNotifications, ConnectedNetwork, LatestBlock,
     },
     ethereum,
     ethereum::{Block, ChainId, Hash, U256},
@@ -45,11 +47,15 @@ static TRANSFER_LOG_MSG: Lazy<Hash> = Lazy::new(|| {
 pub struct Deployed {
     pub transaction: ethereum::Hash,
     pub location: htlc_location::Ethereum,
+    /// Height of the block that included the transaction, if known.
+    pub block_height: Option<u32>,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct Funded {
     pub transaction: ethereum::Hash,
+    /// Height of the block that included the transaction, if known.
+    pub block_height: Option<u32>,
 }
 
 #[derive(Debug, Clone, Error)]
@@ -63,11 +69,15 @@ pub struct IncorrectlyFunded {
 pub struct Redeemed {
     pub transaction: ethereum::Hash,
     pub secret: Secret,
+    /// Height of the block that included the transaction, if known.
+    pub block_height: Option<u32>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Refunded {
     pub transaction: ethereum::Hash,
+    /// Height of the block that included the transaction, if known.
+    pub block_height: Option<u32>,
 }
 
 #[async_trait::async_trait]
@@ -99,6 +109,26 @@ pub trait WatchForRedeemed {
     ) -> Redeemed;
 }
 
+/// Watches for a redeem transaction that has not been mined yet.
+///
+/// This reveals the secret before it can be learned through
+/// [`WatchForRedeemed`], the redeem transaction may still be replaced though.
+#[async_trait::async_trait]
+pub trait WatchForRedeemedInMempool {
+    async fn watch_for_redeemed_in_mempool(&self, params: Params, deploy_event: Deployed)
+        -> Secret;
+}
+
+#[async_trait::async_trait]
+pub trait WatchForRefunded {
+    async fn watch_for_refunded(
+        &self,
+        params: Params,
+        deploy_event: Deployed,
+        utc_start_of_swap: OffsetDateTime,
+    ) -> Refunded;
+}
+
 pub async fn watch_for_deployed<C>(
     connector: &C,
     params: Params,
@@ -107,19 +137,24 @@ pub async fn watch_for_deployed<C>(
 where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = Hash>
+        + BlockNotifications
         + ReceiptByHash
         + ConnectedNetwork<Network = ChainId>,
 {
     let expected_bytecode = params.clone().bytecode();
 
-    let (transaction, location) =
-        watch_for_contract_creation(connector, start_of_swap, &expected_bytecode)
+    // The expiries only account for the deploy transaction to be mined. This
+    // is safe because funding the HTLC has to reach finality before anybody
+    // acts upon it.
+    let (transaction, location, block_height) =
+        watch_for_contract_creation(connector, start_of_swap, &expected_bytecode, 1)
             .instrument(tracing::info_span!("", action = "deploy"))
             .await?;
 
     Ok(Deployed {
         transaction: transaction.hash,
         location,
+        block_height,
     })
 }
 
@@ -132,9 +167,11 @@ pub async fn watch_for_funded<C>(
 where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = Hash>
+        + BlockNotifications
         + ReceiptByHash
         + TransactionByHash
         + ConnectedNetwork<Network = ChainId>
+        + BlockByNumber
         + GetLogs,
 {
     use crate::btsieve::ethereum::Event;
@@ -148,9 +185,12 @@ where
         ],
     };
 
-    let (transaction, log) = watch_for_event(connector, start_of_swap, event)
-        .instrument(tracing::info_span!("", action = "fund"))
-        .await?;
+    let confirmations = required_confirmations(connector).await?;
+    let (transaction, log) = restart_on_reorg(|| {
+        watch_for_event(connector, start_of_swap, event.clone(), confirmations)
+            .instrument(tracing::info_span!("", action = "fund"))
+    })
+    .await?;
 
     let expected_asset = &params.asset;
 
@@ -160,6 +200,7 @@ where
     match expected_asset.cmp(&asset) {
         Ordering::Equal => Ok(Ok(Funded {
             transaction: transaction.hash,
+            block_height: log.block_height(),
         })),
         _ => Ok(Err(IncorrectlyFunded {
             expected: params.asset,
@@ -176,9 +217,11 @@ pub async fn watch_for_redeemed<C>(
 where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = Hash>
+        + BlockNotifications
         + ReceiptByHash
         + TransactionByHash
         + ConnectedNetwork<Network = ChainId>
+        + BlockByNumber
         + GetLogs,
 {
     use crate::btsieve::ethereum::Event;
@@ -188,7 +231,9 @@ where
         topics: vec![Some(*REDEEM_LOG_MSG)],
     };
 
-    let (transaction, log) = watch_for_event(connector, start_of_swap, event)
+    // The secret is revealed once the redeem transaction is mined, a reorg
+    // cannot take it back. Hence we do not wait for further confirmations.
+    let (transaction, log) = watch_for_event(connector, start_of_swap, event, 1)
         .instrument(tracing::info_span!("", action = "redeem"))
         .await?;
 
@@ -198,7 +243,40 @@ where
     Ok(Redeemed {
         transaction: transaction.hash,
         secret,
+        block_height: log.block_height(),
+    })
+}
+
+pub async fn watch_for_redeemed_in_mempool<C>(
+    connector: &C,
+    params: &Params,
+    deployed: Deployed,
+) -> Result<Secret>
+where
+    C: PendingTransactions,
+{
+    let secret = watch_for_pending_transaction(connector, |transaction| {
+        if transaction.to != Some(deployed.location) {
+            return None;
+        }
+
+        secret_from_redeem_data(&transaction.input.0, params.secret_hash)
     })
+    .instrument(tracing::info_span!("", action = "redeem"))
+    .await?;
+
+    Ok(secret)
+}
+
+/// The HTLC is redeemed by calling it with the secret as the only data, this
+/// holds for the heth HTLC as well.
+///
+/// Anybody can call the HTLC with arbitrary data, hence only a secret that
+/// matches the secret hash is accepted.
+pub(crate) fn secret_from_redeem_data(data: &[u8], secret_hash: SecretHash) -> Option<Secret> {
+    Secret::from_vec(data)
+        .ok()
+        .filter(|secret| SecretHash::new(*secret) == secret_hash)
 }
 
 pub async fn watch_for_refunded<C>(
@@ -209,9 +287,11 @@ pub async fn watch_for_refunded<C>(
 where
     C: LatestBlock<Block = Block>
         + BlockByHash<Block = Block, BlockHash = Hash>
+        + BlockNotifications
         + ReceiptByHash
         + TransactionByHash
         + ConnectedNetwork<Network = ChainId>
+        + BlockByNumber
         + GetLogs,
 {
     use crate::btsieve::ethereum::Event;
@@ -221,12 +301,16 @@ where
         topics: vec![Some(*REFUND_LOG_MSG)],
     };
 
-    let (transaction, _) = watch_for_event(connector, start_of_swap, event)
-        .instrument(tracing::info_span!("", action = "refund"))
-        .await?;
+    let confirmations = required_confirmations(connector).await?;
+    let (transaction, log) = restart_on_reorg(|| {
+        watch_for_event(connector, start_of_swap, event.clone(), confirmations)
+            .instrument(tracing::info_span!("", action = "refund"))
+    })
+    .await?;
 
     Ok(Refunded {
         transaction: transaction.hash,
+        block_height: log.block_height(),
     })
 }
 
@@ -245,6 +329,21 @@ impl Params {
         Htlc::from(self.clone()).into()
     }
 
+    /// The gas limit of the action built by `build_deploy_action`.
+    pub fn deploy_tx_gas_limit() -> u64 {
+        Htlc::deploy_tx_gas_limit()
+    }
+
+    /// The gas limit of the action built by `build_fund_action`.
+    pub fn fund_tx_gas_limit() -> u64 {
+        Htlc::fund_tx_gas_limit()
+    }
+
+    /// The gas limit of the action built by `build_redeem_action`.
+    pub fn redeem_tx_gas_limit() -> u64 {
+        Htlc::redeem_tx_gas_limit()
+    }
+
     pub fn build_deploy_action(&self) -> DeployContract {
         let chain_id = self.chain_id;
         let htlc = Htlc::from(self.clone());
@@ -357,3 +456,32 @@ impl quickcheck::Arbitrary for Params {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_secret_from_redeem_data() {
+        let secret = Secret::from_vec(b"This is our favourite passphrase").unwrap();
+        let secret_hash = SecretHash::new(secret);
+
+        let extracted = secret_from_redeem_data(secret.as_raw_secret(), secret_hash);
+
+        assert_eq!(extracted, Some(secret));
+    }
+
+    #[test]
+    fn ignores_data_not_matching_the_secret_hash() {
+        let secret = Secret::from_vec(b"This is our favourite passphrase").unwrap();
+        let other = Secret::from_vec(&[1u8; 32]).unwrap();
+        let secret_hash = SecretHash::new(secret);
+
+        assert_eq!(secret_from_redeem_data(&[], secret_hash), None);
+        assert_eq!(secret_from_redeem_data(&[0u8; 31], secret_hash), None);
+        assert_eq!(
+            secret_from_redeem_data(other.as_raw_secret(), secret_hash),
+            None
+        );
+    }
+}
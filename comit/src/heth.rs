@@ -0,0 +1,326 @@
+//! Htlc Ether atomic swap protocol.
+
+use crate::{
+    asset,
+    asset::{ethereum::FromWei, Ether},
+    btsieve::{
+        ethereum::{
+            required_confirmations, watch_for_contract_creation, watch_for_event,
+            watch_for_pending_transaction, BlockByNumber, GetLogs, PendingTransactions,
+            ReceiptByHash, TransactionByHash,
+        },
+        restart_on_reorg, BlockByHash, BlockNotifications, ConnectedNetwork, LatestBlock,
+    },
+    ethereum,
+    ethereum::{Block, ChainId, Hash},
+    herc20, htlc_location, identity,
+    swap::actions::{CallContract, DeployContract},
+    timestamp::Timestamp,
+    Secret, SecretHash,
+};
+use anyhow::Result;
+use blockchain_contracts::ethereum::heth::Htlc;
+use conquer_once::Lazy;
+use thiserror::Error;
+use time::OffsetDateTime;
+use tracing_futures::Instrument;
+
+static REDEEM_LOG_MSG: Lazy<Hash> = Lazy::new(|| {
+    blockchain_contracts::ethereum::REDEEMED_LOG_MSG
+        .parse()
+        .expect("to be valid hex")
+});
+static REFUND_LOG_MSG: Lazy<Hash> = Lazy::new(|| {
+    blockchain_contracts::ethereum::REFUNDED_LOG_MSG
+        .parse()
+        .expect("to be valid hex")
+});
+
+/// Represents the data available at said state.
+///
+/// In contrast to herc20, the ether HTLC is funded by the transaction that
+/// deploys it, hence there is no separate deployed state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Funded {
+    pub transaction: ethereum::Hash,
+    pub location: htlc_location::Ethereum,
+    /// Height of the block that included the transaction, if known.
+    pub block_height: Option<u32>,
+}
+
+#[derive(Debug, Clone, Error)]
+#[error("heth HTLC was incorrectly funded, expected {expected} but got {got}")]
+pub struct IncorrectlyFunded {
+    pub expected: asset::Ether,
+    pub got: asset::Ether,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Redeemed {
+    pub transaction: ethereum::Hash,
+    pub secret: Secret,
+    /// Height of the block that included the transaction, if known.
+    pub block_height: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Refunded {
+    pub transaction: ethereum::Hash,
+    /// Height of the block that included the transaction, if known.
+    pub block_height: Option<u32>,
+}
+
+#[async_trait::async_trait]
+pub trait WatchForFunded {
+    async fn watch_for_funded(
+        &self,
+        params: Params,
+        utc_start_of_swap: OffsetDateTime,
+    ) -> Result<Funded, IncorrectlyFunded>;
+}
+
+#[async_trait::async_trait]
+pub trait WatchForRedeemed {
+    async fn watch_for_redeemed(
+        &self,
+        params: Params,
+        fund_event: Funded,
+        utc_start_of_swap: OffsetDateTime,
+    ) -> Redeemed;
+}
+
+/// Watches for a redeem transaction that has not been mined yet.
+///
+/// This reveals the secret before it can be learned through
+/// [`WatchForRedeemed`], the redeem transaction may still be replaced though.
+#[async_trait::async_trait]
+pub trait WatchForRedeemedInMempool {
+    async fn watch_for_redeemed_in_mempool(&self, params: Params, fund_event: Funded) -> Secret;
+}
+
+#[async_trait::async_trait]
+pub trait WatchForRefunded {
+    async fn watch_for_refunded(
+        &self,
+        params: Params,
+        fund_event: Funded,
+        utc_start_of_swap: OffsetDateTime,
+    ) -> Refunded;
+}
+
+pub async fn watch_for_funded<C>(
+    connector: &C,
+    params: Params,
+    start_of_swap: OffsetDateTime,
+) -> Result<Result<Funded, IncorrectlyFunded>>
+where
+    C: LatestBlock<Block = Block>
+        + BlockByHash<Block = Block, BlockHash = Hash>
+        + BlockNotifications
+        + ReceiptByHash
+        + ConnectedNetwork<Network = ChainId>,
+{
+    let expected_bytecode = params.bytecode();
+    let confirmations = required_confirmations(connector).await?;
+
+    let (transaction, location, block_height) = restart_on_reorg(|| {
+        watch_for_contract_creation(connector, start_of_swap, &expected_bytecode, confirmations)
+            .instrument(tracing::info_span!("", action = "fund"))
+    })
+    .await?;
+
+    let asset = Ether::from_wei(transaction.value);
+
+    if asset != params.asset {
+        return Ok(Err(IncorrectlyFunded {
+            expected: params.asset,
+            got: asset,
+        }));
+    }
+
+    Ok(Ok(Funded {
+        transaction: transaction.hash,
+        location,
+        block_height,
+    }))
+}
+
+pub async fn watch_for_redeemed<C>(
+    connector: &C,
+    start_of_swap: OffsetDateTime,
+    funded: Funded,
+) -> Result<Redeemed>
+where
+    C: LatestBlock<Block = Block>
+        + BlockByHash<Block = Block, BlockHash = Hash>
+        + BlockNotifications
+        + ReceiptByHash
+        + TransactionByHash
+        + ConnectedNetwork<Network = ChainId>
+        + BlockByNumber
+        + GetLogs,
+{
+    use crate::btsieve::ethereum::Event;
+
+    let event = Event {
+        address: funded.location,
+        topics: vec![Some(*REDEEM_LOG_MSG)],
+    };
+
+    // The secret is revealed once the redeem transaction is mined, a reorg
+    // cannot take it back. Hence we do not wait for further confirmations.
+    let (transaction, log) = watch_for_event(connector, start_of_swap, event, 1)
+        .instrument(tracing::info_span!("", action = "redeem"))
+        .await?;
+
+    let secret =
+        Secret::from_vec(&log.data.0).expect("Must be able to construct secret from log data");
+
+    Ok(Redeemed {
+        transaction: transaction.hash,
+        secret,
+        block_height: log.block_height(),
+    })
+}
+
+pub async fn watch_for_redeemed_in_mempool<C>(
+    connector: &C,
+    params: &Params,
+    funded: Funded,
+) -> Result<Secret>
+where
+    C: PendingTransactions,
+{
+    let secret = watch_for_pending_transaction(connector, |transaction| {
+        if transaction.to != Some(funded.location) {
+            return None;
+        }
+
+        herc20::secret_from_redeem_data(&transaction.input.0, params.secret_hash)
+    })
+    .instrument(tracing::info_span!("", action = "redeem"))
+    .await?;
+
+    Ok(secret)
+}
+
+pub async fn watch_for_refunded<C>(
+    connector: &C,
+    start_of_swap: OffsetDateTime,
+    funded: Funded,
+) -> Result<Refunded>
+where
+    C: LatestBlock<Block = Block>
+        + BlockByHash<Block = Block, BlockHash = Hash>
+        + BlockNotifications
+        + ReceiptByHash
+        + TransactionByHash
+        + ConnectedNetwork<Network = ChainId>
+        + BlockByNumber
+        + GetLogs,
+{
+    use crate::btsieve::ethereum::Event;
+
+    let event = Event {
+        address: funded.location,
+        topics: vec![Some(*REFUND_LOG_MSG)],
+    };
+
+    let confirmations = required_confirmations(connector).await?;
+    let (transaction, log) = restart_on_reorg(|| {
+        watch_for_event(connector, start_of_swap, event.clone(), confirmations)
+            .instrument(tracing::info_span!("", action = "refund"))
+    })
+    .await?;
+
+    Ok(Refunded {
+        transaction: transaction.hash,
+        block_height: log.block_height(),
+    })
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Params {
+    pub asset: asset::Ether,
+    pub redeem_identity: identity::Ethereum,
+    pub refund_identity: identity::Ethereum,
+    pub expiry: Timestamp,
+    pub secret_hash: SecretHash,
+    pub chain_id: ChainId,
+}
+
+impl Params {
+    pub fn bytecode(&self) -> Vec<u8> {
+        Htlc::from(self.clone()).into()
+    }
+
+    /// Deploying the ether HTLC also funds it because the asset is sent along
+    /// with the contract creation transaction.
+    pub fn build_fund_action(&self) -> DeployContract {
+        let htlc = Htlc::from(self.clone());
+        let gas_limit = Htlc::deploy_tx_gas_limit();
+
+        DeployContract {
+            data: htlc.into(),
+            amount: self.asset.clone(),
+            gas_limit,
+            chain_id: self.chain_id,
+        }
+    }
+
+    pub fn build_refund_action(&self, htlc_location: htlc_location::Ethereum) -> CallContract {
+        let data = None;
+        let gas_limit = Htlc::refund_tx_gas_limit();
+
+        CallContract {
+            to: htlc_location,
+            data,
+            gas_limit,
+            chain_id: self.chain_id,
+        }
+    }
+
+    pub fn build_redeem_action(
+        &self,
+        htlc_location: htlc_location::Ethereum,
+        secret: Secret,
+    ) -> CallContract {
+        let data = Some(secret.into_raw_secret().to_vec());
+        let gas_limit = Htlc::redeem_tx_gas_limit();
+
+        CallContract {
+            to: htlc_location,
+            data,
+            gas_limit,
+            chain_id: self.chain_id,
+        }
+    }
+}
+
+impl From<Params> for Htlc {
+    fn from(params: Params) -> Self {
+        let refund_address = blockchain_contracts::ethereum::Address(params.refund_identity.into());
+        let redeem_address = blockchain_contracts::ethereum::Address(params.redeem_identity.into());
+
+        Htlc::new(
+            params.expiry.into(),
+            refund_address,
+            redeem_address,
+            params.secret_hash.into(),
+        )
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl quickcheck::Arbitrary for Params {
+    fn arbitrary<G: quickcheck::Gen>(g: &mut G) -> Self {
+        Self {
+            asset: Ether::from_wei(u128::arbitrary(g)),
+            redeem_identity: ethereum::Address::arbitrary(g),
+            refund_identity: ethereum::Address::arbitrary(g),
+            expiry: Timestamp::arbitrary(g),
+            secret_hash: SecretHash::arbitrary(g),
+            chain_id: ChainId::arbitrary(g),
+        }
+    }
+}
@@ -22,6 +22,7 @@ pub mod ethereum;
 pub mod expiries;
 pub mod hbit;
 pub mod herc20;
+pub mod heth;
 pub mod htlc_location;
 pub mod identity;
 pub mod ledger;
@@ -57,7 +58,7 @@ pub mod export {
 }
 
 pub use self::{
-    order::{BtcDaiOrder, OrderId, Position, Price, Quantity},
+    order::{BtcErc20Order, OrderId, Position, Price, Quantity, TradingPair},
     secret::Secret,
     secret_hash::SecretHash,
     timestamp::Timestamp,
@@ -87,6 +88,8 @@ pub enum LockProtocol {
     Hbit,
     /// The [`herc20`](crate::herc20) locking protocol.
     Herc20,
+    /// The [`heth`](crate::heth) locking protocol.
+    Heth,
 }
 
 #[derive(
@@ -1,5 +1,6 @@
 pub mod orderbook;
 pub mod setup_swap;
+pub mod transport;
 #[cfg(any(test, feature = "test"))]
 pub mod test;
 
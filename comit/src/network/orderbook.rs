@@ -1,10 +1,14 @@
+mod discovery;
 mod makerbook;
 mod order_source;
 
+pub use discovery::DiscoveryConfig;
+
 use crate::{
     orderpool::{Match, OrderPool},
-    BtcDaiOrder, OrderId,
+    BtcErc20Order, OrderId, TradingPair,
 };
+use discovery::Discovery;
 use libp2p::{
     identity::Keypair,
     swarm::{NetworkBehaviourAction, NetworkBehaviourEventProcess, PollParameters},
@@ -24,6 +28,7 @@ use std::{
 pub struct Orderbook {
     makerbook: Makerbook,
     order_source: OrderSource,
+    discovery: Discovery,
 
     #[behaviour(ignore)]
     events: VecDeque<BehaviourOutEvent>,
@@ -37,27 +42,53 @@ impl Orderbook {
         Orderbook {
             makerbook: Makerbook::new(key),
             order_source: OrderSource::default(),
+            discovery: Discovery::new(me.clone()),
             events: VecDeque::new(),
             orderpool: OrderPool::new(me),
         }
     }
 
-    /// Declare oneself to the network as a maker.
-    pub fn declare_as_maker(&mut self) {
-        self.makerbook.login();
+    /// Find makers we are not connected to yet, see [`DiscoveryConfig`].
+    pub fn with_discovery(mut self, config: DiscoveryConfig) -> anyhow::Result<Orderbook> {
+        self.discovery = Discovery::with_config(self.discovery.local_peer_id(), config)?;
+
+        Ok(self)
     }
 
-    /// Announce retraction of oneself as a maker, undoes `declare_as_maker()`.
-    pub fn retract(&mut self) {
-        self.makerbook.logout();
-        self.orderpool.clear_own_orders();
+    /// Declare oneself to the network as a maker of the given trading pair.
+    pub fn declare_as_maker(&mut self, trading_pair: &TradingPair) {
+        self.makerbook.login(trading_pair);
+        self.discovery.register(trading_pair.clone());
+    }
+
+    /// Announce retraction of oneself as a maker of the given trading pair,
+    /// undoes `declare_as_maker()`.
+    pub fn retract(&mut self, trading_pair: &TradingPair) {
+        self.makerbook.logout(trading_pair);
+        self.discovery.unregister(trading_pair);
+        self.orderpool.clear_own_orders_of(trading_pair);
     }
 
     /// Publish this order so it is visible to other peers.
-    pub fn publish(&mut self, order: BtcDaiOrder) {
+    pub fn publish(&mut self, order: BtcErc20Order) {
+        self.discovery.register(order.trading_pair.clone());
         self.orderpool.publish(order);
     }
 
+    /// Replace all orders we published with the given ones at once.
+    pub fn replace_own_orders(&mut self, orders: Vec<BtcErc20Order>) {
+        for order in orders.iter() {
+            self.discovery.register(order.trading_pair.clone());
+        }
+        self.orderpool.replace_own_orders(orders);
+    }
+
+    /// Keep looking for makers of the given trading pair and get the orders
+    /// of every maker we find.
+    pub fn find_makers(&mut self, trading_pair: TradingPair) {
+        self.discovery.find_makers(trading_pair);
+    }
+
     /// Cancel an order we previously published.
     pub fn cancel(&mut self, id: OrderId) {
         self.orderpool.cancel(id);
@@ -108,9 +139,13 @@ pub enum BehaviourOutEvent {
 impl NetworkBehaviourEventProcess<makerbook::BehaviourOutEvent> for Orderbook {
     fn inject_event(&mut self, event: makerbook::BehaviourOutEvent) {
         match event {
-            makerbook::BehaviourOutEvent::Logout { peer } => {
-                self.order_source.stop_getting_orders_from(&peer);
-                self.orderpool.remove_all_from(&peer)
+            makerbook::BehaviourOutEvent::Logout { peer, trading_pair } => {
+                self.orderpool
+                    .remove_trading_pair_from(&peer, &trading_pair);
+
+                if !self.orderpool.has_orders_from(&peer) {
+                    self.order_source.stop_getting_orders_from(&peer);
+                }
             }
         }
     }
@@ -132,3 +167,20 @@ impl NetworkBehaviourEventProcess<order_source::BehaviourOutEvent> for Orderbook
         }
     }
 }
+
+impl NetworkBehaviourEventProcess<discovery::BehaviourOutEvent> for Orderbook {
+    fn inject_event(&mut self, event: discovery::BehaviourOutEvent) {
+        match event {
+            discovery::BehaviourOutEvent::MakerDiscovered { maker } => {
+                // mDNS keeps reporting the peers in the local network, most of
+                // them we already get orders from.
+                if self.order_source.is_getting_orders_from(&maker) {
+                    return;
+                }
+
+                tracing::debug!("getting orders from discovered maker {}", maker);
+                self.order_source.start_getting_orders_from(maker);
+            }
+        }
+    }
+}
@@ -0,0 +1,658 @@
+use crate::TradingPair;
+use anyhow::Context as _;
+use futures::{AsyncRead, AsyncWrite};
+use libp2p::{
+    core::upgrade,
+    mdns::{MdnsEvent, TokioMdns},
+    multiaddr::Protocol,
+    request_response::{
+        ProtocolName, ProtocolSupport, RequestResponse, RequestResponseCodec,
+        RequestResponseConfig, RequestResponseEvent, RequestResponseMessage,
+    },
+    swarm::{toggle::Toggle, NetworkBehaviourAction, NetworkBehaviourEventProcess, PollParameters},
+    Multiaddr, NetworkBehaviour, PeerId,
+};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tokio::time::Delay;
+
+/// How often we register with the rendezvous points and ask them for makers.
+const ROUND_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Registrations that are not renewed within this time expire, this is how
+/// makers that went offline disappear from the rendezvous points.
+const REGISTRATION_TTL: Duration = Duration::from_secs(3 * 60);
+
+/// Upper bounds for what we accept as a rendezvous point, so a single peer
+/// cannot make us keep arbitrary amounts of data.
+const MAX_REGISTRATIONS: usize = 1024;
+const MAX_TRADING_PAIRS: usize = 16;
+const MAX_ADDRESSES: usize = 8;
+const MAX_MAKERS_PER_RESPONSE: usize = 64;
+
+const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+/// Configures how we find makers without knowing their addresses upfront.
+#[derive(Clone, Debug, Default)]
+pub struct DiscoveryConfig {
+    /// Rendezvous points to register with and to ask for makers, each address
+    /// has to end with the `/p2p/<peer id>` of the rendezvous point.
+    pub rendezvous_points: Vec<Multiaddr>,
+    /// Whether to act as a rendezvous point for other peers.
+    pub rendezvous_server: bool,
+    /// Whether to find peers in the local network via mDNS.
+    pub mdns: bool,
+}
+
+#[derive(Debug)]
+pub enum BehaviourOutEvent {
+    /// We found a peer that is likely to be a maker, either of a trading pair
+    /// we are looking for or in the local network.
+    MakerDiscovered { maker: PeerId },
+}
+
+/// A [NetworkBehaviour] for finding makers we are not connected to yet.
+///
+/// Makers register the trading pairs they trade with the configured rendezvous
+/// points, together with the addresses they can be reached at. Takers ask the
+/// rendezvous points for the makers of the trading pairs they are interested
+/// in. Any node can act as a rendezvous point.
+///
+/// In the local network, peers find each other via mDNS.
+#[derive(NetworkBehaviour)]
+#[behaviour(poll_method = "poll", out_event = "BehaviourOutEvent")]
+#[allow(missing_debug_implementations)]
+pub struct Discovery {
+    rendezvous: RequestResponse<RendezvousCodec>,
+    mdns: Toggle<TokioMdns>,
+
+    #[behaviour(ignore)]
+    me: PeerId,
+    #[behaviour(ignore)]
+    rendezvous_points: Vec<PeerId>,
+    /// Only present if we act as a rendezvous point.
+    #[behaviour(ignore)]
+    registrations: Option<Registrations>,
+    /// The trading pairs we register ourselves as a maker of.
+    #[behaviour(ignore)]
+    offered: HashSet<TradingPair>,
+    /// Whether the rendezvous points know us as a maker.
+    #[behaviour(ignore)]
+    registered: bool,
+    /// The trading pairs we are looking for makers of.
+    #[behaviour(ignore)]
+    wanted: HashSet<TradingPair>,
+    /// Our addresses as of the last registration.
+    #[behaviour(ignore)]
+    addresses: Vec<Multiaddr>,
+    #[behaviour(ignore)]
+    known_addresses: HashSet<(PeerId, Multiaddr)>,
+    /// Fires when the next round is due, `None` if it is due right away.
+    #[behaviour(ignore)]
+    next_round: Option<Delay>,
+    #[behaviour(ignore)]
+    events: VecDeque<BehaviourOutEvent>,
+}
+
+impl Discovery {
+    /// Discovery that does not find anybody.
+    pub fn new(me: PeerId) -> Self {
+        Self::with_support(me, ProtocolSupport::Outbound)
+    }
+
+    pub fn with_config(me: PeerId, config: DiscoveryConfig) -> anyhow::Result<Self> {
+        let mut discovery = if config.rendezvous_server {
+            let mut discovery = Self::with_support(me, ProtocolSupport::Full);
+            discovery.registrations = Some(Registrations::default());
+            discovery
+        } else {
+            Self::new(me)
+        };
+
+        for mut address in config.rendezvous_points {
+            let peer = match address.pop() {
+                Some(Protocol::P2p(hash)) => PeerId::from_multihash(hash).ok(),
+                _ => None,
+            }
+            .with_context(|| {
+                format!(
+                    "rendezvous point {} does not end with /p2p/<peer id>",
+                    address
+                )
+            })?;
+
+            discovery.rendezvous.add_address(&peer, address);
+            discovery.rendezvous_points.push(peer);
+        }
+
+        if config.mdns {
+            let mdns = TokioMdns::new().context("failed to start mDNS")?;
+            discovery.mdns = Some(mdns).into();
+        }
+
+        Ok(discovery)
+    }
+
+    fn with_support(me: PeerId, support: ProtocolSupport) -> Self {
+        Self {
+            rendezvous: RequestResponse::new(
+                RendezvousCodec,
+                vec![(RendezvousProtocol, support)],
+                RequestResponseConfig::default(),
+            ),
+            mdns: None.into(),
+            me,
+            rendezvous_points: Vec::new(),
+            registrations: None,
+            offered: HashSet::new(),
+            registered: false,
+            wanted: HashSet::new(),
+            addresses: Vec::new(),
+            known_addresses: HashSet::new(),
+            next_round: None,
+            events: VecDeque::new(),
+        }
+    }
+
+    pub fn local_peer_id(&self) -> PeerId {
+        self.me.clone()
+    }
+
+    /// Register ourselves as a maker of the given trading pair.
+    pub fn register(&mut self, trading_pair: TradingPair) {
+        if self.offered.insert(trading_pair) {
+            self.next_round = None;
+        }
+    }
+
+    /// Stop registering ourselves as a maker of the given trading pair.
+    pub fn unregister(&mut self, trading_pair: &TradingPair) {
+        if self.offered.remove(trading_pair) {
+            self.next_round = None;
+        }
+    }
+
+    /// Keep looking for makers of the given trading pair.
+    pub fn find_makers(&mut self, trading_pair: TradingPair) {
+        if self.wanted.insert(trading_pair) {
+            self.next_round = None;
+        }
+    }
+
+    fn start_round(&mut self, now: Instant) {
+        self.next_round = Some(tokio::time::delay_for(ROUND_INTERVAL));
+
+        // An empty registration makes the rendezvous points forget about us.
+        if !self.offered.is_empty() || self.registered {
+            let trading_pairs = self.offered.iter().cloned().collect::<Vec<_>>();
+
+            for point in &self.rendezvous_points {
+                self.rendezvous
+                    .send_request(point, wire::Request::Register {
+                        trading_pairs: trading_pairs.clone(),
+                        addresses: self.addresses.clone(),
+                    });
+            }
+            if let Some(registrations) = &mut self.registrations {
+                registrations.register(self.me.clone(), trading_pairs, self.addresses.clone(), now);
+            }
+
+            self.registered = !self.offered.is_empty();
+        }
+
+        for trading_pair in &self.wanted {
+            for point in &self.rendezvous_points {
+                self.rendezvous
+                    .send_request(point, wire::Request::Discover {
+                        trading_pair: trading_pair.clone(),
+                    });
+            }
+            if let Some(registrations) = &self.registrations {
+                for (maker, _) in registrations.makers_of(trading_pair, now) {
+                    if maker != self.me {
+                        self.events
+                            .push_back(BehaviourOutEvent::MakerDiscovered { maker });
+                    }
+                }
+            }
+        }
+    }
+
+    fn poll<BIE>(
+        &mut self,
+        cx: &mut Context<'_>,
+        params: &mut impl PollParameters,
+    ) -> Poll<NetworkBehaviourAction<BIE, BehaviourOutEvent>> {
+        // We only learn our addresses once the swarm listens, registrations
+        // without them are of no use.
+        let addresses = params
+            .external_addresses()
+            .chain(params.listened_addresses())
+            .take(MAX_ADDRESSES)
+            .collect::<Vec<_>>();
+        if addresses != self.addresses {
+            self.addresses = addresses;
+            if !self.offered.is_empty() {
+                self.next_round = None;
+            }
+        }
+
+        // Polling the delay of the next round makes sure we are woken up once
+        // it is due.
+        loop {
+            let round_due = match &mut self.next_round {
+                Some(next_round) => Pin::new(next_round).poll(cx).is_ready(),
+                None => true,
+            };
+            if !round_due {
+                break;
+            }
+
+            self.start_round(Instant::now());
+        }
+
+        if let Some(event) = self.events.pop_front() {
+            return Poll::Ready(NetworkBehaviourAction::GenerateEvent(event));
+        }
+
+        Poll::Pending
+    }
+}
+
+impl NetworkBehaviourEventProcess<RequestResponseEvent<wire::Request, wire::Response>>
+    for Discovery
+{
+    fn inject_event(&mut self, event: RequestResponseEvent<wire::Request, wire::Response>) {
+        match event {
+            RequestResponseEvent::Message {
+                peer,
+                message:
+                    RequestResponseMessage::Request {
+                        request, channel, ..
+                    },
+            } => {
+                // Only rendezvous points accept inbound requests.
+                let registrations = match &mut self.registrations {
+                    Some(registrations) => registrations,
+                    None => return,
+                };
+                let now = Instant::now();
+
+                let response = match request {
+                    wire::Request::Register {
+                        trading_pairs,
+                        addresses,
+                    } => {
+                        tracing::debug!("{} registered as a maker of {:?}", peer, trading_pairs);
+                        registrations.register(peer, trading_pairs, addresses, now);
+
+                        wire::Response::Registered
+                    }
+                    wire::Request::Discover { trading_pair } => wire::Response::Makers(
+                        registrations
+                            .makers_of(&trading_pair, now)
+                            .into_iter()
+                            .map(|(maker, addresses)| wire::Maker::from_model(maker, addresses))
+                            .collect(),
+                    ),
+                };
+
+                self.rendezvous.send_response(channel, response);
+            }
+            RequestResponseEvent::Message {
+                peer,
+                message: RequestResponseMessage::Response { response, .. },
+            } => match response {
+                wire::Response::Registered => {
+                    tracing::debug!("registered with rendezvous point {}", peer);
+                }
+                wire::Response::Makers(makers) => {
+                    for maker in makers {
+                        let (maker, addresses) = match maker.into_model() {
+                            Ok(maker) => maker,
+                            Err(e) => {
+                                tracing::debug!("rendezvous point {} sent {:#}", peer, e);
+                                continue;
+                            }
+                        };
+                        if maker == self.me {
+                            continue;
+                        }
+
+                        for address in addresses {
+                            if self
+                                .known_addresses
+                                .insert((maker.clone(), address.clone()))
+                            {
+                                self.rendezvous.add_address(&maker, address);
+                            }
+                        }
+
+                        self.events
+                            .push_back(BehaviourOutEvent::MakerDiscovered { maker });
+                    }
+                }
+            },
+            RequestResponseEvent::OutboundFailure { peer, error, .. } => {
+                tracing::warn!("request to rendezvous point {} failed: {:?}", peer, error);
+            }
+            RequestResponseEvent::InboundFailure { peer, error, .. } => {
+                tracing::debug!("rendezvous request from {} failed: {:?}", peer, error);
+            }
+        }
+    }
+}
+
+impl NetworkBehaviourEventProcess<MdnsEvent> for Discovery {
+    fn inject_event(&mut self, event: MdnsEvent) {
+        if let MdnsEvent::Discovered(peers) = event {
+            let peers = peers.map(|(peer, _)| peer).collect::<HashSet<_>>();
+
+            for peer in peers {
+                self.events
+                    .push_back(BehaviourOutEvent::MakerDiscovered { maker: peer });
+            }
+        }
+    }
+}
+
+/// The makers that registered with us as a rendezvous point.
+#[derive(Debug, Default)]
+struct Registrations(HashMap<PeerId, Registration>);
+
+#[derive(Debug)]
+struct Registration {
+    trading_pairs: Vec<TradingPair>,
+    addresses: Vec<Multiaddr>,
+    expires_at: Instant,
+}
+
+impl Registrations {
+    fn register(
+        &mut self,
+        maker: PeerId,
+        mut trading_pairs: Vec<TradingPair>,
+        mut addresses: Vec<Multiaddr>,
+        now: Instant,
+    ) {
+        self.0
+            .retain(|_, registration| registration.expires_at > now);
+
+        if trading_pairs.is_empty() {
+            self.0.remove(&maker);
+            return;
+        }
+        if self.0.len() >= MAX_REGISTRATIONS && !self.0.contains_key(&maker) {
+            tracing::warn!("ignoring registration of {}, too many makers", maker);
+            return;
+        }
+
+        trading_pairs.truncate(MAX_TRADING_PAIRS);
+        addresses.truncate(MAX_ADDRESSES);
+
+        let _ = self.0.insert(maker, Registration {
+            trading_pairs,
+            addresses,
+            expires_at: now + REGISTRATION_TTL,
+        });
+    }
+
+    fn makers_of(&self, trading_pair: &TradingPair, now: Instant) -> Vec<(PeerId, Vec<Multiaddr>)> {
+        self.0
+            .iter()
+            .filter(|(_, registration)| {
+                registration.expires_at > now && registration.trading_pairs.contains(trading_pair)
+            })
+            .take(MAX_MAKERS_PER_RESPONSE)
+            .map(|(maker, registration)| (maker.clone(), registration.addresses.clone()))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RendezvousProtocol;
+
+impl ProtocolName for RendezvousProtocol {
+    fn protocol_name(&self) -> &[u8] {
+        b"/comit/rendezvous/1.0.0"
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RendezvousCodec;
+
+#[async_trait::async_trait]
+impl RequestResponseCodec for RendezvousCodec {
+    type Protocol = RendezvousProtocol;
+    type Request = wire::Request;
+    type Response = wire::Response;
+
+    async fn read_request<T>(&mut self, _: &Self::Protocol, io: &mut T) -> io::Result<Self::Request>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_message(io).await
+    }
+
+    async fn read_response<T>(
+        &mut self,
+        _: &Self::Protocol,
+        io: &mut T,
+    ) -> io::Result<Self::Response>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_message(io).await
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        _: &Self::Protocol,
+        io: &mut T,
+        request: Self::Request,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_message(io, &request).await
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        _: &Self::Protocol,
+        io: &mut T,
+        response: Self::Response,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_message(io, &response).await
+    }
+}
+
+async fn read_message<T, M>(io: &mut T) -> io::Result<M>
+where
+    T: AsyncRead + Unpin + Send,
+    M: serde::de::DeserializeOwned,
+{
+    let message = upgrade::read_one(io, MAX_MESSAGE_SIZE)
+        .await
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let message = serde_json::from_slice(&message)?;
+
+    Ok(message)
+}
+
+async fn write_message<T, M>(io: &mut T, message: &M) -> io::Result<()>
+where
+    T: AsyncWrite + Unpin + Send,
+    M: serde::Serialize,
+{
+    let bytes = serde_json::to_vec(message)?;
+    upgrade::write_one(io, &bytes).await?;
+
+    Ok(())
+}
+
+/// A dedicated module for the types that represent our messages "on the wire".
+mod wire {
+    use crate::TradingPair;
+    use libp2p::{Multiaddr, PeerId};
+    use serde::{Deserialize, Serialize};
+    use std::str::FromStr;
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Request {
+        /// Registers the sender as a maker of the given trading pairs, an
+        /// empty list removes the registration.
+        Register {
+            trading_pairs: Vec<TradingPair>,
+            addresses: Vec<Multiaddr>,
+        },
+        /// Asks for the makers of the given trading pair.
+        Discover { trading_pair: TradingPair },
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Response {
+        Registered,
+        Makers(Vec<Maker>),
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct Maker {
+        pub peer_id: String,
+        pub addresses: Vec<Multiaddr>,
+    }
+
+    impl Maker {
+        pub fn from_model(peer_id: PeerId, addresses: Vec<Multiaddr>) -> Self {
+            Self {
+                peer_id: peer_id.to_base58(),
+                addresses,
+            }
+        }
+
+        pub fn into_model(self) -> anyhow::Result<(PeerId, Vec<Multiaddr>)> {
+            let peer_id = PeerId::from_str(&self.peer_id)
+                .map_err(|_| anyhow::anyhow!("invalid peer id {}", self.peer_id))?;
+
+            Ok((peer_id, self.addresses))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::test::new_swarm;
+    use futures::future::{self, FutureExt};
+    use libp2p::swarm::SwarmEvent;
+    use tokio::time;
+
+    fn registrations_with(maker: &PeerId, now: Instant) -> Registrations {
+        let mut registrations = Registrations::default();
+        registrations.register(
+            maker.clone(),
+            vec![TradingPair::btc_dai()],
+            vec!["/ip4/127.0.0.1/tcp/9939".parse().unwrap()],
+            now,
+        );
+
+        registrations
+    }
+
+    #[test]
+    fn registration_expires_unless_renewed() {
+        let maker = PeerId::random();
+        let now = Instant::now();
+        let registrations = registrations_with(&maker, now);
+
+        let before_expiry = registrations.makers_of(&TradingPair::btc_dai(), now + ROUND_INTERVAL);
+        let after_expiry = registrations.makers_of(&TradingPair::btc_dai(), now + REGISTRATION_TTL);
+
+        assert_eq!(before_expiry.len(), 1);
+        assert_eq!(before_expiry[0].0, maker);
+        assert!(after_expiry.is_empty());
+    }
+
+    #[test]
+    fn empty_registration_removes_maker() {
+        let maker = PeerId::random();
+        let now = Instant::now();
+        let mut registrations = registrations_with(&maker, now);
+
+        registrations.register(maker, vec![], vec![], now);
+
+        assert!(registrations
+            .makers_of(&TradingPair::btc_dai(), now)
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn taker_discovers_maker_through_rendezvous_point() {
+        let (mut server, server_addr, server_id) = new_swarm(|me, _| {
+            Discovery::with_config(me, DiscoveryConfig {
+                rendezvous_server: true,
+                ..Default::default()
+            })
+            .unwrap()
+        });
+        let config = DiscoveryConfig {
+            rendezvous_points: vec![server_addr.with(Protocol::P2p(server_id.into()))],
+            ..Default::default()
+        };
+        let (mut maker, _, maker_id) = new_swarm({
+            let config = config.clone();
+            move |me, _| Discovery::with_config(me, config.clone()).unwrap()
+        });
+        let (mut taker, ..) =
+            new_swarm(move |me, _| Discovery::with_config(me, config.clone()).unwrap());
+
+        maker.register(TradingPair::btc_dai());
+        time::timeout(Duration::from_secs(10), async {
+            while server
+                .registrations
+                .as_ref()
+                .unwrap()
+                .makers_of(&TradingPair::btc_dai(), Instant::now())
+                .is_empty()
+            {
+                let _ =
+                    future::select(maker.next_event().boxed(), server.next_event().boxed()).await;
+            }
+        })
+        .await
+        .expect("maker to register within 10 seconds");
+
+        tokio::spawn(async move {
+            loop {
+                let _ =
+                    future::select(maker.next_event().boxed(), server.next_event().boxed()).await;
+            }
+        });
+
+        taker.find_makers(TradingPair::btc_dai());
+        let discovered = time::timeout(Duration::from_secs(10), async {
+            loop {
+                if let SwarmEvent::Behaviour(BehaviourOutEvent::MakerDiscovered { maker }) =
+                    taker.next_event().await
+                {
+                    return maker;
+                }
+            }
+        })
+        .await
+        .expect("taker to discover maker within 10 seconds");
+
+        assert_eq!(discovered, maker_id);
+    }
+}
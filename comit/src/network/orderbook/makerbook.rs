@@ -1,3 +1,4 @@
+use crate::TradingPair;
 use byteorder::{BigEndian, ByteOrder};
 use conquer_once::Lazy;
 use libp2p::{
@@ -26,7 +27,10 @@ pub enum BehaviourOutEvent {
     /// pair.
     ///
     /// Connections to this peer can be closed as a result of this event.
-    Logout { peer: PeerId },
+    Logout {
+        peer: PeerId,
+        trading_pair: TradingPair,
+    },
 }
 
 /// A [NetworkBehaviour] for discovering peers that are likely to trade with us.
@@ -62,9 +66,9 @@ impl Makerbook {
         }
     }
 
-    pub fn login(&mut self) {
+    pub fn login(&mut self, trading_pair: &TradingPair) {
         let message = serde_json::to_vec(&wire::Message::Login {
-            trading_pair: wire::TradingPair::BtcDai,
+            trading_pair: trading_pair.clone(),
         })
         .expect("serialization doesn't panic");
         if self.gossipsub.publish(&COMIT_MAKERS, message).is_err() {
@@ -72,9 +76,9 @@ impl Makerbook {
         }
     }
 
-    pub fn logout(&mut self) {
+    pub fn logout(&mut self, trading_pair: &TradingPair) {
         let message = serde_json::to_vec(&wire::Message::Logout {
-            trading_pair: wire::TradingPair::BtcDai,
+            trading_pair: trading_pair.clone(),
         })
         .expect("serialization doesn't panic");
         if self.gossipsub.publish(&COMIT_MAKERS, message).is_err() {
@@ -129,7 +133,10 @@ impl NetworkBehaviourEventProcess<GossipsubEvent> for Makerbook {
                     );
                     self.actions
                         .push_back(NetworkBehaviourAction::GenerateEvent(
-                            BehaviourOutEvent::Logout { peer: source },
+                            BehaviourOutEvent::Logout {
+                                peer: source,
+                                trading_pair,
+                            },
                         ))
                 }
             }
@@ -150,8 +157,8 @@ fn content_based_id(message: &GossipsubMessage) -> MessageId {
 }
 
 mod wire {
+    use crate::TradingPair;
     use serde::{Deserialize, Serialize};
-    use std::fmt;
 
     /// All messages sent to the `/comit/makers` topic.
     #[derive(Debug, Serialize, Deserialize)]
@@ -163,19 +170,4 @@ mod wire {
         /// no longer available for trading the given trading pair.
         Logout { trading_pair: TradingPair },
     }
-
-    /// Defines the set of trading pairs that we support.
-    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
-    pub enum TradingPair {
-        #[serde(rename = "BTC/DAI")]
-        BtcDai,
-    }
-
-    impl fmt::Display for TradingPair {
-        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            write!(f, "{}", match self {
-                TradingPair::BtcDai => "BTC/DAI",
-            })
-        }
-    }
 }
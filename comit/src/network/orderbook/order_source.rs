@@ -1,7 +1,7 @@
 use crate::{
     expiries::{AlphaOffset, BetaOffset},
     order::SwapProtocol,
-    BtcDaiOrder, Price, Quantity,
+    BtcErc20Order, Price, Quantity,
 };
 use futures::{AsyncRead, AsyncWrite};
 use libp2p::{
@@ -30,18 +30,21 @@ use time::NumericalDuration;
 /// Wait at least this long before re-getting orders from a maker.
 const POLLING_INTERVAL: Duration = Duration::from_secs(5);
 
+/// The maximum size of a response, makers publish orders for several trading
+/// pairs in a single response.
+const MAX_RESPONSE_SIZE: usize = 64 * 1024;
+
 /// A [NetworkBehaviour] that acts as a source for orders.
 ///
 /// Orders are pulled regularly from a given set of makers. Every connection
 /// established will be tried as a potential order source.
 #[allow(missing_debug_implementations)]
 pub struct OrderSource {
-    get_orders: RequestResponse<GetBtcDaiOrdersCodec>,
+    get_orders: RequestResponse<GetOrdersCodec>,
     /// Makers we will attempt to get updated orders from.
     active_makers: HashSet<PeerId>,
     last_polled_makers_at: Instant,
-    actions:
-        VecDeque<NetworkBehaviourAction<RequestProtocol<GetBtcDaiOrdersCodec>, BehaviourOutEvent>>,
+    actions: VecDeque<NetworkBehaviourAction<RequestProtocol<GetOrdersCodec>, BehaviourOutEvent>>,
 }
 
 impl OrderSource {
@@ -54,8 +57,12 @@ impl OrderSource {
         self.active_makers.remove(maker);
     }
 
+    pub fn is_getting_orders_from(&self, maker: &PeerId) -> bool {
+        self.active_makers.contains(maker)
+    }
+
     /// Respond to a get orders request.
-    pub fn send_orders(&mut self, handle: ResponseHandle, orders: Vec<BtcDaiOrder>) {
+    pub fn send_orders(&mut self, handle: ResponseHandle, orders: Vec<BtcErc20Order>) {
         self.get_orders.send_response(handle.0, orders);
     }
 
@@ -65,8 +72,7 @@ impl OrderSource {
 }
 
 impl NetworkBehaviour for OrderSource {
-    type ProtocolsHandler =
-        <RequestResponse<GetBtcDaiOrdersCodec> as NetworkBehaviour>::ProtocolsHandler;
+    type ProtocolsHandler = <RequestResponse<GetOrdersCodec> as NetworkBehaviour>::ProtocolsHandler;
     type OutEvent = BehaviourOutEvent;
 
     fn new_handler(&mut self) -> Self::ProtocolsHandler {
@@ -133,7 +139,7 @@ impl NetworkBehaviour for OrderSource {
         &mut self,
         peer_id: PeerId,
         connection: ConnectionId,
-        event: RequestResponseHandlerEvent<GetBtcDaiOrdersCodec>,
+        event: RequestResponseHandlerEvent<GetOrdersCodec>,
     ) {
         self.get_orders.inject_event(peer_id, connection, event)
     }
@@ -176,7 +182,7 @@ impl NetworkBehaviour for OrderSource {
         &mut self,
         cx: &mut Context<'_>,
         params: &mut impl PollParameters,
-    ) -> Poll<NetworkBehaviourAction<RequestProtocol<GetBtcDaiOrdersCodec>, Self::OutEvent>> {
+    ) -> Poll<NetworkBehaviourAction<RequestProtocol<GetOrdersCodec>, Self::OutEvent>> {
         match self.get_orders.poll(cx, params) {
             Poll::Ready(NetworkBehaviourAction::GenerateEvent(event)) => match event {
                 RequestResponseEvent::Message {
@@ -261,8 +267,8 @@ impl Default for OrderSource {
     fn default() -> Self {
         let config = RequestResponseConfig::default();
         let behaviour = RequestResponse::new(
-            GetBtcDaiOrdersCodec::default(),
-            vec![(GetBtcDaiOrdersProtocol, ProtocolSupport::Full)],
+            GetOrdersCodec::default(),
+            vec![(GetOrdersProtocol, ProtocolSupport::Full)],
             config,
         );
 
@@ -282,7 +288,7 @@ pub enum BehaviourOutEvent {
     /// We retrieved orders from the given maker.
     RetrievedOrders {
         maker: PeerId,
-        orders: Vec<BtcDaiOrder>,
+        orders: Vec<BtcErc20Order>,
     },
     /// The given maker disconnected.
     ///
@@ -296,26 +302,26 @@ pub enum BehaviourOutEvent {
 ///
 /// This type allows us to keep the `wire` module private to this module.
 #[derive(Debug)]
-pub struct ResponseHandle(ResponseChannel<Vec<BtcDaiOrder>>);
+pub struct ResponseHandle(ResponseChannel<Vec<BtcErc20Order>>);
 
 #[derive(Debug, Clone, Copy)]
-pub struct GetBtcDaiOrdersProtocol;
+pub struct GetOrdersProtocol;
 
-impl ProtocolName for GetBtcDaiOrdersProtocol {
+impl ProtocolName for GetOrdersProtocol {
     fn protocol_name(&self) -> &[u8] {
-        b"/comit/get-orders/btc-dai/1.0.0"
+        b"/comit/get-orders/1.0.0"
     }
 }
 
 #[derive(Clone, Copy, Debug, Default)]
-pub struct GetBtcDaiOrdersCodec;
+pub struct GetOrdersCodec;
 
 #[async_trait::async_trait]
-impl RequestResponseCodec for GetBtcDaiOrdersCodec {
-    type Protocol = GetBtcDaiOrdersProtocol;
+impl RequestResponseCodec for GetOrdersCodec {
+    type Protocol = GetOrdersProtocol;
     type Request = ();
     // TODO: Allow a response of "I am not a maker" to stop asking them.
-    type Response = Vec<BtcDaiOrder>;
+    type Response = Vec<BtcErc20Order>;
 
     /// Reads a get orders request from the given I/O stream.
     async fn read_request<T>(&mut self, _: &Self::Protocol, _: &mut T) -> io::Result<Self::Request>
@@ -334,11 +340,11 @@ impl RequestResponseCodec for GetBtcDaiOrdersCodec {
     where
         T: AsyncRead + Unpin + Send,
     {
-        let message = upgrade::read_one(io, 1024)
+        let message = upgrade::read_one(io, MAX_RESPONSE_SIZE)
             .await
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
         let mut de = serde_json::Deserializer::from_slice(&message);
-        let orders = Vec::<wire::BtcDaiOrder>::deserialize(&mut de)?;
+        let orders = Vec::<wire::Order>::deserialize(&mut de)?;
 
         Ok(orders.into_iter().map(|wire| wire.into_model()).collect())
     }
@@ -370,7 +376,7 @@ impl RequestResponseCodec for GetBtcDaiOrdersCodec {
         let bytes = serde_json::to_vec(
             &orders
                 .into_iter()
-                .map(wire::BtcDaiOrder::from_model)
+                .map(wire::Order::from_model)
                 .collect::<Vec<_>>(),
         )?;
         upgrade::write_one(io, &bytes).await?;
@@ -381,19 +387,21 @@ impl RequestResponseCodec for GetBtcDaiOrdersCodec {
 
 /// A dedicated module for the types that represent our messages "on the wire".
 mod wire {
-    use crate::{asset, asset::Erc20Quantity, OrderId, Position};
+    use crate::{asset, asset::Erc20Quantity, OrderId, Position, TradingPair};
     use serde::{Deserialize, Serialize};
     use time::OffsetDateTime;
 
     #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
-    pub struct BtcDaiOrder {
+    pub struct Order {
         pub id: OrderId,
+        pub trading_pair: TradingPair,
         pub position: Position,
         pub swap_protocol: SwapProtocol,
         #[serde(with = "time::serde::timestamp")]
         pub created_at: OffsetDateTime,
         #[serde(with = "asset::bitcoin::sats_as_string")]
         pub quantity: asset::Bitcoin,
+        /// In the smallest unit of the token per bitcoin.
         pub price: Erc20Quantity,
     }
 
@@ -408,13 +416,22 @@ mod wire {
             herc20_expiry_offset: i64,
             hbit_expiry_offset: i64,
         },
+        HbitHeth {
+            hbit_expiry_offset: i64,
+            heth_expiry_offset: i64,
+        },
+        HethHbit {
+            heth_expiry_offset: i64,
+            hbit_expiry_offset: i64,
+        },
     }
 }
 
-impl wire::BtcDaiOrder {
-    fn into_model(self) -> BtcDaiOrder {
-        let wire::BtcDaiOrder {
+impl wire::Order {
+    fn into_model(self) -> BtcErc20Order {
+        let wire::Order {
             id,
+            trading_pair,
             position,
             swap_protocol,
             created_at,
@@ -422,19 +439,21 @@ impl wire::BtcDaiOrder {
             price,
         } = self;
 
-        BtcDaiOrder {
+        BtcErc20Order {
             id,
+            trading_pair,
             position,
             swap_protocol: swap_protocol.into_model(),
             created_at,
             quantity: Quantity::new(quantity),
-            price: Price::from_wei_per_sat(price),
+            price: Price::from_wei_per_btc(price),
         }
     }
 
-    fn from_model(model: BtcDaiOrder) -> Self {
-        let BtcDaiOrder {
+    fn from_model(model: BtcErc20Order) -> Self {
+        let BtcErc20Order {
             id,
+            trading_pair,
             position,
             swap_protocol,
             created_at,
@@ -444,11 +463,12 @@ impl wire::BtcDaiOrder {
 
         Self {
             id,
+            trading_pair,
             position,
             swap_protocol: wire::SwapProtocol::from_model(swap_protocol),
             created_at,
             quantity: quantity.to_inner(),
-            price: price.wei_per_sat(), /* This is consistent with how we convert into the wire
+            price: price.wei_per_btc(), /* This is consistent with how we convert into the wire
                                          * model above. */
         }
     }
@@ -471,6 +491,20 @@ impl wire::SwapProtocol {
                 hbit_expiry_offset: AlphaOffset::from(hbit_expiry_offset.seconds()),
                 herc20_expiry_offset: BetaOffset::from(herc20_expiry_offset.seconds()),
             },
+            wire::SwapProtocol::HbitHeth {
+                hbit_expiry_offset,
+                heth_expiry_offset,
+            } => SwapProtocol::HbitHeth {
+                hbit_expiry_offset: AlphaOffset::from(hbit_expiry_offset.seconds()),
+                heth_expiry_offset: BetaOffset::from(heth_expiry_offset.seconds()),
+            },
+            wire::SwapProtocol::HethHbit {
+                heth_expiry_offset,
+                hbit_expiry_offset,
+            } => SwapProtocol::HethHbit {
+                heth_expiry_offset: AlphaOffset::from(heth_expiry_offset.seconds()),
+                hbit_expiry_offset: BetaOffset::from(hbit_expiry_offset.seconds()),
+            },
         }
     }
 
@@ -492,6 +526,20 @@ impl wire::SwapProtocol {
                 herc20_expiry_offset: Duration::from(herc20_expiry_offset).whole_seconds(),
                 hbit_expiry_offset: Duration::from(hbit_expiry_offset).whole_seconds(),
             },
+            SwapProtocol::HbitHeth {
+                hbit_expiry_offset,
+                heth_expiry_offset,
+            } => wire::SwapProtocol::HbitHeth {
+                hbit_expiry_offset: Duration::from(hbit_expiry_offset).whole_seconds(),
+                heth_expiry_offset: Duration::from(heth_expiry_offset).whole_seconds(),
+            },
+            SwapProtocol::HethHbit {
+                heth_expiry_offset,
+                hbit_expiry_offset,
+            } => wire::SwapProtocol::HethHbit {
+                heth_expiry_offset: Duration::from(heth_expiry_offset).whole_seconds(),
+                hbit_expiry_offset: Duration::from(hbit_expiry_offset).whole_seconds(),
+            },
         }
     }
 }
@@ -504,9 +552,9 @@ mod tests {
     proptest::proptest! {
         #[test]
         fn conversions_to_and_from_wire_model_are_consistent(
-            order in proptest::order::btc_dai_order(),
+            order in proptest::order::btc_erc20_order(),
         ) {
-            let round_tripped = wire::BtcDaiOrder::from_model(order.clone()).into_model();
+            let round_tripped = wire::Order::from_model(order.clone()).into_model();
 
             assert_eq!(order, round_tripped);
         }
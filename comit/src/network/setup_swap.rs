@@ -1,5 +1,6 @@
 use crate::{
-    asset, ethereum::ChainId, hbit, herc20, identity, ledger, Role, SecretHash, Timestamp,
+    asset, ethereum::ChainId, hbit, herc20, heth, identity, ledger, OrderId, Role, SecretHash,
+    Timestamp,
 };
 use anyhow::Result;
 use futures::prelude::*;
@@ -12,6 +13,7 @@ use libp2p::{
     swarm::{NetworkBehaviourAction, NetworkBehaviourEventProcess, PollParameters},
     NetworkBehaviour, PeerId,
 };
+use num::BigUint;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, VecDeque},
@@ -19,22 +21,47 @@ use std::{
     io,
     marker::PhantomData,
     task::{Context, Poll},
+    time::{Duration, Instant},
 };
 
 #[derive(Clone, Copy, Debug, thiserror::Error)]
 #[error("Already have role dependent parameters for this set of common parameters")]
 pub struct AlreadyHaveRoleParams;
 
+#[derive(Clone, Copy, Debug, thiserror::Error)]
+#[error("The ethereum asset does not match the swap protocol {0:?}")]
+pub struct AssetDoesNotMatchProtocol(pub SwapProtocol);
+
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum SetupFailed {
+    #[error("Alice and Bob proposed quantities at different prices")]
+    PriceMismatch,
+    #[error(transparent)]
+    AssetDoesNotMatchProtocol(#[from] AssetDoesNotMatchProtocol),
+    #[error("The counterparty did not send its parameters within {0:?}")]
+    Timeout(Duration),
+}
+
+/// How long we wait for the parameters of the counterparty.
+const SETUP_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
 #[derive(Clone, Debug)]
 #[allow(clippy::large_enum_variant)]
 pub enum BehaviourOutEvent<C> {
     // TODO: Split this into two events, one for HbitHerc20 and one for Herc20Hbit
     ExecutableSwap(ExecutableSwap<C>),
+    ExecutableHethSwap(ExecutableHethSwap<C>),
     AlreadyHaveRoleParams {
         peer: PeerId,
         have: RoleDependentParams,
         received: RoleDependentParams,
     },
+    /// Setting up the swap we sent our parameters for failed.
+    SetupFailed {
+        peer: PeerId,
+        context: C,
+        error: SetupFailed,
+    },
 }
 
 #[derive(Clone, Debug)]
@@ -47,6 +74,16 @@ pub struct ExecutableSwap<C> {
     pub context: C,
 }
 
+#[derive(Clone, Debug)]
+pub struct ExecutableHethSwap<C> {
+    pub our_role: Role,
+    pub heth: heth::Params,
+    pub hbit: hbit::SharedParams,
+    pub swap_protocol: SwapProtocol,
+    pub peer_id: PeerId,
+    pub context: C,
+}
+
 impl<C> BehaviourOutEvent<C> {
     fn new_executable_swap(
         our_role: Role,
@@ -56,60 +93,87 @@ impl<C> BehaviourOutEvent<C> {
         swap_protocol: SwapProtocol,
         peer_id: PeerId,
         context: C,
-    ) -> Self {
-        match swap_protocol {
-            SwapProtocol::HbitHerc20 => BehaviourOutEvent::ExecutableSwap(ExecutableSwap {
-                our_role,
-                herc20: herc20::Params {
-                    asset: common.erc20,
-                    redeem_identity: alice.ethereum_identity,
-                    refund_identity: bob.ethereum_identity,
-                    expiry: Timestamp::from(common.ethereum_absolute_expiry),
-                    secret_hash: alice.secret_hash,
-                    chain_id: common.ethereum_chain_id,
-                },
-                hbit: hbit::SharedParams {
-                    network: common.bitcoin_network,
-                    asset: common.bitcoin,
-                    redeem_identity: bob.bitcoin_identity,
-                    refund_identity: alice.bitcoin_identity,
-                    expiry: Timestamp::from(common.bitcoin_absolute_expiry),
-                    secret_hash: alice.secret_hash,
-                },
-                swap_protocol,
-                peer_id,
-                context,
-            }),
-            SwapProtocol::Herc20Hbit => BehaviourOutEvent::ExecutableSwap(ExecutableSwap {
-                our_role,
-                herc20: herc20::Params {
-                    asset: common.erc20,
-                    redeem_identity: bob.ethereum_identity,
-                    refund_identity: alice.ethereum_identity,
-                    expiry: Timestamp::from(common.ethereum_absolute_expiry),
-                    secret_hash: alice.secret_hash,
-                    chain_id: common.ethereum_chain_id,
-                },
-                hbit: hbit::SharedParams {
-                    network: common.bitcoin_network,
-                    asset: common.bitcoin,
-                    redeem_identity: alice.bitcoin_identity,
-                    refund_identity: bob.bitcoin_identity,
-                    expiry: Timestamp::from(common.bitcoin_absolute_expiry),
-                    secret_hash: alice.secret_hash,
-                },
-                swap_protocol,
-                peer_id,
-                context,
-            }),
-        }
+    ) -> Result<Self, AssetDoesNotMatchProtocol> {
+        // Alice redeems on the beta ledger, Bob redeems on the alpha ledger.
+        let (
+            ethereum_redeem_identity,
+            ethereum_refund_identity,
+            bitcoin_redeem_identity,
+            bitcoin_refund_identity,
+        ) = match swap_protocol {
+            SwapProtocol::HbitHerc20 | SwapProtocol::HbitHeth => (
+                alice.ethereum_identity,
+                bob.ethereum_identity,
+                bob.bitcoin_identity,
+                alice.bitcoin_identity,
+            ),
+            SwapProtocol::Herc20Hbit | SwapProtocol::HethHbit => (
+                bob.ethereum_identity,
+                alice.ethereum_identity,
+                alice.bitcoin_identity,
+                bob.bitcoin_identity,
+            ),
+        };
+
+        let hbit = hbit::SharedParams {
+            network: common.bitcoin_network,
+            asset: common.bitcoin,
+            redeem_identity: bitcoin_redeem_identity,
+            refund_identity: bitcoin_refund_identity,
+            expiry: Timestamp::from(common.bitcoin_absolute_expiry),
+            secret_hash: alice.secret_hash,
+        };
+
+        let event = match (swap_protocol, common.ethereum) {
+            (SwapProtocol::HbitHerc20, EthereumAsset::Erc20(asset))
+            | (SwapProtocol::Herc20Hbit, EthereumAsset::Erc20(asset)) => {
+                BehaviourOutEvent::ExecutableSwap(ExecutableSwap {
+                    our_role,
+                    herc20: herc20::Params {
+                        asset,
+                        redeem_identity: ethereum_redeem_identity,
+                        refund_identity: ethereum_refund_identity,
+                        expiry: Timestamp::from(common.ethereum_absolute_expiry),
+                        secret_hash: alice.secret_hash,
+                        chain_id: common.ethereum_chain_id,
+                    },
+                    hbit,
+                    swap_protocol,
+                    peer_id,
+                    context,
+                })
+            }
+            (SwapProtocol::HbitHeth, EthereumAsset::Ether(asset))
+            | (SwapProtocol::HethHbit, EthereumAsset::Ether(asset)) => {
+                BehaviourOutEvent::ExecutableHethSwap(ExecutableHethSwap {
+                    our_role,
+                    heth: heth::Params {
+                        asset,
+                        redeem_identity: ethereum_redeem_identity,
+                        refund_identity: ethereum_refund_identity,
+                        expiry: Timestamp::from(common.ethereum_absolute_expiry),
+                        secret_hash: alice.secret_hash,
+                        chain_id: common.ethereum_chain_id,
+                    },
+                    hbit,
+                    swap_protocol,
+                    peer_id,
+                    context,
+                })
+            }
+            _ => return Err(AssetDoesNotMatchProtocol(swap_protocol)),
+        };
+
+        Ok(event)
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum SwapProtocol {
     HbitHerc20,
     Herc20Hbit,
+    HbitHeth,
+    HethHbit,
 }
 
 #[derive(NetworkBehaviour)]
@@ -118,12 +182,60 @@ pub enum SwapProtocol {
 pub struct SetupSwap<C: Clone + Send + 'static> {
     hbit_herc20: RequestResponse<Codec<HbitHerc20Protocol>>,
     herc20_hbit: RequestResponse<Codec<Herc20HbitProtocol>>,
+    hbit_heth: RequestResponse<Codec<HbitHethProtocol>>,
+    heth_hbit: RequestResponse<Codec<HethHbitProtocol>>,
     #[behaviour(ignore)]
     events: VecDeque<BehaviourOutEvent<C>>,
     #[behaviour(ignore)]
-    swap_data: HashMap<CommonParams, RoleDependentParams>,
-    #[behaviour(ignore)]
-    context: HashMap<CommonParams, C>,
+    swap_data: HashMap<SetupKey, Pending<C>>,
+}
+
+/// Identifies a swap that is being set up with a peer.
+///
+/// The quantities are not part of the key because they are negotiated: each
+/// party proposes the quantity it is able to fill. The ids of the matched
+/// orders tell apart several swaps that are set up with the same peer at the
+/// same time.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct SetupKey {
+    peer: PeerId,
+    swap_protocol: SwapProtocol,
+    alice_order_id: OrderId,
+    bob_order_id: OrderId,
+    token_contract: Option<identity::Ethereum>,
+    ethereum_absolute_expiry: u32,
+    bitcoin_absolute_expiry: u32,
+    ethereum_chain_id: ChainId,
+    bitcoin_network: ledger::Bitcoin,
+}
+
+impl SetupKey {
+    fn new(peer: PeerId, swap_protocol: SwapProtocol, common: &CommonParams) -> Self {
+        SetupKey {
+            peer,
+            swap_protocol,
+            alice_order_id: common.alice_order_id,
+            bob_order_id: common.bob_order_id,
+            token_contract: match &common.ethereum {
+                EthereumAsset::Erc20(erc20) => Some(erc20.token_contract),
+                EthereumAsset::Ether(_) => None,
+            },
+            ethereum_absolute_expiry: common.ethereum_absolute_expiry,
+            bitcoin_absolute_expiry: common.bitcoin_absolute_expiry,
+            ethereum_chain_id: common.ethereum_chain_id,
+            bitcoin_network: common.bitcoin_network,
+        }
+    }
+}
+
+/// The parameters of whoever sent first, waiting for the other party.
+#[derive(Debug)]
+struct Pending<C> {
+    common: CommonParams,
+    params: RoleDependentParams,
+    /// Only set if we sent first.
+    context: Option<C>,
+    since: Instant,
 }
 
 impl<C: Clone + Send + 'static> Default for SetupSwap<C> {
@@ -139,9 +251,18 @@ impl<C: Clone + Send + 'static> Default for SetupSwap<C> {
                 vec![(Herc20HbitProtocol, ProtocolSupport::Full)],
                 RequestResponseConfig::default(),
             ),
+            hbit_heth: RequestResponse::new(
+                Codec::default(),
+                vec![(HbitHethProtocol, ProtocolSupport::Full)],
+                RequestResponseConfig::default(),
+            ),
+            heth_hbit: RequestResponse::new(
+                Codec::default(),
+                vec![(HethHbitProtocol, ProtocolSupport::Full)],
+                RequestResponseConfig::default(),
+            ),
             events: Default::default(),
             swap_data: Default::default(),
-            context: Default::default(),
         }
     }
 }
@@ -155,36 +276,46 @@ impl<C: Clone + Send + 'static> SetupSwap<C> {
         swap_protocol: SwapProtocol,
         context: C,
     ) -> Result<()> {
-        match (self.swap_data.get(&common), &to_send) {
-            (Some(RoleDependentParams::Alice(alice)), RoleDependentParams::Bob(bob)) => {
-                self.events
-                    .push_back(BehaviourOutEvent::new_executable_swap(
+        if !common.ethereum.matches(swap_protocol) {
+            return Err(anyhow::Error::from(AssetDoesNotMatchProtocol(
+                swap_protocol,
+            )));
+        }
+
+        let key = SetupKey::new(to.clone(), swap_protocol, &common);
+        match self.swap_data.remove(&key) {
+            Some(pending) => match (pending.params, to_send) {
+                (RoleDependentParams::Alice(alice), RoleDependentParams::Bob(bob)) => self
+                    .complete(
                         Role::Bob,
-                        common.clone(),
-                        alice,
-                        &bob,
+                        (pending.common, alice),
+                        (common.clone(), bob),
                         swap_protocol,
                         to.clone(),
                         context,
-                    ));
-            }
-            (Some(RoleDependentParams::Bob(bob)), RoleDependentParams::Alice(alice)) => {
-                self.events
-                    .push_back(BehaviourOutEvent::new_executable_swap(
+                    ),
+                (RoleDependentParams::Bob(bob), RoleDependentParams::Alice(alice)) => self
+                    .complete(
                         Role::Alice,
-                        common.clone(),
-                        &alice,
-                        bob,
+                        (common.clone(), alice),
+                        (pending.common, bob),
                         swap_protocol,
                         to.clone(),
                         context,
-                    ));
-            }
-            (None, role) => {
-                self.swap_data.insert(common.clone(), *role);
-                self.context.insert(common.clone(), context);
+                    ),
+                _ => {
+                    self.swap_data.insert(key, pending);
+                    return Err(anyhow::Error::from(AlreadyHaveRoleParams));
+                }
+            },
+            None => {
+                self.swap_data.insert(key, Pending {
+                    common: common.clone(),
+                    params: to_send,
+                    context: Some(context),
+                    since: Instant::now(),
+                });
             }
-            (Some(_), _) => return Err(anyhow::Error::from(AlreadyHaveRoleParams)),
         }
 
         tracing::info!("Setting up swap with {}", to);
@@ -196,97 +327,200 @@ impl<C: Clone + Send + 'static> SetupSwap<C> {
             SwapProtocol::HbitHerc20 => self
                 .hbit_herc20
                 .send_request(to, to_send.to_message(common)),
+            SwapProtocol::HbitHeth => self.hbit_heth.send_request(to, to_send.to_message(common)),
+            SwapProtocol::HethHbit => self.heth_hbit.send_request(to, to_send.to_message(common)),
         };
         Ok(())
     }
 
     fn alice_receive_hbit_herc20(&mut self, from: PeerId, common: CommonParams, bob: BobParams) {
-        self.alice_receive(from, common, bob, SwapProtocol::HbitHerc20);
+        self.receive(
+            from,
+            common,
+            RoleDependentParams::Bob(bob),
+            SwapProtocol::HbitHerc20,
+        );
     }
 
     fn alice_receive_herc20_hbit(&mut self, from: PeerId, common: CommonParams, bob: BobParams) {
-        self.alice_receive(from, common, bob, SwapProtocol::Herc20Hbit);
+        self.receive(
+            from,
+            common,
+            RoleDependentParams::Bob(bob),
+            SwapProtocol::Herc20Hbit,
+        );
     }
 
     fn bob_receive_hbit_herc20(&mut self, from: PeerId, common: CommonParams, alice: AliceParams) {
-        self.bob_receive(from, common, alice, SwapProtocol::HbitHerc20);
+        self.receive(
+            from,
+            common,
+            RoleDependentParams::Alice(alice),
+            SwapProtocol::HbitHerc20,
+        );
     }
 
     fn bob_receive_herc20_hbit(&mut self, from: PeerId, common: CommonParams, alice: AliceParams) {
-        self.bob_receive(from, common, alice, SwapProtocol::Herc20Hbit);
+        self.receive(
+            from,
+            common,
+            RoleDependentParams::Alice(alice),
+            SwapProtocol::Herc20Hbit,
+        );
     }
-    fn alice_receive(
+
+    fn alice_receive_hbit_heth(&mut self, from: PeerId, common: CommonParams, bob: BobParams) {
+        self.receive(
+            from,
+            common,
+            RoleDependentParams::Bob(bob),
+            SwapProtocol::HbitHeth,
+        );
+    }
+
+    fn alice_receive_heth_hbit(&mut self, from: PeerId, common: CommonParams, bob: BobParams) {
+        self.receive(
+            from,
+            common,
+            RoleDependentParams::Bob(bob),
+            SwapProtocol::HethHbit,
+        );
+    }
+
+    fn bob_receive_hbit_heth(&mut self, from: PeerId, common: CommonParams, alice: AliceParams) {
+        self.receive(
+            from,
+            common,
+            RoleDependentParams::Alice(alice),
+            SwapProtocol::HbitHeth,
+        );
+    }
+
+    fn bob_receive_heth_hbit(&mut self, from: PeerId, common: CommonParams, alice: AliceParams) {
+        self.receive(
+            from,
+            common,
+            RoleDependentParams::Alice(alice),
+            SwapProtocol::HethHbit,
+        );
+    }
+
+    fn receive(
         &mut self,
         from: PeerId,
         common: CommonParams,
-        bob: BobParams,
+        received: RoleDependentParams,
         swap_protocol: SwapProtocol,
     ) {
-        match self.swap_data.get(&common) {
-            Some(RoleDependentParams::Alice(alice)) => {
-                // todo: remove unwrap
-                let context = self.context.get(&common).cloned().unwrap();
-                self.events
-                    .push_back(BehaviourOutEvent::new_executable_swap(
-                        Role::Alice,
-                        common,
-                        alice,
-                        &bob,
-                        swap_protocol,
-                        from,
+        let key = SetupKey::new(from.clone(), swap_protocol, &common);
+        match self.swap_data.remove(&key) {
+            Some(pending) => match (pending.params, received, pending.context) {
+                (
+                    RoleDependentParams::Alice(alice),
+                    RoleDependentParams::Bob(bob),
+                    Some(context),
+                ) => self.complete(
+                    Role::Alice,
+                    (pending.common, alice),
+                    (common, bob),
+                    swap_protocol,
+                    from,
+                    context,
+                ),
+                (
+                    RoleDependentParams::Bob(bob),
+                    RoleDependentParams::Alice(alice),
+                    Some(context),
+                ) => self.complete(
+                    Role::Bob,
+                    (common, alice),
+                    (pending.common, bob),
+                    swap_protocol,
+                    from,
+                    context,
+                ),
+                (have, received, context) => {
+                    self.swap_data.insert(key, Pending {
+                        common: pending.common,
+                        params: have,
                         context,
-                    ));
-            }
-            Some(RoleDependentParams::Bob(have)) => {
-                self.events
-                    .push_back(BehaviourOutEvent::AlreadyHaveRoleParams {
-                        peer: from,
-                        have: RoleDependentParams::Bob(*have),
-                        received: RoleDependentParams::Bob(bob),
+                        since: pending.since,
                     });
-            }
+                    self.events
+                        .push_back(BehaviourOutEvent::AlreadyHaveRoleParams {
+                            peer: from,
+                            have,
+                            received,
+                        });
+                }
+            },
             None => {
-                self.swap_data
-                    .insert(common.clone(), RoleDependentParams::Bob(bob));
+                self.swap_data.insert(key, Pending {
+                    common,
+                    params: received,
+                    context: None,
+                    since: Instant::now(),
+                });
             }
         }
     }
 
-    fn bob_receive(
+    /// Both parties sent their parameters, agree on the quantity and emit the
+    /// resulting swap.
+    fn complete(
         &mut self,
-        from: PeerId,
-        common: CommonParams,
-        alice: AliceParams,
+        our_role: Role,
+        (alice_common, alice): (CommonParams, AliceParams),
+        (bob_common, bob): (CommonParams, BobParams),
         swap_protocol: SwapProtocol,
+        peer: PeerId,
+        context: C,
     ) {
-        match self.swap_data.get(&common) {
-            Some(RoleDependentParams::Alice(have)) => {
-                self.events
-                    .push_back(BehaviourOutEvent::AlreadyHaveRoleParams {
-                        peer: from,
-                        have: RoleDependentParams::Alice(*have),
-                        received: RoleDependentParams::Alice(alice),
-                    });
-            }
-            Some(RoleDependentParams::Bob(bob)) => {
-                // todo: remove unwrap
-                let context = self.context.get(&common).cloned().unwrap();
-                self.events
-                    .push_back(BehaviourOutEvent::new_executable_swap(
-                        Role::Bob,
-                        common,
-                        &alice,
-                        bob,
-                        swap_protocol,
-                        from,
-                        context,
-                    ))
+        let event = agree_on(alice_common, bob_common)
+            .and_then(|common| {
+                BehaviourOutEvent::new_executable_swap(
+                    our_role,
+                    common,
+                    &alice,
+                    &bob,
+                    swap_protocol,
+                    peer.clone(),
+                    context.clone(),
+                )
+                .map_err(SetupFailed::from)
+            })
+            .unwrap_or_else(|error| {
+                tracing::warn!("rejecting swap from {}: {}", peer, error);
+                BehaviourOutEvent::SetupFailed {
+                    peer,
+                    context,
+                    error,
+                }
+            });
+
+        self.events.push_back(event);
+    }
+
+    /// Give up on swaps the counterparty did not send its parameters for.
+    fn expire(&mut self, now: Instant) {
+        let events = &mut self.events;
+
+        self.swap_data.retain(|key, pending| {
+            if now.duration_since(pending.since) < SETUP_TIMEOUT {
+                return true;
             }
-            None => {
-                self.swap_data
-                    .insert(common.clone(), RoleDependentParams::Alice(alice));
+
+            match pending.context.take() {
+                Some(context) => events.push_back(BehaviourOutEvent::SetupFailed {
+                    peer: key.peer.clone(),
+                    context,
+                    error: SetupFailed::Timeout(SETUP_TIMEOUT),
+                }),
+                None => tracing::info!("ignoring swap proposed by {}, it expired", key.peer),
             }
-        }
+
+            false
+        });
     }
 
     fn poll<InEvent>(
@@ -294,9 +528,20 @@ impl<C: Clone + Send + 'static> SetupSwap<C> {
         _: &mut Context<'_>,
         _: &mut impl PollParameters,
     ) -> Poll<NetworkBehaviourAction<InEvent, BehaviourOutEvent<C>>> {
+        self.expire(Instant::now());
+
         if let Some(event) = self.events.pop_front() {
-            if let BehaviourOutEvent::ExecutableSwap(swap) = &event {
-                tracing::info!("Successfully set up swap with {}", swap.peer_id);
+            match &event {
+                BehaviourOutEvent::ExecutableSwap(swap) => {
+                    tracing::info!("Successfully set up swap with {}", swap.peer_id)
+                }
+                BehaviourOutEvent::ExecutableHethSwap(swap) => {
+                    tracing::info!("Successfully set up swap with {}", swap.peer_id)
+                }
+                BehaviourOutEvent::AlreadyHaveRoleParams { .. } => {}
+                BehaviourOutEvent::SetupFailed { peer, error, .. } => {
+                    tracing::info!("Failed to set up swap with {}: {}", peer, error)
+                }
             }
 
             return Poll::Ready(NetworkBehaviourAction::GenerateEvent(event));
@@ -306,6 +551,36 @@ impl<C: Clone + Send + 'static> SetupSwap<C> {
     }
 }
 
+/// Each party proposes the quantity it is able to fill, the smaller proposal
+/// is swapped.
+///
+/// Both proposals have to be at the same price. The party with the smaller
+/// proposal computed the ethereum quantity, which may be rounded to the wei.
+fn agree_on(alice: CommonParams, bob: CommonParams) -> Result<CommonParams, SetupFailed> {
+    let alice_sats = BigUint::from(alice.bitcoin.as_sat());
+    let bob_sats = BigUint::from(bob.bitcoin.as_sat());
+
+    // Compare alice_wei / alice_sats and bob_wei / bob_sats without dividing.
+    let alice_price = alice.ethereum.wei() * &bob_sats;
+    let bob_price = bob.ethereum.wei() * &alice_sats;
+    let difference = if alice_price > bob_price {
+        alice_price - bob_price
+    } else {
+        bob_price - alice_price
+    };
+    let rounding_tolerance = alice_sats.max(bob_sats);
+
+    if difference > rounding_tolerance {
+        return Err(SetupFailed::PriceMismatch);
+    }
+
+    if alice.bitcoin <= bob.bitcoin {
+        Ok(alice)
+    } else {
+        Ok(bob)
+    }
+}
+
 impl<C: Clone + Send + 'static>
     NetworkBehaviourEventProcess<RequestResponseEvent<Message<HbitHerc20Protocol>, ()>>
     for SetupSwap<C>
@@ -368,6 +643,64 @@ impl<C: Clone + Send + 'static>
     }
 }
 
+impl<C: Clone + Send + 'static>
+    NetworkBehaviourEventProcess<RequestResponseEvent<Message<HbitHethProtocol>, ()>>
+    for SetupSwap<C>
+{
+    fn inject_event(&mut self, event: RequestResponseEvent<Message<HbitHethProtocol>, ()>) {
+        match event {
+            RequestResponseEvent::Message {
+                peer,
+                message:
+                    RequestResponseMessage::Request {
+                        request: message, ..
+                    },
+            } => match message {
+                Message::Alice { alice, common, .. } => {
+                    self.bob_receive_hbit_heth(peer, common, alice)
+                }
+                Message::Bob { bob, common, .. } => self.alice_receive_hbit_heth(peer, common, bob),
+            },
+            RequestResponseEvent::OutboundFailure { error, .. } => {
+                tracing::warn!("outbound failure: {:?}", error);
+            }
+            RequestResponseEvent::InboundFailure { error, .. } => {
+                tracing::warn!("inbound failure: {:?}", error);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<C: Clone + Send + 'static>
+    NetworkBehaviourEventProcess<RequestResponseEvent<Message<HethHbitProtocol>, ()>>
+    for SetupSwap<C>
+{
+    fn inject_event(&mut self, event: RequestResponseEvent<Message<HethHbitProtocol>, ()>) {
+        match event {
+            RequestResponseEvent::Message {
+                peer,
+                message:
+                    RequestResponseMessage::Request {
+                        request: message, ..
+                    },
+            } => match message {
+                Message::Alice { alice, common, .. } => {
+                    self.bob_receive_heth_hbit(peer, common, alice)
+                }
+                Message::Bob { bob, common, .. } => self.alice_receive_heth_hbit(peer, common, bob),
+            },
+            RequestResponseEvent::OutboundFailure { error, .. } => {
+                tracing::warn!("outbound failure: {:?}", error);
+            }
+            RequestResponseEvent::InboundFailure { error, .. } => {
+                tracing::warn!("inbound failure: {:?}", error);
+            }
+            _ => {}
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct HbitHerc20Protocol;
 
@@ -386,12 +719,35 @@ impl ProtocolName for Herc20HbitProtocol {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HbitHethProtocol;
+
+impl ProtocolName for HbitHethProtocol {
+    fn protocol_name(&self) -> &[u8] {
+        b"/comit/setup-swap/hbit-heth/1.0.0"
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HethHbitProtocol;
+
+impl ProtocolName for HethHbitProtocol {
+    fn protocol_name(&self) -> &[u8] {
+        b"/comit/setup-swap/heth-hbit/1.0.0"
+    }
+}
+
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Codec<U: ProtocolName + Send + Clone>(PhantomData<U>);
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct CommonParams {
-    pub erc20: asset::Erc20,
+    /// The order of Alice that was matched.
+    pub alice_order_id: OrderId,
+    /// The order of Bob that was matched.
+    pub bob_order_id: OrderId,
+    #[serde(flatten)]
+    pub ethereum: EthereumAsset,
     #[serde(with = "asset::bitcoin::sats_as_string")]
     pub bitcoin: asset::Bitcoin,
     pub ethereum_absolute_expiry: u32,
@@ -400,6 +756,35 @@ pub struct CommonParams {
     pub bitcoin_network: ledger::Bitcoin,
 }
 
+/// The asset locked on the Ethereum side of the swap.
+///
+/// Serialized under the key `erc20` or `ether` respectively.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum EthereumAsset {
+    Erc20(asset::Erc20),
+    Ether(asset::Ether),
+}
+
+impl EthereumAsset {
+    fn wei(&self) -> BigUint {
+        match self {
+            EthereumAsset::Erc20(erc20) => BigUint::from(erc20.quantity.clone()),
+            EthereumAsset::Ether(ether) => BigUint::from_bytes_le(&ether.to_bytes()),
+        }
+    }
+
+    fn matches(&self, swap_protocol: SwapProtocol) -> bool {
+        matches!(
+            (self, swap_protocol),
+            (EthereumAsset::Erc20(_), SwapProtocol::HbitHerc20)
+                | (EthereumAsset::Erc20(_), SwapProtocol::Herc20Hbit)
+                | (EthereumAsset::Ether(_), SwapProtocol::HbitHeth)
+                | (EthereumAsset::Ether(_), SwapProtocol::HethHbit)
+        )
+    }
+}
+
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub struct AliceParams {
     pub ethereum_identity: identity::Ethereum,
@@ -517,11 +902,14 @@ where
 mod tests {
     use super::*;
     use crate::{
+        asset::ethereum::FromWei,
         network::test::{await_events_or_timeout, connect, new_swarm},
         Secret,
     };
     use bitcoin::secp256k1;
     use std::{future::Future, str::FromStr};
+    use tokio::sync::oneshot;
+    use uuid::Uuid;
 
     #[tokio::test]
     async fn given_bob_sends_when_alice_sends_one_then_swap_is_confirmed() {
@@ -542,7 +930,12 @@ mod tests {
         );
 
         let common = CommonParams {
-            erc20: asset::Erc20::new(identity::Ethereum::random(), asset::Erc20Quantity::zero()),
+            alice_order_id: OrderId::random(),
+            bob_order_id: OrderId::random(),
+            ethereum: EthereumAsset::Erc20(asset::Erc20::new(
+                identity::Ethereum::random(),
+                asset::Erc20Quantity::zero(),
+            )),
             bitcoin: asset::Bitcoin::from_sat(0),
             ethereum_absolute_expiry: 0,
             bitcoin_absolute_expiry: 0,
@@ -588,6 +981,321 @@ mod tests {
         .await;
     }
 
+    #[tokio::test]
+    async fn given_erc20_asset_when_setting_up_hbit_heth_swap_then_fails() {
+        let (mut swarm, _, _) = new_swarm(|_, _| SetupSwap::default());
+
+        let common = CommonParams {
+            alice_order_id: OrderId::random(),
+            bob_order_id: OrderId::random(),
+            ethereum: EthereumAsset::Erc20(asset::Erc20::new(
+                identity::Ethereum::random(),
+                asset::Erc20Quantity::zero(),
+            )),
+            bitcoin: asset::Bitcoin::from_sat(0),
+            ethereum_absolute_expiry: 0,
+            bitcoin_absolute_expiry: 0,
+            ethereum_chain_id: ChainId::GETH_DEV,
+            bitcoin_network: ledger::Bitcoin::Regtest,
+        };
+        let bob = BobParams {
+            ethereum_identity: identity::Ethereum::random(),
+            bitcoin_identity: identity::Bitcoin::from(
+                secp256k1::PublicKey::from_str(
+                    "02c2a8efce029526d364c2cf39d89e3cdda05e5df7b2cbfc098b4e3d02b70b5275",
+                )
+                .unwrap(),
+            ),
+        };
+
+        let result = swarm.send(
+            &PeerId::random(),
+            RoleDependentParams::Bob(bob),
+            common,
+            SwapProtocol::HbitHeth,
+            1,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn given_alice_proposes_less_than_bob_then_swap_is_set_up_with_alices_quantity() {
+        let (mut alice_swarm, _, alice_id) = new_swarm(|_, _| SetupSwap::default());
+        let (mut bob_swarm, _, bob_id) = new_swarm(|_, _| SetupSwap::default());
+        connect(&mut alice_swarm, &mut bob_swarm).await;
+
+        bob_swarm
+            .send(
+                &alice_id,
+                bob_params(),
+                common_params(100_000_000, 10_000),
+                SwapProtocol::HbitHerc20,
+                2,
+            )
+            .expect("bob failed to send");
+        alice_swarm
+            .send(
+                &bob_id,
+                alice_params(),
+                common_params(50_000_000, 10_000),
+                SwapProtocol::HbitHerc20,
+                1,
+            )
+            .expect("alice failed to send");
+
+        match await_events_or_timeout(alice_swarm.next(), bob_swarm.next()).await {
+            (
+                BehaviourOutEvent::ExecutableSwap(alice_swap),
+                BehaviourOutEvent::ExecutableSwap(bob_swap),
+            ) => {
+                assert_eq!(alice_swap.hbit, bob_swap.hbit);
+                assert_eq!(alice_swap.herc20, bob_swap.herc20);
+                assert_eq!(alice_swap.hbit.asset, asset::Bitcoin::from_sat(50_000_000));
+            }
+            events => panic!("expected both parties to confirm the swap: {:?}", events),
+        }
+    }
+
+    #[tokio::test]
+    async fn given_several_alices_with_the_same_expiries_then_all_swaps_are_set_up() {
+        let (mut first_alice_swarm, _, first_alice_id) = new_swarm(|_, _| SetupSwap::default());
+        let (mut second_alice_swarm, _, second_alice_id) = new_swarm(|_, _| SetupSwap::default());
+        let (mut bob_swarm, _, bob_id) = new_swarm(|_, _| SetupSwap::default());
+        connect(&mut first_alice_swarm, &mut bob_swarm).await;
+        connect(&mut second_alice_swarm, &mut bob_swarm).await;
+
+        // Bob already reserved 50_000_000 sats for the first Alice, he only
+        // offers the rest of his order to the second one.
+        bob_swarm
+            .send(
+                &first_alice_id,
+                bob_params(),
+                common_params(50_000_000, 10_000),
+                SwapProtocol::HbitHerc20,
+                1,
+            )
+            .expect("bob failed to send");
+        bob_swarm
+            .send(
+                &second_alice_id,
+                bob_params(),
+                common_params(50_000_000, 10_000),
+                SwapProtocol::HbitHerc20,
+                2,
+            )
+            .expect("bob failed to send");
+        first_alice_swarm
+            .send(
+                &bob_id,
+                alice_params(),
+                common_params(50_000_000, 10_000),
+                SwapProtocol::HbitHerc20,
+                3,
+            )
+            .expect("alice failed to send");
+        second_alice_swarm
+            .send(
+                &bob_id,
+                alice_params(),
+                common_params(80_000_000, 10_000),
+                SwapProtocol::HbitHerc20,
+                4,
+            )
+            .expect("alice failed to send");
+
+        let first_alice_event = spawn_swarm(first_alice_swarm);
+        let second_alice_event = spawn_swarm(second_alice_swarm);
+        let (alice_events, bob_events) = await_events_or_timeout(
+            futures::future::join(first_alice_event, second_alice_event),
+            async { vec![bob_swarm.next().await, bob_swarm.next().await] },
+        )
+        .await;
+
+        for event in vec![alice_events.0.unwrap(), alice_events.1.unwrap()]
+            .into_iter()
+            .chain(bob_events)
+        {
+            match event {
+                BehaviourOutEvent::ExecutableSwap(swap) => {
+                    assert_eq!(swap.hbit.asset, asset::Bitcoin::from_sat(50_000_000))
+                }
+                event => panic!("expected the swap to be confirmed: {:?}", event),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn given_two_orders_matched_with_the_same_peer_then_both_swaps_are_set_up() {
+        let (mut alice_swarm, _, alice_id) = new_swarm(|_, _| SetupSwap::default());
+        let (mut bob_swarm, _, bob_id) = new_swarm(|_, _| SetupSwap::default());
+        connect(&mut alice_swarm, &mut bob_swarm).await;
+
+        let first = common_params(50_000_000, 10_000);
+        let second = CommonParams {
+            bob_order_id: OrderId::random(),
+            ..common_params(30_000_000, 10_000)
+        };
+
+        bob_swarm
+            .send(
+                &alice_id,
+                bob_params(),
+                first.clone(),
+                SwapProtocol::HbitHerc20,
+                1,
+            )
+            .expect("bob failed to send");
+        bob_swarm
+            .send(
+                &alice_id,
+                bob_params(),
+                second.clone(),
+                SwapProtocol::HbitHerc20,
+                2,
+            )
+            .expect("bob failed to send");
+        alice_swarm
+            .send(&bob_id, alice_params(), first, SwapProtocol::HbitHerc20, 3)
+            .expect("alice failed to send");
+        alice_swarm
+            .send(&bob_id, alice_params(), second, SwapProtocol::HbitHerc20, 4)
+            .expect("alice failed to send");
+
+        let alice_events = spawn_swarm(alice_swarm);
+        let (_, bob_events) = await_events_or_timeout(alice_events, async {
+            vec![bob_swarm.next().await, bob_swarm.next().await]
+        })
+        .await;
+
+        let mut quantities = bob_events
+            .into_iter()
+            .map(|event| match event {
+                BehaviourOutEvent::ExecutableSwap(swap) => swap.hbit.asset.as_sat(),
+                event => panic!("expected the swap to be confirmed: {:?}", event),
+            })
+            .collect::<Vec<_>>();
+        quantities.sort();
+
+        assert_eq!(quantities, vec![30_000_000, 50_000_000]);
+    }
+
+    #[tokio::test]
+    async fn given_proposals_at_different_prices_then_setup_fails() {
+        let (mut alice_swarm, _, alice_id) = new_swarm(|_, _| SetupSwap::default());
+        let (mut bob_swarm, _, bob_id) = new_swarm(|_, _| SetupSwap::default());
+        connect(&mut alice_swarm, &mut bob_swarm).await;
+
+        bob_swarm
+            .send(
+                &alice_id,
+                bob_params(),
+                common_params(100_000_000, 10_000),
+                SwapProtocol::HbitHerc20,
+                2,
+            )
+            .expect("bob failed to send");
+        alice_swarm
+            .send(
+                &bob_id,
+                alice_params(),
+                common_params(100_000_000, 9_000),
+                SwapProtocol::HbitHerc20,
+                1,
+            )
+            .expect("alice failed to send");
+
+        match await_events_or_timeout(alice_swarm.next(), bob_swarm.next()).await {
+            (
+                BehaviourOutEvent::SetupFailed {
+                    context: 1,
+                    error: SetupFailed::PriceMismatch,
+                    ..
+                },
+                BehaviourOutEvent::SetupFailed {
+                    context: 2,
+                    error: SetupFailed::PriceMismatch,
+                    ..
+                },
+            ) => {}
+            events => panic!("expected both parties to fail: {:?}", events),
+        }
+    }
+
+    #[test]
+    fn rounding_of_the_ethereum_quantity_is_tolerated() {
+        let alice = common_params(3, 10);
+        let mut bob = common_params(7, 10);
+        bob.ethereum = EthereumAsset::Erc20(asset::Erc20::new(
+            identity::Ethereum::from([0u8; 20]),
+            asset::Erc20Quantity::from_wei(69u32),
+        ));
+
+        let agreed = agree_on(alice.clone(), bob).unwrap();
+
+        assert_eq!(agreed, alice);
+    }
+
+    /// Swap the given quantity at a price of `wei_per_sat`.
+    fn common_params(sats: u64, wei_per_sat: u64) -> CommonParams {
+        CommonParams {
+            alice_order_id: OrderId::from(Uuid::nil()),
+            bob_order_id: OrderId::from(Uuid::nil()),
+            ethereum: EthereumAsset::Erc20(asset::Erc20::new(
+                identity::Ethereum::from([0u8; 20]),
+                asset::Erc20Quantity::from_wei(sats)
+                    .checked_mul(wei_per_sat)
+                    .unwrap(),
+            )),
+            bitcoin: asset::Bitcoin::from_sat(sats),
+            ethereum_absolute_expiry: 0,
+            bitcoin_absolute_expiry: 0,
+            ethereum_chain_id: ChainId::GETH_DEV,
+            bitcoin_network: ledger::Bitcoin::Regtest,
+        }
+    }
+
+    fn alice_params() -> RoleDependentParams {
+        RoleDependentParams::Alice(AliceParams {
+            ethereum_identity: identity::Ethereum::random(),
+            bitcoin_identity: bitcoin_identity(),
+            secret_hash: SecretHash::new(Secret::from(*b"hello world, you are beautiful!!")),
+        })
+    }
+
+    fn bob_params() -> RoleDependentParams {
+        RoleDependentParams::Bob(BobParams {
+            ethereum_identity: identity::Ethereum::random(),
+            bitcoin_identity: bitcoin_identity(),
+        })
+    }
+
+    fn bitcoin_identity() -> identity::Bitcoin {
+        identity::Bitcoin::from(
+            secp256k1::PublicKey::from_str(
+                "02c2a8efce029526d364c2cf39d89e3cdda05e5df7b2cbfc098b4e3d02b70b5275",
+            )
+            .unwrap(),
+        )
+    }
+
+    /// Keep polling the swarm in the background so it delivers our messages
+    /// after it emitted its first event.
+    fn spawn_swarm(
+        mut swarm: libp2p::Swarm<SetupSwap<i32>>,
+    ) -> oneshot::Receiver<BehaviourOutEvent<i32>> {
+        let (sender, receiver) = oneshot::channel();
+
+        tokio::spawn(async move {
+            let _ = sender.send(swarm.next().await);
+            loop {
+                swarm.next().await;
+            }
+        });
+
+        receiver
+    }
+
     async fn assert_both_confirmed<C: PartialEq + Debug>(
         alice_event: impl Future<Output = BehaviourOutEvent<C>>,
         bob_event: impl Future<Output = BehaviourOutEvent<C>>,
@@ -0,0 +1,153 @@
+use futures::{AsyncRead, AsyncWrite};
+use libp2p::{
+    core::{
+        identity,
+        muxing::StreamMuxerBox,
+        transport::{Boxed, Transport},
+        upgrade::{SelectUpgrade, Version},
+    },
+    dns::DnsConfig,
+    mplex::MplexConfig,
+    multiaddr::Protocol,
+    noise::{self, NoiseConfig, X25519Spec},
+    tcp::TokioTcpConfig,
+    yamux, Multiaddr, PeerId,
+};
+use libp2p_tokio_socks5::Socks5TokioTcpConfig;
+use std::collections::HashMap;
+
+/// The local port Tor forwards connections to our hidden service to.
+pub const PORT: u16 = 9939;
+
+/// The port Tor listens on for SOCKS5 connections by default.
+const DEFAULT_SOCKS5_PORT: u16 = 9050;
+
+pub type ComitTransport = Boxed<(PeerId, StreamMuxerBox)>;
+
+/// Builds the libp2p transport of a COMIT node with the following features:
+/// - TCP connection, over the Tor network for onion addresses
+/// - DNS name resolution
+/// - authentication via noise
+/// - multiplexing via yamux or mplex
+///
+/// If we listen on an onion address or a SOCKS5 port is configured, all
+/// connections go through the Tor network so we do not reveal our IP address.
+pub fn build(
+    keypair: identity::Keypair,
+    listen: &[Multiaddr],
+    tor_socks5_port: Option<u16>,
+) -> anyhow::Result<ComitTransport> {
+    let onion_map = onion_map(listen);
+    let tor_only = tor_socks5_port.is_some() || !onion_map.is_empty();
+
+    let socks = Socks5TokioTcpConfig::new(tor_socks5_port.unwrap_or(DEFAULT_SOCKS5_PORT))
+        .nodelay(true)
+        .onion_map(onion_map);
+
+    if tor_only {
+        upgrade(DnsConfig::new(socks)?, keypair)
+    } else {
+        // TCP does not support onion addresses, those are left to Tor.
+        let transport = TokioTcpConfig::new().nodelay(true).or_transport(socks);
+        upgrade(DnsConfig::new(transport)?, keypair)
+    }
+}
+
+fn upgrade<T>(transport: T, keypair: identity::Keypair) -> anyhow::Result<ComitTransport>
+where
+    T: Transport + Clone + Send + Sync + 'static,
+    T::Output: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    T::Error: Send + Sync + 'static,
+    T::Listener: Send + 'static,
+    T::ListenerUpgrade: Send + 'static,
+    T::Dial: Send + 'static,
+{
+    let dh_keys = noise::Keypair::<X25519Spec>::new().into_authentic(&keypair)?;
+    let noise = NoiseConfig::xx(dh_keys).into_authenticated();
+
+    let transport = transport
+        .upgrade(Version::V1)
+        .authenticate(noise)
+        .multiplex(SelectUpgrade::new(
+            yamux::Config::default(),
+            MplexConfig::new(),
+        ))
+        .map(|(peer, muxer), _| (peer, StreamMuxerBox::new(muxer)))
+        .boxed();
+
+    Ok(transport)
+}
+
+/// Maps the onion address we listen on to the local port Tor forwards to.
+///
+/// It only makes sense to listen on a single address when using Tor.
+fn onion_map(listen: &[Multiaddr]) -> HashMap<Multiaddr, u16> {
+    let mut map = HashMap::new();
+
+    if let [addr] = listen {
+        if is_onion(addr.clone()) {
+            map.insert(addr.clone(), PORT);
+        }
+    }
+
+    map
+}
+
+/// True if `addr` is a Tor onion address v2 or v3.
+pub fn is_onion(mut addr: Multiaddr) -> bool {
+    match addr.pop() {
+        Some(Protocol::Onion(..)) => true,
+        Some(Protocol::Onion3(_)) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_onion_works_positive_v3() {
+        let addr = "/onion3/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd:1234"
+            .parse()
+            .expect("failed to parse multiaddr");
+        assert!(is_onion(addr))
+    }
+
+    #[test]
+    fn is_onion_works_positive_v2() {
+        let addr = "/onion/aaimaq4ygg2iegci:80"
+            .parse()
+            .expect("failed to parse multiaddr");
+        assert!(is_onion(addr))
+    }
+
+    #[test]
+    fn is_onion_works_negative() {
+        let addr = "/ip4/127.0.0.1/tcp/1234"
+            .parse()
+            .expect("failed to parse multiaddr");
+        assert!(!is_onion(addr))
+    }
+
+    #[test]
+    fn single_onion_listen_address_is_mapped_to_local_port() {
+        let onion: Multiaddr =
+            "/onion3/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd:1234"
+                .parse()
+                .expect("failed to parse multiaddr");
+
+        let map = onion_map(&[onion.clone()]);
+
+        assert_eq!(map.get(&onion), Some(&PORT));
+    }
+
+    #[test]
+    fn tcp_listen_address_is_not_mapped() {
+        let tcp: Multiaddr = "/ip4/0.0.0.0/tcp/9939"
+            .parse()
+            .expect("failed to parse multiaddr");
+
+        assert!(onion_map(&[tcp]).is_empty());
+    }
+}
@@ -5,7 +5,7 @@ use crate::{
     Network, Role,
 };
 use serde::{Deserialize, Serialize};
-use std::{fmt::Display, marker::PhantomData, str::FromStr};
+use std::{convert::TryFrom, fmt, fmt::Display, marker::PhantomData, str::FromStr};
 use time::{Duration, OffsetDateTime};
 use uuid::Uuid;
 
@@ -42,9 +42,110 @@ impl From<Uuid> for OrderId {
     }
 }
 
+/// A trading pair of bitcoin, the base currency, against ether or an ERC20
+/// token, the quote currency.
+///
+/// The token is identified by its ticker symbol. Both parties of a trade
+/// resolve the symbol to a token contract through their own configuration, an
+/// order thus only matches orders of the same trading pair. `ETH` denotes
+/// ether, which is locked with [`heth`](crate::heth) instead.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TradingPair {
+    quote: String,
+}
+
+impl TradingPair {
+    /// Constructs the trading pair of bitcoin against the token with the given
+    /// ticker symbol.
+    ///
+    /// Symbols are case-insensitive and normalized to upper case.
+    pub fn btc_against(symbol: &str) -> Result<Self, InvalidTradingPair> {
+        let is_valid = !symbol.is_empty() && symbol.chars().all(|c| c.is_ascii_alphanumeric());
+
+        if !is_valid {
+            return Err(InvalidTradingPair(format!("BTC/{}", symbol)));
+        }
+
+        Ok(Self {
+            quote: symbol.to_ascii_uppercase(),
+        })
+    }
+
+    pub fn btc_dai() -> Self {
+        Self {
+            quote: String::from("DAI"),
+        }
+    }
+
+    pub fn btc_eth() -> Self {
+        Self {
+            quote: String::from("ETH"),
+        }
+    }
+
+    /// Whether the quote currency is ether rather than a token.
+    pub fn is_ether(&self) -> bool {
+        self.quote == "ETH"
+    }
+
+    /// The ticker symbol of the quote currency.
+    pub fn quote(&self) -> &str {
+        &self.quote
+    }
+}
+
+impl Display for TradingPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BTC/{}", self.quote)
+    }
+}
+
+/// Parses trading pairs in the form `BTC/DAI`.
+///
+/// `BTC-DAI` is accepted as well because it can be used as part of a URL.
+impl FromStr for TradingPair {
+    type Err = InvalidTradingPair;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, |c| c == '/' || c == '-');
+
+        match (parts.next(), parts.next()) {
+            (Some(base), Some(quote)) if base.eq_ignore_ascii_case("BTC") => {
+                Self::btc_against(quote).map_err(|_| InvalidTradingPair(s.to_owned()))
+            }
+            _ => Err(InvalidTradingPair(s.to_owned())),
+        }
+    }
+}
+
+impl TryFrom<String> for TradingPair {
+    type Error = InvalidTradingPair;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<TradingPair> for String {
+    fn from(pair: TradingPair) -> Self {
+        pair.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{0} is not a trading pair of BTC against ether or an ERC20 token")]
+pub struct InvalidTradingPair(String);
+
+/// An order to trade bitcoin against ether or an ERC20 token.
+///
+/// The price is given in the smallest unit of the quote currency per bitcoin,
+/// i.e. in wei for ether, hence it can express fractions of the smallest unit
+/// per satoshi.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct BtcDaiOrder {
+pub struct BtcErc20Order {
     pub id: OrderId,
+    pub trading_pair: TradingPair,
     pub position: Position,
     pub swap_protocol: SwapProtocol,
     pub created_at: OffsetDateTime,
@@ -52,7 +153,10 @@ pub struct BtcDaiOrder {
     pub price: Price<Bitcoin, Erc20Quantity>,
 }
 
-impl BtcDaiOrder {
+impl BtcErc20Order {
+    /// Constructs a buy order for BTC/DAI, see
+    /// [`with_trading_pair`](BtcErc20Order::with_trading_pair) for other
+    /// tokens.
     pub fn buy(
         quantity: Quantity<Bitcoin>,
         price: Price<Bitcoin, Erc20Quantity>,
@@ -61,6 +165,9 @@ impl BtcDaiOrder {
         Self::new(Position::Buy, quantity, price, swap_protocol)
     }
 
+    /// Constructs a sell order for BTC/DAI, see
+    /// [`with_trading_pair`](BtcErc20Order::with_trading_pair) for other
+    /// tokens.
     pub fn sell(
         quantity: Quantity<Bitcoin>,
         price: Price<Bitcoin, Erc20Quantity>,
@@ -74,9 +181,10 @@ impl BtcDaiOrder {
         quantity: Quantity<Bitcoin>,
         price: Price<Bitcoin, Erc20Quantity>,
         swap_protocol: SwapProtocol,
-    ) -> BtcDaiOrder {
+    ) -> BtcErc20Order {
         Self {
             id: OrderId::random(),
+            trading_pair: TradingPair::btc_dai(),
             position,
             quantity,
             price,
@@ -85,6 +193,13 @@ impl BtcDaiOrder {
         }
     }
 
+    pub fn with_trading_pair(self, trading_pair: TradingPair) -> Self {
+        Self {
+            trading_pair,
+            ..self
+        }
+    }
+
     pub fn quote(&self) -> Erc20Quantity {
         self.quantity * self.price.clone()
     }
@@ -132,29 +247,35 @@ pub struct Price<B, Q> {
 }
 
 impl Price<Bitcoin, Erc20Quantity> {
-    /// Constructs a new instance of Price where the rate is given in WEI/SAT.
+    /// Constructs a new instance of Price where the rate is given in WEI/BTC.
     ///
     /// This is how we store the data internally and hence we don't need to do
-    /// any conversions.
-    pub fn from_wei_per_sat(rate: Erc20Quantity) -> Self {
+    /// any conversions. In contrast to a rate in WEI/SAT, this can express
+    /// prices of tokens with fewer than 8 decimals to the smallest unit.
+    pub fn from_wei_per_btc(rate: Erc20Quantity) -> Self {
         Price {
             inner: rate,
             _base: PhantomData,
         }
     }
 
-    pub fn wei_per_sat(&self) -> Erc20Quantity {
-        self.inner.clone()
+    /// Constructs a new instance of Price where the rate is given in WEI/SAT.
+    pub fn from_wei_per_sat(rate: Erc20Quantity) -> Self {
+        let rate = rate
+            .checked_mul(SATS_PER_BTC)
+            .expect("the price of bitcoin to not go through the roof");
+
+        Self::from_wei_per_btc(rate)
     }
 
     pub fn wei_per_btc(&self) -> Erc20Quantity {
-        self.inner
-            .clone()
-            .checked_mul(100_000_000)
-            .expect("the price of bitcoin to not go through the roof")
+        self.inner.clone()
     }
 }
 
+/// The number of satoshis in one bitcoin.
+pub(crate) const SATS_PER_BTC: u64 = 100_000_000;
+
 #[cfg(test)]
 pub fn dai_per_btc(dai: u64) -> Price<Bitcoin, Erc20Quantity> {
     use crate::asset::ethereum::TryFromWei;
@@ -169,17 +290,19 @@ pub fn dai_per_btc(dai: u64) -> Price<Bitcoin, Erc20Quantity> {
 }
 
 #[cfg(test)]
-impl BtcDaiOrder {
+impl BtcErc20Order {
     pub fn new_test(
         id: OrderId,
+        trading_pair: TradingPair,
         position: Position,
         quantity: Quantity<Bitcoin>,
         price: Price<Bitcoin, Erc20Quantity>,
         swap_protocol: SwapProtocol,
         created_at: OffsetDateTime,
-    ) -> BtcDaiOrder {
+    ) -> BtcErc20Order {
         Self {
             id,
+            trading_pair,
             position,
             quantity,
             price,
@@ -235,6 +358,8 @@ pub enum Position {
 /// expire.
 /// - X + herc20_expiry_offset = absolute timestamp for when the
 /// [`herc20`](crate::herc20) lock will expire.
+/// - X + heth_expiry_offset = absolute timestamp for when the
+/// [`heth`](crate::heth) lock will expire.
 ///
 /// Note that an `expiry_offset` is different from the actual locking duration
 /// as the two locking protocols start a different points in time.
@@ -256,18 +381,26 @@ pub enum SwapProtocol {
         herc20_expiry_offset: AlphaOffset,
         hbit_expiry_offset: BetaOffset,
     },
+    HbitHeth {
+        hbit_expiry_offset: AlphaOffset,
+        heth_expiry_offset: BetaOffset,
+    },
+    HethHbit {
+        heth_expiry_offset: AlphaOffset,
+        hbit_expiry_offset: BetaOffset,
+    },
 }
 
 impl SwapProtocol {
     pub fn role(&self, position: Position) -> Role {
         match position {
             Position::Buy => match self {
-                SwapProtocol::HbitHerc20 { .. } => Role::Bob,
-                SwapProtocol::Herc20Hbit { .. } => Role::Alice,
+                SwapProtocol::HbitHerc20 { .. } | SwapProtocol::HbitHeth { .. } => Role::Bob,
+                SwapProtocol::Herc20Hbit { .. } | SwapProtocol::HethHbit { .. } => Role::Alice,
             },
             Position::Sell => match self {
-                SwapProtocol::HbitHerc20 { .. } => Role::Alice,
-                SwapProtocol::Herc20Hbit { .. } => Role::Bob,
+                SwapProtocol::HbitHerc20 { .. } | SwapProtocol::HbitHeth { .. } => Role::Alice,
+                SwapProtocol::Herc20Hbit { .. } | SwapProtocol::HethHbit { .. } => Role::Bob,
             },
         }
     }
@@ -275,12 +408,12 @@ impl SwapProtocol {
     pub fn position(&self, role: Role) -> Position {
         match role {
             Role::Alice => match self {
-                SwapProtocol::HbitHerc20 { .. } => Position::Sell,
-                SwapProtocol::Herc20Hbit { .. } => Position::Buy,
+                SwapProtocol::HbitHerc20 { .. } | SwapProtocol::HbitHeth { .. } => Position::Sell,
+                SwapProtocol::Herc20Hbit { .. } | SwapProtocol::HethHbit { .. } => Position::Buy,
             },
             Role::Bob => match self {
-                SwapProtocol::HbitHerc20 { .. } => Position::Buy,
-                SwapProtocol::Herc20Hbit { .. } => Position::Sell,
+                SwapProtocol::HbitHerc20 { .. } | SwapProtocol::HbitHeth { .. } => Position::Buy,
+                SwapProtocol::Herc20Hbit { .. } | SwapProtocol::HethHbit { .. } => Position::Sell,
             },
         }
     }
@@ -293,10 +426,18 @@ impl SwapProtocol {
             SwapProtocol::HbitHerc20 {
                 hbit_expiry_offset, ..
             } => Duration::from(*hbit_expiry_offset),
+            SwapProtocol::HethHbit {
+                hbit_expiry_offset, ..
+            } => Duration::from(*hbit_expiry_offset),
+            SwapProtocol::HbitHeth {
+                hbit_expiry_offset, ..
+            } => Duration::from(*hbit_expiry_offset),
         }
     }
 
-    pub fn herc20_expiry_offset(&self) -> Duration {
+    /// The expiry offset of the lock on Ethereum, be it
+    /// [`herc20`](crate::herc20) or [`heth`](crate::heth).
+    pub fn ethereum_expiry_offset(&self) -> Duration {
         match self {
             SwapProtocol::Herc20Hbit {
                 herc20_expiry_offset,
@@ -306,6 +447,21 @@ impl SwapProtocol {
                 herc20_expiry_offset,
                 ..
             } => Duration::from(*herc20_expiry_offset),
+            SwapProtocol::HethHbit {
+                heth_expiry_offset, ..
+            } => Duration::from(*heth_expiry_offset),
+            SwapProtocol::HbitHeth {
+                heth_expiry_offset, ..
+            } => Duration::from(*heth_expiry_offset),
+        }
+    }
+
+    /// Whether ether is locked with [`heth`](crate::heth) rather than a token
+    /// with [`herc20`](crate::herc20).
+    pub fn is_heth(&self) -> bool {
+        match self {
+            SwapProtocol::HbitHeth { .. } | SwapProtocol::HethHbit { .. } => true,
+            SwapProtocol::HbitHerc20 { .. } | SwapProtocol::Herc20Hbit { .. } => false,
         }
     }
 
@@ -331,12 +487,47 @@ impl SwapProtocol {
             }
         }
     }
+
+    /// Constructs the swap protocol for an order of the given trading pair,
+    /// orders of BTC/ETH lock ether with [`heth`](crate::heth), all others lock
+    /// their token with [`herc20`](crate::herc20).
+    pub fn for_trading_pair(
+        trading_pair: &TradingPair,
+        role: Role,
+        position: Position,
+        network: Network,
+    ) -> Self {
+        if !trading_pair.is_ether() {
+            return Self::new(role, position, network);
+        }
+
+        match (role, position) {
+            (Role::Bob, Position::Buy) | (Role::Alice, Position::Sell) => {
+                let (hbit_expiry_offset, heth_expiry_offset) =
+                    expiries::expiry_offsets_hbit_heth(network);
+
+                SwapProtocol::HbitHeth {
+                    hbit_expiry_offset,
+                    heth_expiry_offset,
+                }
+            }
+            (Role::Alice, Position::Buy) | (Role::Bob, Position::Sell) => {
+                let (heth_expiry_offset, hbit_expiry_offset) =
+                    expiries::expiry_offsets_heth_hbit(network);
+
+                SwapProtocol::HethHbit {
+                    heth_expiry_offset,
+                    hbit_expiry_offset,
+                }
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::proptest;
+    use crate::{asset::ethereum::FromWei, proptest};
 
     proptest::proptest! {
         #[test]
@@ -360,6 +551,18 @@ mod tests {
         }
     }
 
+    proptest::proptest! {
+        #[test]
+        fn btc_eth_orders_lock_ether_with_heth(role in proptest::role(), position in proptest::order::position()) {
+            let swap_protocol = SwapProtocol::for_trading_pair(&TradingPair::btc_eth(), role, position, Network::Main);
+            let other_swap_protocol = SwapProtocol::for_trading_pair(&TradingPair::btc_dai(), role, position, Network::Main);
+
+            assert!(swap_protocol.is_heth());
+            assert!(!other_swap_protocol.is_heth());
+            assert_eq!(swap_protocol.role(position), role);
+        }
+    }
+
     #[test]
     fn dai_per_btc_turns_into_wei_per_sat() {
         // 1 BTC : 9_000 DAI = 1 BTC : 9_000_000_000_000_000_000_000 WEI = 100_000_000
@@ -368,4 +571,40 @@ mod tests {
 
         assert_eq!(dai_per_btc(9000), Price::from_wei_per_sat(wei_per_sat))
     }
+
+    #[test]
+    fn price_can_be_a_fraction_of_the_smallest_unit_per_sat() {
+        // 12_345.67 of a token with 6 decimals per BTC is 123.4567 of its
+        // smallest unit per SAT.
+        let price = Price::from_wei_per_btc(Erc20Quantity::from_wei(12_345_670_000u64));
+        let swap_protocol = SwapProtocol::new(Role::Alice, Position::Sell, Network::Main);
+        let order = BtcErc20Order::sell(btc(0.5), price, swap_protocol);
+
+        assert_eq!(order.quote(), Erc20Quantity::from_wei(6_172_835_000u64));
+    }
+
+    #[test]
+    fn trading_pair_is_parsed_from_either_separator() {
+        let usdc = TradingPair::btc_against("usdc").unwrap();
+
+        assert_eq!(TradingPair::from_str("BTC/USDC").unwrap(), usdc);
+        assert_eq!(TradingPair::from_str("btc-usdc").unwrap(), usdc);
+        assert_eq!(usdc.to_string(), "BTC/USDC");
+    }
+
+    #[test]
+    fn trading_pair_must_have_bitcoin_as_base_currency() {
+        assert!(TradingPair::from_str("ETH/DAI").is_err());
+        assert!(TradingPair::from_str("BTC/").is_err());
+        assert!(TradingPair::from_str("BTC/D/AI").is_err());
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn trading_pair_roundtrips_through_string(trading_pair in proptest::order::trading_pair()) {
+            let round_tripped = TradingPair::from_str(&trading_pair.to_string()).unwrap();
+
+            assert_eq!(trading_pair, round_tripped);
+        }
+    }
 }
@@ -1,6 +1,6 @@
 use crate::{
-    asset, asset::Erc20Quantity, order::SwapProtocol, BtcDaiOrder, OrderId, Position, Price,
-    Quantity,
+    asset, asset::Erc20Quantity, order::SwapProtocol, BtcErc20Order, OrderId, Position, Price,
+    Quantity, TradingPair,
 };
 use anyhow::Result;
 use libp2p::PeerId;
@@ -16,9 +16,14 @@ use time::OffsetDateTime;
 /// A collection of orders gathered from several makers.
 #[derive(Debug)]
 pub struct OrderPool {
-    inner: HashMap<PeerId, HashMap<OrderId, BtcDaiOrder>>,
+    inner: HashMap<PeerId, HashMap<OrderId, BtcErc20Order>>,
 
     reserved_quantities: HashMap<OrderId, asset::Bitcoin>,
+    /// The quantity reserved for each match, keyed by our and their order.
+    ///
+    /// Allows us to release exactly what we reserved once the swap setup for
+    /// a match completes or fails.
+    reservations: HashMap<(OrderId, OrderId), asset::Bitcoin>,
     /// Our own id.
     ///
     /// Allows us to filter out our own orders.
@@ -37,8 +42,8 @@ pub struct OrderPool {
 /// quantity of any of the orders change.
 #[derive(Debug, PartialEq, Eq, Hash)]
 struct NoMatch {
-    ours: BtcDaiOrder,
-    theirs: BtcDaiOrder,
+    ours: BtcErc20Order,
+    theirs: BtcErc20Order,
 }
 
 impl OrderPool {
@@ -46,6 +51,7 @@ impl OrderPool {
         Self {
             inner: Default::default(),
             reserved_quantities: Default::default(),
+            reservations: Default::default(),
             me,
             no_match_cache: LruCache::new(100), /* cap this at a 100 entries to avoid unbounded
                                                  * memory growth */
@@ -62,7 +68,7 @@ impl OrderPool {
         None
     }
 
-    pub fn publish(&mut self, order: BtcDaiOrder) {
+    pub fn publish(&mut self, order: BtcErc20Order) {
         let id = order.id;
         self.inner
             .entry(self.me.clone())
@@ -76,7 +82,7 @@ impl OrderPool {
     ///
     /// This replaces all current orders of this peer with the newly received
     /// ones.
-    pub fn receive(&mut self, maker: PeerId, orders: Vec<BtcDaiOrder>) {
+    pub fn receive(&mut self, maker: PeerId, orders: Vec<BtcErc20Order>) {
         let map = HashMap::from_iter(orders.into_iter().map(|o| (o.id, o)));
 
         self.inner.insert(maker, map);
@@ -86,28 +92,68 @@ impl OrderPool {
         self.inner.remove(maker);
     }
 
+    /// Remove the orders of the given trading pair this maker published,
+    /// orders of other trading pairs are kept.
+    pub fn remove_trading_pair_from(&mut self, maker: &PeerId, trading_pair: &TradingPair) {
+        if let Entry::Occupied(mut entry) = self.inner.entry(maker.clone()) {
+            entry
+                .get_mut()
+                .retain(|_, order| &order.trading_pair != trading_pair);
+
+            if entry.get().is_empty() {
+                entry.remove();
+            }
+        }
+    }
+
+    pub fn has_orders_from(&self, maker: &PeerId) -> bool {
+        self.inner
+            .get(maker)
+            .map_or(false, |orders| !orders.is_empty())
+    }
+
     pub fn clear_own_orders(&mut self) {
         self.inner.remove(&self.me);
     }
 
+    /// Replace all our orders with the given ones at once, other peers never
+    /// see a mix of old and new orders.
+    pub fn replace_own_orders(&mut self, orders: Vec<BtcErc20Order>) {
+        if orders.is_empty() {
+            self.clear_own_orders();
+            return;
+        }
+
+        let ids = orders.iter().map(|o| o.id).collect::<Vec<_>>();
+        let map = HashMap::from_iter(orders.into_iter().map(|o| (o.id, o)));
+        self.inner.insert(self.me.clone(), map);
+
+        tracing::info!("published orders {:?}", ids);
+    }
+
+    pub fn clear_own_orders_of(&mut self, trading_pair: &TradingPair) {
+        let me = self.me.clone();
+        self.remove_trading_pair_from(&me, trading_pair);
+    }
+
     pub fn cancel(&mut self, id: OrderId) {
         self.remove_ours(id);
     }
 
-    pub fn remove_ours(&mut self, id: OrderId) -> Option<BtcDaiOrder> {
+    pub fn remove_ours(&mut self, id: OrderId) -> Option<BtcErc20Order> {
         if let Some(map) = self.inner.get_mut(&self.me) {
             return map.remove(&id);
         }
         None
     }
 
-    pub fn all(&self) -> impl Iterator<Item = (&PeerId, &BtcDaiOrder)> {
+    pub fn all(&self) -> impl Iterator<Item = (&PeerId, &BtcErc20Order)> {
         self.inner
             .iter()
             .flat_map(|(maker, orders)| iter::from_fn(move || Some(maker)).zip(orders.values()))
     }
 
-    pub fn theirs(&self) -> impl Iterator<Item = (&PeerId, &BtcDaiOrder)> + Clone {
+    pub fn theirs(&self) -> impl Iterator<Item = (&PeerId, &BtcErc20Order)> + Clone {
         let me = &self.me;
 
         self.inner
@@ -122,7 +168,7 @@ impl OrderPool {
             .flatten()
     }
 
-    pub fn ours(&self) -> impl Iterator<Item = &BtcDaiOrder> {
+    pub fn ours(&self) -> impl Iterator<Item = &BtcErc20Order> {
         self.inner
             .get(&self.me)
             .map(|orders| orders.values())
@@ -131,45 +177,80 @@ impl OrderPool {
     }
 
     /// Notify the OrderPool that we successfully setup a swap with a given
-    /// quantity for one of our orders.
+    /// quantity for the match of our and their order.
     ///
     /// While this was in progress, the OrderPool had "reserved" a certain
-    /// quantity for this order. Now that we setup a swap successfully, we can
-    /// clear this reservation and actually update the amount of the order.
+    /// quantity for both orders. Now that we setup a swap successfully, we can
+    /// clear this reservation and actually update the amount of the orders.
+    /// The quantity agreed on during the setup may be less than what we
+    /// reserved.
     pub fn notify_swap_setup_successful(
         &mut self,
-        order_id: OrderId,
+        ours: OrderId,
+        theirs: OrderId,
         quantity: Quantity<asset::Bitcoin>,
     ) -> Result<()> {
         let quantity = quantity.to_inner();
 
-        if let Some(reserved_quantity) = self.reserved_quantities.get_mut(&order_id) {
-            if *reserved_quantity < quantity {
-                anyhow::bail!(
-                    "attempted to un-reserve {} but only {} were reserved",
-                    reserved_quantity,
-                    *reserved_quantity
-                );
-            }
+        match self.reservations.get(&(ours, theirs)) {
+            Some(reserved) if *reserved < quantity => anyhow::bail!(
+                "attempted to fill {} but only {} were reserved",
+                quantity,
+                reserved
+            ),
+            Some(_) => self.release(ours, theirs),
+            None => tracing::warn!("we never reserved anything for order {}", ours),
+        }
+
+        for orders in self.inner.values_mut() {
+            for id in &[ours, theirs] {
+                if let Entry::Occupied(mut entry) = orders.entry(*id) {
+                    let order = entry.get_mut();
 
-            *reserved_quantity -= quantity;
-        } else {
-            tracing::warn!("we never reserved anything for order {}", order_id);
+                    if order.quantity.to_inner() <= quantity {
+                        entry.remove();
+                    } else {
+                        order.quantity = Quantity::new(order.quantity.to_inner() - quantity);
+                    }
+                }
+            }
         }
 
-        if let Some(our_orders) = self.inner.get_mut(&self.me) {
-            if let Entry::Occupied(mut entry) = our_orders.entry(order_id) {
-                let order = entry.get_mut();
+        Ok(())
+    }
+
+    /// Notify the OrderPool that setting up a swap for the match of our and
+    /// their order failed.
+    ///
+    /// The quantity reserved for this match becomes available for other
+    /// matches again.
+    pub fn notify_swap_setup_failed(&mut self, ours: OrderId, theirs: OrderId) {
+        self.release(ours, theirs);
+    }
 
-                if order.quantity.to_inner() == quantity {
+    fn release(&mut self, ours: OrderId, theirs: OrderId) {
+        let reserved = match self.reservations.remove(&(ours, theirs)) {
+            Some(reserved) => reserved,
+            None => return,
+        };
+
+        for id in &[ours, theirs] {
+            if let Entry::Occupied(mut entry) = self.reserved_quantities.entry(*id) {
+                let remaining = entry
+                    .get()
+                    .checked_sub(reserved)
+                    .unwrap_or(asset::Bitcoin::ZERO);
+
+                if remaining == asset::Bitcoin::ZERO {
                     entry.remove();
                 } else {
-                    order.quantity = Quantity::new(order.quantity.to_inner() - quantity);
+                    *entry.get_mut() = remaining;
                 }
             }
         }
 
-        Ok(())
+        // Pairs that did not match because of this reservation might match now.
+        self.no_match_cache.clear();
     }
 
     pub fn is_ours(&self, id: OrderId) -> bool {
@@ -181,28 +262,26 @@ impl OrderPool {
 
         let mut matches = Vec::new();
 
-        // TODO: Figure out how to not duplicate this so the borrow-checker still gets
-        // things
-        let ours = self
+        // Orders are matched oldest first, HashMap iteration order would make
+        // the fills differ between runs.
+        let mut ours = self
             .inner
             .get(me)
             .map(|orders| orders.values())
             .into_iter()
-            .flatten();
-        let theirs = self
+            .flatten()
+            .collect::<Vec<_>>();
+        ours.sort_by_key(|order| (order.created_at, order.id));
+        let mut theirs = self
             .inner
             .iter()
-            .filter_map(move |(maker, orders)| {
-                if maker != me {
-                    Some(iter::from_fn(move || Some(maker)).zip(orders.values()))
-                } else {
-                    None
-                }
-            })
-            .flatten();
+            .filter(|(maker, _)| *maker != me)
+            .flat_map(|(maker, orders)| iter::from_fn(move || Some(maker)).zip(orders.values()))
+            .collect::<Vec<_>>();
+        theirs.sort_by_key(|(_, order)| (order.created_at, order.id));
 
         for ours in ours {
-            for (peer, theirs) in theirs.clone() {
+            for (peer, theirs) in theirs.iter().copied() {
                 let reserved_ours = self
                     .reserved_quantities
                     .get(&ours.id)
@@ -242,11 +321,14 @@ impl OrderPool {
                         .or_default()
                         .add_assign(quantity.to_inner());
 
-                    // TODO: We should reset this once we receive orders again from them
                     self.reserved_quantities
                         .entry(theirs.id)
                         .or_default()
                         .add_assign(quantity.to_inner());
+                    self.reservations
+                        .entry((ours.id, theirs.id))
+                        .or_default()
+                        .add_assign(quantity.to_inner());
                 } else {
                     self.no_match_cache.put(
                         NoMatch {
@@ -263,7 +345,7 @@ impl OrderPool {
     }
 }
 
-fn make_reference_point(left: &BtcDaiOrder, right: &BtcDaiOrder) -> OffsetDateTime {
+fn make_reference_point(left: &BtcErc20Order, right: &BtcErc20Order) -> OffsetDateTime {
     left.created_at.max(right.created_at)
 }
 
@@ -291,13 +373,22 @@ impl Match {
 
 #[tracing::instrument(level = "debug", fields(left = %left.id, right = %right.id, %reserved_left, %reserved_right))]
 fn match_orders(
-    left: &BtcDaiOrder,
-    right: &BtcDaiOrder,
+    left: &BtcErc20Order,
+    right: &BtcErc20Order,
     reserved_left: &asset::Bitcoin,
     reserved_right: &asset::Bitcoin,
 ) -> Option<InternalMatch> {
     use Position::*;
 
+    if left.trading_pair != right.trading_pair {
+        tracing::trace!(
+            "orders of {} and {} don't match",
+            left.trading_pair,
+            right.trading_pair
+        );
+        return None;
+    }
+
     let price = match (left.position, right.position) {
         (Sell, Buy) if left.price <= right.price => &left.price,
         (Buy, Sell) if left.price >= right.price => &right.price,
@@ -309,9 +400,9 @@ fn match_orders(
             tracing::trace!(
                 "{}ing at {} and {}ing at {} does not match",
                 left.position,
-                left.price.wei_per_sat(),
+                left.price.wei_per_btc(),
                 right.position,
-                right.price.wei_per_sat()
+                right.price.wei_per_btc()
             );
             return None;
         }
@@ -322,23 +413,19 @@ fn match_orders(
         return None;
     }
 
-    // TODO: partial order matching
-    if left.quantity != right.quantity {
-        tracing::trace!("orders with different quantities don't match");
-        return None;
-    }
-
-    let remaining_left = left.quantity.to_inner() - *reserved_left;
-    let remaining_right = right.quantity.to_inner() - *reserved_right;
+    let remaining_left = remaining_quantity(left, reserved_left);
+    let remaining_right = remaining_quantity(right, reserved_right);
 
     if remaining_left == asset::Bitcoin::ZERO || remaining_right == asset::Bitcoin::ZERO {
         tracing::trace!("cannot fill order because of existing reserved funds");
         return None;
     }
 
-    let quantity = remaining_left;
+    // The bigger order is filled partially, its remaining quantity is available
+    // for further matches.
+    let quantity = remaining_left.min(remaining_right);
 
-    tracing::info!("matched with {} at price {}", quantity, price.wei_per_sat());
+    tracing::info!("matched with {} at price {}", quantity, price.wei_per_btc());
 
     Some(InternalMatch {
         price: price.clone(),
@@ -346,6 +433,14 @@ fn match_orders(
     })
 }
 
+fn remaining_quantity(order: &BtcErc20Order, reserved: &asset::Bitcoin) -> asset::Bitcoin {
+    order
+        .quantity
+        .to_inner()
+        .checked_sub(*reserved)
+        .unwrap_or(asset::Bitcoin::ZERO)
+}
+
 // TODO: Find better name
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 struct InternalMatch {
@@ -366,8 +461,8 @@ mod tests {
 
     #[test]
     fn given_two_orders_with_same_price_then_should_match() {
-        let buy = BtcDaiOrder::buy(btc(1.0), dai_per_btc(9000), herc20_hbit());
-        let sell = BtcDaiOrder::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
+        let buy = BtcErc20Order::buy(btc(1.0), dai_per_btc(9000), herc20_hbit());
+        let sell = BtcErc20Order::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
 
         let r#match = match_orders(&buy, &sell, &Bitcoin::ZERO, &Bitcoin::ZERO);
 
@@ -379,8 +474,8 @@ mod tests {
 
     #[test]
     fn given_two_sell_orders_then_should_not_match() {
-        let sell_1 = BtcDaiOrder::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
-        let sell_2 = BtcDaiOrder::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
+        let sell_1 = BtcErc20Order::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
+        let sell_2 = BtcErc20Order::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
 
         let r#match = match_orders(&sell_1, &sell_2, &Bitcoin::ZERO, &Bitcoin::ZERO);
 
@@ -389,8 +484,8 @@ mod tests {
 
     #[test]
     fn given_sell_for_9000_when_buy_for_8500_then_no_match() {
-        let sell = BtcDaiOrder::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
-        let buy = BtcDaiOrder::buy(btc(1.0), dai_per_btc(8500), herc20_hbit());
+        let sell = BtcErc20Order::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
+        let buy = BtcErc20Order::buy(btc(1.0), dai_per_btc(8500), herc20_hbit());
 
         let r#match = match_orders(&sell, &buy, &Bitcoin::ZERO, &Bitcoin::ZERO);
 
@@ -399,8 +494,8 @@ mod tests {
 
     #[test]
     fn given_sell_for_8500_when_buy_for_9000_then_match_at_8500() {
-        let sell = BtcDaiOrder::sell(btc(1.0), dai_per_btc(8500), herc20_hbit());
-        let buy = BtcDaiOrder::buy(btc(1.0), dai_per_btc(9000), herc20_hbit());
+        let sell = BtcErc20Order::sell(btc(1.0), dai_per_btc(8500), herc20_hbit());
+        let buy = BtcErc20Order::buy(btc(1.0), dai_per_btc(9000), herc20_hbit());
 
         let r#match = match_orders(&sell, &buy, &Bitcoin::ZERO, &Bitcoin::ZERO);
 
@@ -410,21 +505,41 @@ mod tests {
         });
     }
 
-    // only temporary until we take care of partial matching properly
     #[test]
-    fn given_different_quantities_then_no_match() {
-        let sell = BtcDaiOrder::sell(btc(0.5), dai_per_btc(9000), herc20_hbit());
-        let buy = BtcDaiOrder::buy(btc(1.0), dai_per_btc(9000), herc20_hbit());
+    fn given_different_quantities_then_matches_smaller_quantity() {
+        let sell = BtcErc20Order::sell(btc(0.5), dai_per_btc(9000), herc20_hbit());
+        let buy = BtcErc20Order::buy(btc(1.0), dai_per_btc(9000), herc20_hbit());
 
         let r#match = match_orders(&sell, &buy, &Bitcoin::ZERO, &Bitcoin::ZERO);
 
-        assert_that(&r#match).is_none();
+        assert_that(&r#match).is_some().is_equal_to(&InternalMatch {
+            price: dai_per_btc(9000),
+            quantity: btc(0.5),
+        });
+    }
+
+    #[test]
+    fn given_reserved_quantity_on_both_sides_then_matches_smaller_remaining_quantity() {
+        let sell = BtcErc20Order::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
+        let buy = BtcErc20Order::buy(btc(2.0), dai_per_btc(9000), herc20_hbit());
+
+        let r#match = match_orders(
+            &sell,
+            &buy,
+            &btc(0.25).to_inner(),
+            &btc(1.5).to_inner(),
+        );
+
+        assert_that(&r#match).is_some().is_equal_to(&InternalMatch {
+            price: dai_per_btc(9000),
+            quantity: btc(0.5),
+        });
     }
 
     #[test]
     fn given_reserved_quantity_then_only_matches_remaining_quantity() {
-        let sell = BtcDaiOrder::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
-        let buy = BtcDaiOrder::buy(btc(1.0), dai_per_btc(9000), herc20_hbit());
+        let sell = BtcErc20Order::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
+        let buy = BtcErc20Order::buy(btc(1.0), dai_per_btc(9000), herc20_hbit());
 
         let r#match = match_orders(&sell, &buy, &btc(0.5).to_inner(), &Bitcoin::ZERO);
 
@@ -436,8 +551,8 @@ mod tests {
 
     #[test]
     fn given_whole_order_reserved_then_no_match() {
-        let sell = BtcDaiOrder::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
-        let buy = BtcDaiOrder::buy(btc(1.0), dai_per_btc(9000), herc20_hbit());
+        let sell = BtcErc20Order::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
+        let buy = BtcErc20Order::buy(btc(1.0), dai_per_btc(9000), herc20_hbit());
 
         let r#match = match_orders(&sell, &buy, &Bitcoin::ONE_BTC, &Bitcoin::ZERO);
 
@@ -446,8 +561,20 @@ mod tests {
 
     #[test]
     fn given_different_swap_protocols_then_no_match() {
-        let sell = BtcDaiOrder::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
-        let buy = BtcDaiOrder::buy(btc(1.0), dai_per_btc(9000), hbit_herc20());
+        let sell = BtcErc20Order::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
+        let buy = BtcErc20Order::buy(btc(1.0), dai_per_btc(9000), hbit_herc20());
+
+        let r#match = match_orders(&sell, &buy, &Bitcoin::ZERO, &Bitcoin::ZERO);
+
+        assert_that(&r#match).is_none();
+    }
+
+    #[test]
+    fn given_different_trading_pairs_then_no_match() {
+        let usdc = TradingPair::btc_against("USDC").unwrap();
+        let sell = BtcErc20Order::sell(btc(1.0), dai_per_btc(9000), herc20_hbit());
+        let buy =
+            BtcErc20Order::buy(btc(1.0), dai_per_btc(9000), herc20_hbit()).with_trading_pair(usdc);
 
         let r#match = match_orders(&sell, &buy, &Bitcoin::ZERO, &Bitcoin::ZERO);
 
@@ -456,11 +583,11 @@ mod tests {
 
     #[test]
     fn given_same_swap_protocols_with_different_parameters_then_no_match() {
-        let sell = BtcDaiOrder::sell(btc(1.0), dai_per_btc(9000), SwapProtocol::HbitHerc20 {
+        let sell = BtcErc20Order::sell(btc(1.0), dai_per_btc(9000), SwapProtocol::HbitHerc20 {
             hbit_expiry_offset: 2.hours().into(),
             herc20_expiry_offset: 1.hours().into(),
         });
-        let buy = BtcDaiOrder::buy(btc(1.0), dai_per_btc(9000), SwapProtocol::HbitHerc20 {
+        let buy = BtcErc20Order::buy(btc(1.0), dai_per_btc(9000), SwapProtocol::HbitHerc20 {
             hbit_expiry_offset: 3.hours().into(),
             herc20_expiry_offset: 1.hours().into(),
         });
@@ -472,7 +599,7 @@ mod tests {
 
     #[test]
     fn make_reference_point_picks_the_more_recent_one() {
-        let proto = BtcDaiOrder::buy(
+        let proto = BtcErc20Order::buy(
             Quantity::new(Bitcoin::ZERO),
             Price::from_wei_per_sat(Erc20Quantity::zero()),
             hbit_herc20(),
@@ -497,8 +624,8 @@ mod tests {
     proptest::proptest! {
         #[test]
         fn match_order_is_commutative(
-            left in proptest::order::btc_dai_order(),
-            right in proptest::order::btc_dai_order(),
+            left in proptest::order::btc_erc20_order(),
+            right in proptest::order::btc_erc20_order(),
             reserved_left in proptest::asset::bitcoin(),
             reserved_right in proptest::asset::bitcoin()
         ) {
@@ -514,8 +641,8 @@ mod tests {
     fn orderpool_does_not_emit_the_same_match_twice() {
         let mut pool = OrderPool::new(PeerId::random());
 
-        pool.publish(BtcDaiOrder::buy(btc(0.5), dai_per_btc(9000), hbit_herc20()));
-        pool.receive(PeerId::random(), vec![BtcDaiOrder::sell(
+        pool.publish(BtcErc20Order::buy(btc(0.5), dai_per_btc(9000), hbit_herc20()));
+        pool.receive(PeerId::random(), vec![BtcErc20Order::sell(
             btc(0.5),
             dai_per_btc(9000),
             hbit_herc20(),
@@ -533,19 +660,166 @@ mod tests {
     fn given_a_match_when_notified_about_successful_swap_then_removes_order_from_pool() {
         let mut pool = OrderPool::new(PeerId::random());
 
-        let our_order = BtcDaiOrder::buy(btc(0.5), dai_per_btc(9000), hbit_herc20());
+        let our_order = BtcErc20Order::buy(btc(0.5), dai_per_btc(9000), hbit_herc20());
+        let their_order = BtcErc20Order::sell(btc(0.5), dai_per_btc(9000), hbit_herc20());
         pool.publish(our_order.clone());
-        pool.receive(PeerId::random(), vec![BtcDaiOrder::sell(
-            btc(0.5),
+        pool.receive(PeerId::random(), vec![their_order.clone()]);
+        pool.matches();
+
+        pool.notify_swap_setup_successful(our_order.id, their_order.id, btc(0.5))
+            .unwrap();
+
+        assert_that(&pool.ours().next()).is_none();
+    }
+
+    #[test]
+    fn order_is_filled_partially_by_several_counterparties() {
+        let mut pool = OrderPool::new(PeerId::random());
+
+        let our_order = BtcErc20Order::buy(btc(1.0), dai_per_btc(9000), hbit_herc20());
+        let first = BtcErc20Order::sell(btc(0.25), dai_per_btc(9000), hbit_herc20());
+        let second = BtcErc20Order::sell(btc(0.5), dai_per_btc(9000), hbit_herc20());
+        pool.publish(our_order.clone());
+        pool.receive(PeerId::random(), vec![first.clone()]);
+        pool.receive(PeerId::random(), vec![second.clone()]);
+
+        let matches = pool.matches();
+
+        assert_that(&matches).has_length(2);
+        assert_that(&matches).matching_contains(|m| m.quantity == btc(0.25));
+        assert_that(&matches).matching_contains(|m| m.quantity == btc(0.5));
+
+        pool.notify_swap_setup_successful(our_order.id, first.id, btc(0.25))
+            .unwrap();
+        pool.notify_swap_setup_successful(our_order.id, second.id, btc(0.5))
+            .unwrap();
+
+        assert_that(&pool.ours().next().map(|o| o.quantity))
+            .is_some()
+            .is_equal_to(&btc(0.25));
+    }
+
+    #[test]
+    fn remaining_quantity_of_partially_filled_order_can_be_matched_again() {
+        let mut pool = OrderPool::new(PeerId::random());
+
+        let our_order = BtcErc20Order::buy(btc(1.0), dai_per_btc(9000), hbit_herc20());
+        let their_order = BtcErc20Order::sell(btc(0.5), dai_per_btc(9000), hbit_herc20());
+        pool.publish(our_order.clone());
+        pool.receive(PeerId::random(), vec![their_order.clone()]);
+        pool.matches();
+        pool.notify_swap_setup_successful(our_order.id, their_order.id, btc(0.5))
+            .unwrap();
+
+        pool.receive(PeerId::random(), vec![BtcErc20Order::sell(
+            btc(1.0),
             dai_per_btc(9000),
             hbit_herc20(),
         )]);
+        let matches = pool.matches();
+
+        assert_that(&matches)
+            .matching_contains(|m| m.ours == our_order.id && m.quantity == btc(0.5));
+    }
+
+    #[test]
+    fn given_several_takers_then_the_oldest_order_is_filled_first() {
+        let mut pool = OrderPool::new(PeerId::random());
+
+        let our_order = BtcErc20Order::sell(btc(1.0), dai_per_btc(9000), hbit_herc20());
+        let mut first = BtcErc20Order::buy(btc(0.5), dai_per_btc(9000), hbit_herc20());
+        first.created_at = OffsetDateTime::from_unix_timestamp(0);
+        let mut second = BtcErc20Order::buy(btc(0.8), dai_per_btc(9000), hbit_herc20());
+        second.created_at = OffsetDateTime::from_unix_timestamp(1000);
+        pool.publish(our_order.clone());
+        pool.receive(PeerId::random(), vec![second.clone()]);
+        pool.receive(PeerId::random(), vec![first.clone()]);
+
+        let matches = pool.matches();
+
+        assert_that(&matches).has_length(2);
+        assert_that(&matches).matching_contains(|m| m.theirs == first.id && m.quantity == btc(0.5));
+        assert_that(&matches)
+            .matching_contains(|m| m.theirs == second.id && m.quantity == btc(0.5));
+    }
+
+    #[test]
+    fn given_a_failed_swap_setup_then_the_reserved_quantity_can_be_matched_again() {
+        let mut pool = OrderPool::new(PeerId::random());
+
+        let our_order = BtcErc20Order::sell(btc(1.0), dai_per_btc(9000), hbit_herc20());
+        let mut first = BtcErc20Order::buy(btc(1.0), dai_per_btc(9000), hbit_herc20());
+        first.created_at = OffsetDateTime::from_unix_timestamp(0);
+        pool.publish(our_order.clone());
+        pool.receive(PeerId::random(), vec![first.clone()]);
+        pool.matches();
+        let mut second = BtcErc20Order::buy(btc(0.8), dai_per_btc(9000), hbit_herc20());
+        second.created_at = OffsetDateTime::from_unix_timestamp(1000);
+        pool.receive(PeerId::random(), vec![second.clone()]);
+        assert_that(&pool.matches()).has_length(0);
+
+        pool.notify_swap_setup_failed(our_order.id, first.id);
+        let matches = pool.matches();
+
+        assert_that(&matches).matching_contains(|m| m.theirs == first.id && m.quantity == btc(1.0));
+        assert_that(&matches).has_length(1);
+    }
+
+    #[test]
+    fn given_less_than_reserved_was_agreed_on_then_the_rest_is_released() {
+        let mut pool = OrderPool::new(PeerId::random());
+
+        let our_order = BtcErc20Order::sell(btc(1.0), dai_per_btc(9000), hbit_herc20());
+        let their_order = BtcErc20Order::buy(btc(1.0), dai_per_btc(9000), hbit_herc20());
+        pool.publish(our_order.clone());
+        pool.receive(PeerId::random(), vec![their_order.clone()]);
         pool.matches();
 
-        pool.notify_swap_setup_successful(our_order.id, btc(0.5))
+        pool.notify_swap_setup_successful(our_order.id, their_order.id, btc(0.4))
             .unwrap();
+        let matches = pool.matches();
 
-        assert_that(&pool.ours().next()).is_none();
+        assert_that(&matches).matching_contains(|m| m.quantity == btc(0.6));
+    }
+
+    #[test]
+    fn removing_a_trading_pair_keeps_the_orders_of_other_pairs() {
+        let mut pool = OrderPool::new(PeerId::random());
+        let maker = PeerId::random();
+        let usdc = TradingPair::btc_against("USDC").unwrap();
+        let dai_order = BtcErc20Order::sell(btc(1.0), dai_per_btc(9000), hbit_herc20());
+        let usdc_order = BtcErc20Order::sell(btc(1.0), dai_per_btc(9000), hbit_herc20())
+            .with_trading_pair(usdc.clone());
+        pool.receive(maker.clone(), vec![dai_order, usdc_order.clone()]);
+
+        pool.remove_trading_pair_from(&maker, &TradingPair::btc_dai());
+
+        let remaining = pool.theirs().map(|(_, order)| order.clone()).collect::<Vec<_>>();
+        assert_that(&remaining).is_equal_to(vec![usdc_order]);
+        assert!(pool.has_orders_from(&maker));
+
+        pool.remove_trading_pair_from(&maker, &usdc);
+
+        assert!(!pool.has_orders_from(&maker));
+    }
+
+    #[test]
+    fn replacing_own_orders_removes_the_previous_ones() {
+        let mut pool = OrderPool::new(PeerId::random());
+        let old = BtcErc20Order::sell(btc(1.0), dai_per_btc(9000), hbit_herc20());
+        let first = BtcErc20Order::sell(btc(1.0), dai_per_btc(9100), hbit_herc20());
+        let second = BtcErc20Order::sell(btc(1.0), dai_per_btc(9200), hbit_herc20());
+        pool.publish(old.clone());
+
+        pool.replace_own_orders(vec![first.clone(), second.clone()]);
+
+        assert!(!pool.is_ours(old.id));
+        assert!(pool.is_ours(first.id));
+        assert!(pool.is_ours(second.id));
+
+        pool.replace_own_orders(vec![]);
+
+        assert_that(&pool.ours().count()).is_equal_to(0);
     }
 
     fn hbit_herc20() -> SwapProtocol {
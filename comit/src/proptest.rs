@@ -37,7 +37,7 @@ pub mod asset {
 
 pub mod order {
     use super::*;
-    use crate::{BtcDaiOrder, OrderId, Position, Price, Quantity};
+    use crate::{BtcErc20Order, OrderId, Position, Price, Quantity, TradingPair};
 
     pub fn position() -> impl Strategy<Value = Position> {
         prop_oneof![Just(Position::Buy), Just(Position::Sell)]
@@ -54,12 +54,19 @@ pub mod order {
     }
 
     prop_compose! {
-        pub fn btc_dai_order()(id in order_id(), price in asset::erc20_quantity(), quantity in asset::bitcoin(), swap_protocol in swap_protocol(), position in position(), created_at in time::offset_date_time()) -> BtcDaiOrder {
-            BtcDaiOrder::new_test(
+        pub fn trading_pair()(symbol in "[A-Z0-9]{1,6}") -> TradingPair {
+            TradingPair::btc_against(&symbol).unwrap()
+        }
+    }
+
+    prop_compose! {
+        pub fn btc_erc20_order()(id in order_id(), trading_pair in trading_pair(), price in asset::erc20_quantity(), quantity in asset::bitcoin(), swap_protocol in swap_protocol(), position in position(), created_at in time::offset_date_time()) -> BtcErc20Order {
+            BtcErc20Order::new_test(
                 id,
+                trading_pair,
                 position,
                 Quantity::new(quantity),
-                Price::from_wei_per_sat(price),
+                Price::from_wei_per_btc(price),
                 swap_protocol,
                 created_at
             )
@@ -67,7 +74,12 @@ pub mod order {
     }
 
     pub fn swap_protocol() -> impl Strategy<Value = SwapProtocol> {
-        prop_oneof![swap_protocol_hbit_herc20(), swap_protocol_herc20_hbit()]
+        prop_oneof![
+            swap_protocol_hbit_herc20(),
+            swap_protocol_herc20_hbit(),
+            swap_protocol_hbit_heth(),
+            swap_protocol_heth_hbit()
+        ]
     }
 
     prop_compose! {
@@ -87,6 +99,24 @@ pub mod order {
             }
         }
     }
+
+    prop_compose! {
+        pub fn swap_protocol_hbit_heth()(hbit_expiry_offset in expiries::alpha_offset(), heth_expiry_offset in expiries::beta_offset()) -> SwapProtocol {
+            SwapProtocol::HbitHeth {
+                hbit_expiry_offset,
+                heth_expiry_offset,
+            }
+        }
+    }
+
+    prop_compose! {
+        pub fn swap_protocol_heth_hbit()(heth_expiry_offset in expiries::alpha_offset(), hbit_expiry_offset in expiries::beta_offset()) -> SwapProtocol {
+            SwapProtocol::HethHbit {
+                heth_expiry_offset,
+                hbit_expiry_offset,
+            }
+        }
+    }
 }
 
 pub fn role() -> impl Strategy<Value = Role> {
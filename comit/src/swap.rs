@@ -1,22 +1,41 @@
 pub mod actions;
 pub mod hbit_herc20;
+pub mod hbit_heth;
 pub mod herc20_hbit;
+pub mod heth_hbit;
 
 pub use crate::{ethereum, *};
 pub use hbit_herc20::{hbit_herc20_alice, hbit_herc20_bob};
+pub use hbit_heth::{hbit_heth_alice, hbit_heth_bob};
 pub use herc20_hbit::{herc20_hbit_alice, herc20_hbit_bob};
+pub use heth_hbit::{heth_hbit_alice, heth_hbit_bob};
 
-use crate::swap::actions::{CallContract, DeployContract, SendToAddress, SpendOutput};
-use std::error::Error as StdError;
+use crate::{
+    expiries::{AliceAction, AliceState, BobAction, BobState, CurrentTime, Expiries},
+    swap::actions::{CallContract, DeployContract, SendToAddress, SpendOutput},
+};
+use bitcoin::secp256k1::{Secp256k1, Signing};
+use futures::future::{self, Either, Future};
+use genawaiter::sync::Co;
+use std::{cmp, error::Error as StdError, time::Duration};
 use thiserror::Error;
+use time::OffsetDateTime;
+
+/// How often we check the ledger time once an HTLC is about to expire.
+const EXPIRY_POLL_INTERVAL: Duration = Duration::from_secs(10);
 
 #[derive(Debug, Clone)]
 pub enum Action {
     Herc20Deploy(DeployContract),
     Herc20Fund(CallContract),
     Herc20Redeem(CallContract, Secret),
+    Herc20Refund(CallContract),
+    HethFund(DeployContract),
+    HethRedeem(CallContract, Secret),
+    HethRefund(CallContract),
     HbitFund(SendToAddress),
     HbitRedeem(SpendOutput, Secret),
+    HbitRefund(SpendOutput),
 }
 
 #[derive(Debug, Clone, Copy, Error)]
@@ -30,3 +49,693 @@ where
     #[error("beta ledger was incorrectly funded")]
     BetaIncorrectlyFunded(#[source] B),
 }
+
+/// The verdict of the expiries on an action yielded by a swap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// There is enough time left to safely execute the action.
+    Execute,
+    /// We have already funded our HTLC but it is no longer safe to proceed.
+    /// The action must not be executed; the swap will refund once our HTLC
+    /// expires.
+    WaitToRefund,
+    /// We have not funded yet and it is no longer safe to do so.
+    Abort,
+}
+
+/// Consults the expiries on whether Alice should execute the given action.
+///
+/// Every action of Alice uniquely identifies the state she is in, regardless
+/// of the protocol.
+pub async fn alice_verdict<A, B>(expiries: &Expiries<A, B>, action: &Action) -> Verdict
+where
+    A: CurrentTime,
+    B: CurrentTime,
+{
+    let (state, expected) = match action {
+        Action::Herc20Deploy(_) => (AliceState::Started, AliceAction::DeployAlpha),
+        Action::Herc20Fund(_) => (AliceState::AlphaDeployed, AliceAction::FundAlpha),
+        Action::HethFund(_) | Action::HbitFund(_) => (AliceState::Started, AliceAction::FundAlpha),
+        Action::Herc20Redeem(..) | Action::HethRedeem(..) | Action::HbitRedeem(..) => {
+            (AliceState::BetaFunded, AliceAction::RedeemBeta)
+        }
+        // Refunds are only yielded once the HTLC expired.
+        Action::Herc20Refund(_) | Action::HethRefund(_) | Action::HbitRefund(_) => {
+            return Verdict::Execute
+        }
+    };
+
+    match expiries.next_action_for_alice(state).await {
+        next if next == expected => Verdict::Execute,
+        AliceAction::Abort => Verdict::Abort,
+        _ => Verdict::WaitToRefund,
+    }
+}
+
+/// Consults the expiries on whether Bob should execute the given action.
+///
+/// Every action of Bob uniquely identifies the state he is in, regardless of
+/// the protocol.
+pub async fn bob_verdict<A, B>(expiries: &Expiries<A, B>, action: &Action) -> Verdict
+where
+    A: CurrentTime,
+    B: CurrentTime,
+{
+    let (state, expected) = match action {
+        Action::Herc20Deploy(_) => (BobState::AlphaFunded, BobAction::DeployBeta),
+        Action::Herc20Fund(_) => (BobState::BetaDeployed, BobAction::FundBeta),
+        Action::HethFund(_) | Action::HbitFund(_) => (BobState::AlphaFunded, BobAction::FundBeta),
+        Action::Herc20Redeem(..) | Action::HethRedeem(..) | Action::HbitRedeem(..) => (
+            BobState::RedeemBetaTransactionBroadcast,
+            BobAction::RedeemAlpha,
+        ),
+        // Refunds are only yielded once the HTLC expired.
+        Action::Herc20Refund(_) | Action::HethRefund(_) | Action::HbitRefund(_) => {
+            return Verdict::Execute
+        }
+    };
+
+    match expiries.next_action_for_bob(state).await {
+        next if next == expected => Verdict::Execute,
+        BobAction::Abort => Verdict::Abort,
+        _ => Verdict::WaitToRefund,
+    }
+}
+
+/// Races the given watcher against the expiry of an HTLC.
+///
+/// Returns `None` if the expiry elapsed before the watcher resolved.
+async fn before_expiry<W, C>(watcher: W, ledger: &C, expiry: Timestamp) -> Option<W::Output>
+where
+    W: Future,
+    C: CurrentTime,
+{
+    let expired = wait_until_expired(ledger, expiry);
+
+    futures::pin_mut!(watcher);
+    futures::pin_mut!(expired);
+
+    match future::select(watcher, expired).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(((), _)) => None,
+    }
+}
+
+/// Resolves to the secret as soon as it is revealed, either by a redeem
+/// transaction that was mined or by one that is still waiting to be mined.
+async fn revealed_secret<R, M>(redeemed: R, redeemed_in_mempool: M) -> Secret
+where
+    R: Future<Output = Secret>,
+    M: Future<Output = Secret>,
+{
+    futures::pin_mut!(redeemed);
+    futures::pin_mut!(redeemed_in_mempool);
+
+    match future::select(redeemed, redeemed_in_mempool).await {
+        Either::Left((secret, _)) | Either::Right((secret, _)) => secret,
+    }
+}
+
+/// Resolves once the time of the given ledger is past the expiry.
+async fn wait_until_expired<C>(ledger: &C, expiry: Timestamp)
+where
+    C: CurrentTime,
+{
+    loop {
+        let now = ledger.current_time().await;
+        if now > expiry {
+            return;
+        }
+
+        // The ledger time advances roughly at the pace of the wall clock but may jump
+        // ahead with a new block, hence we sleep for the remaining time but never
+        // longer than the poll interval before checking again.
+        let remaining = u32::from(expiry) - u32::from(now) + 1;
+        let remaining = Duration::from_secs(u64::from(remaining));
+
+        tokio::time::delay_for(cmp::min(remaining, EXPIRY_POLL_INTERVAL)).await;
+    }
+}
+
+async fn refund_hbit<W, C, E>(
+    co: &Co<Result<Action, E>>,
+    hbit: &W,
+    secp: &Secp256k1<C>,
+    params: &hbit::Params,
+    fund_event: hbit::Funded,
+    utc_start_of_swap: OffsetDateTime,
+) where
+    W: hbit::WatchForRefunded,
+    C: Signing,
+{
+    tracing::info!("hbit htlc expired");
+
+    co.yield_(Ok(Action::HbitRefund(
+        params.build_refund_action(secp, fund_event.location),
+    )))
+    .await;
+    let _ = hbit
+        .watch_for_refunded(params, fund_event, utc_start_of_swap)
+        .await;
+
+    tracing::info!("we refunded the hbit htlc");
+}
+
+async fn refund_herc20<W, E>(
+    co: &Co<Result<Action, E>>,
+    herc20: &W,
+    params: herc20::Params,
+    deploy_event: herc20::Deployed,
+    utc_start_of_swap: OffsetDateTime,
+) where
+    W: herc20::WatchForRefunded,
+{
+    tracing::info!("herc20 htlc expired");
+
+    co.yield_(Ok(Action::Herc20Refund(
+        params.build_refund_action(deploy_event.location),
+    )))
+    .await;
+    let _ = herc20
+        .watch_for_refunded(params, deploy_event, utc_start_of_swap)
+        .await;
+
+    tracing::info!("we refunded the herc20 htlc");
+}
+
+async fn refund_heth<W, E>(
+    co: &Co<Result<Action, E>>,
+    heth: &W,
+    params: heth::Params,
+    fund_event: heth::Funded,
+    utc_start_of_swap: OffsetDateTime,
+) where
+    W: heth::WatchForRefunded,
+{
+    tracing::info!("heth htlc expired");
+
+    co.yield_(Ok(Action::HethRefund(
+        params.build_refund_action(fund_event.location),
+    )))
+    .await;
+    let _ = heth
+        .watch_for_refunded(params, fund_event, utc_start_of_swap)
+        .await;
+
+    tracing::info!("we refunded the heth htlc");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        asset::{self, ethereum::FromWei},
+        ethereum::ChainId,
+        htlc_location, identity, ledger, Network,
+    };
+    use ::bitcoin::{
+        hashes::Hash as _,
+        secp256k1::{All, SecretKey},
+        OutPoint, Txid,
+    };
+    use async_trait::async_trait;
+    use futures::{Stream, StreamExt};
+    use std::{fmt::Debug, str::FromStr};
+
+    const THIRTY_DAYS: u32 = 30 * 24 * 60 * 60;
+
+    #[derive(Clone, Copy, Debug)]
+    struct FixedTime(Timestamp);
+
+    #[async_trait]
+    impl CurrentTime for FixedTime {
+        async fn current_time(&self) -> Timestamp {
+            self.0
+        }
+    }
+
+    fn expiries(start_at: Timestamp, now: Timestamp) -> Expiries<FixedTime, FixedTime> {
+        Expiries::new_hbit_herc20(Network::Main, start_at, FixedTime(now), FixedTime(now))
+    }
+
+    fn call_contract() -> CallContract {
+        CallContract {
+            to: identity::Ethereum::default(),
+            data: None,
+            gas_limit: 0,
+            chain_id: ChainId::GETH_DEV,
+        }
+    }
+
+    fn secret() -> Secret {
+        Secret::from_str("68d627971643a6f97f27c58957826fcba853ec2077fd10ec6b93d8e61deb4c66")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn bob_funds_at_start_of_swap() {
+        let now = Timestamp::now();
+        let expiries = expiries(now, now);
+
+        let verdict = bob_verdict(&expiries, &Action::Herc20Fund(call_contract())).await;
+
+        assert_eq!(verdict, Verdict::Execute);
+    }
+
+    #[tokio::test]
+    async fn bob_does_not_fund_once_expiries_elapsed() {
+        let now = Timestamp::now();
+        let expiries = expiries(now.minus(THIRTY_DAYS), now);
+
+        let verdict = bob_verdict(&expiries, &Action::Herc20Fund(call_contract())).await;
+
+        assert_eq!(verdict, Verdict::Abort);
+    }
+
+    #[tokio::test]
+    async fn alice_does_not_redeem_once_expiries_elapsed() {
+        let now = Timestamp::now();
+        let expiries = expiries(now.minus(THIRTY_DAYS), now);
+
+        let verdict =
+            alice_verdict(&expiries, &Action::Herc20Redeem(call_contract(), secret())).await;
+
+        assert_eq!(verdict, Verdict::WaitToRefund);
+    }
+
+    #[tokio::test]
+    async fn refund_is_always_executed() {
+        let now = Timestamp::now();
+        let expiries = expiries(now.minus(THIRTY_DAYS), now);
+
+        let verdict = alice_verdict(&expiries, &Action::Herc20Refund(call_contract())).await;
+
+        assert_eq!(verdict, Verdict::Execute);
+    }
+
+    /// Ledgers on which every HTLC is funded right away but never redeemed,
+    /// their time is past the expiries of all HTLCs.
+    #[derive(Clone, Copy, Debug)]
+    struct Unresponsive(Timestamp);
+
+    #[async_trait]
+    impl CurrentTime for Unresponsive {
+        async fn current_time(&self) -> Timestamp {
+            self.0
+        }
+    }
+
+    #[async_trait]
+    impl hbit::WatchForFunded for Unresponsive {
+        async fn watch_for_funded(
+            &self,
+            _: &hbit::Params,
+            _: OffsetDateTime,
+        ) -> Result<hbit::Funded, hbit::IncorrectlyFunded> {
+            Ok(hbit::Funded {
+                location: OutPoint::null(),
+                block_height: None,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl hbit::WatchForRedeemed for Unresponsive {
+        async fn watch_for_redeemed(
+            &self,
+            _: &hbit::Params,
+            _: hbit::Funded,
+            _: OffsetDateTime,
+        ) -> hbit::Redeemed {
+            future::pending().await
+        }
+    }
+
+    #[async_trait]
+    impl hbit::WatchForRedeemedInMempool for Unresponsive {
+        async fn watch_for_redeemed_in_mempool(&self, _: &hbit::Params, _: hbit::Funded) -> Secret {
+            future::pending().await
+        }
+    }
+
+    #[async_trait]
+    impl hbit::WatchForRefunded for Unresponsive {
+        async fn watch_for_refunded(
+            &self,
+            _: &hbit::Params,
+            _: hbit::Funded,
+            _: OffsetDateTime,
+        ) -> hbit::Refunded {
+            hbit::Refunded {
+                transaction: Txid::from_inner([0u8; 32]),
+                block_height: None,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl herc20::WatchForDeployed for Unresponsive {
+        async fn watch_for_deployed(
+            &self,
+            _: herc20::Params,
+            _: OffsetDateTime,
+        ) -> herc20::Deployed {
+            herc20::Deployed {
+                transaction: ethereum::Hash::default(),
+                location: htlc_location::Ethereum::default(),
+                block_height: None,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl herc20::WatchForFunded for Unresponsive {
+        async fn watch_for_funded(
+            &self,
+            _: herc20::Params,
+            _: herc20::Deployed,
+            _: OffsetDateTime,
+        ) -> Result<herc20::Funded, herc20::IncorrectlyFunded> {
+            Ok(herc20::Funded {
+                transaction: ethereum::Hash::default(),
+                block_height: None,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl herc20::WatchForRedeemed for Unresponsive {
+        async fn watch_for_redeemed(
+            &self,
+            _: herc20::Params,
+            _: herc20::Deployed,
+            _: OffsetDateTime,
+        ) -> herc20::Redeemed {
+            future::pending().await
+        }
+    }
+
+    #[async_trait]
+    impl herc20::WatchForRedeemedInMempool for Unresponsive {
+        async fn watch_for_redeemed_in_mempool(
+            &self,
+            _: herc20::Params,
+            _: herc20::Deployed,
+        ) -> Secret {
+            future::pending().await
+        }
+    }
+
+    #[async_trait]
+    impl herc20::WatchForRefunded for Unresponsive {
+        async fn watch_for_refunded(
+            &self,
+            _: herc20::Params,
+            _: herc20::Deployed,
+            _: OffsetDateTime,
+        ) -> herc20::Refunded {
+            herc20::Refunded {
+                transaction: ethereum::Hash::default(),
+                block_height: None,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl heth::WatchForFunded for Unresponsive {
+        async fn watch_for_funded(
+            &self,
+            _: heth::Params,
+            _: OffsetDateTime,
+        ) -> Result<heth::Funded, heth::IncorrectlyFunded> {
+            Ok(heth::Funded {
+                transaction: ethereum::Hash::default(),
+                location: htlc_location::Ethereum::default(),
+                block_height: None,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl heth::WatchForRedeemed for Unresponsive {
+        async fn watch_for_redeemed(
+            &self,
+            _: heth::Params,
+            _: heth::Funded,
+            _: OffsetDateTime,
+        ) -> heth::Redeemed {
+            future::pending().await
+        }
+    }
+
+    #[async_trait]
+    impl heth::WatchForRedeemedInMempool for Unresponsive {
+        async fn watch_for_redeemed_in_mempool(&self, _: heth::Params, _: heth::Funded) -> Secret {
+            future::pending().await
+        }
+    }
+
+    #[async_trait]
+    impl heth::WatchForRefunded for Unresponsive {
+        async fn watch_for_refunded(
+            &self,
+            _: heth::Params,
+            _: heth::Funded,
+            _: OffsetDateTime,
+        ) -> heth::Refunded {
+            heth::Refunded {
+                transaction: ethereum::Hash::default(),
+                block_height: None,
+            }
+        }
+    }
+
+    fn hbit_params(secp: &Secp256k1<All>, expiry: Timestamp) -> hbit::Params {
+        let transient_sk = SecretKey::from_slice(&[1u8; 32]).unwrap();
+        let identity = identity::Bitcoin::from_secret_key(secp, &transient_sk);
+
+        hbit::Params {
+            shared: hbit::SharedParams {
+                network: ledger::Bitcoin::Regtest,
+                asset: asset::Bitcoin::from_sat(100_000),
+                redeem_identity: identity,
+                refund_identity: identity,
+                expiry,
+                secret_hash: SecretHash::new(secret()),
+            },
+            transient_sk,
+            final_address: ::bitcoin::Address::p2pkh(&identity.into(), ::bitcoin::Network::Regtest),
+        }
+    }
+
+    fn herc20_params(expiry: Timestamp) -> herc20::Params {
+        herc20::Params {
+            asset: asset::Erc20::new(
+                identity::Ethereum::default(),
+                asset::Erc20Quantity::from_wei(1_000u32),
+            ),
+            redeem_identity: identity::Ethereum::default(),
+            refund_identity: identity::Ethereum::default(),
+            expiry,
+            secret_hash: SecretHash::new(secret()),
+            chain_id: ChainId::GETH_DEV,
+        }
+    }
+
+    fn heth_params(expiry: Timestamp) -> heth::Params {
+        heth::Params {
+            asset: asset::Ether::from_wei(1_000u32),
+            redeem_identity: identity::Ethereum::default(),
+            refund_identity: identity::Ethereum::default(),
+            expiry,
+            secret_hash: SecretHash::new(secret()),
+            chain_id: ChainId::GETH_DEV,
+        }
+    }
+
+    async fn actions<S, E>(swap: S) -> Vec<Action>
+    where
+        S: Stream<Item = Result<Action, E>>,
+        E: Debug,
+    {
+        swap.map(|action| action.unwrap()).collect().await
+    }
+
+    #[tokio::test]
+    async fn hbit_herc20_alice_refunds_if_redeem_is_not_mined_before_expiry() {
+        let now = Timestamp::now();
+        let secp = Secp256k1::new();
+        let hbit_params = hbit_params(&secp, now.minus(60));
+
+        let swap = hbit_herc20_alice(
+            Unresponsive(now),
+            Unresponsive(now),
+            secp,
+            hbit_params,
+            herc20_params(now.minus(120)),
+            secret(),
+            OffsetDateTime::now_utc(),
+        );
+
+        assert!(matches!(actions(swap).await.as_slice(), [
+            Action::HbitFund(_),
+            Action::Herc20Redeem(..),
+            Action::HbitRefund(_)
+        ]));
+    }
+
+    #[tokio::test]
+    async fn hbit_herc20_bob_refunds_if_alice_does_not_redeem_before_expiry() {
+        let now = Timestamp::now();
+        let secp = Secp256k1::new();
+        let hbit_params = hbit_params(&secp, now.minus(60));
+
+        let swap = hbit_herc20_bob(
+            Unresponsive(now),
+            Unresponsive(now),
+            secp,
+            hbit_params,
+            herc20_params(now.minus(120)),
+            OffsetDateTime::now_utc(),
+        );
+
+        assert!(matches!(actions(swap).await.as_slice(), [
+            Action::Herc20Deploy(_),
+            Action::Herc20Fund(_),
+            Action::Herc20Refund(_)
+        ]));
+    }
+
+    #[tokio::test]
+    async fn herc20_hbit_alice_refunds_if_redeem_is_not_mined_before_expiry() {
+        let now = Timestamp::now();
+        let secp = Secp256k1::new();
+        let hbit_params = hbit_params(&secp, now.minus(120));
+
+        let swap = herc20_hbit_alice(
+            Unresponsive(now),
+            Unresponsive(now),
+            secp,
+            herc20_params(now.minus(60)),
+            hbit_params,
+            secret(),
+            OffsetDateTime::now_utc(),
+        );
+
+        assert!(matches!(actions(swap).await.as_slice(), [
+            Action::Herc20Deploy(_),
+            Action::Herc20Fund(_),
+            Action::HbitRedeem(..),
+            Action::Herc20Refund(_)
+        ]));
+    }
+
+    #[tokio::test]
+    async fn herc20_hbit_bob_refunds_if_alice_does_not_redeem_before_expiry() {
+        let now = Timestamp::now();
+        let secp = Secp256k1::new();
+        let hbit_params = hbit_params(&secp, now.minus(60));
+
+        let swap = herc20_hbit_bob(
+            Unresponsive(now),
+            Unresponsive(now),
+            secp,
+            herc20_params(now.minus(120)),
+            hbit_params,
+            OffsetDateTime::now_utc(),
+        );
+
+        assert!(matches!(actions(swap).await.as_slice(), [
+            Action::HbitFund(_),
+            Action::HbitRefund(_)
+        ]));
+    }
+
+    #[tokio::test]
+    async fn hbit_heth_alice_refunds_if_redeem_is_not_mined_before_expiry() {
+        let now = Timestamp::now();
+        let secp = Secp256k1::new();
+        let hbit_params = hbit_params(&secp, now.minus(60));
+
+        let swap = hbit_heth_alice(
+            Unresponsive(now),
+            Unresponsive(now),
+            secp,
+            hbit_params,
+            heth_params(now.minus(120)),
+            secret(),
+            OffsetDateTime::now_utc(),
+        );
+
+        assert!(matches!(actions(swap).await.as_slice(), [
+            Action::HbitFund(_),
+            Action::HethRedeem(..),
+            Action::HbitRefund(_)
+        ]));
+    }
+
+    #[tokio::test]
+    async fn hbit_heth_bob_refunds_if_alice_does_not_redeem_before_expiry() {
+        let now = Timestamp::now();
+        let secp = Secp256k1::new();
+        let hbit_params = hbit_params(&secp, now.minus(60));
+
+        let swap = hbit_heth_bob(
+            Unresponsive(now),
+            Unresponsive(now),
+            secp,
+            hbit_params,
+            heth_params(now.minus(120)),
+            OffsetDateTime::now_utc(),
+        );
+
+        assert!(matches!(actions(swap).await.as_slice(), [
+            Action::HethFund(_),
+            Action::HethRefund(_)
+        ]));
+    }
+
+    #[tokio::test]
+    async fn heth_hbit_alice_refunds_if_redeem_is_not_mined_before_expiry() {
+        let now = Timestamp::now();
+        let secp = Secp256k1::new();
+        let hbit_params = hbit_params(&secp, now.minus(120));
+
+        let swap = heth_hbit_alice(
+            Unresponsive(now),
+            Unresponsive(now),
+            secp,
+            heth_params(now.minus(60)),
+            hbit_params,
+            secret(),
+            OffsetDateTime::now_utc(),
+        );
+
+        assert!(matches!(actions(swap).await.as_slice(), [
+            Action::HethFund(_),
+            Action::HbitRedeem(..),
+            Action::HethRefund(_)
+        ]));
+    }
+
+    #[tokio::test]
+    async fn heth_hbit_bob_refunds_if_alice_does_not_redeem_before_expiry() {
+        let now = Timestamp::now();
+        let secp = Secp256k1::new();
+        let hbit_params = hbit_params(&secp, now.minus(60));
+
+        let swap = heth_hbit_bob(
+            Unresponsive(now),
+            Unresponsive(now),
+            secp,
+            heth_params(now.minus(120)),
+            hbit_params,
+            OffsetDateTime::now_utc(),
+        );
+
+        assert!(matches!(actions(swap).await.as_slice(), [
+            Action::HbitFund(_),
+            Action::HbitRefund(_)
+        ]));
+    }
+}
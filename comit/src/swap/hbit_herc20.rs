@@ -1,24 +1,32 @@
 use crate::{
-    swap::{hbit, herc20, Action, Error},
+    expiries::CurrentTime,
+    swap::{
+        before_expiry, hbit, herc20, refund_hbit, refund_herc20, revealed_secret, Action, Error,
+    },
     Secret,
 };
 use bitcoin::secp256k1::{Secp256k1, Signing};
-use futures::Stream;
+use futures::{future, FutureExt, Stream};
 use genawaiter::sync::Gen;
 use time::OffsetDateTime;
 
 /// Execute a Hbit<->Herc20 swap for Alice.
-pub fn hbit_herc20_alice<A, B>(
+///
+/// Once Alice has funded the hbit HTLC, every subsequent step is raced against
+/// its expiry. If the expiry elapses first, the swap yields the refund action.
+pub fn hbit_herc20_alice<A, B, C>(
     hbit: A,
     herc20: B,
+    secp: Secp256k1<C>,
     hbit_params: hbit::Params,
     herc20_params: herc20::Params,
     secret: Secret,
     utc_start_of_swap: OffsetDateTime,
 ) -> impl Stream<Item = Result<Action, Error<hbit::IncorrectlyFunded, herc20::IncorrectlyFunded>>>
 where
-    A: hbit::WatchForFunded + hbit::WatchForRedeemed,
+    A: hbit::WatchForFunded + hbit::WatchForRedeemed + hbit::WatchForRefunded + CurrentTime,
     B: herc20::WatchForDeployed + herc20::WatchForFunded + herc20::WatchForRedeemed,
+    C: Signing,
 {
     Gen::new(|co| async move {
         tracing::info!("starting swap");
@@ -35,21 +43,56 @@ where
 
         tracing::info!("we funded the hbit htlc");
 
-        let herc20_deployed = herc20
-            .watch_for_deployed(herc20_params.clone(), utc_start_of_swap)
-            .await;
+        let hbit_expiry = hbit_params.shared.expiry;
+
+        let herc20_deployed = match before_expiry(
+            herc20.watch_for_deployed(herc20_params.clone(), utc_start_of_swap),
+            &hbit,
+            hbit_expiry,
+        )
+        .await
+        {
+            Some(herc20_deployed) => herc20_deployed,
+            None => {
+                refund_hbit(
+                    &co,
+                    &hbit,
+                    &secp,
+                    &hbit_params,
+                    hbit_funded,
+                    utc_start_of_swap,
+                )
+                .await;
+                return;
+            }
+        };
 
         tracing::info!("bob deployed the herc20 htlc");
 
-        match herc20
-            .watch_for_funded(herc20_params.clone(), herc20_deployed, utc_start_of_swap)
-            .await
+        match before_expiry(
+            herc20.watch_for_funded(herc20_params.clone(), herc20_deployed, utc_start_of_swap),
+            &hbit,
+            hbit_expiry,
+        )
+        .await
         {
-            Ok(_) => {}
-            Err(e) => {
+            Some(Ok(_)) => {}
+            Some(Err(e)) => {
                 co.yield_(Err(Error::BetaIncorrectlyFunded(e))).await;
                 return;
             }
+            None => {
+                refund_hbit(
+                    &co,
+                    &hbit,
+                    &secp,
+                    &hbit_params,
+                    hbit_funded,
+                    utc_start_of_swap,
+                )
+                .await;
+                return;
+            }
         };
 
         tracing::info!("bob funded the herc20 htlc");
@@ -59,21 +102,57 @@ where
             secret,
         )))
         .await;
-        let _ = herc20
-            .watch_for_redeemed(herc20_params, herc20_deployed, utc_start_of_swap)
+        let herc20_redeemed = before_expiry(
+            herc20.watch_for_redeemed(herc20_params, herc20_deployed, utc_start_of_swap),
+            &hbit,
+            hbit_expiry,
+        )
+        .await;
+
+        if herc20_redeemed.is_none() {
+            refund_hbit(
+                &co,
+                &hbit,
+                &secp,
+                &hbit_params,
+                hbit_funded,
+                utc_start_of_swap,
+            )
             .await;
+            return;
+        }
 
         tracing::info!("we redeemed the herc20 htlc");
 
-        let _ = hbit
-            .watch_for_redeemed(&hbit_params, hbit_funded, utc_start_of_swap)
+        let hbit_redeemed = before_expiry(
+            hbit.watch_for_redeemed(&hbit_params, hbit_funded, utc_start_of_swap),
+            &hbit,
+            hbit_expiry,
+        )
+        .await;
+
+        if hbit_redeemed.is_none() {
+            refund_hbit(
+                &co,
+                &hbit,
+                &secp,
+                &hbit_params,
+                hbit_funded,
+                utc_start_of_swap,
+            )
             .await;
+            return;
+        }
 
         tracing::info!("bob redeemed the hbit htlc");
     })
 }
 
 /// Execute a Hbit<->Herc20 swap for Bob.
+///
+/// Once Bob has funded the herc20 HTLC, waiting for Alice to redeem it is
+/// raced against its expiry. If the expiry elapses first, the swap yields the
+/// refund action.
 pub fn hbit_herc20_bob<A, B, C>(
     hbit: A,
     herc20: B,
@@ -84,7 +163,12 @@ pub fn hbit_herc20_bob<A, B, C>(
 ) -> impl Stream<Item = Result<Action, Error<hbit::IncorrectlyFunded, herc20::IncorrectlyFunded>>>
 where
     A: hbit::WatchForFunded + hbit::WatchForRedeemed,
-    B: herc20::WatchForDeployed + herc20::WatchForFunded + herc20::WatchForRedeemed,
+    B: herc20::WatchForDeployed
+        + herc20::WatchForFunded
+        + herc20::WatchForRedeemed
+        + herc20::WatchForRedeemedInMempool
+        + herc20::WatchForRefunded
+        + CurrentTime,
     C: Signing,
 {
     Gen::new(|co| async move {
@@ -127,21 +211,67 @@ where
 
         tracing::info!("we funded the herc20 htlc");
 
-        let herc20_redeemed = herc20
-            .watch_for_redeemed(herc20_params.clone(), herc20_deployed, utc_start_of_swap)
-            .await;
+        let secret = match before_expiry(
+            revealed_secret(
+                herc20
+                    .watch_for_redeemed(herc20_params.clone(), herc20_deployed, utc_start_of_swap)
+                    .map(|redeemed| redeemed.secret),
+                herc20.watch_for_redeemed_in_mempool(herc20_params.clone(), herc20_deployed),
+            ),
+            &herc20,
+            herc20_params.expiry,
+        )
+        .await
+        {
+            Some(secret) => secret,
+            None => {
+                refund_herc20(
+                    &co,
+                    &herc20,
+                    herc20_params,
+                    herc20_deployed,
+                    utc_start_of_swap,
+                )
+                .await;
+                return;
+            }
+        };
 
-        tracing::info!("alice redeemed the herc20 htlc");
+        tracing::info!("alice revealed the secret");
 
         co.yield_(Ok(Action::HbitRedeem(
-            hbit_params.build_redeem_action(&secp, hbit_funded.location, herc20_redeemed.secret),
-            herc20_redeemed.secret,
+            hbit_params.build_redeem_action(&secp, hbit_funded.location, secret),
+            secret,
         )))
         .await;
-        let _ = hbit
-            .watch_for_redeemed(&hbit_params, hbit_funded, utc_start_of_swap)
-            .await;
+
+        // The secret may stem from a redeem transaction that was never mined.
+        // Alice cannot redeem our HTLC after its expiry, hence we refund it.
+        let (_, herc20_redeemed) = future::join(
+            hbit.watch_for_redeemed(&hbit_params, hbit_funded, utc_start_of_swap),
+            before_expiry(
+                herc20.watch_for_redeemed(
+                    herc20_params.clone(),
+                    herc20_deployed,
+                    utc_start_of_swap,
+                ),
+                &herc20,
+                herc20_params.expiry,
+            ),
+        )
+        .await;
 
         tracing::info!("we redeemed the hbit htlc");
+
+        if herc20_redeemed.is_none() {
+            refund_herc20(
+                &co,
+                &herc20,
+                herc20_params,
+                herc20_deployed,
+                utc_start_of_swap,
+            )
+            .await;
+        }
     })
 }
@@ -0,0 +1,219 @@
+use crate::{
+    expiries::CurrentTime,
+    swap::{before_expiry, hbit, heth, refund_hbit, refund_heth, revealed_secret, Action, Error},
+    Secret,
+};
+use bitcoin::secp256k1::{Secp256k1, Signing};
+use futures::{future, FutureExt, Stream};
+use genawaiter::sync::Gen;
+use time::OffsetDateTime;
+
+/// Execute a Hbit<->Heth swap for Alice.
+///
+/// Once Alice has funded the hbit HTLC, every subsequent step is raced against
+/// its expiry. If the expiry elapses first, the swap yields the refund action.
+pub fn hbit_heth_alice<A, B, C>(
+    hbit: A,
+    heth: B,
+    secp: Secp256k1<C>,
+    hbit_params: hbit::Params,
+    heth_params: heth::Params,
+    secret: Secret,
+    utc_start_of_swap: OffsetDateTime,
+) -> impl Stream<Item = Result<Action, Error<hbit::IncorrectlyFunded, heth::IncorrectlyFunded>>>
+where
+    A: hbit::WatchForFunded + hbit::WatchForRedeemed + hbit::WatchForRefunded + CurrentTime,
+    B: heth::WatchForFunded + heth::WatchForRedeemed,
+    C: Signing,
+{
+    Gen::new(|co| async move {
+        tracing::info!("starting swap");
+
+        co.yield_(Ok(Action::HbitFund(hbit_params.build_fund_action())))
+            .await;
+        let hbit_funded = match hbit.watch_for_funded(&hbit_params, utc_start_of_swap).await {
+            Ok(hbit_funded) => hbit_funded,
+            Err(e) => {
+                co.yield_(Err(Error::AlphaIncorrectlyFunded(e))).await;
+                return;
+            }
+        };
+
+        tracing::info!("we funded the hbit htlc");
+
+        let hbit_expiry = hbit_params.shared.expiry;
+
+        let heth_funded = match before_expiry(
+            heth.watch_for_funded(heth_params.clone(), utc_start_of_swap),
+            &hbit,
+            hbit_expiry,
+        )
+        .await
+        {
+            Some(Ok(heth_funded)) => heth_funded,
+            Some(Err(e)) => {
+                co.yield_(Err(Error::BetaIncorrectlyFunded(e))).await;
+                return;
+            }
+            None => {
+                refund_hbit(
+                    &co,
+                    &hbit,
+                    &secp,
+                    &hbit_params,
+                    hbit_funded,
+                    utc_start_of_swap,
+                )
+                .await;
+                return;
+            }
+        };
+
+        tracing::info!("bob funded the heth htlc");
+
+        co.yield_(Ok(Action::HethRedeem(
+            heth_params.build_redeem_action(heth_funded.location, secret),
+            secret,
+        )))
+        .await;
+        let heth_redeemed = before_expiry(
+            heth.watch_for_redeemed(heth_params, heth_funded, utc_start_of_swap),
+            &hbit,
+            hbit_expiry,
+        )
+        .await;
+
+        if heth_redeemed.is_none() {
+            refund_hbit(
+                &co,
+                &hbit,
+                &secp,
+                &hbit_params,
+                hbit_funded,
+                utc_start_of_swap,
+            )
+            .await;
+            return;
+        }
+
+        tracing::info!("we redeemed the heth htlc");
+
+        let hbit_redeemed = before_expiry(
+            hbit.watch_for_redeemed(&hbit_params, hbit_funded, utc_start_of_swap),
+            &hbit,
+            hbit_expiry,
+        )
+        .await;
+
+        if hbit_redeemed.is_none() {
+            refund_hbit(
+                &co,
+                &hbit,
+                &secp,
+                &hbit_params,
+                hbit_funded,
+                utc_start_of_swap,
+            )
+            .await;
+            return;
+        }
+
+        tracing::info!("bob redeemed the hbit htlc");
+    })
+}
+
+/// Execute a Hbit<->Heth swap for Bob.
+///
+/// Once Bob has funded the heth HTLC, waiting for Alice to redeem it is raced
+/// against its expiry. If the expiry elapses first, the swap yields the refund
+/// action.
+pub fn hbit_heth_bob<A, B, C>(
+    hbit: A,
+    heth: B,
+    secp: Secp256k1<C>,
+    hbit_params: hbit::Params,
+    heth_params: heth::Params,
+    utc_start_of_swap: OffsetDateTime,
+) -> impl Stream<Item = Result<Action, Error<hbit::IncorrectlyFunded, heth::IncorrectlyFunded>>>
+where
+    A: hbit::WatchForFunded + hbit::WatchForRedeemed,
+    B: heth::WatchForFunded
+        + heth::WatchForRedeemed
+        + heth::WatchForRedeemedInMempool
+        + heth::WatchForRefunded
+        + CurrentTime,
+    C: Signing,
+{
+    Gen::new(|co| async move {
+        tracing::info!("starting swap");
+
+        let hbit_funded = match hbit.watch_for_funded(&hbit_params, utc_start_of_swap).await {
+            Ok(hbit_funded) => hbit_funded,
+            Err(e) => {
+                co.yield_(Err(Error::AlphaIncorrectlyFunded(e))).await;
+                return;
+            }
+        };
+
+        tracing::info!("alice funded the hbit htlc");
+
+        co.yield_(Ok(Action::HethFund(heth_params.build_fund_action())))
+            .await;
+        let heth_funded = match heth
+            .watch_for_funded(heth_params.clone(), utc_start_of_swap)
+            .await
+        {
+            Ok(heth_funded) => heth_funded,
+            Err(e) => {
+                co.yield_(Err(Error::BetaIncorrectlyFunded(e))).await;
+                return;
+            }
+        };
+
+        tracing::info!("we funded the heth htlc");
+
+        let secret = match before_expiry(
+            revealed_secret(
+                heth.watch_for_redeemed(heth_params.clone(), heth_funded, utc_start_of_swap)
+                    .map(|redeemed| redeemed.secret),
+                heth.watch_for_redeemed_in_mempool(heth_params.clone(), heth_funded),
+            ),
+            &heth,
+            heth_params.expiry,
+        )
+        .await
+        {
+            Some(secret) => secret,
+            None => {
+                refund_heth(&co, &heth, heth_params, heth_funded, utc_start_of_swap).await;
+                return;
+            }
+        };
+
+        tracing::info!("alice revealed the secret");
+
+        co.yield_(Ok(Action::HbitRedeem(
+            hbit_params.build_redeem_action(&secp, hbit_funded.location, secret),
+            secret,
+        )))
+        .await;
+
+        // The secret may stem from a redeem transaction that was never mined.
+        // Alice cannot redeem our HTLC after its expiry, hence we refund it.
+        let (_, heth_redeemed) = future::join(
+            hbit.watch_for_redeemed(&hbit_params, hbit_funded, utc_start_of_swap),
+            before_expiry(
+                heth.watch_for_redeemed(heth_params.clone(), heth_funded, utc_start_of_swap),
+                &heth,
+                heth_params.expiry,
+            ),
+        )
+        .await;
+
+        tracing::info!("we redeemed the hbit htlc");
+
+        if heth_redeemed.is_none() {
+            refund_heth(&co, &heth, heth_params, heth_funded, utc_start_of_swap).await;
+        }
+    })
+}
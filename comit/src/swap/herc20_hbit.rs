@@ -1,13 +1,20 @@
 use crate::{
-    swap::{hbit, herc20, Action, Error},
+    expiries::CurrentTime,
+    swap::{
+        before_expiry, hbit, herc20, refund_hbit, refund_herc20, revealed_secret, Action, Error,
+    },
     Secret,
 };
 use bitcoin::secp256k1::{Secp256k1, Signing};
-use futures::Stream;
+use futures::{future, FutureExt, Stream};
 use genawaiter::sync::Gen;
 use time::OffsetDateTime;
 
 /// Execute a Herc20<->Hbit swap for Alice.
+///
+/// Once Alice has funded the herc20 HTLC, every subsequent step is raced
+/// against its expiry. If the expiry elapses first, the swap yields the refund
+/// action.
 pub fn herc20_hbit_alice<A, B, C>(
     herc20: A,
     hbit: B,
@@ -18,7 +25,11 @@ pub fn herc20_hbit_alice<A, B, C>(
     utc_start_of_swap: OffsetDateTime,
 ) -> impl Stream<Item = Result<Action, Error<herc20::IncorrectlyFunded, hbit::IncorrectlyFunded>>>
 where
-    A: herc20::WatchForDeployed + herc20::WatchForFunded + herc20::WatchForRedeemed,
+    A: herc20::WatchForDeployed
+        + herc20::WatchForFunded
+        + herc20::WatchForRedeemed
+        + herc20::WatchForRefunded
+        + CurrentTime,
     B: hbit::WatchForRedeemed + hbit::WatchForFunded,
     C: Signing,
 {
@@ -52,12 +63,31 @@ where
 
         tracing::info!("we funded the herc20 htlc");
 
-        let hbit_funded = match hbit.watch_for_funded(&hbit_params, utc_start_of_swap).await {
-            Ok(hbit_funded) => hbit_funded,
-            Err(e) => {
+        let herc20_expiry = herc20_params.expiry;
+
+        let hbit_funded = match before_expiry(
+            hbit.watch_for_funded(&hbit_params, utc_start_of_swap),
+            &herc20,
+            herc20_expiry,
+        )
+        .await
+        {
+            Some(Ok(hbit_funded)) => hbit_funded,
+            Some(Err(e)) => {
                 co.yield_(Err(Error::BetaIncorrectlyFunded(e))).await;
                 return;
             }
+            None => {
+                refund_herc20(
+                    &co,
+                    &herc20,
+                    herc20_params,
+                    herc20_deployed,
+                    utc_start_of_swap,
+                )
+                .await;
+                return;
+            }
         };
 
         tracing::info!("bob funded the hbit htlc");
@@ -67,31 +97,71 @@ where
             secret,
         )))
         .await;
-        let _ = hbit
-            .watch_for_redeemed(&hbit_params, hbit_funded, utc_start_of_swap)
+        let hbit_redeemed = before_expiry(
+            hbit.watch_for_redeemed(&hbit_params, hbit_funded, utc_start_of_swap),
+            &herc20,
+            herc20_expiry,
+        )
+        .await;
+
+        if hbit_redeemed.is_none() {
+            refund_herc20(
+                &co,
+                &herc20,
+                herc20_params,
+                herc20_deployed,
+                utc_start_of_swap,
+            )
             .await;
+            return;
+        }
 
         tracing::info!("we redeemed the hbit htlc");
 
-        let _ = herc20
-            .watch_for_redeemed(herc20_params, herc20_deployed, utc_start_of_swap)
+        let herc20_redeemed = before_expiry(
+            herc20.watch_for_redeemed(herc20_params.clone(), herc20_deployed, utc_start_of_swap),
+            &herc20,
+            herc20_expiry,
+        )
+        .await;
+
+        if herc20_redeemed.is_none() {
+            refund_herc20(
+                &co,
+                &herc20,
+                herc20_params,
+                herc20_deployed,
+                utc_start_of_swap,
+            )
             .await;
+            return;
+        }
 
         tracing::info!("bob redeemed the herc20 htlc");
     })
 }
 
 /// Execute a Herc20<->Hbit swap for Bob.
-pub fn herc20_hbit_bob<A, B>(
+///
+/// Once Bob has funded the hbit HTLC, waiting for Alice to redeem it is raced
+/// against its expiry. If the expiry elapses first, the swap yields the refund
+/// action.
+pub fn herc20_hbit_bob<A, B, C>(
     herc20: A,
     hbit: B,
+    secp: Secp256k1<C>,
     herc20_params: herc20::Params,
     hbit_params: hbit::Params,
     utc_start_of_swap: OffsetDateTime,
 ) -> impl Stream<Item = Result<Action, Error<herc20::IncorrectlyFunded, hbit::IncorrectlyFunded>>>
 where
     A: herc20::WatchForDeployed + herc20::WatchForFunded + herc20::WatchForRedeemed,
-    B: hbit::WatchForRedeemed + hbit::WatchForFunded,
+    B: hbit::WatchForRedeemed
+        + hbit::WatchForRedeemedInMempool
+        + hbit::WatchForFunded
+        + hbit::WatchForRefunded
+        + CurrentTime,
+    C: Signing,
 {
     Gen::new(|co| async move {
         tracing::info!("starting swap");
@@ -127,21 +197,64 @@ where
 
         tracing::info!("we funded the hbit htlc");
 
-        let hbit_redeemed = hbit
-            .watch_for_redeemed(&hbit_params, hbit_funded, utc_start_of_swap)
-            .await;
+        let secret = match before_expiry(
+            revealed_secret(
+                hbit.watch_for_redeemed(&hbit_params, hbit_funded, utc_start_of_swap)
+                    .map(|redeemed| redeemed.secret),
+                hbit.watch_for_redeemed_in_mempool(&hbit_params, hbit_funded),
+            ),
+            &hbit,
+            hbit_params.shared.expiry,
+        )
+        .await
+        {
+            Some(secret) => secret,
+            None => {
+                refund_hbit(
+                    &co,
+                    &hbit,
+                    &secp,
+                    &hbit_params,
+                    hbit_funded,
+                    utc_start_of_swap,
+                )
+                .await;
+                return;
+            }
+        };
 
-        tracing::info!("alice redeemed the hbit htlc");
+        tracing::info!("alice revealed the secret");
 
         co.yield_(Ok(Action::Herc20Redeem(
-            herc20_params.build_redeem_action(herc20_deployed.location, hbit_redeemed.secret),
-            hbit_redeemed.secret,
+            herc20_params.build_redeem_action(herc20_deployed.location, secret),
+            secret,
         )))
         .await;
-        let _ = herc20
-            .watch_for_redeemed(herc20_params, herc20_deployed, utc_start_of_swap)
-            .await;
+
+        // The secret may stem from a redeem transaction that was never mined.
+        // Alice cannot redeem our HTLC after its expiry, hence we refund it.
+        let (_, hbit_redeemed) = future::join(
+            herc20.watch_for_redeemed(herc20_params, herc20_deployed, utc_start_of_swap),
+            before_expiry(
+                hbit.watch_for_redeemed(&hbit_params, hbit_funded, utc_start_of_swap),
+                &hbit,
+                hbit_params.shared.expiry,
+            ),
+        )
+        .await;
 
         tracing::info!("we redeemed the herc20 htlc");
+
+        if hbit_redeemed.is_none() {
+            refund_hbit(
+                &co,
+                &hbit,
+                &secp,
+                &hbit_params,
+                hbit_funded,
+                utc_start_of_swap,
+            )
+            .await;
+        }
     })
 }
@@ -0,0 +1,214 @@
+use crate::{
+    expiries::CurrentTime,
+    swap::{before_expiry, hbit, heth, refund_hbit, refund_heth, revealed_secret, Action, Error},
+    Secret,
+};
+use bitcoin::secp256k1::{Secp256k1, Signing};
+use futures::{future, FutureExt, Stream};
+use genawaiter::sync::Gen;
+use time::OffsetDateTime;
+
+/// Execute a Heth<->Hbit swap for Alice.
+///
+/// Once Alice has funded the heth HTLC, every subsequent step is raced against
+/// its expiry. If the expiry elapses first, the swap yields the refund action.
+pub fn heth_hbit_alice<A, B, C>(
+    heth: A,
+    hbit: B,
+    secp: Secp256k1<C>,
+    heth_params: heth::Params,
+    hbit_params: hbit::Params,
+    secret: Secret,
+    utc_start_of_swap: OffsetDateTime,
+) -> impl Stream<Item = Result<Action, Error<heth::IncorrectlyFunded, hbit::IncorrectlyFunded>>>
+where
+    A: heth::WatchForFunded + heth::WatchForRedeemed + heth::WatchForRefunded + CurrentTime,
+    B: hbit::WatchForRedeemed + hbit::WatchForFunded,
+    C: Signing,
+{
+    Gen::new(|co| async move {
+        tracing::info!("starting swap");
+
+        co.yield_(Ok(Action::HethFund(heth_params.build_fund_action())))
+            .await;
+        let heth_funded = match heth
+            .watch_for_funded(heth_params.clone(), utc_start_of_swap)
+            .await
+        {
+            Ok(heth_funded) => heth_funded,
+            Err(e) => {
+                co.yield_(Err(Error::AlphaIncorrectlyFunded(e))).await;
+                return;
+            }
+        };
+
+        tracing::info!("we funded the heth htlc");
+
+        let heth_expiry = heth_params.expiry;
+
+        let hbit_funded = match before_expiry(
+            hbit.watch_for_funded(&hbit_params, utc_start_of_swap),
+            &heth,
+            heth_expiry,
+        )
+        .await
+        {
+            Some(Ok(hbit_funded)) => hbit_funded,
+            Some(Err(e)) => {
+                co.yield_(Err(Error::BetaIncorrectlyFunded(e))).await;
+                return;
+            }
+            None => {
+                refund_heth(&co, &heth, heth_params, heth_funded, utc_start_of_swap).await;
+                return;
+            }
+        };
+
+        tracing::info!("bob funded the hbit htlc");
+
+        co.yield_(Ok(Action::HbitRedeem(
+            hbit_params.build_redeem_action(&secp, hbit_funded.location, secret),
+            secret,
+        )))
+        .await;
+        let hbit_redeemed = before_expiry(
+            hbit.watch_for_redeemed(&hbit_params, hbit_funded, utc_start_of_swap),
+            &heth,
+            heth_expiry,
+        )
+        .await;
+
+        if hbit_redeemed.is_none() {
+            refund_heth(&co, &heth, heth_params, heth_funded, utc_start_of_swap).await;
+            return;
+        }
+
+        tracing::info!("we redeemed the hbit htlc");
+
+        let heth_redeemed = before_expiry(
+            heth.watch_for_redeemed(heth_params.clone(), heth_funded, utc_start_of_swap),
+            &heth,
+            heth_expiry,
+        )
+        .await;
+
+        if heth_redeemed.is_none() {
+            refund_heth(&co, &heth, heth_params, heth_funded, utc_start_of_swap).await;
+            return;
+        }
+
+        tracing::info!("bob redeemed the heth htlc");
+    })
+}
+
+/// Execute a Heth<->Hbit swap for Bob.
+///
+/// Once Bob has funded the hbit HTLC, waiting for Alice to redeem it is raced
+/// against its expiry. If the expiry elapses first, the swap yields the refund
+/// action.
+pub fn heth_hbit_bob<A, B, C>(
+    heth: A,
+    hbit: B,
+    secp: Secp256k1<C>,
+    heth_params: heth::Params,
+    hbit_params: hbit::Params,
+    utc_start_of_swap: OffsetDateTime,
+) -> impl Stream<Item = Result<Action, Error<heth::IncorrectlyFunded, hbit::IncorrectlyFunded>>>
+where
+    A: heth::WatchForFunded + heth::WatchForRedeemed,
+    B: hbit::WatchForRedeemed
+        + hbit::WatchForRedeemedInMempool
+        + hbit::WatchForFunded
+        + hbit::WatchForRefunded
+        + CurrentTime,
+    C: Signing,
+{
+    Gen::new(|co| async move {
+        tracing::info!("starting swap");
+
+        let heth_funded = match heth
+            .watch_for_funded(heth_params.clone(), utc_start_of_swap)
+            .await
+        {
+            Ok(heth_funded) => heth_funded,
+            Err(e) => {
+                co.yield_(Err(Error::AlphaIncorrectlyFunded(e))).await;
+                return;
+            }
+        };
+
+        tracing::info!("alice funded the heth htlc");
+
+        co.yield_(Ok(Action::HbitFund(hbit_params.build_fund_action())))
+            .await;
+        let hbit_funded = match hbit.watch_for_funded(&hbit_params, utc_start_of_swap).await {
+            Ok(hbit_funded) => hbit_funded,
+            Err(e) => {
+                co.yield_(Err(Error::BetaIncorrectlyFunded(e))).await;
+                return;
+            }
+        };
+
+        tracing::info!("we funded the hbit htlc");
+
+        let secret = match before_expiry(
+            revealed_secret(
+                hbit.watch_for_redeemed(&hbit_params, hbit_funded, utc_start_of_swap)
+                    .map(|redeemed| redeemed.secret),
+                hbit.watch_for_redeemed_in_mempool(&hbit_params, hbit_funded),
+            ),
+            &hbit,
+            hbit_params.shared.expiry,
+        )
+        .await
+        {
+            Some(secret) => secret,
+            None => {
+                refund_hbit(
+                    &co,
+                    &hbit,
+                    &secp,
+                    &hbit_params,
+                    hbit_funded,
+                    utc_start_of_swap,
+                )
+                .await;
+                return;
+            }
+        };
+
+        tracing::info!("alice revealed the secret");
+
+        co.yield_(Ok(Action::HethRedeem(
+            heth_params.build_redeem_action(heth_funded.location, secret),
+            secret,
+        )))
+        .await;
+
+        // The secret may stem from a redeem transaction that was never mined.
+        // Alice cannot redeem our HTLC after its expiry, hence we refund it.
+        let (_, hbit_redeemed) = future::join(
+            heth.watch_for_redeemed(heth_params, heth_funded, utc_start_of_swap),
+            before_expiry(
+                hbit.watch_for_redeemed(&hbit_params, hbit_funded, utc_start_of_swap),
+                &hbit,
+                hbit_params.shared.expiry,
+            ),
+        )
+        .await;
+
+        tracing::info!("we redeemed the heth htlc");
+
+        if hbit_redeemed.is_none() {
+            refund_hbit(
+                &co,
+                &hbit,
+                &secp,
+                &hbit_params,
+                hbit_funded,
+                utc_start_of_swap,
+            )
+            .await;
+        }
+    })
+}
@@ -95,6 +95,14 @@ impl From<Timestamp> for i64 {
     }
 }
 
+/// Precision is seconds only i.e., nanoseconds are ignored.
+impl From<time::OffsetDateTime> for Timestamp {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn from(item: time::OffsetDateTime) -> Self {
+        Self(item.timestamp() as u32)
+    }
+}
+
 impl From<crate::ethereum::U256> for Timestamp {
     fn from(value: crate::ethereum::U256) -> Self {
         value.low_u32().into()
@@ -28,13 +28,14 @@ async fn find_transaction_go_back_into_the_past() {
 
     let start_of_swap =
         OffsetDateTime::from_unix_timestamp(block1_with_transaction.header.time as i64);
-    let (expected_transaction, _out_point) = watch_for_created_outpoint(
+    let (expected_transaction, _out_point, _block_height) = watch_for_created_outpoint(
         &connector,
         start_of_swap,
         Address::from_str(
             include_str!("test_data/bitcoin/find_transaction_go_back_into_the_past/address").trim(),
         )
         .unwrap(),
+        1,
     )
     .await
     .unwrap();
@@ -2,7 +2,10 @@ use anyhow::Context;
 use async_trait::async_trait;
 use bitcoin::BlockHash;
 use comit::{
-    btsieve::{BlockByHash, ConnectedNetwork, LatestBlock},
+    btsieve::{
+        bitcoin::{ScriptTransaction, TransactionsByScript},
+        BlockByHash, BlockNotifications, ConnectedNetwork, LatestBlock,
+    },
     ledger,
 };
 use futures::{stream::BoxStream, StreamExt};
@@ -64,6 +67,15 @@ impl BlockByHash for BitcoinConnectorMock {
     }
 }
 
+/// The latest blocks are already handed out one per second, hence there is
+/// nothing to wait for.
+#[async_trait]
+impl BlockNotifications for BitcoinConnectorMock {
+    async fn wait_for_new_block(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl ConnectedNetwork for BitcoinConnectorMock {
     type Network = ledger::Bitcoin;
@@ -73,6 +85,24 @@ impl ConnectedNetwork for BitcoinConnectorMock {
     }
 }
 
+/// Like bitcoind, the mock makes the watchers scan the blocks.
+#[async_trait]
+impl TransactionsByScript for BitcoinConnectorMock {
+    async fn transactions_by_script(
+        &self,
+        _script: &bitcoin::Script,
+    ) -> anyhow::Result<Option<Vec<ScriptTransaction>>> {
+        Ok(None)
+    }
+
+    async fn transaction_by_txid(
+        &self,
+        txid: bitcoin::Txid,
+    ) -> anyhow::Result<bitcoin::Transaction> {
+        anyhow::bail!("transaction {} cannot be looked up by its id", txid)
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("ran out of blocks in chain")]
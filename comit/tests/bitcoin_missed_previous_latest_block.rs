@@ -30,7 +30,7 @@ async fn find_transaction_missed_previous_latest_block() {
     // otherwise we run into the problem, that we try to fetch blocks prior to the
     // first one
     let start_of_swap = OffsetDateTime::from_unix_timestamp(block1.header.time as i64 + 1);
-    let (expected_transaction, _out_point) = watch_for_created_outpoint(
+    let (expected_transaction, _out_point, _block_height) = watch_for_created_outpoint(
         &connector,
         start_of_swap,
         Address::from_str(
@@ -38,6 +38,7 @@ async fn find_transaction_missed_previous_latest_block() {
                 .trim(),
         )
         .unwrap(),
+        1,
     )
     .await
     .unwrap();
@@ -77,7 +78,7 @@ async fn find_transaction_missed_previous_latest_block_with_big_gap() {
     // otherwise we run into the problem, that we try to fetch blocks prior to the
     // first one
     let start_of_swap = OffsetDateTime::from_unix_timestamp(block1.header.time as i64 + 1);
-    let (expected_transaction, _out_point) = watch_for_created_outpoint(
+    let (expected_transaction, _out_point, _block_height) = watch_for_created_outpoint(
         &connector,
         start_of_swap,
         Address::from_str(
@@ -87,6 +88,7 @@ async fn find_transaction_missed_previous_latest_block_with_big_gap() {
             .trim(),
         )
         .unwrap(),
+        1,
     )
     .await
     .unwrap();
@@ -115,7 +117,7 @@ async fn find_transaction_if_blockchain_reorganisation() {
     );
 
     let start_of_swap = OffsetDateTime::now_utc();
-    let (expected_transaction, _out_point) = watch_for_created_outpoint(
+    let (expected_transaction, _out_point, _block_height) = watch_for_created_outpoint(
         &connector,
         start_of_swap,
         Address::from_str(
@@ -123,6 +125,7 @@ async fn find_transaction_if_blockchain_reorganisation() {
                 .trim(),
         )
         .unwrap(),
+        1,
     )
     .await
     .unwrap();
@@ -154,13 +157,13 @@ async fn find_transaction_if_blockchain_reorganisation_with_long_chain() {
     );
 
     let start_of_swap = OffsetDateTime::now_utc();
-    let (expected_transaction, _out_point) = watch_for_created_outpoint(&connector, start_of_swap, Address::from_str(
+    let (expected_transaction, _out_point, _block_height) = watch_for_created_outpoint(&connector, start_of_swap, Address::from_str(
         include_str!(
             "test_data/bitcoin/find_transaction_if_blockchain_reorganisation_with_long_chain/address"
         ).trim()
         ,
     )
-        .unwrap(),)
+        .unwrap(), 1)
         .await
         .unwrap();
 
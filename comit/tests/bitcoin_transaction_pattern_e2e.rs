@@ -68,10 +68,11 @@ mod tests {
                 .await
                 .expect("failed to send money to address");
 
-        let (funding_transaction, _out_point) = watch_for_created_outpoint(
+        let (funding_transaction, _out_point, _block_height) = watch_for_created_outpoint(
             &connector,
             start_of_swap,
             target_address.to_string().parse().unwrap(),
+            1,
         )
         .await
         .unwrap();
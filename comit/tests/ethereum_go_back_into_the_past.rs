@@ -49,7 +49,7 @@ async fn find_transaction_go_back_into_the_past() {
         OffsetDateTime::from_unix_timestamp(block1_with_transaction.timestamp.as_u32() as i64);
 
     let (got_transaction, got_receipt) =
-        matching_transaction_and_receipt(&connector, start_of_swap, {
+        matching_transaction_and_receipt(&connector, start_of_swap, 1, {
             |transaction| transaction.to == want_transaction.to
         })
         .await
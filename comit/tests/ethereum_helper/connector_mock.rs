@@ -1,7 +1,9 @@
 use anyhow::Context;
 use async_trait::async_trait;
 use comit::{
-    btsieve::{ethereum::ReceiptByHash, BlockByHash, ConnectedNetwork, LatestBlock},
+    btsieve::{
+        ethereum::ReceiptByHash, BlockByHash, BlockNotifications, ConnectedNetwork, LatestBlock,
+    },
     ethereum::{Block, ChainId, Hash, TransactionReceipt},
 };
 use futures::{stream::BoxStream, StreamExt};
@@ -81,6 +83,15 @@ impl ReceiptByHash for EthereumConnectorMock {
     }
 }
 
+/// The latest blocks are already handed out one per second, hence there is
+/// nothing to wait for.
+#[async_trait]
+impl BlockNotifications for EthereumConnectorMock {
+    async fn wait_for_new_block(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl ConnectedNetwork for EthereumConnectorMock {
     type Network = ChainId;
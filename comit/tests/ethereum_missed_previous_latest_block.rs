@@ -46,7 +46,7 @@ async fn find_transaction_missed_previous_latest_block_single_block_gap() {
     let start_of_swap = OffsetDateTime::from_unix_timestamp(block2.timestamp.as_u32() as i64);
 
     let (got_transaction, got_receipt) =
-        matching_transaction_and_receipt(&connector, start_of_swap, {
+        matching_transaction_and_receipt(&connector, start_of_swap, 1, {
             |transaction| transaction.to == want_transaction.to
         })
         .await
@@ -100,7 +100,7 @@ async fn find_transaction_missed_previous_latest_block_two_block_gap() {
     let start_of_swap = OffsetDateTime::from_unix_timestamp(block2.timestamp.as_u32() as i64);
 
     let (got_transaction, got_receipt) =
-        matching_transaction_and_receipt(&connector, start_of_swap, {
+        matching_transaction_and_receipt(&connector, start_of_swap, 1, {
             |transaction| transaction.to == want_transaction.to
         })
         .await
@@ -14,6 +14,14 @@ pub const SATS_IN_BITCOIN_EXP: u16 = 8;
 // Had a size of 2073vB. 3000 seems to be a safe bet.
 pub const MAX_EXPECTED_TRANSACTION_VBYTE_WEIGHT: u64 = 3000;
 
+// A fund transaction of the HTLC usually spends one or two segwit inputs and
+// has the HTLC and a change output, this is its typical size.
+pub const EXPECTED_FUND_TRANSACTION_VBYTE_WEIGHT: u64 = 250;
+
+// A redeem transaction spends the HTLC output revealing the secret and pays to
+// one output of our wallet.
+pub const EXPECTED_REDEEM_TRANSACTION_VBYTE_WEIGHT: u64 = 150;
+
 #[cfg(test)]
 pub mod amount {
     use super::*;
@@ -119,8 +119,8 @@ pub enum CreateTransaction {
         /// `<txid>:<vout>`. Only required for swaps where nectar buys BTC/DAI.
         #[structopt(long)]
         outpoint: Option<::bitcoin::OutPoint>,
-        /// The Ethereum address where the `herc20` HTLC is located. Only
-        /// required for swaps where nectar sells BTC/DAI.
+        /// The Ethereum address where the `herc20` or `heth` HTLC is located.
+        /// Only required for swaps where nectar sells BTC/DAI.
         #[structopt(long)]
         address: Option<ethereum::Address>,
         /// The actual amount that was sent to the `hbit` HTLC. If this is not
@@ -137,8 +137,8 @@ pub enum CreateTransaction {
         /// `<txid>:<vout>`. Only required for swaps where nectar sells BTC/DAI.
         #[structopt(long)]
         outpoint: Option<::bitcoin::OutPoint>,
-        /// The Ethereum address where the `herc20` HTLC is located. Only
-        /// required for swaps where nectar buys BTC/DAI.
+        /// The Ethereum address where the `herc20` or `heth` HTLC is located.
+        /// Only required for swaps where nectar buys BTC/DAI.
         #[structopt(long)]
         address: Option<ethereum::Address>,
         /// The actual amount that was sent to the `hbit` HTLC. If this is not
@@ -200,9 +200,17 @@ pub fn into_history_trade(
 ) -> history::Trade {
     use crate::history::*;
 
-    let (swap, position) = match swap {
-        SwapKind::HbitHerc20(swap) => (swap, history::Position::Sell),
-        SwapKind::Herc20Hbit(swap) => (swap, history::Position::Buy),
+    let position = match swap {
+        SwapKind::HbitHerc20(_) | SwapKind::HbitHeth(_) => history::Position::Sell,
+        SwapKind::Herc20Hbit(_) | SwapKind::HethHbit(_) => history::Position::Buy,
+    };
+    let (quote_symbol, quote_amount) = match &swap {
+        SwapKind::HbitHerc20(swap) | SwapKind::Herc20Hbit(swap) => {
+            (Symbol::Dai, swap.herc20_params.asset.quantity.to_wei_dec())
+        }
+        SwapKind::HbitHeth(swap) | SwapKind::HethHbit(swap) => {
+            (Symbol::Eth, swap.heth_params.asset.to_wei_dec())
+        }
     };
 
     #[cfg(test)]
@@ -210,13 +218,13 @@ pub fn into_history_trade(
         OffsetDateTime::parse("2020-07-10T17:48:26.123+10:00", time::Format::Rfc3339).unwrap();
 
     Trade {
-        utc_start_timestamp: swap.start_of_swap,
+        utc_start_timestamp: swap.start_of_swap(),
         utc_final_timestamp: final_timestamp,
         base_symbol: Symbol::Btc,
-        quote_symbol: Symbol::Dai,
+        quote_symbol,
         position,
-        base_precise_amount: swap.hbit_params.shared.asset.as_sat().into(),
-        quote_precise_amount: BigUint::from_str(&swap.herc20_params.asset.quantity.to_wei_dec())
+        base_precise_amount: swap.hbit_params().shared.asset.as_sat().into(),
+        quote_precise_amount: BigUint::from_str(&quote_amount)
             .expect("number to number conversion"),
         peer: peer_id,
     }
@@ -227,14 +235,23 @@ pub struct FinishedSwap {
     pub swap: SwapKind,
     pub peer: ActivePeer,
     pub final_timestamp: OffsetDateTime,
+    /// Whether the swap ended with us refunding our HTLC, i.e. no trade
+    /// happened.
+    pub refunded: bool,
 }
 
 impl FinishedSwap {
-    pub fn new(swap: SwapKind, taker: ActivePeer, final_timestamp: OffsetDateTime) -> Self {
+    pub fn new(
+        swap: SwapKind,
+        taker: ActivePeer,
+        final_timestamp: OffsetDateTime,
+        refunded: bool,
+    ) -> Self {
         Self {
             swap,
             peer: taker,
             final_timestamp,
+            refunded,
         }
     }
 }
@@ -4,9 +4,8 @@ use crate::{
     config::Settings,
     ethereum,
     history::History,
-    swap::{Database, SwapExecutor},
+    swap::{self, Database, SwapExecutor},
 };
-use comit::btsieve::{bitcoin::BitcoindConnector, ethereum::Web3Connector};
 use futures::{future::TryFutureExt, StreamExt};
 use std::sync::Arc;
 
@@ -24,14 +23,39 @@ pub async fn resume_only(
     let db = Arc::new(db);
     let mut history = History::new(settings.data.dir.join("history.csv").as_path())?;
 
+    let block_store = swap::open_block_store(&settings.data)?;
+    let bitcoin_connector = Arc::new(swap::bitcoin::new_connector(
+        &settings.bitcoin,
+        block_store.as_ref(),
+    )?);
+    let ethereum_connector = Arc::new(swap::ethereum::new_connector(
+        &settings.ethereum,
+        block_store.as_ref(),
+    )?);
+    if let Some(block_store) = block_store {
+        let db = Arc::clone(&db);
+        tokio::spawn(block_store.prune_periodically(move || {
+            futures::future::ready(swap::start_of_oldest_active_swap(&db))
+        }));
+    }
+
+    tokio::spawn(swap::follow_chains(
+        Arc::clone(&bitcoin_connector),
+        Arc::clone(&ethereum_connector),
+    ));
+
     let (executor, mut finished_swap_receiver) = SwapExecutor::new(
         db.clone(),
         Arc::new(bitcoin_wallet),
         bitcoin_fee,
         Arc::new(ethereum_wallet),
         ethereum_gas_price,
-        Arc::new(BitcoindConnector::new(settings.bitcoin.bitcoind.node_url)?),
-        Arc::new(Web3Connector::new(settings.ethereum.node_url)),
+        bitcoin_connector,
+        ethereum_connector,
+    );
+    let executor = executor.with_mempool_watching(
+        settings.bitcoin.watch_mempool,
+        settings.ethereum.watch_mempool,
     );
 
     for swap in db.all_active_swaps()? {
@@ -46,20 +70,22 @@ pub async fn resume_only(
 }
 
 fn handle_finished_swap(finished_swap: FinishedSwap, db: &Database, history: &mut History) {
-    let trade = into_history_trade(
-        finished_swap.peer.peer_id(),
-        finished_swap.swap.clone(),
-        #[cfg(not(test))]
-        finished_swap.final_timestamp,
-    );
+    if !finished_swap.refunded {
+        let trade = into_history_trade(
+            finished_swap.peer.peer_id(),
+            finished_swap.swap.clone(),
+            #[cfg(not(test))]
+            finished_swap.final_timestamp,
+        );
 
-    let _ = history.write(trade).map_err(|error| {
-        tracing::error!(
-            "Unable to register history entry: {:#}; {:?}",
-            error,
-            finished_swap
-        )
-    });
+        let _ = history.write(trade).map_err(|error| {
+            tracing::error!(
+                "Unable to register history entry: {:#}; {:?}",
+                error,
+                finished_swap
+            )
+        });
+    }
 
     let swap_id = finished_swap.swap.swap_id();
 
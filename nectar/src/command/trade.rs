@@ -3,20 +3,21 @@ mod event_loop;
 use crate::{
     bitcoin,
     command::trade::event_loop::EventLoop,
-    config::{KrakenApiHost, Settings},
+    config::Settings,
     ethereum::{self, dai},
     history::History,
-    maker::strategy,
-    mid_market_rate::get_btc_dai_mid_market_rate,
+    maker::{
+        fees::SwapFees,
+        risk::RiskLimits,
+        strategy::{self, Strategy},
+    },
+    mid_market_rate,
     network::{self, new_swarm},
-    swap::{Database, SwapExecutor, SwapKind, SwapParams},
+    swap::{self, Database, HethSwapParams, SwapExecutor, SwapKind, SwapParams},
     Maker, MidMarketRate, Seed, Spread,
 };
 use anyhow::Context;
-use comit::{
-    btsieve::{bitcoin::BitcoindConnector, ethereum::Web3Connector},
-    Role,
-};
+use comit::Role;
 use futures::{channel::mpsc, Future, SinkExt};
 use futures_timer::Delay;
 use std::{sync::Arc, time::Duration};
@@ -33,9 +34,27 @@ pub async fn trade(
 
     let bitcoind_client = bitcoin::Client::new(settings.bitcoin.bitcoind.node_url.clone());
 
+    let mut rate_aggregator = mid_market_rate::Aggregator::from_settings(&settings.maker);
+
+    let bitcoin_fee = bitcoin::Fee::new(settings.bitcoin.clone(), bitcoind_client.clone());
+    let ethereum_gas_price = ethereum::GasPrice::new(settings.ethereum.gas_price.clone());
+    let kraken = mid_market_rate::Kraken::new(settings.maker.kraken_api_host.clone());
+
+    // The fees are filled in by the first fee update if they are not
+    // available yet, orders do not include them until then.
+    let initial_fees = match swap_fees(&bitcoin_fee, &ethereum_gas_price, &kraken).await {
+        Ok(fees) => Some(fees),
+        Err(e) => {
+            tracing::warn!("Starting without fees: {:#}", e);
+            None
+        }
+    };
+
     let mut maker = init_maker(
+        &mut rate_aggregator,
+        initial_fees,
         Arc::clone(&bitcoin_wallet),
-        bitcoind_client.clone(),
+        bitcoind_client,
         Arc::clone(&ethereum_wallet),
         settings.clone(),
         network,
@@ -50,38 +69,54 @@ pub async fn trade(
 
     let mut swarm = new_swarm(network::Seed::new(seed.bytes()), &settings)?;
 
-    let initial_sell_order = maker
-        .new_sell_order()
-        .context("Could not generate sell order")?;
-
-    let initial_buy_order = maker
-        .new_buy_order()
-        .context("Could not generate buy order")?;
+    let initial_orders = maker
+        .new_orders()
+        .context("Could not generate initial orders")?;
 
-    swarm.orderbook.publish(initial_sell_order);
-    swarm.orderbook.publish(initial_buy_order);
+    swarm
+        .orderbook
+        .replace_own_orders(initial_orders.into_orders());
 
     let update_interval = Duration::from_secs(15u64);
 
     let (rate_future, rate_update_receiver) =
-        init_rate_updates(Duration::from_secs(5 * 60), settings.maker.kraken_api_host);
+        init_rate_updates(Duration::from_secs(5 * 60), rate_aggregator);
     let (btc_balance_future, btc_balance_update_receiver) =
         init_bitcoin_balance_updates(update_interval, Arc::clone(&bitcoin_wallet));
     let (dai_balance_future, dai_balance_update_receiver) =
         init_dai_balance_updates(update_interval, Arc::clone(&ethereum_wallet));
+    let (fee_future, fee_update_receiver) = init_fee_updates(
+        Duration::from_secs(60),
+        bitcoin_fee.clone(),
+        ethereum_gas_price.clone(),
+        kraken,
+    );
 
     tokio::spawn(rate_future);
     tokio::spawn(btc_balance_future);
     tokio::spawn(dai_balance_future);
+    tokio::spawn(fee_future);
 
-    let bitcoin_connector = Arc::new(BitcoindConnector::new(
-        settings.bitcoin.bitcoind.node_url.clone(),
+    let block_store = swap::open_block_store(&settings.data)?;
+    let bitcoin_connector = Arc::new(swap::bitcoin::new_connector(
+        &settings.bitcoin,
+        block_store.as_ref(),
     )?);
-    let ethereum_connector = Arc::new(Web3Connector::new(settings.ethereum.node_url.clone()));
-
-    let bitcoin_fee = bitcoin::Fee::new(settings.bitcoin.clone(), bitcoind_client);
+    let ethereum_connector = Arc::new(swap::ethereum::new_connector(
+        &settings.ethereum,
+        block_store.as_ref(),
+    )?);
+    if let Some(block_store) = block_store {
+        let db = Arc::clone(&db);
+        tokio::spawn(block_store.prune_periodically(move || {
+            futures::future::ready(swap::start_of_oldest_active_swap(&db))
+        }));
+    }
 
-    let ethereum_gas_price = ethereum::GasPrice::new(settings.ethereum.gas_price);
+    tokio::spawn(swap::follow_chains(
+        Arc::clone(&bitcoin_connector),
+        Arc::clone(&ethereum_connector),
+    ));
 
     let (swap_executor, swap_execution_finished_receiver) = SwapExecutor::new(
         Arc::clone(&db),
@@ -92,6 +127,10 @@ pub async fn trade(
         bitcoin_connector,
         ethereum_connector,
     );
+    let swap_executor = swap_executor.with_mempool_watching(
+        settings.bitcoin.watch_mempool,
+        settings.ethereum.watch_mempool,
+    );
 
     respawn_swaps(Arc::clone(&db), &mut maker, swap_executor.clone())
         .context("Could not respawn swaps")?;
@@ -114,11 +153,14 @@ pub async fn trade(
             rate_update_receiver,
             btc_balance_update_receiver,
             dai_balance_update_receiver,
+            fee_update_receiver,
         )
         .await
 }
 
 async fn init_maker(
+    rate_aggregator: &mut mid_market_rate::Aggregator,
+    initial_fees: Option<SwapFees>,
     bitcoin_wallet: Arc<bitcoin::Wallet>,
     bitcoind_client: bitcoin::Client,
     ethereum_wallet: Arc<ethereum::Wallet>,
@@ -137,35 +179,40 @@ async fn init_maker(
 
     let btc_dai = settings.maker.btc_dai;
 
-    let initial_rate = get_btc_dai_mid_market_rate(&settings.maker.kraken_api_host)
+    let initial_rate = rate_aggregator
+        .btc_dai_mid_market_rate()
         .await
         .context("Could not get rate")?;
 
     let spread: Spread = settings.maker.spread;
 
-    let strategy = strategy::AllIn::new(
+    let strategy = strategy::AnyStrategy::new(
+        settings.maker.strategy,
         settings.bitcoin.clone(),
-        btc_dai.max_buy_quantity,
-        btc_dai.max_sell_quantity,
+        btc_dai,
         spread,
+        settings.maker.skew,
+        settings.maker.max_fee_share,
         bitcoind_client,
-    );
+    )?;
 
     Ok(Maker::new(
         initial_btc_balance,
         initial_dai_balance,
         initial_rate,
+        initial_fees,
         strategy,
         settings.bitcoin.network,
         settings.ethereum.chain,
         Role::Bob,
         network,
-    ))
+    )
+    .with_risk_limits(RiskLimits::new(settings.maker.risk)))
 }
 
 fn init_rate_updates(
     update_interval: Duration,
-    kraken_api_host: KrakenApiHost,
+    mut rate_aggregator: mid_market_rate::Aggregator,
 ) -> (
     impl Future<Output = comit::Never> + Send,
     mpsc::Receiver<anyhow::Result<MidMarketRate>>,
@@ -174,7 +221,7 @@ fn init_rate_updates(
 
     let future = async move {
         loop {
-            let rate = get_btc_dai_mid_market_rate(&kraken_api_host).await;
+            let rate = rate_aggregator.btc_dai_mid_market_rate().await;
 
             let _ = sender.send(rate).await.map_err(|e| {
                 tracing::trace!(
@@ -190,6 +237,57 @@ fn init_rate_updates(
     (future, receiver)
 }
 
+/// The fees of the transactions of a swap at the current prices.
+async fn swap_fees(
+    bitcoin_fee: &bitcoin::Fee,
+    ethereum_gas_price: &ethereum::GasPrice,
+    kraken: &mid_market_rate::Kraken,
+) -> anyhow::Result<SwapFees> {
+    let vbyte_rate = bitcoin_fee
+        .vbyte_rate()
+        .await
+        .context("Could not get Bitcoin fee rate")?;
+    let gas_price = ethereum_gas_price
+        .gas_price()
+        .await
+        .context("Could not get Ethereum gas price")?;
+    let eth_dai_rate = kraken
+        .eth_dai_mid_market_rate()
+        .await
+        .context("Could not get ETH/DAI rate")?;
+
+    Ok(SwapFees::new(vbyte_rate, gas_price, eth_dai_rate))
+}
+
+fn init_fee_updates(
+    update_interval: Duration,
+    bitcoin_fee: bitcoin::Fee,
+    ethereum_gas_price: ethereum::GasPrice,
+    kraken: mid_market_rate::Kraken,
+) -> (
+    impl Future<Output = comit::Never> + Send,
+    mpsc::Receiver<anyhow::Result<SwapFees>>,
+) {
+    let (mut sender, receiver) = make_update_channel();
+
+    let future = async move {
+        loop {
+            let fees = swap_fees(&bitcoin_fee, &ethereum_gas_price, &kraken).await;
+
+            let _ = sender.send(fees).await.map_err(|e| {
+                tracing::trace!(
+                    "Error when sending fee update from sender to receiver: {}",
+                    e
+                )
+            });
+
+            Delay::new(update_interval).await;
+        }
+    };
+
+    (future, receiver)
+}
+
 fn make_update_channel<T>() -> (mpsc::Sender<T>, mpsc::Receiver<T>) {
     // We start with one sender and never clone it, hence we have an effective
     // buffer size of 1. This is good because we actually want back-pressure on
@@ -273,7 +371,20 @@ fn respawn_swaps(
                 let fund_amount = hbit_params.shared.asset;
                 maker.strategy.herc20_hbit_swap_resumed(fund_amount)?;
             }
+            // No DAI is reserved for a swap in which we fund ether, it still
+            // counts as an ongoing buy.
+            SwapKind::HbitHeth(_) => {
+                maker.strategy.hbit_herc20_swap_resumed(dai::Amount::zero());
+            }
+            SwapKind::HethHbit(HethSwapParams {
+                ref hbit_params, ..
+            }) => {
+                let fund_amount = hbit_params.shared.asset;
+                maker.strategy.herc20_hbit_swap_resumed(fund_amount)?;
+            }
         };
+        // Account for the swap in the risk limits
+        maker.swap_resumed(&swap);
 
         swap_executor.execute(swap);
     }
@@ -315,14 +426,23 @@ mod tests {
                 btc_dai: Default::default(),
                 spread: StaticStub::static_stub(),
                 kraken_api_host: Default::default(),
+                rate: Default::default(),
+                strategy: Default::default(),
+                skew: None,
+                max_fee_share: 100,
+                risk: Default::default(),
             },
             network: Network {
                 listen: vec!["/ip4/98.97.96.95/tcp/20500"
                     .parse()
                     .expect("invalid multiaddr")],
+                rendezvous_points: vec![],
+                mdns: false,
+                tor_socks5_port: None,
             },
             data: Data {
                 dir: Default::default(),
+                persistent_block_cache: false,
             },
             logging: Logging {
                 level: LevelFilter::Trace,
@@ -330,10 +450,12 @@ mod tests {
             bitcoin: settings::Bitcoin::default_from_network(ledger::Bitcoin::Regtest),
             ethereum: settings::Ethereum {
                 node_url: ethereum_blockchain.node_url.clone(),
+                fallback_node_urls: vec![],
                 chain: ethereum::Chain::new(
                     ChainId::GETH_DEV,
                     ethereum_blockchain.token_contract(),
                 ),
+                watch_mempool: false,
                 gas_price: Default::default(),
             },
             sentry: None,
@@ -3,9 +3,9 @@ use crate::{
     command::{into_history_trade, FinishedSwap},
     ethereum::{self, dai},
     history::History,
-    maker::{PublishOrders, TakeRequestDecision},
+    maker::{fees::SwapFees, PublishOrders, TakeRequestDecision},
     network::{self, ActivePeer, SetupSwapContext, Swarm},
-    swap::{Database, SwapExecutor, SwapKind, SwapParams},
+    swap::{Database, HethSwapParams, SwapExecutor, SwapKind, SwapParams},
     Maker, MidMarketRate, SwapId,
 };
 use anyhow::{bail, Context, Result};
@@ -13,10 +13,11 @@ use comit::{
     identity,
     network::{
         orderbook,
-        setup_swap::{self, BobParams, CommonParams, RoleDependentParams},
+        setup_swap::{self, BobParams, CommonParams, EthereumAsset, RoleDependentParams},
     },
     order::SwapProtocol,
     orderpool::Match,
+    BtcErc20Order, Quantity,
 };
 use futures::{channel::mpsc::Receiver, FutureExt, StreamExt};
 use std::sync::Arc;
@@ -58,6 +59,7 @@ impl EventLoop {
         mut rate_update_receiver: Receiver<Result<MidMarketRate>>,
         mut btc_balance_update_receiver: Receiver<Result<bitcoin::Amount>>,
         mut dai_balance_update_receiver: Receiver<Result<dai::Amount>>,
+        mut fee_update_receiver: Receiver<Result<SwapFees>>,
     ) -> anyhow::Result<()> {
         loop {
             futures::select! {
@@ -79,7 +81,8 @@ impl EventLoop {
                             tracing::error!("Rate update handling failed: {:#}", err);
                         }
                     } else if let Some(Err(err)) = new_rate {
-                        tracing::error!("Rate retrieval failed: {:#}", err);
+                        tracing::error!("Rate retrieval failed, retracting orders: {:#}", err);
+                        self.handle_rate_failure();
                     }
                 },
                 new_btc_balance = btc_balance_update_receiver.next().fuse() => {
@@ -105,55 +108,65 @@ impl EventLoop {
                             Err(err) => tracing::error!("Dai balance update failed: {:#}", err),
                         }
                     }
+                },
+                new_fees = fee_update_receiver.next().fuse() => {
+                    if let Some(new_fees) = new_fees {
+                        match new_fees {
+                            Ok(new_fees) => {
+                                if let Err(err) = self.handle_fee_update(new_fees) {
+                                    tracing::error!("Fee update handling failed: {:#}", err);
+                                }
+                            }
+                            Err(err) => tracing::error!("Fee update failed: {:#}", err),
+                        }
+                    }
                 }
             }
         }
     }
 
     fn handle_rate_update(&mut self, new_rate: MidMarketRate) -> Result<()> {
-        let publish_order = self.maker.update_rate(new_rate)?;
-        if let Some(PublishOrders {
-            new_sell_order,
-            new_buy_order,
-        }) = publish_order
-        {
-            let orderbook = &mut self.swarm.orderbook;
-
-            orderbook.clear_own_orders();
-            orderbook.publish(new_sell_order);
-            orderbook.publish(new_buy_order);
+        if let Some(publish_orders) = self.maker.update_rate(new_rate)? {
+            self.publish(publish_orders);
         }
 
         Ok(())
     }
 
-    fn handle_btc_balance_update(&mut self, new_btc_balance: bitcoin::Amount) -> Result<()> {
-        if let Some(PublishOrders {
-            new_sell_order,
-            new_buy_order,
-        }) = self.maker.update_bitcoin_balance(new_btc_balance)?
-        {
-            let orderbook = &mut self.swarm.orderbook;
+    /// Replace our orders in the orderbook at once, takers never see a mix of
+    /// old and new orders.
+    fn publish(&mut self, publish_orders: PublishOrders) {
+        self.swarm
+            .orderbook
+            .replace_own_orders(publish_orders.into_orders());
+    }
 
-            orderbook.clear_own_orders();
-            orderbook.publish(new_sell_order);
-            orderbook.publish(new_buy_order);
+    /// Without a trustworthy rate we must not quote, hence we retract our
+    /// orders until the rate sources agree again.
+    fn handle_rate_failure(&mut self) {
+        self.maker.invalidate_rate();
+        self.swarm.orderbook.clear_own_orders();
+    }
+
+    fn handle_btc_balance_update(&mut self, new_btc_balance: bitcoin::Amount) -> Result<()> {
+        if let Some(publish_orders) = self.maker.update_bitcoin_balance(new_btc_balance)? {
+            self.publish(publish_orders);
         }
 
         Ok(())
     }
 
     fn handle_dai_balance_update(&mut self, new_dai_balance: dai::Amount) -> Result<()> {
-        if let Some(PublishOrders {
-            new_sell_order,
-            new_buy_order,
-        }) = self.maker.update_dai_balance(new_dai_balance)?
-        {
-            let orderbook = &mut self.swarm.orderbook;
+        if let Some(publish_orders) = self.maker.update_dai_balance(new_dai_balance)? {
+            self.publish(publish_orders);
+        }
+
+        Ok(())
+    }
 
-            orderbook.clear_own_orders();
-            orderbook.publish(new_sell_order);
-            orderbook.publish(new_buy_order);
+    fn handle_fee_update(&mut self, new_fees: SwapFees) -> Result<()> {
+        if let Some(publish_orders) = self.maker.update_fees(new_fees)? {
+            self.publish(publish_orders);
         }
 
         Ok(())
@@ -166,23 +179,25 @@ impl EventLoop {
             .await
             .context("Unable to remove from active takers");
 
-        let trade = into_history_trade(
-            finished_swap.peer.peer_id(),
-            finished_swap.swap.clone(),
-            #[cfg(not(test))]
-            finished_swap.final_timestamp,
-        );
-
-        self.history
-            .write(trade)
-            .with_context(|| format!("Unable to register history entry: {:?}", finished_swap))?;
+        if !finished_swap.refunded {
+            let trade = into_history_trade(
+                finished_swap.peer.peer_id(),
+                finished_swap.swap.clone(),
+                #[cfg(not(test))]
+                finished_swap.final_timestamp,
+            );
+
+            self.history.write(trade).with_context(|| {
+                format!("Unable to register history entry: {:?}", finished_swap)
+            })?;
+        }
 
         self.database
             .remove_swap(&finished_swap.swap.swap_id())
             .await
             .context("Unable to delete swap from db")?;
 
-        self.maker.strategy.swap_finished(finished_swap.swap);
+        self.maker.swap_finished(finished_swap.swap);
 
         peer_db_res
     }
@@ -230,195 +245,344 @@ impl EventLoop {
                 let swap_kind = match exec_swap.swap_protocol {
                     setup_swap::SwapProtocol::HbitHerc20 => SwapKind::HbitHerc20(params),
                     setup_swap::SwapProtocol::Herc20Hbit => SwapKind::Herc20Hbit(params),
+                    setup_swap::SwapProtocol::HbitHeth | setup_swap::SwapProtocol::HethHbit => {
+                        bail!(
+                            "{:?} swap {} was set up with herc20 parameters",
+                            exec_swap.swap_protocol,
+                            swap_id
+                        )
+                    }
                 };
 
-                self.database
-                    .insert_swap(swap_kind.clone())
-                    .await
-                    .with_context(|| format!("Could not insert swap {}", swap_id))?;
-
-                sentry::capture_message(
-                    format!(
-                        "starting execution for {} swap with swap-id {}",
-                        swap_kind, swap_id
-                    )
-                    .as_str(),
-                    sentry::Level::Info,
-                );
+                self.start_swap(
+                    swap_kind,
+                    exec_swap.context,
+                    Quantity::new(exec_swap.hbit.asset),
+                )
+                .await?;
+            }
+            setup_swap::BehaviourOutEvent::ExecutableHethSwap(exec_swap) => {
+                let swap_id = exec_swap.context.swap_id;
+                let start_of_swap = exec_swap.context.match_ref_point;
+                let bitcoin_transient_sk = self
+                    .bitcoin_wallet
+                    .derive_transient_sk(exec_swap.context.bitcoin_transient_key_index)
+                    .context("Could not derive Bitcoin transient key")?;
+                let hbit_params = crate::swap::hbit::Params {
+                    shared: exec_swap.hbit,
+                    transient_sk: bitcoin_transient_sk,
+                    final_address: self.bitcoin_wallet.new_address().await?,
+                };
+
+                let params = HethSwapParams {
+                    swap_id,
+                    start_of_swap,
+                    hbit_params,
+                    heth_params: exec_swap.heth,
+                    secret_hash: exec_swap.hbit.secret_hash,
+                    taker: ActivePeer {
+                        peer_id: exec_swap.peer_id,
+                    },
+                };
+                let swap_kind = match exec_swap.swap_protocol {
+                    setup_swap::SwapProtocol::HbitHeth => SwapKind::HbitHeth(params),
+                    setup_swap::SwapProtocol::HethHbit => SwapKind::HethHbit(params),
+                    setup_swap::SwapProtocol::HbitHerc20 | setup_swap::SwapProtocol::Herc20Hbit => {
+                        bail!(
+                            "{:?} swap {} was set up with heth parameters",
+                            exec_swap.swap_protocol,
+                            swap_id
+                        )
+                    }
+                };
 
-                self.swap_executor.execute(swap_kind);
+                self.start_swap(
+                    swap_kind,
+                    exec_swap.context,
+                    Quantity::new(exec_swap.hbit.asset),
+                )
+                .await?;
             }
             setup_swap::BehaviourOutEvent::AlreadyHaveRoleParams { peer, .. } => {
                 bail!("already received role params from {}", peer)
             }
+            setup_swap::BehaviourOutEvent::SetupFailed {
+                peer,
+                context,
+                error,
+            } => {
+                self.swarm
+                    .orderbook
+                    .orderpool_mut()
+                    .notify_swap_setup_failed(context.our_order, context.their_order);
+                self.maker.swap_setup_failed(&context.taken_order);
+                self.database
+                    .remove_active_peer(&ActivePeer {
+                        peer_id: peer.clone(),
+                    })
+                    .await
+                    .context("Unable to remove from active takers")?;
+
+                bail!("failed to set up swap with {}: {}", peer, error)
+            }
         }
 
         Ok(())
     }
 
+    async fn start_swap(
+        &mut self,
+        swap_kind: SwapKind,
+        context: SetupSwapContext,
+        quantity: Quantity<comit::asset::Bitcoin>,
+    ) -> Result<()> {
+        let swap_id = swap_kind.swap_id();
+
+        self.database
+            .insert_swap(swap_kind.clone())
+            .await
+            .with_context(|| format!("Could not insert swap {}", swap_id))?;
+
+        sentry::capture_message(
+            format!(
+                "starting execution for {} swap with swap-id {}",
+                swap_kind, swap_id
+            )
+            .as_str(),
+            sentry::Level::Info,
+        );
+
+        self.swap_executor.execute(swap_kind);
+
+        self.swarm
+            .orderbook
+            .orderpool_mut()
+            .notify_swap_setup_successful(context.our_order, context.their_order, quantity)
+            .context("Could not update order pool")?;
+
+        Ok(())
+    }
+
     async fn handle_orderbook_event(&mut self, event: orderbook::BehaviourOutEvent) -> Result<()> {
         match event {
-            orderbook::BehaviourOutEvent::OrderMatch(Match {
-                peer,
-                price,
-                quantity,
-                swap_protocol,
-                match_reference_point: match_ref_point,
+            orderbook::BehaviourOutEvent::OrderMatch(r#match) => {
+                let (ours, theirs) = (r#match.ours, r#match.theirs);
+
+                let result = self.handle_order_match(r#match).await;
+                if result.is_err() {
+                    self.swarm
+                        .orderbook
+                        .orderpool_mut()
+                        .notify_swap_setup_failed(ours, theirs);
+                }
+
+                result
+            }
+        }
+    }
+
+    async fn handle_order_match(&mut self, r#match: Match) -> Result<()> {
+        let Match {
+            peer,
+            price,
+            quantity,
+            swap_protocol,
+            match_reference_point: match_ref_point,
+            ours,
+            theirs,
+            ..
+        } = r#match;
+
+        let taker = ActivePeer {
+            peer_id: peer.clone(),
+        };
+
+        let ongoing_trades_with_taker =
+            self.database.active_swaps_with(&taker).with_context(|| {
+                format!(
+                    "could not determine if taker has ongoing trade; taker: {}, order: {}",
+                    taker.peer_id(),
+                    ours,
+                )
+            })?;
+
+        if !self
+            .maker
+            .allows_another_swap_with(ongoing_trades_with_taker)
+        {
+            bail!(
+                "ignoring take order request from taker with {} ongoing trades, taker: {:#}, order: {}",
+                ongoing_trades_with_taker,
+                taker.peer_id(),
                 ours,
-                ..
-            }) => {
-                let taker = ActivePeer {
-                    peer_id: peer.clone(),
-                };
+            );
+        }
 
-                let ongoing_trade_with_taker_exists = self
-                    .database
-                    .contains_active_peer(&taker)
-                    .with_context(|| {
-                        format!(
-                            "could not determine if taker has ongoing trade; taker: {}, order: {}",
-                            taker.peer_id(),
-                            ours,
-                        )
-                    })?;
-
-                if ongoing_trade_with_taker_exists {
-                    bail!(
-                        "ignoring take order request from taker with ongoing trade, taker: {:#}, order: {}",
-                        taker.peer_id(),
-                        ours,
-                    );
-                }
+        let swap_id = SwapId::default();
+        let index = self
+            .database
+            .fetch_inc_bitcoin_transient_key_index()
+            .await
+            .context("Could not fetch the index for the Bitcoin transient key")?;
 
-                let swap_id = SwapId::default();
-                let index = self
-                    .database
-                    .fetch_inc_bitcoin_transient_key_index()
-                    .await
-                    .context("Could not fetch the index for the Bitcoin transient key")?;
+        let token_contract = self.ethereum_wallet.dai_contract_address();
+        let ethereum_identity = self.ethereum_wallet.account();
+        let bitcoin_transient_sk = self
+            .bitcoin_wallet
+            .derive_transient_sk(index)
+            .context("Could not derive Bitcoin transient key")?;
 
-                let token_contract = self.ethereum_wallet.dai_contract_address();
-                let ethereum_identity = self.ethereum_wallet.account();
-                let bitcoin_transient_sk = self
-                    .bitcoin_wallet
-                    .derive_transient_sk(index)
-                    .context("Could not derive Bitcoin transient key")?;
+        let bitcoin_identity =
+            identity::Bitcoin::from_secret_key(&crate::SECP, &bitcoin_transient_sk);
 
-                let bitcoin_identity =
-                    identity::Bitcoin::from_secret_key(&crate::SECP, &bitcoin_transient_sk);
-
-                let erc20_quantity = quantity * price.clone();
-
-                let ethereum_chain_id = self.ethereum_wallet.chain_id();
-                let bitcoin_network = self.bitcoin_wallet.ledger;
-
-                let (ethereum_absolute_expiry, bitcoin_absolute_expiry, swap_protocol) =
-                    match swap_protocol {
-                        SwapProtocol::HbitHerc20 {
-                            hbit_expiry_offset,
-                            herc20_expiry_offset,
-                        } => {
-                            // todo: do checked addition
-                            #[allow(clippy::cast_sign_loss)]
-                            #[allow(clippy::cast_possible_truncation)]
-                            let ethereum_absolute_expiry =
-                                (match_ref_point + time::Duration::from(herc20_expiry_offset))
-                                    .timestamp() as u32;
-                            #[allow(clippy::cast_sign_loss)]
-                            #[allow(clippy::cast_possible_truncation)]
-                            let bitcoin_absolute_expiry =
-                                (match_ref_point + time::Duration::from(hbit_expiry_offset))
-                                    .timestamp() as u32;
-
-                            (
-                                ethereum_absolute_expiry,
-                                bitcoin_absolute_expiry,
-                                setup_swap::SwapProtocol::HbitHerc20,
-                            )
-                        }
-                        SwapProtocol::Herc20Hbit {
-                            hbit_expiry_offset,
-                            herc20_expiry_offset,
-                        } => {
-                            // todo: do checked addition
-                            #[allow(clippy::cast_sign_loss)]
-                            #[allow(clippy::cast_possible_truncation)]
-                            let ethereum_absolute_expiry =
-                                (match_ref_point + time::Duration::from(herc20_expiry_offset))
-                                    .timestamp() as u32;
-                            #[allow(clippy::cast_sign_loss)]
-                            #[allow(clippy::cast_possible_truncation)]
-                            let bitcoin_absolute_expiry =
-                                (match_ref_point + time::Duration::from(hbit_expiry_offset))
-                                    .timestamp() as u32;
-
-                            (
-                                ethereum_absolute_expiry,
-                                bitcoin_absolute_expiry,
-                                setup_swap::SwapProtocol::Herc20Hbit,
-                            )
-                        }
-                    };
+        let erc20_quantity = quantity * price.clone();
 
-                let our_order = self
-                    .swarm
-                    .orderbook
-                    .orderpool()
-                    .ours()
-                    .find(|o| o.id == ours)
-                    .context("unable to find order that just matched in order pool")?;
-
-                let decision = self
-                    .maker
-                    .process_taken_order(our_order.clone())
-                    .context("Processing taken order yielded error")?;
-
-                match decision {
-                    TakeRequestDecision::GoForSwap => {
-                        self.swarm
-                            .setup_swap
-                            .send(
-                                &peer,
-                                RoleDependentParams::Bob(BobParams {
-                                    bitcoin_identity,
-                                    ethereum_identity,
-                                }),
-                                CommonParams {
-                                    erc20: comit::asset::Erc20 {
-                                        token_contract,
-                                        quantity: erc20_quantity,
-                                    },
-                                    bitcoin: quantity.to_inner(),
-                                    ethereum_absolute_expiry,
-                                    bitcoin_absolute_expiry,
-                                    ethereum_chain_id,
-                                    bitcoin_network,
-                                },
-                                swap_protocol,
-                                SetupSwapContext {
-                                    swap_id,
-                                    match_ref_point,
-                                    bitcoin_transient_key_index: index,
-                                },
-                            )
-                            .context("Sending setup swap message yielded error")?;
-
-                        let _ = self
-                            .database
-                            .insert_active_peer(ActivePeer { peer_id: peer })
-                            .await
-                            .context("Failed to confirm order")?;
-
-                        // todo: publish new order here?
-                        // What if i publish a new order here and the does go
-                        // through?
+        let ethereum_chain_id = self.ethereum_wallet.chain_id();
+        let bitcoin_network = self.bitcoin_wallet.ledger;
+
+        let (ethereum_absolute_expiry, bitcoin_absolute_expiry, swap_protocol) = match swap_protocol
+        {
+            SwapProtocol::HbitHerc20 {
+                hbit_expiry_offset,
+                herc20_expiry_offset,
+            } => {
+                // todo: do checked addition
+                #[allow(clippy::cast_sign_loss)]
+                #[allow(clippy::cast_possible_truncation)]
+                let ethereum_absolute_expiry = (match_ref_point
+                    + time::Duration::from(herc20_expiry_offset))
+                .timestamp() as u32;
+                #[allow(clippy::cast_sign_loss)]
+                #[allow(clippy::cast_possible_truncation)]
+                let bitcoin_absolute_expiry =
+                    (match_ref_point + time::Duration::from(hbit_expiry_offset)).timestamp() as u32;
+
+                (
+                    ethereum_absolute_expiry,
+                    bitcoin_absolute_expiry,
+                    setup_swap::SwapProtocol::HbitHerc20,
+                )
+            }
+            SwapProtocol::Herc20Hbit {
+                hbit_expiry_offset,
+                herc20_expiry_offset,
+            } => {
+                // todo: do checked addition
+                #[allow(clippy::cast_sign_loss)]
+                #[allow(clippy::cast_possible_truncation)]
+                let ethereum_absolute_expiry = (match_ref_point
+                    + time::Duration::from(herc20_expiry_offset))
+                .timestamp() as u32;
+                #[allow(clippy::cast_sign_loss)]
+                #[allow(clippy::cast_possible_truncation)]
+                let bitcoin_absolute_expiry =
+                    (match_ref_point + time::Duration::from(hbit_expiry_offset)).timestamp() as u32;
+
+                (
+                    ethereum_absolute_expiry,
+                    bitcoin_absolute_expiry,
+                    setup_swap::SwapProtocol::Herc20Hbit,
+                )
+            }
+            // We only make BTC/DAI orders, hence nothing locking ether can match them.
+            SwapProtocol::HbitHeth { .. } | SwapProtocol::HethHbit { .. } => bail!(
+                "order {} matched with heth but nectar only trades BTC/DAI",
+                ours
+            ),
+        };
+
+        let our_order = self
+            .swarm
+            .orderbook
+            .orderpool()
+            .ours()
+            .find(|o| o.id == ours)
+            .context("unable to find order that just matched in order pool")?;
+
+        // The taker may take only part of our order.
+        let taken_order = BtcErc20Order {
+            quantity,
+            ..our_order.clone()
+        };
+
+        let decision = self
+            .maker
+            .process_taken_order(taken_order.clone())
+            .context("Processing taken order yielded error")?;
+
+        match decision {
+            TakeRequestDecision::GoForSwap => {
+                let sent = self.swarm.setup_swap.send(
+                    &peer,
+                    RoleDependentParams::Bob(BobParams {
+                        bitcoin_identity,
+                        ethereum_identity,
+                    }),
+                    CommonParams {
+                        alice_order_id: theirs,
+                        bob_order_id: ours,
+                        ethereum: EthereumAsset::Erc20(comit::asset::Erc20 {
+                            token_contract,
+                            quantity: erc20_quantity,
+                        }),
+                        bitcoin: quantity.to_inner(),
+                        ethereum_absolute_expiry,
+                        bitcoin_absolute_expiry,
+                        ethereum_chain_id,
+                        bitcoin_network,
+                    },
+                    swap_protocol,
+                    SetupSwapContext {
+                        swap_id,
+                        match_ref_point,
+                        bitcoin_transient_key_index: index,
+                        our_order: ours,
+                        their_order: theirs,
+                        taken_order: taken_order.clone(),
+                    },
+                );
+                if let Err(e) = sent {
+                    self.maker.swap_setup_failed(&taken_order);
+                    return Err(e).context("Sending setup swap message yielded error");
+                }
+
+                let _ = self
+                    .database
+                    .insert_active_peer(ActivePeer { peer_id: peer })
+                    .await
+                    .context("Failed to confirm order")?;
+
+                // The orders that do not fit within the risk limits anymore
+                // must not stay published.
+                if self.maker.is_kill_switch_engaged() {
+                    self.retract_all_orders();
+                } else {
+                    match self.maker.new_orders() {
+                        Ok(publish_orders) => self.publish(publish_orders),
+                        Err(e) => tracing::warn!("Could not update orders: {:#}", e),
                     }
-                    TakeRequestDecision::InsufficientFunds => bail!("Insufficient funds"),
-                    TakeRequestDecision::RateNotProfitable => bail!("Rate not profitable"),
-                };
+                }
             }
-        }
+            TakeRequestDecision::InsufficientFunds => bail!("Insufficient funds"),
+            TakeRequestDecision::RateNotProfitable => bail!("Rate not profitable"),
+            TakeRequestDecision::RiskLimitWouldBeBreached => bail!("Risk limit would be breached"),
+            TakeRequestDecision::RiskLimitReached => {
+                self.retract_all_orders();
+
+                bail!("Risk limit reached")
+            }
+        };
 
         Ok(())
     }
+
+    /// The kill switch is engaged, nothing is quoted until nectar is
+    /// restarted.
+    fn retract_all_orders(&mut self) {
+        self.swarm.orderbook.retract(&comit::TradingPair::btc_dai());
+        sentry::capture_message(
+            "risk limit reached, retracted all orders",
+            sentry::Level::Error,
+        );
+    }
 }
@@ -17,18 +17,55 @@ use anyhow::Context;
 #[serde(deny_unknown_fields)]
 pub struct Data {
     pub dir: PathBuf,
+    /// Whether to keep the blocks the swaps look at on disk, so they do not
+    /// have to be fetched again after a restart.
+    #[serde(default)]
+    pub persistent_block_cache: bool,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Network {
     pub listen: Vec<Multiaddr>,
+    /// Rendezvous points to register with as a maker, each address has to end
+    /// with the `/p2p/<peer id>` of the rendezvous point.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rendezvous_points: Vec<Multiaddr>,
+    /// Whether to make ourselves known to the peers in the local network.
+    #[serde(default)]
+    pub mdns: bool,
+    /// The port of the local Tor SOCKS5 proxy, if set all connections are
+    /// routed through the Tor network.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tor_socks5_port: Option<u16>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Bitcoind {
     pub node_url: Url,
+    /// Additional nodes we fail over to if the primary one is unhealthy or
+    /// lags behind.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_node_urls: Vec<Url>,
+    /// The `-zmqpubhashblock` endpoint of the primary node, used to get
+    /// notified about new blocks instead of long-polling for them.
+    pub zmq_url: Option<Url>,
+}
+
+/// An Esplora server to watch swaps through instead of bitcoind.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Esplora {
+    pub url: Url,
+}
+
+/// An Electrum server to watch swaps through instead of bitcoind, e.g.
+/// `ssl://electrum.blockstream.info:50002`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Electrum {
+    pub url: Url,
 }
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
@@ -42,6 +79,45 @@ pub struct BtcDai {
     pub max_sell_quantity: Option<bitcoin::Amount>,
 }
 
+/// The strategy nectar uses to create orders and to decide whether to go
+/// through with a taken order.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Strategy {
+    /// Orders of the full available balance, capped by the maximum quantities
+    /// of `[maker.btc_dai]`.
+    AllIn,
+    /// A ladder of orders of a fixed size per side, every level one step
+    /// further away from the mid-market rate than the one below.
+    Ladder {
+        #[serde(with = "::bitcoin::util::amount::serde::as_btc")]
+        order_size: bitcoin::Amount,
+        /// The number of orders per side
+        levels: u16,
+        /// The spread added per level, format is permyriad
+        spread_step: u16,
+    },
+}
+
+impl Default for Strategy {
+    fn default() -> Self {
+        Self::AllIn
+    }
+}
+
+/// Shift of the spread depending on the share of the value of our inventory
+/// held in bitcoin, applies to all strategies.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Skew {
+    /// The share of the value of our inventory we want to hold in bitcoin,
+    /// format is permyriad
+    pub target_btc_share: u16,
+    /// The spread shift if we hold only bitcoin or only dai, format is
+    /// permyriad
+    pub max_skew: u16,
+}
+
 #[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum EstimateMode {
@@ -120,17 +196,37 @@ mod tests {
             r#"
             listen = ["/ip4/0.0.0.0/tcp/9939", "/ip4/127.0.0.1/tcp/9939"]
             "#,
+            r#"
+            listen = ["/onion3/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd:9939"]
+            tor_socks5_port = 9050
+            "#,
         ];
 
         let expected = vec![
             Network {
                 listen: vec!["/ip4/0.0.0.0/tcp/9939".parse().unwrap()],
+                rendezvous_points: vec![],
+                mdns: false,
+                tor_socks5_port: None,
             },
             Network {
                 listen: (vec![
                     "/ip4/0.0.0.0/tcp/9939".parse().unwrap(),
                     "/ip4/127.0.0.1/tcp/9939".parse().unwrap(),
                 ]),
+                rendezvous_points: vec![],
+                mdns: false,
+                tor_socks5_port: None,
+            },
+            Network {
+                listen: vec![
+                    "/onion3/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd:9939"
+                        .parse()
+                        .unwrap(),
+                ],
+                rendezvous_points: vec![],
+                mdns: false,
+                tor_socks5_port: Some(9050),
             },
         ];
 
@@ -153,23 +249,47 @@ mod tests {
                 }),
                 spread: Some(Spread::new(500).unwrap()),
                 kraken_api_host: Some("https://api.kraken.com".parse().unwrap()),
+                rate: Some(file::RateAggregation {
+                    sources: Some(vec![
+                        file::RateSource::Kraken,
+                        file::RateSource::Coinbase,
+                        file::RateSource::Bitstamp,
+                    ]),
+                    file: None,
+                    min_sources: Some(2),
+                    max_deviation: Some(100),
+                    max_age_secs: Some(600),
+                }),
+                strategy: None,
+                skew: None,
+                max_fee_share: Some(100),
+                risk: None,
             }),
             network: Some(Network {
                 listen: vec!["/ip4/0.0.0.0/tcp/9939".parse().unwrap()],
+                rendezvous_points: vec![],
+                mdns: false,
+                tor_socks5_port: None,
             }),
             data: Some(Data {
                 dir: "/Users/froyer/Library/Application Support/nectar"
                     .parse()
                     .unwrap(),
+                persistent_block_cache: false,
             }),
             logging: Some(file::Logging {
                 level: Some(Level::Info),
             }),
             bitcoin: Some(file::Bitcoin {
                 network: ledger::Bitcoin::Regtest,
+                watch_mempool: None,
                 bitcoind: Some(Bitcoind {
                     node_url: "http://localhost:18443/".parse().unwrap(),
+                    fallback_node_urls: vec![],
+                    zmq_url: None,
                 }),
+                esplora: None,
+                electrum: None,
                 fees: Some(file::BitcoinFees {
                     strategy: Some(file::BitcoinFeeStrategy::Static),
                     sat_per_vbyte: Some(bitcoin::Amount::from_sat(12)),
@@ -179,7 +299,9 @@ mod tests {
             }),
             ethereum: Some(file::Ethereum {
                 chain_id: ChainId::MAINNET,
+                watch_mempool: None,
                 node_url: Some("http://localhost:8545/".parse().unwrap()),
+                fallback_node_urls: vec![],
                 local_dai_contract_address: None,
                 gas_price: Some(file::EthereumGasPrice {
                     service: file::EthereumGasPriceService::Geth,
@@ -1,12 +1,16 @@
 use crate::{
-    config::{Bitcoind, BtcDai, Data, EstimateMode, Network},
+    config::{Bitcoind, BtcDai, Data, Electrum, Esplora, EstimateMode, Network, Skew, Strategy},
+    ethereum::dai,
     Spread,
 };
 use comit::{ethereum::ChainId, ledger};
 use config as config_rs;
 use log::LevelFilter;
 use serde::{Deserialize, Serialize};
-use std::{ffi::OsStr, path::Path};
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
 use url::Url;
 
 /// This struct aims to represent the configuration file as it appears on disk.
@@ -32,14 +36,67 @@ pub struct Maker {
     pub spread: Option<Spread>,
     pub kraken_api_host: Option<Url>,
     pub btc_dai: Option<BtcDai>,
+    pub rate: Option<RateAggregation>,
+    pub strategy: Option<Strategy>,
+    pub skew: Option<Skew>,
+    pub max_fee_share: Option<u16>,
+    pub risk: Option<Risk>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateAggregation {
+    /// The exchanges to fetch the mid-market rate from
+    pub sources: Option<Vec<RateSource>>,
+    /// A file to read the mid-market rate from, in addition to the exchanges
+    pub file: Option<PathBuf>,
+    /// The minimum number of sources that have to agree on the rate
+    pub min_sources: Option<usize>,
+    /// The maximum deviation of a rate from the median in permyriad
+    pub max_deviation: Option<u16>,
+    /// Rates older than this are ignored
+    pub max_age_secs: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Risk {
+    /// The maximum quantity of bitcoin traded in all ongoing swaps
+    #[serde(default)]
+    #[serde(with = "::bitcoin::util::amount::serde::as_btc::opt")]
+    pub max_notional: Option<bitcoin::Amount>,
+    /// The maximum quantity of bitcoin traded in the last 24 hours
+    #[serde(default)]
+    #[serde(with = "::bitcoin::util::amount::serde::as_btc::opt")]
+    pub max_daily_btc_volume: Option<bitcoin::Amount>,
+    /// The maximum quantity of dai traded in the last 24 hours
+    #[serde(default)]
+    #[serde(with = "crate::config::serde::dai_amount")]
+    pub max_daily_dai_volume: Option<dai::Amount>,
+    /// The maximum number of ongoing swaps with the same taker
+    pub max_swaps_per_peer: Option<usize>,
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateSource {
+    Kraken,
+    Coinbase,
+    Bitstamp,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Bitcoin {
     pub network: ledger::Bitcoin,
+    #[serde(default)]
+    pub watch_mempool: Option<bool>,
     pub bitcoind: Option<Bitcoind>,
     #[serde(default)]
+    pub esplora: Option<Esplora>,
+    #[serde(default)]
+    pub electrum: Option<Electrum>,
+    #[serde(default)]
     pub fees: Option<BitcoinFees>,
 }
 
@@ -47,7 +104,13 @@ pub struct Bitcoin {
 #[serde(deny_unknown_fields)]
 pub struct Ethereum {
     pub chain_id: ChainId,
+    #[serde(default)]
+    pub watch_mempool: Option<bool>,
     pub node_url: Option<Url>,
+    /// Additional nodes we fail over to if the primary one is unhealthy or
+    /// lags behind.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_node_urls: Vec<Url>,
     #[serde(default)]
     #[serde(with = "crate::config::serde::ethereum_address")]
     pub local_dai_contract_address: Option<comit::ethereum::Address>,
@@ -214,11 +277,35 @@ mod tests {
 # 1000 is 10.00% spread
 spread = 1000
 kraken_api_host = "https://api.kraken.com"
+max_fee_share = 100
 
 [maker.btc_dai]
 max_buy_quantity = 1.23456
 max_sell_quantity = 1.23456
 
+[maker.risk]
+max_notional = 2.5
+max_daily_btc_volume = 10.0
+max_daily_dai_volume = 100000.5
+max_swaps_per_peer = 2
+
+[maker.rate]
+sources = ["kraken", "coinbase", "bitstamp"]
+file = "/tmp/nectar/rate"
+min_sources = 2
+max_deviation = 100
+max_age_secs = 600
+
+[maker.strategy]
+kind = "ladder"
+order_size = 0.01
+levels = 5
+spread_step = 50
+
+[maker.skew]
+target_btc_share = 5000
+max_skew = 100
+
 [network]
 listen = ["/ip4/0.0.0.0/tcp/9939"]
 
@@ -258,21 +345,57 @@ url = "https://public_key@account.ingest.sentry.io/project_id"
                 }),
                 spread: Some(Spread::new(1000).unwrap()),
                 kraken_api_host: Some("https://api.kraken.com".parse().unwrap()),
+                rate: Some(RateAggregation {
+                    sources: Some(vec![
+                        RateSource::Kraken,
+                        RateSource::Coinbase,
+                        RateSource::Bitstamp,
+                    ]),
+                    file: Some(PathBuf::from("/tmp/nectar/rate")),
+                    min_sources: Some(2),
+                    max_deviation: Some(100),
+                    max_age_secs: Some(600),
+                }),
+                strategy: Some(Strategy::Ladder {
+                    order_size: bitcoin::Amount::from_btc(0.01).unwrap(),
+                    levels: 5,
+                    spread_step: 50,
+                }),
+                skew: Some(Skew {
+                    target_btc_share: 5000,
+                    max_skew: 100,
+                }),
+                max_fee_share: Some(100),
+                risk: Some(Risk {
+                    max_notional: Some(bitcoin::Amount::from_btc(2.5).unwrap()),
+                    max_daily_btc_volume: Some(bitcoin::Amount::from_btc(10.0).unwrap()),
+                    max_daily_dai_volume: Some(dai::Amount::from_dai_trunc(100_000.5).unwrap()),
+                    max_swaps_per_peer: Some(2),
+                }),
             }),
             network: Some(Network {
                 listen: vec!["/ip4/0.0.0.0/tcp/9939".parse().unwrap()],
+                rendezvous_points: vec![],
+                mdns: false,
+                tor_socks5_port: None,
             }),
             data: Some(Data {
                 dir: PathBuf::from("/tmp/nectar/"),
+                persistent_block_cache: false,
             }),
             logging: Some(Logging {
                 level: Some(Level::Debug),
             }),
             bitcoin: Some(Bitcoin {
                 network: ledger::Bitcoin::Regtest,
+                watch_mempool: None,
                 bitcoind: Some(Bitcoind {
                     node_url: "http://localhost:18443".parse().unwrap(),
+                    fallback_node_urls: vec![],
+                    zmq_url: None,
                 }),
+                esplora: None,
+                electrum: None,
                 fees: Some(BitcoinFees {
                     strategy: Some(BitcoinFeeStrategy::Bitcoind),
                     sat_per_vbyte: None,
@@ -282,7 +405,9 @@ url = "https://public_key@account.ingest.sentry.io/project_id"
             }),
             ethereum: Some(Ethereum {
                 chain_id: ChainId::GETH_DEV,
+                watch_mempool: None,
                 node_url: Some("http://localhost:8545".parse().unwrap()),
+                fallback_node_urls: vec![],
                 local_dai_contract_address: Some(
                     "0x6A9865aDE2B6207dAAC49f8bCba9705dEB0B0e6D"
                         .parse()
@@ -323,21 +448,35 @@ url = "https://public_key@account.ingest.sentry.io/project_id"
                 }),
                 spread: Some(Spread::new(1000).unwrap()),
                 kraken_api_host: Some("https://api.kraken.com".parse().unwrap()),
+                rate: None,
+                strategy: None,
+                skew: None,
+                max_fee_share: None,
+                risk: None,
             }),
             network: Some(Network {
                 listen: vec!["/ip4/0.0.0.0/tcp/9939".parse().unwrap()],
+                rendezvous_points: vec![],
+                mdns: false,
+                tor_socks5_port: None,
             }),
             data: Some(Data {
                 dir: PathBuf::from("/tmp/nectar/"),
+                persistent_block_cache: false,
             }),
             logging: Some(Logging {
                 level: Some(Level::Debug),
             }),
             bitcoin: Some(Bitcoin {
                 network: ledger::Bitcoin::Regtest,
+                watch_mempool: None,
                 bitcoind: Some(Bitcoind {
                     node_url: "http://localhost:18443".parse().unwrap(),
+                    fallback_node_urls: vec![],
+                    zmq_url: None,
                 }),
+                esplora: None,
+                electrum: None,
                 fees: Some(BitcoinFees {
                     strategy: Some(BitcoinFeeStrategy::Bitcoind),
                     sat_per_vbyte: None,
@@ -347,7 +486,9 @@ url = "https://public_key@account.ingest.sentry.io/project_id"
             }),
             ethereum: Some(Ethereum {
                 chain_id: ChainId::GETH_DEV,
+                watch_mempool: None,
                 node_url: Some("http://localhost:8545".parse().unwrap()),
+                fallback_node_urls: vec![],
                 local_dai_contract_address: Some(
                     "0x6A9865aDE2B6207dAAC49f8bCba9705dEB0B0e6D"
                         .parse()
@@ -377,9 +518,11 @@ max_sell_quantity = 1.23456
 
 [network]
 listen = ["/ip4/0.0.0.0/tcp/9939"]
+mdns = false
 
 [data]
 dir = "/tmp/nectar/"
+persistent_block_cache = false
 
 [logging]
 level = "debug"
@@ -460,23 +603,38 @@ url = "https://public_key@account.ingest.sentry.io/project_id"
         let expected = vec![
             Bitcoin {
                 network: ledger::Bitcoin::Mainnet,
+                watch_mempool: None,
                 bitcoind: Some(Bitcoind {
                     node_url: Url::parse("http://example.com:8332").unwrap(),
+                    fallback_node_urls: vec![],
+                    zmq_url: None,
                 }),
+                esplora: None,
+                electrum: None,
                 fees: None,
             },
             Bitcoin {
                 network: ledger::Bitcoin::Testnet,
+                watch_mempool: None,
                 bitcoind: Some(Bitcoind {
                     node_url: Url::parse("http://example.com:18332").unwrap(),
+                    fallback_node_urls: vec![],
+                    zmq_url: None,
                 }),
+                esplora: None,
+                electrum: None,
                 fees: None,
             },
             Bitcoin {
                 network: ledger::Bitcoin::Regtest,
+                watch_mempool: None,
                 bitcoind: Some(Bitcoind {
                     node_url: Url::parse("http://example.com:18443").unwrap(),
+                    fallback_node_urls: vec![],
+                    zmq_url: None,
                 }),
+                esplora: None,
+                electrum: None,
                 fees: Some(BitcoinFees {
                     strategy: Some(BitcoinFeeStrategy::Bitcoind),
                     sat_per_vbyte: Some(bitcoin::Amount::from_sat(12)),
@@ -522,7 +680,9 @@ url = "https://public_key@account.ingest.sentry.io/project_id"
         let expected = vec![
             Ethereum {
                 chain_id: ChainId::GETH_DEV,
+                watch_mempool: None,
                 node_url: Some(Url::parse("http://example.com:8545").unwrap()),
+                fallback_node_urls: vec![],
                 local_dai_contract_address: Some(
                     "0x31F42841c2db5173425b5223809CF3A38FEde360"
                         .parse()
@@ -535,7 +695,9 @@ url = "https://public_key@account.ingest.sentry.io/project_id"
             },
             Ethereum {
                 chain_id: ChainId::ROPSTEN,
+                watch_mempool: None,
                 node_url: Some(Url::parse("http://example.com:8545").unwrap()),
+                fallback_node_urls: vec![],
                 local_dai_contract_address: None,
                 gas_price: Some(EthereumGasPrice {
                     service: EthereumGasPriceService::EthGasStation,
@@ -544,7 +706,9 @@ url = "https://public_key@account.ingest.sentry.io/project_id"
             },
             Ethereum {
                 chain_id: ChainId::MAINNET,
+                watch_mempool: None,
                 node_url: Some(Url::parse("http://example.com:8545").unwrap()),
+                fallback_node_urls: vec![],
                 local_dai_contract_address: None,
                 gas_price: None,
             },
@@ -1 +1,2 @@
+pub mod dai_amount;
 pub mod ethereum_address;
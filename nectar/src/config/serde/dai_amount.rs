@@ -0,0 +1,34 @@
+use crate::{
+    ethereum::dai::{self, ATTOS_IN_DAI_EXP},
+    float_maths::string_int_to_float,
+};
+use serde::{de, ser, Deserialize, Deserializer, Serializer};
+
+/// Deserialize an optional amount of dai given as a decimal number, e.g. 100.5
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<dai::Amount>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let dai: Option<f64> = Option::deserialize(deserializer)?;
+
+    dai.map(dai::Amount::from_dai_trunc)
+        .transpose()
+        .map_err(|error| {
+            de::Error::custom(format!("Could not deserialize dai amount: {:#}", error))
+        })
+}
+
+pub fn serialize<S>(value: &Option<dai::Amount>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match value {
+        Some(value) => {
+            let dai = string_int_to_float(value.as_atto().to_string(), ATTOS_IN_DAI_EXP.into());
+            let dai = dai.parse::<f64>().map_err(ser::Error::custom)?;
+
+            serializer.serialize_f64(dai)
+        }
+        None => serializer.serialize_none(),
+    }
+}
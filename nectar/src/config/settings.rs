@@ -1,14 +1,18 @@
 use crate::{
     bitcoin,
     config::{
-        file, file::EthereumGasPriceService, Bitcoind, BtcDai, Data, EstimateMode, File, Network,
+        file, file::EthereumGasPriceService, Bitcoind, BtcDai, Data, Electrum, Esplora,
+        EstimateMode, File, Network, Skew, Strategy,
     },
-    ethereum, Spread,
+    ethereum,
+    ethereum::dai,
+    Spread,
 };
 use anyhow::{Context, Result};
 use comit::ledger;
 use conquer_once::Lazy;
 use log::LevelFilter;
+use std::{path::PathBuf, time::Duration};
 use url::Url;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -25,7 +29,9 @@ pub struct Settings {
 #[derive(Clone, Debug, PartialEq)]
 pub struct Bitcoin {
     pub network: ledger::Bitcoin,
+    pub watch_mempool: bool,
     pub bitcoind: Bitcoind,
+    pub connector: BitcoinConnector,
     pub fees: BitcoinFees,
 }
 
@@ -33,7 +39,9 @@ impl Bitcoin {
     pub fn default_from_network(network: ledger::Bitcoin) -> Self {
         Self {
             network,
+            watch_mempool: false,
             bitcoind: Bitcoind::new(network),
+            connector: BitcoinConnector::Bitcoind,
             fees: Default::default(),
         }
     }
@@ -53,24 +61,53 @@ impl Bitcoin {
 
         let network = bitcoin.network;
         let bitcoind = bitcoin.bitcoind.unwrap_or_else(|| Bitcoind::new(network));
+        let connector = match (bitcoin.esplora, bitcoin.electrum) {
+            (Some(_), Some(_)) => anyhow::bail!(
+                "bitcoin.esplora and bitcoin.electrum must not be present at the same time"
+            ),
+            (Some(Esplora { url }), None) => BitcoinConnector::Esplora(url),
+            (None, Some(Electrum { url })) => BitcoinConnector::Electrum(url),
+            (None, None) => BitcoinConnector::Bitcoind,
+        };
+        let watch_mempool = bitcoin.watch_mempool.unwrap_or(false);
+        if watch_mempool {
+            if let BitcoinConnector::Electrum(_) = connector {
+                anyhow::bail!("bitcoin.watch_mempool is not supported with bitcoin.electrum");
+            }
+        }
         let fees = bitcoin
             .fees
             .map_or_else(BitcoinFees::default, BitcoinFees::from);
 
         Ok(Bitcoin {
             network,
+            watch_mempool,
             bitcoind,
+            connector,
             fees,
         })
     }
 }
 
+/// The backend used to follow the Bitcoin blockchain.
+///
+/// bitcoind is still needed for the wallet and fee estimation, the other
+/// backends only replace it for watching swaps.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BitcoinConnector {
+    Bitcoind,
+    Esplora(Url),
+    Electrum(Url),
+}
+
 #[cfg(test)]
 impl crate::StaticStub for Bitcoin {
     fn static_stub() -> Self {
         Bitcoin {
             network: ledger::Bitcoin::Regtest,
+            watch_mempool: false,
             bitcoind: Bitcoind::new(ledger::Bitcoin::Regtest),
+            connector: BitcoinConnector::Bitcoind,
             fees: BitcoinFees::static_stub(),
         }
     }
@@ -90,15 +127,28 @@ impl Bitcoind {
             }
         };
 
-        Bitcoind { node_url }
+        Bitcoind {
+            node_url,
+            fallback_node_urls: vec![],
+            zmq_url: None,
+        }
     }
 }
 
 impl From<Bitcoin> for file::Bitcoin {
     fn from(bitcoin: Bitcoin) -> Self {
+        let (esplora, electrum) = match bitcoin.connector {
+            BitcoinConnector::Bitcoind => (None, None),
+            BitcoinConnector::Esplora(url) => (Some(Esplora { url }), None),
+            BitcoinConnector::Electrum(url) => (None, Some(Electrum { url })),
+        };
+
         file::Bitcoin {
             network: bitcoin.network,
+            watch_mempool: Some(bitcoin.watch_mempool),
             bitcoind: Some(bitcoin.bitcoind),
+            esplora,
+            electrum,
             fees: Some(bitcoin.fees.into()),
         }
     }
@@ -107,7 +157,9 @@ impl From<Bitcoin> for file::Bitcoin {
 #[derive(Clone, Debug, PartialEq)]
 pub struct Ethereum {
     pub node_url: Url,
+    pub fallback_node_urls: Vec<Url>,
     pub chain: ethereum::Chain,
+    pub watch_mempool: bool,
     pub gas_price: EthereumGasPrice,
 }
 
@@ -120,7 +172,9 @@ impl Ethereum {
 
         Ok(Ethereum {
             node_url,
+            fallback_node_urls: vec![],
             chain,
+            watch_mempool: false,
             gas_price: Default::default(),
         })
     }
@@ -157,7 +211,9 @@ impl Ethereum {
 
         Ok(Ethereum {
             node_url,
+            fallback_node_urls: ethereum.fallback_node_urls,
             chain,
+            watch_mempool: ethereum.watch_mempool.unwrap_or(false),
             gas_price,
         })
     }
@@ -171,13 +227,17 @@ impl From<Ethereum> for file::Ethereum {
                 dai_contract_address,
             } => file::Ethereum {
                 chain_id: chain_id.into(),
+                watch_mempool: Some(ethereum.watch_mempool),
                 node_url: Some(ethereum.node_url),
+                fallback_node_urls: ethereum.fallback_node_urls,
                 local_dai_contract_address: Some(dai_contract_address),
                 gas_price: Some(ethereum.gas_price.into()),
             },
             _ => file::Ethereum {
                 chain_id: ethereum.chain.chain_id(),
+                watch_mempool: Some(ethereum.watch_mempool),
                 node_url: Some(ethereum.node_url),
+                fallback_node_urls: ethereum.fallback_node_urls,
                 local_dai_contract_address: None,
                 gas_price: Some(ethereum.gas_price.into()),
             },
@@ -189,7 +249,9 @@ impl Default for Ethereum {
     fn default() -> Self {
         Self {
             node_url: Url::parse("http://localhost:8545").expect("static string to be a valid url"),
+            fallback_node_urls: vec![],
             chain: ethereum::Chain::Mainnet,
+            watch_mempool: false,
             gas_price: Default::default(),
         }
     }
@@ -203,6 +265,159 @@ pub struct Maker {
     /// is 5.2% spread
     pub spread: Spread,
     pub kraken_api_host: KrakenApiHost,
+    pub rate: RateAggregation,
+    pub strategy: Strategy,
+    /// Skew of the spread depending on our inventory, none if absent
+    pub skew: Option<Skew>,
+    /// Maximum share of the value of an order the expected fees may take,
+    /// format is permyriad.
+    pub max_fee_share: u16,
+    pub risk: Risk,
+}
+
+/// Limits of the risk nectar takes, checked when an order is taken
+#[derive(Clone, Debug, PartialEq, derivative::Derivative)]
+#[derivative(Default)]
+pub struct Risk {
+    /// Maximum quantity of bitcoin traded in all ongoing swaps, none if absent
+    pub max_notional: Option<bitcoin::Amount>,
+    /// Maximum quantity of bitcoin traded in the last 24 hours, none if absent
+    pub max_daily_btc_volume: Option<bitcoin::Amount>,
+    /// Maximum quantity of dai traded in the last 24 hours, none if absent
+    pub max_daily_dai_volume: Option<dai::Amount>,
+    /// Maximum number of ongoing swaps with the same taker
+    #[derivative(Default(value = "1"))]
+    pub max_swaps_per_peer: usize,
+}
+
+impl Risk {
+    fn from_file(file: file::Risk) -> Result<Self> {
+        let default = Self::default();
+
+        let max_swaps_per_peer = file
+            .max_swaps_per_peer
+            .unwrap_or(default.max_swaps_per_peer);
+        if max_swaps_per_peer == 0 {
+            anyhow::bail!("max_swaps_per_peer must be at least 1");
+        }
+
+        Ok(Self {
+            max_notional: file.max_notional,
+            max_daily_btc_volume: file.max_daily_btc_volume,
+            max_daily_dai_volume: file.max_daily_dai_volume,
+            max_swaps_per_peer,
+        })
+    }
+}
+
+impl From<Risk> for file::Risk {
+    fn from(risk: Risk) -> Self {
+        file::Risk {
+            max_notional: risk.max_notional,
+            max_daily_btc_volume: risk.max_daily_btc_volume,
+            max_daily_dai_volume: risk.max_daily_dai_volume,
+            max_swaps_per_peer: Some(risk.max_swaps_per_peer),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateAggregation {
+    pub sources: Vec<RateSource>,
+    /// Minimum number of sources that have to agree on the mid-market rate
+    pub min_sources: usize,
+    /// Maximum deviation of a rate from the median, format is permyriad
+    pub max_deviation: u16,
+    /// Rates older than this are ignored
+    pub max_age: Duration,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RateSource {
+    Kraken,
+    Coinbase,
+    Bitstamp,
+    File(PathBuf),
+}
+
+impl RateAggregation {
+    fn from_file(file: file::RateAggregation) -> Result<Self> {
+        let default = Self::default();
+
+        let mut sources = file.sources.map_or(default.sources, |sources| {
+            sources.into_iter().map(RateSource::from).collect()
+        });
+        if let Some(path) = file.file {
+            sources.push(RateSource::File(path));
+        }
+
+        let min_sources = file.min_sources.unwrap_or(default.min_sources);
+        if min_sources == 0 || min_sources > sources.len() {
+            anyhow::bail!(
+                "min_sources must be between 1 and the number of configured rate sources ({}) but is {}",
+                sources.len(),
+                min_sources
+            );
+        }
+
+        Ok(Self {
+            sources,
+            min_sources,
+            max_deviation: file.max_deviation.unwrap_or(default.max_deviation),
+            max_age: file
+                .max_age_secs
+                .map_or(default.max_age, Duration::from_secs),
+        })
+    }
+}
+
+impl Default for RateAggregation {
+    fn default() -> Self {
+        Self {
+            sources: vec![
+                RateSource::Kraken,
+                RateSource::Coinbase,
+                RateSource::Bitstamp,
+            ],
+            min_sources: 2,
+            max_deviation: 100,
+            max_age: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+impl From<file::RateSource> for RateSource {
+    fn from(source: file::RateSource) -> Self {
+        match source {
+            file::RateSource::Kraken => RateSource::Kraken,
+            file::RateSource::Coinbase => RateSource::Coinbase,
+            file::RateSource::Bitstamp => RateSource::Bitstamp,
+        }
+    }
+}
+
+impl From<RateAggregation> for file::RateAggregation {
+    fn from(rate: RateAggregation) -> Self {
+        let mut sources = Vec::new();
+        let mut file = None;
+
+        for source in rate.sources {
+            match source {
+                RateSource::Kraken => sources.push(file::RateSource::Kraken),
+                RateSource::Coinbase => sources.push(file::RateSource::Coinbase),
+                RateSource::Bitstamp => sources.push(file::RateSource::Bitstamp),
+                RateSource::File(path) => file = Some(path),
+            }
+        }
+
+        file::RateAggregation {
+            sources: Some(sources),
+            file,
+            min_sources: Some(rate.min_sources),
+            max_deviation: Some(rate.max_deviation),
+            max_age_secs: Some(rate.max_age.as_secs()),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -256,8 +471,8 @@ static DEFAULT_BITCOIN_STATIC_FEE_SAT: Lazy<bitcoin::Amount> =
     Lazy::new(|| bitcoin::Amount::from_sat(50));
 
 static DEFAULT_MAX_BITCOIN_FEE_SAT_PER_VBYTE: Lazy<bitcoin::Amount> =
-// Bitcoind's highest estimate in the past year:
-// https://txstats.com/dashboard/db/fee-estimation?orgId=1&panelId=5&fullscreen&from=now-1y&to=now
+    // Bitcoind's highest estimate in the past year:
+    // https://txstats.com/dashboard/db/fee-estimation?orgId=1&panelId=5&fullscreen&from=now-1y&to=now
     Lazy::new(|| bitcoin::Amount::from_sat(200));
 
 #[cfg(test)]
@@ -358,9 +573,19 @@ impl Default for EthereumGasPrice {
     }
 }
 
+const DEFAULT_MAX_FEE_SHARE: u16 = 100;
+
 impl Maker {
-    fn from_file(file: file::Maker) -> Self {
-        Self {
+    fn from_file(file: file::Maker) -> Result<Self> {
+        let max_fee_share = file.max_fee_share.unwrap_or(DEFAULT_MAX_FEE_SHARE);
+        if max_fee_share > 10_000 {
+            anyhow::bail!(
+                "max_fee_share must be between 0 and 10000 permyriad but is {}",
+                max_fee_share
+            );
+        }
+
+        Ok(Self {
             btc_dai: file.btc_dai.unwrap_or_default(),
             spread: file
                 .spread
@@ -368,7 +593,17 @@ impl Maker {
             kraken_api_host: file
                 .kraken_api_host
                 .map_or_else(KrakenApiHost::default, KrakenApiHost),
-        }
+            rate: file.rate.map_or_else(
+                || Ok(RateAggregation::default()),
+                RateAggregation::from_file,
+            )?,
+            strategy: file.strategy.unwrap_or_default(),
+            skew: file.skew,
+            max_fee_share,
+            risk: file
+                .risk
+                .map_or_else(|| Ok(Risk::default()), Risk::from_file)?,
+        })
     }
 }
 
@@ -378,6 +613,11 @@ impl Default for Maker {
             btc_dai: BtcDai::default(),
             spread: Spread::new(500).expect("500 is a valid spread value"),
             kraken_api_host: KrakenApiHost::default(),
+            rate: RateAggregation::default(),
+            strategy: Strategy::default(),
+            skew: None,
+            max_fee_share: DEFAULT_MAX_FEE_SHARE,
+            risk: Risk::default(),
         }
     }
 }
@@ -443,6 +683,11 @@ impl From<Maker> for file::Maker {
             },
             spread: Some(maker.spread),
             kraken_api_host: Some(maker.kraken_api_host.0),
+            rate: Some(maker.rate.into()),
+            strategy: Some(maker.strategy),
+            skew: maker.skew,
+            max_fee_share: Some(maker.max_fee_share),
+            risk: Some(maker.risk.into()),
         }
     }
 }
@@ -463,7 +708,7 @@ impl Settings {
         } = config_file;
 
         Ok(Self {
-            maker: maker.map_or_else(Maker::default, Maker::from_file),
+            maker: maker.map_or_else(|| Ok(Maker::default()), Maker::from_file)?,
             network: network.unwrap_or_else(|| {
                 let default_socket = "/ip4/0.0.0.0/tcp/9939"
                     .parse()
@@ -471,6 +716,9 @@ impl Settings {
 
                 Network {
                     listen: vec![default_socket],
+                    rendezvous_points: vec![],
+                    mdns: false,
+                    tor_socks5_port: None,
                 }
             }),
             data: {
@@ -478,6 +726,7 @@ impl Settings {
                     crate::fs::data_dir().context("unable to determine default data path")?;
                 data.unwrap_or(Data {
                     dir: default_data_dir,
+                    persistent_block_cache: false,
                 })
             },
             logging: {
@@ -546,6 +795,9 @@ mod tests {
             .map(|settings| &settings.network)
             .is_equal_to(Network {
                 listen: vec!["/ip4/0.0.0.0/tcp/9939".parse().unwrap()],
+                rendezvous_points: vec![],
+                mdns: false,
+                tor_socks5_port: None,
             })
     }
 
@@ -560,9 +812,13 @@ mod tests {
             .map(|settings| &settings.bitcoin)
             .is_equal_to(Bitcoin {
                 network: ledger::Bitcoin::Mainnet,
+                watch_mempool: false,
                 bitcoind: Bitcoind {
                     node_url: "http://localhost:8332".parse().unwrap(),
+                    fallback_node_urls: vec![],
+                    zmq_url: None,
                 },
+                connector: BitcoinConnector::Bitcoind,
                 fees: BitcoinFees::BitcoindEstimateSmartfee {
                     mode: EstimateMode::Economical,
                     max_sat_per_vbyte: bitcoin::Amount::from_sat(200),
@@ -582,7 +838,10 @@ mod tests {
             let config_file = File {
                 bitcoin: Some(file::Bitcoin {
                     network,
+                    watch_mempool: None,
                     bitcoind: None,
+                    esplora: None,
+                    electrum: None,
                     fees: None,
                 }),
                 ..File::default()
@@ -595,14 +854,88 @@ mod tests {
                 .map(|settings| &settings.bitcoin)
                 .is_equal_to(Bitcoin {
                     network,
+                    watch_mempool: false,
                     bitcoind: Bitcoind {
                         node_url: url.parse().unwrap(),
+                        fallback_node_urls: vec![],
+                        zmq_url: None,
                     },
+                    connector: BitcoinConnector::Bitcoind,
                     fees: Default::default(),
                 })
         }
     }
 
+    #[test]
+    fn bitcoin_electrum_connector() {
+        let config_file = File {
+            bitcoin: Some(file::Bitcoin {
+                network: ledger::Bitcoin::Mainnet,
+                watch_mempool: None,
+                bitcoind: None,
+                esplora: None,
+                electrum: Some(Electrum {
+                    url: "ssl://electrum.blockstream.info:50002".parse().unwrap(),
+                }),
+                fees: None,
+            }),
+            ..File::default()
+        };
+
+        let settings = Settings::from_config_file_and_defaults(config_file, None);
+
+        assert_that(&settings)
+            .is_ok()
+            .map(|settings| &settings.bitcoin.connector)
+            .is_equal_to(BitcoinConnector::Electrum(
+                "ssl://electrum.blockstream.info:50002".parse().unwrap(),
+            ))
+    }
+
+    #[test]
+    fn watching_mempool_with_electrum_connector_is_an_error() {
+        let config_file = File {
+            bitcoin: Some(file::Bitcoin {
+                network: ledger::Bitcoin::Mainnet,
+                watch_mempool: Some(true),
+                bitcoind: None,
+                esplora: None,
+                electrum: Some(Electrum {
+                    url: "ssl://electrum.blockstream.info:50002".parse().unwrap(),
+                }),
+                fees: None,
+            }),
+            ..File::default()
+        };
+
+        let settings = Settings::from_config_file_and_defaults(config_file, None);
+
+        assert_that(&settings).is_err();
+    }
+
+    #[test]
+    fn bitcoin_esplora_and_electrum_connector_is_an_error() {
+        let config_file = File {
+            bitcoin: Some(file::Bitcoin {
+                network: ledger::Bitcoin::Mainnet,
+                watch_mempool: None,
+                bitcoind: None,
+                esplora: Some(Esplora {
+                    url: "https://blockstream.info/api".parse().unwrap(),
+                }),
+                electrum: Some(Electrum {
+                    url: "ssl://electrum.blockstream.info:50002".parse().unwrap(),
+                }),
+                fees: None,
+            }),
+            ..File::default()
+        };
+
+        let settings = Settings::from_config_file_and_defaults(config_file, None);
+
+        assert_that(&settings).is_err();
+    }
+
     #[test]
     fn ethereum_defaults() {
         let config_file = File { ..File::default() };
@@ -614,8 +947,73 @@ mod tests {
             .map(|settings| &settings.ethereum)
             .is_equal_to(Ethereum {
                 node_url: "http://localhost:8545".parse().unwrap(),
+                fallback_node_urls: vec![],
                 chain: ethereum::Chain::Mainnet,
+                watch_mempool: false,
                 gas_price: EthereumGasPrice::EthGasStation(DEFAULT_ETH_GAS_STATION_URL.clone()),
             })
     }
+
+    #[test]
+    fn rate_file_is_added_to_rate_sources() {
+        let config_file = File {
+            maker: Some(file::Maker {
+                spread: None,
+                kraken_api_host: None,
+                btc_dai: None,
+                rate: Some(file::RateAggregation {
+                    sources: Some(vec![file::RateSource::Kraken]),
+                    file: Some(PathBuf::from("/tmp/rate")),
+                    min_sources: None,
+                    max_deviation: None,
+                    max_age_secs: None,
+                }),
+                strategy: None,
+                skew: None,
+                max_fee_share: None,
+                risk: None,
+            }),
+            ..File::default()
+        };
+
+        let settings = Settings::from_config_file_and_defaults(config_file, None);
+
+        assert_that(&settings)
+            .is_ok()
+            .map(|settings| &settings.maker.rate)
+            .is_equal_to(RateAggregation {
+                sources: vec![
+                    RateSource::Kraken,
+                    RateSource::File(PathBuf::from("/tmp/rate")),
+                ],
+                ..RateAggregation::default()
+            })
+    }
+
+    #[test]
+    fn min_sources_greater_than_number_of_sources_is_rejected() {
+        let config_file = File {
+            maker: Some(file::Maker {
+                spread: None,
+                kraken_api_host: None,
+                btc_dai: None,
+                rate: Some(file::RateAggregation {
+                    sources: Some(vec![file::RateSource::Kraken]),
+                    file: None,
+                    min_sources: Some(2),
+                    max_deviation: None,
+                    max_age_secs: None,
+                }),
+                strategy: None,
+                skew: None,
+                max_fee_share: None,
+                risk: None,
+            }),
+            ..File::default()
+        };
+
+        let settings = Settings::from_config_file_and_defaults(config_file, None);
+
+        assert_that(&settings).is_err();
+    }
 }
@@ -1,6 +1,7 @@
 use self::{
     hbit::{HbitFunded, HbitRedeemed, HbitRefunded},
     herc20::{Herc20Deployed, Herc20Funded, Herc20Redeemed, Herc20Refunded},
+    heth::{HethFunded, HethRedeemed, HethRefunded},
 };
 #[cfg(test)]
 use crate::StaticStub;
@@ -8,11 +9,12 @@ use crate::{network, network::ActivePeer, swap, swap::SwapKind, SwapId};
 use anyhow::{anyhow, Context};
 use conquer_once::Lazy;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashSet, iter::FromIterator};
+use std::convert::TryFrom;
 use time::OffsetDateTime;
 
 mod hbit;
 mod herc20;
+mod heth;
 
 static ACTIVE_PEER_KEY: Lazy<Vec<u8>> =
     Lazy::new(|| serialize(&"active_peer").expect("this slice can be serialized"));
@@ -283,7 +285,7 @@ impl Database {
                     _,
                 )))
             })
-            .map(|res| res.map(SwapKind::from))
+            .map(|res| res.and_then(SwapKind::try_from))
             .collect()
     }
 
@@ -349,18 +351,20 @@ impl Database {
 impl Load<SwapKind> for Database {
     fn load(&self, swap_id: SwapId) -> anyhow::Result<Option<SwapKind>> {
         let swap = self.get_swap(&swap_id)?;
-        let swap_kind = swap.map(|swap| SwapKind::from((swap, swap_id)));
+        let swap_kind = swap
+            .map(|swap| SwapKind::try_from((swap, swap_id)))
+            .transpose()?;
 
         Ok(swap_kind)
     }
 }
 
-/// These methods are used to prevent a peer from having more than one ongoing
-/// swap with nectar An active peer refers to one that has an ongoing swap with
-/// nectar.
+/// These methods are used to prevent a peer from having more ongoing swaps
+/// with nectar than allowed. An active peer refers to one that has an ongoing
+/// swap with nectar, it is stored once per ongoing swap.
 impl Database {
     pub async fn insert_active_peer(&self, peer: ActivePeer) -> anyhow::Result<()> {
-        self.modify_peers_with(|peers: &mut HashSet<ActivePeer>| peers.insert(peer.clone()))?;
+        self.modify_peers_with(|peers: &mut Vec<ActivePeer>| peers.push(peer.clone()))?;
 
         self.db
             .flush_async()
@@ -369,8 +373,13 @@ impl Database {
             .context("failed to flush db")
     }
 
+    /// Removes the peer of one ongoing swap.
     pub async fn remove_active_peer(&self, peer: &ActivePeer) -> anyhow::Result<()> {
-        self.modify_peers_with(|peers: &mut HashSet<ActivePeer>| peers.remove(peer))?;
+        self.modify_peers_with(|peers: &mut Vec<ActivePeer>| {
+            if let Some(index) = peers.iter().position(|active| active == peer) {
+                peers.remove(index);
+            }
+        })?;
         self.db
             .flush_async()
             .await
@@ -378,35 +387,31 @@ impl Database {
             .context("failed to flush db")
     }
 
-    pub fn contains_active_peer(&self, peer: &ActivePeer) -> anyhow::Result<bool> {
+    /// The number of ongoing swaps with the peer.
+    pub fn active_swaps_with(&self, peer: &ActivePeer) -> anyhow::Result<usize> {
         let peers = self.peers()?;
 
-        Ok(peers.contains(&peer))
+        Ok(peers.iter().filter(|active| *active == peer).count())
     }
 
-    fn modify_peers_with(
-        &self,
-        operation_fn: impl Fn(&mut HashSet<ActivePeer>) -> bool,
-    ) -> anyhow::Result<()> {
+    fn modify_peers_with(&self, operation_fn: impl Fn(&mut Vec<ActivePeer>)) -> anyhow::Result<()> {
         let mut peers = self.peers()?;
 
         operation_fn(&mut peers);
 
-        let updated_peers = Vec::<ActivePeer>::from_iter(peers);
-        let updated_peers = serialize(&updated_peers)?;
+        let updated_peers = serialize(&peers)?;
 
         self.db.insert(ACTIVE_PEER_KEY.clone(), updated_peers)?;
 
         Ok(())
     }
 
-    fn peers(&self) -> anyhow::Result<HashSet<ActivePeer>> {
+    fn peers(&self) -> anyhow::Result<Vec<ActivePeer>> {
         let peers = self
             .db
             .get(ACTIVE_PEER_KEY.clone())?
             .ok_or_else(|| anyhow::anyhow!("no key \"active_peer\" in db"))?;
         let peers: Vec<ActivePeer> = deserialize(&peers)?;
-        let peers = HashSet::<ActivePeer>::from_iter(peers);
 
         Ok(peers)
     }
@@ -430,7 +435,8 @@ where
 struct Swap {
     pub kind: Kind,
     pub hbit_params: hbit::Params,
-    pub herc20_params: herc20::Params,
+    pub herc20_params: Option<herc20::Params>,
+    pub heth_params: Option<heth::Params>,
     pub secret_hash: comit::SecretHash,
     pub utc_start_of_swap: OffsetDateTime,
     pub active_peer: network::ActivePeer,
@@ -441,6 +447,9 @@ struct Swap {
     pub herc20_funded: Option<Herc20Funded>,
     pub herc20_redeemed: Option<Herc20Redeemed>,
     pub herc20_refunded: Option<Herc20Refunded>,
+    pub heth_funded: Option<HethFunded>,
+    pub heth_redeemed: Option<HethRedeemed>,
+    pub heth_refunded: Option<HethRefunded>,
     pub archived: Option<bool>,
 }
 
@@ -448,6 +457,8 @@ struct Swap {
 enum Kind {
     HbitHerc20,
     Herc20Hbit,
+    HbitHeth,
+    HethHbit,
 }
 
 #[cfg(test)]
@@ -458,7 +469,8 @@ impl StaticStub for Swap {
         Swap {
             kind: Kind::HbitHerc20,
             hbit_params: StaticStub::static_stub(),
-            herc20_params: StaticStub::static_stub(),
+            herc20_params: Some(StaticStub::static_stub()),
+            heth_params: None,
             secret_hash: comit::SecretHash::new(
                 comit::Secret::from_str(
                     "aa68d627971643a6f97f27c58957826fcba853ec2077fd10ec6b93d8e61deb4c",
@@ -474,55 +486,101 @@ impl StaticStub for Swap {
             herc20_funded: None,
             herc20_redeemed: None,
             herc20_refunded: None,
+            heth_funded: None,
+            heth_redeemed: None,
+            heth_refunded: None,
             archived: None,
         }
     }
 }
 
-impl From<(Swap, SwapId)> for SwapKind {
-    fn from(swap_data: (Swap, SwapId)) -> Self {
+impl TryFrom<(Swap, SwapId)> for SwapKind {
+    type Error = anyhow::Error;
+
+    fn try_from(swap_data: (Swap, SwapId)) -> anyhow::Result<Self> {
         let (swap, swap_id) = swap_data;
 
         let Swap {
             kind,
             hbit_params,
             herc20_params,
+            heth_params,
             secret_hash,
             utc_start_of_swap: start_of_swap,
             active_peer: taker,
             ..
         } = swap;
 
-        let swap = swap::SwapParams {
-            hbit_params: hbit_params.into(),
-            herc20_params: herc20_params.into(),
-            secret_hash,
-            start_of_swap,
-            swap_id,
-            taker,
+        let swap_kind = match kind {
+            Kind::HbitHerc20 | Kind::Herc20Hbit => {
+                let herc20_params = herc20_params
+                    .with_context(|| format!("no herc20 params stored for swap {}", swap_id))?;
+                let swap = swap::SwapParams {
+                    hbit_params: hbit_params.into(),
+                    herc20_params: herc20_params.into(),
+                    secret_hash,
+                    start_of_swap,
+                    swap_id,
+                    taker,
+                };
+
+                match kind {
+                    Kind::HbitHerc20 => SwapKind::HbitHerc20(swap),
+                    _ => SwapKind::Herc20Hbit(swap),
+                }
+            }
+            Kind::HbitHeth | Kind::HethHbit => {
+                let heth_params = heth_params
+                    .with_context(|| format!("no heth params stored for swap {}", swap_id))?;
+                let swap = swap::HethSwapParams {
+                    hbit_params: hbit_params.into(),
+                    heth_params: heth_params.into(),
+                    secret_hash,
+                    start_of_swap,
+                    swap_id,
+                    taker,
+                };
+
+                match kind {
+                    Kind::HbitHeth => SwapKind::HbitHeth(swap),
+                    _ => SwapKind::HethHbit(swap),
+                }
+            }
         };
 
-        match kind {
-            Kind::HbitHerc20 => SwapKind::HbitHerc20(swap),
-            Kind::Herc20Hbit => SwapKind::Herc20Hbit(swap),
-        }
+        Ok(swap_kind)
     }
 }
 
 impl From<SwapKind> for Swap {
     fn from(swap_kind: SwapKind) -> Self {
-        let (kind, swap) = match swap_kind {
-            SwapKind::HbitHerc20(swap) => (Kind::HbitHerc20, swap),
-            SwapKind::Herc20Hbit(swap) => (Kind::Herc20Hbit, swap),
+        let (kind, herc20_params, heth_params) = match &swap_kind {
+            SwapKind::HbitHerc20(swap) => (
+                Kind::HbitHerc20,
+                Some(swap.herc20_params.clone().into()),
+                None,
+            ),
+            SwapKind::Herc20Hbit(swap) => (
+                Kind::Herc20Hbit,
+                Some(swap.herc20_params.clone().into()),
+                None,
+            ),
+            SwapKind::HbitHeth(swap) => {
+                (Kind::HbitHeth, None, Some(swap.heth_params.clone().into()))
+            }
+            SwapKind::HethHbit(swap) => {
+                (Kind::HethHbit, None, Some(swap.heth_params.clone().into()))
+            }
         };
 
         Swap {
             kind,
-            hbit_params: swap.hbit_params.into(),
-            herc20_params: swap.herc20_params.into(),
-            secret_hash: swap.secret_hash,
-            utc_start_of_swap: swap.start_of_swap,
-            active_peer: swap.taker,
+            hbit_params: swap_kind.hbit_params().clone().into(),
+            herc20_params,
+            heth_params,
+            secret_hash: swap_kind.secret_hash(),
+            utc_start_of_swap: swap_kind.start_of_swap(),
+            active_peer: swap_kind.taker(),
             hbit_funded: None,
             hbit_redeemed: None,
             hbit_refunded: None,
@@ -530,6 +588,9 @@ impl From<SwapKind> for Swap {
             herc20_funded: None,
             herc20_redeemed: None,
             herc20_refunded: None,
+            heth_funded: None,
+            heth_redeemed: None,
+            heth_refunded: None,
             archived: None,
         }
     }
@@ -585,13 +646,26 @@ mod tests {
 
         let _ = db.insert_active_peer(peer.clone()).await.unwrap();
 
-        let res = db.contains_active_peer(&peer);
-        assert!(matches!(res, Ok(true)));
+        let res = db.active_swaps_with(&peer);
+        assert!(matches!(res, Ok(1)));
 
         let _ = db.remove_active_peer(&peer).await.unwrap();
-        let res = db.contains_active_peer(&peer);
+        let res = db.active_swaps_with(&peer);
+
+        matches!(res, Ok(0))
+    }
+
+    #[quickcheck_async::tokio]
+    async fn every_swap_with_a_peer_is_counted(peer: ActivePeer) -> bool {
+        let db = Database::new_test().unwrap();
+
+        db.insert_active_peer(peer.clone()).await.unwrap();
+        db.insert_active_peer(peer.clone()).await.unwrap();
+        assert_eq!(db.active_swaps_with(&peer).unwrap(), 2);
+
+        db.remove_active_peer(&peer).await.unwrap();
 
-        matches!(res, Ok(false))
+        db.active_swaps_with(&peer).unwrap() == 1
     }
 
     #[tokio::test]
@@ -669,15 +743,15 @@ mod tests {
     async fn peer_is_not_active_for_archived_swap(swap: SwapKind) -> bool {
         let db = Database::new_test().unwrap();
         let swap_id = swap.swap_id();
-        let peer = swap.params().taker;
+        let peer = swap.taker();
 
         db.insert_swap(swap.clone()).await.unwrap();
         db.insert_active_peer(peer.clone()).await.unwrap();
-        assert!(db.contains_active_peer(&peer).unwrap());
+        assert_eq!(db.active_swaps_with(&peer).unwrap(), 1);
 
         db.archive_swap(&swap_id).await.unwrap();
 
-        !db.contains_active_peer(&peer).unwrap()
+        db.active_swaps_with(&peer).unwrap() == 0
     }
 
     #[tokio::test]
@@ -11,6 +11,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct HbitFunded {
     pub location: ::bitcoin::OutPoint,
+    #[serde(default)]
+    pub block_height: Option<u32>,
 }
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
@@ -32,6 +34,7 @@ impl From<HbitFunded> for hbit::Funded {
     fn from(funded: HbitFunded) -> Self {
         hbit::Funded {
             location: funded.location,
+            block_height: funded.block_height,
         }
     }
 }
@@ -40,6 +43,7 @@ impl From<hbit::Funded> for HbitFunded {
     fn from(funded: hbit::Funded) -> Self {
         HbitFunded {
             location: funded.location,
+            block_height: funded.block_height,
         }
     }
 }
@@ -70,6 +74,8 @@ impl Load<hbit::Funded> for Database {
 pub struct HbitRedeemed {
     pub transaction: bitcoin::Txid,
     pub secret: Secret,
+    #[serde(default)]
+    pub block_height: Option<u32>,
 }
 
 impl From<HbitRedeemed> for hbit::Redeemed {
@@ -77,6 +83,7 @@ impl From<HbitRedeemed> for hbit::Redeemed {
         hbit::Redeemed {
             transaction: event.transaction,
             secret: event.secret,
+            block_height: event.block_height,
         }
     }
 }
@@ -86,6 +93,7 @@ impl From<hbit::Redeemed> for HbitRedeemed {
         HbitRedeemed {
             transaction: event.transaction,
             secret: event.secret,
+            block_height: event.block_height,
         }
     }
 }
@@ -115,12 +123,15 @@ impl Load<hbit::Redeemed> for Database {
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct HbitRefunded {
     pub transaction: bitcoin::Txid,
+    #[serde(default)]
+    pub block_height: Option<u32>,
 }
 
 impl From<HbitRefunded> for hbit::Refunded {
     fn from(event: HbitRefunded) -> Self {
         hbit::Refunded {
             transaction: event.transaction,
+            block_height: event.block_height,
         }
     }
 }
@@ -129,6 +140,7 @@ impl From<hbit::Refunded> for HbitRefunded {
     fn from(event: hbit::Refunded) -> Self {
         HbitRefunded {
             transaction: event.transaction,
+            block_height: event.block_height,
         }
     }
 }
@@ -249,6 +261,7 @@ mod tests {
         swap::SwapKind,
         StaticStub,
     };
+    use std::convert::TryFrom;
 
     fn bitcoin_transaction() -> ::bitcoin::Transaction {
         ::bitcoin::Transaction {
@@ -274,11 +287,14 @@ mod tests {
         let swap = Swap::static_stub();
         let swap_id = SwapId::default();
 
-        let swap_kind = SwapKind::from((swap, swap_id));
+        let swap_kind = SwapKind::try_from((swap, swap_id)).unwrap();
 
         db.insert_swap(swap_kind).await.unwrap();
 
-        let funded = hbit::Funded { location };
+        let funded = hbit::Funded {
+            location,
+            block_height: Some(100),
+        };
         db.save(funded, swap_id).await.unwrap();
 
         let stored_funded: hbit::Funded = db
@@ -287,6 +303,7 @@ mod tests {
             .expect("found the event");
 
         assert_eq!(stored_funded.location, location);
+        assert_eq!(stored_funded.block_height, Some(100));
     }
 
     #[tokio::test]
@@ -297,13 +314,14 @@ mod tests {
         let swap = Swap::static_stub();
         let swap_id = SwapId::default();
 
-        let swap_kind = SwapKind::from((swap, swap_id));
+        let swap_kind = SwapKind::try_from((swap, swap_id)).unwrap();
 
         db.insert_swap(swap_kind).await.unwrap();
 
         let event = hbit::Redeemed {
             transaction,
             secret,
+            block_height: Some(100),
         };
         db.save(event, swap_id).await.unwrap();
 
@@ -314,6 +332,7 @@ mod tests {
 
         assert_eq!(stored_event.transaction, transaction);
         assert_eq!(stored_event.secret, secret);
+        assert_eq!(stored_event.block_height, Some(100));
     }
 
     #[tokio::test]
@@ -323,11 +342,14 @@ mod tests {
         let swap = Swap::static_stub();
         let swap_id = SwapId::default();
 
-        let swap_kind = SwapKind::from((swap, swap_id));
+        let swap_kind = SwapKind::try_from((swap, swap_id)).unwrap();
 
         db.insert_swap(swap_kind).await.unwrap();
 
-        let event = hbit::Refunded { transaction };
+        let event = hbit::Refunded {
+            transaction,
+            block_height: None,
+        };
         db.save(event, swap_id).await.unwrap();
 
         let stored_event: hbit::Refunded = db
@@ -10,6 +10,8 @@ use serde::{Deserialize, Serialize};
 pub struct Herc20Deployed {
     pub transaction: ethereum::Hash,
     pub location: comit::htlc_location::Ethereum,
+    #[serde(default)]
+    pub block_height: Option<u32>,
 }
 
 impl From<Herc20Deployed> for herc20::Deployed {
@@ -17,6 +19,7 @@ impl From<Herc20Deployed> for herc20::Deployed {
         herc20::Deployed {
             transaction: event.transaction,
             location: event.location,
+            block_height: event.block_height,
         }
     }
 }
@@ -26,6 +29,7 @@ impl From<herc20::Deployed> for Herc20Deployed {
         Herc20Deployed {
             transaction: event.transaction,
             location: event.location,
+            block_height: event.block_height,
         }
     }
 }
@@ -55,12 +59,15 @@ impl Load<herc20::Deployed> for Database {
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Herc20Funded {
     pub transaction: ethereum::Hash,
+    #[serde(default)]
+    pub block_height: Option<u32>,
 }
 
 impl From<Herc20Funded> for herc20::Funded {
     fn from(event: Herc20Funded) -> Self {
         herc20::Funded {
             transaction: event.transaction,
+            block_height: event.block_height,
         }
     }
 }
@@ -69,6 +76,7 @@ impl From<herc20::Funded> for Herc20Funded {
     fn from(event: herc20::Funded) -> Self {
         Herc20Funded {
             transaction: event.transaction,
+            block_height: event.block_height,
         }
     }
 }
@@ -99,6 +107,8 @@ impl Load<herc20::Funded> for Database {
 pub struct Herc20Redeemed {
     pub transaction: ethereum::Hash,
     pub secret: Secret,
+    #[serde(default)]
+    pub block_height: Option<u32>,
 }
 
 impl From<Herc20Redeemed> for herc20::Redeemed {
@@ -106,6 +116,7 @@ impl From<Herc20Redeemed> for herc20::Redeemed {
         herc20::Redeemed {
             transaction: event.transaction,
             secret: event.secret,
+            block_height: event.block_height,
         }
     }
 }
@@ -115,6 +126,7 @@ impl From<herc20::Redeemed> for Herc20Redeemed {
         Herc20Redeemed {
             transaction: event.transaction,
             secret: event.secret,
+            block_height: event.block_height,
         }
     }
 }
@@ -144,12 +156,15 @@ impl Load<herc20::Redeemed> for Database {
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Herc20Refunded {
     pub transaction: ethereum::Hash,
+    #[serde(default)]
+    pub block_height: Option<u32>,
 }
 
 impl From<Herc20Refunded> for herc20::Refunded {
     fn from(event: Herc20Refunded) -> Self {
         herc20::Refunded {
             transaction: event.transaction,
+            block_height: event.block_height,
         }
     }
 }
@@ -158,6 +173,7 @@ impl From<herc20::Refunded> for Herc20Refunded {
     fn from(event: herc20::Refunded) -> Self {
         Herc20Refunded {
             transaction: event.transaction,
+            block_height: event.block_height,
         }
     }
 }
@@ -277,6 +293,7 @@ impl crate::StaticStub for Params {
 mod tests {
     use super::*;
     use crate::{database::Swap, swap::SwapKind, StaticStub};
+    use std::convert::TryFrom;
 
     #[tokio::test]
     async fn save_and_load_herc20_deployed() {
@@ -286,13 +303,14 @@ mod tests {
         let transaction = comit::transaction::Ethereum::default().hash;
         let location = comit::htlc_location::Ethereum::random();
 
-        let swap_kind = SwapKind::from((swap, swap_id));
+        let swap_kind = SwapKind::try_from((swap, swap_id)).unwrap();
 
         db.insert_swap(swap_kind).await.unwrap();
 
         let event = herc20::Deployed {
             transaction,
             location,
+            block_height: Some(100),
         };
         db.save(event, swap_id).await.unwrap();
 
@@ -303,6 +321,7 @@ mod tests {
 
         assert_eq!(stored_event.transaction, transaction);
         assert_eq!(stored_event.location, location);
+        assert_eq!(stored_event.block_height, Some(100));
     }
 
     #[tokio::test]
@@ -312,11 +331,14 @@ mod tests {
         let swap_id = SwapId::default();
         let transaction = comit::transaction::Ethereum::default().hash;
 
-        let swap_kind = SwapKind::from((swap, swap_id));
+        let swap_kind = SwapKind::try_from((swap, swap_id)).unwrap();
 
         db.insert_swap(swap_kind).await.unwrap();
 
-        let event = herc20::Funded { transaction };
+        let event = herc20::Funded {
+            transaction,
+            block_height: Some(100),
+        };
         db.save(event, swap_id).await.unwrap();
 
         let stored_event: herc20::Funded = db
@@ -335,13 +357,14 @@ mod tests {
         let transaction = comit::transaction::Ethereum::default().hash;
         let secret = Secret::from_vec(b"are those thirty-two bytes? Hum.").unwrap();
 
-        let swap_kind = SwapKind::from((swap, swap_id));
+        let swap_kind = SwapKind::try_from((swap, swap_id)).unwrap();
 
         db.insert_swap(swap_kind).await.unwrap();
 
         let event = herc20::Redeemed {
             transaction,
             secret,
+            block_height: None,
         };
         db.save(event, swap_id).await.unwrap();
 
@@ -361,11 +384,14 @@ mod tests {
         let swap_id = SwapId::default();
         let transaction = comit::transaction::Ethereum::default().hash;
 
-        let swap_kind = SwapKind::from((swap, swap_id));
+        let swap_kind = SwapKind::try_from((swap, swap_id)).unwrap();
 
         db.insert_swap(swap_kind).await.unwrap();
 
-        let event = herc20::Refunded { transaction };
+        let event = herc20::Refunded {
+            transaction,
+            block_height: None,
+        };
         db.save(event, swap_id).await.unwrap();
 
         let stored_event: herc20::Refunded = db
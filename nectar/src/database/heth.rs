@@ -0,0 +1,288 @@
+use crate::{
+    database::{Database, Load, Save},
+    swap::heth,
+    SwapId,
+};
+use comit::{asset::Ether, ethereum, identity, Secret, SecretHash, Timestamp};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct HethFunded {
+    pub transaction: ethereum::Hash,
+    pub location: comit::htlc_location::Ethereum,
+    #[serde(default)]
+    pub block_height: Option<u32>,
+}
+
+impl From<HethFunded> for heth::Funded {
+    fn from(event: HethFunded) -> Self {
+        heth::Funded {
+            transaction: event.transaction,
+            location: event.location,
+            block_height: event.block_height,
+        }
+    }
+}
+
+impl From<heth::Funded> for HethFunded {
+    fn from(event: heth::Funded) -> Self {
+        HethFunded {
+            transaction: event.transaction,
+            location: event.location,
+            block_height: event.block_height,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Save<heth::Funded> for Database {
+    async fn save(&self, event: heth::Funded, swap_id: SwapId) -> anyhow::Result<()> {
+        self.update_swap(&swap_id, |mut old_swap| match &old_swap.heth_funded {
+            Some(_) => anyhow::bail!("Heth Funded event is already stored"),
+            None => {
+                old_swap.heth_funded = Some(event.into());
+                Ok(old_swap)
+            }
+        })
+        .await
+    }
+}
+
+impl Load<heth::Funded> for Database {
+    fn load(&self, swap_id: SwapId) -> anyhow::Result<Option<heth::Funded>> {
+        let swap = self.get_swap_or_bail(&swap_id)?;
+
+        Ok(swap.heth_funded.map(Into::into))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct HethRedeemed {
+    pub transaction: ethereum::Hash,
+    pub secret: Secret,
+    #[serde(default)]
+    pub block_height: Option<u32>,
+}
+
+impl From<HethRedeemed> for heth::Redeemed {
+    fn from(event: HethRedeemed) -> Self {
+        heth::Redeemed {
+            transaction: event.transaction,
+            secret: event.secret,
+            block_height: event.block_height,
+        }
+    }
+}
+
+impl From<heth::Redeemed> for HethRedeemed {
+    fn from(event: heth::Redeemed) -> Self {
+        HethRedeemed {
+            transaction: event.transaction,
+            secret: event.secret,
+            block_height: event.block_height,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Save<heth::Redeemed> for Database {
+    async fn save(&self, event: heth::Redeemed, swap_id: SwapId) -> anyhow::Result<()> {
+        self.update_swap(&swap_id, |mut old_swap| match &old_swap.heth_redeemed {
+            Some(_) => anyhow::bail!("Heth Redeem event is already stored"),
+            None => {
+                old_swap.heth_redeemed = Some(event.into());
+                Ok(old_swap)
+            }
+        })
+        .await
+    }
+}
+
+impl Load<heth::Redeemed> for Database {
+    fn load(&self, swap_id: SwapId) -> anyhow::Result<Option<heth::Redeemed>> {
+        let swap = self.get_swap_or_bail(&swap_id)?;
+
+        Ok(swap.heth_redeemed.map(Into::into))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct HethRefunded {
+    pub transaction: ethereum::Hash,
+    #[serde(default)]
+    pub block_height: Option<u32>,
+}
+
+impl From<HethRefunded> for heth::Refunded {
+    fn from(event: HethRefunded) -> Self {
+        heth::Refunded {
+            transaction: event.transaction,
+            block_height: event.block_height,
+        }
+    }
+}
+
+impl From<heth::Refunded> for HethRefunded {
+    fn from(event: heth::Refunded) -> Self {
+        HethRefunded {
+            transaction: event.transaction,
+            block_height: event.block_height,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Save<heth::Refunded> for Database {
+    async fn save(&self, event: heth::Refunded, swap_id: SwapId) -> anyhow::Result<()> {
+        self.update_swap(&swap_id, |mut old_swap| match &old_swap.heth_refunded {
+            Some(_) => anyhow::bail!("Heth Refunded event is already stored"),
+            None => {
+                old_swap.heth_refunded = Some(event.into());
+                Ok(old_swap)
+            }
+        })
+        .await
+    }
+}
+
+impl Load<heth::Refunded> for Database {
+    fn load(&self, swap_id: SwapId) -> anyhow::Result<Option<heth::Refunded>> {
+        let swap = self.get_swap_or_bail(&swap_id)?;
+
+        Ok(swap.heth_refunded.map(Into::into))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Params {
+    pub asset: Ether,
+    pub redeem_identity: identity::Ethereum,
+    pub refund_identity: identity::Ethereum,
+    pub expiry: Timestamp,
+    pub secret_hash: SecretHash,
+    pub chain_id: ethereum::ChainId,
+}
+
+impl From<Params> for comit::heth::Params {
+    fn from(params: Params) -> Self {
+        let Params {
+            asset,
+            redeem_identity,
+            refund_identity,
+            expiry,
+            secret_hash,
+            chain_id,
+        } = params;
+
+        comit::heth::Params {
+            asset,
+            redeem_identity,
+            refund_identity,
+            expiry,
+            secret_hash,
+            chain_id,
+        }
+    }
+}
+
+impl From<comit::heth::Params> for Params {
+    fn from(params: comit::heth::Params) -> Self {
+        Params {
+            asset: params.asset,
+            redeem_identity: params.redeem_identity,
+            refund_identity: params.refund_identity,
+            expiry: params.expiry,
+            secret_hash: params.secret_hash,
+            chain_id: params.chain_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{database::Swap, swap::SwapKind, StaticStub};
+    use std::convert::TryFrom;
+
+    #[tokio::test]
+    async fn save_and_load_heth_funded() {
+        let db = Database::new_test().unwrap();
+        let swap = Swap::static_stub();
+        let swap_id = SwapId::default();
+        let transaction = comit::transaction::Ethereum::default().hash;
+        let location = comit::htlc_location::Ethereum::random();
+
+        let swap_kind = SwapKind::try_from((swap, swap_id)).unwrap();
+
+        db.insert_swap(swap_kind).await.unwrap();
+
+        let event = heth::Funded {
+            transaction,
+            location,
+            block_height: Some(100),
+        };
+        db.save(event, swap_id).await.unwrap();
+
+        let stored_event: heth::Funded = db
+            .load(swap_id)
+            .expect("No error loading")
+            .expect("found the event");
+
+        assert_eq!(stored_event.transaction, transaction);
+        assert_eq!(stored_event.location, location);
+        assert_eq!(stored_event.block_height, Some(100));
+    }
+
+    #[tokio::test]
+    async fn save_and_load_heth_redeemed() {
+        let db = Database::new_test().unwrap();
+        let swap = Swap::static_stub();
+        let swap_id = SwapId::default();
+        let transaction = comit::transaction::Ethereum::default().hash;
+        let secret = Secret::from_vec(b"are those thirty-two bytes? Hum.").unwrap();
+
+        let swap_kind = SwapKind::try_from((swap, swap_id)).unwrap();
+
+        db.insert_swap(swap_kind).await.unwrap();
+
+        let event = heth::Redeemed {
+            transaction,
+            secret,
+            block_height: None,
+        };
+        db.save(event, swap_id).await.unwrap();
+
+        let stored_event: heth::Redeemed = db
+            .load(swap_id)
+            .expect("No error loading")
+            .expect("found the event");
+
+        assert_eq!(stored_event.transaction, transaction);
+        assert_eq!(stored_event.secret, secret);
+    }
+
+    #[tokio::test]
+    async fn save_and_load_heth_refunded() {
+        let db = Database::new_test().unwrap();
+        let swap = Swap::static_stub();
+        let swap_id = SwapId::default();
+        let transaction = comit::transaction::Ethereum::default().hash;
+
+        let swap_kind = SwapKind::try_from((swap, swap_id)).unwrap();
+
+        db.insert_swap(swap_kind).await.unwrap();
+
+        let event = heth::Refunded {
+            transaction,
+            block_height: None,
+        };
+        db.save(event, swap_id).await.unwrap();
+
+        let stored_event: heth::Refunded = db
+            .load(swap_id)
+            .expect("No error loading")
+            .expect("found the event");
+
+        assert_eq!(stored_event.transaction, transaction);
+    }
+}
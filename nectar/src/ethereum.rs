@@ -127,6 +127,10 @@ pub mod ether {
 
             u_int_value.try_into()
         }
+
+        pub fn as_wei(&self) -> BigUint {
+            BigUint::from_bytes_le(&self.0.to_bytes())
+        }
     }
 
     impl TryFrom<BigUint> for Amount {
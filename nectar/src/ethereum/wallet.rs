@@ -120,9 +120,9 @@ impl Wallet {
         &self,
         DeployContract {
             data,
+            amount,
             gas_limit,
             chain_id,
-            ..
         }: DeployContract,
         gas_price: ether::Amount,
     ) -> anyhow::Result<(Hash, ethereum::Address)> {
@@ -133,7 +133,7 @@ impl Wallet {
                     gas_price: gas_price.into(),
                     gas_limit: gas_limit.into(),
                     to: clarity::Address::default(),
-                    value: 0u64.into(),
+                    value: ether::Amount::from(amount).into(),
                     data,
                     signature: None,
                 },
@@ -410,6 +410,7 @@ impl From<DeployedContract> for comit::herc20::Deployed {
         Self {
             transaction: from.transaction,
             location: from.contract_address,
+            block_height: None,
         }
     }
 }
@@ -73,6 +73,7 @@ pub struct Trade {
 pub enum Symbol {
     Btc,
     Dai,
+    Eth,
 }
 
 #[derive(Debug, Copy, Clone, Serialize)]
@@ -50,7 +50,7 @@ use conquer_once::Lazy;
 pub use anyhow::Result;
 pub use maker::Maker;
 pub use mid_market_rate::MidMarketRate;
-pub use rate::{Rate, Spread};
+pub use rate::{Rate, Skew, Spread};
 pub use seed::Seed;
 pub use swap_id::SwapId;
 
@@ -1,42 +1,62 @@
+use self::{
+    fees::{FeesTooHigh, SwapFees},
+    risk::RiskLimits,
+};
 use crate::{
     bitcoin,
     ethereum::{self, dai},
-    order::Symbol,
+    order::{BtcDaiOrderForm, Symbol},
+    swap::SwapKind,
     MidMarketRate,
 };
-use comit::{ledger, order::SwapProtocol, BtcDaiOrder, Position, Role};
+use comit::{ledger, order::SwapProtocol, BtcErc20Order, Position, Role};
+use time::OffsetDateTime;
 
+pub mod fees;
+pub mod risk;
 pub mod strategy;
 
 // Bundles the state of the application
 #[derive(Debug)]
-pub struct Maker {
+pub struct Maker<S = strategy::AnyStrategy> {
     btc_balance: Option<bitcoin::Amount>,
     dai_balance: Option<dai::Amount>,
     mid_market_rate: Option<MidMarketRate>,
-    pub strategy: strategy::AllIn,
+    fees: Option<SwapFees>,
+    risk: RiskLimits,
+    pub strategy: S,
     bitcoin_network: ledger::Bitcoin,
     ethereum_chain: ethereum::Chain,
     role: Role,
     comit_network: comit::Network,
 }
 
-impl Maker {
+impl<S> Maker<S>
+where
+    S: strategy::Strategy,
+{
     #![allow(clippy::too_many_arguments)]
     pub fn new(
         btc_balance: bitcoin::Amount,
         dai_balance: dai::Amount,
         mid_market_rate: MidMarketRate,
-        strategy: strategy::AllIn,
+        fees: Option<SwapFees>,
+        mut strategy: S,
         bitcoin_network: ledger::Bitcoin,
         dai_chain: ethereum::Chain,
         role: Role,
         comit_network: comit::Network,
     ) -> Self {
+        if let Some(fees) = &fees {
+            strategy.fees_updated(fees.clone());
+        }
+
         Maker {
             btc_balance: Some(btc_balance),
             dai_balance: Some(dai_balance),
             mid_market_rate: Some(mid_market_rate),
+            fees,
+            risk: RiskLimits::default(),
             strategy,
             bitcoin_network,
             ethereum_chain: dai_chain,
@@ -45,6 +65,10 @@ impl Maker {
         }
     }
 
+    pub fn with_risk_limits(self, risk: RiskLimits) -> Self {
+        Self { risk, ..self }
+    }
+
     pub fn update_rate(
         &mut self,
         mid_market_rate: MidMarketRate,
@@ -56,10 +80,7 @@ impl Maker {
             _ => {
                 self.mid_market_rate = Some(mid_market_rate);
 
-                Ok(Some(PublishOrders {
-                    new_sell_order: self.new_sell_order()?,
-                    new_buy_order: self.new_buy_order()?,
-                }))
+                Ok(Some(self.new_orders()?))
             }
         }
     }
@@ -68,6 +89,17 @@ impl Maker {
         self.mid_market_rate = None;
     }
 
+    pub fn update_fees(&mut self, fees: SwapFees) -> anyhow::Result<Option<PublishOrders>> {
+        if self.fees.as_ref() == Some(&fees) {
+            return Ok(None);
+        }
+
+        self.strategy.fees_updated(fees.clone());
+        self.fees = Some(fees);
+
+        Ok(Some(self.new_orders()?))
+    }
+
     pub fn update_bitcoin_balance(
         &mut self,
         balance: bitcoin::Amount,
@@ -81,10 +113,7 @@ impl Maker {
 
         self.btc_balance = Some(balance);
 
-        Ok(Some(PublishOrders {
-            new_sell_order: self.new_sell_order()?,
-            new_buy_order: self.new_buy_order()?,
-        }))
+        Ok(Some(self.new_orders()?))
     }
 
     pub fn invalidate_bitcoin_balance(&mut self) {
@@ -104,10 +133,7 @@ impl Maker {
 
         self.dai_balance = Some(balance);
 
-        Ok(Some(PublishOrders {
-            new_sell_order: self.new_sell_order()?,
-            new_buy_order: self.new_buy_order()?,
-        }))
+        Ok(Some(self.new_orders()?))
     }
 
     pub fn invalidate_dai_balance(&mut self) {
@@ -118,40 +144,119 @@ impl Maker {
         SwapProtocol::new(self.role, position, self.comit_network)
     }
 
-    pub fn new_sell_order(&self) -> anyhow::Result<BtcDaiOrder> {
+    /// All orders to publish given the current rate and balances, replacing
+    /// the ones published before. None once the kill switch is engaged.
+    pub fn new_orders(&self) -> anyhow::Result<PublishOrders> {
+        if self.risk.is_kill_switch_engaged() {
+            return Ok(PublishOrders {
+                new_sell_orders: Vec::new(),
+                new_buy_orders: Vec::new(),
+            });
+        }
+
+        Ok(PublishOrders {
+            new_sell_orders: self.new_sell_orders()?,
+            new_buy_orders: self.new_buy_orders()?,
+        })
+    }
+
+    pub fn new_sell_orders(&self) -> anyhow::Result<Vec<BtcErc20Order>> {
+        let orders = self.strategy_sell_orders()?;
+
+        Ok(self.within_risk_limits(orders, OffsetDateTime::now_utc()))
+    }
+
+    pub fn new_buy_orders(&self) -> anyhow::Result<Vec<BtcErc20Order>> {
+        let orders = self.strategy_buy_orders()?;
+
+        Ok(self.within_risk_limits(orders, OffsetDateTime::now_utc()))
+    }
+
+    fn strategy_sell_orders(&self) -> anyhow::Result<Vec<BtcErc20Order>> {
         let mid_market_rate = self
             .mid_market_rate
             .ok_or_else(|| RateNotAvailable(Position::Sell))?;
         let btc_balance = self
             .btc_balance
             .ok_or_else(|| BalanceNotAvailable(Symbol::Btc))?;
+        let dai_balance = self
+            .dai_balance
+            .as_ref()
+            .ok_or_else(|| BalanceNotAvailable(Symbol::Dai))?;
 
-        let form = self
-            .strategy
-            .new_sell(btc_balance, mid_market_rate.into())?;
-        let order = form.to_comit_order(self.swap_protocol(Position::Sell));
-
-        Ok(order)
+        let forms = without_unprofitable(self.strategy.new_sells(
+            btc_balance,
+            dai_balance,
+            mid_market_rate.into(),
+        ))?;
+        let orders = forms
+            .into_iter()
+            .map(|form| form.to_comit_order(self.swap_protocol(Position::Sell)))
+            .collect();
+
+        Ok(orders)
     }
 
-    pub fn new_buy_order(&self) -> anyhow::Result<BtcDaiOrder> {
+    fn strategy_buy_orders(&self) -> anyhow::Result<Vec<BtcErc20Order>> {
         let mid_market_rate = self
             .mid_market_rate
             .ok_or_else(|| RateNotAvailable(Position::Buy))?;
         let dai_balance = self
             .dai_balance
-            .clone()
+            .as_ref()
             .ok_or_else(|| BalanceNotAvailable(Symbol::Dai))?;
+        let btc_balance = self
+            .btc_balance
+            .ok_or_else(|| BalanceNotAvailable(Symbol::Btc))?;
 
-        let form = self.strategy.new_buy(dai_balance, mid_market_rate.into())?;
-        let order = form.to_comit_order(self.swap_protocol(Position::Buy));
+        let forms = without_unprofitable(self.strategy.new_buys(
+            btc_balance,
+            dai_balance,
+            mid_market_rate.into(),
+        ))?;
+        let orders = forms
+            .into_iter()
+            .map(|form| form.to_comit_order(self.swap_protocol(Position::Buy)))
+            .collect();
+
+        Ok(orders)
+    }
 
-        Ok(order)
+    /// Orders that would breach a risk limit cannot be taken, they are not
+    /// published.
+    fn within_risk_limits(
+        &self,
+        orders: Vec<BtcErc20Order>,
+        now: OffsetDateTime,
+    ) -> Vec<BtcErc20Order> {
+        orders
+            .into_iter()
+            .filter(|order| match self.risk.check(order, now) {
+                Ok(()) => true,
+                Err(breach) => {
+                    tracing::debug!("Not publishing order: {:#}", breach);
+                    false
+                }
+            })
+            .collect()
+    }
+
+    /// A risk limit is reached once none of the orders the strategy would
+    /// publish fits within the limits anymore.
+    fn is_risk_limit_reached(&self, now: OffsetDateTime) -> bool {
+        let orders = self
+            .strategy_sell_orders()
+            .unwrap_or_default()
+            .into_iter()
+            .chain(self.strategy_buy_orders().unwrap_or_default())
+            .collect::<Vec<_>>();
+
+        !orders.is_empty() && self.within_risk_limits(orders, now).is_empty()
     }
 
     pub fn process_taken_order(
         &mut self,
-        order: BtcDaiOrder,
+        order: BtcErc20Order,
     ) -> anyhow::Result<TakeRequestDecision> {
         let current_mid_market_rate = self
             .mid_market_rate
@@ -166,12 +271,88 @@ impl Maker {
             .as_ref()
             .ok_or_else(|| BalanceNotAvailable(Symbol::Btc))?;
 
-        self.strategy.process_taken_order(
-            order,
+        let now = OffsetDateTime::now_utc();
+        if self.engage_kill_switch_if_exposed(now) {
+            return Ok(TakeRequestDecision::RiskLimitReached);
+        }
+        if let Err(breach) = self.risk.check(&order, now) {
+            tracing::warn!("Rejecting taken order: {:#}", breach);
+            return Ok(TakeRequestDecision::RiskLimitWouldBeBreached);
+        }
+
+        let decision = self.strategy.process_taken_order(
+            order.clone(),
             current_mid_market_rate.into(),
             dai_balance,
             btc_balance,
-        )
+        )?;
+        if decision == TakeRequestDecision::GoForSwap {
+            self.risk.swap_started(&order, now);
+
+            if self.is_risk_limit_reached(now) {
+                tracing::error!("Engaging the kill switch: risk limit reached");
+                self.risk.engage_kill_switch();
+            }
+        }
+
+        Ok(decision)
+    }
+
+    pub fn is_kill_switch_engaged(&self) -> bool {
+        self.risk.is_kill_switch_engaged()
+    }
+
+    /// Whether the taker may start another swap given the number of swaps
+    /// already ongoing with them.
+    pub fn allows_another_swap_with(&self, ongoing_swaps: usize) -> bool {
+        ongoing_swaps < self.risk.max_swaps_per_peer()
+    }
+
+    /// Release the risk accounted and the funds reserved for a taken order
+    /// whose swap failed to be set up.
+    pub fn swap_setup_failed(&mut self, order: &BtcErc20Order) {
+        self.risk.swap_aborted(order);
+        self.strategy.swap_setup_failed(order);
+    }
+
+    pub fn swap_resumed(&mut self, swap: &SwapKind) {
+        let now = OffsetDateTime::now_utc();
+
+        self.risk.swap_resumed(swap, now);
+        let _ = self.engage_kill_switch_if_exposed(now);
+    }
+
+    /// Engages the kill switch if the tracked exposure exceeds a risk limit.
+    /// Returns whether the kill switch is engaged.
+    fn engage_kill_switch_if_exposed(&mut self, now: OffsetDateTime) -> bool {
+        if self.risk.is_kill_switch_engaged() {
+            return true;
+        }
+        if let Err(breach) = self.risk.check_exposure(now) {
+            tracing::error!("Engaging the kill switch: {:#}", breach);
+            self.risk.engage_kill_switch();
+            return true;
+        }
+
+        false
+    }
+
+    pub fn swap_finished(&mut self, swap: SwapKind) {
+        self.risk.swap_finished(&swap);
+        self.strategy.swap_finished(swap);
+    }
+}
+
+/// Orders whose expected fees are too high are not published at all.
+fn without_unprofitable(
+    forms: anyhow::Result<Vec<BtcDaiOrderForm>>,
+) -> anyhow::Result<Vec<BtcDaiOrderForm>> {
+    match forms {
+        Err(e) if e.is::<FeesTooHigh>() => {
+            tracing::info!("Not publishing order: {:#}", e);
+            Ok(Vec::new())
+        }
+        forms => forms,
     }
 }
 
@@ -180,12 +361,23 @@ pub enum TakeRequestDecision {
     GoForSwap,
     RateNotProfitable,
     InsufficientFunds,
+    RiskLimitWouldBeBreached,
+    RiskLimitReached,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct PublishOrders {
-    pub new_sell_order: BtcDaiOrder,
-    pub new_buy_order: BtcDaiOrder,
+    pub new_sell_orders: Vec<BtcErc20Order>,
+    pub new_buy_orders: Vec<BtcErc20Order>,
+}
+
+impl PublishOrders {
+    pub fn into_orders(self) -> Vec<BtcErc20Order> {
+        self.new_sell_orders
+            .into_iter()
+            .chain(self.new_buy_orders)
+            .collect()
+    }
 }
 
 #[derive(Debug, Copy, Clone, thiserror::Error)]
@@ -202,20 +394,26 @@ mod tests {
     use crate::{
         bitcoin,
         bitcoin::amount::{btc, some_btc},
-        ethereum::dai::{dai, some_dai},
+        config,
+        ethereum::{
+            dai::{dai, some_dai},
+            ether,
+        },
         order::btc_dai_order,
         rate::rate,
         MidMarketRate, Rate, Spread, StaticStub,
     };
     use std::convert::TryFrom;
 
-    impl StaticStub for Maker {
+    impl StaticStub for Maker<strategy::AllIn> {
         fn static_stub() -> Self {
             Self {
                 btc_balance: Some(bitcoin::Amount::default()),
                 dai_balance: Some(dai::Amount::default()),
                 strategy: strategy::AllIn::static_stub(),
                 mid_market_rate: Some(MidMarketRate::static_stub()),
+                fees: None,
+                risk: RiskLimits::default(),
                 bitcoin_network: ledger::Bitcoin::Mainnet,
                 ethereum_chain: ethereum::Chain::static_stub(),
                 role: Role::Bob,
@@ -235,17 +433,17 @@ mod tests {
             ..StaticStub::static_stub()
         };
 
-        let taken_order = BtcDaiOrder {
+        let taken_order = BtcErc20Order {
             ..StaticStub::static_stub()
         };
 
         let result = maker.process_taken_order(taken_order);
         assert!(result.is_err());
 
-        let result = maker.new_buy_order();
+        let result = maker.new_buy_orders();
         assert!(result.is_err());
 
-        let result = maker.new_sell_order();
+        let result = maker.new_sell_orders();
         assert!(result.is_err());
     }
 
@@ -308,6 +506,31 @@ mod tests {
         assert_eq!(maker.mid_market_rate, Some(new_mid_market_rate))
     }
 
+    #[test]
+    fn no_orders_published_if_fees_are_too_high() {
+        let mut maker = Maker {
+            btc_balance: some_btc(0.001),
+            dai_balance: some_dai(10.0),
+            mid_market_rate: some_rate(10_000.0),
+            strategy: strategy::AllIn::static_stub().with_max_fee_share(100),
+            ..StaticStub::static_stub()
+        };
+        let fees = SwapFees::new(
+            bitcoin::Amount::from_sat(10),
+            ether::Amount::zero(),
+            rate(1.0),
+        );
+
+        let publish_orders = maker
+            .update_fees(fees.clone())
+            .unwrap()
+            .expect("to publish new orders if fees change");
+
+        assert!(publish_orders.new_sell_orders.is_empty());
+        assert!(publish_orders.new_buy_orders.is_empty());
+        assert!(maker.update_fees(fees).unwrap().is_none());
+    }
+
     #[test]
     fn no_new_sell_order_if_no_btc_balance_change() {
         let mut maker = Maker {
@@ -383,7 +606,7 @@ mod tests {
             ..StaticStub::static_stub()
         };
 
-        let new_sell_order = maker.new_sell_order().unwrap();
+        let new_sell_order = maker.new_sell_orders().unwrap().remove(0);
         assert_eq!(new_sell_order.quantity.sats(), btc(1.0).as_sat());
 
         let result = maker.process_taken_order(new_sell_order).unwrap();
@@ -408,7 +631,7 @@ mod tests {
             ..StaticStub::static_stub()
         };
 
-        let new_buy_order = maker.new_buy_order().unwrap();
+        let new_buy_order = maker.new_buy_orders().unwrap().remove(0);
         assert_eq!(dai::Amount::from(new_buy_order.quote()), dai(1.0));
 
         let result = maker.process_taken_order(new_buy_order).unwrap();
@@ -416,6 +639,90 @@ mod tests {
         assert_eq!(result, TakeRequestDecision::GoForSwap);
     }
 
+    #[test]
+    fn order_that_would_breach_a_risk_limit_is_rejected() {
+        let strategy = strategy::AllIn::new(
+            StaticStub::static_stub(),
+            None,
+            Some(btc(1.0)),
+            Spread::static_stub(),
+            StaticStub::static_stub(),
+        );
+
+        let mut maker = Maker {
+            btc_balance: some_btc(3.0),
+            mid_market_rate: some_rate(1.0),
+            strategy,
+            risk: RiskLimits::new(config::Risk {
+                max_notional: Some(btc(0.5)),
+                ..Default::default()
+            }),
+            ..StaticStub::static_stub()
+        };
+
+        let sell_order = maker.strategy_sell_orders().unwrap().remove(0);
+        let result = maker.process_taken_order(sell_order).unwrap();
+
+        assert_eq!(result, TakeRequestDecision::RiskLimitWouldBeBreached);
+        assert!(!maker.risk.is_kill_switch_engaged());
+        assert!(maker.new_sell_orders().unwrap().is_empty());
+    }
+
+    #[test]
+    fn kill_switch_is_engaged_once_a_risk_limit_is_reached() {
+        let strategy = strategy::AllIn::new(
+            StaticStub::static_stub(),
+            None,
+            Some(btc(1.0)),
+            Spread::static_stub(),
+            StaticStub::static_stub(),
+        );
+
+        let mut maker = Maker {
+            btc_balance: some_btc(3.0),
+            mid_market_rate: some_rate(1.0),
+            strategy,
+            risk: RiskLimits::new(config::Risk {
+                max_notional: Some(btc(1.5)),
+                ..Default::default()
+            }),
+            ..StaticStub::static_stub()
+        };
+
+        let new_sell_order = maker.new_sell_orders().unwrap().remove(0);
+        let result = maker.process_taken_order(new_sell_order).unwrap();
+
+        assert_eq!(result, TakeRequestDecision::GoForSwap);
+        assert!(maker.is_kill_switch_engaged());
+        assert!(maker.new_orders().unwrap().into_orders().is_empty());
+    }
+
+    #[test]
+    fn kill_switch_is_engaged_if_resumed_swaps_exceed_a_risk_limit() {
+        let strategy = strategy::AllIn::new(
+            StaticStub::static_stub(),
+            None,
+            Some(btc(1.0)),
+            Spread::static_stub(),
+            StaticStub::static_stub(),
+        );
+
+        let mut maker = Maker {
+            btc_balance: some_btc(3.0),
+            mid_market_rate: some_rate(1.0),
+            strategy,
+            risk: RiskLimits::new(config::Risk {
+                max_notional: Some(btc(0.1)),
+                ..Default::default()
+            }),
+            ..StaticStub::static_stub()
+        };
+
+        maker.swap_resumed(&SwapKind::HbitHerc20(StaticStub::static_stub()));
+
+        assert!(maker.new_orders().unwrap().into_orders().is_empty());
+    }
+
     #[test]
     fn new_buy_order_with_max_buy() {
         let strategy = strategy::AllIn::new(
@@ -434,7 +741,7 @@ mod tests {
             ..StaticStub::static_stub()
         };
 
-        let new_buy_order = maker.new_buy_order().unwrap();
+        let new_buy_order = maker.new_buy_orders().unwrap().remove(0);
         assert_eq!(new_buy_order.quantity.to_inner(), btc(0.002));
         assert_eq!(dai::Amount::from(new_buy_order.quote()), dai(18.0));
     }
@@ -457,7 +764,7 @@ mod tests {
             ..StaticStub::static_stub()
         };
 
-        let new_buy_order = maker.new_buy_order().unwrap();
+        let new_buy_order = maker.new_buy_orders().unwrap().remove(0);
         assert_eq!(new_buy_order.quantity.to_inner(), btc(0.002));
         assert_eq!(dai::Amount::from(new_buy_order.quote()), dai(20.0));
     }
@@ -0,0 +1,173 @@
+use crate::{
+    bitcoin,
+    ethereum::{dai, ether},
+    Rate, Result,
+};
+use comit::{herc20, Position};
+use num::{BigUint, Integer, ToPrimitive, Zero};
+
+/// The prices of the transactions we publish during a swap, used to include
+/// the expected on-chain fees in the price of our orders.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwapFees {
+    bitcoin_vbyte_rate: bitcoin::Amount,
+    ethereum_gas_price: ether::Amount,
+    eth_dai_rate: Rate,
+}
+
+impl SwapFees {
+    pub fn new(
+        bitcoin_vbyte_rate: bitcoin::Amount,
+        ethereum_gas_price: ether::Amount,
+        eth_dai_rate: Rate,
+    ) -> Self {
+        Self {
+            bitcoin_vbyte_rate,
+            ethereum_gas_price,
+            eth_dai_rate,
+        }
+    }
+
+    /// The fees we expect to pay when swapping an order of the given
+    /// position, converted to dai.
+    ///
+    /// Selling bitcoin, we fund the bitcoin HTLC and redeem the ERC20 HTLC.
+    /// Buying bitcoin, we deploy and fund the ERC20 HTLC and redeem the
+    /// bitcoin HTLC.
+    pub fn in_dai(&self, position: Position, mid_market_rate: Rate) -> dai::Amount {
+        let (vbytes, gas) = match position {
+            Position::Sell => (
+                bitcoin::EXPECTED_FUND_TRANSACTION_VBYTE_WEIGHT,
+                herc20::Params::redeem_tx_gas_limit(),
+            ),
+            Position::Buy => (
+                bitcoin::EXPECTED_REDEEM_TRANSACTION_VBYTE_WEIGHT,
+                herc20::Params::deploy_tx_gas_limit() + herc20::Params::fund_tx_gas_limit(),
+            ),
+        };
+
+        // The mid-market rate is in atto dai per sat, the ETH/DAI rate has to
+        // be scaled down by its precision to be in atto dai per wei.
+        let bitcoin_fee =
+            BigUint::from(self.bitcoin_vbyte_rate.as_sat()) * vbytes * mid_market_rate.integer();
+        let ethereum_fee = self.ethereum_gas_price.as_wei() * gas * self.eth_dai_rate.integer()
+            / 10u64.pow(Rate::PRECISION.into());
+
+        dai::Amount::from_atto(bitcoin_fee + ethereum_fee)
+    }
+
+    /// Include the fees of swapping `quantity` in the rate: the fees per sat
+    /// are added to the rate of a sell order and subtracted from the rate of
+    /// a buy order.
+    ///
+    /// Fails with [`FeesTooHigh`] if the fees exceed `max_fee_share`
+    /// (permyriad) of the value of the order at the mid-market rate.
+    pub fn include_in(
+        &self,
+        rate: Rate,
+        position: Position,
+        quantity: bitcoin::Amount,
+        mid_market_rate: Rate,
+        max_fee_share: u16,
+    ) -> Result<Rate> {
+        let fees = self.in_dai(position, mid_market_rate).as_atto();
+        let sats = BigUint::from(quantity.as_sat());
+        let value = &sats * mid_market_rate.integer();
+
+        if fees.clone() * 10_000u16 > value * max_fee_share {
+            anyhow::bail!(FeesTooHigh(position))
+        }
+        if sats.is_zero() {
+            return Ok(rate);
+        }
+
+        let fees_per_sat = fees.div_ceil(&sats);
+        let integer = match position {
+            Position::Sell => rate.integer() + fees_per_sat,
+            Position::Buy if fees_per_sat <= rate.integer() => rate.integer() - fees_per_sat,
+            Position::Buy => anyhow::bail!(FeesTooHigh(position)),
+        };
+        let integer = integer
+            .to_u64()
+            .ok_or_else(|| anyhow::anyhow!("Result is unexpectedly large"))?;
+
+        Ok(Rate::new(integer))
+    }
+}
+
+#[derive(Debug, Copy, Clone, thiserror::Error)]
+#[error("Expected fees are too high to create a profitable {0} order.")]
+pub struct FeesTooHigh(pub Position);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bitcoin::amount::btc, ethereum::dai::dai, rate::rate};
+
+    fn bitcoin_fees(sats_per_vbyte: u64) -> SwapFees {
+        SwapFees::new(
+            bitcoin::Amount::from_sat(sats_per_vbyte),
+            ether::Amount::zero(),
+            rate(1.0),
+        )
+    }
+
+    #[test]
+    fn bitcoin_fees_are_converted_at_the_mid_market_rate() {
+        let fees = bitcoin_fees(10);
+
+        // 250 vbyte * 10 sat/vbyte = 0.000025 BTC
+        assert_eq!(fees.in_dai(Position::Sell, rate(10_000.0)), dai(0.25));
+        // 150 vbyte * 10 sat/vbyte = 0.000015 BTC
+        assert_eq!(fees.in_dai(Position::Buy, rate(10_000.0)), dai(0.15));
+    }
+
+    #[test]
+    fn ethereum_fees_are_converted_at_the_eth_dai_rate() {
+        let fees = SwapFees::new(bitcoin::Amount::ZERO, ether::Amount::from(1u64), rate(2.0));
+
+        let redeem_gas = herc20::Params::redeem_tx_gas_limit();
+
+        assert_eq!(
+            fees.in_dai(Position::Sell, rate(10_000.0)),
+            dai::Amount::from_atto(BigUint::from(redeem_gas * 2))
+        );
+    }
+
+    #[test]
+    fn fees_raise_the_sell_rate_and_lower_the_buy_rate() {
+        let fees = bitcoin_fees(10);
+
+        let sell_rate = fees
+            .include_in(
+                rate(10_100.0),
+                Position::Sell,
+                btc(1.0),
+                rate(10_000.0),
+                100,
+            )
+            .unwrap();
+        let buy_rate = fees
+            .include_in(rate(9_900.0), Position::Buy, btc(1.0), rate(10_000.0), 100)
+            .unwrap();
+
+        assert_eq!(sell_rate, rate(10_100.25));
+        assert_eq!(buy_rate, rate(9_899.85));
+    }
+
+    #[test]
+    fn given_fees_above_max_share_of_order_value_fail() {
+        let fees = bitcoin_fees(10);
+
+        // The order is worth 1 DAI, the fees are 0.25 DAI
+        let result = fees.include_in(
+            rate(10_000.0),
+            Position::Sell,
+            btc(0.0001),
+            rate(10_000.0),
+            100,
+        );
+
+        assert!(result.unwrap_err().downcast::<FeesTooHigh>().is_ok());
+    }
+}
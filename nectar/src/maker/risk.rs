@@ -0,0 +1,362 @@
+use crate::{bitcoin, config, ethereum::dai, swap::SwapKind};
+use comit::BtcErc20Order;
+use std::collections::VecDeque;
+use time::{Duration, OffsetDateTime};
+
+/// Keeps track of the risk we take and enforces the configured limits when
+/// an order is taken. An order that would breach a limit is rejected and not
+/// published in the first place. Once a limit is reached, i.e. none of the
+/// orders we would publish fits within the limits anymore, or should the
+/// tracked exposure ever exceed a limit, e.g. because of the swaps resumed
+/// after a restart, the kill switch is engaged and stays so until nectar is
+/// restarted.
+#[derive(Debug, Default)]
+pub struct RiskLimits {
+    limits: config::Risk,
+    locked_notional: bitcoin::Amount,
+    volume: VecDeque<Trade>,
+    kill_switch_engaged: bool,
+}
+
+/// The quantities of a swap we started, counted towards the daily volume.
+#[derive(Debug, Clone)]
+struct Trade {
+    started_at: OffsetDateTime,
+    btc: bitcoin::Amount,
+    dai: dai::Amount,
+}
+
+impl RiskLimits {
+    pub fn new(limits: config::Risk) -> Self {
+        Self {
+            limits,
+            ..Default::default()
+        }
+    }
+
+    pub fn max_swaps_per_peer(&self) -> usize {
+        self.limits.max_swaps_per_peer
+    }
+
+    pub fn is_kill_switch_engaged(&self) -> bool {
+        self.kill_switch_engaged
+    }
+
+    pub fn engage_kill_switch(&mut self) {
+        self.kill_switch_engaged = true;
+    }
+
+    /// Check that the tracked exposure is within the limits.
+    pub fn check_exposure(&self, now: OffsetDateTime) -> Result<(), LimitBreached> {
+        if let Some(max) = self.limits.max_notional {
+            let notional = self.locked_notional;
+            if notional > max {
+                return Err(LimitBreached::Notional { notional, max });
+            }
+        }
+
+        if let Some(max) = self.limits.max_daily_btc_volume {
+            let volume = self.btc_volume(now);
+            if volume > max {
+                return Err(LimitBreached::DailyBtcVolume { volume, max });
+            }
+        }
+
+        if let Some(max) = self.limits.max_daily_dai_volume.clone() {
+            let volume = self.dai_volume(now);
+            if volume > max {
+                return Err(LimitBreached::DailyDaiVolume { volume, max });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that swapping the order keeps us within the limits.
+    pub fn check(&self, order: &BtcErc20Order, now: OffsetDateTime) -> Result<(), LimitBreached> {
+        let btc = order.quantity.to_inner();
+        let dai = dai::Amount::from(order.quote());
+
+        if let Some(max) = self.limits.max_notional {
+            let notional = self.locked_notional + btc;
+            if notional > max {
+                return Err(LimitBreached::Notional { notional, max });
+            }
+        }
+
+        if let Some(max) = self.limits.max_daily_btc_volume {
+            let volume = self.btc_volume(now) + btc;
+            if volume > max {
+                return Err(LimitBreached::DailyBtcVolume { volume, max });
+            }
+        }
+
+        if let Some(max) = self.limits.max_daily_dai_volume.clone() {
+            let volume = self.dai_volume(now) + dai;
+            if volume > max {
+                return Err(LimitBreached::DailyDaiVolume { volume, max });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn swap_started(&mut self, order: &BtcErc20Order, now: OffsetDateTime) {
+        let btc = order.quantity.to_inner();
+
+        self.prune_volume(now);
+        self.locked_notional += btc;
+        self.volume.push_back(Trade {
+            started_at: now,
+            btc,
+            dai: order.quote().into(),
+        });
+    }
+
+    /// Release what was accounted for a swap that failed to be set up.
+    pub fn swap_aborted(&mut self, order: &BtcErc20Order) {
+        let btc = order.quantity.to_inner();
+        let dai = dai::Amount::from(order.quote());
+
+        self.locked_notional = self
+            .locked_notional
+            .checked_sub(btc)
+            .unwrap_or(bitcoin::Amount::ZERO);
+        if let Some(index) = self
+            .volume
+            .iter()
+            .rposition(|trade| trade.btc == btc && trade.dai == dai)
+        {
+            self.volume.remove(index);
+        }
+    }
+
+    /// Account for a swap started before a restart. Only swaps that are still
+    /// ongoing count towards the daily volume.
+    pub fn swap_resumed(&mut self, swap: &SwapKind, now: OffsetDateTime) {
+        let btc = swap.hbit_params().shared.asset;
+        let start_of_swap = swap.start_of_swap();
+
+        self.locked_notional += btc;
+        if start_of_swap > now - Duration::days(1) {
+            // Ether swaps are not quoted in DAI, they only count towards the
+            // bitcoin volume.
+            let dai = match swap {
+                SwapKind::HbitHerc20(params) | SwapKind::Herc20Hbit(params) => {
+                    params.herc20_params.asset.clone().into()
+                }
+                SwapKind::HbitHeth(_) | SwapKind::HethHbit(_) => dai::Amount::zero(),
+            };
+            let index = self
+                .volume
+                .iter()
+                .position(|trade| trade.started_at > start_of_swap)
+                .unwrap_or_else(|| self.volume.len());
+            self.volume.insert(index, Trade {
+                started_at: start_of_swap,
+                btc,
+                dai,
+            });
+        }
+    }
+
+    /// The quantity of a finished swap is not locked anymore, it still counts
+    /// towards the daily volume.
+    pub fn swap_finished(&mut self, swap: &SwapKind) {
+        let btc = swap.hbit_params().shared.asset;
+
+        self.locked_notional = self
+            .locked_notional
+            .checked_sub(btc)
+            .unwrap_or(bitcoin::Amount::ZERO);
+    }
+
+    /// Forget the trades that do not count towards the daily volume anymore.
+    fn prune_volume(&mut self, now: OffsetDateTime) {
+        while let Some(trade) = self.volume.front() {
+            if trade.started_at > now - Duration::days(1) {
+                break;
+            }
+            self.volume.pop_front();
+        }
+    }
+
+    /// The trades of the last 24 hours.
+    fn daily_trades(&self, now: OffsetDateTime) -> impl Iterator<Item = &Trade> {
+        let since = now - Duration::days(1);
+
+        self.volume
+            .iter()
+            .filter(move |trade| trade.started_at > since)
+    }
+
+    fn btc_volume(&self, now: OffsetDateTime) -> bitcoin::Amount {
+        self.daily_trades(now)
+            .fold(bitcoin::Amount::ZERO, |volume, trade| volume + trade.btc)
+    }
+
+    fn dai_volume(&self, now: OffsetDateTime) -> dai::Amount {
+        self.daily_trades(now)
+            .fold(dai::Amount::zero(), |volume, trade| {
+                volume + trade.dai.clone()
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum LimitBreached {
+    #[error("Ongoing swaps would lock {notional}, the limit is {max}.")]
+    Notional {
+        notional: bitcoin::Amount,
+        max: bitcoin::Amount,
+    },
+    #[error("Bitcoin volume of the last 24 hours would be {volume}, the limit is {max}.")]
+    DailyBtcVolume {
+        volume: bitcoin::Amount,
+        max: bitcoin::Amount,
+    },
+    #[error("Dai volume of the last 24 hours would be {volume}, the limit is {max}.")]
+    DailyDaiVolume {
+        volume: dai::Amount,
+        max: dai::Amount,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bitcoin::amount::btc, ethereum::dai::dai, order::btc_dai_order, rate::rate};
+    use comit::Position;
+
+    #[test]
+    fn given_no_limits_any_order_is_accepted() {
+        let risk = RiskLimits::default();
+        let order = btc_dai_order(Position::Sell, btc(1_000.0), rate(10_000.0));
+
+        assert_eq!(risk.check(&order, OffsetDateTime::now_utc()), Ok(()));
+    }
+
+    #[test]
+    fn notional_of_ongoing_swaps_is_capped() {
+        let mut risk = RiskLimits::new(config::Risk {
+            max_notional: Some(btc(1.5)),
+            ..Default::default()
+        });
+        let now = OffsetDateTime::now_utc();
+        let order = btc_dai_order(Position::Sell, btc(1.0), rate(10_000.0));
+
+        assert_eq!(risk.check(&order, now), Ok(()));
+        risk.swap_started(&order, now);
+
+        assert_eq!(
+            risk.check(&order, now),
+            Err(LimitBreached::Notional {
+                notional: btc(2.0),
+                max: btc(1.5)
+            })
+        );
+    }
+
+    #[test]
+    fn finished_swaps_do_not_count_towards_the_notional() {
+        let mut risk = RiskLimits::new(config::Risk {
+            max_notional: Some(btc(1.5)),
+            ..Default::default()
+        });
+        let now = OffsetDateTime::now_utc();
+        let order = btc_dai_order(Position::Sell, btc(1.0), rate(10_000.0));
+        let swap = SwapKind::Herc20Hbit(crate::StaticStub::static_stub());
+
+        risk.swap_resumed(&swap, now);
+        risk.swap_finished(&swap);
+
+        assert_eq!(risk.check(&order, now), Ok(()));
+    }
+
+    #[test]
+    fn aborted_swaps_are_not_accounted_for() {
+        let mut risk = RiskLimits::new(config::Risk {
+            max_notional: Some(btc(1.5)),
+            max_daily_btc_volume: Some(btc(1.5)),
+            ..Default::default()
+        });
+        let now = OffsetDateTime::now_utc();
+        let order = btc_dai_order(Position::Sell, btc(1.0), rate(10_000.0));
+
+        risk.swap_started(&order, now);
+        risk.swap_aborted(&order);
+
+        assert_eq!(risk.check(&order, now), Ok(()));
+    }
+
+    #[test]
+    fn exposure_within_the_limits_does_not_breach() {
+        let mut risk = RiskLimits::new(config::Risk {
+            max_notional: Some(btc(1.5)),
+            ..Default::default()
+        });
+        let now = OffsetDateTime::now_utc();
+        let order = btc_dai_order(Position::Sell, btc(1.0), rate(10_000.0));
+
+        risk.swap_started(&order, now);
+
+        assert!(risk.check(&order, now).is_err());
+        assert_eq!(risk.check_exposure(now), Ok(()));
+    }
+
+    #[test]
+    fn resumed_swaps_can_exceed_the_limits() {
+        let mut risk = RiskLimits::new(config::Risk {
+            max_notional: Some(bitcoin::Amount::ZERO),
+            ..Default::default()
+        });
+        let now = OffsetDateTime::now_utc();
+        let swap = SwapKind::Herc20Hbit(crate::StaticStub::static_stub());
+
+        risk.swap_resumed(&swap, now);
+
+        assert!(risk.check_exposure(now).is_err());
+    }
+
+    #[test]
+    fn daily_volume_is_capped_over_a_rolling_window() {
+        let mut risk = RiskLimits::new(config::Risk {
+            max_daily_btc_volume: Some(btc(1.5)),
+            ..Default::default()
+        });
+        let now = OffsetDateTime::now_utc();
+        let yesterday = now - Duration::hours(23);
+        let order = btc_dai_order(Position::Buy, btc(1.0), rate(10_000.0));
+
+        risk.swap_started(&order, yesterday);
+
+        assert_eq!(
+            risk.check(&order, now),
+            Err(LimitBreached::DailyBtcVolume {
+                volume: btc(2.0),
+                max: btc(1.5)
+            })
+        );
+        assert_eq!(risk.check(&order, now + Duration::hours(2)), Ok(()));
+    }
+
+    #[test]
+    fn daily_dai_volume_is_capped() {
+        let mut risk = RiskLimits::new(config::Risk {
+            max_daily_dai_volume: Some(dai(15_000.0)),
+            ..Default::default()
+        });
+        let now = OffsetDateTime::now_utc();
+        let order = btc_dai_order(Position::Buy, btc(1.0), rate(10_000.0));
+
+        risk.swap_started(&order, now);
+
+        assert_eq!(
+            risk.check(&order, now),
+            Err(LimitBreached::DailyDaiVolume {
+                volume: dai(20_000.0),
+                max: dai(15_000.0)
+            })
+        );
+    }
+}
@@ -1,19 +1,279 @@
+mod ladder;
+
 use crate::{
     bitcoin,
     bitcoin::Fee,
     config,
     ethereum::dai,
-    maker::TakeRequestDecision,
+    maker::{
+        fees::{FeesTooHigh, SwapFees},
+        TakeRequestDecision,
+    },
     order::{BtcDaiOrderForm, Symbol},
     swap::SwapKind,
-    Rate, Result, Spread,
+    Rate, Result, Skew, Spread,
 };
 use anyhow::anyhow;
-use comit::{BtcDaiOrder, Position, Quantity};
+use comit::{BtcErc20Order, Position, Quantity};
 use std::cmp::min;
 
+pub use self::ladder::Ladder;
+
+/// A market-making strategy: decides which orders to publish and whether to
+/// go through with an order that was taken.
+///
+/// The strategy is informed about every swap that starts, fails to be set up,
+/// is resumed or finishes, this allows it to keep track of the funds locked in
+/// swaps.
+pub trait Strategy {
+    /// Create a new sell order given our balances and the mid-market rate.
+    /// Bitcoin is always the base asset, sell order sells bitcoin.
+    fn new_sell(
+        &self,
+        base_balance: bitcoin::Amount,
+        quote_balance: &dai::Amount,
+        mid_market_rate: Rate,
+    ) -> Result<BtcDaiOrderForm>;
+
+    /// Create a new buy order given our balances and the mid-market rate.
+    /// Bitcoin is always the base asset, buy order buys bitcoin.
+    fn new_buy(
+        &self,
+        base_balance: bitcoin::Amount,
+        quote_balance: &dai::Amount,
+        mid_market_rate: Rate,
+    ) -> Result<BtcDaiOrderForm>;
+
+    /// Create all sell orders to publish at once, the first one is the one
+    /// of [`Strategy::new_sell`].
+    fn new_sells(
+        &self,
+        base_balance: bitcoin::Amount,
+        quote_balance: &dai::Amount,
+        mid_market_rate: Rate,
+    ) -> Result<Vec<BtcDaiOrderForm>> {
+        Ok(vec![self.new_sell(
+            base_balance,
+            quote_balance,
+            mid_market_rate,
+        )?])
+    }
+
+    /// Create all buy orders to publish at once, the first one is the one of
+    /// [`Strategy::new_buy`].
+    fn new_buys(
+        &self,
+        base_balance: bitcoin::Amount,
+        quote_balance: &dai::Amount,
+        mid_market_rate: Rate,
+    ) -> Result<Vec<BtcDaiOrderForm>> {
+        Ok(vec![self.new_buy(
+            base_balance,
+            quote_balance,
+            mid_market_rate,
+        )?])
+    }
+
+    /// Decide whether we should proceed with an order that was taken.
+    ///
+    /// If the decision is to go for the swap, the funds needed for it are
+    /// reserved.
+    fn process_taken_order(
+        &mut self,
+        order: BtcErc20Order,
+        current_mid_market_rate: Rate,
+        dai_balance: &dai::Amount,
+        btc_balance: &bitcoin::Amount,
+    ) -> Result<TakeRequestDecision>;
+
+    /// Process a taken order whose swap failed to be set up, it never
+    /// started.
+    fn swap_setup_failed(&mut self, order: &BtcErc20Order);
+
+    /// Process a finished swap.
+    fn swap_finished(&mut self, swap: SwapKind);
+
+    /// Inform the strategy about the current fees of the transactions of a
+    /// swap, they are included in the price of the orders from now on.
+    fn fees_updated(&mut self, fees: SwapFees);
+
+    /// Inform the strategy that a hbit_herc20 swap execution was resumed
+    fn hbit_herc20_swap_resumed(&mut self, fund_amount: dai::Amount);
+
+    /// Inform the strategy that a herc20_hbit swap execution was resumed
+    fn herc20_hbit_swap_resumed(&mut self, fund_amount: bitcoin::Amount) -> Result<()>;
+}
+
+/// The strategy selected in the `[maker]` section of the config.
+#[derive(Debug)]
+pub enum AnyStrategy {
+    AllIn(AllIn),
+    Ladder(Ladder),
+}
+
+impl AnyStrategy {
+    pub fn new(
+        strategy: config::Strategy,
+        config: config::Bitcoin,
+        btc_dai: config::BtcDai,
+        spread: Spread,
+        skew: Option<config::Skew>,
+        max_fee_share: u16,
+        bitcoind_client: bitcoin::Client,
+    ) -> anyhow::Result<Self> {
+        let skew = skew
+            .map(|skew| Skew::new(skew.target_btc_share, skew.max_skew))
+            .transpose()?;
+        let all_in = AllIn::new(
+            config,
+            btc_dai.max_buy_quantity,
+            btc_dai.max_sell_quantity,
+            spread,
+            bitcoind_client,
+        )
+        .with_skew(skew)
+        .with_max_fee_share(max_fee_share);
+
+        let strategy = match strategy {
+            config::Strategy::AllIn => AnyStrategy::AllIn(all_in),
+            config::Strategy::Ladder {
+                order_size,
+                levels,
+                spread_step,
+            } => AnyStrategy::Ladder(Ladder::new(all_in, order_size, levels, spread_step)?),
+        };
+
+        Ok(strategy)
+    }
+}
+
+impl Strategy for AnyStrategy {
+    fn new_sell(
+        &self,
+        base_balance: bitcoin::Amount,
+        quote_balance: &dai::Amount,
+        mid_market_rate: Rate,
+    ) -> Result<BtcDaiOrderForm> {
+        match self {
+            AnyStrategy::AllIn(strategy) => {
+                strategy.new_sell(base_balance, quote_balance, mid_market_rate)
+            }
+            AnyStrategy::Ladder(strategy) => {
+                strategy.new_sell(base_balance, quote_balance, mid_market_rate)
+            }
+        }
+    }
+
+    fn new_buy(
+        &self,
+        base_balance: bitcoin::Amount,
+        quote_balance: &dai::Amount,
+        mid_market_rate: Rate,
+    ) -> Result<BtcDaiOrderForm> {
+        match self {
+            AnyStrategy::AllIn(strategy) => {
+                strategy.new_buy(base_balance, quote_balance, mid_market_rate)
+            }
+            AnyStrategy::Ladder(strategy) => {
+                strategy.new_buy(base_balance, quote_balance, mid_market_rate)
+            }
+        }
+    }
+
+    fn new_sells(
+        &self,
+        base_balance: bitcoin::Amount,
+        quote_balance: &dai::Amount,
+        mid_market_rate: Rate,
+    ) -> Result<Vec<BtcDaiOrderForm>> {
+        match self {
+            AnyStrategy::AllIn(strategy) => {
+                strategy.new_sells(base_balance, quote_balance, mid_market_rate)
+            }
+            AnyStrategy::Ladder(strategy) => {
+                strategy.new_sells(base_balance, quote_balance, mid_market_rate)
+            }
+        }
+    }
+
+    fn new_buys(
+        &self,
+        base_balance: bitcoin::Amount,
+        quote_balance: &dai::Amount,
+        mid_market_rate: Rate,
+    ) -> Result<Vec<BtcDaiOrderForm>> {
+        match self {
+            AnyStrategy::AllIn(strategy) => {
+                strategy.new_buys(base_balance, quote_balance, mid_market_rate)
+            }
+            AnyStrategy::Ladder(strategy) => {
+                strategy.new_buys(base_balance, quote_balance, mid_market_rate)
+            }
+        }
+    }
+
+    fn process_taken_order(
+        &mut self,
+        order: BtcErc20Order,
+        current_mid_market_rate: Rate,
+        dai_balance: &dai::Amount,
+        btc_balance: &bitcoin::Amount,
+    ) -> Result<TakeRequestDecision> {
+        match self {
+            AnyStrategy::AllIn(strategy) => strategy.process_taken_order(
+                order,
+                current_mid_market_rate,
+                dai_balance,
+                btc_balance,
+            ),
+            AnyStrategy::Ladder(strategy) => strategy.process_taken_order(
+                order,
+                current_mid_market_rate,
+                dai_balance,
+                btc_balance,
+            ),
+        }
+    }
+
+    fn swap_setup_failed(&mut self, order: &BtcErc20Order) {
+        match self {
+            AnyStrategy::AllIn(strategy) => strategy.swap_setup_failed(order),
+            AnyStrategy::Ladder(strategy) => strategy.swap_setup_failed(order),
+        }
+    }
+
+    fn swap_finished(&mut self, swap: SwapKind) {
+        match self {
+            AnyStrategy::AllIn(strategy) => strategy.swap_finished(swap),
+            AnyStrategy::Ladder(strategy) => strategy.swap_finished(swap),
+        }
+    }
+
+    fn fees_updated(&mut self, fees: SwapFees) {
+        match self {
+            AnyStrategy::AllIn(strategy) => strategy.fees_updated(fees),
+            AnyStrategy::Ladder(strategy) => strategy.fees_updated(fees),
+        }
+    }
+
+    fn hbit_herc20_swap_resumed(&mut self, fund_amount: dai::Amount) {
+        match self {
+            AnyStrategy::AllIn(strategy) => strategy.hbit_herc20_swap_resumed(fund_amount),
+            AnyStrategy::Ladder(strategy) => strategy.hbit_herc20_swap_resumed(fund_amount),
+        }
+    }
+
+    fn herc20_hbit_swap_resumed(&mut self, fund_amount: bitcoin::Amount) -> Result<()> {
+        match self {
+            AnyStrategy::AllIn(strategy) => strategy.herc20_hbit_swap_resumed(fund_amount),
+            AnyStrategy::Ladder(strategy) => strategy.herc20_hbit_swap_resumed(fund_amount),
+        }
+    }
+}
+
 /// Create orders with the full balance, capped by a configuration setting.
-/// A spread is applied on the passed mid-market rate
+/// A spread is applied on the passed mid-market rate and the expected fees of
+/// the swap are included in the price
 #[derive(Debug)]
 pub struct AllIn {
     bitcoin_fee: Fee,
@@ -22,6 +282,9 @@ pub struct AllIn {
     max_buy_quantity: Option<bitcoin::Amount>,
     max_sell_quantity: Option<bitcoin::Amount>,
     spread: Spread,
+    skew: Option<Skew>,
+    fees: Option<SwapFees>,
+    max_fee_share: u16,
 }
 
 impl AllIn {
@@ -40,42 +303,71 @@ impl AllIn {
             max_buy_quantity,
             max_sell_quantity,
             spread,
+            skew: None,
+            fees: None,
+            max_fee_share: 10_000,
         }
     }
-}
 
-// Methods that are likely to be in the `Strategy` trait
-impl AllIn {
-    /// Inform the strategy that a hbit_herc20 swap execution was resumed
-    pub fn hbit_herc20_swap_resumed(&mut self, fund_amount: dai::Amount) {
-        self.dai_reserved_funds += fund_amount;
+    /// Skew the spread of all orders depending on our inventory.
+    pub fn with_skew(self, skew: Option<Skew>) -> Self {
+        Self { skew, ..self }
     }
 
-    /// Inform the strategy that a herc20_hbit swap execution was resumed
-    pub fn herc20_hbit_swap_resumed(&mut self, fund_amount: bitcoin::Amount) -> Result<()> {
-        let amount_to_reserve = fund_amount
-            .checked_add(self.bitcoin_fee.max_tx_fee())
-            .ok_or_else(|| anyhow!(Overflow))?;
+    /// Do not create or take orders whose expected fees exceed the given
+    /// share (permyriad) of their value.
+    pub fn with_max_fee_share(self, max_fee_share: u16) -> Self {
+        Self {
+            max_fee_share,
+            ..self
+        }
+    }
 
-        self.btc_reserved_funds = self
-            .btc_reserved_funds
-            .checked_add(amount_to_reserve)
-            .ok_or_else(|| anyhow!(Overflow))?;
+    fn skewed(
+        &self,
+        spread: Spread,
+        position: Position,
+        btc_balance: bitcoin::Amount,
+        dai_balance: &dai::Amount,
+        mid_market_rate: Rate,
+    ) -> Spread {
+        match self.skew {
+            Some(skew) => skew.apply(spread, position, btc_balance, dai_balance, mid_market_rate),
+            None => spread,
+        }
+    }
 
-        Ok(())
+    /// Include the expected fees of swapping `quantity` in the rate, as soon
+    /// as we know them.
+    fn include_fees(
+        &self,
+        rate: Rate,
+        position: Position,
+        quantity: bitcoin::Amount,
+        mid_market_rate: Rate,
+    ) -> Result<Rate> {
+        match &self.fees {
+            Some(fees) => fees.include_in(
+                rate,
+                position,
+                quantity,
+                mid_market_rate,
+                self.max_fee_share,
+            ),
+            None => Ok(rate),
+        }
     }
 
-    /// Create a new sell order given the passed parameters.
-    /// Bitcoin is always the base asset, sell order sells bitcoin.
-    ///
-    /// The quantity is the full available balance minus the expected mining fee
-    /// or the max btc sell parameters, whichever is the lowest.
-    /// The spread parameter is applied on the mid market rate to decide
-    /// the price.
-    pub fn new_sell(
+    /// The quantity is the full available balance minus the expected mining
+    /// fee or `max_quantity`, whichever is the lowest.
+    /// The spread is applied on the mid market rate and the expected fees are
+    /// added to decide the price.
+    fn sell(
         &self,
         base_balance: bitcoin::Amount,
         mid_market_rate: Rate,
+        spread: Spread,
+        max_quantity: Option<bitcoin::Amount>,
     ) -> Result<BtcDaiOrderForm> {
         match self
             .btc_reserved_funds
@@ -95,12 +387,13 @@ impl AllIn {
             None => anyhow::bail!(Overflow),
         }
 
-        let base_amount = match self.max_sell_quantity {
+        let base_amount = match max_quantity {
             Some(max_quantity) => min(base_balance - self.btc_reserved_funds, max_quantity),
             None => base_balance - self.btc_reserved_funds,
         };
 
-        let rate = self.spread.apply(mid_market_rate, Position::Sell)?;
+        let rate = spread.apply(mid_market_rate, Position::Sell)?;
+        let rate = self.include_fees(rate, Position::Sell, base_amount, mid_market_rate)?;
 
         Ok(BtcDaiOrderForm {
             position: Position::Sell,
@@ -109,19 +402,18 @@ impl AllIn {
         })
     }
 
-    /// Create a new buy order given the passed parameters.
-    /// Bitcoin is always the base asset, buy order buys bitcoin.
-    ///
     /// The quantity is the full available dai balance in btc given the current
-    /// rate or the maximum buy quantity parameter, whichever is the
-    /// lowest. The spread parameter is applied on the mid market rate to
-    /// decide the price.
-    pub fn new_buy(
+    /// rate or `max_quantity`, whichever is the lowest.
+    /// The spread is applied on the mid market rate and the expected fees are
+    /// subtracted to decide the price.
+    fn buy(
         &self,
-        quote_balance: dai::Amount,
+        quote_balance: &dai::Amount,
         mid_market_rate: Rate,
+        spread: Spread,
+        max_quantity: Option<bitcoin::Amount>,
     ) -> Result<BtcDaiOrderForm> {
-        if quote_balance <= self.dai_reserved_funds {
+        if *quote_balance <= self.dai_reserved_funds {
             sentry::capture_message(
                 "DAI balance too low to create order",
                 sentry::Level::Warning,
@@ -129,14 +421,15 @@ impl AllIn {
             anyhow::bail!(InsufficientFunds(Symbol::Dai))
         }
 
-        let rate = self.spread.apply(mid_market_rate, Position::Buy)?;
-        let max_quote = quote_balance - self.dai_reserved_funds.clone();
+        let rate = spread.apply(mid_market_rate, Position::Buy)?;
+        let max_quote = quote_balance.clone() - self.dai_reserved_funds.clone();
         let max_quote_worth_in_base = max_quote.worth_in(rate)?;
 
-        let base_amount = match self.max_buy_quantity {
+        let base_amount = match max_quantity {
             Some(max_quantity) => min(max_quote_worth_in_base, max_quantity),
             None => max_quote_worth_in_base,
         };
+        let rate = self.include_fees(rate, Position::Buy, base_amount, mid_market_rate)?;
 
         Ok(BtcDaiOrderForm {
             position: Position::Buy,
@@ -145,21 +438,31 @@ impl AllIn {
         })
     }
 
-    /// Decide whether we should proceed with an order,
     /// Checks:
+    /// - Order is considered profitable given the spread and the fees
     /// - funds are available
-    /// - Order is considered profitable
     /// - Reserve the funds (assumes we proceed with the order)
     /// // TODO: extract the reserve part and expect consumer to call
     /// `hbit_herc20_swap_resumed` when a swap starts.
-    pub fn process_taken_order(
+    fn take(
         &mut self,
-        order: BtcDaiOrder,
+        order: BtcErc20Order,
         current_mid_market_rate: Rate,
+        spread: Spread,
         dai_balance: &dai::Amount,
         btc_balance: &bitcoin::Amount,
-    ) -> anyhow::Result<TakeRequestDecision> {
-        let current_profitable_rate = self.spread.apply(current_mid_market_rate, order.position)?;
+    ) -> Result<TakeRequestDecision> {
+        let current_profitable_rate = spread.apply(current_mid_market_rate, order.position)?;
+        let current_profitable_rate = match self.include_fees(
+            current_profitable_rate,
+            order.position,
+            order.quantity.to_inner(),
+            current_mid_market_rate,
+        ) {
+            Ok(rate) => rate,
+            Err(e) if e.is::<FeesTooHigh>() => return Ok(TakeRequestDecision::RateNotProfitable),
+            Err(e) => return Err(e),
+        };
 
         if !is_as_profitable_as(&order, current_profitable_rate) {
             return Ok(TakeRequestDecision::RateNotProfitable);
@@ -191,9 +494,98 @@ impl AllIn {
 
         Ok(TakeRequestDecision::GoForSwap)
     }
+}
 
-    /// Process a finished swap.
-    pub fn swap_finished(&mut self, swap: SwapKind) {
+impl Strategy for AllIn {
+    /// The quantity is the full available balance minus the expected mining fee
+    /// or the max btc sell parameters, whichever is the lowest.
+    /// The spread parameter is applied on the mid market rate to decide
+    /// the price.
+    fn new_sell(
+        &self,
+        base_balance: bitcoin::Amount,
+        quote_balance: &dai::Amount,
+        mid_market_rate: Rate,
+    ) -> Result<BtcDaiOrderForm> {
+        let spread = self.skewed(
+            self.spread,
+            Position::Sell,
+            base_balance,
+            quote_balance,
+            mid_market_rate,
+        );
+
+        self.sell(
+            base_balance,
+            mid_market_rate,
+            spread,
+            self.max_sell_quantity,
+        )
+    }
+
+    /// The quantity is the full available dai balance in btc given the current
+    /// rate or the maximum buy quantity parameter, whichever is the
+    /// lowest. The spread parameter is applied on the mid market rate to
+    /// decide the price.
+    fn new_buy(
+        &self,
+        base_balance: bitcoin::Amount,
+        quote_balance: &dai::Amount,
+        mid_market_rate: Rate,
+    ) -> Result<BtcDaiOrderForm> {
+        let spread = self.skewed(
+            self.spread,
+            Position::Buy,
+            base_balance,
+            quote_balance,
+            mid_market_rate,
+        );
+
+        self.buy(
+            quote_balance,
+            mid_market_rate,
+            spread,
+            self.max_buy_quantity,
+        )
+    }
+
+    fn process_taken_order(
+        &mut self,
+        order: BtcErc20Order,
+        current_mid_market_rate: Rate,
+        dai_balance: &dai::Amount,
+        btc_balance: &bitcoin::Amount,
+    ) -> Result<TakeRequestDecision> {
+        let spread = self.skewed(
+            self.spread,
+            order.position,
+            *btc_balance,
+            dai_balance,
+            current_mid_market_rate,
+        );
+        self.take(
+            order,
+            current_mid_market_rate,
+            spread,
+            dai_balance,
+            btc_balance,
+        )
+    }
+
+    /// Release the funds reserved when the order was taken.
+    fn swap_setup_failed(&mut self, order: &BtcErc20Order) {
+        match order.position {
+            Position::Buy => {
+                self.dai_reserved_funds -= dai::Amount::from(order.quote());
+            }
+            Position::Sell => {
+                self.btc_reserved_funds -=
+                    order.quantity.to_inner() + self.bitcoin_fee.max_tx_fee();
+            }
+        }
+    }
+
+    fn swap_finished(&mut self, swap: SwapKind) {
         match swap {
             SwapKind::Herc20Hbit(swap) => {
                 self.btc_reserved_funds -=
@@ -202,11 +594,38 @@ impl AllIn {
             SwapKind::HbitHerc20(swap) => {
                 self.dai_reserved_funds -= swap.herc20_params.asset.into();
             }
+            SwapKind::HethHbit(swap) => {
+                self.btc_reserved_funds -=
+                    swap.hbit_params.shared.asset + self.bitcoin_fee.max_tx_fee();
+            }
+            // No ether is reserved for a swap in which we fund ether.
+            SwapKind::HbitHeth(_) => {}
         }
     }
+
+    fn fees_updated(&mut self, fees: SwapFees) {
+        self.fees = Some(fees);
+    }
+
+    fn hbit_herc20_swap_resumed(&mut self, fund_amount: dai::Amount) {
+        self.dai_reserved_funds += fund_amount;
+    }
+
+    fn herc20_hbit_swap_resumed(&mut self, fund_amount: bitcoin::Amount) -> Result<()> {
+        let amount_to_reserve = fund_amount
+            .checked_add(self.bitcoin_fee.max_tx_fee())
+            .ok_or_else(|| anyhow!(Overflow))?;
+
+        self.btc_reserved_funds = self
+            .btc_reserved_funds
+            .checked_add(amount_to_reserve)
+            .ok_or_else(|| anyhow!(Overflow))?;
+
+        Ok(())
+    }
 }
 
-fn is_as_profitable_as(order: &BtcDaiOrder, profitable_rate: Rate) -> bool {
+fn is_as_profitable_as(order: &BtcErc20Order, profitable_rate: Rate) -> bool {
     match order.position {
         Position::Buy => {
             // We are buying BTC for DAI
@@ -247,8 +666,13 @@ pub struct BalanceNotAvailable(Symbol);
 mod test {
     use super::*;
     use crate::{
-        bitcoin::amount::btc, config, config::BitcoinFees, ethereum::dai::dai,
-        order::btc_dai_order, rate::rate, MidMarketRate, StaticStub,
+        bitcoin::amount::btc,
+        config,
+        config::BitcoinFees,
+        ethereum::{dai::dai, ether},
+        order::btc_dai_order,
+        rate::rate,
+        MidMarketRate, StaticStub,
     };
     use num::BigUint;
     use proptest::prelude::*;
@@ -278,7 +702,7 @@ mod test {
 
         let strategy = AllIn::new(config, None, None, spread, StaticStub::static_stub());
 
-        let result = strategy.new_sell(btc(0.07), rate);
+        let result = strategy.new_sell(btc(0.07), &dai(0.0), rate);
         assert!(result.unwrap_err().downcast::<InsufficientFunds>().is_ok());
     }
 
@@ -292,10 +716,10 @@ mod test {
         strategy.herc20_hbit_swap_resumed(btc(1.0)).unwrap();
         strategy.hbit_herc20_swap_resumed(dai(1.0));
 
-        let result = strategy.new_sell(btc(1.0), rate);
+        let result = strategy.new_sell(btc(1.0), &dai(0.0), rate);
         assert!(result.unwrap_err().downcast::<InsufficientFunds>().is_ok());
 
-        let result = strategy.new_buy(dai(1.0), rate);
+        let result = strategy.new_buy(btc(0.0), &dai(1.0), rate);
         assert!(result.unwrap_err().downcast::<InsufficientFunds>().is_ok());
     }
 
@@ -304,15 +728,91 @@ mod test {
         let strategy = AllIn::static_stub();
 
         let rate = Rate::try_from(1.0).unwrap();
-        let order = strategy.new_sell(btc(10.0), rate).unwrap();
+        let order = strategy.new_sell(btc(10.0), &dai(0.0), rate).unwrap();
 
         assert_eq!(order.quantity.to_inner(), btc(10.0));
 
-        let order = strategy.new_buy(dai(10.0), rate).unwrap();
+        let order = strategy.new_buy(btc(0.0), &dai(10.0), rate).unwrap();
 
         assert_eq!(dai::Amount::from(order.quote()), dai(10.0));
     }
 
+    #[test]
+    fn given_a_skew_and_only_bitcoin_sell_with_narrowed_spread() {
+        let mut strategy = AllIn::new(
+            StaticStub::static_stub(),
+            None,
+            None,
+            Spread::new(200).unwrap(),
+            StaticStub::static_stub(),
+        )
+        .with_skew(Some(Skew::new(5000, 100).unwrap()));
+
+        let order = strategy.new_sell(btc(10.0), &dai(0.0), rate(1.0)).unwrap();
+
+        assert_eq!(dai::Amount::from(order.quote()), dai(10.1));
+
+        let decision = strategy
+            .process_taken_order(
+                btc_dai_order(Position::Sell, btc(1.0), rate(1.01)),
+                rate(1.0),
+                &dai(0.0),
+                &btc(10.0),
+            )
+            .unwrap();
+
+        assert_eq!(decision, TakeRequestDecision::GoForSwap);
+    }
+
+    #[test]
+    fn given_fees_include_them_in_the_price() {
+        let mut strategy = AllIn::new(
+            StaticStub::static_stub(),
+            Some(btc(1.0)),
+            Some(btc(1.0)),
+            Spread::new(0).unwrap(),
+            StaticStub::static_stub(),
+        );
+        strategy.fees_updated(SwapFees::new(
+            bitcoin::Amount::from_sat(10),
+            ether::Amount::zero(),
+            rate(1.0),
+        ));
+
+        let sell = strategy
+            .new_sell(btc(10.0), &dai(0.0), rate(10_000.0))
+            .unwrap();
+        let buy = strategy
+            .new_buy(btc(0.0), &dai(100_000.0), rate(10_000.0))
+            .unwrap();
+
+        assert_eq!(dai::Amount::from(sell.quote()), dai(10_000.25));
+        assert_eq!(dai::Amount::from(buy.quote()), dai(9_999.85));
+    }
+
+    #[test]
+    fn given_fees_above_max_fee_share_no_order_and_not_profitable() {
+        let mut strategy = AllIn::static_stub().with_max_fee_share(100);
+        strategy.fees_updated(SwapFees::new(
+            bitcoin::Amount::from_sat(10),
+            ether::Amount::zero(),
+            rate(1.0),
+        ));
+
+        let result = strategy.new_sell(btc(0.001), &dai(0.0), rate(10_000.0));
+        let decision = strategy
+            .process_taken_order(
+                btc_dai_order(Position::Sell, btc(0.001), rate(20_000.0)),
+                rate(10_000.0),
+                &dai(0.0),
+                &btc(1.0),
+            )
+            .unwrap();
+
+        assert!(result.unwrap_err().downcast::<FeesTooHigh>().is_ok());
+        assert_eq!(decision, TakeRequestDecision::RateNotProfitable);
+    }
+
     #[test]
     fn given_a_balance_and_locked_funds_return_order_selling_available_balance() {
         let rate = Rate::try_from(1.0).unwrap();
@@ -322,12 +822,12 @@ mod test {
         strategy.herc20_hbit_swap_resumed(btc(2.0)).unwrap();
         strategy.hbit_herc20_swap_resumed(dai(2.0));
 
-        let order = strategy.new_sell(btc(10.0), rate).unwrap();
+        let order = strategy.new_sell(btc(10.0), &dai(0.0), rate).unwrap();
 
         // 35 sat * 1000 fees.
         assert_eq!(order.quantity.to_inner(), btc(8.0));
 
-        let order = strategy.new_buy(dai(10.0), rate).unwrap();
+        let order = strategy.new_buy(btc(0.0), &dai(10.0), rate).unwrap();
 
         assert_eq!(dai::Amount::from(order.quote()), dai(8.0));
     }
@@ -343,11 +843,11 @@ mod test {
             StaticStub::static_stub(),
         );
 
-        let order = strategy.new_sell(btc(10.0), rate).unwrap();
+        let order = strategy.new_sell(btc(10.0), &dai(0.0), rate).unwrap();
 
         assert_eq!(order.quantity.to_inner(), btc(2.0));
 
-        let order = strategy.new_buy(dai(10.0), rate).unwrap();
+        let order = strategy.new_buy(btc(0.0), &dai(10.0), rate).unwrap();
 
         assert_eq!(dai::Amount::from(order.quote()), dai(2.0));
     }
@@ -363,7 +863,7 @@ mod test {
             StaticStub::static_stub(),
         );
 
-        let order = strategy.new_sell(btc(10.0), rate).unwrap();
+        let order = strategy.new_sell(btc(10.0), &dai(0.0), rate).unwrap();
 
         assert_eq!(order.quantity.to_inner(), btc(10.0));
     }
@@ -384,7 +884,7 @@ mod test {
             StaticStub::static_stub(),
         );
 
-        let result = strategy.new_sell(btc(1.0), rate);
+        let result = strategy.new_sell(btc(1.0), &dai(0.0), rate);
 
         assert!(result.unwrap_err().downcast::<InsufficientFunds>().is_ok());
     }
@@ -405,7 +905,7 @@ mod test {
             StaticStub::static_stub(),
         );
 
-        let result = strategy.new_sell(btc(0.07), rate);
+        let result = strategy.new_sell(btc(0.07), &dai(0.0), rate);
 
         assert!(result.unwrap_err().downcast::<InsufficientFunds>().is_ok());
     }
@@ -426,25 +926,25 @@ mod test {
         strategy.hbit_herc20_swap_resumed(dai(50.0));
 
         let rate = Rate::try_from(0.1).unwrap();
-        let order = strategy.new_sell(btc(1050.0), rate).unwrap();
+        let order = strategy.new_sell(btc(1050.0), &dai(0.0), rate).unwrap();
 
         assert_eq!(order.quantity.to_inner(), btc(1000.0));
         assert_eq!(dai::Amount::from(order.quote()), dai(100.0));
 
         let rate = Rate::try_from(10.0).unwrap();
-        let order = strategy.new_sell(btc(1050.0), rate).unwrap();
+        let order = strategy.new_sell(btc(1050.0), &dai(0.0), rate).unwrap();
 
         assert_eq!(order.quantity.to_inner(), btc(1000.0));
         assert_eq!(dai::Amount::from(order.quote()), dai(10_000.0));
 
         let rate = Rate::try_from(0.1).unwrap();
-        let order = strategy.new_buy(dai(1050.0), rate).unwrap();
+        let order = strategy.new_buy(btc(0.0), &dai(1050.0), rate).unwrap();
 
         assert_eq!(order.quantity.to_inner(), btc(10_000.0));
         assert_eq!(dai::Amount::from(order.quote()), dai(1000.0));
 
         let rate = Rate::try_from(10.0).unwrap();
-        let order = strategy.new_buy(dai(1050.0), rate).unwrap();
+        let order = strategy.new_buy(btc(0.0), &dai(1050.0), rate).unwrap();
 
         assert_eq!(order.quantity.to_inner(), btc(100.0));
         assert_eq!(dai::Amount::from(order.quote()), dai(1000.0));
@@ -471,7 +971,7 @@ mod test {
         strategy.herc20_hbit_swap_resumed(btc(0.5)).unwrap();
         strategy.hbit_herc20_swap_resumed(dai(51.0));
 
-        let order = strategy.new_sell(btc(1.5), rate).unwrap();
+        let order = strategy.new_sell(btc(1.5), &dai(0.0), rate).unwrap();
 
         assert_eq!(order.quantity.to_inner(), btc(1.0));
         assert_eq!(dai::Amount::from(order.quote()), dai(10_300.0));
@@ -481,7 +981,7 @@ mod test {
             BigUint::from(97000000000000 as u64)
         );
 
-        let order = strategy.new_buy(dai(10_051.0), rate).unwrap();
+        let order = strategy.new_buy(btc(0.0), &dai(10_051.0), rate).unwrap();
 
         assert_eq!(order.quantity.to_inner(), btc(1.03092783));
         assert_eq!(dai::Amount::from(order.quote()), dai(9999.999951));
@@ -520,7 +1020,7 @@ mod test {
                 let dai_balance = dai::Amount::from_atto(dai_balance);
 
                 let strategy = AllIn::new(StaticStub::static_stub(), None, Some(max_buy_quantity), spread, StaticStub::static_stub(),);
-                let _: anyhow::Result<BtcDaiOrderForm> = strategy.new_buy(dai_balance, rate);
+                let _: anyhow::Result<BtcDaiOrderForm> = strategy.new_buy(btc(0.0), &dai_balance, rate);
             }
         }
     }
@@ -538,7 +1038,7 @@ mod test {
 
                 let dai_balance = dai::Amount::from_atto(dai_balance);
 
-                let _: anyhow::Result<BtcDaiOrderForm> = strategy.new_buy(dai_balance, rate);
+                let _: anyhow::Result<BtcDaiOrderForm> = strategy.new_buy(btc(0.0), &dai_balance, rate);
             }
         }
     }
@@ -555,7 +1055,7 @@ mod test {
             if let (Ok(rate), Ok(spread)) = (rate, spread) {
                 let strategy = AllIn::new(StaticStub::static_stub(), Some(max_sell_quantity), None, spread, StaticStub::static_stub());
 
-                let _: anyhow::Result<BtcDaiOrderForm> = strategy.new_sell(btc_balance, rate);
+                let _: anyhow::Result<BtcDaiOrderForm> = strategy.new_sell(btc_balance, &dai(0.0), rate);
             }
         }
     }
@@ -571,7 +1071,7 @@ mod test {
             if let (Ok(rate), Ok(spread)) = (rate, spread) {
                 let strategy = AllIn::new(StaticStub::static_stub(), None, None, spread, StaticStub::static_stub());
 
-                let _: anyhow::Result<BtcDaiOrderForm> = strategy.new_sell(btc_balance, rate);
+                let _: anyhow::Result<BtcDaiOrderForm> = strategy.new_sell(btc_balance, &dai(0.0), rate);
             }
         }
     }
@@ -641,6 +1141,30 @@ mod test {
         assert_eq!(strategy.dai_reserved_funds, dai(1.5))
     }
 
+    #[test]
+    fn funds_released_upon_failed_swap_setup() {
+        let mut strategy = AllIn::static_stub();
+
+        let sell_order = btc_dai_order(Position::Sell, btc(1.5), rate(0.0));
+        let buy_order = btc_dai_order(Position::Buy, btc(1.0), rate(1.5));
+        strategy
+            .process_taken_order(
+                sell_order.clone(),
+                Rate::static_stub(),
+                &dai(0.0),
+                &btc(3.0),
+            )
+            .unwrap();
+        strategy
+            .process_taken_order(buy_order.clone(), rate(1.5), &dai(10.0), &btc(0.0))
+            .unwrap();
+        strategy.swap_setup_failed(&sell_order);
+        strategy.swap_setup_failed(&buy_order);
+
+        assert_eq!(strategy.btc_reserved_funds, btc(0.0));
+        assert_eq!(strategy.dai_reserved_funds, dai(0.0));
+    }
+
     #[test]
     fn not_enough_btc_funds_to_reserve_for_a_sell_order() {
         let mut strategy = AllIn::new(
@@ -0,0 +1,431 @@
+use crate::{
+    bitcoin,
+    ethereum::dai,
+    maker::{
+        fees::SwapFees,
+        strategy::{AllIn, InsufficientFunds, Strategy},
+        TakeRequestDecision,
+    },
+    order::{BtcDaiOrderForm, Symbol},
+    swap::SwapKind,
+    Rate, Result, Spread,
+};
+use comit::{BtcErc20Order, Position};
+
+/// Create orders of a fixed size.
+///
+/// Every level of the ladder is one order that may be swapped at the same
+/// time as the levels below, the spread of each level is `spread_step` wider
+/// than the one of the level below. The levels that are not in use by an
+/// ongoing swap are published at once. Once all levels of a side are in use,
+/// no further orders of that side are created until a swap finishes.
+#[derive(Debug)]
+pub struct Ladder {
+    all_in: AllIn,
+    order_size: bitcoin::Amount,
+    levels: u16,
+    spread_step: u16,
+    ongoing_sells: u16,
+    ongoing_buys: u16,
+}
+
+impl Ladder {
+    pub fn new(
+        all_in: AllIn,
+        order_size: bitcoin::Amount,
+        levels: u16,
+        spread_step: u16,
+    ) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            order_size > bitcoin::Amount::ZERO,
+            "order size of the ladder must not be zero"
+        );
+        anyhow::ensure!(levels > 0, "ladder must have at least one level");
+
+        Ok(Self {
+            all_in,
+            order_size,
+            levels,
+            spread_step,
+            ongoing_sells: 0,
+            ongoing_buys: 0,
+        })
+    }
+
+    /// The spread of the next order given the number of orders of the same
+    /// side that are still being swapped.
+    fn spread_at(&self, level: u16) -> Spread {
+        self.all_in
+            .spread
+            .widen(self.spread_step.saturating_mul(level))
+    }
+
+    fn next_level(&self, position: Position) -> Result<u16> {
+        let (level, symbol) = match position {
+            Position::Sell => (self.ongoing_sells, Symbol::Btc),
+            Position::Buy => (self.ongoing_buys, Symbol::Dai),
+        };
+
+        if level >= self.levels {
+            anyhow::bail!(LadderExhausted(symbol))
+        }
+
+        Ok(level)
+    }
+
+    fn ensure_full_size(&self, form: BtcDaiOrderForm, symbol: Symbol) -> Result<BtcDaiOrderForm> {
+        if form.quantity.to_inner() < self.order_size {
+            anyhow::bail!(InsufficientFunds(symbol))
+        }
+
+        Ok(form)
+    }
+
+    /// The sell order of the given level, `available` is the part of our
+    /// bitcoin balance that is not needed for the levels below.
+    fn sell_at(
+        &self,
+        level: u16,
+        available: bitcoin::Amount,
+        base_balance: bitcoin::Amount,
+        quote_balance: &dai::Amount,
+        mid_market_rate: Rate,
+    ) -> Result<BtcDaiOrderForm> {
+        let spread = self.all_in.skewed(
+            self.spread_at(level),
+            Position::Sell,
+            base_balance,
+            quote_balance,
+            mid_market_rate,
+        );
+        let form = self
+            .all_in
+            .sell(available, mid_market_rate, spread, Some(self.order_size))?;
+
+        self.ensure_full_size(form, Symbol::Btc)
+    }
+
+    /// The buy order of the given level, `available` is the part of our dai
+    /// balance that is not needed for the levels below.
+    fn buy_at(
+        &self,
+        level: u16,
+        available: &dai::Amount,
+        base_balance: bitcoin::Amount,
+        quote_balance: &dai::Amount,
+        mid_market_rate: Rate,
+    ) -> Result<BtcDaiOrderForm> {
+        let spread = self.all_in.skewed(
+            self.spread_at(level),
+            Position::Buy,
+            base_balance,
+            quote_balance,
+            mid_market_rate,
+        );
+        let form = self
+            .all_in
+            .buy(available, mid_market_rate, spread, Some(self.order_size))?;
+
+        self.ensure_full_size(form, Symbol::Dai)
+    }
+}
+
+impl Strategy for Ladder {
+    fn new_sell(
+        &self,
+        base_balance: bitcoin::Amount,
+        quote_balance: &dai::Amount,
+        mid_market_rate: Rate,
+    ) -> Result<BtcDaiOrderForm> {
+        let level = self.next_level(Position::Sell)?;
+
+        self.sell_at(
+            level,
+            base_balance,
+            base_balance,
+            quote_balance,
+            mid_market_rate,
+        )
+    }
+
+    fn new_buy(
+        &self,
+        base_balance: bitcoin::Amount,
+        quote_balance: &dai::Amount,
+        mid_market_rate: Rate,
+    ) -> Result<BtcDaiOrderForm> {
+        let level = self.next_level(Position::Buy)?;
+
+        self.buy_at(
+            level,
+            quote_balance,
+            base_balance,
+            quote_balance,
+            mid_market_rate,
+        )
+    }
+
+    /// One order for every level that is not in use, as far as our balance
+    /// allows.
+    fn new_sells(
+        &self,
+        base_balance: bitcoin::Amount,
+        quote_balance: &dai::Amount,
+        mid_market_rate: Rate,
+    ) -> Result<Vec<BtcDaiOrderForm>> {
+        let first = self.next_level(Position::Sell)?;
+        let mut available = base_balance;
+        let mut forms = Vec::new();
+
+        for level in first..self.levels {
+            let form = match self.sell_at(
+                level,
+                available,
+                base_balance,
+                quote_balance,
+                mid_market_rate,
+            ) {
+                Ok(form) => form,
+                Err(e) if forms.is_empty() => return Err(e),
+                Err(_) => break,
+            };
+
+            // Taking an order reserves its quantity and the funding fee
+            let reserved = form.quantity.to_inner() + self.all_in.bitcoin_fee.max_tx_fee();
+            available = available
+                .checked_sub(reserved)
+                .unwrap_or(bitcoin::Amount::ZERO);
+            forms.push(form);
+        }
+
+        Ok(forms)
+    }
+
+    /// One order for every level that is not in use, as far as our balance
+    /// allows.
+    fn new_buys(
+        &self,
+        base_balance: bitcoin::Amount,
+        quote_balance: &dai::Amount,
+        mid_market_rate: Rate,
+    ) -> Result<Vec<BtcDaiOrderForm>> {
+        let first = self.next_level(Position::Buy)?;
+        let mut available = quote_balance.clone();
+        let mut forms = Vec::new();
+
+        for level in first..self.levels {
+            let form = match self.buy_at(
+                level,
+                &available,
+                base_balance,
+                quote_balance,
+                mid_market_rate,
+            ) {
+                Ok(form) => form,
+                Err(e) if forms.is_empty() => return Err(e),
+                Err(_) => break,
+            };
+
+            let reserved = dai::Amount::from(form.quote());
+            available = if reserved < available {
+                available - reserved
+            } else {
+                dai::Amount::default()
+            };
+            forms.push(form);
+        }
+
+        Ok(forms)
+    }
+
+    /// Any order at least as good as the first level of the ladder is taken.
+    fn process_taken_order(
+        &mut self,
+        order: BtcErc20Order,
+        current_mid_market_rate: Rate,
+        dai_balance: &dai::Amount,
+        btc_balance: &bitcoin::Amount,
+    ) -> Result<TakeRequestDecision> {
+        let position = order.position;
+        let spread = self.all_in.skewed(
+            self.spread_at(0),
+            position,
+            *btc_balance,
+            dai_balance,
+            current_mid_market_rate,
+        );
+        let decision = self.all_in.take(
+            order,
+            current_mid_market_rate,
+            spread,
+            dai_balance,
+            btc_balance,
+        )?;
+
+        if decision == TakeRequestDecision::GoForSwap {
+            match position {
+                Position::Sell => self.ongoing_sells = self.ongoing_sells.saturating_add(1),
+                Position::Buy => self.ongoing_buys = self.ongoing_buys.saturating_add(1),
+            }
+        }
+
+        Ok(decision)
+    }
+
+    /// The level taken by the order is free again.
+    fn swap_setup_failed(&mut self, order: &BtcErc20Order) {
+        match order.position {
+            Position::Sell => self.ongoing_sells = self.ongoing_sells.saturating_sub(1),
+            Position::Buy => self.ongoing_buys = self.ongoing_buys.saturating_sub(1),
+        }
+
+        self.all_in.swap_setup_failed(order)
+    }
+
+    fn swap_finished(&mut self, swap: SwapKind) {
+        match swap {
+            SwapKind::Herc20Hbit(_) | SwapKind::HethHbit(_) => {
+                self.ongoing_sells = self.ongoing_sells.saturating_sub(1)
+            }
+            SwapKind::HbitHerc20(_) | SwapKind::HbitHeth(_) => {
+                self.ongoing_buys = self.ongoing_buys.saturating_sub(1)
+            }
+        }
+
+        self.all_in.swap_finished(swap)
+    }
+
+    fn fees_updated(&mut self, fees: SwapFees) {
+        self.all_in.fees_updated(fees)
+    }
+
+    fn hbit_herc20_swap_resumed(&mut self, fund_amount: dai::Amount) {
+        self.ongoing_buys = self.ongoing_buys.saturating_add(1);
+        self.all_in.hbit_herc20_swap_resumed(fund_amount)
+    }
+
+    fn herc20_hbit_swap_resumed(&mut self, fund_amount: bitcoin::Amount) -> Result<()> {
+        self.ongoing_sells = self.ongoing_sells.saturating_add(1);
+        self.all_in.herc20_hbit_swap_resumed(fund_amount)
+    }
+}
+
+#[derive(Debug, Copy, Clone, thiserror::Error)]
+#[error("All levels of the ladder are in use, no new order to spend {0}.")]
+pub struct LadderExhausted(Symbol);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bitcoin::amount::btc, ethereum::dai::dai, order::btc_dai_order, rate::rate, StaticStub,
+    };
+
+    fn ladder(levels: u16) -> Ladder {
+        let all_in = AllIn::new(
+            StaticStub::static_stub(),
+            None,
+            None,
+            Spread::new(100).unwrap(),
+            StaticStub::static_stub(),
+        );
+
+        Ladder::new(all_in, btc(1.0), levels, 100).unwrap()
+    }
+
+    #[test]
+    fn every_ongoing_swap_widens_the_spread_of_the_next_order() {
+        let mut ladder = ladder(2);
+
+        let first = ladder.new_sell(btc(10.0), &dai(0.0), rate(1.0)).unwrap();
+        ladder
+            .process_taken_order(
+                btc_dai_order(Position::Sell, btc(1.0), rate(1.01)),
+                rate(1.0),
+                &dai(0.0),
+                &btc(10.0),
+            )
+            .unwrap();
+        let second = ladder.new_sell(btc(10.0), &dai(0.0), rate(1.0)).unwrap();
+
+        assert_eq!(first.quantity.to_inner(), btc(1.0));
+        assert_eq!(second.quantity.to_inner(), btc(1.0));
+        assert_eq!(dai::Amount::from(first.quote()), dai(1.01));
+        assert_eq!(dai::Amount::from(second.quote()), dai(1.02));
+    }
+
+    #[test]
+    fn no_order_once_all_levels_are_in_use() {
+        let mut ladder = ladder(1);
+
+        let decision = ladder
+            .process_taken_order(
+                btc_dai_order(Position::Buy, btc(1.0), rate(0.99)),
+                rate(1.0),
+                &dai(10.0),
+                &btc(0.0),
+            )
+            .unwrap();
+        let result = ladder.new_buy(btc(0.0), &dai(10.0), rate(1.0));
+
+        assert_eq!(decision, TakeRequestDecision::GoForSwap);
+        assert!(result.unwrap_err().downcast::<LadderExhausted>().is_ok());
+        assert!(ladder.new_sell(btc(10.0), &dai(0.0), rate(1.0)).is_ok());
+    }
+
+    #[test]
+    fn failed_swap_setup_frees_the_level() {
+        let mut ladder = ladder(1);
+        let taken_order = btc_dai_order(Position::Buy, btc(1.0), rate(0.99));
+
+        ladder
+            .process_taken_order(taken_order.clone(), rate(1.0), &dai(10.0), &btc(0.0))
+            .unwrap();
+        ladder.swap_setup_failed(&taken_order);
+        let order = ladder.new_buy(btc(0.0), &dai(10.0), rate(1.0)).unwrap();
+
+        assert_eq!(dai::Amount::from(order.quote()), dai(0.99));
+    }
+
+    #[test]
+    fn publish_all_levels_the_balance_allows() {
+        let ladder = ladder(3);
+
+        let sells = ladder.new_sells(btc(2.5), &dai(0.0), rate(1.0)).unwrap();
+        let buys = ladder.new_buys(btc(0.0), &dai(2.5), rate(1.0)).unwrap();
+
+        let sell_quotes = sells
+            .iter()
+            .map(|form| dai::Amount::from(form.quote()))
+            .collect::<Vec<_>>();
+        let buy_quotes = buys
+            .iter()
+            .map(|form| dai::Amount::from(form.quote()))
+            .collect::<Vec<_>>();
+        assert_eq!(sell_quotes, vec![dai(1.01), dai(1.02)]);
+        assert_eq!(buy_quotes, vec![dai(0.99), dai(0.98)]);
+    }
+
+    #[test]
+    fn levels_in_use_are_not_published() {
+        let mut ladder = ladder(3);
+
+        ladder.herc20_hbit_swap_resumed(btc(1.0)).unwrap();
+        let sells = ladder.new_sells(btc(10.0), &dai(0.0), rate(1.0)).unwrap();
+
+        let sell_quotes = sells
+            .iter()
+            .map(|form| dai::Amount::from(form.quote()))
+            .collect::<Vec<_>>();
+        assert_eq!(sell_quotes, vec![dai(1.02), dai(1.03)]);
+    }
+
+    #[test]
+    fn no_order_smaller_than_order_size() {
+        let ladder = ladder(1);
+
+        let result = ladder.new_sell(btc(0.5), &dai(0.0), rate(1.0));
+
+        assert!(result.unwrap_err().downcast::<InsufficientFunds>().is_ok());
+    }
+}
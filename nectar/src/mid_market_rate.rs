@@ -1,12 +1,242 @@
-use crate::{config::KrakenApiHost, Rate};
-use std::convert::TryInto;
+mod bitstamp;
+mod coinbase;
+mod file;
+mod kraken;
 
-/// Get mid-market rate for the trading pair BTC-DAI.
+use crate::{
+    config::{RateAggregation, RateSource as RateSourceConfig},
+    Rate,
+};
+use anyhow::Context;
+use num::{BigUint, ToPrimitive};
+use rust_decimal::Decimal;
+use std::{convert::TryFrom, fmt::Debug, time::Duration};
+use time::OffsetDateTime;
+
+pub use self::{bitstamp::Bitstamp, coinbase::Coinbase, file::File, kraken::Kraken};
+
+/// A source for the mid-market rate of the trading pair BTC-DAI.
+#[async_trait::async_trait]
+pub trait RateSource: Debug + Send + Sync {
+    /// A human readable name of the source, used for logging.
+    fn name(&self) -> &'static str;
+
+    async fn btc_dai_mid_market_rate(&self) -> anyhow::Result<Quote>;
+}
+
+/// A mid-market rate together with the time it was observed at.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quote {
+    pub rate: MidMarketRate,
+    pub timestamp: OffsetDateTime,
+}
+
+impl Quote {
+    pub fn new(rate: MidMarketRate, timestamp: OffsetDateTime) -> Self {
+        Self { rate, timestamp }
+    }
+
+    pub fn now(rate: MidMarketRate) -> Self {
+        Self::new(rate, OffsetDateTime::now_utc())
+    }
+}
+
+/// Combines the mid-market rates of several sources into one.
 ///
-/// Currently, this function only delegates to Kraken. Eventually, it
-/// could return a value based on multiple sources.
-pub async fn get_btc_dai_mid_market_rate(host: &KrakenApiHost) -> anyhow::Result<MidMarketRate> {
-    kraken::get_btc_dai_mid_market_rate(host).await
+/// The last quote of every source is kept, hence an outage of a source only
+/// affects the resulting rate once the quote is older than the configured
+/// maximum age.
+#[derive(Debug)]
+pub struct Aggregator {
+    sources: Vec<Box<dyn RateSource>>,
+    last_quotes: Vec<Option<Quote>>,
+    min_sources: usize,
+    max_deviation: u16,
+    max_age: Duration,
+}
+
+impl Aggregator {
+    pub fn new(sources: Vec<Box<dyn RateSource>>, settings: &RateAggregation) -> Self {
+        let last_quotes = vec![None; sources.len()];
+
+        Self {
+            sources,
+            last_quotes,
+            min_sources: settings.min_sources,
+            max_deviation: settings.max_deviation,
+            max_age: settings.max_age,
+        }
+    }
+
+    pub fn from_settings(settings: &crate::config::Maker) -> Self {
+        let sources = settings
+            .rate
+            .sources
+            .iter()
+            .map(|source| -> Box<dyn RateSource> {
+                match source {
+                    RateSourceConfig::Kraken => {
+                        Box::new(Kraken::new(settings.kraken_api_host.clone()))
+                    }
+                    RateSourceConfig::Coinbase => Box::new(Coinbase::default()),
+                    RateSourceConfig::Bitstamp => Box::new(Bitstamp::default()),
+                    RateSourceConfig::File(path) => Box::new(File::new(path.clone())),
+                }
+            })
+            .collect();
+
+        Self::new(sources, &settings.rate)
+    }
+
+    /// Get mid-market rate for the trading pair BTC-DAI.
+    ///
+    /// Queries all sources and returns the median of the quotes that are not
+    /// stale. Quotes that deviate more than the configured threshold from the
+    /// median are rejected as outliers. Fails if fewer than the minimum number
+    /// of sources remain, in which case we should stop quoting.
+    pub async fn btc_dai_mid_market_rate(&mut self) -> anyhow::Result<MidMarketRate> {
+        let quotes = futures::future::join_all(
+            self.sources
+                .iter()
+                .map(|source| source.btc_dai_mid_market_rate()),
+        )
+        .await;
+
+        for ((source, last_quote), quote) in self
+            .sources
+            .iter()
+            .zip(self.last_quotes.iter_mut())
+            .zip(quotes)
+        {
+            match quote {
+                Ok(quote) => *last_quote = Some(quote),
+                Err(e) => tracing::warn!("failed to get rate from {}: {:#}", source.name(), e),
+            }
+        }
+
+        let quotes = self
+            .sources
+            .iter()
+            .zip(self.last_quotes.iter())
+            .filter_map(|(source, quote)| quote.map(|quote| (source.name(), quote)))
+            .collect::<Vec<_>>();
+
+        aggregate(
+            &quotes,
+            OffsetDateTime::now_utc(),
+            self.min_sources,
+            self.max_deviation,
+            self.max_age,
+        )
+    }
+}
+
+fn aggregate(
+    quotes: &[(&'static str, Quote)],
+    now: OffsetDateTime,
+    min_sources: usize,
+    max_deviation: u16,
+    max_age: Duration,
+) -> anyhow::Result<MidMarketRate> {
+    let fresh = quotes
+        .iter()
+        .filter(|(name, quote)| {
+            let is_fresh = now - quote.timestamp <= max_age;
+            if !is_fresh {
+                tracing::warn!("ignoring stale rate from {}", name);
+            }
+
+            is_fresh
+        })
+        .map(|(name, quote)| (*name, quote.rate))
+        .collect::<Vec<_>>();
+
+    if fresh.len() < min_sources {
+        anyhow::bail!(
+            "only {} of the required {} rate sources are available",
+            fresh.len(),
+            min_sources
+        )
+    }
+
+    let median = median(fresh.iter().map(|(_, rate)| *rate))?;
+
+    let agreeing = fresh
+        .into_iter()
+        .filter(|(name, rate)| {
+            let deviation = deviation_permyriad(*rate, median);
+            if deviation > max_deviation {
+                tracing::warn!(
+                    "rejecting rate {} from {} which deviates {} permyriad from median {}",
+                    Rate::from(*rate),
+                    name,
+                    deviation,
+                    Rate::from(median)
+                );
+                return false;
+            }
+
+            true
+        })
+        .map(|(_, rate)| rate)
+        .collect::<Vec<_>>();
+
+    if agreeing.len() < min_sources {
+        anyhow::bail!(
+            "rate sources disagree, only {} of the required {} are within {} permyriad of the median",
+            agreeing.len(),
+            min_sources,
+            max_deviation
+        )
+    }
+
+    median(agreeing.into_iter())
+}
+
+fn median(rates: impl Iterator<Item = MidMarketRate>) -> anyhow::Result<MidMarketRate> {
+    let mut rates = rates.map(|rate| rate.0.integer()).collect::<Vec<_>>();
+    rates.sort();
+
+    let middle = rates.len() / 2;
+    let median = match rates.len() {
+        0 => anyhow::bail!("cannot compute median of no rates"),
+        len if len % 2 == 0 => (&rates[middle - 1] + &rates[middle]) / 2u8,
+        _ => rates[middle].clone(),
+    };
+    let median = median.to_u64().context("median rate overflows")?;
+
+    Ok(MidMarketRate(Rate::new(median)))
+}
+
+/// The absolute deviation of the rate from the reference in permyriad.
+fn deviation_permyriad(rate: MidMarketRate, reference: MidMarketRate) -> u16 {
+    let rate = rate.0.integer();
+    let reference = reference.0.integer();
+
+    let difference = if rate > reference {
+        rate - &reference
+    } else {
+        &reference - rate
+    };
+
+    if reference == BigUint::from(0u8) {
+        return u16::MAX;
+    }
+
+    let deviation = difference * 10_000u16 / reference;
+    deviation.to_u16().unwrap_or(u16::MAX)
+}
+
+/// Compute the mid-market rate from the best bid and ask price.
+fn mid_market_rate(bid: Decimal, ask: Decimal) -> anyhow::Result<MidMarketRate> {
+    let value = (bid + ask) / Decimal::from(2);
+    let value = value
+        .checked_mul(Decimal::from(10u64.pow(Rate::PRECISION.into())))
+        .context("scaling rate to precision of 10 overflowed")?;
+
+    let rate = Rate::try_from(value)?;
+
+    Ok(MidMarketRate(rate))
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -31,164 +261,85 @@ impl From<MidMarketRate> for Rate {
     }
 }
 
-mod kraken {
+#[cfg(test)]
+mod tests {
     use super::*;
-    use rust_decimal::Decimal;
-    use serde::{de::Error, Deserialize};
-    use std::convert::TryFrom;
+    use crate::rate::rate;
 
-    /// Fetch mid-market rate for the trading pair BTC-DAI from Kraken.
-    ///
-    /// More info here: https://www.kraken.com/features/api
-    /// Rate limits: For public API a frequency of 1 call per second is
-    /// acceptable, More info here: https://support.kraken.com/hc/en-us/articles/206548367-What-are-the-REST-API-rate-limits-
-    pub async fn get_btc_dai_mid_market_rate(
-        host: &KrakenApiHost,
-    ) -> anyhow::Result<MidMarketRate> {
-        let endpoint = host.with_trading_pair("XBTDAI")?;
-
-        let mid_market_rate = reqwest::get(endpoint)
-            .await?
-            .json::<TickerResponse>()
-            .await
-            .map(|response| response.result.xbtdai)?
-            .try_into()?;
-
-        Ok(mid_market_rate)
-    }
-
-    #[derive(Deserialize)]
-    struct TickerResponse {
-        result: Ticker,
-    }
-
-    #[derive(Deserialize)]
-    struct Ticker {
-        #[serde(rename = "XBTDAI")]
-        xbtdai: AskAndBid,
-    }
-
-    #[derive(Clone, Copy, Debug, Deserialize)]
-    #[serde(try_from = "TickerData")]
-    pub struct AskAndBid {
-        pub ask: Decimal,
-        pub bid: Decimal,
-    }
-
-    #[derive(Deserialize)]
-    struct TickerData {
-        #[serde(rename = "a")]
-        ask: Vec<String>,
-        #[serde(rename = "b")]
-        bid: Vec<String>,
-    }
-
-    impl TryFrom<TickerData> for AskAndBid {
-        type Error = serde_json::Error;
-
-        fn try_from(value: TickerData) -> Result<Self, Self::Error> {
-            let ask_price = value
-                .ask
-                .first()
-                .ok_or_else(|| serde_json::Error::custom("no ask price"))?;
-            let bid_price = value
-                .bid
-                .first()
-                .ok_or_else(|| serde_json::Error::custom("no bid price"))?;
-
-            Ok(AskAndBid {
-                ask: ask_price.parse().map_err(serde_json::Error::custom)?,
-                bid: bid_price.parse().map_err(serde_json::Error::custom)?,
-            })
-        }
-    }
+    const MIN_SOURCES: usize = 2;
+    const MAX_DEVIATION: u16 = 100;
+    const MAX_AGE: Duration = Duration::from_secs(600);
 
-    impl TryFrom<AskAndBid> for MidMarketRate {
-        type Error = anyhow::Error;
+    fn quote(value: f64, now: OffsetDateTime) -> Quote {
+        Quote::new(MidMarketRate(rate(value)), now)
+    }
 
-        fn try_from(AskAndBid { ask, bid }: AskAndBid) -> anyhow::Result<Self> {
-            let value = (bid + ask) / Decimal::from(2);
+    #[test]
+    fn median_of_odd_number_of_rates_is_middle_rate() {
+        let now = OffsetDateTime::now_utc();
+        let quotes = [
+            ("kraken", quote(9000.0, now)),
+            ("coinbase", quote(9050.0, now)),
+            ("bitstamp", quote(9010.0, now)),
+        ];
 
-            let kraken_precision = 100_000u64; // data from kraken has a precision of 5 digits (see example data below)
-            let rate_precision = 100_000u64; // rate has a precision of 10 digits, need another 5
+        let aggregated = aggregate(&quotes, now, MIN_SOURCES, MAX_DEVIATION, MAX_AGE).unwrap();
 
-            let value = value * Decimal::from(kraken_precision * rate_precision);
+        assert_eq!(aggregated, MidMarketRate(rate(9010.0)))
+    }
 
-            let rate = Rate::try_from(value)?;
+    #[test]
+    fn median_of_even_number_of_rates_is_mean_of_middle_rates() {
+        let now = OffsetDateTime::now_utc();
+        let quotes = [
+            ("kraken", quote(9000.0, now)),
+            ("coinbase", quote(9020.0, now)),
+        ];
 
-            tracing::trace!(
-                "Computed Kraken BTC/DAI mid-market rate {} from bid {} and ask {}",
-                rate,
-                bid,
-                ask
-            );
+        let aggregated = aggregate(&quotes, now, MIN_SOURCES, MAX_DEVIATION, MAX_AGE).unwrap();
 
-            Ok(Self(rate))
-        }
+        assert_eq!(aggregated, MidMarketRate(rate(9010.0)))
     }
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
-
-        const TICKER_EXAMPLE: &str = r#"{
-    "error": [],
-    "result": {
-        "XBTDAI": {
-            "a": [
-                "9489.50000",
-                "1",
-                "1.000"
-            ],
-            "b": [
-                "9462.70000",
-                "1",
-                "1.000"
-            ],
-            "c": [
-                "9496.50000",
-                "0.00220253"
-            ],
-            "v": [
-                "0.19793959",
-                "0.55769847"
-            ],
-            "p": [
-                "9583.44469",
-                "9593.15707"
-            ],
-            "t": [
-                12,
-                22
-            ],
-            "l": [
-                "9496.50000",
-                "9496.50000"
-            ],
-            "h": [
-                "9594.90000",
-                "9616.10000"
-            ],
-            "o": "9562.30000"
-        }
+    #[test]
+    fn outlier_is_rejected() {
+        let now = OffsetDateTime::now_utc();
+        let quotes = [
+            ("kraken", quote(9000.0, now)),
+            ("coinbase", quote(9020.0, now)),
+            ("bitstamp", quote(10000.0, now)),
+        ];
+
+        let aggregated = aggregate(&quotes, now, MIN_SOURCES, MAX_DEVIATION, MAX_AGE).unwrap();
+
+        assert_eq!(aggregated, MidMarketRate(rate(9010.0)))
     }
-}"#;
 
-        #[test]
-        fn given_ticker_example_data_deserializes_correctly() {
-            serde_json::from_str::<TickerResponse>(TICKER_EXAMPLE).unwrap();
-        }
+    #[test]
+    fn disagreeing_sources_yield_no_rate() {
+        let now = OffsetDateTime::now_utc();
+        let quotes = [
+            ("kraken", quote(9000.0, now)),
+            ("coinbase", quote(10000.0, now)),
+        ];
 
-        #[test]
-        fn ask_and_bid_to_midmarket_rate() {
-            let ask_and_bid = AskAndBid {
-                ask: "9489.54321".parse().unwrap(),
-                bid: "9462.76543".parse().unwrap(),
-            };
+        let result = aggregate(&quotes, now, MIN_SOURCES, MAX_DEVIATION, MAX_AGE);
 
-            let mid_market_rate: MidMarketRate = ask_and_bid.try_into().unwrap();
+        assert!(result.is_err())
+    }
 
-            assert_eq!(mid_market_rate, MidMarketRate(Rate::new(94761543200000)))
-        }
+    #[test]
+    fn stale_quotes_are_ignored() {
+        let now = OffsetDateTime::now_utc();
+        let quotes = [
+            ("kraken", quote(9000.0, now)),
+            ("coinbase", quote(9010.0, now - Duration::from_secs(601))),
+        ];
+
+        let result = aggregate(&quotes, now, MIN_SOURCES, MAX_DEVIATION, MAX_AGE);
+        assert!(result.is_err());
+
+        let aggregated = aggregate(&quotes, now, 1, MAX_DEVIATION, MAX_AGE).unwrap();
+        assert_eq!(aggregated, MidMarketRate(rate(9000.0)))
     }
 }
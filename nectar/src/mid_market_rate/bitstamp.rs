@@ -0,0 +1,88 @@
+use crate::mid_market_rate::{mid_market_rate, Quote, RateSource};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use time::OffsetDateTime;
+use url::Url;
+
+/// Fetch mid-market rate for the trading pair BTC-DAI from Bitstamp.
+///
+/// Bitstamp does not list DAI, hence we use BTC-USD under the assumption that
+/// DAI trades at its peg. Should it not, the rate is rejected as an outlier by
+/// the aggregation with other sources.
+///
+/// More info here: https://www.bitstamp.net/api/#ticker
+/// Rate limits: 8000 requests per 10 minutes.
+#[derive(Debug, Clone)]
+pub struct Bitstamp {
+    host: Url,
+}
+
+impl Default for Bitstamp {
+    fn default() -> Self {
+        Self {
+            host: "https://www.bitstamp.net"
+                .parse()
+                .expect("static url always parses correctly"),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl RateSource for Bitstamp {
+    fn name(&self) -> &'static str {
+        "bitstamp"
+    }
+
+    async fn btc_dai_mid_market_rate(&self) -> anyhow::Result<Quote> {
+        let endpoint = self.host.join("/api/v2/ticker/btcusd/")?;
+
+        let ticker = reqwest::get(endpoint).await?.json::<Ticker>().await?;
+
+        ticker.into_quote()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Ticker {
+    bid: Decimal,
+    ask: Decimal,
+    /// Unix timestamp in seconds, encoded as string.
+    timestamp: String,
+}
+
+impl Ticker {
+    fn into_quote(self) -> anyhow::Result<Quote> {
+        let rate = mid_market_rate(self.bid, self.ask)?;
+        let timestamp = OffsetDateTime::from_unix_timestamp(self.timestamp.parse()?);
+
+        Ok(Quote::new(rate, timestamp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rate::rate, MidMarketRate};
+
+    const TICKER_EXAMPLE: &str = r#"{
+    "high": "16795.00",
+    "last": "16600.00",
+    "timestamp": "1605607923",
+    "bid": "16590.00",
+    "vwap": "16381.09",
+    "volume": "5762.53946296",
+    "low": "15858.65",
+    "ask": "16610.00",
+    "open": 16712.23
+}"#;
+
+    #[test]
+    fn ticker_example_data_converts_to_quote() {
+        let ticker = serde_json::from_str::<Ticker>(TICKER_EXAMPLE).unwrap();
+
+        let quote = ticker.into_quote().unwrap();
+
+        assert_eq!(quote.rate, MidMarketRate::new(rate(16600.0)));
+        assert_eq!(quote.timestamp.unix_timestamp(), 1_605_607_923);
+    }
+}
@@ -0,0 +1,121 @@
+use crate::mid_market_rate::{mid_market_rate, Quote, RateSource};
+use anyhow::Context;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use url::Url;
+
+/// Fetch mid-market rate for the trading pair BTC-DAI from Coinbase Pro.
+///
+/// Coinbase Pro does not list BTC-DAI, hence the rate is crossed from the
+/// BTC-USDC and DAI-USDC order books.
+///
+/// More info here: https://docs.pro.coinbase.com/#get-product-ticker
+/// Rate limits: For public endpoints 3 requests per second.
+#[derive(Debug, Clone)]
+pub struct Coinbase {
+    host: Url,
+}
+
+impl Default for Coinbase {
+    fn default() -> Self {
+        Self {
+            host: "https://api.pro.coinbase.com"
+                .parse()
+                .expect("static url always parses correctly"),
+        }
+    }
+}
+
+impl Coinbase {
+    async fn ticker(&self, product: &str) -> anyhow::Result<Ticker> {
+        let endpoint = self.host.join(&format!("/products/{}/ticker", product))?;
+
+        let ticker = reqwest::Client::new()
+            .get(endpoint)
+            // Coinbase rejects requests without a user agent.
+            .header(reqwest::header::USER_AGENT, "nectar")
+            .send()
+            .await?
+            .json::<Ticker>()
+            .await
+            .with_context(|| format!("failed to deserialize {} ticker", product))?;
+
+        Ok(ticker)
+    }
+}
+
+#[async_trait::async_trait]
+impl RateSource for Coinbase {
+    fn name(&self) -> &'static str {
+        "coinbase"
+    }
+
+    async fn btc_dai_mid_market_rate(&self) -> anyhow::Result<Quote> {
+        let (btc_usdc, dai_usdc) =
+            futures::try_join!(self.ticker("BTC-USDC"), self.ticker("DAI-USDC"))?;
+
+        let rate = cross(btc_usdc, dai_usdc)?;
+
+        Ok(Quote::now(rate))
+    }
+}
+
+/// Cross the BTC and DAI quotes in the same currency to get BTC-DAI.
+///
+/// We buy BTC for DAI by selling DAI at the DAI bid and buying BTC at the BTC
+/// ask, and vice versa.
+fn cross(btc: Ticker, dai: Ticker) -> anyhow::Result<crate::MidMarketRate> {
+    let bid = btc
+        .bid
+        .checked_div(dai.ask)
+        .context("DAI ask price is zero")?;
+    let ask = btc
+        .ask
+        .checked_div(dai.bid)
+        .context("DAI bid price is zero")?;
+
+    mid_market_rate(bid, ask)
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+struct Ticker {
+    bid: Decimal,
+    ask: Decimal,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rate::rate, MidMarketRate};
+
+    const TICKER_EXAMPLE: &str = r#"{
+    "trade_id": 4729088,
+    "price": "333.99",
+    "size": "0.193",
+    "bid": "333.98",
+    "ask": "333.99",
+    "volume": "5957.11914015",
+    "time": "2015-11-14T20:46:03.511254Z"
+}"#;
+
+    #[test]
+    fn given_ticker_example_data_deserializes_correctly() {
+        serde_json::from_str::<Ticker>(TICKER_EXAMPLE).unwrap();
+    }
+
+    #[test]
+    fn crosses_btc_and_dai_quotes() {
+        let btc = Ticker {
+            bid: "9000".parse().unwrap(),
+            ask: "9020".parse().unwrap(),
+        };
+        let dai = Ticker {
+            bid: "1".parse().unwrap(),
+            ask: "1".parse().unwrap(),
+        };
+
+        let mid_market_rate = cross(btc, dai).unwrap();
+
+        assert_eq!(mid_market_rate, MidMarketRate::new(rate(9010.0)))
+    }
+}
@@ -0,0 +1,62 @@
+use crate::mid_market_rate::{mid_market_rate, Quote, RateSource};
+use anyhow::Context;
+use rust_decimal::Decimal;
+use std::path::PathBuf;
+use time::OffsetDateTime;
+
+/// Read the mid-market rate for the trading pair BTC-DAI from a file.
+///
+/// The file contains a single decimal number, e.g. `9500.25`. The time the
+/// file was last modified is considered the time of the quote. This is meant
+/// for testing and for operators who want to feed a rate from their own
+/// infrastructure.
+#[derive(Debug, Clone)]
+pub struct File {
+    path: PathBuf,
+}
+
+impl File {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+#[async_trait::async_trait]
+impl RateSource for File {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    async fn btc_dai_mid_market_rate(&self) -> anyhow::Result<Quote> {
+        let read = || -> anyhow::Result<Quote> {
+            let contents = std::fs::read_to_string(&self.path)?;
+            let modified = std::fs::metadata(&self.path)?.modified()?;
+
+            let rate = contents.trim().parse::<Decimal>()?;
+            let rate = mid_market_rate(rate, rate)?;
+
+            Ok(Quote::new(rate, OffsetDateTime::from(modified)))
+        };
+
+        read().with_context(|| format!("failed to read rate from {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rate::rate, MidMarketRate};
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[tokio::test]
+    async fn reads_rate_from_file() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "9500.25").unwrap();
+
+        let source = File::new(file.path().to_path_buf());
+        let quote = source.btc_dai_mid_market_rate().await.unwrap();
+
+        assert_eq!(quote.rate, MidMarketRate::new(rate(9500.25)));
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{
+    config::KrakenApiHost,
+    mid_market_rate::{mid_market_rate, MidMarketRate, Quote, RateSource},
+    Rate,
+};
+use rust_decimal::Decimal;
+use serde::{de::Error, Deserialize};
+use std::convert::{TryFrom, TryInto};
+
+/// Fetch mid-market rate for the trading pair BTC-DAI from Kraken.
+///
+/// More info here: https://www.kraken.com/features/api
+/// Rate limits: For public API a frequency of 1 call per second is
+/// acceptable, More info here: https://support.kraken.com/hc/en-us/articles/206548367-What-are-the-REST-API-rate-limits-
+#[derive(Debug, Clone)]
+pub struct Kraken {
+    host: KrakenApiHost,
+}
+
+impl Kraken {
+    pub fn new(host: KrakenApiHost) -> Self {
+        Self { host }
+    }
+
+    /// Fetch mid-market rate for the trading pair ETH-DAI, needed to express
+    /// the Ethereum transaction fees in DAI.
+    pub async fn eth_dai_mid_market_rate(&self) -> anyhow::Result<Rate> {
+        let endpoint = self.host.with_trading_pair("ETHDAI")?;
+
+        let AskAndBid { ask, bid } = reqwest::get(endpoint)
+            .await?
+            .json::<TickerResponse<EthDaiTicker>>()
+            .await
+            .map(|response| response.result.ethdai)?;
+        let rate = Rate::from(mid_market_rate(bid, ask)?);
+
+        tracing::trace!(
+            "Computed Kraken ETH/DAI mid-market rate {} from bid {} and ask {}",
+            rate,
+            bid,
+            ask
+        );
+
+        Ok(rate)
+    }
+}
+
+#[async_trait::async_trait]
+impl RateSource for Kraken {
+    fn name(&self) -> &'static str {
+        "kraken"
+    }
+
+    async fn btc_dai_mid_market_rate(&self) -> anyhow::Result<Quote> {
+        let endpoint = self.host.with_trading_pair("XBTDAI")?;
+
+        let mid_market_rate: MidMarketRate = reqwest::get(endpoint)
+            .await?
+            .json::<TickerResponse<Ticker>>()
+            .await
+            .map(|response| response.result.xbtdai)?
+            .try_into()?;
+
+        // The ticker does not contain a timestamp, it is always up to date.
+        Ok(Quote::now(mid_market_rate))
+    }
+}
+
+#[derive(Deserialize)]
+struct TickerResponse<T> {
+    result: T,
+}
+
+#[derive(Deserialize)]
+struct Ticker {
+    #[serde(rename = "XBTDAI")]
+    xbtdai: AskAndBid,
+}
+
+#[derive(Deserialize)]
+struct EthDaiTicker {
+    #[serde(rename = "ETHDAI")]
+    ethdai: AskAndBid,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(try_from = "TickerData")]
+pub struct AskAndBid {
+    pub ask: Decimal,
+    pub bid: Decimal,
+}
+
+#[derive(Deserialize)]
+struct TickerData {
+    #[serde(rename = "a")]
+    ask: Vec<String>,
+    #[serde(rename = "b")]
+    bid: Vec<String>,
+}
+
+impl TryFrom<TickerData> for AskAndBid {
+    type Error = serde_json::Error;
+
+    fn try_from(value: TickerData) -> Result<Self, Self::Error> {
+        let ask_price = value
+            .ask
+            .first()
+            .ok_or_else(|| serde_json::Error::custom("no ask price"))?;
+        let bid_price = value
+            .bid
+            .first()
+            .ok_or_else(|| serde_json::Error::custom("no bid price"))?;
+
+        Ok(AskAndBid {
+            ask: ask_price.parse().map_err(serde_json::Error::custom)?,
+            bid: bid_price.parse().map_err(serde_json::Error::custom)?,
+        })
+    }
+}
+
+impl TryFrom<AskAndBid> for MidMarketRate {
+    type Error = anyhow::Error;
+
+    fn try_from(AskAndBid { ask, bid }: AskAndBid) -> anyhow::Result<Self> {
+        let rate = mid_market_rate(bid, ask)?;
+
+        tracing::trace!(
+            "Computed Kraken BTC/DAI mid-market rate {} from bid {} and ask {}",
+            crate::Rate::from(rate),
+            bid,
+            ask
+        );
+
+        Ok(rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICKER_EXAMPLE: &str = r#"{
+    "error": [],
+    "result": {
+        "XBTDAI": {
+            "a": [
+                "9489.50000",
+                "1",
+                "1.000"
+            ],
+            "b": [
+                "9462.70000",
+                "1",
+                "1.000"
+            ],
+            "c": [
+                "9496.50000",
+                "0.00220253"
+            ],
+            "v": [
+                "0.19793959",
+                "0.55769847"
+            ],
+            "p": [
+                "9583.44469",
+                "9593.15707"
+            ],
+            "t": [
+                12,
+                22
+            ],
+            "l": [
+                "9496.50000",
+                "9496.50000"
+            ],
+            "h": [
+                "9594.90000",
+                "9616.10000"
+            ],
+            "o": "9562.30000"
+        }
+    }
+}"#;
+
+    #[test]
+    fn given_ticker_example_data_deserializes_correctly() {
+        serde_json::from_str::<TickerResponse<Ticker>>(TICKER_EXAMPLE).unwrap();
+    }
+
+    #[test]
+    fn ask_and_bid_to_midmarket_rate() {
+        let ask_and_bid = AskAndBid {
+            ask: "9489.54321".parse().unwrap(),
+            bid: "9462.76543".parse().unwrap(),
+        };
+
+        let mid_market_rate: MidMarketRate = ask_and_bid.try_into().unwrap();
+
+        assert_eq!(mid_market_rate, MidMarketRate(Rate::new(94761543200000)))
+    }
+}
@@ -1,6 +1,9 @@
 use crate::SwapId;
 use ::bitcoin::hashes::{sha256, Hash, HashEngine};
-use comit::network::{orderbook, setup_swap};
+use comit::{
+    network::{orderbook, setup_swap, transport, DiscoveryConfig},
+    BtcErc20Order, OrderId,
+};
 use futures::Future;
 use libp2p::{
     identity::{ed25519, Keypair},
@@ -17,12 +20,22 @@ pub const SEED_LENGTH: usize = 32;
 pub fn new_swarm(seed: Seed, settings: &crate::config::Settings) -> anyhow::Result<Swarm> {
     use anyhow::Context as _;
 
-    let behaviour = Nectar::new(seed);
+    let discovery = DiscoveryConfig {
+        rendezvous_points: settings.network.rendezvous_points.clone(),
+        rendezvous_server: false,
+        mdns: settings.network.mdns,
+    };
+
+    let behaviour = Nectar::new(seed, discovery)?;
 
     let local_key_pair = behaviour.identity();
     let local_peer_id = behaviour.peer_id();
 
-    let transport = transport::build_transport(local_key_pair)?;
+    let transport = transport::build(
+        local_key_pair,
+        &settings.network.listen,
+        settings.network.tor_socks5_port,
+    )?;
 
     let mut swarm = libp2p::swarm::SwarmBuilder::new(transport, behaviour, local_peer_id.clone())
         .executor(Box::new(TokioExecutor {
@@ -57,11 +70,15 @@ impl From<setup_swap::BehaviourOutEvent<SetupSwapContext>> for BehaviourOutEvent
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 pub struct SetupSwapContext {
     pub swap_id: SwapId,
     pub bitcoin_transient_key_index: u32,
     pub match_ref_point: OffsetDateTime,
+    pub our_order: OrderId,
+    pub their_order: OrderId,
+    /// Our order with the quantity taken, accounted for in the risk limits.
+    pub taken_order: BtcErc20Order,
 }
 
 /// A `NetworkBehaviour` that delegates to the `Orderbook` and `SetupSwap`
@@ -77,15 +94,16 @@ pub struct Nectar {
 }
 
 impl Nectar {
-    fn new(seed: Seed) -> Self {
+    fn new(seed: Seed, discovery: DiscoveryConfig) -> anyhow::Result<Self> {
         let identity = seed.derive_libp2p_identity();
         let peer_id = PeerId::from(identity.public());
 
-        Self {
-            orderbook: comit::network::Orderbook::new(peer_id, identity.clone()),
+        Ok(Self {
+            orderbook: comit::network::Orderbook::new(peer_id, identity.clone())
+                .with_discovery(discovery)?,
             identity,
             setup_swap: Default::default(),
-        }
+        })
     }
 
     pub fn identity(&self) -> Keypair {
@@ -182,49 +200,6 @@ impl Seed {
     }
 }
 
-mod transport {
-    use libp2p::{
-        core::{
-            muxing::StreamMuxerBox,
-            transport::{Boxed, Transport},
-            upgrade::{SelectUpgrade, Version},
-        },
-        dns::DnsConfig,
-        mplex::MplexConfig,
-        noise,
-        noise::{NoiseConfig, X25519Spec},
-        tcp::TokioTcpConfig,
-        yamux, PeerId,
-    };
-
-    pub type NectarTransport = Boxed<(PeerId, StreamMuxerBox)>;
-
-    /// Builds a libp2p transport with the following features:
-    /// - TcpConnection
-    /// - DNS name resolution
-    /// - authentication via noise
-    /// - multiplexing via yamux or mplex
-    pub fn build_transport(keypair: libp2p::identity::Keypair) -> anyhow::Result<NectarTransport> {
-        let dh_keys = noise::Keypair::<X25519Spec>::new().into_authentic(&keypair)?;
-        let noise = NoiseConfig::xx(dh_keys).into_authenticated();
-
-        let transport = TokioTcpConfig::new().nodelay(true);
-        let transport = DnsConfig::new(transport)?;
-
-        let transport = transport
-            .upgrade(Version::V1)
-            .authenticate(noise)
-            .multiplex(SelectUpgrade::new(
-                yamux::Config::default(),
-                MplexConfig::new(),
-            ))
-            .map(|(peer, muxer), _| (peer, StreamMuxerBox::new(muxer)))
-            .boxed();
-
-        Ok(transport)
-    }
-}
-
 #[cfg(test)]
 mod arbitrary {
     use super::*;
@@ -19,8 +19,8 @@ pub struct BtcDaiOrderForm {
 }
 
 impl BtcDaiOrderForm {
-    pub fn to_comit_order(&self, swap_protocol: SwapProtocol) -> comit::BtcDaiOrder {
-        comit::BtcDaiOrder::new(
+    pub fn to_comit_order(&self, swap_protocol: SwapProtocol) -> comit::BtcErc20Order {
+        comit::BtcErc20Order::new(
             self.position,
             self.quantity,
             self.price.clone(),
@@ -61,11 +61,12 @@ impl crate::StaticStub for BtcDaiOrderForm {
 }
 
 #[cfg(test)]
-impl crate::StaticStub for comit::BtcDaiOrder {
+impl crate::StaticStub for comit::BtcErc20Order {
     fn static_stub() -> Self {
         use std::convert::TryFrom;
         Self {
             id: "7e0a0846-9765-4221-adf9-cda739a998d2".parse().unwrap(),
+            trading_pair: comit::TradingPair::btc_dai(),
             position: Position::Buy,
             swap_protocol: SwapProtocol::new(
                 comit::Role::Alice,
@@ -84,9 +85,10 @@ pub fn btc_dai_order(
     position: Position,
     btc_quantity: bitcoin::Amount,
     btc_dai_rate: crate::Rate,
-) -> comit::BtcDaiOrder {
-    comit::BtcDaiOrder {
+) -> comit::BtcErc20Order {
+    comit::BtcErc20Order {
         id: comit::OrderId::random(),
+        trading_pair: comit::TradingPair::btc_dai(),
         position,
         swap_protocol: SwapProtocol::new(comit::Role::Alice, Position::Buy, comit::Network::Dev),
         created_at: time::OffsetDateTime::from_unix_timestamp(0),
@@ -1,3 +1,4 @@
+use crate::{bitcoin, ethereum::dai};
 use anyhow::Context;
 use comit::{
     asset::{ethereum::FromWei, Erc20Quantity},
@@ -93,6 +94,16 @@ impl Spread {
         Ok(Spread(permyriad))
     }
 
+    /// Widens the spread by the given permyriad, up to 100%.
+    pub fn widen(self, permyriad: u16) -> Spread {
+        Spread(self.0.saturating_add(permyriad).min(10_000))
+    }
+
+    /// Narrows the spread by the given permyriad, down to 0%.
+    pub fn narrow(self, permyriad: u16) -> Spread {
+        Spread(self.0.saturating_sub(permyriad))
+    }
+
     pub fn apply(self, rate: Rate, position: Position) -> anyhow::Result<Rate> {
         let ten_thousand = BigUint::from(10_000u16);
 
@@ -112,6 +123,77 @@ impl Spread {
     }
 }
 
+/// Skew: shift of the spread towards the side of which we hold too much to
+/// bring our inventory back to a target share of bitcoin
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Skew {
+    target_btc_share: u16,
+    max_skew: u16,
+}
+
+impl Skew {
+    /// Both inputs are in permyriad (per ten thousand): `target_btc_share` is
+    /// the share of the value of our inventory we want to hold in bitcoin,
+    /// `max_skew` is the shift of the spread if we hold only one of the two
+    /// assets.
+    pub fn new(target_btc_share: u16, max_skew: u16) -> anyhow::Result<Skew> {
+        if target_btc_share > 10000 {
+            anyhow::bail!("Target bitcoin share must be between 0% and 100%");
+        }
+
+        Ok(Skew {
+            target_btc_share,
+            max_skew,
+        })
+    }
+
+    /// If more than the target share of our inventory is held in bitcoin, the
+    /// spread of sell orders is narrowed and the one of buy orders widened,
+    /// the reverse if we hold too much dai. The shift grows linearly with the
+    /// deviation from the target.
+    pub fn apply(
+        self,
+        spread: Spread,
+        position: Position,
+        btc_balance: bitcoin::Amount,
+        dai_balance: &dai::Amount,
+        mid_market_rate: Rate,
+    ) -> Spread {
+        let ten_thousand = 10_000u128;
+
+        // Dust that is worth less than a satoshi does not count
+        let dai_worth = dai_balance
+            .worth_in(mid_market_rate)
+            .map_or(0, |worth| worth.as_sat());
+        let btc_worth = btc_balance.as_sat();
+        let total = u128::from(btc_worth) + u128::from(dai_worth);
+        if total == 0 {
+            return spread;
+        }
+
+        let btc_share = u128::from(btc_worth) * ten_thousand / total;
+        let target = u128::from(self.target_btc_share);
+        let max_skew = u128::from(self.max_skew);
+
+        let (btc_heavy, skew) = if btc_share > target {
+            (
+                true,
+                max_skew * (btc_share - target) / (ten_thousand - target),
+            )
+        } else if btc_share < target {
+            (false, max_skew * (target - btc_share) / target)
+        } else {
+            return spread;
+        };
+        let skew = u16::try_from(skew).expect("skew is at most max_skew");
+
+        match (position, btc_heavy) {
+            (Position::Sell, true) | (Position::Buy, false) => spread.narrow(skew),
+            (Position::Sell, false) | (Position::Buy, true) => spread.widen(skew),
+        }
+    }
+}
+
 #[cfg(test)]
 pub fn rate(rate: f64) -> Rate {
     Rate::try_from(rate).unwrap()
@@ -120,7 +202,7 @@ pub fn rate(rate: f64) -> Rate {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::StaticStub;
+    use crate::{bitcoin::amount::btc, ethereum::dai::dai, StaticStub};
     use proptest::prelude::*;
 
     impl StaticStub for Spread {
@@ -203,6 +285,69 @@ mod tests {
         assert_eq!(rate, res);
     }
 
+    #[test]
+    fn widen_and_narrow_stay_within_bounds() {
+        let spread = Spread::new(300).unwrap();
+
+        assert_eq!(spread.widen(200), Spread(500));
+        assert_eq!(spread.widen(u16::MAX), Spread(10_000));
+        assert_eq!(spread.narrow(200), Spread(100));
+        assert_eq!(spread.narrow(400), Spread(0));
+    }
+
+    #[test]
+    fn skew_error_on_target_above_hundred() {
+        let skew = Skew::new(10100, 100);
+        assert!(skew.is_err());
+    }
+
+    #[test]
+    fn balanced_inventory_is_not_skewed() {
+        let skew = Skew::new(5000, 100).unwrap();
+        let spread = Spread::new(200).unwrap();
+
+        let sell = skew.apply(spread, Position::Sell, btc(1.0), &dai(1.0), rate(1.0));
+        let buy = skew.apply(spread, Position::Buy, btc(1.0), &dai(1.0), rate(1.0));
+
+        assert_eq!(sell, spread);
+        assert_eq!(buy, spread);
+    }
+
+    #[test]
+    fn btc_heavy_inventory_sells_more_aggressively() {
+        let skew = Skew::new(5000, 100).unwrap();
+        let spread = Spread::new(200).unwrap();
+
+        // 75% bitcoin is half way between the target and holding only bitcoin
+        let sell = skew.apply(spread, Position::Sell, btc(3.0), &dai(1.0), rate(1.0));
+        let buy = skew.apply(spread, Position::Buy, btc(3.0), &dai(1.0), rate(1.0));
+
+        assert_eq!(sell, Spread(150));
+        assert_eq!(buy, Spread(250));
+    }
+
+    #[test]
+    fn dai_heavy_inventory_buys_more_aggressively() {
+        let skew = Skew::new(5000, 100).unwrap();
+        let spread = Spread::new(200).unwrap();
+
+        let sell = skew.apply(spread, Position::Sell, btc(0.0), &dai(10.0), rate(1.0));
+        let buy = skew.apply(spread, Position::Buy, btc(0.0), &dai(10.0), rate(1.0));
+
+        assert_eq!(sell, Spread(300));
+        assert_eq!(buy, Spread(100));
+    }
+
+    #[test]
+    fn empty_inventory_is_not_skewed() {
+        let skew = Skew::new(5000, 100).unwrap();
+        let spread = Spread::new(200).unwrap();
+
+        let sell = skew.apply(spread, Position::Sell, btc(0.0), &dai(0.0), rate(1.0));
+
+        assert_eq!(sell, spread);
+    }
+
     proptest! {
         #[test]
         fn spread_new_doesnt_panic(s in any::<u16>()) {
@@ -4,6 +4,7 @@ pub mod bitcoin;
 pub mod ethereum;
 pub mod hbit;
 pub mod herc20;
+pub mod heth;
 
 use crate::{
     command::FinishedSwap,
@@ -11,9 +12,13 @@ use crate::{
     network::ActivePeer,
     SwapId,
 };
-use ::comit::btsieve::{bitcoin::BitcoindConnector, ethereum::Web3Connector};
 use anyhow::Result;
-use comit::swap::Action;
+use comit::{
+    btsieve::BlockStore,
+    expiries::{CurrentTime, Expiries},
+    swap::{alice_verdict, bob_verdict, Action, Verdict},
+    Network, Never, Role,
+};
 use futures::{channel::mpsc, SinkExt, Stream, TryStreamExt};
 use std::{future::Future, sync::Arc};
 use time::OffsetDateTime;
@@ -25,17 +30,44 @@ pub use crate::database::Database;
 pub enum SwapKind {
     HbitHerc20(SwapParams),
     Herc20Hbit(SwapParams),
+    HbitHeth(HethSwapParams),
+    HethHbit(HethSwapParams),
 }
 
 impl SwapKind {
-    pub fn params(&self) -> SwapParams {
+    pub fn swap_id(&self) -> SwapId {
         match self {
-            SwapKind::HbitHerc20(params) | SwapKind::Herc20Hbit(params) => params.clone(),
+            SwapKind::HbitHerc20(params) | SwapKind::Herc20Hbit(params) => params.swap_id,
+            SwapKind::HbitHeth(params) | SwapKind::HethHbit(params) => params.swap_id,
         }
     }
 
-    pub fn swap_id(&self) -> SwapId {
-        self.params().swap_id
+    pub fn start_of_swap(&self) -> OffsetDateTime {
+        match self {
+            SwapKind::HbitHerc20(params) | SwapKind::Herc20Hbit(params) => params.start_of_swap,
+            SwapKind::HbitHeth(params) | SwapKind::HethHbit(params) => params.start_of_swap,
+        }
+    }
+
+    pub fn taker(&self) -> ActivePeer {
+        match self {
+            SwapKind::HbitHerc20(params) | SwapKind::Herc20Hbit(params) => params.taker.clone(),
+            SwapKind::HbitHeth(params) | SwapKind::HethHbit(params) => params.taker.clone(),
+        }
+    }
+
+    pub fn secret_hash(&self) -> comit::SecretHash {
+        match self {
+            SwapKind::HbitHerc20(params) | SwapKind::Herc20Hbit(params) => params.secret_hash,
+            SwapKind::HbitHeth(params) | SwapKind::HethHbit(params) => params.secret_hash,
+        }
+    }
+
+    pub fn hbit_params(&self) -> &hbit::Params {
+        match self {
+            SwapKind::HbitHerc20(params) | SwapKind::Herc20Hbit(params) => &params.hbit_params,
+            SwapKind::HbitHeth(params) | SwapKind::HethHbit(params) => &params.hbit_params,
+        }
     }
 }
 
@@ -49,6 +81,47 @@ pub struct SwapParams {
     pub taker: ActivePeer,
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HethSwapParams {
+    pub hbit_params: hbit::Params,
+    pub heth_params: heth::Params,
+    pub secret_hash: comit::SecretHash,
+    pub start_of_swap: OffsetDateTime,
+    pub swap_id: SwapId,
+    pub taker: ActivePeer,
+}
+
+/// Follows the Bitcoin and the Ethereum chain on behalf of all swaps, meant to
+/// be spawned as a task.
+pub async fn follow_chains(
+    bitcoin_connector: Arc<bitcoin::Connector>,
+    ethereum_connector: Arc<ethereum::Connector>,
+) -> Never {
+    let (never, _) =
+        futures::future::join(bitcoin_connector.follow(), ethereum_connector.follow()).await;
+
+    never
+}
+
+/// Opens the store that keeps blocks on disk, if configured.
+pub fn open_block_store(data: &crate::config::Data) -> Result<Option<BlockStore>> {
+    if !data.persistent_block_cache {
+        return Ok(None);
+    }
+
+    let block_store = BlockStore::open(&data.dir.join("block_cache"))?;
+
+    Ok(Some(block_store))
+}
+
+/// The start of the oldest active swap, the block store keeps the blocks from
+/// then on.
+pub fn start_of_oldest_active_swap(db: &Database) -> Result<Option<OffsetDateTime>> {
+    let swaps = db.all_active_swaps()?;
+
+    Ok(swaps.iter().map(|swap| swap.start_of_swap()).min())
+}
+
 #[cfg(test)]
 impl crate::StaticStub for SwapParams {
     fn static_stub() -> Self {
@@ -113,10 +186,11 @@ mod arbitrary {
 
     impl Arbitrary for SwapKind {
         fn arbitrary<G: Gen>(g: &mut G) -> Self {
-            if bool::arbitrary(g) {
-                SwapKind::HbitHerc20(SwapParams::arbitrary(g))
-            } else {
-                SwapKind::Herc20Hbit(SwapParams::arbitrary(g))
+            match u8::arbitrary(g) % 4 {
+                0 => SwapKind::HbitHerc20(SwapParams::arbitrary(g)),
+                1 => SwapKind::Herc20Hbit(SwapParams::arbitrary(g)),
+                2 => SwapKind::HbitHeth(HethSwapParams::arbitrary(g)),
+                _ => SwapKind::HethHbit(HethSwapParams::arbitrary(g)),
             }
         }
     }
@@ -133,6 +207,19 @@ mod arbitrary {
             }
         }
     }
+
+    impl Arbitrary for HethSwapParams {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            HethSwapParams {
+                hbit_params: hbit::Params::arbitrary(g),
+                heth_params: heth::Params::arbitrary(g),
+                secret_hash: SecretHash::arbitrary(g),
+                start_of_swap: OffsetDateTime::from_unix_timestamp(u32::arbitrary(g) as i64),
+                swap_id: SwapId::arbitrary(g),
+                taker: ActivePeer::arbitrary(g),
+            }
+        }
+    }
 }
 
 #[cfg(all(test, feature = "testcontainers"))]
@@ -145,7 +232,6 @@ mod tests {
             self,
             ethereum::{Erc20Quantity, FromWei},
         },
-        btsieve::{bitcoin::BitcoindConnector, ethereum::Web3Connector},
         ethereum::ChainId,
         identity, ledger, Secret, SecretHash, Timestamp,
     };
@@ -217,7 +303,17 @@ mod tests {
             let node_url = blockchain.node_url.clone();
 
             (
-                Arc::new(BitcoindConnector::new(node_url.clone())?),
+                Arc::new(bitcoin::new_connector(
+                    &crate::config::Bitcoin {
+                        bitcoind: crate::config::Bitcoind {
+                            node_url: node_url.clone(),
+                            fallback_node_urls: vec![],
+                            zmq_url: None,
+                        },
+                        ..StaticStub::static_stub()
+                    },
+                    None,
+                )?),
                 node_url,
                 blockchain,
             )
@@ -231,7 +327,16 @@ mod tests {
             let token_contract = blockchain.token_contract();
 
             (
-                Arc::new(Web3Connector::new(node_url.clone())),
+                Arc::new(ethereum::new_connector(
+                    &crate::config::Ethereum {
+                        node_url: node_url.clone(),
+                        fallback_node_urls: vec![],
+                        chain: crate::ethereum::Chain::new(ethereum_chain_id, token_contract),
+                        watch_mempool: false,
+                        gas_price: Default::default(),
+                    },
+                    None,
+                )?),
                 node_url,
                 blockchain,
                 token_contract,
@@ -276,9 +381,13 @@ mod tests {
             let bitcoin_fee = crate::bitcoin::Fee::new(
                 crate::config::Bitcoin {
                     network: ledger::Bitcoin::Regtest,
+                    watch_mempool: false,
                     bitcoind: crate::config::Bitcoind {
                         node_url: bitcoind_url.clone(),
+                        fallback_node_urls: vec![],
+                        zmq_url: None,
                     },
+                    connector: crate::config::BitcoinConnector::Bitcoind,
                     fees: crate::config::BitcoinFees::SatsPerByte(bitcoin::Amount::from_sat(50)),
                 },
                 crate::bitcoin::Client::new(bitcoind_url.clone()),
@@ -289,11 +398,13 @@ mod tests {
                     inner: Arc::new(bitcoin_wallet),
                     connector: Arc::clone(&bitcoin_connector),
                     fee: bitcoin_fee,
+                    watch_mempool: false,
                 },
                 ethereum::Wallet {
                     inner: Arc::new(ethereum_wallet),
                     connector: Arc::clone(&ethereum_connector),
                     gas_price: ethereum_gas_price,
+                    watch_mempool: false,
                 },
             )
         };
@@ -332,9 +443,13 @@ mod tests {
             let bitcoin_fee = crate::bitcoin::Fee::new(
                 crate::config::Bitcoin {
                     network: ledger::Bitcoin::Regtest,
+                    watch_mempool: false,
                     bitcoind: crate::config::Bitcoind {
                         node_url: bitcoind_url.clone(),
+                        fallback_node_urls: vec![],
+                        zmq_url: None,
                     },
+                    connector: crate::config::BitcoinConnector::Bitcoind,
                     fees: crate::config::BitcoinFees::SatsPerByte(bitcoin::Amount::from_sat(50)),
                 },
                 crate::bitcoin::Client::new(bitcoind_url.clone()),
@@ -345,11 +460,13 @@ mod tests {
                     inner: Arc::new(bitcoin_wallet),
                     connector: Arc::clone(&bitcoin_connector),
                     fee: bitcoin_fee,
+                    watch_mempool: true,
                 },
                 ethereum::Wallet {
                     inner: Arc::new(ethereum_wallet),
                     connector: Arc::clone(&ethereum_connector),
                     gas_price: ethereum_gas_price,
+                    watch_mempool: true,
                 },
             )
         };
@@ -391,23 +508,37 @@ mod tests {
 
             alice_db.insert_swap(swap).await.unwrap();
 
+            let hbit_facade = hbit::Facade {
+                swap_id,
+                db: alice_db.clone(),
+                wallet: alice_bitcoin_wallet.clone(),
+            };
+            let herc20_facade = herc20::Facade {
+                swap_id,
+                db: alice_db.clone(),
+                wallet: alice_ethereum_wallet.clone(),
+            };
+            let expiries = Expiries::new_hbit_herc20_with_expiries(
+                Network::Dev,
+                start_of_swap.into(),
+                hbit_params.shared.expiry,
+                herc20_params.expiry,
+                hbit_facade.clone(),
+                herc20_facade.clone(),
+            );
+
             drive(
                 comit::swap::hbit_herc20_alice(
-                    hbit::Facade {
-                        swap_id,
-                        db: alice_db.clone(),
-                        wallet: alice_bitcoin_wallet.clone(),
-                    },
-                    herc20::Facade {
-                        swap_id,
-                        db: alice_db.clone(),
-                        wallet: alice_ethereum_wallet.clone(),
-                    },
+                    hbit_facade,
+                    herc20_facade,
+                    crate::SECP.clone(),
                     hbit_params,
                     herc20_params.clone(),
                     secret,
                     start_of_swap,
                 ),
+                expiries,
+                Role::Alice,
                 alice_bitcoin_wallet.clone(),
                 alice_ethereum_wallet.clone(),
                 alice_db.clone(),
@@ -439,23 +570,36 @@ mod tests {
                 final_address: bob_bitcoin_wallet.inner.new_address().await?,
             };
 
+            let hbit_facade = hbit::Facade {
+                swap_id,
+                db: bob_db.clone(),
+                wallet: bob_bitcoin_wallet.clone(),
+            };
+            let herc20_facade = herc20::Facade {
+                swap_id,
+                db: bob_db.clone(),
+                wallet: bob_ethereum_wallet.clone(),
+            };
+            let expiries = Expiries::new_hbit_herc20_with_expiries(
+                Network::Dev,
+                start_of_swap.into(),
+                hbit_params.shared.expiry,
+                herc20_params.expiry,
+                hbit_facade.clone(),
+                herc20_facade.clone(),
+            );
+
             drive(
                 comit::swap::hbit_herc20_bob(
-                    hbit::Facade {
-                        swap_id,
-                        db: bob_db.clone(),
-                        wallet: bob_bitcoin_wallet.clone(),
-                    },
-                    herc20::Facade {
-                        swap_id,
-                        db: bob_db.clone(),
-                        wallet: bob_ethereum_wallet.clone(),
-                    },
+                    hbit_facade,
+                    herc20_facade,
                     crate::SECP.clone(),
                     hbit_params,
                     herc20_params.clone(),
                     start_of_swap,
                 ),
+                expiries,
+                Role::Bob,
                 bob_bitcoin_wallet.clone(),
                 bob_ethereum_wallet.clone(),
                 bob_db.clone(),
@@ -527,8 +671,10 @@ pub struct SwapExecutor {
     ethereum_wallet: Arc<crate::ethereum::Wallet>,
     ethereum_gas_price: crate::ethereum::GasPrice,
     finished_swap_sender: mpsc::Sender<FinishedSwap>,
-    bitcoin_connector: Arc<BitcoindConnector>,
-    ethereum_connector: Arc<Web3Connector>,
+    bitcoin_connector: Arc<bitcoin::Connector>,
+    ethereum_connector: Arc<ethereum::Connector>,
+    watch_bitcoin_mempool: bool,
+    watch_ethereum_mempool: bool,
 }
 
 impl SwapExecutor {
@@ -538,8 +684,8 @@ impl SwapExecutor {
         bitcoin_fee: crate::bitcoin::Fee,
         ethereum_wallet: Arc<crate::ethereum::Wallet>,
         ethereum_gas_price: crate::ethereum::GasPrice,
-        bitcoin_connector: Arc<BitcoindConnector>,
-        ethereum_connector: Arc<Web3Connector>,
+        bitcoin_connector: Arc<bitcoin::Connector>,
+        ethereum_connector: Arc<ethereum::Connector>,
     ) -> (Self, mpsc::Receiver<FinishedSwap>) {
         // buffer increases by 1 for every clone of `Sender` and we use every sender
         // only once, hence making the initial buffer size 0 is good enough
@@ -555,10 +701,22 @@ impl SwapExecutor {
             finished_swap_sender,
             bitcoin_connector,
             ethereum_connector,
+            watch_bitcoin_mempool: false,
+            watch_ethereum_mempool: false,
         };
 
         (executor, finished_swap_receiver)
     }
+
+    /// Configures whether swaps look for the secret in transactions that are
+    /// not mined yet.
+    pub fn with_mempool_watching(self, bitcoin: bool, ethereum: bool) -> Self {
+        Self {
+            watch_bitcoin_mempool: bitcoin,
+            watch_ethereum_mempool: ethereum,
+            ..self
+        }
+    }
 }
 
 impl SwapExecutor {
@@ -569,11 +727,13 @@ impl SwapExecutor {
                 inner: self.bitcoin_wallet.clone(),
                 connector: self.bitcoin_connector.clone(),
                 fee: self.bitcoin_fee.clone(),
+                watch_mempool: self.watch_bitcoin_mempool,
             },
             ethereum::Wallet {
                 inner: self.ethereum_wallet.clone(),
                 connector: self.ethereum_connector.clone(),
                 gas_price: self.ethereum_gas_price.clone(),
+                watch_mempool: self.watch_ethereum_mempool,
             },
             self.db.clone(),
             self.finished_swap_sender.clone(),
@@ -605,17 +765,28 @@ async fn execute(
             swap_id,
             ..
         }) => {
+            let hbit_facade = hbit::Facade {
+                swap_id,
+                db: db.clone(),
+                wallet: bitcoin_wallet.clone(),
+            };
+            let herc20_facade = herc20::Facade {
+                swap_id,
+                db: db.clone(),
+                wallet: ethereum_wallet.clone(),
+            };
+            let expiries = Expiries::new_hbit_herc20_with_expiries(
+                Network::from(hbit_params.shared.network),
+                start_of_swap.into(),
+                hbit_params.shared.expiry,
+                herc20_params.expiry,
+                hbit_facade.clone(),
+                herc20_facade.clone(),
+            );
+
             let swap = comit::swap::hbit_herc20_bob(
-                hbit::Facade {
-                    swap_id,
-                    db: db.clone(),
-                    wallet: bitcoin_wallet.clone(),
-                },
-                herc20::Facade {
-                    swap_id,
-                    db: db.clone(),
-                    wallet: ethereum_wallet.clone(),
-                },
+                hbit_facade,
+                herc20_facade,
                 crate::SECP.clone(),
                 hbit_params,
                 herc20_params,
@@ -623,7 +794,16 @@ async fn execute(
             )
             .instrument(tracing::error_span!("hbit_herc20_bob", %swap_id));
 
-            drive(swap, bitcoin_wallet, ethereum_wallet, db, swap_id).await?;
+            drive(
+                swap,
+                expiries,
+                Role::Bob,
+                bitcoin_wallet,
+                ethereum_wallet,
+                db,
+                swap_id,
+            )
+            .await?;
         }
         SwapKind::Herc20Hbit(SwapParams {
             hbit_params,
@@ -632,86 +812,255 @@ async fn execute(
             swap_id,
             ..
         }) => {
+            let herc20_facade = herc20::Facade {
+                swap_id,
+                db: db.clone(),
+                wallet: ethereum_wallet.clone(),
+            };
+            let hbit_facade = hbit::Facade {
+                swap_id,
+                db: db.clone(),
+                wallet: bitcoin_wallet.clone(),
+            };
+            let expiries = Expiries::new_herc20_hbit_with_expiries(
+                Network::from(hbit_params.shared.network),
+                start_of_swap.into(),
+                herc20_params.expiry,
+                hbit_params.shared.expiry,
+                herc20_facade.clone(),
+                hbit_facade.clone(),
+            );
+
             let swap = comit::swap::herc20_hbit_bob(
-                herc20::Facade {
-                    swap_id,
-                    db: db.clone(),
-                    wallet: ethereum_wallet.clone(),
-                },
-                hbit::Facade {
-                    swap_id,
-                    db: db.clone(),
-                    wallet: bitcoin_wallet.clone(),
-                },
+                herc20_facade,
+                hbit_facade,
+                crate::SECP.clone(),
                 herc20_params,
                 hbit_params,
                 start_of_swap,
             )
             .instrument(tracing::error_span!("herc20_hbit_bob", %swap_id));
 
-            drive(swap, bitcoin_wallet, ethereum_wallet, db, swap_id).await?;
+            drive(
+                swap,
+                expiries,
+                Role::Bob,
+                bitcoin_wallet,
+                ethereum_wallet,
+                db,
+                swap_id,
+            )
+            .await?;
+        }
+        SwapKind::HbitHeth(HethSwapParams {
+            hbit_params,
+            heth_params,
+            start_of_swap,
+            swap_id,
+            ..
+        }) => {
+            let hbit_facade = hbit::Facade {
+                swap_id,
+                db: db.clone(),
+                wallet: bitcoin_wallet.clone(),
+            };
+            let heth_facade = heth::Facade {
+                swap_id,
+                db: db.clone(),
+                wallet: ethereum_wallet.clone(),
+            };
+            let expiries = Expiries::new_hbit_heth_with_expiries(
+                Network::from(hbit_params.shared.network),
+                start_of_swap.into(),
+                hbit_params.shared.expiry,
+                heth_params.expiry,
+                hbit_facade.clone(),
+                heth_facade.clone(),
+            );
+
+            let swap = comit::swap::hbit_heth_bob(
+                hbit_facade,
+                heth_facade,
+                crate::SECP.clone(),
+                hbit_params,
+                heth_params,
+                start_of_swap,
+            )
+            .instrument(tracing::error_span!("hbit_heth_bob", %swap_id));
+
+            drive(
+                swap,
+                expiries,
+                Role::Bob,
+                bitcoin_wallet,
+                ethereum_wallet,
+                db,
+                swap_id,
+            )
+            .await?;
+        }
+        SwapKind::HethHbit(HethSwapParams {
+            hbit_params,
+            heth_params,
+            start_of_swap,
+            swap_id,
+            ..
+        }) => {
+            let heth_facade = heth::Facade {
+                swap_id,
+                db: db.clone(),
+                wallet: ethereum_wallet.clone(),
+            };
+            let hbit_facade = hbit::Facade {
+                swap_id,
+                db: db.clone(),
+                wallet: bitcoin_wallet.clone(),
+            };
+            let expiries = Expiries::new_heth_hbit_with_expiries(
+                Network::from(hbit_params.shared.network),
+                start_of_swap.into(),
+                heth_params.expiry,
+                hbit_params.shared.expiry,
+                heth_facade.clone(),
+                hbit_facade.clone(),
+            );
+
+            let swap = comit::swap::heth_hbit_bob(
+                heth_facade,
+                hbit_facade,
+                crate::SECP.clone(),
+                heth_params,
+                hbit_params,
+                start_of_swap,
+            )
+            .instrument(tracing::error_span!("heth_hbit_bob", %swap_id));
+
+            drive(
+                swap,
+                expiries,
+                Role::Bob,
+                bitcoin_wallet,
+                ethereum_wallet,
+                db,
+                swap_id,
+            )
+            .await?;
         }
     };
 
-    let active_peer = swap.params().taker;
+    let active_peer = swap.taker();
     let swap_id = swap.swap_id();
+    let refunded = is_refunded(db.as_ref(), swap_id)?;
     if let Err(e) = sender
         .send(FinishedSwap::new(
             swap,
             active_peer,
             OffsetDateTime::now_utc(),
+            refunded,
         ))
         .await
     {
         tracing::warn!("failed to notify about finished swap {}", e)
     };
 
-    tracing::info!("swap {} finished successfully", swap_id);
+    if refunded {
+        tracing::info!("swap {} was refunded", swap_id);
 
-    sentry::capture_message(
-        format!("successful execution for swap {}", swap_id).as_str(),
-        sentry::Level::Info,
-    );
+        sentry::capture_message(
+            format!("refunded execution for swap {}", swap_id).as_str(),
+            sentry::Level::Warning,
+        );
+    } else {
+        tracing::info!("swap {} finished successfully", swap_id);
+
+        sentry::capture_message(
+            format!("successful execution for swap {}", swap_id).as_str(),
+            sentry::Level::Info,
+        );
+    }
 
     Ok(())
 }
 
-async fn drive<E>(
+/// Whether we refunded our HTLC instead of redeeming the counterparty's.
+fn is_refunded(db: &Database, swap_id: SwapId) -> Result<bool> {
+    let hbit_refunded: Option<hbit::Refunded> = db.load(swap_id)?;
+    let herc20_refunded: Option<herc20::Refunded> = db.load(swap_id)?;
+    let heth_refunded: Option<heth::Refunded> = db.load(swap_id)?;
+
+    Ok(hbit_refunded.is_some() || herc20_refunded.is_some() || heth_refunded.is_some())
+}
+
+async fn drive<A, B, E>(
     mut swap: impl Stream<Item = Result<Action, E>> + Unpin,
+    expiries: Expiries<A, B>,
+    role: Role,
     bitcoin_wallet: bitcoin::Wallet,
     ethereum_wallet: ethereum::Wallet,
     db: Arc<Database>,
     swap_id: SwapId,
 ) -> Result<()>
 where
+    A: CurrentTime,
+    B: CurrentTime,
     E: std::error::Error + Send + Sync + 'static,
 {
     while let Some(action) = swap.try_next().await? {
+        let verdict = match role {
+            Role::Alice => alice_verdict(&expiries, &action).await,
+            Role::Bob => bob_verdict(&expiries, &action).await,
+        };
+
         match action {
             Action::Herc20Deploy(inner) => {
                 let action = ethereum_wallet.execute_deploy(inner);
 
-                execute_idempotently(db.as_ref(), swap_id, action).await?;
+                execute_idempotently(db.as_ref(), swap_id, verdict, action).await?;
             }
             Action::Herc20Fund(inner) => {
                 let action = ethereum_wallet.execute_fund(inner);
 
-                execute_idempotently(db.as_ref(), swap_id, action).await?;
+                execute_idempotently(db.as_ref(), swap_id, verdict, action).await?;
             }
             Action::Herc20Redeem(inner, secret) => {
                 let action = ethereum_wallet.execute_redeem(inner, secret);
 
-                execute_idempotently(db.as_ref(), swap_id, action).await?;
+                execute_idempotently(db.as_ref(), swap_id, verdict, action).await?;
             }
             Action::HbitFund(inner) => {
                 let action = bitcoin_wallet.execute_fund(inner);
 
-                execute_idempotently(db.as_ref(), swap_id, action).await?;
+                execute_idempotently(db.as_ref(), swap_id, verdict, action).await?;
+            }
+            Action::Herc20Refund(inner) => {
+                let action = ethereum_wallet.execute_refund(inner);
+
+                execute_idempotently(db.as_ref(), swap_id, verdict, action).await?;
             }
             Action::HbitRedeem(inner, secret) => {
                 let action = bitcoin_wallet.execute_redeem(inner, secret);
 
-                execute_idempotently(db.as_ref(), swap_id, action).await?;
+                execute_idempotently(db.as_ref(), swap_id, verdict, action).await?;
+            }
+            Action::HbitRefund(inner) => {
+                let action = bitcoin_wallet.execute_refund(inner);
+
+                execute_idempotently(db.as_ref(), swap_id, verdict, action).await?;
+            }
+            Action::HethFund(inner) => {
+                let action = ethereum_wallet.execute_heth_fund(inner);
+
+                execute_idempotently(db.as_ref(), swap_id, verdict, action).await?;
+            }
+            Action::HethRedeem(inner, secret) => {
+                let action = ethereum_wallet.execute_heth_redeem(inner, secret);
+
+                execute_idempotently(db.as_ref(), swap_id, verdict, action).await?;
+            }
+            Action::HethRefund(inner) => {
+                let action = ethereum_wallet.execute_heth_refund(inner);
+
+                execute_idempotently(db.as_ref(), swap_id, verdict, action).await?;
             }
         }
     }
@@ -719,9 +1068,15 @@ where
     Ok(())
 }
 
+/// Executes the action unless it has already been executed before.
+///
+/// The verdict of the expiries is only consulted for actions we have not yet
+/// executed, a resumed swap must not abort because of an action that already
+/// happened.
 async fn execute_idempotently<T, DB>(
     db: &DB,
     swap_id: SwapId,
+    verdict: Verdict,
     action: impl Future<Output = Result<T>>,
 ) -> Result<()>
 where
@@ -732,6 +1087,18 @@ where
         return Ok(());
     }
 
+    match verdict {
+        Verdict::Execute => {}
+        Verdict::WaitToRefund => {
+            tracing::warn!("not enough time left to safely complete the swap, waiting to refund");
+
+            return Ok(());
+        }
+        Verdict::Abort => {
+            anyhow::bail!("not enough time left to safely complete the swap, aborting")
+        }
+    }
+
     let result = action.await?;
     db.save(result, swap_id).await?;
 
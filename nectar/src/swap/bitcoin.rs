@@ -1,7 +1,11 @@
-use crate::{bitcoin, swap::hbit};
+use crate::{bitcoin, config, swap::hbit};
 use anyhow::Result;
 use comit::{
-    btsieve::{BlockByHash, LatestBlock},
+    btsieve::{
+        bitcoin::{AnyConnector, BitcoindConnector, Cache, ElectrumConnector, EsploraConnector},
+        BlockByHash, BlockStore, ChainFollower, LatestBlock, MultiConnector,
+    },
+    ledger,
     swap::actions::{SendToAddress, SpendOutput},
     Secret,
 };
@@ -10,11 +14,56 @@ use std::sync::Arc;
 pub use crate::bitcoin::Amount;
 pub use ::bitcoin::{secp256k1::SecretKey, Address, Block, BlockHash, OutPoint, Transaction};
 
+/// The connector shared by all swaps.
+pub type Connector =
+    ChainFollower<Cache<MultiConnector<AnyConnector, ledger::Bitcoin>>, Block, BlockHash>;
+
+const BLOCK_CACHE_CAPACITY: usize = 144;
+
+pub fn new_connector(
+    settings: &config::Bitcoin,
+    block_store: Option<&BlockStore>,
+) -> Result<Connector> {
+    let connector = match &settings.connector {
+        config::BitcoinConnector::Bitcoind => {
+            let mut connector = BitcoindConnector::new(settings.bitcoind.node_url.clone())?;
+            if let Some(zmq_url) = &settings.bitcoind.zmq_url {
+                connector = connector.with_hash_block_subscription(zmq_url.clone());
+            }
+
+            AnyConnector::Bitcoind(connector)
+        }
+        config::BitcoinConnector::Esplora(url) => {
+            AnyConnector::Esplora(EsploraConnector::new(url.clone()))
+        }
+        config::BitcoinConnector::Electrum(url) => {
+            AnyConnector::Electrum(ElectrumConnector::new(url.clone())?)
+        }
+    };
+    let fallbacks = settings
+        .bitcoind
+        .fallback_node_urls
+        .iter()
+        .map(|url| BitcoindConnector::new(url.clone()).map(AnyConnector::Bitcoind))
+        .collect::<Result<Vec<_>>>()?;
+    let connector = MultiConnector::new(settings.network, connector).with_fallbacks(fallbacks);
+
+    let cache = Cache::new(connector, BLOCK_CACHE_CAPACITY);
+    let cache = match block_store {
+        Some(block_store) => cache.with_block_store(block_store)?,
+        None => cache,
+    };
+
+    Ok(ChainFollower::new(cache))
+}
+
 #[derive(Debug, Clone)]
 pub struct Wallet {
     pub inner: Arc<crate::bitcoin::Wallet>,
     pub fee: bitcoin::Fee,
-    pub connector: Arc<comit::btsieve::bitcoin::BitcoindConnector>,
+    pub connector: Arc<Connector>,
+    /// Whether to look for the secret in the mempool of the connector.
+    pub watch_mempool: bool,
 }
 
 impl Wallet {
@@ -30,7 +79,10 @@ impl Wallet {
 
         tracing::info!("signed hbit fund transaction {}", txid);
 
-        Ok(hbit::Funded { location })
+        Ok(hbit::Funded {
+            location,
+            block_height: None,
+        })
     }
 
     pub async fn execute_redeem(
@@ -53,6 +105,25 @@ impl Wallet {
         Ok(hbit::Redeemed {
             transaction: txid,
             secret,
+            block_height: None,
+        })
+    }
+
+    pub async fn execute_refund(&self, action: SpendOutput) -> Result<hbit::Refunded> {
+        let vbyte_rate = self.fee.vbyte_rate().await?;
+        let network = action.network;
+        let transaction = action.sign(&crate::SECP, vbyte_rate)?;
+
+        let txid = self
+            .inner
+            .send_raw_transaction(transaction, network)
+            .await?;
+
+        tracing::info!("signed hbit refund transaction {}", txid);
+
+        Ok(hbit::Refunded {
+            transaction: txid,
+            block_height: None,
         })
     }
 }
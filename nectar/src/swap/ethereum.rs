@@ -1,5 +1,11 @@
-use crate::swap::herc20;
-use comit::btsieve::LatestBlock;
+use crate::{
+    config,
+    swap::{herc20, heth},
+};
+use comit::btsieve::{
+    ethereum::{Cache, Web3Connector},
+    BlockStore, ChainFollower, LatestBlock, MultiConnector,
+};
 use std::sync::Arc;
 
 use comit::swap::actions::{CallContract, DeployContract};
@@ -8,11 +14,42 @@ pub use comit::{
     Secret,
 };
 
+/// The connector shared by all swaps.
+pub type Connector = ChainFollower<Cache<MultiConnector<Web3Connector, ChainId>>, Block, Hash>;
+
+const BLOCK_CACHE_CAPACITY: usize = 720;
+const RECEIPT_CACHE_CAPACITY: usize = 720;
+
+pub fn new_connector(
+    settings: &config::Ethereum,
+    block_store: Option<&BlockStore>,
+) -> anyhow::Result<Connector> {
+    let connector = Web3Connector::new(settings.node_url.clone());
+    let fallbacks = settings
+        .fallback_node_urls
+        .iter()
+        .cloned()
+        .map(Web3Connector::new);
+    let connector =
+        MultiConnector::new(settings.chain.chain_id(), connector).with_fallbacks(fallbacks);
+
+    let cache = Cache::new(connector, BLOCK_CACHE_CAPACITY, RECEIPT_CACHE_CAPACITY);
+    let cache = match block_store {
+        Some(block_store) => cache.with_block_store(block_store)?,
+        None => cache,
+    };
+
+    Ok(ChainFollower::new(cache))
+}
+
 #[derive(Debug, Clone)]
 pub struct Wallet {
     pub inner: Arc<crate::ethereum::Wallet>,
-    pub connector: Arc<comit::btsieve::ethereum::Web3Connector>,
+    pub connector: Arc<Connector>,
     pub gas_price: crate::ethereum::GasPrice,
+    /// Whether to look for the secret in the pending transactions of the
+    /// connector.
+    pub watch_mempool: bool,
 }
 
 impl Wallet {
@@ -25,6 +62,7 @@ impl Wallet {
         Ok(herc20::Deployed {
             transaction: tx_hash,
             location: contract_address,
+            block_height: None,
         })
     }
 
@@ -36,6 +74,7 @@ impl Wallet {
 
         Ok(herc20::Funded {
             transaction: tx_hash,
+            block_height: None,
         })
     }
 
@@ -53,6 +92,64 @@ impl Wallet {
         Ok(herc20::Redeemed {
             transaction: tx_hash,
             secret,
+            block_height: None,
+        })
+    }
+
+    pub async fn execute_refund(&self, action: CallContract) -> anyhow::Result<herc20::Refunded> {
+        let gas_price = self.gas_price.gas_price().await?;
+        let tx_hash = self.inner.call_contract(action, gas_price).await?;
+
+        tracing::info!("signed herc20 refund transaction {}", tx_hash);
+
+        Ok(herc20::Refunded {
+            transaction: tx_hash,
+            block_height: None,
+        })
+    }
+
+    pub async fn execute_heth_fund(&self, action: DeployContract) -> anyhow::Result<heth::Funded> {
+        let gas_price = self.gas_price.gas_price().await?;
+        let (tx_hash, contract_address) = self.inner.deploy_contract(action, gas_price).await?;
+
+        tracing::info!("signed heth fund transaction {}", tx_hash);
+
+        Ok(heth::Funded {
+            transaction: tx_hash,
+            location: contract_address,
+            block_height: None,
+        })
+    }
+
+    pub async fn execute_heth_redeem(
+        &self,
+        action: CallContract,
+        secret: Secret,
+    ) -> anyhow::Result<heth::Redeemed> {
+        let gas_price = self.gas_price.gas_price().await?;
+        let tx_hash = self.inner.call_contract(action, gas_price).await?;
+
+        tracing::info!("signed heth redeem transaction {}", tx_hash);
+
+        Ok(heth::Redeemed {
+            transaction: tx_hash,
+            secret,
+            block_height: None,
+        })
+    }
+
+    pub async fn execute_heth_refund(
+        &self,
+        action: CallContract,
+    ) -> anyhow::Result<heth::Refunded> {
+        let gas_price = self.gas_price.gas_price().await?;
+        let tx_hash = self.inner.call_contract(action, gas_price).await?;
+
+        tracing::info!("signed heth refund transaction {}", tx_hash);
+
+        Ok(heth::Refunded {
+            transaction: tx_hash,
+            block_height: None,
         })
     }
 }
@@ -10,13 +10,17 @@ use crate::{
 };
 use backoff::{backoff::Constant, future::FutureOperation};
 use comit::{
+    btsieve::bitcoin::median_time_past,
+    expiries::CurrentTime,
     ledger,
-    swap::hbit::{WatchForFunded, WatchForRedeemed},
+    swap::hbit::{WatchForFunded, WatchForRedeemed, WatchForRedeemedInMempool, WatchForRefunded},
+    Secret, Timestamp,
 };
-use futures::TryFutureExt;
+use futures::{future, TryFutureExt};
 use std::{sync::Arc, time::Duration};
 use time::OffsetDateTime;
 
+#[derive(Clone)]
 pub struct Facade {
     pub wallet: Wallet,
     pub db: Arc<Database>,
@@ -51,11 +55,11 @@ impl WatchForFunded for Facade {
         params: &Params,
         start_of_swap: OffsetDateTime,
     ) -> Result<Funded, IncorrectlyFunded> {
-        if let Ok(Some(Funded { location })) = self.db.load(self.swap_id) {
-            self.wait_until_confirmed(location.txid, params.shared.network)
+        if let Ok(Some(funded @ Funded { .. })) = self.db.load(self.swap_id) {
+            self.wait_until_confirmed(funded.location.txid, params.shared.network)
                 .await;
 
-            return Ok(Funded { location });
+            return Ok(funded);
         }
 
         let operation = || {
@@ -88,18 +92,11 @@ impl WatchForRedeemed for Facade {
         fund_event: Funded,
         start_of_swap: OffsetDateTime,
     ) -> Redeemed {
-        if let Ok(Some(Redeemed {
-            transaction,
-            secret,
-        })) = self.db.load(self.swap_id)
-        {
-            self.wait_until_confirmed(transaction, params.shared.network)
+        if let Ok(Some(redeemed @ Redeemed { .. })) = self.db.load(self.swap_id) {
+            self.wait_until_confirmed(redeemed.transaction, params.shared.network)
                 .await;
 
-            return Redeemed {
-                transaction,
-                secret,
-            };
+            return redeemed;
         }
 
         let operation = || {
@@ -124,3 +121,89 @@ impl WatchForRedeemed for Facade {
         redeemed
     }
 }
+
+#[async_trait::async_trait]
+impl WatchForRedeemedInMempool for Facade {
+    async fn watch_for_redeemed_in_mempool(&self, params: &Params, fund_event: Funded) -> Secret {
+        // The secret is learned once the redeem transaction is mined instead.
+        if !self.wallet.watch_mempool {
+            return future::pending().await;
+        }
+
+        let operation = || {
+            watch_for_redeemed_in_mempool(
+                self.wallet.connector.as_ref(),
+                &params.shared,
+                fund_event.location,
+            )
+            .map_err(backoff::Error::Transient)
+        };
+
+        operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!(
+                    "failed to watch mempool for hbit redeem, retrying ...: {:#}",
+                    e
+                )
+            })
+            .await
+            .expect("transient error is never returned")
+    }
+}
+
+#[async_trait::async_trait]
+impl WatchForRefunded for Facade {
+    async fn watch_for_refunded(
+        &self,
+        params: &Params,
+        fund_event: Funded,
+        start_of_swap: OffsetDateTime,
+    ) -> Refunded {
+        if let Ok(Some(refunded @ Refunded { .. })) = self.db.load(self.swap_id) {
+            self.wait_until_confirmed(refunded.transaction, params.shared.network)
+                .await;
+
+            return refunded;
+        }
+
+        let operation = || {
+            watch_for_refunded(
+                self.wallet.connector.as_ref(),
+                &params.shared,
+                fund_event.location,
+                start_of_swap,
+            )
+            .map_err(backoff::Error::Transient)
+        };
+
+        let refunded = operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!("failed to watch for hbit refund, retrying ...: {:#}", e)
+            })
+            .await
+            .expect("transient error is never returned");
+
+        let _ = self.db.save(refunded, self.swap_id).await;
+
+        refunded
+    }
+}
+
+#[async_trait::async_trait]
+impl CurrentTime for Facade {
+    async fn current_time(&self) -> Timestamp {
+        let operation = || {
+            median_time_past(self.wallet.connector.as_ref()).map_err(backoff::Error::Transient)
+        };
+
+        operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!(
+                    "failed to get median time past of bitcoin chain, retrying ...: {:#}",
+                    e
+                )
+            })
+            .await
+            .expect("transient error is never returned")
+    }
+}
@@ -10,15 +10,25 @@ use crate::{
 };
 use backoff::{backoff::Constant, future::FutureOperation};
 use comit::{
+    btsieve::ethereum::latest_block_timestamp,
     ethereum,
     ethereum::ChainId,
-    herc20::{watch_for_deployed, watch_for_funded, watch_for_redeemed},
-    swap::herc20::{WatchForDeployed, WatchForFunded, WatchForRedeemed},
+    expiries::CurrentTime,
+    herc20::{
+        watch_for_deployed, watch_for_funded, watch_for_redeemed, watch_for_redeemed_in_mempool,
+        watch_for_refunded,
+    },
+    swap::herc20::{
+        WatchForDeployed, WatchForFunded, WatchForRedeemed, WatchForRedeemedInMempool,
+        WatchForRefunded,
+    },
+    Secret, Timestamp,
 };
-use futures::TryFutureExt;
+use futures::{future, TryFutureExt};
 use std::{sync::Arc, time::Duration};
 use time::OffsetDateTime;
 
+#[derive(Clone)]
 pub struct Facade {
     pub wallet: Wallet,
     pub db: Arc<Database>,
@@ -53,18 +63,11 @@ impl WatchForDeployed for Facade {
         params: Params,
         utc_start_of_swap: OffsetDateTime,
     ) -> Deployed {
-        if let Ok(Some(Deployed {
-            transaction,
-            location,
-        })) = self.db.load(self.swap_id)
-        {
-            self.wait_until_confirmed(transaction, params.chain_id)
+        if let Ok(Some(deployed @ Deployed { .. })) = self.db.load(self.swap_id) {
+            self.wait_until_confirmed(deployed.transaction, params.chain_id)
                 .await;
 
-            return Deployed {
-                transaction,
-                location,
-            };
+            return deployed;
         }
 
         let operation = || {
@@ -100,11 +103,11 @@ impl WatchForFunded for Facade {
         deploy_event: Deployed,
         utc_start_of_swap: OffsetDateTime,
     ) -> Result<Funded, IncorrectlyFunded> {
-        if let Ok(Some(Funded { transaction })) = self.db.load(self.swap_id) {
-            self.wait_until_confirmed(transaction, params.chain_id)
+        if let Ok(Some(funded @ Funded { .. })) = self.db.load(self.swap_id) {
+            self.wait_until_confirmed(funded.transaction, params.chain_id)
                 .await;
 
-            return Ok(Funded { transaction });
+            return Ok(funded);
         }
 
         let operation = || {
@@ -138,18 +141,11 @@ impl WatchForRedeemed for Facade {
         deploy_event: Deployed,
         utc_start_of_swap: OffsetDateTime,
     ) -> Redeemed {
-        if let Ok(Some(Redeemed {
-            transaction,
-            secret,
-        })) = self.db.load(self.swap_id)
-        {
-            self.wait_until_confirmed(transaction, params.chain_id)
+        if let Ok(Some(redeemed @ Redeemed { .. })) = self.db.load(self.swap_id) {
+            self.wait_until_confirmed(redeemed.transaction, params.chain_id)
                 .await;
 
-            return Redeemed {
-                transaction,
-                secret,
-            };
+            return redeemed;
         }
 
         let operation = || {
@@ -174,6 +170,92 @@ impl WatchForRedeemed for Facade {
     }
 }
 
+#[async_trait::async_trait]
+impl WatchForRedeemedInMempool for Facade {
+    async fn watch_for_redeemed_in_mempool(
+        &self,
+        params: Params,
+        deploy_event: Deployed,
+    ) -> Secret {
+        // The secret is learned once the redeem transaction is mined instead.
+        if !self.wallet.watch_mempool {
+            return future::pending().await;
+        }
+
+        let operation = || {
+            watch_for_redeemed_in_mempool(self.wallet.connector.as_ref(), &params, deploy_event)
+                .map_err(backoff::Error::Transient)
+        };
+
+        operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!(
+                    "failed to watch pending transactions for herc20 redeem, retrying ...: {:#}",
+                    e
+                )
+            })
+            .await
+            .expect("transient error is never returned")
+    }
+}
+
+#[async_trait::async_trait]
+impl WatchForRefunded for Facade {
+    async fn watch_for_refunded(
+        &self,
+        params: Params,
+        deploy_event: Deployed,
+        utc_start_of_swap: OffsetDateTime,
+    ) -> Refunded {
+        if let Ok(Some(refunded @ Refunded { .. })) = self.db.load(self.swap_id) {
+            self.wait_until_confirmed(refunded.transaction, params.chain_id)
+                .await;
+
+            return refunded;
+        }
+
+        let operation = || {
+            watch_for_refunded(
+                self.wallet.connector.as_ref(),
+                utc_start_of_swap,
+                deploy_event,
+            )
+            .map_err(backoff::Error::Transient)
+        };
+
+        let refunded = operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!("failed to watch for herc20 refund, retrying ...: {:#}", e)
+            })
+            .await
+            .expect("transient error is never returned");
+
+        let _ = self.db.save(refunded, self.swap_id).await;
+
+        refunded
+    }
+}
+
+#[async_trait::async_trait]
+impl CurrentTime for Facade {
+    async fn current_time(&self) -> Timestamp {
+        let operation = || {
+            latest_block_timestamp(self.wallet.connector.as_ref())
+                .map_err(backoff::Error::Transient)
+        };
+
+        operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!(
+                    "failed to get timestamp of latest ethereum block, retrying ...: {:#}",
+                    e
+                )
+            })
+            .await
+            .expect("transient error is never returned")
+    }
+}
+
 #[cfg(all(test, feature = "testcontainers"))]
 pub fn params(
     secret_hash: comit::SecretHash,
@@ -0,0 +1,205 @@
+pub use comit::heth::{Funded, IncorrectlyFunded, Params, Redeemed, Refunded};
+
+use crate::{
+    database::{Load, Save},
+    swap::{ethereum::Wallet, Database},
+    SwapId,
+};
+use backoff::{backoff::Constant, future::FutureOperation};
+use comit::{
+    btsieve::ethereum::latest_block_timestamp,
+    ethereum,
+    ethereum::ChainId,
+    expiries::CurrentTime,
+    heth::{
+        watch_for_funded, watch_for_redeemed, watch_for_redeemed_in_mempool, watch_for_refunded,
+        WatchForFunded, WatchForRedeemed, WatchForRedeemedInMempool, WatchForRefunded,
+    },
+    Secret, Timestamp,
+};
+use futures::{future, TryFutureExt};
+use std::{sync::Arc, time::Duration};
+use time::OffsetDateTime;
+
+#[derive(Clone)]
+pub struct Facade {
+    pub wallet: Wallet,
+    pub db: Arc<Database>,
+    pub swap_id: SwapId,
+}
+
+impl Facade {
+    async fn wait_until_confirmed(&self, tx: ethereum::Hash, chain_id: ChainId) {
+        let operation = || {
+            self.wallet
+                .inner
+                .wait_until_confirmed(tx, chain_id)
+                .map_err(backoff::Error::Transient)
+        };
+
+        let _ = operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!(
+                    "failed to wait for {} getting confirmed, retrying ...: {:#}",
+                    tx,
+                    e
+                )
+            })
+            .await;
+    }
+}
+
+#[async_trait::async_trait]
+impl WatchForFunded for Facade {
+    async fn watch_for_funded(
+        &self,
+        params: Params,
+        utc_start_of_swap: OffsetDateTime,
+    ) -> Result<Funded, IncorrectlyFunded> {
+        if let Ok(Some(funded @ Funded { .. })) = self.db.load(self.swap_id) {
+            self.wait_until_confirmed(funded.transaction, params.chain_id)
+                .await;
+
+            return Ok(funded);
+        }
+
+        let operation = || {
+            watch_for_funded(
+                self.wallet.connector.as_ref(),
+                params.clone(),
+                utc_start_of_swap,
+            )
+            .map_err(backoff::Error::Transient)
+        };
+
+        let funded = operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!("failed to watch for heth funding, retrying ...: {:#}", e)
+            })
+            .await
+            .expect("transient error is never returned")?;
+
+        let _ = self.db.save(funded, self.swap_id).await;
+
+        Ok(funded)
+    }
+}
+
+#[async_trait::async_trait]
+impl WatchForRedeemed for Facade {
+    async fn watch_for_redeemed(
+        &self,
+        params: Params,
+        fund_event: Funded,
+        utc_start_of_swap: OffsetDateTime,
+    ) -> Redeemed {
+        if let Ok(Some(redeemed @ Redeemed { .. })) = self.db.load(self.swap_id) {
+            self.wait_until_confirmed(redeemed.transaction, params.chain_id)
+                .await;
+
+            return redeemed;
+        }
+
+        let operation = || {
+            watch_for_redeemed(
+                self.wallet.connector.as_ref(),
+                utc_start_of_swap,
+                fund_event,
+            )
+            .map_err(backoff::Error::Transient)
+        };
+
+        let redeemed = operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!("failed to watch for heth redeem, retrying ...: {:#}", e)
+            })
+            .await
+            .expect("transient error is never returned");
+
+        let _ = self.db.save(redeemed, self.swap_id).await;
+
+        redeemed
+    }
+}
+
+#[async_trait::async_trait]
+impl WatchForRedeemedInMempool for Facade {
+    async fn watch_for_redeemed_in_mempool(&self, params: Params, fund_event: Funded) -> Secret {
+        // The secret is learned once the redeem transaction is mined instead.
+        if !self.wallet.watch_mempool {
+            return future::pending().await;
+        }
+
+        let operation = || {
+            watch_for_redeemed_in_mempool(self.wallet.connector.as_ref(), &params, fund_event)
+                .map_err(backoff::Error::Transient)
+        };
+
+        operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!(
+                    "failed to watch pending transactions for heth redeem, retrying ...: {:#}",
+                    e
+                )
+            })
+            .await
+            .expect("transient error is never returned")
+    }
+}
+
+#[async_trait::async_trait]
+impl WatchForRefunded for Facade {
+    async fn watch_for_refunded(
+        &self,
+        params: Params,
+        fund_event: Funded,
+        utc_start_of_swap: OffsetDateTime,
+    ) -> Refunded {
+        if let Ok(Some(refunded @ Refunded { .. })) = self.db.load(self.swap_id) {
+            self.wait_until_confirmed(refunded.transaction, params.chain_id)
+                .await;
+
+            return refunded;
+        }
+
+        let operation = || {
+            watch_for_refunded(
+                self.wallet.connector.as_ref(),
+                utc_start_of_swap,
+                fund_event,
+            )
+            .map_err(backoff::Error::Transient)
+        };
+
+        let refunded = operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!("failed to watch for heth refund, retrying ...: {:#}", e)
+            })
+            .await
+            .expect("transient error is never returned");
+
+        let _ = self.db.save(refunded, self.swap_id).await;
+
+        refunded
+    }
+}
+
+#[async_trait::async_trait]
+impl CurrentTime for Facade {
+    async fn current_time(&self) -> Timestamp {
+        let operation = || {
+            latest_block_timestamp(self.wallet.connector.as_ref())
+                .map_err(backoff::Error::Transient)
+        };
+
+        operation
+            .retry_notify(Constant::new(Duration::from_secs(1)), |e, _| {
+                tracing::warn!(
+                    "failed to get timestamp of latest ethereum block, retrying ...: {:#}",
+                    e
+                )
+            })
+            .await
+            .expect("transient error is never returned")
+    }
+}
@@ -91,8 +91,7 @@ export class CndActor implements Stoppable, DumpState, GetListenAddress, GetPeer
         const weiPerBtc = BigInt(parseFixed(price, 18).toString());
 
         const satsPerBtc = 100000000n;
-        const weiPerSat = weiPerBtc / satsPerBtc;
-        const dai = sats * weiPerSat;
+        const dai = (sats * weiPerBtc) / satsPerBtc;
 
         switch (position) {
             case Position.Buy: {
@@ -158,7 +157,7 @@ export class CndActor implements Stoppable, DumpState, GetListenAddress, GetPeer
         this.mostRecentOrderHref = await this.cnd.createBtcDaiOrder({
             position,
             quantity: sats,
-            price: weiPerSat,
+            price: weiPerBtc,
             swap: {
                 role: this.role,
                 bitcoin_address: await this.wallets.bitcoin.getAddress(),